@@ -14,7 +14,8 @@
 ///! forks of logterm customise the files in src/custom
 #[path = "../custom/mod.rs"]
 pub mod custom;
-use self::custom::app::{set_main_view, App, DashViewMain};
+use self::custom::app::App;
+use self::custom::keys::BindingKey;
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
@@ -24,9 +25,11 @@ extern crate env_logger;
 ///! logtail and its forks share code in src/
 #[path = "../mod.rs"]
 pub mod shared;
+use shared::terminal::{install_panic_hook, restore_terminal, set_terminal_teardown};
 
 use crossterm::{
-	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode},
+	cursor::Show,
+	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyModifiers, MouseButton, MouseEvent},
 	execute,
 	terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -35,15 +38,15 @@ use std::{
 	error::Error,
 	io::{stdout, Write},
 	thread,
-	time::{Duration, Instant,SystemTime, UNIX_EPOCH},
+	time::{Duration, Instant},
 };
 
 use chrono::Utc;
 
-use tui::{backend::CrosstermBackend, Terminal};
+use tui::{backend::CrosstermBackend, layout::Rect, Terminal};
 
 use futures::{
-	future::FutureExt, // for `.fuse()`
+	future::{self, FutureExt}, // for `.fuse()`
 	pin_mut,
 	select,
 };
@@ -59,18 +62,40 @@ use tokio::sync::mpsc;
 // RUSTFLAGS="-A unused" cargo run --bin logtail-crossterm --features="crossterm" /var/log/auth.log /var/log/dmesg
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn Error>> {
-	env_logger::init();
-	info!("Started");
+	install_panic_hook();
+	let result = terminal_main().await;
+	restore_terminal();
+	result
+}
+
+async fn terminal_main() -> Result<(), Box<dyn Error>> {
 
 	let mut app = match App::new().await {
 		Ok(app) => app,
 		Err(_e) => return Ok(()),
 	};
 
+	if app.opt.check {
+		let healthy = app.check_health();
+		std::process::exit(if healthy { 0 } else { 1 });
+	}
+
+	if let Some(path) = app.opt.parse_report.clone() {
+		return Ok(app.print_parse_report(&path)?);
+	}
+
+	if app.opt.dump_json.as_deref() == Some("-") {
+		return Ok(app.dump_json_to_stdout().await?);
+	}
+
 	// Terminal initialization
 	enable_raw_mode()?;
 	let mut stdout = stdout();
 	execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+	set_terminal_teardown(|| {
+		let _ = disable_raw_mode();
+		let _ = execute!(std::io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+	});
 	let backend = CrosstermBackend::new(stdout);
 	let mut terminal = Terminal::new(backend)?;
 	let mut rx = initialise_events(app.opt.tick_rate);
@@ -79,69 +104,93 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 	// Use futures of async functions to handle events
 	// concurrently with logfile changes.
 
-	let start = SystemTime::now()
-		.duration_since(UNIX_EPOCH)
-		.expect("Time went backwards");
-	let mut next_update = start - Duration::from_secs(2);
 	loop {
-		if next_update < SystemTime::now()
-			.duration_since(UNIX_EPOCH)
-			.expect("Time went backwards") {
+		// However many lines have arrived, draw at most once per tick
+		if app.redraw.is_due(Instant::now()) {
 			terminal.draw(|f| draw_dashboard(f, &mut app))?;
-			next_update += Duration::from_secs(1);
+			app.redraw.drawn(Instant::now());
 		}
 
-		let logfiles_future = app.logfiles.next().fuse();
+		let follow_logfiles = app.has_logfiles_to_follow();
+		let logfiles = &mut app.logfiles;
+		let logfiles_future = async move {
+			if follow_logfiles {
+				logfiles.next().await
+			} else {
+				future::pending().await // Only reading stdin, or waiting for logfiles
+			}
+		}
+		.fuse();
 		let events_future = rx.recv().fuse();
 		pin_mut!(logfiles_future, events_future);
 
 		select! {
 			e = events_future => {
 			match e {
-				Some(Event::Input(event)) => {
+				Some(Event::Input(CEvent::Key(event))) => {
 					match event.code {
 						// For debugging, ~ sends a line to the debug_window
 						KeyCode::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", event).as_str()),
 
-						KeyCode::Char('q')|
-						KeyCode::Char('Q') => {
-							disable_raw_mode()?;
-							execute!(
-								terminal.backend_mut(),
-								LeaveAlternateScreen,
-								DisableMouseCapture
-							)?;
-							terminal.show_cursor()?;
+						// In raw mode, as in a Windows console, Ctrl-C arrives as a key rather than a signal
+						KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+							restore_terminal();
+							app.save_on_exit()?;
 							break Ok(());
-						},
-						// KeyCode::Char('s')|
-						// KeyCode::Char('S') => app.set_main_view(DashViewMain::DashSummary),
-						KeyCode::Char('v')|
-						KeyCode::Char('V') => set_main_view(DashViewMain::DashNode, &mut app),
-
-						KeyCode::Char('+')|
-						KeyCode::Char('i')|
-						KeyCode::Char('I') => app.scale_timeline_up(),
-						KeyCode::Char('-')|
-						KeyCode::Char('o')|
-						KeyCode::Char('O') => app.scale_timeline_down(),
-
-						KeyCode::Down => app.handle_arrow_down(),
-						KeyCode::Up => app.handle_arrow_up(),
-						KeyCode::Right|
-						KeyCode::Tab => app.change_focus_next(),
-						KeyCode::Left => app.change_focus_previous(),
-
-						KeyCode::Char('g') => set_main_view(DashViewMain::DashDebug, &mut app),
-						_ => {}
+						}
+
+						// Not to be taken for the key alone
+						_ if event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+
+						code => {
+							if let Some(key) = binding_key(code) {
+								if app.handle_key(key)? {
+									restore_terminal();
+									app.save_on_exit()?;
+									break Ok(());
+								}
+							}
+						}
 					};
 					terminal.draw(|f| draw_dashboard(f, &mut app)).unwrap();
+					app.redraw.drawn(Instant::now());
 				}
 
+				Some(Event::Input(CEvent::Mouse(MouseEvent::Down(MouseButton::Left, column, row, _)))) => {
+					app.handle_click(column, row);
+					terminal.draw(|f| draw_dashboard(f, &mut app)).unwrap();
+					app.redraw.drawn(Instant::now());
+				}
+
+				Some(Event::Input(CEvent::Resize(width, height))) => {
+					// Clear what was drawn at the old size rather than leave stray characters
+					let area = Rect::new(0, 0, width, height);
+					terminal.resize(area)?;
+					terminal.clear()?;
+					app.resize(area);
+					terminal.draw(|f| draw_dashboard(f, &mut app))?;
+					app.redraw.drawn(Instant::now());
+				}
+
+				Some(Event::Input(_)) => {},
+
 				Some(Event::Tick) => {
 					app.update_timelines(&Utc::now());
-				// draw_dashboard(&mut f, &dash_state, &mut monitors).unwrap();
-				// draw_dashboard(f, &dash_state, &mut monitors)?;
+					app.follow_logfiles()?;
+					app.poll_metrics_endpoints(&Utc::now());
+					app.rescan_glob_patterns(&Utc::now()).await?;
+					app.watch_unwatched_logfiles().await;
+					app.save_state_periodically(&Utc::now());
+					app.export_metrics_periodically(&Utc::now());
+					app.notify_error_spikes(&Utc::now());
+					app.evaluate_alerts(&Utc::now());
+					app.redraw.mark_stale();
+					if app.run_for.is_over(Instant::now()) {
+						terminal.draw(|f| draw_dashboard(f, &mut app))?;
+						restore_terminal();
+						app.save_on_exit()?;
+						break Ok(());
+					}
 				}
 
 				None => {},
@@ -157,31 +206,33 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 					// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
 
 					match app.get_monitor_for_file_path(&source) {
-						Some(monitor) => {
-							monitor.append_to_content(line.line())?;
-							if monitor.is_debug_dashboard_log {
-								app.dash_state._debug_window(line.line());
-							}
-						},
+						Some(_monitor) => {
+							app.follow_logfile(&source)?;
+							app.redraw.mark_stale();
+						}
 						None => {
 							app.dash_state._debug_window(format!("NO MONITOR FOR: {}", source).as_str());
 						},
 					}
 				},
+				Some(Err(e)) if e.kind() == std::io::ErrorKind::InvalidData => {
+					// linemux rejects lines which aren't UTF-8, but vdash reads them itself
+					app.follow_logfiles()?;
+					app.redraw.mark_stale();
+				},
 				Some(Err(e)) => {
 					app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
 					panic!("{}", e)
 				}
 				None => {
-					app.dash_state._debug_window(format!("logfile error: None").as_str());
-					()
+					app.dash_state._debug_window("logfile error: None");
 				}
 		}
 			},
 		}
 	}
 }
-type Rx = tokio::sync::mpsc::UnboundedReceiver<Event<crossterm::event::KeyEvent>>;
+type Rx = tokio::sync::mpsc::UnboundedReceiver<Event<CEvent>>;
 
 fn initialise_events(tick_rate: u64) -> Rx {
 	let tick_rate = Duration::from_millis(tick_rate);
@@ -191,13 +242,11 @@ fn initialise_events(tick_rate: u64) -> Rx {
 		let mut last_tick = Instant::now();
 		loop {
 			// poll for tick rate duration, if no events, sent tick event.
-			if event::poll(tick_rate - last_tick.elapsed()).unwrap() {
-				if let CEvent::Key(key) = event::read().unwrap() {
-					match tx.send(Event::Input(key)) {
-						Ok(()) => {},
-						Err(e) => println!("send error: {}", e),
-
-					}
+			if event::poll(tick_rate.checked_sub(last_tick.elapsed()).unwrap_or_default()).unwrap() {
+				// Keys, mouse and resize events are all handled
+				match tx.send(Event::Input(event::read().unwrap())) {
+					Ok(()) => {},
+					Err(e) => println!("send error: {}", e),
 				}
 			}
 			if last_tick.elapsed() >= tick_rate {
@@ -218,3 +267,20 @@ fn initialise_events(tick_rate: u64) -> Rx {
 	});
 	rx
 }
+
+fn binding_key(code: KeyCode) -> Option<BindingKey> {
+	match code {
+		KeyCode::Char(c) => Some(BindingKey::Char(c)),
+		KeyCode::Left => Some(BindingKey::Left),
+		KeyCode::Right => Some(BindingKey::Right),
+		KeyCode::Up => Some(BindingKey::Up),
+		KeyCode::Down => Some(BindingKey::Down),
+		KeyCode::Tab => Some(BindingKey::Tab),
+		KeyCode::Enter => Some(BindingKey::Enter),
+		KeyCode::Esc => Some(BindingKey::Esc),
+		KeyCode::Backspace => Some(BindingKey::Backspace),
+		KeyCode::Home => Some(BindingKey::Home),
+		KeyCode::End => Some(BindingKey::End),
+		_ => None,
+	}
+}
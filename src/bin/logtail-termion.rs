@@ -11,12 +11,13 @@
 
 #![recursion_limit = "1024"] // Prevent select! macro blowing up
 
-use std::io;
+use std::io::{self, Write};
 
 ///! forks of logterm customise the files in src/custom
 #[path = "../custom/mod.rs"]
 pub mod custom;
-use self::custom::app::{set_main_view, App, DashViewMain};
+use self::custom::app::App;
+use self::custom::keys::BindingKey;
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
@@ -26,7 +27,8 @@ extern crate env_logger;
 ///! logtail and its forks share code in src/
 #[path = "../mod.rs"]
 pub mod shared;
-use shared::event::{Event, Events};
+use shared::event::{Config, Event, Events, DISABLE_FOCUS_REPORTING, ENABLE_FOCUS_REPORTING};
+use shared::terminal::{install_panic_hook, restore_terminal, set_terminal_teardown};
 
 use termion::{event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
 use tui::{
@@ -35,13 +37,13 @@ use tui::{
 };
 
 use std::{
-	time::{Duration, SystemTime, UNIX_EPOCH},
+	time::{Duration, Instant},
 };
 
 use chrono::Utc;
 
 use futures::{
-	future::FutureExt, // for `.fuse()`
+	future::{self, FutureExt}, // for `.fuse()`
 	pin_mut,
 	select,
 };
@@ -50,10 +52,10 @@ use tokio_stream::StreamExt;
 
 #[tokio::main]
 pub async fn main() -> std::io::Result<()> {
-	env_logger::init();
-	info!("Started");
-
-	match terminal_main().await {
+	install_panic_hook();
+	let result = terminal_main().await;
+	restore_terminal();
+	match result {
 		Ok(()) => (),
 		Err(e) => println!("{}", e),
 	}
@@ -68,34 +70,65 @@ async fn terminal_main() -> std::io::Result<()> {
 		}
 	};
 
-	let mut events = Events::new();
+	if app.opt.check {
+		let healthy = app.check_health();
+		std::process::exit(if healthy { 0 } else { 1 });
+	}
+
+	if let Some(path) = app.opt.parse_report.clone() {
+		return app.print_parse_report(&path);
+	}
+
+	if app.opt.dump_json.as_deref() == Some("-") {
+		return app.dump_json_to_stdout().await;
+	}
+
+	let mut events = Events::with_config(Config {
+		tick_rate: Duration::from_millis(app.opt.tick_rate),
+		keys_from_tty: app.stdin_reader.is_some(),
+		..Config::default()
+	});
+	// Quitting is left to the key map, as 'q' may be rebound or typed into the filter
+	events.disable_exit_key();
 
 	// Terminal initialization
 	info!("Intialising terminal (termion backend)");
-	let stdout = io::stdout().into_raw_mode()?;
-	let stdout = MouseTerminal::from(stdout);
-	let stdout = AlternateScreen::from(stdout);
+	let raw_mode = io::stdout().into_raw_mode()?;
+	set_terminal_teardown(move || {
+		drop(raw_mode); // Restores the terminal's previous mode
+		let mut stdout = io::stdout();
+		let _ = write!(stdout, "{}{}{}", DISABLE_FOCUS_REPORTING, termion::screen::ToMainScreen, termion::cursor::Show);
+		let _ = stdout.flush();
+	});
+	let stdout = MouseTerminal::from(io::stdout());
+	let mut stdout = AlternateScreen::from(stdout);
+	write!(stdout, "{}", ENABLE_FOCUS_REPORTING)?; // Ignored by terminals which don't report focus
 	let backend = TermionBackend::new(stdout);
 	let mut terminal = Terminal::new(backend)?;
 
 	// Use futures of async functions to handle events
 	// concurrently with logfile changes.
 	info!("Processing started");
+	let tick_rate = events.tick_rate();
 
-	let start = SystemTime::now()
-		.duration_since(UNIX_EPOCH)
-		.expect("Time went backwards");
-	let mut next_update = start - Duration::from_secs(2);
 	loop {
-		if next_update < SystemTime::now()
-			.duration_since(UNIX_EPOCH)
-			.expect("Time went backwards") {
+		// However many lines have arrived, draw at most once per tick
+		if app.redraw.is_due(Instant::now()) {
 			terminal.draw(|f| draw_dashboard(f, &mut app))?;
-			next_update += Duration::from_secs(1);
+			app.redraw.drawn(Instant::now());
 		}
 
 		let events_future = events.rx.recv().fuse();
-		let logfiles_future = app.logfiles.next().fuse();
+		let follow_logfiles = app.has_logfiles_to_follow();
+		let logfiles = &mut app.logfiles;
+		let logfiles_future = async move {
+			if follow_logfiles {
+				logfiles.next().await
+			} else {
+				future::pending().await // Only reading stdin, or waiting for logfiles
+			}
+		}
+		.fuse();
 		pin_mut!(events_future, logfiles_future);
 
 		select! {
@@ -106,31 +139,19 @@ async fn terminal_main() -> std::io::Result<()> {
 							// For debugging, ~ sends a line to the debug_window
 							Key::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", input).as_str()),
 
-							Key::Char('q')|
-							Key::Char('Q') => return Ok(()),
-						// Key::Char('s')|
-						// Key::Char('S') => app.set_main_view(DashViewMain::DashSummary),
-							Key::Char('v')|
-							Key::Char('V') => set_main_view(DashViewMain::DashNode, &mut app),
-
-							Key::Char('+')|
-							Key::Char('i')|
-							Key::Char('I') => app.scale_timeline_up(),
-							Key::Char('-')|
-							Key::Char('o')|
-							Key::Char('O') => app.scale_timeline_down(),
-
-							Key::Down => app.handle_arrow_down(),
-							Key::Up => app.handle_arrow_up(),
-							Key::Right|
-							Key::Char('\t') => app.change_focus_next(),
-							Key::Left => app.change_focus_previous(),
-
-							Key::Char('g') => set_main_view(DashViewMain::DashDebug, &mut app),
-								_ => {},
+							// In raw mode Ctrl-C arrives as a key rather than a signal
+							Key::Ctrl('c') => return app.save_on_exit(),
+
+							_ => {
+								if let Some(key) = binding_key(input) {
+									if app.handle_key(key)? {
+										return app.save_on_exit();
+									}
+								}
+							},
 						};
 						match terminal.draw(|f| draw_dashboard(f, &mut app)) {
-							Ok(_) => {},
+							Ok(_) => app.redraw.drawn(Instant::now()),
 							Err(e) => {
 								error!("terminal.draw() '{:#?}'", e);
 								return Err(e);
@@ -138,16 +159,34 @@ async fn terminal_main() -> std::io::Result<()> {
 						};
 					}
 
+					Some(Event::Focus(focused)) => {
+						// Slower while in the background, e.g. in another tmux pane
+						tick_rate.set(app.redraw.set_focused(focused));
+					}
+
 					Some(Event::Tick) => {
 						trace!("Event::Tick");
 						app.update_timelines(&Utc::now());
-						match terminal.draw(|f| draw_dashboard(f, &mut app)) {
-							Ok(_) => {},
-							Err(e) => {
-								error!("terminal.draw() '{:#?}'", e);
-								return Err(e);
-							}
-						};
+						app.follow_logfiles()?;
+						app.poll_metrics_endpoints(&Utc::now());
+						app.rescan_glob_patterns(&Utc::now()).await?;
+						app.watch_unwatched_logfiles().await;
+						app.save_state_periodically(&Utc::now());
+						app.export_metrics_periodically(&Utc::now());
+						app.notify_error_spikes(&Utc::now());
+						app.evaluate_alerts(&Utc::now());
+						app.redraw.mark_stale();
+						if app.run_for.is_over(Instant::now()) {
+							terminal.draw(|f| draw_dashboard(f, &mut app))?;
+							return app.save_on_exit();
+						}
+						// termion has no resize events, so look for a new size each tick
+						let size = terminal.size()?;
+						if size != app.dash_state.screen_area {
+							terminal.resize(size)?;
+							terminal.clear()?;
+							app.resize(size);
+						}
 						trace!("Event::Tick DONE");
 					}
 
@@ -162,16 +201,14 @@ async fn terminal_main() -> std::io::Result<()> {
 						let source = String::from(source_str);
 						// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
 
-						match app.get_monitor_for_file_path(&source) {
-							Some(monitor) => {
-								trace!("APPENDING: {}", line.line());
-								monitor.append_to_content(line.line())?;
-								if monitor.is_debug_dashboard_log {
-									app.dash_state._debug_window(line.line());
-								}
-							},
-							None => (),
-						}
+						trace!("FOLLOWING: {}", source);
+						app.follow_logfile(&source)?;
+						app.redraw.mark_stale();
+					},
+					Some(Err(e)) if e.kind() == io::ErrorKind::InvalidData => {
+						// linemux rejects lines which aren't UTF-8, but vdash reads them itself
+						app.follow_logfiles()?;
+						app.redraw.mark_stale();
 					},
 					Some(Err(e)) => {
 						app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
@@ -179,11 +216,27 @@ async fn terminal_main() -> std::io::Result<()> {
 						return Err(e)
 					},
 					None => {
-						app.dash_state._debug_window(format!("logfile error: None").as_str());
-						()
+						app.dash_state._debug_window("logfile error: None");
 					}
 				}
 			},
 		}
 	}
 }
+
+fn binding_key(key: Key) -> Option<BindingKey> {
+	match key {
+		Key::Char('\t') => Some(BindingKey::Tab),
+		Key::Char('\n') => Some(BindingKey::Enter),
+		Key::Char(c) => Some(BindingKey::Char(c)),
+		Key::Left => Some(BindingKey::Left),
+		Key::Right => Some(BindingKey::Right),
+		Key::Up => Some(BindingKey::Up),
+		Key::Down => Some(BindingKey::Down),
+		Key::Esc => Some(BindingKey::Esc),
+		Key::Backspace => Some(BindingKey::Backspace),
+		Key::Home => Some(BindingKey::Home),
+		Key::End => Some(BindingKey::End),
+		_ => None,
+	}
+}
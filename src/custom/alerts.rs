@@ -0,0 +1,370 @@
+///! Alerts defined with --alerts, shown as a banner while a node breaks a rule
+///!
+///! The TOML file lists rules, each comparing one metric of every node with
+///! a threshold. Rules are evaluated on each refresh and an alert is shown
+///! for every node and rule breached, most severe first, until the metric
+///! is back within the threshold:
+///!
+///!	[[alert]]
+///!	metric = "storage_ratio"
+///!	comparator = ">"
+///!	threshold = 0.9
+///!	severity = "critical"
+///!
+///!	[[alert]]
+///!	metric = "errors_per_minute"
+///!	comparator = ">="
+///!	threshold = 10
+///!
+///! Metrics are storage_ratio, errors_per_minute, warnings_per_minute,
+///! peers, store_cost and minutes_since_activity. Severities are info,
+///! warning (the default) and critical. A node breaking a critical rule
+///! blinks, its summary row and storage gauge shown reversed every other
+///! BLINK_TICKS ticks, unless --no-blink is given.
+///!
+///! Pressing '!' acknowledges the alert at the top of the banner, the most
+///! severe not yet acknowledged. It is then shown muted below the others and
+///! no longer blinks for as long as the rule stays broken. Once the metric is
+///! back within the threshold the alert clears, so if the rule is broken again
+///! it fires afresh.
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::custom::app::NodeMetrics;
+
+#[derive(Deserialize)]
+struct AlertsFile {
+	#[serde(default)]
+	alert: Vec<AlertEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AlertEntry {
+	metric: String,
+	comparator: String,
+	threshold: f64,
+	severity: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	Info,
+	Warning,
+	Critical,
+}
+
+impl std::str::FromStr for Severity {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Severity, String> {
+		match s {
+			"info" => Ok(Severity::Info),
+			"warning" => Ok(Severity::Warning),
+			"critical" => Ok(Severity::Critical),
+			_ => Err(format!("invalid severity '{}', expected info, warning or critical", s)),
+		}
+	}
+}
+
+struct AlertMetric {
+	name: &'static str,
+	value: fn(&NodeMetrics, &DateTime<Utc>) -> Option<f64>,
+}
+
+static ALERT_METRICS: [AlertMetric; 6] = [
+	AlertMetric { name: "storage_ratio", value: |m, _| m.storage_ratio() },
+	AlertMetric { name: "errors_per_minute", value: |m, _| Some(m.errors_per_minute() as f64) },
+	AlertMetric { name: "warnings_per_minute", value: |m, _| Some(m.warnings_per_minute() as f64) },
+	AlertMetric { name: "peers", value: |m, _| Some(m.peers as f64) },
+	AlertMetric { name: "store_cost", value: |m, _| m.store_cost.map(|cost| cost as f64) },
+	AlertMetric { name: "minutes_since_activity", value: |m, now| m.time_since_activity(now).map(|t| t.num_seconds() as f64 / 60.0) },
+];
+
+struct Comparator {
+	symbol: &'static str,
+	breached: fn(f64, f64) -> bool, // Given the value and the threshold
+}
+
+static COMPARATORS: [Comparator; 5] = [
+	Comparator { symbol: ">", breached: |value, threshold| value > threshold },
+	Comparator { symbol: ">=", breached: |value, threshold| value >= threshold },
+	Comparator { symbol: "<", breached: |value, threshold| value < threshold },
+	Comparator { symbol: "<=", breached: |value, threshold| value <= threshold },
+	Comparator { symbol: "==", breached: |value, threshold| (value - threshold).abs() < f64::EPSILON },
+];
+
+pub struct AlertRule {
+	pub metric: &'static str,
+	pub comparator: &'static str,
+	pub threshold: f64,
+	pub severity: Severity,
+	value: fn(&NodeMetrics, &DateTime<Utc>) -> Option<f64>,
+	breached: fn(f64, f64) -> bool,
+}
+
+impl AlertRule {
+	///! The rule as written, e.g. 'storage_ratio > 0.9'
+	pub fn describe(&self) -> String {
+		format!("{} {} {}", self.metric, self.comparator, self.threshold)
+	}
+
+	///! The value of the metric if it breaks the rule. A metric which is
+	///! unknown, such as the storage ratio of a node without a capacity, never does
+	pub fn breached_by(&self, metrics: &NodeMetrics, now: &DateTime<Utc>) -> Option<f64> {
+		(self.value)(metrics, now).filter(|value| (self.breached)(*value, self.threshold))
+	}
+}
+
+pub fn load_alert_rules(path: &Path) -> Result<Vec<AlertRule>, String> {
+	let toml = std::fs::read_to_string(path).map_err(|e| format!("failed to read alerts {}: {}", path.display(), e))?;
+	alert_rules_from_toml(&toml).map_err(|e| format!("invalid alerts {}: {}", path.display(), e))
+}
+
+pub fn alert_rules_from_toml(toml: &str) -> Result<Vec<AlertRule>, String> {
+	let file: AlertsFile = toml::from_str(toml).map_err(|e| e.to_string())?;
+	let mut rules = Vec::<AlertRule>::new();
+	for entry in file.alert {
+		let metric = ALERT_METRICS
+			.iter()
+			.find(|metric| metric.name == entry.metric)
+			.ok_or_else(|| format!("unknown metric '{}'", entry.metric))?;
+		let comparator = COMPARATORS
+			.iter()
+			.find(|comparator| comparator.symbol == entry.comparator)
+			.ok_or_else(|| format!("invalid comparator '{}' for {}, expected >, >=, <, <= or ==", entry.comparator, entry.metric))?;
+		let severity = match &entry.severity {
+			Some(severity) => severity.parse::<Severity>()?,
+			None => Severity::Warning,
+		};
+		rules.push(AlertRule {
+			metric: metric.name,
+			comparator: comparator.symbol,
+			threshold: entry.threshold,
+			severity,
+			value: metric.value,
+			breached: comparator.breached,
+		});
+	}
+	Ok(rules)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+	pub node: String,
+	pub rule: String, // As given by AlertRule::describe()
+	pub severity: Severity,
+	pub value: f64,
+	pub acknowledged: bool, // Muted until it clears, see acknowledge_next()
+}
+
+impl Alert {
+	///! As shown in the banner, e.g. 'CRITICAL node-1: storage_ratio > 0.9 (0.95)'
+	pub fn describe(&self) -> String {
+		let severity = match self.severity {
+			Severity::Info => "INFO",
+			Severity::Warning => "WARNING",
+			Severity::Critical => "CRITICAL",
+		};
+		let acknowledged = if self.acknowledged { " - acknowledged" } else { "" };
+		format!("{} {}: {} ({}){}", severity, self.node, self.rule, (self.value * 100.0).round() / 100.0, acknowledged)
+	}
+
+	// The same rule broken by the same node
+	fn is_same(&self, other: &Alert) -> bool {
+		self.node == other.node && self.rule == other.rule
+	}
+}
+
+///! Ticks in each phase of the blink of a node in critical alert
+pub const BLINK_TICKS: u64 = 3;
+
+///! Whether alerting elements are shown lit (reversed) at this tick: on for
+///! the first `every` ticks, off for the next and so on. Always lit if every is 0
+pub fn blink_phase(tick: u64, every: u64) -> bool {
+	every == 0 || (tick / every).is_multiple_of(2)
+}
+
+///! Every rule broken by each (name, metrics) node, most severe first, then
+///! in the order of the nodes and rules given
+pub fn evaluate_alerts<'a>(
+	rules: &[AlertRule],
+	nodes: impl Iterator<Item = (&'a str, &'a NodeMetrics)>,
+	now: &DateTime<Utc>,
+) -> Vec<Alert> {
+	let mut alerts = Vec::<Alert>::new();
+	for (name, metrics) in nodes {
+		for rule in rules {
+			if let Some(value) = rule.breached_by(metrics, now) {
+				alerts.push(Alert {
+					node: name.to_string(),
+					rule: rule.describe(),
+					severity: rule.severity,
+					value,
+					acknowledged: false,
+				});
+			}
+		}
+	}
+	sort_alerts(&mut alerts);
+	alerts
+}
+
+// Most severe first, with those acknowledged after the rest
+fn sort_alerts(alerts: &mut [Alert]) {
+	alerts.sort_by_key(|alert| (alert.acknowledged, std::cmp::Reverse(alert.severity)));
+}
+
+///! The alerts firing now, as given by evaluate_alerts(), keeping the
+///! acknowledgement of those which were firing before. Those acknowledged but
+///! now cleared are forgotten, so fire afresh if their rule is broken again
+pub fn carry_acknowledgements(previous: &[Alert], mut firing: Vec<Alert>) -> Vec<Alert> {
+	for alert in firing.iter_mut() {
+		alert.acknowledged = previous.iter().any(|earlier| earlier.acknowledged && earlier.is_same(alert));
+	}
+	sort_alerts(&mut firing);
+	firing
+}
+
+///! Acknowledge the alert at the top of the banner, the most severe not yet
+///! acknowledged, returning its description. None when all are acknowledged
+pub fn acknowledge_next(alerts: &mut [Alert]) -> Option<String> {
+	let alert = alerts.iter_mut().find(|alert| !alert.acknowledged)?;
+	alert.acknowledged = true;
+	let description = alert.describe();
+	sort_alerts(alerts);
+	Some(description)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::Opt;
+	use structopt::StructOpt;
+
+	const RULES: &str = r#"
+		[[alert]]
+		metric = "errors_per_minute"
+		comparator = ">="
+		threshold = 10
+
+		[[alert]]
+		metric = "storage_ratio"
+		comparator = ">"
+		threshold = 0.9
+		severity = "critical"
+
+		[[alert]]
+		metric = "peers"
+		comparator = "<"
+		threshold = 5
+		severity = "info"
+	"#;
+
+	fn node(now: &DateTime<Utc>, used_space: u64, errors: usize, peers: u64) -> NodeMetrics {
+		let mut metrics = NodeMetrics::new(&Opt::from_iter(vec!["vdash"]));
+		metrics.update_timelines(now);
+		metrics.capacity_override = Some(1000);
+		metrics.used_space = used_space;
+		metrics.peers = peers;
+		for _ in 0..errors {
+			metrics.errors_timeline.increment_value(now);
+		}
+		metrics
+	}
+
+	#[test]
+	fn fires_alerts_for_rules_broken() {
+		let now = Utc::now();
+		let rules = alert_rules_from_toml(RULES).unwrap();
+		let healthy = node(&now, 500, 2, 20);
+		let full = node(&now, 950, 0, 20);
+		let failing = node(&now, 950, 12, 3);
+		let nodes = vec![("healthy", &healthy), ("full", &full), ("failing", &failing)];
+
+		let alerts = evaluate_alerts(&rules, nodes.into_iter(), &now);
+		let fired: Vec<(&str, &str, Severity)> = alerts.iter().map(|a| (a.node.as_str(), a.rule.as_str(), a.severity)).collect();
+		assert_eq!(fired, vec![
+			("full", "storage_ratio > 0.9", Severity::Critical),
+			("failing", "storage_ratio > 0.9", Severity::Critical),
+			("failing", "errors_per_minute >= 10", Severity::Warning),
+			("failing", "peers < 5", Severity::Info),
+		]);
+		assert_eq!(alerts[0].describe(), "CRITICAL full: storage_ratio > 0.9 (0.95)");
+	}
+
+	#[test]
+	fn clears_alerts_when_resolved() {
+		let now = Utc::now();
+		let rules = alert_rules_from_toml(RULES).unwrap();
+		let mut metrics = node(&now, 950, 0, 20);
+		assert_eq!(evaluate_alerts(&rules, vec![("node-1", &metrics)].into_iter(), &now).len(), 1);
+		metrics.used_space = 900;
+		assert!(evaluate_alerts(&rules, vec![("node-1", &metrics)].into_iter(), &now).is_empty());
+
+		// An unknown metric never breaks a rule
+		metrics.capacity_override = None;
+		metrics.used_space = 950;
+		assert!(evaluate_alerts(&rules, vec![("node-1", &metrics)].into_iter(), &now).is_empty());
+	}
+
+	#[test]
+	fn acknowledges_alerts_until_they_clear() {
+		let now = Utc::now();
+		let rules = alert_rules_from_toml(RULES).unwrap();
+		let mut full = node(&now, 950, 0, 20);
+		let failing = node(&now, 500, 12, 20);
+		let evaluate = |previous: &[Alert], full: &NodeMetrics| {
+			carry_acknowledgements(previous, evaluate_alerts(&rules, vec![("full", full), ("failing", &failing)].into_iter(), &now))
+		};
+		let acknowledged = |alerts: &[Alert]| -> Vec<(String, bool)> { alerts.iter().map(|a| (a.node.clone(), a.acknowledged)).collect() };
+
+		// Active: the critical alert is on top
+		let mut alerts = evaluate(&[], &full);
+		assert_eq!(acknowledged(&alerts), vec![(String::from("full"), false), (String::from("failing"), false)]);
+
+		// Acknowledged: moved below the others, and stays so while the rule is broken
+		assert_eq!(acknowledge_next(&mut alerts), Some(String::from("CRITICAL full: storage_ratio > 0.9 (0.95) - acknowledged")));
+		assert_eq!(acknowledged(&alerts), vec![(String::from("failing"), false), (String::from("full"), true)]);
+		let alerts = evaluate(&alerts, &full);
+		assert_eq!(acknowledged(&alerts), vec![(String::from("failing"), false), (String::from("full"), true)]);
+
+		// Cleared once resolved, then active again when broken again
+		full.used_space = 500;
+		let alerts = evaluate(&alerts, &full);
+		assert_eq!(acknowledged(&alerts), vec![(String::from("failing"), false)]);
+		full.used_space = 990;
+		let mut alerts = evaluate(&alerts, &full);
+		assert_eq!(acknowledged(&alerts), vec![(String::from("full"), false), (String::from("failing"), false)]);
+
+		// Nothing left to acknowledge
+		acknowledge_next(&mut alerts);
+		acknowledge_next(&mut alerts);
+		assert_eq!(acknowledge_next(&mut alerts), None);
+		assert!(alerts.iter().all(|alert| alert.acknowledged));
+	}
+
+	#[test]
+	fn blinks_every_n_ticks() {
+		let phases = |every: u64| -> Vec<bool> { (0..8).map(|tick| blink_phase(tick, every)).collect() };
+		assert_eq!(phases(1), vec![true, false, true, false, true, false, true, false]);
+		assert_eq!(phases(3), vec![true, true, true, false, false, false, true, true]);
+		assert_eq!(phases(0), vec![true; 8]);
+	}
+
+	#[test]
+	fn reports_bad_rules() {
+		let error = |toml: &str| alert_rules_from_toml(toml).err().unwrap();
+		assert_eq!(error("[[alert]]\nmetric = \"cpu\"\ncomparator = \">\"\nthreshold = 1"), "unknown metric 'cpu'");
+		assert_eq!(
+			error("[[alert]]\nmetric = \"peers\"\ncomparator = \"!=\"\nthreshold = 1"),
+			"invalid comparator '!=' for peers, expected >, >=, <, <= or =="
+		);
+		assert_eq!(
+			error("[[alert]]\nmetric = \"peers\"\ncomparator = \"<\"\nthreshold = 1\nseverity = \"dire\""),
+			"invalid severity 'dire', expected info, warning or critical"
+		);
+		assert!(alert_rules_from_toml("").unwrap().is_empty());
+	}
+}
@@ -0,0 +1,240 @@
+///! Colours and styles given by ANSI escape sequences in logfile lines
+///!
+///! Nodes which colour their logs already contain SGR sequences such as
+///! '\x1b[31m', which are shown as styled text instead of escapes. Other
+///! escape sequences are dropped. The reverse, an SGR sequence for a style,
+///! is used to write screenshots in colour
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+const ESC: char = '\x1b';
+
+///! A line as spans styled by its SGR sequences, starting from the base style
+pub fn ansi_spans(line: &str, base: Style) -> Spans<'static> {
+	let mut spans = Vec::new();
+	let mut text = String::new();
+	let mut style = base;
+	let mut chars = line.chars();
+	while let Some(c) = chars.next() {
+		if c != ESC {
+			text.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('[') => {
+				// Control sequence: parameters up to a final byte
+				let mut params = String::new();
+				let mut final_byte = None;
+				for c in chars.by_ref() {
+					if ('@'..='~').contains(&c) {
+						final_byte = Some(c);
+						break;
+					}
+					params.push(c);
+				}
+				if final_byte == Some('m') {
+					let next_style = apply_sgr(style, base, &params);
+					if next_style != style {
+						push_span(&mut spans, &mut text, style);
+						style = next_style;
+					}
+				}
+			}
+			Some(']') => {
+				// Operating system command, ended by BEL or ESC \
+				while let Some(c) = chars.next() {
+					if c == '\x07' {
+						break;
+					}
+					if c == ESC {
+						chars.next();
+						break;
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+	push_span(&mut spans, &mut text, style);
+	Spans::from(spans)
+}
+
+fn push_span(spans: &mut Vec<Span<'static>>, text: &mut String, style: Style) {
+	if !text.is_empty() {
+		spans.push(Span::styled(std::mem::take(text), style));
+	}
+}
+
+///! The style after the SGR parameters, e.g. '1;31'. Parameters which
+///! aren't understood leave the style as it was
+fn apply_sgr(style: Style, base: Style, params: &str) -> Style {
+	let codes: Option<Vec<u16>> = params
+		.split(';')
+		.map(|param| if param.is_empty() { Some(0) } else { param.parse().ok() })
+		.collect();
+	let codes = match codes {
+		Some(codes) => codes,
+		None => return style,
+	};
+
+	let mut style = style;
+	let mut codes = codes.into_iter();
+	while let Some(code) = codes.next() {
+		match code {
+			0 => style = base,
+			1 => style.add_modifier.insert(Modifier::BOLD),
+			2 => style.add_modifier.insert(Modifier::DIM),
+			3 => style.add_modifier.insert(Modifier::ITALIC),
+			4 => style.add_modifier.insert(Modifier::UNDERLINED),
+			5 => style.add_modifier.insert(Modifier::SLOW_BLINK),
+			6 => style.add_modifier.insert(Modifier::RAPID_BLINK),
+			7 => style.add_modifier.insert(Modifier::REVERSED),
+			8 => style.add_modifier.insert(Modifier::HIDDEN),
+			9 => style.add_modifier.insert(Modifier::CROSSED_OUT),
+			22 => style.add_modifier.remove(Modifier::BOLD | Modifier::DIM),
+			23 => style.add_modifier.remove(Modifier::ITALIC),
+			24 => style.add_modifier.remove(Modifier::UNDERLINED),
+			25 => style.add_modifier.remove(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+			27 => style.add_modifier.remove(Modifier::REVERSED),
+			28 => style.add_modifier.remove(Modifier::HIDDEN),
+			29 => style.add_modifier.remove(Modifier::CROSSED_OUT),
+			30..=37 => style.fg = Some(basic_color(code - 30)),
+			38 => match extended_color(&mut codes) {
+				Some(color) => style.fg = Some(color),
+				None => break,
+			},
+			39 => style.fg = base.fg,
+			40..=47 => style.bg = Some(basic_color(code - 40)),
+			48 => match extended_color(&mut codes) {
+				Some(color) => style.bg = Some(color),
+				None => break,
+			},
+			49 => style.bg = base.bg,
+			90..=97 => style.fg = Some(bright_color(code - 90)),
+			100..=107 => style.bg = Some(bright_color(code - 100)),
+			_ => {}
+		}
+	}
+	style
+}
+
+///! The SGR sequence which resets the terminal's style and then sets the
+///! given colours and modifiers, e.g. '\x1b[0;1;31m'
+pub fn sgr_sequence(fg: Color, bg: Color, modifier: Modifier) -> String {
+	let mut codes = vec![String::from("0")];
+	let modifiers = [
+		(Modifier::BOLD, 1),
+		(Modifier::DIM, 2),
+		(Modifier::ITALIC, 3),
+		(Modifier::UNDERLINED, 4),
+		(Modifier::SLOW_BLINK, 5),
+		(Modifier::RAPID_BLINK, 6),
+		(Modifier::REVERSED, 7),
+		(Modifier::HIDDEN, 8),
+		(Modifier::CROSSED_OUT, 9),
+	];
+	for (flag, code) in modifiers.iter() {
+		if modifier.contains(*flag) {
+			codes.push(code.to_string());
+		}
+	}
+	codes.extend(color_codes(fg, 30));
+	codes.extend(color_codes(bg, 40));
+	format!("{}[{}m", ESC, codes.join(";"))
+}
+
+///! The SGR codes for a foreground (base 30) or background (base 40) colour
+fn color_codes(color: Color, base: u16) -> Option<String> {
+	match color {
+		Color::Reset => None,
+		Color::Indexed(index) => Some(format!("{};5;{}", base + 8, index)),
+		Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+		color => (0..8)
+			.find(|index| basic_color(*index) == color)
+			.map(|index| base + index)
+			.or_else(|| (0..8).find(|index| bright_color(*index) == color).map(|index| base + 60 + index))
+			.map(|code| code.to_string()),
+	}
+}
+
+fn basic_color(index: u16) -> Color {
+	[Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::Gray][index as usize]
+}
+
+fn bright_color(index: u16) -> Color {
+	[
+		Color::DarkGray,
+		Color::LightRed,
+		Color::LightGreen,
+		Color::LightYellow,
+		Color::LightBlue,
+		Color::LightMagenta,
+		Color::LightCyan,
+		Color::White,
+	][index as usize]
+}
+
+///! The colour of a 38 or 48 code: '5;n' for a 256 colour palette index or
+///! '2;r;g;b' for RGB
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+	let byte = |code: Option<u16>| code.filter(|code| *code <= 255).map(|code| code as u8);
+	match codes.next()? {
+		5 => byte(codes.next()).map(Color::Indexed),
+		2 => Some(Color::Rgb(byte(codes.next())?, byte(codes.next())?, byte(codes.next())?)),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn span_texts_and_styles(spans: &Spans) -> Vec<(String, Style)> {
+		spans.0.iter().map(|span| (span.content.to_string(), span.style)).collect()
+	}
+
+	#[test]
+	fn styles_spans_from_sgr_codes() {
+		let base = Style::default().fg(Color::Gray);
+		let line = "\x1b[2m2024-01-15T20:21:02Z\x1b[0m \x1b[1;31mERROR\x1b[0m sn_node: \x1b[38;5;208mrecord\x1b[39m stored";
+		let bold_red = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+		assert_eq!(
+			span_texts_and_styles(&ansi_spans(line, base)),
+			vec![
+				(String::from("2024-01-15T20:21:02Z"), base.add_modifier(Modifier::DIM)),
+				(String::from(" "), base),
+				(String::from("ERROR"), bold_red),
+				(String::from(" sn_node: "), base),
+				(String::from("record"), base.fg(Color::Indexed(208))),
+				(String::from(" stored"), base),
+			]
+		);
+	}
+
+	#[test]
+	fn drops_other_escape_sequences() {
+		let base = Style::default();
+		let line = "\x1b]0;title\x07plain \x1b[2Kline\x1b[?25l \x1b[48;2;0;0;255mblue\x1b[m\x1b";
+		assert_eq!(
+			span_texts_and_styles(&ansi_spans(line, base)),
+			vec![
+				(String::from("plain line "), base),
+				(String::from("blue"), base.bg(Color::Rgb(0, 0, 255))),
+			]
+		);
+		assert_eq!(span_texts_and_styles(&ansi_spans("no escapes", base)), vec![(String::from("no escapes"), base)]);
+		assert!(ansi_spans("", base).0.is_empty());
+	}
+
+	#[test]
+	fn writes_sgr_sequences_read_back_as_the_same_style() {
+		assert_eq!(sgr_sequence(Color::Reset, Color::Reset, Modifier::empty()), "\x1b[0m");
+		assert_eq!(sgr_sequence(Color::Red, Color::Reset, Modifier::BOLD), "\x1b[0;1;31m");
+		assert_eq!(sgr_sequence(Color::White, Color::Indexed(208), Modifier::empty()), "\x1b[0;97;48;5;208m");
+
+		let base = Style::default();
+		let style = base.fg(Color::Rgb(1, 2, 3)).bg(Color::LightBlue).add_modifier(Modifier::REVERSED | Modifier::ITALIC);
+		let line = format!("{}styled", sgr_sequence(Color::Rgb(1, 2, 3), Color::LightBlue, Modifier::REVERSED | Modifier::ITALIC));
+		assert_eq!(span_texts_and_styles(&ansi_spans(&line, base)), vec![(String::from("styled"), style)]);
+	}
+}
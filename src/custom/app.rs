@@ -2,21 +2,75 @@
 ///!
 ///! Edit src/custom/app.rs to create a customised fork of logtail-dash
 use linemux::MuxedLines;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
 
-use chrono::{DateTime, Duration, Utc};
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Error, ErrorKind, Write};
+use chrono::{DateTime, Duration, Local, Utc};
+use std::fs::File;
+use std::io::{Error, Write};
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use tempfile::NamedTempFile;
-
-use crate::custom::opt::{Opt, MIN_TIMELINE_STEPS};
+use tui::layout::Rect;
+
+use crate::custom::alerts::{acknowledge_next, carry_acknowledgements, evaluate_alerts, load_alert_rules, Alert, AlertRule};
+use crate::custom::capacity::{storage_ratio, NodeCapacities};
+use crate::custom::check::{evaluate_health, HealthCriteria, HealthWeights};
+use crate::custom::clipboard::copy_to_clipboard;
+use crate::custom::csv::TimelinesCsv;
+use crate::custom::diagnostics::{diagnose_line, Diagnostic, Diagnostics, SharedDiagnostics};
+use crate::custom::endpoint::MetricsEndpoint;
+use crate::custom::flash::ChangeFlash;
+use crate::custom::format::NumberFormat;
+use crate::custom::fuzzy::{fuzzy_match, FuzzyMatch};
+use crate::custom::heatmap::{find_heatmap_metric, next_heatmap_metric};
+use crate::custom::keys::{Action, BindingKey, KeyMap};
+use crate::custom::names::{default_node_name, NodeNames, DEFAULT_GROUP};
+use crate::custom::notify::{error_spike_message, notify_desktop, ErrorSpikeDetector};
+use crate::custom::opt::{dedup_logfile_paths, expand_logfile_paths, glob_matches, is_glob_pattern, Opt};
+use crate::custom::focus::FocusMetric;
+use crate::custom::jump::{jump_match, NodeJump};
+use crate::custom::panels::{HiddenPanels, PanelKind};
+use crate::custom::prefs::{preferences_path, UiPreferences};
+use crate::custom::parse_report::ParseReport;
+use crate::custom::platform::{logfile_key, normalize_path, LogfileStamp};
+use crate::custom::prometheus::{metrics_tls_config, spawn_metrics_server, SharedSnapshot};
+use crate::custom::query::{spawn_query_socket, QuerySocket};
+use crate::custom::latency::{parse_latency, LatencySamples, LATENCY_SAMPLES_MAX};
+use crate::custom::log_profile::LogProfile;
+use crate::custom::redraw::RedrawLimiter;
+use crate::custom::replay::Replay;
+use crate::custom::run_for::RunFor;
+use crate::custom::rotated::{read_gz_lines, rotated_logfiles};
+use crate::custom::screenshot::write_screenshot;
+use crate::custom::snapshot::MetricsSnapshot;
+use crate::custom::spotlight::{load_spotlight_keywords, SharedSpotlight, Spotlight};
+use crate::custom::state::DashboardState;
+use crate::custom::stdin::{StdinReader, STDIN_LOGFILE};
+use crate::custom::tail::LineTail;
+use crate::custom::theme::Theme;
+use crate::custom::ui::widgets::sparkline::{Downsample, Scale, SparkStyle};
+use crate::custom::ui::{node_stats_text, ClickTargets, PrimaryGauge, SummaryColumns};
+use crate::custom::user_metrics::{load_user_metric_specs, UserMetricSpec, UserMetrics};
+use crate::custom::validate::validate_config;
+use crate::custom::warnings::{RecentWarnings, SharedWarnings, WarningEntry};
+use crate::custom::timelines::{zoom_timeline, ChartStyles, GoodDirections, TimelineSet, TimelineSpec};
+use crate::shared::logging::init_logging;
 use crate::shared::util::StatefulList;
 
 pub static DEBUG_WINDOW_NAME: &str = "Debug Window";
 
-use std::sync::Mutex;
+// Seconds between saves of --state-file
+pub static STATE_SAVE_INTERVAL: i64 = 60;
+
+// Nodes shown side by side in the compare view
+pub const COMPARE_NODES: usize = 2;
+
+// The timeline of rewards received, whose values are attos
+pub static REWARDS_TIMELINE: &str = "REWARDS";
+
+use std::sync::{Arc, Mutex};
 lazy_static::lazy_static! {
 	pub static ref DEBUG_LOGFILE: Mutex<Option<NamedTempFile>> =
 		Mutex::<Option<NamedTempFile>>::new(None);
@@ -25,24 +79,19 @@ lazy_static::lazy_static! {
 #[macro_export]
 macro_rules! debug_log {
 	($message:expr) => {
-		unsafe {
-			debug_log($message);
-		}
+		debug_log($message);
 	};
 }
 
-pub unsafe fn debug_log(message: &str) {
+pub fn debug_log(message: &str) {
 	// --debug-window - prints parser results for a single logfile
 	// to a temp logfile which is displayed in the adjacent window.
-	match &(*DEBUG_LOGFILE.lock().unwrap()) {
-		Some(f) => {
-			use std::io::Seek;
-			if let Ok(mut file) = f.reopen() {
-				file.seek(std::io::SeekFrom::End(0)).unwrap();
-				writeln!(file, "{}", message).unwrap();
-			}
+	if let Some(f) = &(*DEBUG_LOGFILE.lock().unwrap()) {
+		use std::io::Seek;
+		if let Ok(mut file) = f.reopen() {
+			file.seek(std::io::SeekFrom::End(0)).unwrap();
+			writeln!(file, "{}", message).unwrap();
 		}
-		None => (),
 	};
 }
 
@@ -53,35 +102,229 @@ pub struct App {
 	pub logfile_with_focus: String,
 	pub logfiles: MuxedLines,
 	pub logfile_names: Vec<String>,
+	unwatched_logfiles: Vec<String>, // Not yet added to logfiles, see watch_unwatched_logfiles()
+	pub glob_patterns: Vec<String>,
+	pub next_glob_rescan: DateTime<Utc>,
+	pub next_state_save: DateTime<Utc>,
+	pub next_metrics_export: DateTime<Utc>,
+	pub shared_snapshot: Option<SharedSnapshot>, // Served by --metrics-addr and --query-socket
+	pub query_socket: Option<QuerySocket>,
+	pub stdin_reader: Option<StdinReader>, // When LOGFILE '-' is given
+	pub replay: Option<Replay>, // Of the --replay logfile
+	pub node_capacities: NodeCapacities,
+	pub node_names: NodeNames,
+	pub error_spike_detector: Option<ErrorSpikeDetector>, // For --notify
+	pub user_metric_specs: Arc<Vec<UserMetricSpec>>,
+	pub alert_rules: Vec<AlertRule>, // From --alerts
+	pub redraw: RedrawLimiter, // Draws at most once per --tick-rate
+	pub run_for: RunFor, // From launch, see --run-for
 }
 
 impl App {
 	pub async fn new() -> Result<App, std::io::Error> {
+		let launched = std::time::Instant::now();
 		let mut opt = Opt::from_args();
+		if opt.validate {
+			// Before anything else is opened, which would stop at the first problem
+			let report = validate_config(&opt);
+			print!("{}", report);
+			std::process::exit(if report.is_valid() { 0 } else { 1 });
+		}
+		if let Err(e) = init_logging(opt.log_file.as_deref(), opt.log_level) {
+			let path = opt.log_file.as_deref().map_or(String::new(), |path| path.display().to_string());
+			println!("{}: failed to open --log-file {}: {}", Opt::clap().get_name(), path, e);
+			return exit_with_usage("invalid log file");
+		}
+		log::info!("Started");
+		if opt.ignore_existing {
+			opt.from = ReadFrom::End;
+		}
+		if let Some(replay_logfile) = &opt.replay {
+			if !opt.files.contains(replay_logfile) {
+				opt.files.push(replay_logfile.clone());
+			}
+		}
 
-		if opt.files.is_empty() {
+		if opt.files.is_empty() && opt.parse_report.is_none() {
 			println!("{}: no logfile(s) specified.", Opt::clap().get_name());
 			return exit_with_usage("missing logfiles");
 		}
 
-		if opt.timeline_steps < MIN_TIMELINE_STEPS {
-			println!(
-				"Timeline steps number is too small, minimum is {}",
-				MIN_TIMELINE_STEPS
-			);
-			return exit_with_usage("invalid parameter");
+		let glob_patterns: Vec<String> = opt.files.iter().filter(|f| is_glob_pattern(f)).cloned().collect();
+		opt.files = match expand_logfile_paths(&opt.files) {
+			Ok(files) => files,
+			Err(e) => {
+				println!("{}: {}", Opt::clap().get_name(), e);
+				return exit_with_usage("no matching logfiles");
+			}
+		};
+		if !opt.keep_duplicates {
+			let (files, duplicates) = dedup_logfile_paths(&opt.files);
+			for (duplicate, kept) in &duplicates {
+				println!("Ignoring {}, the same logfile as {} (see --keep-duplicates)", duplicate, kept);
+				log::warn!("ignoring {}, the same logfile as {}", duplicate, kept);
+			}
+			opt.files = files;
 		}
 
 		let mut dash_state = DashState::new();
 		dash_state.debug_window = opt.debug_window;
+		dash_state.recent_warnings = RecentWarnings::shared(opt.warnings_max);
+		dash_state.diagnostics = Diagnostics::shared(opt.diagnostics_max);
+		if let Some(path) = &opt.debug_log {
+			if let Err(e) = dash_state.diagnostics.lock().unwrap().open_debug_log(path) {
+				println!("{}: failed to open --debug-log {}: {}", Opt::clap().get_name(), path.display(), e);
+				return exit_with_usage("invalid debug log");
+			}
+		}
+		if let Some(path) = &opt.spotlight {
+			match load_spotlight_keywords(path) {
+				Ok(keywords) => dash_state.spotlight = Spotlight::shared(keywords, opt.warnings_max),
+				Err(e) => {
+					println!("{}: {}", Opt::clap().get_name(), e);
+					return exit_with_usage("invalid spotlight");
+				}
+			}
+		}
+		dash_state.timelines = opt.timeline_steps.timelines.clone();
+		dash_state.stale_after = opt.stale_after;
+		dash_state.check_errors = opt.check_errors;
+		dash_state.health_weights = opt.health_weights;
+		dash_state.health_colors = opt.health_colors;
+		dash_state.primary_gauge = opt.primary_gauge;
+		let timeline_names: Vec<String> = NodeMetrics::new(&opt).timelines().iter().map(|timeline| timeline.get_name().clone()).collect();
+		dash_state.heatmap_metric = match find_heatmap_metric(&opt.heatmap_metric, &timeline_names) {
+			Ok(metric) => metric,
+			Err(e) => {
+				println!("{}: {}", Opt::clap().get_name(), e);
+				return exit_with_usage("invalid heatmap metric");
+			}
+		};
+		dash_state.gauge_peers = opt.gauge_peers;
+		dash_state.downsample = opt.downsample;
+		dash_state.spark_style = opt.spark_style;
+		for (timeline, style) in &opt.chart_styles {
+			dash_state.chart_styles.set(timeline.as_deref(), *style);
+		}
+		if opt.trend_colors {
+			let mut directions = GoodDirections::default();
+			for (timeline, direction) in &opt.good_directions {
+				directions.set(timeline, *direction);
+			}
+			dash_state.trend_directions = Some(directions);
+		}
+		dash_state.summary_columns = opt.columns.clone();
+		dash_state.top_talkers = opt.top_talkers;
+		dash_state.blink = !opt.no_blink;
+		dash_state.change_flash = ChangeFlash::new(opt.flash_ticks);
+		dash_state.number_format = NumberFormat {
+			units: opt.units,
+			group_digits: opt.group_digits,
+			precision: opt.precision,
+			compact_from: opt.compact_counts,
+		};
+		dash_state.key_map = KeyMap::preset(opt.keys);
+		for (action, keys) in &opt.key_bindings {
+			dash_state.key_map.bind(*action, keys);
+		}
+		dash_state.spike_sigma = if opt.spike_sigma > 0.0 { Some(opt.spike_sigma) } else { None };
+		dash_state.theme = match Theme::resolve(opt.theme.as_deref(), opt.palette, opt.no_color) {
+			Ok(theme) => theme,
+			Err(e) => {
+				println!("{}: {}", Opt::clap().get_name(), e);
+				return exit_with_usage("invalid theme");
+			}
+		};
+		let preferences = match preferences_path(&opt) {
+			Some(path) => match UiPreferences::load(&path) {
+				Ok(preferences) => preferences.unwrap_or_default(),
+				Err(e) => {
+					println!("Ignoring preferences file {}: {}", path.display(), e);
+					UiPreferences::default()
+				}
+			},
+			None => UiPreferences::default(),
+		};
+		preferences.apply(&mut dash_state);
+		if let Some(view) = opt.view {
+			dash_state.main_view = view;
+		}
+		if let Some(sort_key) = opt.sort {
+			dash_state.sort_key = sort_key;
+		}
+		if let Some(hidden_panels) = &opt.hide {
+			dash_state.hidden_panels = hidden_panels.clone();
+		}
+		let node_filter = opt.filter.clone().or(preferences.filter);
 		if opt.debug_dashboard {
 			dash_state.main_view = DashViewMain::DashDebug;
 		}
+		let node_capacities = match &opt.node_capacities {
+			Some(path) => match NodeCapacities::load(path) {
+				Ok(node_capacities) => node_capacities,
+				Err(e) => {
+					println!("{}: {}", Opt::clap().get_name(), e);
+					return exit_with_usage("invalid node capacities");
+				}
+			},
+			None => NodeCapacities::default(),
+		};
+		let mut node_names = match &opt.names {
+			Some(path) => match NodeNames::load(path) {
+				Ok(node_names) => node_names,
+				Err(e) => {
+					println!("{}: {}", Opt::clap().get_name(), e);
+					return exit_with_usage("invalid node names");
+				}
+			},
+			None => NodeNames::default(),
+		};
+		for (logfile, name) in opt.node_names.iter() {
+			node_names.insert(logfile, name);
+		}
+		let error_spike_detector = if opt.notify {
+			Some(ErrorSpikeDetector::new(opt.notify_errors, opt.notify_debounce))
+		} else {
+			None
+		};
+		let user_metric_specs = match &opt.metrics_config {
+			Some(path) => match load_user_metric_specs(path) {
+				Ok(specs) => Arc::new(specs),
+				Err(e) => {
+					println!("{}: {}", Opt::clap().get_name(), e);
+					return exit_with_usage("invalid metrics config");
+				}
+			},
+			None => Arc::new(Vec::new()),
+		};
+		let alert_rules = match &opt.alerts {
+			Some(path) => match load_alert_rules(path) {
+				Ok(rules) => rules,
+				Err(e) => {
+					println!("{}: {}", Opt::clap().get_name(), e);
+					return exit_with_usage("invalid alerts");
+				}
+			},
+			None => Vec::new(),
+		};
+
+		let mut saved_state = match &opt.state_file {
+			Some(path) => match DashboardState::load(path) {
+				Ok(state) => state.unwrap_or_default(),
+				Err(e) => {
+					println!("Ignoring state file {}: {}", path.display(), e);
+					DashboardState::default()
+				}
+			},
+			None => DashboardState::default(),
+		};
+		saved_state.retain_logfiles(&opt.files);
 
 		let mut monitors: HashMap<String, LogMonitor> = HashMap::new();
 		let mut logfiles = MuxedLines::new()?;
 		let mut debug_logfile_name = String::new();
 		let mut logfile_names = Vec::<String>::new();
+		let mut unwatched_logfiles = Vec::<String>::new();
 
 		let mut debug_logfile: Option<tempfile::NamedTempFile> = if opt.debug_window {
 			opt.files = opt.files[0..1].to_vec();
@@ -89,7 +332,7 @@ impl App {
 			let path = named_file.path();
 			let path_str = path
 				.to_str()
-				.ok_or_else(|| Error::new(ErrorKind::Other, "invalid path"))?;
+				.ok_or_else(|| Error::other("invalid path"))?;
 			opt.files.push(String::from(path_str));
 			debug_logfile_name = String::from(path_str);
 			Some(named_file)
@@ -97,49 +340,139 @@ impl App {
 			None
 		};
 
-		println!("Loading {} files...", opt.files.len());
+		eprintln!("Loading {} files...", opt.files.len());
 		let mut first_logfile = String::new();
+		let mut stdin_reader = None;
+		let mut replay = None;
 		for f in &opt.files {
-			println!("file: {}", f);
+			eprintln!("file: {}", f);
+			log::info!("monitoring logfile {}", f);
 			if first_logfile.is_empty() {
 				first_logfile = f.to_string();
 			}
 			let mut monitor = LogMonitor::new(&opt, f.to_string(), opt.lines_max);
-			if opt.debug_window && monitor.index == 0 {
-				if let Some(named_file) = debug_logfile {
-					*DEBUG_LOGFILE.lock().unwrap() = Some(named_file);
-					debug_logfile = None;
-				}
-			}
-			if opt.ignore_existing {
+			monitor.metrics.capacity_override = node_capacities.capacity_for(f);
+			monitor.name = node_names.name_for(f);
+			monitor.tag = node_names.tag_for(f);
+			monitor.metrics.user_metrics = UserMetrics::new(user_metric_specs.clone());
+			monitor.recent_warnings = Some(dash_state.recent_warnings.clone());
+			monitor.spotlight = Some(dash_state.spotlight.clone());
+			monitor.diagnostics = Some(dash_state.diagnostics.clone());
+			monitor.watch_metrics_endpoint(&opt);
+			if f == STDIN_LOGFILE {
+				// Read by a thread rather than followed as a logfile
+				stdin_reader = Some(StdinReader::spawn(std::io::BufReader::new(std::io::stdin())));
 				logfile_names.push(f.to_string());
 				monitors.insert(f.to_string(), monitor);
-			} else {
-				match monitor.load_logfile(&mut dash_state) {
-					Ok(()) => {
-						logfile_names.push(f.to_string());
-						monitors.insert(f.to_string(), monitor);
-					}
+				continue;
+			}
+			if opt.replay.as_deref() == Some(f.as_str()) {
+				// Fed a tick at a time rather than followed, and neither restored nor saved
+				match Replay::load(f, opt.speed, opt.log_profile) {
+					Ok(loaded) => replay = Some(loaded),
 					Err(e) => {
 						println!("...failed: {}", e);
 						return Err(e);
 					}
 				}
+				monitor.is_replay = true;
+				logfile_names.push(f.to_string());
+				monitors.insert(f.to_string(), monitor);
+				continue;
+			}
+			if opt.debug_window && monitor.index == 0 {
+				if let Some(named_file) = debug_logfile {
+					*DEBUG_LOGFILE.lock().unwrap() = Some(named_file);
+					debug_logfile = None;
+				}
+			}
+			let logfile_len = std::fs::metadata(f).map(|m| m.len()).unwrap_or(0);
+			let mut saved_offset = None;
+			if let Some(node_state) = saved_state.nodes.get(f) {
+				// Only restore if the logfile hasn't been truncated or replaced since
+				if node_state.logfile_offset <= logfile_len {
+					eprintln!("...restoring saved state");
+					node_state.restore(&mut monitor.metrics);
+					saved_offset = Some(node_state.logfile_offset);
+				}
+			}
+			// Saved state already includes the history of rotated logfiles
+			if opt.replay_gz && opt.from == ReadFrom::Start && saved_offset.is_none() {
+				for warning in monitor.replay_rotated_logfiles() {
+					println!("Warning: {}", warning);
+				}
+			}
+			let logfile_offset = start_offset(opt.from, logfile_len, saved_offset);
+			match monitor.load_logfile_from(&mut dash_state, logfile_offset) {
+				Ok(()) => {
+					logfile_names.push(f.to_string());
+					monitors.insert(f.to_string(), monitor);
+				}
+				Err(e) => {
+					println!("...failed: {}", e);
+					return Err(e);
+				}
 			}
 
 			match logfiles.add_file(&f).await {
 				Ok(_) => (),
+				Err(e) if !Path::new(f).exists() => {
+					// Typically the node hasn't created its directory yet
+					println!("Waiting for {} to be created ({})", f, e);
+					unwatched_logfiles.push(f.to_string());
+				}
+				Err(e) => {
+					// Such as a logfile without read permission, which is tried again later
+					println!("Unable to watch {} ({})", f, e);
+					unwatched_logfiles.push(f.to_string());
+				}
+			}
+		}
+
+		let shared_snapshot = if opt.metrics_addr.is_some() || opt.query_socket.is_some() {
+			Some(SharedSnapshot::default())
+		} else {
+			None
+		};
+		let metrics_tls = match metrics_tls_config(opt.metrics_tls_cert.as_deref(), opt.metrics_tls_key.as_deref()) {
+			Ok(metrics_tls) => metrics_tls,
+			Err(e) => {
+				eprintln!("{}: {}", Opt::clap().get_name(), e);
+				return exit_with_usage("invalid metrics TLS options");
+			}
+		};
+		if let (Some(addr), Some(snapshot)) = (&opt.metrics_addr, &shared_snapshot) {
+			match spawn_metrics_server(addr, metrics_tls.as_ref(), snapshot.clone()) {
+				Ok(local_addr) => {
+					let scheme = if metrics_tls.is_some() { "https" } else { "http" };
+					eprintln!("Serving metrics at {}://{}/metrics", scheme, local_addr);
+					log::info!("metrics server started at {}://{}/metrics", scheme, local_addr);
+				}
 				Err(e) => {
-					println!("ERROR: {}", e);
-					println!(
-						"Note: it is ok for the file not to exist, but the file's parent directory must exist."
-					);
+					log::error!("metrics server failed to start at {}: {}", addr, e);
+					eprintln!("ERROR: failed to serve metrics at {}: {}", addr, e);
 					return Err(e);
 				}
 			}
 		}
+		let query_socket = match (&opt.query_socket, &shared_snapshot) {
+			(Some(path), Some(snapshot)) => match spawn_query_socket(path, snapshot.clone()) {
+				Ok(query_socket) => Some(query_socket),
+				Err(e) => {
+					eprintln!("ERROR: failed to answer queries on {}: {}", path.display(), e);
+					return Err(e);
+				}
+			},
+			_ => None,
+		};
 
 		let activate_debug_dashboard = opt.debug_dashboard;
+		let glob_rescan = opt.glob_rescan;
+		let redraw = RedrawLimiter::new(
+			std::time::Duration::from_millis(opt.tick_rate),
+			std::time::Duration::from_millis(opt.idle_tick_rate),
+		);
+		let run_for = RunFor::new(launched, opt.run_for.map(std::time::Duration::from_secs));
 		let mut app = App {
 			opt,
 			dash_state,
@@ -147,6 +480,22 @@ impl App {
 			logfile_with_focus: first_logfile.clone(),
 			logfiles,
 			logfile_names,
+			unwatched_logfiles,
+			glob_patterns,
+			next_glob_rescan: Utc::now() + Duration::seconds(glob_rescan as i64),
+			next_state_save: Utc::now() + Duration::seconds(STATE_SAVE_INTERVAL),
+			next_metrics_export: Utc::now(),
+			shared_snapshot,
+			query_socket,
+			stdin_reader,
+			replay,
+			node_capacities,
+			node_names,
+			error_spike_detector,
+			user_metric_specs,
+			alert_rules,
+			redraw,
+			run_for,
 		};
 		app.update_timelines(&Utc::now());
 
@@ -156,14 +505,294 @@ impl App {
 
 		if activate_debug_dashboard {
 			app.set_logfile_with_focus(debug_logfile_name);
-		} else {
+		} else if !first_logfile.is_empty() {
 			app.set_logfile_with_focus(first_logfile);
 		}
+		if let Some(node_filter) = node_filter {
+			app.set_node_filter(&node_filter);
+		}
 		Ok(app)
 	}
 
+	///! Save the view, sort order and filter for next time, unless --no-prefs
+	pub fn save_preferences(&self) -> Result<(), std::io::Error> {
+		if let Some(path) = preferences_path(&self.opt) {
+			UiPreferences::from_dash_state(&self.dash_state).save(&path)?;
+		}
+		Ok(())
+	}
+
+	///! Save the preferences and --state-file on quitting, attempting both
+	///! even if one fails
+	pub fn save_on_exit(&mut self) -> Result<(), std::io::Error> {
+		log::info!("stopping, with the metrics server and query socket if started");
+		let preferences = self.save_preferences();
+		self.save_state()?;
+		preferences
+	}
+
+	///! Save node metrics to --state-file, if given
+	pub fn save_state(&mut self) -> Result<(), std::io::Error> {
+		if let Some(path) = &self.opt.state_file {
+			DashboardState::from_monitors(&self.monitors).save(path)?;
+		}
+		Ok(())
+	}
+
+	///! Save state every STATE_SAVE_INTERVAL seconds. Failures are shown
+	///! in the debug window rather than ending the dashboard
+	pub fn save_state_periodically(&mut self, now: &DateTime<Utc>) {
+		if self.opt.state_file.is_none() || *now < self.next_state_save {
+			return;
+		}
+		self.next_state_save = *now + Duration::seconds(STATE_SAVE_INTERVAL);
+		if let Err(e) = self.save_state() {
+			self.dash_state._debug_window(format!("failed to save state: {}", e).as_str());
+		}
+	}
+
+	///! Update --dump-json, --metrics-addr and --query-socket at most once a second
+	pub fn export_metrics_periodically(&mut self, now: &DateTime<Utc>) {
+		if *now < self.next_metrics_export {
+			return;
+		}
+		if self.opt.dump_json.is_none() && self.shared_snapshot.is_none() {
+			return;
+		}
+		self.next_metrics_export = *now + Duration::seconds(1);
+
+		let snapshot = MetricsSnapshot::from_monitors(&self.logfile_names, &self.monitors);
+		if let Some(path) = self.opt.dump_json.clone() {
+			if let Err(e) = snapshot.write_json(&path) {
+				self.dash_state._debug_window(format!("failed to write {}: {}", path, e).as_str());
+			}
+		}
+		if let Some(shared) = &self.shared_snapshot {
+			*shared.lock().unwrap() = Some(snapshot);
+		}
+	}
+
+	///! For --notify, show a desktop notification for each node whose error rate has spiked
+	pub fn notify_error_spikes(&mut self, now: &DateTime<Utc>) {
+		let detector = match &mut self.error_spike_detector {
+			Some(detector) => detector,
+			None => return,
+		};
+		let quiet = self.opt.quiet_hours.is_some_and(|quiet_hours| quiet_hours.contains(now.with_timezone(&Local).time()));
+		let mut messages = Vec::<String>::new();
+		for monitor in self.monitors.values().filter(|monitor| !monitor.is_debug_dashboard_log && monitor.enabled) {
+			let errors_per_minute = monitor.metrics.errors_per_minute();
+			if detector.check(&monitor.logfile, errors_per_minute, now) {
+				let (title, text) = error_spike_message(&monitor.name, errors_per_minute);
+				if quiet {
+					messages.push(format!("not notifying '{}' during quiet hours", text));
+				} else if let Err(e) = notify_desktop(&title, &text) {
+					messages.push(format!("failed to notify '{}': {}", text, e));
+				}
+			}
+		}
+		for message in messages {
+			self.dash_state._debug_window(&message);
+		}
+	}
+
+	///! Replace the alerts shown with those of the --alerts rules broken now
+	pub fn evaluate_alerts(&mut self, now: &DateTime<Utc>) {
+		self.dash_state.ticks = self.dash_state.ticks.wrapping_add(1); // For the blink of critical alerts and the flash of changes
+		if self.alert_rules.is_empty() {
+			return;
+		}
+		let nodes = self
+			.logfile_names
+			.iter()
+			.filter_map(|logfile| self.monitors.get(logfile))
+			.filter(|monitor| !monitor.is_debug_dashboard_log && monitor.enabled)
+			.map(|monitor| (monitor.name.as_str(), &monitor.metrics));
+		let firing = evaluate_alerts(&self.alert_rules, nodes, now);
+		self.dash_state.active_alerts = carry_acknowledgements(&self.dash_state.active_alerts, firing);
+	}
+
+	///! Show the next of the nodes' timelines in the heatmap view
+	pub fn next_heatmap_metric(&mut self) {
+		let names: Vec<String> = match self.monitors.values().next() {
+			Some(monitor) => monitor.metrics.timelines().iter().map(|timeline| timeline.get_name().clone()).collect(),
+			None => return,
+		};
+		if let Some(metric) = next_heatmap_metric(&self.dash_state.heatmap_metric, &names) {
+			self.dash_state.heatmap_metric = metric;
+		}
+	}
+
+	///! Copy the status of the node shown, as in its panel, to the clipboard
+	pub fn copy_focused_node_metrics(&mut self) {
+		let monitor = match self.monitors.get(&self.logfile_with_focus) {
+			Some(monitor) => monitor,
+			None => return,
+		};
+		let text = node_stats_text(&self.dash_state, monitor, &Utc::now());
+		let message = match copy_to_clipboard(&text) {
+			Ok(tool) => format!("copied the metrics of {} to the clipboard (with {})", monitor.logfile, tool),
+			Err(e) => format!("failed to copy the metrics of {}: {}", monitor.logfile, e),
+		};
+		self.dash_state._debug_window(&message);
+	}
+
+	///! Mute the alert at the top of the banner until it clears, see acknowledge_next()
+	pub fn acknowledge_alert(&mut self) {
+		let message = match acknowledge_next(&mut self.dash_state.active_alerts) {
+			Some(alert) => format!("acknowledged {}", alert),
+			None => String::from("no alert to acknowledge"),
+		};
+		self.dash_state._debug_window(&message);
+	}
+
+	///! Monitor the logfiles without a dashboard, writing a line
+	///! of JSON to stdout every second (for '--dump-json -')
+	pub async fn dump_json_to_stdout(&mut self) -> Result<(), std::io::Error> {
+		use tokio_stream::StreamExt;
+
+		let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+		loop {
+			let follow_logfiles = self.has_logfiles_to_follow();
+			tokio::select! {
+				_ = interval.tick() => {
+					let now = Utc::now();
+					self.update_timelines(&now);
+					self.follow_logfiles()?;
+					self.rescan_glob_patterns(&now).await?;
+					self.watch_unwatched_logfiles().await;
+					self.save_state_periodically(&now);
+					let snapshot = MetricsSnapshot::from_monitors(&self.logfile_names, &self.monitors);
+					snapshot.write_json("-")?;
+					if let Some(shared) = &self.shared_snapshot {
+						*shared.lock().unwrap() = Some(snapshot);
+					}
+				}
+				line = self.logfiles.next(), if follow_logfiles => {
+					match line {
+						Some(Ok(line)) => {
+							let source = line.source().to_string_lossy().to_string();
+							self.follow_logfile(&source)?;
+						}
+						Some(Err(e)) => return Err(e),
+						None => {}
+					}
+				}
+			}
+		}
+	}
+
+	///! Print the health of each node, for --check, returning true if all
+	///! are healthy
+	pub fn check_health(&mut self) -> bool {
+		let now = Utc::now();
+		self.update_timelines(&now);
+		let criteria = self.dash_state.health_criteria();
+		let mut all_healthy = true;
+		for logfile in &self.logfile_names {
+			if let Some(monitor) = self.monitors.get(logfile) {
+				if monitor.is_debug_dashboard_log {
+					continue;
+				}
+				let health = evaluate_health(&monitor.metrics, &criteria, &now);
+				println!("{}", health.report_line(&monitor.name));
+				all_healthy &= health.is_healthy();
+			}
+		}
+		all_healthy
+	}
+
+	///! Print the --parse-report of a logfile
+	pub fn print_parse_report(&self, path: &Path) -> Result<(), std::io::Error> {
+		let mut report = ParseReport::new(&self.opt, self.user_metric_specs.clone());
+		report.read_logfile(path)?;
+		print!("{}", report.format(&path.display().to_string()));
+		Ok(())
+	}
+
+	///! Start monitoring any new files matching the LOGFILE glob patterns
+	///! Does nothing until --glob-rescan seconds have passed since the last rescan
+	pub async fn rescan_glob_patterns(&mut self, now: &DateTime<Utc>) -> Result<(), std::io::Error> {
+		if self.glob_patterns.is_empty() || self.dash_state.paused || *now < self.next_glob_rescan {
+			return Ok(());
+		}
+		self.next_glob_rescan = *now + Duration::seconds(self.opt.glob_rescan as i64);
+
+		let mut monitored_keys: Option<Vec<String>> = None; // Of the logfiles monitored, once needed
+		for pattern in self.glob_patterns.clone() {
+			let matches = match glob_matches(&pattern) {
+				Ok(matches) => matches,
+				Err(e) => {
+					self.dash_state._debug_window(&e);
+					continue;
+				}
+			};
+			for f in matches {
+				if self.monitors.contains_key(&f) {
+					continue;
+				}
+				if !self.opt.keep_duplicates {
+					let keys = monitored_keys.get_or_insert_with(|| self.monitors.keys().map(|logfile| logfile_key(logfile)).collect());
+					let key = logfile_key(&f);
+					if keys.contains(&key) {
+						continue; // The same logfile as one monitored, by another path
+					}
+					keys.push(key);
+				}
+				self.dash_state._debug_window(format!("new logfile: {}", f).as_str());
+				log::info!("monitoring new logfile {}, matching a glob pattern", f);
+				let mut monitor = LogMonitor::new(&self.opt, f.clone(), self.opt.lines_max);
+				monitor.metrics.capacity_override = self.node_capacities.capacity_for(&f);
+				monitor.name = self.node_names.name_for(&f);
+				monitor.tag = self.node_names.tag_for(&f);
+				monitor.metrics.user_metrics = UserMetrics::new(self.user_metric_specs.clone());
+				monitor.recent_warnings = Some(self.dash_state.recent_warnings.clone());
+				monitor.spotlight = Some(self.dash_state.spotlight.clone());
+				monitor.diagnostics = Some(self.dash_state.diagnostics.clone());
+				monitor.watch_metrics_endpoint(&self.opt);
+				if self.opt.from == ReadFrom::End {
+					monitor.skip_existing_lines();
+				} else {
+					monitor.load_logfile(&mut self.dash_state)?;
+				}
+				monitor.metrics.update_timelines(now);
+				if let Err(e) = self.logfiles.add_file(&f).await {
+					self.dash_state._debug_window(format!("unable to watch {}: {}", f, e).as_str());
+					self.unwatched_logfiles.push(f.clone());
+				}
+				self.logfile_names.push(f.clone());
+				self.monitors.insert(f, monitor);
+			}
+		}
+		Ok(())
+	}
+
+	///! Start watching logfiles whose directory didn't exist, or which couldn't
+	///! be read, when first added. They are still followed by polling
+	///! meanwhile, see follow_logfiles()
+	pub async fn watch_unwatched_logfiles(&mut self) {
+		for f in std::mem::take(&mut self.unwatched_logfiles) {
+			let parent_exists = match Path::new(&f).parent() {
+				Some(dir) if !dir.as_os_str().is_empty() => dir.exists(),
+				_ => true,
+			};
+			if parent_exists && self.logfiles.add_file(&f).await.is_ok() {
+				self.dash_state._debug_window(format!("watching logfile: {}", f).as_str());
+			} else {
+				self.unwatched_logfiles.push(f);
+			}
+		}
+	}
+
+	///! Does nothing while paused, so the timelines stay as they were
 	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
+		if self.dash_state.paused {
+			return;
+		}
+		let replayed_to = self.replay.as_ref().and_then(|replay| replay.clock());
 		for (_monitor_file, monitor) in self.monitors.iter_mut() {
+			// A replay's timelines follow the times it has replayed
+			let now = if monitor.is_replay { replayed_to.as_ref().unwrap_or(now) } else { now };
 			monitor.metrics.update_timelines(now);
 		}
 	}
@@ -174,23 +803,104 @@ impl App {
 		}
 	}
 
+	///! Read any new lines from a logfile reported by linemux. The lines are
+	///! read by the monitor itself so that it can recover from truncation
+	///! and rotation, which linemux may not report
+	pub fn follow_logfile(&mut self, logfile: &String) -> Result<(), std::io::Error> {
+		if let Some(monitor_file) = self.get_monitor_file_for_path(logfile) {
+			if let Some(monitor) = self.monitors.get_mut(&monitor_file) {
+				monitor.follow_logfile(&mut self.dash_state)?;
+			}
+		}
+		Ok(())
+	}
+
+	///! Catch up with every logfile, including any that linemux has stopped reporting,
+	///! with any lines from stdin and with those due from a --replay
+	pub fn follow_logfiles(&mut self) -> Result<(), std::io::Error> {
+		let now = Utc::now();
+		for node_idx in 0..self.logfile_names.len() {
+			let monitor = match self.monitors.get_mut(&self.logfile_names[node_idx]) {
+				Some(monitor) => monitor,
+				None => continue,
+			};
+			let lines = match (&mut self.stdin_reader, &mut self.replay) {
+				(Some(stdin_reader), _) if monitor.logfile == STDIN_LOGFILE => monitor.read_stdin(&mut self.dash_state, stdin_reader),
+				(_, Some(replay)) if monitor.is_replay => monitor.read_replay(&mut self.dash_state, replay),
+				_ => {
+					monitor.follow_logfile(&mut self.dash_state)?;
+					continue;
+				}
+			};
+			for line in lines {
+				self.process_line(node_idx, &line, &now)?;
+			}
+		}
+		Ok(())
+	}
+
+	///! Put a line of the node at node_idx, in the order of logfile_names,
+	///! through the whole pipeline as if it had been read from its logfile,
+	///! see LogMonitor::process_line(). Lines of a disabled node are dropped
+	pub fn process_line(&mut self, node_idx: usize, line: &str, now: &DateTime<Utc>) -> Result<(), std::io::Error> {
+		let monitors = &mut self.monitors;
+		let monitor = self
+			.logfile_names
+			.get(node_idx)
+			.and_then(|logfile| monitors.get_mut(logfile))
+			.ok_or_else(|| Error::new(std::io::ErrorKind::NotFound, format!("no node {}", node_idx)))?;
+		if monitor.enabled {
+			monitor.process_line(line, now)?;
+		}
+		Ok(())
+	}
+
+	///! Apply the latest metrics fetched from each node's --node-metrics-url
+	pub fn poll_metrics_endpoints(&mut self, now: &DateTime<Utc>) {
+		for monitor in self.monitors.values_mut().filter(|monitor| monitor.enabled) {
+			if let Some(endpoint) = monitor.metrics_endpoint.as_mut() {
+				if let Some(message) = endpoint.update(&mut monitor.metrics, now) {
+					self.dash_state._debug_window(&message);
+				}
+			}
+		}
+	}
+
+	///! False when there are no files for linemux to follow, as when only reading stdin
+	///! or waiting for the directories of logfiles to be created
+	pub fn has_logfiles_to_follow(&self) -> bool {
+		self.logfile_names
+			.iter()
+			.any(|name| name != STDIN_LOGFILE && Some(name) != self.opt.replay.as_ref() && !self.unwatched_logfiles.contains(name))
+	}
+
 	pub fn get_monitor_for_file_path(&mut self, logfile: &String) -> Option<&mut LogMonitor> {
-		let mut monitor_for_path = None;
-		for (monitor_file, monitor) in self.monitors.iter_mut() {
+		let monitor_file = self.get_monitor_file_for_path(logfile)?;
+		self.monitors.get_mut(&monitor_file)
+	}
+
+	// The logfile as given of a path reported by linemux, which is canonical
+	// and on Windows a verbatim path such as '\\?\C:\nodes\safenode.log'.
+	// Cheaper comparisons are tried first, as this is done for every line
+	fn get_monitor_file_for_path(&self, logfile: &String) -> Option<String> {
+		for monitor_file in self.monitors.keys() {
 			if monitor_file.eq(logfile) {
-				monitor_for_path = Some(monitor);
-				break;
+				return Some(monitor_file.clone());
 			}
 			use std::env::current_dir;
 			if let Ok(current_dir) = current_dir() {
 				let logfile_path = Path::new(logfile.as_str());
 				if current_dir.join(monitor_file).eq(&logfile_path) {
-					monitor_for_path = Some(monitor);
-					break;
+					return Some(monitor_file.clone());
 				}
 			}
 		}
-		return monitor_for_path;
+		let normalized = normalize_path(logfile);
+		if let Some(monitor_file) = self.monitors.keys().find(|monitor_file| normalize_path(monitor_file) == normalized) {
+			return Some(monitor_file.clone());
+		}
+		let key = logfile_key(logfile);
+		self.monitors.keys().find(|monitor_file| logfile_key(monitor_file) == key).cloned()
 	}
 
 	pub fn get_debug_dashboard_logfile(&mut self) -> Option<String> {
@@ -203,26 +913,23 @@ impl App {
 	}
 
 	pub fn get_logfile_with_focus(&mut self) -> Option<String> {
-		match (&mut self.monitors).get_mut(&self.logfile_with_focus) {
+		match self.monitors.get_mut(&self.logfile_with_focus) {
 			Some(monitor) => Some(monitor.logfile.clone()),
 			None => None,
 		}
 	}
 
 	pub fn get_monitor_with_focus(&mut self) -> Option<&mut LogMonitor> {
-		match (&mut self.monitors).get_mut(&self.logfile_with_focus) {
+		match self.monitors.get_mut(&self.logfile_with_focus) {
 			Some(monitor) => Some(monitor),
 			None => None,
 		}
 	}
 
 	pub fn set_logfile_with_focus(&mut self, logfile_name: String) {
-		match self.get_monitor_with_focus() {
-			Some(fading_monitor) => {
-				fading_monitor.has_focus = false;
-				self.logfile_with_focus = String::new();
-			}
-			None => (),
+		if let Some(fading_monitor) = self.get_monitor_with_focus() {
+			fading_monitor.has_focus = false;
+			self.logfile_with_focus = String::new();
 		}
 
 		if logfile_name == DEBUG_WINDOW_NAME {
@@ -233,7 +940,7 @@ impl App {
 			self.dash_state.debug_window_has_focus = false;
 		}
 
-		if let Some(focus_monitor) = (&mut self.monitors).get_mut(&logfile_name) {
+		if let Some(focus_monitor) = self.monitors.get_mut(&logfile_name) {
 			focus_monitor.has_focus = true;
 			self.logfile_with_focus = logfile_name.clone();
 		} else {
@@ -241,106 +948,638 @@ impl App {
 		};
 	}
 
-	pub fn change_focus_next(&mut self) {
-		if self.dash_state.main_view == DashViewMain::DashDebug {
-			return;
+	///! Act on a key press. Returns true when the key means quit
+	pub fn handle_key(&mut self, key: BindingKey) -> Result<bool, std::io::Error> {
+		if self.dash_state.filter_editing {
+			match key {
+				BindingKey::Enter => self.finish_filter_edit(),
+				BindingKey::Esc => self.cancel_filter_edit(),
+				BindingKey::Backspace => self.pop_filter_char(),
+				BindingKey::Char(c) => self.push_filter_char(c),
+				_ => {}
+			}
+			return Ok(false);
 		}
-
-		let mut next_i = 0;
-		for (i, name) in self.logfile_names.iter().enumerate() {
-			if name == &self.logfile_with_focus {
-				if i < self.logfile_names.len() - 1 {
-					next_i = i + 1;
-				}
-				break;
+		if self.dash_state.node_jump.is_some() {
+			match key {
+				BindingKey::Enter => self.dash_state.node_jump = None,
+				BindingKey::Esc => self.cancel_node_jump(),
+				BindingKey::Backspace => self.edit_node_jump(None),
+				BindingKey::Char(c) => self.edit_node_jump(Some(c)),
+				_ => {}
 			}
+			return Ok(false);
 		}
 
-		if next_i == 0 && self.opt.debug_window && self.logfile_with_focus != DEBUG_WINDOW_NAME {
-			self.set_logfile_with_focus(DEBUG_WINDOW_NAME.to_string());
-			return;
+		let action = self.dash_state.key_map.action_for_key(key);
+		if self.dash_state.help_visible {
+			// Leave everything else as it was until the help is dismissed
+			if key == BindingKey::Esc || action == Some(Action::ToggleHelp) {
+				self.dash_state.help_visible = false;
+			}
+			return Ok(false);
 		}
 
-		let logfile = self.logfile_names[next_i].to_string();
-		self.set_logfile_with_focus(logfile.clone());
-
-		if let Some(debug_logfile) = self.get_debug_dashboard_logfile() {
-			if logfile.eq(&debug_logfile) {
-				self.change_focus_next();
+		if self.dash_state.timeline_has_focus {
+			match key {
+				BindingKey::Left => {
+					self.move_timeline_cursor(1);
+					return Ok(false);
+				}
+				BindingKey::Right => {
+					self.move_timeline_cursor(-1);
+					return Ok(false);
+				}
+				_ => {}
 			}
 		}
-	}
 
-	pub fn change_focus_previous(&mut self) {
-		if self.dash_state.main_view == DashViewMain::DashDebug {
-			return;
+		match action {
+			Some(Action::Quit) => return Ok(true),
+			Some(Action::ToggleHelp) => self.dash_state.help_visible = true,
+			Some(Action::SummaryView) => set_main_view(DashViewMain::DashSummary, self),
+			Some(Action::NodeView) => set_main_view(DashViewMain::DashNode, self),
+			Some(Action::ToggleDetail) => {
+				let view = if self.dash_state.main_view == DashViewMain::DashDetail {
+					DashViewMain::DashNode
+				} else {
+					DashViewMain::DashDetail
+				};
+				set_main_view(view, self);
+			}
+			Some(Action::DebugView) => set_main_view(DashViewMain::DashDebug, self),
+			Some(Action::WarningsView) => set_main_view(DashViewMain::DashWarnings, self),
+			Some(Action::DiagnosticsView) => {
+				let view = if self.dash_state.main_view == DashViewMain::DashDiagnostics {
+					DashViewMain::DashNode
+				} else {
+					DashViewMain::DashDiagnostics
+				};
+				set_main_view(view, self);
+			}
+			Some(Action::HeatmapView) => {
+				if self.dash_state.main_view == DashViewMain::DashHeatmap {
+					self.next_heatmap_metric();
+				} else {
+					set_main_view(DashViewMain::DashHeatmap, self);
+				}
+			}
+			Some(Action::MarkForCompare) => self.mark_for_compare(),
+			Some(Action::CompareView) => self.toggle_compare_view(),
+			Some(Action::ToggleFollow) => self.toggle_follow(),
+			Some(Action::CycleSortKey) => self.cycle_sort_key(),
+			Some(Action::ReverseSort) => self.toggle_sort_direction(),
+			Some(Action::StartFilter) => self.start_filter_edit(),
+			Some(Action::JumpToNode) => self.dash_state.node_jump = Some(NodeJump::new(&self.logfile_with_focus)),
+			Some(Action::ResetCounters) => self.reset_focused_node_counters(),
+			Some(Action::TogglePause) => self.toggle_pause()?,
+			Some(Action::ZoomIn) => self.scale_timeline_up(),
+			Some(Action::ZoomOut) => self.scale_timeline_down(),
+			Some(Action::ToggleLogScale) => self.toggle_timeline_scale(),
+			Some(Action::ToggleOverlay) => self.dash_state.overlay_timelines = !self.dash_state.overlay_timelines,
+			Some(Action::ToggleNormalize) => self.dash_state.normalize_overlay = !self.dash_state.normalize_overlay,
+			Some(Action::ToggleTimelineFocus) => self.toggle_timeline_focus(),
+			Some(Action::FreezeTimelines) => self.freeze_focused_node_timelines(),
+			Some(Action::ThawTimelines) => self.thaw_focused_node_timelines(),
+			Some(Action::DropMarker) => self.drop_marker(&Utc::now()),
+			Some(Action::ClearMarker) => self.clear_marker(),
+			Some(Action::AcknowledgeAlert) => self.acknowledge_alert(),
+			Some(Action::ToggleRates) => self.dash_state.counter_view = self.dash_state.counter_view.next(),
+			Some(Action::ExportCsv) => {
+				let path = self.opt.export_csv.clone();
+				let message = match self.timelines_csv().write(&path) {
+					Ok(()) => format!("exported timelines to {}", path.display()),
+					Err(e) => format!("failed to export timelines to {}: {}", path.display(), e),
+				};
+				self.dash_state._debug_window(&message);
+			}
+			Some(Action::Snapshot) => {
+				let path = self.opt.snapshot.clone();
+				let ansi = self.opt.snapshot_ansi;
+				let area = self.dash_state.screen_area;
+				let message = match write_screenshot(self, area, &path, ansi) {
+					Ok(()) => format!("wrote the screen to {}", path.display()),
+					Err(e) => format!("failed to write the screen to {}: {}", path.display(), e),
+				};
+				self.dash_state._debug_window(&message);
+			}
+			Some(Action::CopyMetrics) => self.copy_focused_node_metrics(),
+			Some(Action::ToggleStatsPanel) => self.dash_state.hidden_panels.toggle(PanelKind::Stats),
+			Some(Action::ToggleResourcesPanel) => self.dash_state.hidden_panels.toggle(PanelKind::Resources),
+			Some(Action::ToggleLatencyPanel) => self.dash_state.hidden_panels.toggle(PanelKind::Latency),
+			Some(Action::ToggleTimelinesPanel) => self.dash_state.hidden_panels.toggle(PanelKind::Timelines),
+			Some(Action::ToggleGrowthPanel) => self.dash_state.hidden_panels.toggle(PanelKind::Growth),
+			Some(Action::ToggleLogPanel) => self.dash_state.hidden_panels.toggle(PanelKind::Log),
+			Some(Action::CycleFocusMetric) => self.dash_state.focus_metric = FocusMetric::next(self.dash_state.focus_metric),
+			Some(Action::ToggleEnabled) => self.toggle_focused_node_enabled(),
+			Some(Action::ToggleGroup) => self.toggle_focused_group(),
+			Some(Action::NextNode) => self.change_focus_next(),
+			Some(Action::PreviousNode) => self.change_focus_previous(),
+			Some(Action::ScrollUp) => self.handle_arrow_up(),
+			Some(Action::ScrollDown) => self.handle_arrow_down(),
+			Some(Action::ScrollTop) => self.scroll_to_end(false),
+			Some(Action::ScrollBottom) => self.scroll_to_end(true),
+			None => {}
 		}
+		Ok(false)
+	}
 
-		let len = self.logfile_names.len();
-		let mut previous_i = len - 1;
-		for (i, name) in self.logfile_names.iter().enumerate() {
-			if name == &self.logfile_with_focus {
-				if i > 0 {
-					previous_i = i - 1;
+	///! The timelines of every node at each resolution, in logfile order
+	pub fn timelines_csv(&self) -> TimelinesCsv {
+		let names_metrics = NodeMetrics::new(&self.opt);
+		let metric_names: Vec<&str> = names_metrics.timelines().iter().map(|timeline| timeline.get_name().as_str()).collect();
+		let mut csv = TimelinesCsv::new(&metric_names);
+		for logfile in self.logfile_names.iter() {
+			if let Some(monitor) = self.monitors.get(logfile) {
+				if monitor.is_debug_dashboard_log {
+					continue;
+				}
+				for spec in self.dash_state.timelines.iter() {
+					csv.push_node(logfile, &monitor.metrics.timelines(), &spec.name);
 				}
-				break;
 			}
 		}
+		csv
+	}
 
-		if self.opt.debug_window
-			&& previous_i == len - 1
-			&& self.logfile_with_focus != DEBUG_WINDOW_NAME
-		{
-			self.set_logfile_with_focus(DEBUG_WINDOW_NAME.to_string());
+	///! After the terminal is resized, forget the regions of the last frame so
+	///! that no click is mapped to where they used to be, and draw afresh
+	pub fn resize(&mut self, area: Rect) {
+		self.dash_state.screen_area = area;
+		self.dash_state.click_targets = ClickTargets::default();
+		self.redraw.mark_stale();
+	}
+
+	///! Act on a mouse click. Clicking a node row of the summary shows that node
+	pub fn handle_click(&mut self, column: u16, row: u16) {
+		if self.dash_state.filter_editing || self.dash_state.node_jump.is_some() || self.dash_state.help_visible {
 			return;
 		}
 
-		let logfile = self.logfile_names[previous_i].to_string();
-		self.set_logfile_with_focus(logfile.clone());
+		if self.dash_state.main_view == DashViewMain::DashSummary {
+			if let Some(logfile) = self.dash_state.click_targets.node_at(column, row).cloned() {
+				self.dash_state.dash_node_focus = logfile;
+				set_main_view(DashViewMain::DashNode, self);
+			}
+		}
+	}
 
-		if let Some(debug_logfile) = self.get_debug_dashboard_logfile() {
-			if logfile.eq(&debug_logfile) {
-				self.change_focus_previous();
+	///! Type a character of the node to jump to, or with None delete the last,
+	///! and focus the first node matching what has been typed, see jump_match()
+	pub fn edit_node_jump(&mut self, c: Option<char>) {
+		let typed = match &mut self.dash_state.node_jump {
+			Some(jump) => {
+				match c {
+					Some(c) => jump.push(c),
+					None => jump.pop(),
+				}
+				jump.typed().to_string()
 			}
+			None => return,
+		};
+		let logfiles = self.jump_candidates();
+		let names: Vec<&str> = logfiles.iter().map(|logfile| self.monitors.get(logfile).map_or(logfile.as_str(), |monitor| monitor.name.as_str())).collect();
+		let paths: Vec<&str> = logfiles.iter().map(String::as_str).collect();
+		// Nodes without --names are named by their logfile, so may only differ by its directory
+		if let Some(index) = jump_match(&typed, &names).or_else(|| jump_match(&typed, &paths)) {
+			let logfile = logfiles[index].clone();
+			self.focus_node(logfile);
 		}
 	}
 
-	pub fn handle_arrow_up(&mut self) {
-		if let Some(monitor) = self.get_monitor_with_focus() {
-			do_bracketed_next_previous(&mut monitor.content, false);
-		} else if self.opt.debug_window {
-			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, false);
+	///! Escape returns to the node shown before the jump
+	pub fn cancel_node_jump(&mut self) {
+		if let Some(jump) = self.dash_state.node_jump.take() {
+			self.focus_node(jump.origin().to_string());
 		}
 	}
 
-	pub fn handle_arrow_down(&mut self) {
-		if let Some(monitor) = self.get_monitor_with_focus() {
-			do_bracketed_next_previous(&mut monitor.content, true);
-		} else if self.opt.debug_window {
-			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, true);
+	// The logfiles of the nodes which can be jumped to, in the order shown
+	fn jump_candidates(&mut self) -> Vec<String> {
+		if self.dash_state.main_view == DashViewMain::DashSummary {
+			return self.dash_state.summary_logfiles.clone();
 		}
+		let debug_logfile = self.get_debug_dashboard_logfile();
+		let any_match = self.any_node_matches_filter();
+		self.logfile_names
+			.iter()
+			.filter(|logfile| debug_logfile.as_ref() != Some(*logfile) && (!any_match || self.node_matches_filter(logfile)))
+			.cloned()
+			.collect()
 	}
 
-	pub fn scale_timeline_up(&mut self) {
-		if self.dash_state.active_timeline == 0 {
+	fn focus_node(&mut self, logfile: String) {
+		if self.dash_state.main_view == DashViewMain::DashSummary {
+			self.focus_summary_row(logfile);
+		} else {
+			self.set_logfile_with_focus(logfile);
+		}
+	}
+
+	pub fn start_filter_edit(&mut self) {
+		self.dash_state.filter_editing = true;
+	}
+
+	pub fn finish_filter_edit(&mut self) {
+		self.dash_state.filter_editing = false;
+	}
+
+	///! Escape abandons filtering and shows all nodes again
+	pub fn cancel_filter_edit(&mut self) {
+		self.dash_state.filter_editing = false;
+		self.set_node_filter("");
+	}
+
+	pub fn push_filter_char(&mut self, c: char) {
+		let text = format!("{}{}", self.dash_state.node_filter.get_text(), c);
+		self.set_node_filter(&text);
+	}
+
+	pub fn pop_filter_char(&mut self) {
+		let mut text = self.dash_state.node_filter.get_text().clone();
+		text.pop();
+		self.set_node_filter(&text);
+	}
+
+	///! Sets the filter, moving focus off the current node if it is filtered out
+	pub fn set_node_filter(&mut self, text: &str) {
+		self.dash_state.node_filter.set_text(text);
+		if (self.dash_state.main_view == DashViewMain::DashNode || self.dash_state.main_view == DashViewMain::DashDetail)
+			&& !self.node_matches_filter(&self.logfile_with_focus)
+		{
+			self.change_focus_next();
+		}
+	}
+
+	fn any_node_matches_filter(&self) -> bool {
+		let filter = &self.dash_state.node_filter;
+		self.monitors
+			.values()
+			.any(|monitor| !monitor.is_debug_dashboard_log && filter.matches_node(monitor))
+	}
+
+	fn node_matches_filter(&self, logfile: &str) -> bool {
+		match self.monitors.get(logfile) {
+			Some(monitor) => self.dash_state.node_filter.matches_node(monitor),
+			None => self.dash_state.node_filter.matches(logfile),
+		}
+	}
+
+	pub fn change_focus_next(&mut self) {
+		self.change_focus(true);
+	}
+
+	pub fn change_focus_previous(&mut self) {
+		self.change_focus(false);
+	}
+
+	///! Focus the next or previous node, skipping nodes hidden by the filter
+	///! and the --debug-dashboard logfile. The debug window, if shown, comes
+	///! after the last node
+	fn change_focus(&mut self, forward: bool) {
+		if self.dash_state.main_view == DashViewMain::DashDebug {
+			return;
+		}
+
+		let mut names = self.logfile_names.clone();
+		if self.opt.debug_window {
+			names.push(DEBUG_WINDOW_NAME.to_string());
+		}
+		let debug_logfile = self.get_debug_dashboard_logfile();
+		let any_match = self.any_node_matches_filter();
+		let current = names.iter().position(|name| name == &self.logfile_with_focus);
+		let include = |i: usize| {
+			let name = &names[i];
+			name == DEBUG_WINDOW_NAME
+				|| (debug_logfile.as_ref() != Some(name) && (!any_match || self.node_matches_filter(name)))
+		};
+		if let Some(i) = step_index(names.len(), current, forward, !self.opt.no_wrap_nav, include) {
+			self.set_logfile_with_focus(names[i].clone());
+		}
+	}
+
+	///! Whether the detail view's log tail follows new lines of the node shown
+	pub fn toggle_follow(&mut self) {
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			let following = !monitor.tail.is_following();
+			monitor.tail.set_following(following);
+		}
+	}
+
+	pub fn handle_arrow_up(&mut self) {
+		if self.dash_state.main_view == DashViewMain::DashWarnings {
+			self.dash_state.recent_warnings.lock().unwrap().scroll_up(1);
+			return;
+		}
+		if self.dash_state.main_view == DashViewMain::DashSummary {
+			self.step_summary_focus(false);
+			return;
+		}
+		let wrap = !self.opt.no_wrap_nav;
+		let detail = self.dash_state.main_view == DashViewMain::DashDetail;
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			if detail {
+				monitor.tail.scroll_up(1);
+				return;
+			}
+			do_bracketed_next_previous(&mut monitor.content, false, wrap);
+		} else if self.opt.debug_window {
+			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, false, wrap);
+		}
+	}
+
+	pub fn handle_arrow_down(&mut self) {
+		if self.dash_state.main_view == DashViewMain::DashWarnings {
+			self.dash_state.recent_warnings.lock().unwrap().scroll_down(1);
+			return;
+		}
+		if self.dash_state.main_view == DashViewMain::DashSummary {
+			self.step_summary_focus(true);
 			return;
 		}
-		self.dash_state.active_timeline -= 1;
+		let wrap = !self.opt.no_wrap_nav;
+		let detail = self.dash_state.main_view == DashViewMain::DashDetail;
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			if detail {
+				monitor.tail.scroll_down(1);
+				return;
+			}
+			do_bracketed_next_previous(&mut monitor.content, true, wrap);
+		} else if self.opt.debug_window {
+			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, true, wrap);
+		}
 	}
 
-	pub fn scale_timeline_down(&mut self) {
-		if self.dash_state.active_timeline == TIMELINES.len()-1 {
+	///! Select the first or last line of the logfile, or in the detail view
+	///! show the oldest lines of the log or follow the newest. In the summary,
+	///! focus its first or last node
+	pub fn scroll_to_end(&mut self, last: bool) {
+		if self.dash_state.main_view == DashViewMain::DashWarnings {
+			self.dash_state.recent_warnings.lock().unwrap().scroll_to_end(last);
+			return;
+		}
+		if self.dash_state.main_view == DashViewMain::DashSummary {
+			let rows = &self.dash_state.summary_logfiles;
+			if let Some(logfile) = if last { rows.last() } else { rows.first() }.cloned() {
+				self.focus_summary_row(logfile);
+			}
+			return;
+		}
+		let detail = self.dash_state.main_view == DashViewMain::DashDetail;
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			if detail {
+				if last {
+					monitor.tail.set_following(true);
+				} else {
+					monitor.tail.scroll_up(monitor.tail.len());
+				}
+				return;
+			}
+			select_end(&mut monitor.content, last);
+		} else if self.opt.debug_window {
+			select_end(&mut self.dash_state.debug_window_list, last);
+		}
+	}
+
+	///! Focus the node in the summary's row below or above the focused one, in
+	///! the order the rows were last drawn
+	fn step_summary_focus(&mut self, forward: bool) {
+		let rows = &self.dash_state.summary_logfiles;
+		let current = rows.iter().position(|logfile| logfile == &self.logfile_with_focus);
+		if let Some(index) = step_index(rows.len(), current, forward, !self.opt.no_wrap_nav, |_index| true) {
+			let logfile = rows[index].clone();
+			self.focus_summary_row(logfile);
+		}
+	}
+
+	fn focus_summary_row(&mut self, logfile: String) {
+		self.dash_state.dash_node_focus = logfile.clone();
+		self.set_logfile_with_focus(logfile);
+	}
+
+	pub fn reset_focused_node_counters(&mut self) {
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			monitor.metrics.reset_counters(&Utc::now());
+		}
+	}
+
+	///! Freeze the timelines of the node shown at the timescale shown, see TimelineSet::freeze()
+	pub fn freeze_focused_node_timelines(&mut self) {
+		let bucket_set_name = match self.dash_state.timelines.get(self.dash_state.active_timeline) {
+			Some(spec) => spec.name.clone(),
+			None => return,
+		};
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			for timeline in monitor.metrics.timelines_mut() {
+				timeline.freeze(&bucket_set_name);
+			}
+		}
+	}
+
+	pub fn thaw_focused_node_timelines(&mut self) {
+		if let Some(monitor) = self.get_monitor_with_focus() {
+			for timeline in monitor.metrics.timelines_mut() {
+				timeline.thaw();
+			}
+		}
+	}
+
+	///! Note every node's counters now, to show what each adds from here, see NodeMetrics::drop_marker()
+	pub fn drop_marker(&mut self, now: &DateTime<Utc>) {
+		for monitor in self.monitors.values_mut() {
+			monitor.metrics.drop_marker(now);
+		}
+		self.dash_state._debug_window("dropped a marker");
+	}
+
+	pub fn clear_marker(&mut self) {
+		for monitor in self.monitors.values_mut() {
+			monitor.metrics.marker = None;
+		}
+	}
+
+	///! Disable the node shown, or enable it again, see LogMonitor::set_enabled()
+	pub fn toggle_focused_node_enabled(&mut self) {
+		let message = match self.get_monitor_with_focus() {
+			Some(monitor) => {
+				monitor.set_enabled(!monitor.enabled);
+				format!("{} {}", if monitor.enabled { "enabled" } else { "disabled" }, monitor.name)
+			}
+			None => return,
+		};
+		self.dash_state._debug_window(&message);
+	}
+
+	///! Collapse the summary's group of the node shown, hiding its nodes
+	///! under the group's totals, or expand it again
+	pub fn toggle_focused_group(&mut self) {
+		let group = match self.get_monitor_with_focus() {
+			Some(monitor) => monitor.tag.clone().unwrap_or_else(|| String::from(DEFAULT_GROUP)),
+			None => return,
+		};
+		let collapsed = &mut self.dash_state.collapsed_groups;
+		match collapsed.iter().position(|tag| tag == &group) {
+			Some(index) => {
+				collapsed.remove(index);
+			}
+			None => collapsed.push(group),
+		}
+	}
+
+	///! While paused new logfile lines are left unread. On resume the
+	///! timelines catch up with the present before the lines are read so
+	///! that each line is counted in the bucket for its own time
+	pub fn toggle_pause(&mut self) -> Result<(), std::io::Error> {
+		self.dash_state.paused = !self.dash_state.paused;
+		if !self.dash_state.paused {
+			self.update_timelines(&Utc::now());
+			self.follow_logfiles()?;
+		}
+		Ok(())
+	}
+
+	///! Mark the node shown for the compare view, or unmark it if marked.
+	///! Marking a third node unmarks the one marked first
+	pub fn mark_for_compare(&mut self) {
+		let logfile = match self.get_logfile_with_focus() {
+			Some(logfile) if self.monitors.contains_key(&logfile) => logfile,
+			_ => return,
+		};
+		let marked = &mut self.dash_state.compare_nodes;
+		if let Some(index) = marked.iter().position(|node| node == &logfile) {
+			marked.remove(index);
+		} else {
+			marked.push(logfile);
+			if marked.len() > COMPARE_NODES {
+				marked.remove(0);
+			}
+		}
+	}
+
+	///! Show the marked nodes side by side. With fewer than two marked this
+	///! falls back to the node view of the one marked, if any
+	pub fn toggle_compare_view(&mut self) {
+		if self.dash_state.main_view == DashViewMain::DashCompare {
+			set_main_view(DashViewMain::DashNode, self);
+			return;
+		}
+		let monitors = &self.monitors;
+		self.dash_state.compare_nodes.retain(|logfile| monitors.contains_key(logfile));
+		if self.dash_state.compare_nodes.len() == COMPARE_NODES {
+			set_main_view(DashViewMain::DashCompare, self);
 			return;
 		}
-		self.dash_state.active_timeline += 1;
+		self.dash_state._debug_window("mark two nodes with 'a' to compare them");
+		if let Some(logfile) = self.dash_state.compare_nodes.first().cloned() {
+			self.dash_state.dash_node_focus = logfile;
+		}
+		if self.dash_state.main_view == DashViewMain::DashNode {
+			self.set_logfile_with_focus(self.dash_state.dash_node_focus.clone());
+		} else {
+			set_main_view(DashViewMain::DashNode, self);
+		}
+	}
+
+	pub fn cycle_sort_key(&mut self) {
+		self.dash_state.sort_key = self.dash_state.sort_key.next();
+	}
+
+	pub fn toggle_sort_direction(&mut self) {
+		self.dash_state.sort_ascending = !self.dash_state.sort_ascending;
+	}
+
+	pub fn scale_timeline_up(&mut self) {
+		self.zoom_timeline(true);
+	}
+
+	pub fn scale_timeline_down(&mut self) {
+		self.zoom_timeline(false);
+	}
+
+	///! Show the timeline with the next shorter (zoom in) or longer buckets
+	fn zoom_timeline(&mut self, zoom_in: bool) {
+		let active = zoom_timeline(self.dash_state.active_timeline, self.dash_state.timelines.len(), zoom_in);
+		if active != self.dash_state.active_timeline {
+			self.dash_state.active_timeline = active;
+			self.dash_state.timeline_cursor = 0;
+		}
+	}
+
+	pub fn toggle_timeline_scale(&mut self) {
+		self.dash_state.timeline_scale = match self.dash_state.timeline_scale {
+			Scale::Linear => Scale::Log10,
+			Scale::Log10 => Scale::Linear,
+		};
+	}
+
+	///! When the timeline has focus the arrow keys move its cursor
+	pub fn toggle_timeline_focus(&mut self) {
+		self.dash_state.timeline_has_focus = !self.dash_state.timeline_has_focus;
+		self.dash_state.timeline_cursor = 0;
+	}
+
+	///! Move the timeline cursor to older (positive) or newer (negative) buckets
+	pub fn move_timeline_cursor(&mut self, buckets: isize) {
+		let max_cursor = match self.dash_state.timelines.get(self.dash_state.active_timeline) {
+			Some(spec) => spec.steps.saturating_sub(1),
+			None => 0,
+		};
+		let cursor = self.dash_state.timeline_cursor as isize + buckets;
+		self.dash_state.timeline_cursor = cursor.clamp(0, max_cursor as isize) as usize;
+	}
+}
+
+///! Index of the next (forward) or previous of len items for which include
+///! is true, starting from current. Past either end it wraps around if wrap
+///! is set, and otherwise stays at the last included item in that direction.
+///! None if no item is included
+pub fn step_index(len: usize, current: Option<usize>, forward: bool, wrap: bool, include: impl Fn(usize) -> bool) -> Option<usize> {
+	let step = |i: usize, forward: bool, wrap: bool| -> Option<usize> {
+		match (forward, wrap) {
+			(true, _) if i + 1 < len => Some(i + 1),
+			(true, true) => Some(0),
+			(false, _) if i > 0 => Some(i - 1),
+			(false, true) => Some(len - 1),
+			_ => None,
+		}
+	};
+	let current = match current {
+		Some(current) if current < len => current,
+		_ => {
+			// Nothing selected, so start from the first (or last) included item
+			return if forward { (0..len).find(|i| include(*i)) } else { (0..len).rev().find(|i| include(*i)) };
+		}
+	};
+
+	let mut i = current;
+	for _ in 0..len {
+		i = match step(i, forward, wrap) {
+			Some(i) => i,
+			None => break,
+		};
+		if include(i) {
+			return Some(i);
+		}
+	}
+	if include(current) {
+		return Some(current);
+	}
+	// Hidden and at the end, so take the nearest included item behind it
+	let mut i = current;
+	while let Some(previous) = step(i, !forward, false) {
+		if include(previous) {
+			return Some(previous);
+		}
+		i = previous;
 	}
+	None
 }
 
-/// Move selection forward or back without wrapping at start or end
-fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
+/// Move selection forward or back, wrapping at start or end if wrap is set
+fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool, wrap: bool) {
 	if next {
 		if let Some(selected) = list.state.selected() {
-			if selected != list.items.len() - 1 {
+			if wrap || selected != list.items.len() - 1 {
 				list.next();
 			}
 		} else {
@@ -348,7 +1587,7 @@ fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
 		}
 	} else {
 		if let Some(selected) = list.state.selected() {
-			if selected != 0 {
+			if wrap || selected != 0 {
 				list.previous();
 			}
 		} else {
@@ -357,32 +1596,105 @@ fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
 	}
 }
 
+fn select_end(list: &mut StatefulList<String>, last: bool) {
+	if !list.items.is_empty() {
+		list.state.select(Some(if last { list.items.len() - 1 } else { 0 }));
+	}
+}
+
 fn exit_with_usage(reason: &str) -> Result<App, std::io::Error> {
 	println!(
 		"Try '{} --help' for more information.",
 		Opt::clap().get_name()
 	);
-	return Err(Error::new(ErrorKind::Other, reason));
+	Err(Error::other(reason))
 }
 
 use fs2::{statvfs, FsStats};
 
+///! Where to start reading each logfile, see --from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadFrom {
+	Start, // Replaying its history, each line in the bucket for its own time
+	End,   // Only lines added from now on
+}
+
+impl std::str::FromStr for ReadFrom {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<ReadFrom, String> {
+		match s {
+			"start" => Ok(ReadFrom::Start),
+			"end" => Ok(ReadFrom::End),
+			_ => Err(format!("invalid position '{}', expected start or end", s)),
+		}
+	}
+}
+
+///! The offset to start reading a logfile of logfile_len bytes at: its end,
+///! or else where saved state left off, if any, or its start
+pub fn start_offset(from: ReadFrom, logfile_len: u64, saved_offset: Option<u64>) -> u64 {
+	match from {
+		ReadFrom::End => logfile_len,
+		ReadFrom::Start => saved_offset.filter(|offset| *offset <= logfile_len).unwrap_or(0),
+	}
+}
+
 pub struct LogMonitor {
 	pub index: usize,
 	pub content: StatefulList<String>,
 	max_content: usize, // Limit number of lines in content
+	max_line_len: usize, // Characters, see truncate_line()
 	pub has_focus: bool,
+	pub enabled: bool, // Lines are ignored while disabled, see set_enabled()
 	pub logfile: String,
+	pub name: String, // Shown instead of the logfile, see --names
+	pub tag: Option<String>, // Groups the node in the summary, see --names
+	pub logfile_offset: u64, // Bytes of the logfile read so far
+	logfile_stamp: Option<LogfileStamp>, // As last read, to detect rotation
+	pub input_ended: bool, // Set when stdin has closed, or every line has been replayed
+	pub is_replay: bool, // Fed by App::replay rather than read, see --replay
+	pub waiting_for_file: bool, // Set until the logfile has been created
+	pub read_error: Option<String>, // Why the logfile can't be read, until it can
 	pub chunk_store_fsstats: Option<FsStats>,
 	pub chunk_store_pathbuf: PathBuf,
 	pub metrics: NodeMetrics,
 	pub metrics_status: StatefulList<String>,
+	pub tail: LineTail, // Every line read, up to --tail-lines
 	pub is_debug_dashboard_log: bool,
+	pub recent_warnings: Option<SharedWarnings>, // Shared by all nodes, for the warnings view
+	pub spotlight: Option<SharedSpotlight>, // Shared by all nodes, see --spotlight
+	pub diagnostics: Option<SharedDiagnostics>, // Shared by all nodes, for the diagnostics view
+	pub metrics_endpoint: Option<MetricsEndpoint>, // From --node-metrics-url
 }
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 static NEXT_MONITOR: AtomicUsize = AtomicUsize::new(0);
 
+///! A line read from a logfile or stdin, without its line ending. Bytes which
+///! aren't valid UTF-8 become the replacement character, so the rest of the
+///! line is still shown and parsed
+pub fn decode_line(bytes: &[u8]) -> String {
+	let line = String::from_utf8_lossy(bytes);
+	line.trim_end_matches(&['\r', '\n'][..]).to_string()
+}
+
+///! Cut a line longer than max_len characters (0 for no limit) to that many,
+///! the last being '…', so that a huge line can't swamp the dashboard. The
+///! start of the line is kept so that it can still be parsed
+pub fn truncate_line(line: &str, max_len: usize) -> Cow<'_, str> {
+	if max_len == 0 {
+		return Cow::Borrowed(line);
+	}
+	match line.char_indices().nth(max_len) {
+		None => Cow::Borrowed(line),
+		Some(_) => {
+			let end = line.char_indices().nth(max_len - 1).map_or(0, |(index, _)| index);
+			Cow::Owned(format!("{}…", &line[..end]))
+		}
+	}
+}
+
 impl LogMonitor {
 	pub fn new(opt: &Opt, f: String, max_lines: usize) -> LogMonitor {
 		let index = NEXT_MONITOR.fetch_add(1, Ordering::Relaxed);
@@ -399,63 +1711,247 @@ impl LogMonitor {
 			chunk_store_pathbuf.push("chunkdb")
 		}
 
+		let waiting_for_file = f != STDIN_LOGFILE && !Path::new(&f).exists();
+
 		LogMonitor {
 			index,
+			name: default_node_name(&f),
+			tag: None,
 			logfile: f,
+			logfile_offset: 0,
+			logfile_stamp: None,
+			input_ended: false,
+			is_replay: false,
+			waiting_for_file,
+			read_error: None,
 			max_content: max_lines,
+			max_line_len: opt.max_line_len,
 			chunk_store_fsstats: None,
 			chunk_store_pathbuf,
-			metrics: NodeMetrics::new(&opt),
+			metrics: NodeMetrics::new(opt),
 			content: StatefulList::with_items(vec![]),
 			has_focus: false,
+			enabled: true,
 			metrics_status: StatefulList::with_items(vec![]),
+			tail: LineTail::new(opt.tail_lines),
 			is_debug_dashboard_log,
+			recent_warnings: None,
+			spotlight: None,
+			diagnostics: None,
+			metrics_endpoint: None,
+		}
+	}
+
+	///! Fetch the metrics the node serves from its --node-metrics-url, if it
+	///! has one, rather than parse them from its logfile
+	pub fn watch_metrics_endpoint(&mut self, opt: &Opt) {
+		if let Some((_logfile, url)) = opt.node_metrics_urls.iter().find(|(logfile, _url)| *logfile == self.logfile) {
+			self.metrics_endpoint = Some(MetricsEndpoint::spawn(url, std::time::Duration::from_millis(opt.tick_rate)));
+			self.metrics.metrics_from_endpoint = true;
+		}
+	}
+
+	///! Disable the node to ignore what it logs, leaving it listed but out of
+	///! the fleet totals and alerts, or enable it again. Lines logged while
+	///! disabled are skipped rather than read once enabled
+	pub fn set_enabled(&mut self, enabled: bool) {
+		if enabled && !self.enabled && !self.waiting_for_file && self.logfile != STDIN_LOGFILE && !self.is_replay {
+			self.skip_existing_lines();
 		}
+		self.enabled = enabled;
 	}
 
 	pub fn update_chunk_store_fsstats(&mut self) {
-		self.chunk_store_fsstats = match statvfs(&self.chunk_store_pathbuf) {
-			Ok(fsstats) => Some(fsstats),
-			Err(_) => None,
-		};
+		self.chunk_store_fsstats = statvfs(&self.chunk_store_pathbuf).ok();
 	}
 
 	pub fn load_logfile(&mut self, dash_state: &mut DashState) -> std::io::Result<()> {
-		use std::io::{BufRead, BufReader};
+		self.load_logfile_from(dash_state, 0)
+	}
 
-		let f = File::open(self.logfile.to_string());
-		let f = match f {
-			Ok(file) => file,
-			Err(_e) => return Ok(()), // It's ok for a logfile not to exist yet
+	///! Load the logfile starting at a byte offset, such as where saved state left off
+	pub fn load_logfile_from(&mut self, dash_state: &mut DashState, offset: u64) -> std::io::Result<()> {
+		self.update_chunk_store_fsstats();
+		self.logfile_offset = line_boundary(&self.logfile, offset);
+		self.logfile_stamp = LogfileStamp::of(&self.logfile);
+		self.read_new_lines(dash_state)?;
+
+		if !self.content.items.is_empty() {
+			self.content
+				.state
+				.select(Some(self.content.items.len() - 1));
+		}
+
+		Ok(())
+	}
+
+	///! For --replay-gz, read the node's rotated logfiles oldest first, as if
+	///! they came before its logfile. Those which can't be read are skipped,
+	///! or the rest of one found to be corrupt part way, returning a warning for each
+	pub fn replay_rotated_logfiles(&mut self) -> Vec<String> {
+		let mut warnings = Vec::new();
+		for path in rotated_logfiles(&self.logfile) {
+			let now = Utc::now();
+			let replayed = read_gz_lines(&path).and_then(|mut lines| lines.try_for_each(|line| self.process_line(&line?, &now)));
+			if let Err(e) = replayed {
+				warnings.push(format!("skipping {}: {}", path.display(), e));
+			}
+		}
+		warnings
+	}
+
+	///! For --from end, only lines added from now on will be read, along with
+	///! any last line still being written
+	pub fn skip_existing_lines(&mut self) {
+		let logfile_len = std::fs::metadata(&self.logfile).map(|m| m.len()).unwrap_or(0);
+		self.logfile_offset = line_boundary(&self.logfile, logfile_len);
+		self.logfile_stamp = LogfileStamp::of(&self.logfile);
+	}
+
+	///! Read lines added since the last read. If the logfile has shrunk
+	///! (truncated) or been replaced (rotated) it is read again from the start,
+	///! keeping the metrics gathered so far. Nothing is read while paused or disabled
+	pub fn follow_logfile(&mut self, dash_state: &mut DashState) -> std::io::Result<()> {
+		if dash_state.paused || !self.enabled {
+			return Ok(());
+		}
+		let stamp = match LogfileStamp::of(&self.logfile) {
+			Some(stamp) => stamp,
+			None => return Ok(()), // Not created yet, or between removal and re-creation by rotation
 		};
+		let rotated = self.logfile_stamp.is_some_and(|last_read| last_read.is_replaced_by(&stamp));
+		if self.waiting_for_file {
+			dash_state._debug_window(format!("logfile created: {}", self.logfile).as_str());
+			log::info!("logfile created: {}", self.logfile);
+			self.waiting_for_file = false;
+			self.logfile_offset = 0;
+		} else if stamp.len < self.logfile_offset || rotated {
+			let reason = if rotated { "rotated" } else { "truncated" };
+			dash_state._debug_window(format!("logfile {}: {}", reason, self.logfile).as_str());
+			log::info!("logfile {}, reading it again from the start: {}", reason, self.logfile);
+			self.logfile_offset = 0;
+		}
+		self.logfile_stamp = Some(stamp);
+		self.read_new_lines(dash_state)
+	}
 
-		self.update_chunk_store_fsstats();
-		let f = BufReader::new(f);
+	///! The lines received from stdin, to be processed by App::process_line(),
+	///! noting when it has closed. Nothing is read while paused
+	pub fn read_stdin(&mut self, dash_state: &mut DashState, stdin_reader: &mut StdinReader) -> Vec<String> {
+		if dash_state.paused || self.input_ended {
+			return Vec::new();
+		}
+		let lines = stdin_reader.read_lines();
+		if stdin_reader.is_ended() {
+			dash_state._debug_window(format!("end of input: {}", self.logfile).as_str());
+			self.input_ended = true;
+		}
+		lines
+	}
+
+	///! The lines of a --replay which have come due, to be processed by
+	///! App::process_line(), noting once all have. Its clock stands still while paused
+	pub fn read_replay(&mut self, dash_state: &mut DashState, replay: &mut Replay) -> Vec<String> {
+		let lines = replay.advance(std::time::Instant::now(), dash_state.paused);
+		if replay.is_ended() && !self.input_ended {
+			dash_state._debug_window(format!("end of replay: {}", self.logfile).as_str());
+			self.input_ended = true;
+		}
+		lines
+	}
 
-		for line in f.lines() {
-			let line = line.expect("Unable to read line");
-			self.append_to_content(&line)?;
+	// Append complete lines from logfile_offset. A partly written last line
+	// is left to be read once it has been completed. A logfile which can't
+	// be read, such as for lack of permission, is noted in read_error and
+	// tried again each time it is followed
+	fn read_new_lines(&mut self, dash_state: &mut DashState) -> std::io::Result<()> {
+		use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom};
+
+		let f = File::open(&self.logfile);
+		let mut f = match f {
+			Ok(file) => file,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()), // It's ok for a logfile not to exist yet
+			Err(e) => {
+				self.note_read_error(dash_state, &e);
+				return Ok(());
+			}
+		};
+		if let Err(e) = f.seek(SeekFrom::Start(self.logfile_offset)) {
+			self.note_read_error(dash_state, &e);
+			return Ok(());
+		}
+		let mut f = BufReader::new(f);
+
+		let now = Utc::now();
+		let mut bytes = Vec::<u8>::new();
+		loop {
+			bytes.clear();
+			let len = match f.read_until(b'\n', &mut bytes) {
+				Ok(len) => len,
+				Err(e) => {
+					self.note_read_error(dash_state, &e);
+					return Ok(());
+				}
+			};
+			if len == 0 || bytes.last() != Some(&b'\n') {
+				break;
+			}
+			self.logfile_offset += len as u64;
+			let line = decode_line(&bytes);
+			self.process_line(&line, &now)?;
 			if self.is_debug_dashboard_log {
 				dash_state._debug_window(&line);
 			}
 		}
-
-		if self.content.items.len() > 0 {
-			self.content
-				.state
-				.select(Some(self.content.items.len() - 1));
+		if self.read_error.take().is_some() {
+			dash_state._debug_window(format!("logfile readable again: {}", self.logfile).as_str());
 		}
-
 		Ok(())
 	}
 
-	pub fn append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
-		if self.line_filter(&text) {
+	fn note_read_error(&mut self, dash_state: &mut DashState, e: &std::io::Error) {
+		let reason = match e.kind() {
+			std::io::ErrorKind::PermissionDenied => String::from("permission denied"),
+			_ => e.to_string(),
+		};
+		if self.read_error.as_ref() != Some(&reason) {
+			dash_state._debug_window(format!("unable to read logfile {}: {}", self.logfile, reason).as_str());
+			self.read_error = Some(reason);
+		}
+	}
+
+	///! Put a line through the whole pipeline, from parsing to the node's
+	///! metrics, timelines, warnings and diagnostics, wherever it was read
+	///! from. A line without a time is taken as logged at now
+	pub fn process_line(&mut self, text: &str, now: &DateTime<Utc>) -> Result<(), std::io::Error> {
+		let text = &*truncate_line(text, self.max_line_len);
+		self.tail.push(text);
+		if self.line_filter(text) {
 			self._append_to_content(text)?; // Show in TUI
 			if self.is_debug_dashboard_log {
 				return Ok(());
 			}
-			self.metrics.gather_metrics(&text)?;
+			let level = self.metrics.gather_metrics(text, now)?;
+			let time = self.metrics.entry_metadata.as_ref().map_or(*now, |metadata| metadata.time);
+			if let Some(spotlight) = &self.spotlight {
+				spotlight.lock().unwrap().check(&self.name, time, text);
+			}
+			if let (LogLevel::Warn | LogLevel::Error, Some(recent_warnings)) = (level, &self.recent_warnings) {
+				recent_warnings.lock().unwrap().push(WarningEntry {
+					node: self.name.clone(),
+					time,
+					level,
+					line: text.to_string(),
+				});
+			}
+			if let (Some(problem), Some(diagnostics)) = (self.metrics.last_anomaly.take(), &self.diagnostics) {
+				diagnostics.lock().unwrap().push(Diagnostic {
+					node: self.name.clone(),
+					time: *now,
+					problem,
+					line: text.to_string(),
+				});
+			}
 		}
 		Ok(())
 	}
@@ -478,176 +1974,249 @@ impl LogMonitor {
 	}
 }
 
-use regex::Regex;
-lazy_static::lazy_static! {
-	static ref LOG_LINE_PATTERN: Regex =
-		Regex::new(r"(?P<module>\[.*\])* (?P<category>[A-Z]{4,6}) (?P<time_string>[^ ]{27}) (?P<source>\[.*\])(?P<message>.*)").expect("The regex failed to compile. This is a bug.");
+// The end of the last complete line of a logfile at or before offset. This
+// is offset itself unless that falls within a line, as when starting while
+// a line is being written, so that the line is read whole once complete
+// rather than its end being read as a line of its own
+fn line_boundary(logfile: &str, offset: u64) -> u64 {
+	use std::io::{Read, Seek, SeekFrom};
+
+	let mut f = match File::open(logfile) {
+		Ok(file) => file,
+		Err(_e) => return offset,
+	};
+	let mut chunk = [0u8; 4096];
+	let mut end = offset;
+	while end > 0 {
+		let start = end.saturating_sub(chunk.len() as u64);
+		let bytes = &mut chunk[..(end - start) as usize];
+		if f.seek(SeekFrom::Start(start)).is_err() || f.read_exact(bytes).is_err() {
+			return offset; // Beyond the end, which follow_logfile() takes as truncation
+		}
+		if let Some(newline) = bytes.iter().rposition(|byte| *byte == b'\n') {
+			return start + newline as u64 + 1;
+		}
+		end = start;
+	}
+	0
 }
 
-#[derive(PartialEq)]
-pub enum NodeAgebracket {
-	Unknown,
-	Joining,
-	Adult,
-	Elder,
+///! Convert an amount such as '1,250' or '0.000025' of a unit worth
+///! attos_per_unit attos into attos, saturating at u64::MAX. Fractions
+///! of an atto are dropped
+pub fn parse_token_amount(amount: &str, attos_per_unit: u128) -> Option<u64> {
+	let amount: String = amount.chars().filter(|c| *c != ',' && *c != '_').collect();
+	let (whole, fraction) = match amount.split_once('.') {
+		Some((whole, fraction)) => (whole, fraction),
+		None => (amount.as_str(), ""),
+	};
+	let whole = whole.parse::<u128>().ok()?;
+	let mut attos = whole.saturating_mul(attos_per_unit);
+	if !fraction.is_empty() {
+		if !fraction.chars().all(|c| c.is_ascii_digit()) {
+			return None;
+		}
+		let mut scale = attos_per_unit;
+		for digit in fraction.chars() {
+			scale /= 10;
+			attos = attos.saturating_add(scale * digit.to_digit(10)? as u128);
+		}
+	}
+	Some(attos.min(u64::MAX as u128) as u64)
 }
 
-///! Maintains one or more 'marching bucket' histories for
-///! a given metric, each with its own duration and granularity.
-///!
-///! A BucketSet is used to hold the history of values with
-///! a given bucket_duration and maximum number of buckets.
-///!
-///! A BucketSet begins with a single bucket of fixed
-///! duration holding the initial metric value. New buckets
-///! are added as time progresses until the number of buckets
-///! covers the total duration of the BucketSet. At this
-///! point the oldest bucket is removed when a new bucket is
-///! added, so that the total duration remains constant and
-///! the specified maximum number of buckets is never
-///! exceeded.
-///!
-///! By adding more than one BucketSet, a given metric can be
-///! recorded for different durations and with different
-///! granularities. E.g. 60 * 1s buckets covers a minute
-///! and 60 * 1m buckets covers an hour, and so on.
-pub struct TimelineSet {
-	name: String,
-	bucket_sets: HashMap<&'static str, BucketSet>,
+///! Which way records were replicated, when the line says
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplicationDirection {
+	Inbound,
+	Outbound,
 }
 
-pub struct BucketSet {
-	pub bucket_time: Option<DateTime<Utc>>,
-	pub total_duration: Duration,
-	pub bucket_duration: Duration,
-	pub max_buckets: usize,
-	pub buckets: Vec<u64>,
+///! An event of network churn as seen by a node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChurnEvent {
+	Replication(Option<ReplicationDirection>),
+	Relocation,
 }
 
-impl TimelineSet {
-	pub fn new(name: String) -> TimelineSet {
-		TimelineSet {
-			name,
-			bucket_sets: HashMap::<&'static str, BucketSet>::new(),
-		}
+///! Classify a line about record replication, e.g. 'Received replication list
+///! from peer ...' (inbound) or 'Sending replication keys to 5 peers' (outbound),
+///! or relocation, e.g. 'Node relocated to section 10'. The first word of
+///! direction in the line decides it, else 'from'/'to peer', and a replication
+///! with neither has no direction
+pub fn classify_churn(line: &str) -> Option<ChurnEvent> {
+	if RELOCATION_PATTERN.is_match(line) {
+		return Some(ChurnEvent::Relocation);
 	}
-
-	pub fn get_name(&self) -> &String {
-		&self.name
+	if !REPLICATION_PATTERN.is_match(line) {
+		return None;
 	}
+	let captures = REPLICATION_DIRECTION_PATTERN.captures(line).or_else(|| REPLICATION_PEER_PATTERN.captures(line));
+	let direction = captures.map(|captures| {
+		if captures.name("inbound").is_some() {
+			ReplicationDirection::Inbound
+		} else {
+			ReplicationDirection::Outbound
+		}
+	});
+	Some(ChurnEvent::Replication(direction))
+}
 
-	pub fn add_bucket_set(&mut self, name: &'static str, duration: Duration, max_buckets: usize) {
-		self.bucket_sets
-			.insert(name, BucketSet::new(duration, max_buckets));
+///! The address a node logs it is listening on, from a multiaddr such as
+///! '/ip4/10.0.0.5/udp/12000/quic-v1' or a socket address such as
+///! '10.0.0.5:12000' or '[2001:db8::5]:12000'
+pub fn parse_listen_address(line: &str) -> Option<SocketAddr> {
+	let captures = LISTEN_ADDRESS_PATTERN.captures(line)?;
+	if let Some(socket) = captures.name("socket") {
+		return socket.as_str().parse().ok();
 	}
+	let ip = captures["multiaddr_ip"].parse::<IpAddr>().ok()?;
+	let port = captures["multiaddr_port"].parse::<u16>().ok()?;
+	Some(SocketAddr::new(ip, port))
+}
 
-	pub fn get_bucket_set(&mut self, bucket_set_name: &str) -> Option<&BucketSet> {
-		self.bucket_sets.get(bucket_set_name)
-	}
+///! A loopback or unspecified address, which other nodes can't reach
+fn is_local_address(address: &SocketAddr) -> bool {
+	address.ip().is_loopback() || address.ip().is_unspecified()
+}
 
-	///! Update all bucket_sets with new current time
-	///!
-	///! Call significantly more frequently than the smallest BucketSet duration
-	fn update_current_time(&mut self, new_time: &DateTime<Utc>) {
-		// debug_log!("update_current_time()");
-		for (_name, bs) in self.bucket_sets.iter_mut() {
-			if let Some(mut bucket_time) = bs.bucket_time {
-				let mut end_time = bucket_time + bs.bucket_duration;
-				// debug_log!(format!("end_time       : {}", end_time).as_str());
+use regex::Regex;
+lazy_static::lazy_static! {
+	static ref RUNNING_VERSION_PATTERN: Regex =
+		Regex::new(r"Running (?P<binary>\S*node\S*) v?(?P<version>\d+\.\d+\.\d+\S*)").expect("The regex failed to compile. This is a bug.");
+	static ref PEER_ID_PATTERN: Regex =
+		Regex::new(r"PeerId is (?P<peer_id>[[:alnum:]]+)").expect("The regex failed to compile. This is a bug.");
+	static ref LISTEN_ADDRESS_PATTERN: Regex =
+		Regex::new(r"(?i)listen.*?(?:/ip[46]/(?P<multiaddr_ip>[0-9a-f.:]+)/(?:tcp|udp)/(?P<multiaddr_port>\d+)|(?P<socket>\d{1,3}(?:\.\d{1,3}){3}:\d+|\[[0-9a-f:.]+\]:\d+))").expect("The regex failed to compile. This is a bug.");
+	static ref PEER_COUNT_PATTERN: Regex =
+		Regex::new(r"(?:now we have #(?P<connected>\d+) connected peers|[Rr]outing table size:? (?P<table_size>\d+))").expect("The regex failed to compile. This is a bug.");
+	static ref NETWORK_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:(?P<disconnected>disconnected from (?:the )?network|lost (?:its )?connection to (?:the )?network)|(?:re)?connected to (?:the )?network)\b").expect("The regex failed to compile. This is a bug.");
+	static ref TRANSFER_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?P<direction>received|inbound|sent|outbound)\b\D*?(?P<bytes>\d+) ?bytes\b").expect("The regex failed to compile. This is a bug.");
+	static ref RECORDS_STORED_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:records? stored|stored records?|records? count)\s*(?:[:=]|is)\s*(?P<count>\d+)\b").expect("The regex failed to compile. This is a bug.");
+	static ref RECORDS_CHANGED_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?P<change>removed|pruned|replicated)\s+(?:(?P<count>\d+)\s+)?records?\b").expect("The regex failed to compile. This is a bug.");
+	static ref REPLICATION_PATTERN: Regex =
+		Regex::new(r"(?i)\breplicat(?:e|es|ed|ing|ion)\b").expect("The regex failed to compile. This is a bug.");
+	static ref REPLICATION_DIRECTION_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:(?P<inbound>received|receiving|incoming|inbound|fetched|fetching)|sent|sending|outgoing|outbound)\b").expect("The regex failed to compile. This is a bug.");
+	static ref REPLICATION_PEER_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:(?P<inbound>from)|to) (?:\d+ )?peers?\b").expect("The regex failed to compile. This is a bug.");
+	static ref RELOCATION_PATTERN: Regex =
+		Regex::new(r"(?i)\brelocat(?:e|es|ed|ing|ion)\b").expect("The regex failed to compile. This is a bug.");
+	static ref STORE_COST_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:store[ _-]?cost|cost is now)\b\D*?(?P<wrapper>AttoTokens|NanoTokens)?\(?(?P<amount>\d[\d,_]*(?:\.\d+)?)\)?\s*(?P<unit>attos?|nanos?|ANT|tokens?)?\b").expect("The regex failed to compile. This is a bug.");
+}
 
-				while end_time.lt(&new_time) {
-					// debug_log!("Start new bucket");
-					// Start new bucket
-					bs.bucket_time = Some(end_time);
-					bucket_time = end_time;
-					end_time = bucket_time + bs.bucket_duration;
+///! Level of a logfile line, Other when it has no recognisable level
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum LogLevel {
+	Trace,
+	Debug,
+	Info,
+	Warn,
+	Error,
+	Other,
+}
 
-					bs.buckets.push(0);
-					if bs.buckets.len() > bs.max_buckets {
-						bs.buckets.remove(0);
-					}
-				}
-			} else {
-				bs.bucket_time = Some(*new_time);
-			}
-		}
-	}
-
-	fn increment_value(&mut self, time: &DateTime<Utc>) {
-		// debug_log!("increment_value()");
-		for (_name, bs) in self.bucket_sets.iter_mut() {
-			// debug_log!(format!("name       : {}", _name).as_str());
-			let mut index = Some(bs.buckets.len() - 1);
-			// debug_log!(format!("time       : {}", time).as_str());
-			if let Some(bucket_time) = bs.bucket_time {
-			// debug_log!(format!("bucket_time: {}", bucket_time).as_str());
-				if time.lt(&bucket_time) {
-					// Use the closest bucket to this time
-					// debug_log!("increment (closest bucket)");
-					let time_difference = (bucket_time - *time).num_nanoseconds();
-					let bucket_duration = bs.bucket_duration.num_nanoseconds();
-					if time_difference.and(bucket_duration).is_some() {
-						let buckets_behind = time_difference.unwrap() / bucket_duration.unwrap();
-						if buckets_behind as usize >= bs.buckets.len() {
-							// debug_log!(format!("increment DISCARDED buckets_behind: {}", buckets_behind).as_str());
-							index = None;
-						} else {
-							// debug_log!(format!("increment INCLUDED buckets_behind: {}", buckets_behind).as_str());
-							index = Some(bs.buckets.len() - 1 - buckets_behind as usize);
-						}
-					}
-				}
-			}
-			if let Some(index) = index {
-				// debug_log!(format!("increment index: {}", index).as_str());
-				bs.buckets[index] += 1;
+impl LogLevel {
+	///! Classify a line by the first level token near its start, which
+	///! tolerates both ' INFO 2022-...' and '[2022-... INFO module]' prefixes
+	pub fn classify(line: &str) -> LogLevel {
+		for token in line
+			.split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+			.filter(|token| !token.is_empty())
+			.take(3)
+		{
+			match token {
+				"TRACE" => return LogLevel::Trace,
+				"DEBUG" => return LogLevel::Debug,
+				"INFO" => return LogLevel::Info,
+				"WARN" => return LogLevel::Warn,
+				"ERROR" => return LogLevel::Error,
+				_ => {}
 			}
 		}
+		LogLevel::Other
 	}
 }
 
-impl BucketSet {
-	pub fn new(bucket_duration: Duration, max_buckets: usize) -> BucketSet {
-		BucketSet {
-			bucket_duration,
-			max_buckets,
-			total_duration: bucket_duration * max_buckets as i32,
-
-			bucket_time: None,
-			buckets: vec![0; max_buckets],
-		}
-	}
-
-	pub fn set_bucket_value(&mut self, value: u64) {
-		let index = self.buckets.len() - 1;
-		self.buckets[index] = value;
-	}
+#[derive(PartialEq)]
+pub enum NodeAgebracket {
+	Unknown,
+	Joining,
+	Adult,
+	Elder,
+}
 
-	pub fn increment_value(&mut self) {
-		let index = self.buckets.len() - 1;
-		self.buckets[index] += 1;
-	}
+///! Counters of a node, whether over its lifetime or current run, see
+///! NodeMetrics::lifetime() and NodeMetrics::current_run()
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunCounters {
+	pub gets: u64,
+	pub puts: u64,
+	pub errors: u64,
+	pub warnings: u64,
+	pub rewards: u128, // In attos
+	pub bytes_in: u64,
+	pub bytes_out: u64,
+}
 
-	pub fn buckets(&self) -> &Vec<u64> {
-		&self.buckets
+impl RunCounters {
+	///! The counts added since earlier counters of the same node
+	pub fn since(&self, earlier: &RunCounters) -> RunCounters {
+		RunCounters {
+			gets: self.gets.saturating_sub(earlier.gets),
+			puts: self.puts.saturating_sub(earlier.puts),
+			errors: self.errors.saturating_sub(earlier.errors),
+			warnings: self.warnings.saturating_sub(earlier.warnings),
+			rewards: self.rewards.saturating_sub(earlier.rewards),
+			bytes_in: self.bytes_in.saturating_sub(earlier.bytes_in),
+			bytes_out: self.bytes_out.saturating_sub(earlier.bytes_out),
+		}
 	}
+}
 
-	pub fn buckets_mut(&mut self) -> &mut Vec<u64> {
-		&mut self.buckets
-	}
+///! The lifetime counters of a node when a marker was dropped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CounterMarker {
+	pub dropped: DateTime<Utc>,
+	pub counters: RunCounters,
 }
 
 pub struct NodeMetrics {
 	pub node_started: Option<DateTime<Utc>>,
 	pub running_message: Option<String>,
 	pub running_version: Option<String>,
-	pub category_count: HashMap<String, usize>,
-	pub activity_history: Vec<ActivityEntry>,
-	pub log_history: Vec<LogEntry>,
+	pub restarts: u64, // Startup banners after the first
+	pub peer_id: Option<String>,
+	pub listen_address: Option<SocketAddr>, // The latest reachable one logged since the node started
+	pub category_count: HashMap<LogLevel, usize>,
+	pub activity_history: VecDeque<ActivityEntry>, // The last --history-max, oldest first
+	pub log_history: VecDeque<LogEntry>, // The last --history-max, oldest first
+	history_max: usize,
+	pub metrics_from_endpoint: bool, // Those it serves, see --node-metrics-url
+	pub log_profile: &'static LogProfile, // The patterns lines are parsed with, see --log-profile
+	pub rate_window: Duration, // Rates per minute are averaged over, see --rate-window
 
 	pub puts_timeline: TimelineSet,
 	pub gets_timeline: TimelineSet,
-	pub errors_timeline: TimelineSet, // TODO add code to collect and display
+	pub errors_timeline: TimelineSet,
+	pub warnings_timeline: TimelineSet,
+	pub peers_timeline: TimelineSet,
+	pub bytes_in_timeline: TimelineSet,
+	pub bytes_out_timeline: TimelineSet,
+	pub store_cost_timeline: TimelineSet,
+	pub storage_timeline: TimelineSet, // Percent of capacity used
+	pub used_space_timeline: TimelineSet, // Bytes, for projecting when storage will be full
+	pub records_timeline: TimelineSet,
+	pub lines_timeline: TimelineSet, // Every line read, parsed or not
+	pub replications_timeline: TimelineSet,
+	pub relocations_timeline: TimelineSet,
+	pub latency_timeline: TimelineSet, // Mean of the latency samples, in milliseconds
+	pub rewards_timeline: TimelineSet, // Attos received, saturating at u64::MAX (over 18 tokens) a bucket
 
 	pub entry_metadata: Option<LogMeta>,
 	pub agebracket: NodeAgebracket,
@@ -659,9 +2228,32 @@ pub struct NodeMetrics {
 	pub activity_gets: u64,
 	pub activity_puts: u64,
 	pub activity_errors: u64,
+	pub activity_warnings: u64,
+	pub rewards: u128, // In attos
+	pub store_cost: Option<u64>, // Latest quoted, in attos
+	pub records_stored: Option<u64>, // As last logged, adjusted for records removed or replicated since
+	pub replications: u64, // Of records, in either direction or none logged
+	pub replications_in: u64,
+	pub replications_out: u64,
+	pub relocations: u64,
+	pub latencies: LatencySamples, // In microseconds, see parse_latency()
+	pub peers: u64,
+	pub connected: bool, // To the network, until a disconnection is logged
+	pub disconnects: u64, // Episodes of being disconnected from the network
+	pub bytes_in: u64,
+	pub bytes_out: u64,
+	pub counters_reset: Option<DateTime<Utc>>,
+	run_start: RunCounters, // The lifetime counters at the latest startup banner
+	pub marker: Option<CounterMarker>, // Dropped with '@', see drop_marker()
+	pub last_activity: Option<DateTime<Utc>>, // Time of the most recent log entry
 
 	pub used_space: u64,
-	pub max_capacity: u64,
+	pub max_capacity: u64, // As logged, 0 until known
+	pub capacity_override: Option<u64>, // From --node-capacities
+	pub default_capacity: Option<u64>, // From --node-capacity
+
+	pub user_metrics: UserMetrics, // From --metrics-config
+	pub last_anomaly: Option<String>, // What was odd about the last line, see diagnose_line()
 
 	pub global_cpu: f32,
 	pub load_avg_1: f32,
@@ -682,15 +2274,48 @@ pub struct NodeMetrics {
 }
 
 impl NodeMetrics {
-	fn new(opt: &Opt) -> NodeMetrics {
+	pub fn new(opt: &Opt) -> NodeMetrics {
 		let mut puts_timeline = TimelineSet::new("PUTS".to_string());
 		let mut gets_timeline = TimelineSet::new("GETS".to_string());
 		let mut errors_timeline = TimelineSet::new("ERRORS".to_string());
-		for timeline in [&mut puts_timeline, &mut gets_timeline, &mut errors_timeline].iter_mut() {
-			for i in 0..TIMELINES.len() {
-				if let Some(spec) = TIMELINES.get(i) {
-					timeline.add_bucket_set(spec.0, spec.1, opt.timeline_steps);
-				}
+		let mut warnings_timeline = TimelineSet::new("WARNINGS".to_string());
+		let mut peers_timeline = TimelineSet::new("PEERS".to_string());
+		let mut bytes_in_timeline = TimelineSet::new("BYTES IN".to_string());
+		let mut bytes_out_timeline = TimelineSet::new("BYTES OUT".to_string());
+		let mut store_cost_timeline = TimelineSet::new("STORE COST".to_string());
+		let mut storage_timeline = TimelineSet::new("STORAGE %".to_string());
+		let mut used_space_timeline = TimelineSet::new("STORAGE USED".to_string());
+		let mut records_timeline = TimelineSet::new("RECORDS".to_string());
+		let mut lines_timeline = TimelineSet::new("LINES".to_string());
+		let mut replications_timeline = TimelineSet::new("REPLICATIONS".to_string());
+		let mut relocations_timeline = TimelineSet::new("RELOCATIONS".to_string());
+		let mut latency_timeline = TimelineSet::new("LATENCY ms".to_string());
+		let mut rewards_timeline = TimelineSet::new(REWARDS_TIMELINE.to_string());
+		for timeline in [
+			&mut puts_timeline,
+			&mut gets_timeline,
+			&mut errors_timeline,
+			&mut warnings_timeline,
+			&mut peers_timeline,
+			&mut bytes_in_timeline,
+			&mut bytes_out_timeline,
+			&mut store_cost_timeline,
+			&mut storage_timeline,
+			&mut used_space_timeline,
+			&mut records_timeline,
+			&mut lines_timeline,
+			&mut replications_timeline,
+			&mut relocations_timeline,
+			&mut latency_timeline,
+			&mut rewards_timeline,
+		]
+		.iter_mut()
+		{
+			for spec in opt.timeline_steps.timelines.iter() {
+				timeline.add_bucket_set(&spec.name, spec.duration, spec.steps);
+			}
+			if opt.align_buckets {
+				timeline.align_to_wall_clock();
 			}
 		}
 
@@ -699,22 +2324,60 @@ impl NodeMetrics {
 			node_started: None,
 			running_message: None,
 			running_version: None,
+			restarts: 0,
+			peer_id: None,
+			listen_address: None,
 
 			// Logfile entries
-			activity_history: Vec::<ActivityEntry>::new(),
-			log_history: Vec::<LogEntry>::new(),
+			activity_history: VecDeque::<ActivityEntry>::new(),
+			log_history: VecDeque::<LogEntry>::new(),
+			history_max: opt.history_max,
+			metrics_from_endpoint: false,
+			log_profile: opt.log_profile,
+			rate_window: opt.rate_window,
 			entry_metadata: None,
 
 			// Timelines / Sparklines
 			puts_timeline,
 			gets_timeline,
 			errors_timeline,
+			warnings_timeline,
+			peers_timeline,
+			bytes_in_timeline,
+			bytes_out_timeline,
+			store_cost_timeline,
+			storage_timeline,
+			used_space_timeline,
+			records_timeline,
+			lines_timeline,
+			replications_timeline,
+			relocations_timeline,
+			latency_timeline,
+			rewards_timeline,
 
 			// Counts
 			category_count: HashMap::new(),
 			activity_gets: 0,
 			activity_puts: 0,
 			activity_errors: 0,
+			activity_warnings: 0,
+			rewards: 0,
+			store_cost: None,
+			records_stored: None,
+			replications: 0,
+			replications_in: 0,
+			replications_out: 0,
+			relocations: 0,
+			latencies: LatencySamples::new(LATENCY_SAMPLES_MAX),
+			peers: 0,
+			connected: true,
+			disconnects: 0,
+			bytes_in: 0,
+			bytes_out: 0,
+			counters_reset: None,
+			run_start: RunCounters::default(),
+			marker: None,
+			last_activity: None,
 
 			// State (node)
 			agebracket: NodeAgebracket::Unknown,
@@ -729,6 +2392,11 @@ impl NodeMetrics {
 			// Disk use:
 			used_space: 0,
 			max_capacity: 0,
+			capacity_override: None,
+			default_capacity: opt.node_capacity,
+
+			user_metrics: UserMetrics::default(),
+			last_anomaly: None,
 
 
 			global_cpu: 0.0,
@@ -762,6 +2430,7 @@ impl NodeMetrics {
 		}
 	}
 
+	#[allow(dead_code)]
 	fn reset_metrics(&mut self) {
 		self.agebracket = NodeAgebracket::Unknown;
 		self.section_prefix = String::from("");
@@ -772,100 +2441,510 @@ impl NodeMetrics {
 		self.activity_gets = 0;
 		self.activity_puts = 0;
 		self.activity_errors = 0;
+		self.activity_warnings = 0;
+	}
+
+	///! Time since the most recent log entry, never negative even if the
+	///! node's clock is ahead of ours. None if nothing has been logged yet
+	pub fn time_since_activity(&self, now: &DateTime<Utc>) -> Option<Duration> {
+		self.last_activity
+			.map(|last| (*now - last).max(Duration::zero()))
+	}
+
+	///! Time since the node's latest startup banner, None until one is seen
+	pub fn uptime(&self, now: &DateTime<Utc>) -> Option<Duration> {
+		self.node_started.map(|started| (*now - started).max(Duration::zero()))
+	}
+
+	///! True if the node has logged before but not within stale_after
+	pub fn is_stale(&self, now: &DateTime<Utc>, stale_after: Duration) -> bool {
+		match self.time_since_activity(now) {
+			Some(elapsed) => elapsed > stale_after,
+			None => false,
+		}
+	}
+
+	///! Storage capacity, preferring a per-node override to the logged
+	///! capacity and that to the --node-capacity default. None if unknown
+	pub fn capacity(&self) -> Option<u64> {
+		let logged = if self.max_capacity > 0 { Some(self.max_capacity) } else { None };
+		self.capacity_override.or(logged).or(self.default_capacity)
+	}
+
+	///! Fraction of the capacity used, None if the capacity is unknown
+	pub fn storage_ratio(&self) -> Option<f64> {
+		storage_ratio(self.used_space, self.capacity())
+	}
+
+	///! The counters since vdash started or they were last reset, across any
+	///! restarts of the node
+	pub fn lifetime(&self) -> RunCounters {
+		RunCounters {
+			gets: self.activity_gets,
+			puts: self.activity_puts,
+			errors: self.activity_errors,
+			warnings: self.activity_warnings,
+			rewards: self.rewards,
+			bytes_in: self.bytes_in,
+			bytes_out: self.bytes_out,
+		}
+	}
+
+	///! The counters since the node's latest startup banner, or the lifetime
+	///! counters if none has been logged since they started
+	pub fn current_run(&self) -> RunCounters {
+		self.lifetime().since(&self.run_start)
+	}
+
+	///! Note the lifetime counters now, so that the node view can show what
+	///! each has added since. Lifetime counters carry on across restarts of
+	///! the node, so the marker does too
+	pub fn drop_marker(&mut self, now: &DateTime<Utc>) {
+		self.marker = Some(CounterMarker { dropped: *now, counters: self.lifetime() });
+	}
+
+	///! What each counter has added since the marker, None without one
+	pub fn since_marker(&self) -> Option<RunCounters> {
+		self.marker.as_ref().map(|marker| self.lifetime().since(&marker.counters))
+	}
+
+	///! Zero the activity counters and timelines, e.g. when debugging a node
+	pub fn reset_counters(&mut self, now: &DateTime<Utc>) {
+		self.activity_gets = 0;
+		self.activity_puts = 0;
+		self.activity_errors = 0;
+		self.activity_warnings = 0;
+		self.rewards = 0;
+		self.bytes_in = 0;
+		self.bytes_out = 0;
+		self.replications = 0;
+		self.replications_in = 0;
+		self.replications_out = 0;
+		self.relocations = 0;
+		self.latencies.clear();
+		self.run_start = RunCounters::default();
+		if let Some(marker) = self.marker.as_mut() {
+			marker.counters = RunCounters::default(); // All counted from now is since the marker
+		}
+		self.category_count.clear();
+		self.user_metrics.reset();
+		for timeline in self.timelines_mut() {
+			timeline.clear();
+		}
+		self.counters_reset = Some(*now);
 	}
 
 	///! Process a line from a SAFE Node logfile.
 	///! May add a LogMeta to the NodeMetrics::log_history vector.
 	///! Use a created LogMeta to update metrics.
-	pub fn gather_metrics(&mut self, line: &str) -> Result<(), std::io::Error> {
+	///! Returns the level of the line, as classified for the error and warning counts.
+	///! A line without a time, continuing no entry which has one, is taken as logged now
+	pub fn gather_metrics(&mut self, line: &str, now: &DateTime<Utc>) -> Result<LogLevel, std::io::Error> {
 		// let mut parser_result = format!("LogMeta::decode_metadata() failed on: {}", line); // For debugging
 
-		if let Some(metadata) = LogEntry::decode_metadata(line) {
-			self.entry_metadata = Some(metadata);
-		}
-
-		if self.entry_metadata.is_none() {
-			return Ok(());	// Skip until start of first log message
+		let decoded = LogEntry::decode_metadata(line, self.log_profile);
+		let timed = decoded.is_some();
+		match decoded {
+			Some(metadata) => self.entry_metadata = Some(metadata),
+			// Continues the entry before unless that had no time either
+			None if self.entry_metadata.as_ref().is_none_or(|metadata| !metadata.timed) => {
+				self.entry_metadata = Some(LogMeta::logged_at(line, now));
+			}
+			None => {}
 		}
 
 		let entry = LogEntry { logstring: String::from(line) };
 		let entry_metadata = self.entry_metadata.as_ref().unwrap().clone();
 		let entry_time = entry_metadata.time;
+		if self.last_activity.is_none_or(|last| entry_time > last) {
+			self.last_activity = Some(entry_time);
+		}
 
 		self.update_timelines(&entry_time);
+		self.lines_timeline.increment_value(&entry_time);
+		let level = self.count_log_level(line);
+		match level {
+			LogLevel::Error => self.count_error(&entry_time),
+			LogLevel::Warn => self.count_warning(&entry_time),
+			_ => {}
+		}
 		self.parser_output = entry_metadata.parser_output.clone();
-		self.process_logfile_entry(&entry.logstring, &entry_metadata); // May overwrite self.parser_output
-		self.log_history.push(entry); // TODO Trim log_history
+		let recognised = self.process_logfile_entry(&entry.logstring, &entry_metadata); // May overwrite self.parser_output
+		let recognised = self.count_churn(line, &entry_time, recognised) || recognised;
+		let recognised = self.sample_latency(line, &entry_time, recognised) || recognised;
+		let recognised = self.user_metrics.process_line(line) || recognised;
+		// Lines about metrics which are fetched from the node instead aren't missed
+		self.last_anomaly = diagnose_line(line, timed, recognised || self.metrics_from_endpoint);
+		self.peers_timeline.set_value(&entry_time, self.peers);
+		if let Some(store_cost) = self.store_cost {
+			self.store_cost_timeline.set_value(&entry_time, store_cost);
+		}
+		if let Some(records) = self.records_stored {
+			self.records_timeline.set_value(&entry_time, records);
+		}
+		if self.used_space > 0 {
+			self.used_space_timeline.set_value(&entry_time, self.used_space);
+		}
+		if let Some(ratio) = self.storage_ratio() {
+			self.storage_timeline.set_value(&entry_time, (ratio * 100.0).round() as u64);
+		}
+		push_history(&mut self.log_history, entry, self.history_max);
 
 		// --debug-dashboard - prints parser results for a single logfile
 		// to a temp logfile which is displayed in the adjacent window.
 		debug_log!(&self.parser_output.clone());
 
-		Ok(())
+		Ok(level)
 	}
 
 	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
-		for timeline in &mut [
+		for timeline in self.timelines_mut() {
+			timeline.update_current_time(now);
+		}
+	}
+
+	pub fn timelines(&self) -> [&TimelineSet; 16] {
+		[
+			&self.puts_timeline,
+			&self.gets_timeline,
+			&self.errors_timeline,
+			&self.warnings_timeline,
+			&self.peers_timeline,
+			&self.bytes_in_timeline,
+			&self.bytes_out_timeline,
+			&self.store_cost_timeline,
+			&self.storage_timeline,
+			&self.used_space_timeline,
+			&self.records_timeline,
+			&self.lines_timeline,
+			&self.replications_timeline,
+			&self.relocations_timeline,
+			&self.latency_timeline,
+			&self.rewards_timeline,
+		]
+	}
+
+	///! The timeline with a name such as 'PUTS'
+	pub fn timeline_named(&self, name: &str) -> Option<&TimelineSet> {
+		self.timelines().iter().find(|timeline| timeline.get_name() == name).copied()
+	}
+
+	pub fn timelines_mut(&mut self) -> [&mut TimelineSet; 16] {
+		[
 			&mut self.puts_timeline,
 			&mut self.gets_timeline,
 			&mut self.errors_timeline,
+			&mut self.warnings_timeline,
+			&mut self.peers_timeline,
+			&mut self.bytes_in_timeline,
+			&mut self.bytes_out_timeline,
+			&mut self.store_cost_timeline,
+			&mut self.storage_timeline,
+			&mut self.used_space_timeline,
+			&mut self.records_timeline,
+			&mut self.lines_timeline,
+			&mut self.replications_timeline,
+			&mut self.relocations_timeline,
+			&mut self.latency_timeline,
+			&mut self.rewards_timeline,
 		]
-		.iter_mut()
-		{
-			timeline.update_current_time(&now);
-		}
-	}
-
-	///! Return a LogMeta and capture metadata for logfile node start:
-	///!	'Running safe-node v0.24.0'
-	// pub fn parse_start(&mut self, line: &str) -> Option<LogMeta> {
-	// 	let running_prefix = String::from("Running sn_node ");
-
-	// 	if line.starts_with(&running_prefix) {
-	// 		self.running_message = Some(line.to_string());
-	// 		self.running_version = Some(line[running_prefix.len()..].to_string());
-	// 		self.node_started = self.entry_metadata.time;
-	// 		let parser_output = format!(
-	// 			"START at {}",
-	// 			self.entry_metadata
-	// 				.map_or(String::from("None"), |m| format!("{}", m))
-	// 		);
-
-	// 		self.reset_metrics();
-	// 		return Some(LogMeta {
-	// 			category: String::from("START"),
-	// 			time: self.entry_metadata.time,
-	// 			source: String::from(""),
-	// 			message: line.to_string(),
-	// 			parser_output,
-	// 		});
-	// 	}
-
-	// 	None
-	// }
+	}
 
 	///! Process a logfile entry
 	///! Returns true if the line has been processed and can be discarded
 	pub fn process_logfile_entry(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
-		return self.parse_data_response(
-			&line,
+		self.parse_data_response(
+			line,
 			"Running as Node: SendToSection [ msg: MsgEnvelope { message: QueryResponse { response: QueryResponse::",
-		) || self.parse_gets_and_puts(&line, &entry_metadata.time) || self.parse_states(&line, &entry_metadata);
+		) || self.parse_startup(line, &entry_metadata.time)
+			|| self.parse_served_metrics(line, &entry_metadata.time)
+			|| self.parse_network(line)
+			|| self.parse_states(line, entry_metadata)
 	}
 
-	///! TODO: Review and update these tests
-	fn parse_gets_and_puts(&mut self, line: &String, entry_time: &DateTime<Utc>) -> bool {
-		if line.contains("Getting chunk") {
-			self.count_get(&entry_time);
+	///! Parse the metrics which a node's --node-metrics-url also serves,
+	///! unless they are fetched from there instead
+	fn parse_served_metrics(&mut self, line: &String, entry_time: &DateTime<Utc>) -> bool {
+		if self.metrics_from_endpoint {
+			return false;
+		}
+		self.parse_gets_and_puts(line, entry_time)
+			|| self.parse_rewards(line, entry_time)
+			|| self.parse_store_cost(line)
+			|| self.parse_records(line)
+			|| self.parse_peers(line)
+			|| self.parse_transfer(line, entry_time)
+	}
+
+	///! Capture the node version, PeerId and listening address from the
+	///! startup banner, e.g. 'Running safenode v0.110.0', 'PeerId is 12D3KooW...'
+	///! and 'Local node is listening on "/ip4/10.0.0.5/udp/12000/quic-v1"'.
+	///! Each banner overwrites earlier values so a restart after an upgrade is
+	///! noticed, and every banner after the first counts as a restart. Of the
+	///! addresses listened on, a loopback one doesn't replace a reachable one
+	fn parse_startup(&mut self, line: &str, entry_time: &DateTime<Utc>) -> bool {
+		if let Some(captures) = RUNNING_VERSION_PATTERN.captures(line) {
+			let version = captures["version"].to_string();
+			if self.node_started.is_some() {
+				self.restarts += 1;
+			}
+			self.running_message = Some(line.to_string());
+			self.node_started = Some(*entry_time);
+			self.listen_address = None;
+			self.run_start = self.lifetime();
+			self.parser_output = format!("START {} v{} at {}", &captures["binary"], version, entry_time);
+			self.running_version = Some(version);
+			return true;
+		}
+
+		if let Some(address) = parse_listen_address(line) {
+			if self.listen_address.is_none_or(|current| is_local_address(&current) || !is_local_address(&address)) {
+				self.listen_address = Some(address);
+			}
+			self.parser_output = format!("Listening on {}", address);
+			return true;
+		}
+
+		if let Some(captures) = PEER_ID_PATTERN.captures(line) {
+			let peer_id = captures["peer_id"].to_string();
+			self.parser_output = format!("PeerId: {}", peer_id);
+			self.peer_id = Some(peer_id);
+			return true;
+		}
+
+		false
+	}
+
+	///! Track whether the node is on the network, e.g. 'Disconnected from the
+	///! network' and 'Reconnected to the network'. Each disconnection of a
+	///! connected node counts as a new episode
+	fn parse_network(&mut self, line: &str) -> bool {
+		let captures = match NETWORK_PATTERN.captures(line) {
+			Some(captures) => captures,
+			None => return false,
+		};
+		if captures.name("disconnected").is_some() {
+			if self.connected {
+				self.disconnects += 1;
+			}
+			self.connected = false;
+			self.parser_output = format!("disconnected from network, episodes: {}", self.disconnects);
+		} else {
+			self.connected = true;
+			self.parser_output = String::from("connected to network");
+		}
+		true
+	}
+
+	///! Track connected peers, e.g. 'ConnectionEstablished' and 'ConnectionClosed'
+	///! events, or an absolute count such as 'now we have #12 connected peers'
+	fn parse_peers(&mut self, line: &str) -> bool {
+		if let Some(captures) = PEER_COUNT_PATTERN.captures(line) {
+			let count = captures.name("connected").or_else(|| captures.name("table_size"));
+			if let Some(count) = count {
+				match count.as_str().parse::<u64>() {
+					Ok(peers) => {
+						self.peers = peers;
+						self.parser_output = format!("peers: {}", self.peers);
+					}
+					Err(_e) => self.parser_output = format!("failed to parse peer count '{}' in: {}", count.as_str(), line),
+				}
+				return true;
+			}
+		}
+
+		if line.contains("ConnectionEstablished") {
+			self.peers += 1;
+			self.parser_output = format!("peer connected, peers: {}", self.peers);
 			return true;
-		} else if line.contains("StoredNewChunk") {
-			self.count_put(&entry_time);
+		}
+
+		if line.contains("ConnectionClosed") {
+			self.peers = self.peers.saturating_sub(1);
+			self.parser_output = format!("peer disconnected, peers: {}", self.peers);
+			return true;
+		}
+
+		false
+	}
+
+	///! Accumulate bytes transferred, e.g. 'Received 2048 bytes from ...' or
+	///! 'outbound request of 512 bytes'. Lines without a size are left alone
+	fn parse_transfer(&mut self, line: &str, entry_time: &DateTime<Utc>) -> bool {
+		let captures = match TRANSFER_PATTERN.captures(line) {
+			Some(captures) => captures,
+			None => return false,
+		};
+		let bytes = match captures["bytes"].parse::<u64>() {
+			Ok(bytes) => bytes,
+			Err(_e) => {
+				self.parser_output = format!("failed to parse byte count '{}' in: {}", &captures["bytes"], line);
+				return true;
+			}
+		};
+		match captures["direction"].to_lowercase().as_str() {
+			"received" | "inbound" => {
+				self.bytes_in = self.bytes_in.saturating_add(bytes);
+				self.bytes_in_timeline.add_value(entry_time, bytes);
+				self.parser_output = format!("bytes in: +{}, total {}", bytes, self.bytes_in);
+			}
+			_ => {
+				self.bytes_out = self.bytes_out.saturating_add(bytes);
+				self.bytes_out_timeline.add_value(entry_time, bytes);
+				self.parser_output = format!("bytes out: +{}, total {}", bytes, self.bytes_out);
+			}
+		}
+		true
+	}
+
+	///! Accumulate rewards, e.g. 'Total payment of AttoTokens(12000) received'
+	///! NanoTokens amounts are converted so that rewards are always in attos
+	fn parse_rewards(&mut self, line: &str, entry_time: &DateTime<Utc>) -> bool {
+		for (prefix, attos_per_unit) in [("payment of AttoTokens(", 1u128), ("payment of NanoTokens(", 1_000_000_000)] {
+			if let Some(start) = line.find(prefix) {
+				let amount = &line[start + prefix.len()..];
+				let amount = amount.split(')').next().unwrap_or("");
+				match amount.trim().parse::<u128>() {
+					Ok(amount) => {
+						let attos = amount.saturating_mul(attos_per_unit);
+						self.rewards = self.rewards.saturating_add(attos);
+						self.rewards_timeline.add_value(entry_time, attos.min(u128::from(u64::MAX)) as u64);
+						self.parser_output = format!("rewards: +{} attos, total {}", attos, self.rewards);
+					}
+					Err(_e) => self.parser_output = format!("failed to parse reward amount '{}' in: {}", amount, line),
+				}
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Track the latest store cost quoted, e.g. 'Store cost is AttoTokens(5000)',
+	///! 'store cost: NanoTokens(12)', 'Cost is now 1,250 attos' or 'store cost 0.5 ANT'.
+	///! Amounts without a unit are in attos
+	fn parse_store_cost(&mut self, line: &str) -> bool {
+		let captures = match STORE_COST_PATTERN.captures(line) {
+			Some(captures) => captures,
+			None => return false,
+		};
+		let unit = captures.name("wrapper").or_else(|| captures.name("unit")).map_or("", |m| m.as_str());
+		let attos_per_unit = match unit.to_lowercase().as_str() {
+			"nanotokens" | "nano" | "nanos" => 1_000_000_000,
+			"ant" | "token" | "tokens" => 1_000_000_000_000_000_000,
+			_ => 1,
+		};
+		match parse_token_amount(&captures["amount"], attos_per_unit) {
+			Some(attos) => {
+				self.store_cost = Some(attos);
+				self.parser_output = format!("store cost: {} attos", attos);
+			}
+			None => self.parser_output = format!("failed to parse store cost '{}' in: {}", &captures["amount"], line),
+		}
+		true
+	}
+
+	///! Track the records the node stores from its periodic reports, e.g.
+	///! 'Records stored: 1234', adjusted by later events such as 'Removed 3
+	///! records' or 'Replicated record'. Events before the first report are
+	///! ignored, leaving the count to the next report
+	fn parse_records(&mut self, line: &str) -> bool {
+		if let Some(captures) = RECORDS_STORED_PATTERN.captures(line) {
+			match captures["count"].parse::<u64>() {
+				Ok(count) => {
+					self.records_stored = Some(count);
+					self.parser_output = format!("records stored: {}", count);
+				}
+				Err(_e) => self.parser_output = format!("failed to parse record count '{}' in: {}", &captures["count"], line),
+			}
+			return true;
+		}
+
+		let captures = match RECORDS_CHANGED_PATTERN.captures(line) {
+			Some(captures) => captures,
+			None => return false,
+		};
+		let change = match captures.name("count") {
+			Some(count) => match count.as_str().parse::<u64>() {
+				Ok(change) => change,
+				Err(_e) => {
+					self.parser_output = format!("failed to parse record count '{}' in: {}", count.as_str(), line);
+					return true;
+				}
+			},
+			None => 1,
+		};
+		if let Some(records) = self.records_stored {
+			let records = if captures["change"].eq_ignore_ascii_case("replicated") {
+				records.saturating_add(change)
+			} else {
+				records.saturating_sub(change)
+			};
+			self.records_stored = Some(records);
+			self.parser_output = format!("records stored: {}", records);
+		}
+		true
+	}
+
+	///! Add any round trip or response latency in the line to the samples, with
+	///! their mean to the timeline
+	fn sample_latency(&mut self, line: &str, entry_time: &DateTime<Utc>, recognised: bool) -> bool {
+		let micros = match parse_latency(line) {
+			Some(micros) => micros,
+			None => return false,
+		};
+		self.latencies.push(micros);
+		if let Some(stats) = self.latencies.stats() {
+			self.latency_timeline.set_value(entry_time, (stats.mean / 1_000.0).round() as u64);
+		}
+		if !recognised {
+			self.parser_output = format!("latency: {}µs", micros);
+		}
+		true
+	}
+
+	///! Count replication and relocation events, see classify_churn(). Lines
+	///! about replication may also adjust the records stored, so are counted
+	///! whether or not already recognised
+	fn count_churn(&mut self, line: &str, entry_time: &DateTime<Utc>, recognised: bool) -> bool {
+		let event = match classify_churn(line) {
+			Some(event) => event,
+			None => return false,
+		};
+		let output = match event {
+			ChurnEvent::Relocation => {
+				self.relocations += 1;
+				self.relocations_timeline.increment_value(entry_time);
+				format!("relocations: {}", self.relocations)
+			}
+			ChurnEvent::Replication(direction) => {
+				self.replications += 1;
+				match direction {
+					Some(ReplicationDirection::Inbound) => self.replications_in += 1,
+					Some(ReplicationDirection::Outbound) => self.replications_out += 1,
+					None => {}
+				}
+				self.replications_timeline.increment_value(entry_time);
+				format!("replications: {} ({} in, {} out)", self.replications, self.replications_in, self.replications_out)
+			}
+		};
+		if !recognised {
+			self.parser_output = output;
+		}
+		true
+	}
+
+	///! Count GETs and PUTs of chunks and records
+	///! Counts are never reset so they accumulate when a node restarts
+	///! and continues writing to the same logfile.
+	fn parse_gets_and_puts(&mut self, line: &String, entry_time: &DateTime<Utc>) -> bool {
+		if self.log_profile.is_get(line) {
+			self.count_get(entry_time);
 			return true;
-		} else if line.contains("Editing Register success!") {
-			self.count_put(&entry_time);
+		} else if self.log_profile.is_put(line) {
+			self.count_put(entry_time);
 			return true;
 		}
-		return false;
+		false
 	}
 
 	///! Update data metrics from a handler response logfile entry
@@ -879,8 +2958,8 @@ impl NodeMetrics {
 				response = line.as_str()[response_start..response_start + response_end]
 					.as_ref();
 				if !response.is_empty() {
-					let activity_entry = ActivityEntry::new(&line, &self.entry_metadata.as_ref().unwrap(), response);
-					self.activity_history.push(activity_entry);
+					let activity_entry = ActivityEntry::new(line, self.entry_metadata.as_ref().unwrap(), response);
+					push_history(&mut self.activity_history, activity_entry, self.history_max);
 					self.parser_output = format!("node activity: {}", response);
 				}
 			}
@@ -890,16 +2969,12 @@ impl NodeMetrics {
 
 			return true;
 		};
-		return false;
+		false
 	}
 
 	///! Capture state updates from a logfile entry
 	///! Returns true if the line has been processed and can be discarded
-	fn parse_states(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
-		if entry_metadata.category.eq("ERROR") {
-			self.count_error(&entry_metadata.time);
-		}
-
+	fn parse_states(&mut self, line: &String, _entry_metadata: &LogMeta) -> bool {
 		let &content = &line.as_str();
 
 		// System Load
@@ -1010,13 +3085,13 @@ impl NodeMetrics {
 		if content.contains("Sending aggregated JoinRequest")
 		{
 			self.agebracket = NodeAgebracket::Joining;
-			self.parser_output = format!("Age-bracket updated to: Joining");
+			self.parser_output = "Age-bracket updated to: Joining".to_string();
 			return true;
 		}
 
 		if content.contains("Joined the network") {
 			self.agebracket = NodeAgebracket::Adult;
-			self.parser_output = format!("Age updated to: Adult");
+			self.parser_output = "Age updated to: Adult".to_string();
 
 			if let Some(node_name) = self.parse_word("➤", content) {
 				self.parser_output = format!("node name: {}", &node_name);
@@ -1028,7 +3103,7 @@ impl NodeMetrics {
 
 		if content.contains("Relocation: switching from") {
 			self.agebracket = NodeAgebracket::Adult;
-			self.parser_output = format!("Age updated to: Adult");
+			self.parser_output = "Age updated to: Adult".to_string();
 			if let Some(new_node_name) = self.parse_word("to", content) {
 				self.node_name = new_node_name;
 				self.parser_output = format!("New node name: {}", &self.node_name);
@@ -1039,7 +3114,7 @@ impl NodeMetrics {
 
 		if content.contains("PromotedToElder") {
 			self.agebracket = NodeAgebracket::Elder;
-			self.parser_output = format!("Age updated to: Elder");
+			self.parser_output = "Age updated to: Elder".to_string();
 			return true;
 		}
 
@@ -1060,9 +3135,9 @@ impl NodeMetrics {
 		if let Some(position) = content.find(prefix) {
 			let word: Vec<&str> = content[position + prefix.len()..]
 				.trim()
-				.splitn(2, |c| c == ' ' || c == ',')
+				.splitn(2, [' ', ','])
 				.collect();
-			if word.len() > 0 {
+			if !word.is_empty() {
 				match word[0].parse::<usize>() {
 					Ok(value) => return Some(value),
 					Err(_e) => self.parser_output = format!("failed to parse '{}' as usize from: '{}'", word[0], &content[position + prefix.len()..]),
@@ -1076,9 +3151,9 @@ impl NodeMetrics {
 		if let Some(position) = content.find(prefix) {
 			let word: Vec<&str> = content[position + prefix.len()..]
 				.trim()
-				.splitn(2, |c| c == ' ' || c == ',')
+				.splitn(2, [' ', ','])
 				.collect();
-			if word.len() > 0 {
+			if !word.is_empty() {
 				match word[0].parse::<u64>() {
 					Ok(value) => return Some(value),
 					Err(_e) => self.parser_output = format!("failed to parse '{}' as u64 from: '{}'", word[0], &content[position + prefix.len()..]),
@@ -1092,9 +3167,9 @@ impl NodeMetrics {
 		if let Some(position) = content.find(prefix) {
 			let word: Vec<&str> = content[position + prefix.len()..]
 				.trim()
-				.splitn(2, |c| c == ' ' || c == ',')
+				.splitn(2, [' ', ','])
 				.collect();
-			if word.len() > 0 {
+			if !word.is_empty() {
 				match word[0].parse::<f32>() {
 					Ok(value) => return Some(value),
 					Err(_e) => self.parser_output = format!("failed to parse '{}' as float from: '{}'", word[0], &content[position + prefix.len()..]),
@@ -1108,9 +3183,9 @@ impl NodeMetrics {
 		if let Some(start) = content.find(prefix) {
 			let word: Vec<&str> = content[start + prefix.len()..]
 				.trim_start()
-				.splitn(2, |c| c == ' ' || c == ',')
+				.splitn(2, [' ', ','])
 				.collect();
-			if word.len() > 0 {
+			if !word.is_empty() {
 				return Some(word[0].to_string());
 			} else {
 				self.parser_output = format!("failed to parse word at: '{}'", &content[start..]);
@@ -1134,19 +3209,55 @@ impl NodeMetrics {
 		self.errors_timeline.increment_value(time);
 	}
 
-	///! TODO
-	pub fn parse_logentry_counts(&mut self, entry: &LogMeta) {
-		// Categories ('INFO', 'WARN' etc)
-		if !entry.category.is_empty() {
-			let count = match self.category_count.get(&entry.category) {
-				Some(count) => count + 1,
-				None => 1,
-			};
-			self.category_count.insert(entry.category.clone(), count);
-		}
+	fn count_warning(&mut self, time: &DateTime<Utc>) {
+		self.activity_warnings += 1;
+		self.warnings_timeline.increment_value(time);
+	}
+
+	///! Tally lines by level ('INFO', 'WARN' etc)
+	pub fn count_log_level(&mut self, line: &str) -> LogLevel {
+		let level = LogLevel::classify(line);
+		*self.category_count.entry(level).or_insert(0) += 1;
+		level
+	}
+
+	pub fn level_count(&self, level: LogLevel) -> usize {
+		*self.category_count.get(&level).unwrap_or(&0)
+	}
+
+	pub fn errors_per_minute(&self) -> u64 {
+		self.errors_timeline.rate_per_minute(self.rate_window)
+	}
+
+	pub fn warnings_per_minute(&self) -> u64 {
+		self.warnings_timeline.rate_per_minute(self.rate_window)
+	}
+
+	///! Lines logged per minute over the --rate-window, whatever they are about
+	pub fn lines_per_minute(&self) -> u64 {
+		self.lines_timeline.rate_per_minute(self.rate_window)
+	}
+
+	pub fn bytes_in_per_minute(&self) -> u64 {
+		self.bytes_in_timeline.rate_per_minute(self.rate_window)
+	}
+
+	pub fn bytes_out_per_minute(&self) -> u64 {
+		self.bytes_out_timeline.rate_per_minute(self.rate_window)
 	}
 }
 
+///! Add an entry to a history, dropping the oldest beyond max entries
+fn push_history<T>(history: &mut VecDeque<T>, entry: T, max: usize) {
+	if max == 0 {
+		return;
+	}
+	if history.len() == max {
+		history.pop_front();
+	}
+	history.push_back(entry);
+}
+
 ///! Node activity for node activity_history
 pub struct ActivityEntry {
 	pub message: String,
@@ -1182,21 +3293,40 @@ pub struct LogMeta {
 	pub time: DateTime<Utc>,
 	pub source: String,
 	pub message: String,
+	pub timed: bool, // False if the line had no time, so is taken to be logged now
 
 	pub parser_output: String,
 }
 
 impl LogMeta {
-	pub fn clone(&self) -> LogMeta {
+	fn logged_at(line: &str, now: &DateTime<Utc>) -> LogMeta {
 		LogMeta {
-			category: self.category.clone(),
-			time: self.time,
-			source: self.source.clone(),
-			message: self.message.clone(),
-			parser_output: self.parser_output.clone(),
+			category: String::new(),
+			time: *now,
+			source: String::new(),
+			message: String::from(line),
+			timed: false,
+			parser_output: String::from("no log time, taken as now"),
 		}
 	}
 }
+
+///! The time of a log line, in RFC 3339 format with any number of decimal places.
+///! A time without a timezone is taken to be UTC, e.g. all of
+///!	'2022-01-15T20:21:02.659471Z', '2022-01-15T21:21:02.659+01:00' and
+///!	'2022-01-15T20:21:02.659471'
+pub fn parse_log_time(time_string: &str) -> Option<DateTime<Utc>> {
+	if let Ok(time) = DateTime::parse_from_rfc3339(time_string) {
+		return Some(time.with_timezone(&Utc));
+	}
+	if let Ok(time) = DateTime::parse_from_str(time_string, "%Y-%m-%dT%H:%M:%S%.f%z") {
+		return Some(time.with_timezone(&Utc));
+	}
+	chrono::NaiveDateTime::parse_from_str(time_string, "%Y-%m-%dT%H:%M:%S%.f")
+		.ok()
+		.map(|time| DateTime::<Utc>::from_utc(time, Utc))
+}
+
 ///! Used to build a history of what is in the log, one LogMeta per line
 pub struct LogEntry {
 	pub logstring: String,			// One line of raw text from the logfile
@@ -1207,70 +3337,242 @@ impl LogEntry {
 	///! " INFO 2022-01-15T20:21:02.659471Z [sn/src/node/routing/core/mod.rs:L211]:"
 	///! "	 ➤ Writing our latest PrefixMap to disk"
 	///! " ERROR 2022-01-15T20:21:07.643598Z [sn/src/node/routing/api/dispatcher.rs:L450]:"
-	fn decode_metadata(line: &str) -> Option<LogMeta> {
+	///! "[2024-05-09T12:31:33.123456Z INFO sn_node::node] Node started"
+	///! Of these, the layouts of the --log-profile are understood
+	pub fn decode_metadata(line: &str, profile: &LogProfile) -> Option<LogMeta> {
 		if line.is_empty() {
 			return None;
 		}
 
-		if let Some(captures) = LOG_LINE_PATTERN.captures(line) {
-			let category = captures.name("category").map_or("", |m| m.as_str());
-			let time_string = captures.name("time_string").map_or("", |m| m.as_str());
-			let source = captures.name("source").map_or("", |m| m.as_str());
-			let message = captures.name("message").map_or("", |m| m.as_str());
-			let time_str: String;
-
-			let time_utc: DateTime<Utc>;
-
-			match DateTime::parse_from_str(time_string, "%+") {
-				Ok(time) => {
-					time_utc = time.with_timezone(&Utc);
-					time_str = format!("{}", time);
-				}
-				Err(e) => {
-					debug_log!(format!("ERROR parsing logfile time: {}", e).as_str());
-					return None
-				}
-			};
-			let parser_output = format!(
-				"c: {}, t: {}, s: {}, m: {}",
-				category, time_str, source, message
-			);
-
-			return Some(LogMeta {
-				category: String::from(category),
-				time: time_utc,
-				source: String::from(source),
-				message: String::from(message),
-				parser_output,
-			});
-		}
-		None
+		let captures = profile.captures(line)?;
+		let category = captures.name("category").map_or("", |m| m.as_str());
+		let time_string = captures.name("time_string").map_or("", |m| m.as_str());
+		let source = captures.name("source").map_or("", |m| m.as_str());
+		let message = captures.name("message").map_or("", |m| m.as_str());
+
+		let time_utc = match parse_log_time(time_string) {
+			Some(time) => time,
+			None => {
+				debug_log!(format!("ERROR parsing logfile time: {}", time_string).as_str());
+				return None;
+			}
+		};
+		let parser_output = format!(
+			"c: {}, t: {}, s: {}, m: {}",
+			category, time_utc, source, message
+		);
+
+		Some(LogMeta {
+			category: String::from(category),
+			time: time_utc,
+			source: String::from(source),
+			message: String::from(message),
+			timed: true,
+			parser_output,
+		})
 	}
 }
 
 ///! Active UI at top level
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DashViewMain {
 	DashSummary,
 	DashNode,
+	DashDetail, // Everything about one node
+	DashCompare, // Two nodes marked for comparison, side by side
+	DashWarnings, // Recent warnings and errors of every node
+	DashDiagnostics, // Lines vdash couldn't parse, see --debug-log
+	DashHeatmap, // A metric of every node over time, see --heatmap-metric
 	DashDebug,
 }
 
-lazy_static::lazy_static! {
-	pub static ref TIMELINES: std::vec::Vec<(&'static str, Duration)> = vec!(
-		("1 second columns", Duration::seconds(1)),
-		("1 minute columns", Duration::minutes(1)),
-		("1 hour columns", Duration::hours(1)),
-		("1 day columns", Duration::days(1)),
-		("1 week columns", Duration::days(7)),
-		("1 year columns", Duration::days(365)),
-	);
+impl DashViewMain {
+	///! As given to --view, None for the debug dashboard which has --debug-dashboard
+	pub fn name(self) -> Option<&'static str> {
+		match self {
+			DashViewMain::DashSummary => Some("summary"),
+			DashViewMain::DashNode => Some("node"),
+			DashViewMain::DashDetail => Some("detail"),
+			DashViewMain::DashWarnings => Some("warnings"),
+			DashViewMain::DashHeatmap => Some("heatmap"),
+			DashViewMain::DashCompare | DashViewMain::DashDiagnostics | DashViewMain::DashDebug => None,
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<DashViewMain> {
+		[
+			DashViewMain::DashSummary,
+			DashViewMain::DashNode,
+			DashViewMain::DashDetail,
+			DashViewMain::DashWarnings,
+			DashViewMain::DashHeatmap,
+		]
+			.iter()
+			.copied()
+			.find(|view| view.name() == Some(name))
+	}
+}
+
+///! The counters shown by the node stats, cycled with the 'c' key
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CounterView {
+	Lifetime, // Totals since vdash started, across restarts of the node
+	CurrentRun, // Totals since the node's latest startup banner
+	Rates, // Per minute, over the latest complete bucket of each timeline
+}
+
+impl CounterView {
+	pub fn next(self) -> CounterView {
+		match self {
+			CounterView::Lifetime => CounterView::CurrentRun,
+			CounterView::CurrentRun => CounterView::Rates,
+			CounterView::Rates => CounterView::Lifetime,
+		}
+	}
+}
+
+///! Metric used to order the nodes in the summary view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+	Logfile,
+	StorageUsed,
+	ErrorRate,
+	Rewards,
+	Health,
+}
+
+impl SortKey {
+	pub fn next(self) -> SortKey {
+		match self {
+			SortKey::Logfile => SortKey::StorageUsed,
+			SortKey::StorageUsed => SortKey::ErrorRate,
+			SortKey::ErrorRate => SortKey::Rewards,
+			SortKey::Rewards => SortKey::Health,
+			SortKey::Health => SortKey::Logfile,
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			SortKey::Logfile => "name",
+			SortKey::StorageUsed => "storage used",
+			SortKey::ErrorRate => "error rate",
+			SortKey::Rewards => "rewards",
+			SortKey::Health => "health",
+		}
+	}
+
+	///! The key with name(), as used by --sort and the preferences file
+	pub fn from_name(name: &str) -> Option<SortKey> {
+		[SortKey::Logfile, SortKey::StorageUsed, SortKey::ErrorRate, SortKey::Rewards, SortKey::Health]
+			.iter()
+			.copied()
+			.find(|key| key.name() == name)
+	}
+}
+
+///! Filter applied to node names and logfile paths, entered after pressing '/'
+///!
+///! Matches names or logfiles containing the filter text, or matching it as a
+///! regex. Text which isn't a valid regex is only matched literally. Names are
+///! also fuzzy matched, see fuzzy_match(), to rank the nodes shown.
+#[derive(Default)]
+pub struct NodeFilter {
+	text: String,
+	regex: Option<regex::Regex>,
+}
+
+impl NodeFilter {
+	pub fn new(text: &str) -> NodeFilter {
+		let mut filter = NodeFilter::default();
+		filter.set_text(text);
+		filter
+	}
+
+	pub fn get_text(&self) -> &String {
+		&self.text
+	}
+
+	pub fn set_text(&mut self, text: &str) {
+		self.text = text.to_string();
+		self.regex = regex::Regex::new(text).ok();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.text.is_empty()
+	}
+
+	pub fn matches(&self, logfile: &str) -> bool {
+		if self.text.is_empty() || logfile.contains(self.text.as_str()) {
+			return true;
+		}
+		match &self.regex {
+			Some(regex) => regex.is_match(logfile),
+			None => false,
+		}
+	}
+
+	///! Matches a node by its name or its logfile
+	pub fn matches_node(&self, monitor: &LogMonitor) -> bool {
+		self.matches(&monitor.name) || self.matches(&monitor.logfile) || self.fuzzy_match(&monitor.name).is_some()
+	}
+
+	///! How well a node's name fuzzy matches the filter, and the characters matched
+	pub fn fuzzy_match(&self, name: &str) -> Option<FuzzyMatch> {
+		fuzzy_match(&self.text, name)
+	}
 }
 
 pub struct DashState {
 	pub main_view: DashViewMain,
+	pub node_filter: NodeFilter,
+	pub filter_editing: bool,
+	pub node_jump: Option<NodeJump>, // While typing the name of a node to jump to
+	pub sort_key: SortKey,
+	pub sort_ascending: bool,
+	pub paused: bool,
+	pub help_visible: bool,
+	pub counter_view: CounterView,
+	pub click_targets: ClickTargets,
+	pub screen_area: Rect, // As last drawn, for the snapshot key
 	pub active_timeline: usize,
+	pub timeline_scale: Scale,
+	pub timeline_has_focus: bool,
+	pub overlay_timelines: bool, // PUTS with GETS, and bytes in with out, on one chart
+	pub normalize_overlay: bool, // Overlaid series scaled to their own maximum
+	pub timeline_cursor: usize, // Columns before the newest, while timeline_has_focus
+	pub downsample: Downsample, // How buckets are combined when there are more than fit
+	pub spark_style: SparkStyle,
+	pub chart_styles: ChartStyles, // Bars or a line, of each timeline, see --chart-style
+	pub trend_directions: Option<GoodDirections>, // To colour timelines by trend, see --trend-colors
+	pub summary_columns: SummaryColumns, // See --columns
+	pub hidden_panels: HiddenPanels, // Of the node and detail views, see --hide
+	pub focus_metric: Option<FocusMetric>, // Whose panels are emphasised and the others dimmed
+	pub top_talkers: usize, // Nodes listed by line rate beside the summary, see --top-talkers
+	pub number_format: NumberFormat,
+	pub timelines: Vec<TimelineSpec>,
+	pub stale_after: Duration,
+	pub check_errors: u64, // Errors per minute at which a node scores nothing for errors
+	pub health_weights: HealthWeights,
+	pub health_colors: bool, // Colour the summary's rows by health, see --health-colors
+	pub primary_gauge: PrimaryGauge, // Shown in each node's resources
+	pub heatmap_metric: String, // The name of the timeline shown by the heatmap view
+	pub gauge_peers: u64, // Peers at which the peers gauge is full
+	pub spike_sigma: Option<f64>, // None disables highlighting of timeline spikes
+	pub theme: Theme,
+	pub key_map: KeyMap,
 	pub dash_node_focus: String,
+	pub summary_logfiles: Vec<String>, // Of the summary's node rows in the order last drawn
+	pub summary_offset: usize, // The first of the summary's rows shown
+	pub compare_nodes: Vec<String>, // Logfiles marked for the compare view
+	pub collapsed_groups: Vec<String>, // Tags whose nodes are hidden in the summary
+	pub recent_warnings: SharedWarnings, // Of every node, for the warnings view
+	pub spotlight: SharedSpotlight, // Lines of every node matching --spotlight keywords
+	pub diagnostics: SharedDiagnostics, // Lines of every node vdash couldn't parse
+	pub active_alerts: Vec<Alert>, // Most severe first, see --alerts
+	pub blink: bool, // Of nodes in critical alert, see --no-blink
+	pub ticks: u64, // Since starting, to time the blink and the flash of changed values
+	pub change_flash: ChangeFlash, // Values last shown in the summary, see --flash-ticks
 
 	// For --debug-window option
 	pub debug_window_list: StatefulList<String>,
@@ -1279,13 +3581,65 @@ pub struct DashState {
 	max_debug_window: usize,
 }
 
+impl Default for DashState {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl DashState {
 	pub fn new() -> DashState {
 
 		DashState {
 			main_view: DashViewMain::DashNode,
+			node_filter: NodeFilter::default(),
+			filter_editing: false,
+			node_jump: None,
+			sort_key: SortKey::Logfile,
+			sort_ascending: true,
+			paused: false,
+			help_visible: false,
+			counter_view: CounterView::Lifetime,
+			click_targets: ClickTargets::default(),
+			screen_area: Rect::default(),
 			active_timeline: 0,
+			timeline_scale: Scale::Linear,
+			timeline_has_focus: false,
+			overlay_timelines: false,
+			normalize_overlay: false,
+			timeline_cursor: 0,
+			downsample: Downsample::Max,
+			spark_style: SparkStyle::Bars,
+			chart_styles: ChartStyles::default(),
+			trend_directions: None,
+			summary_columns: SummaryColumns::default(),
+			hidden_panels: HiddenPanels::default(),
+			focus_metric: None,
+			top_talkers: 5,
+			number_format: NumberFormat::default(),
+			timelines: Vec::<TimelineSpec>::new(),
+			stale_after: Duration::minutes(5),
+			check_errors: 10,
+			health_weights: HealthWeights::default(),
+			health_colors: false,
+			primary_gauge: PrimaryGauge::Storage,
+			heatmap_metric: String::from("PUTS"),
+			gauge_peers: 50,
+			spike_sigma: Some(3.0),
+			theme: Theme::default(),
+			key_map: KeyMap::default(),
 			dash_node_focus: String::new(),
+			summary_logfiles: Vec::new(),
+			summary_offset: 0,
+			compare_nodes: Vec::new(),
+			collapsed_groups: Vec::new(),
+			recent_warnings: RecentWarnings::shared(200),
+			spotlight: Spotlight::shared(Vec::new(), 200),
+			diagnostics: Diagnostics::shared(200),
+			active_alerts: Vec::new(),
+			blink: true,
+			ticks: 0,
+			change_flash: ChangeFlash::new(0),
 
 			debug_window: false,
 			debug_window_has_focus: false,
@@ -1294,6 +3648,14 @@ impl DashState {
 		}
 	}
 
+	///! What --check and the health scores judge nodes by
+	pub fn health_criteria(&self) -> HealthCriteria {
+		HealthCriteria {
+			stale_after: self.stale_after,
+			max_errors_per_minute: self.check_errors,
+		}
+	}
+
 	pub fn _debug_window(&mut self, text: &str) {
 		self.debug_window_list.items.push(text.to_string());
 		let len = self.debug_window_list.items.len();
@@ -1313,6 +3675,12 @@ pub struct DashVertical {
 	_active_view: usize,
 }
 
+impl Default for DashVertical {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl DashVertical {
 	pub fn new() -> Self {
 		DashVertical { _active_view: 0 }
@@ -1331,8 +3699,12 @@ pub fn set_main_view(view: DashViewMain, app: &mut App) {
 
 pub fn save_focus(app: &mut App) {
 	match app.dash_state.main_view {
-		DashViewMain::DashSummary => {} // TODO
-		DashViewMain::DashNode => {
+		DashViewMain::DashSummary
+		| DashViewMain::DashCompare
+		| DashViewMain::DashWarnings
+		| DashViewMain::DashDiagnostics
+		| DashViewMain::DashHeatmap => {} // TODO
+		DashViewMain::DashNode | DashViewMain::DashDetail => {
 			if let Some(focus) = app.get_logfile_with_focus() {
 				app.dash_state.dash_node_focus = focus;
 			}
@@ -1343,8 +3715,12 @@ pub fn save_focus(app: &mut App) {
 
 pub fn restore_focus(app: &mut App) {
 	match app.dash_state.main_view {
-		DashViewMain::DashSummary => {} // TODO
-		DashViewMain::DashNode => {
+		DashViewMain::DashSummary
+		| DashViewMain::DashCompare
+		| DashViewMain::DashWarnings
+		| DashViewMain::DashDiagnostics
+		| DashViewMain::DashHeatmap => {} // TODO
+		DashViewMain::DashNode | DashViewMain::DashDetail => {
 			app.set_logfile_with_focus(app.dash_state.dash_node_focus.clone())
 		}
 		DashViewMain::DashDebug => {
@@ -1354,3 +3730,1316 @@ pub fn restore_focus(app: &mut App) {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::spotlight::spotlight_keywords_from_toml;
+	use chrono::TimeZone;
+
+	fn test_opt() -> Opt {
+		Opt::from_iter(vec!["vdash"])
+	}
+
+	fn log_line(category: &str, message: &str) -> String {
+		format!(" {} 2022-01-15T20:21:02.659471Z [sn/src/node/mod.rs:L211]: {}", category, message)
+	}
+
+	// An App of the logfiles given, set up as App::new() would but reading
+	// nothing, for lines to be fed to with App::process_line()
+	fn test_app(logfiles: &[&str]) -> App {
+		let opt = Opt::from_iter(vec!["vdash"].into_iter().chain(logfiles.iter().copied()));
+		let dash_state = DashState::new();
+		let mut monitors = HashMap::new();
+		for logfile in &opt.files {
+			let mut monitor = LogMonitor::new(&opt, logfile.clone(), opt.lines_max);
+			monitor.name = default_node_name(&logfile.replace("/safenode.log", ""));
+			monitor.recent_warnings = Some(dash_state.recent_warnings.clone());
+			monitor.spotlight = Some(dash_state.spotlight.clone());
+			monitor.diagnostics = Some(dash_state.diagnostics.clone());
+			monitors.insert(logfile.clone(), monitor);
+		}
+		let tick = std::time::Duration::from_millis(opt.tick_rate);
+		App {
+			dash_state,
+			monitors,
+			logfile_with_focus: opt.files[0].clone(),
+			logfiles: MuxedLines::new().unwrap(),
+			logfile_names: opt.files.clone(),
+			unwatched_logfiles: Vec::new(),
+			glob_patterns: Vec::new(),
+			next_glob_rescan: Utc::now(),
+			next_state_save: Utc::now(),
+			next_metrics_export: Utc::now(),
+			shared_snapshot: None,
+			query_socket: None,
+			stdin_reader: None,
+			replay: None,
+			node_capacities: NodeCapacities::default(),
+			node_names: NodeNames::default(),
+			error_spike_detector: None,
+			user_metric_specs: Arc::new(Vec::new()),
+			alert_rules: Vec::new(),
+			redraw: RedrawLimiter::new(tick, tick),
+			run_for: RunFor::new(std::time::Instant::now(), None),
+			opt,
+		}
+	}
+
+	fn gather(metrics: &mut NodeMetrics, lines: &[String]) {
+		for line in lines {
+			metrics.gather_metrics(line, &Utc::now()).unwrap();
+		}
+	}
+
+	#[test]
+	fn counts_record_puts_and_gets() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Retrieved record 8a3f from local store"),
+			log_line("INFO", "Successfully stored record 91c0"),
+			log_line("INFO", "Nothing of interest"),
+		]);
+		assert_eq!(metrics.activity_puts, 2);
+		assert_eq!(metrics.activity_gets, 1);
+
+		// Lines from a restarted node continue to accumulate
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.1.0"),
+			log_line("INFO", "Retrieved record 91c0"),
+		]);
+		assert_eq!(metrics.activity_puts, 2);
+		assert_eq!(metrics.activity_gets, 2);
+	}
+
+	#[test]
+	fn classifies_log_levels() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		for line in [
+			log_line("INFO", "one"),
+			log_line("INFO", "two"),
+			log_line("WARN", "three"),
+			log_line("ERROR", "four"),
+			log_line("DEBUG", "five"),
+			log_line("TRACE", "six"),
+			"[2024-01-15T20:21:02.659471Z ERROR sn_networking] seven".to_string(),
+			"	 ➤ continuation line with no level, not even INFO in position".to_string(),
+		] {
+			metrics.count_log_level(&line);
+		}
+		assert_eq!(metrics.level_count(LogLevel::Info), 2);
+		assert_eq!(metrics.level_count(LogLevel::Warn), 1);
+		assert_eq!(metrics.level_count(LogLevel::Error), 2);
+		assert_eq!(metrics.level_count(LogLevel::Debug), 1);
+		assert_eq!(metrics.level_count(LogLevel::Trace), 1);
+		assert_eq!(metrics.level_count(LogLevel::Other), 1);
+	}
+
+	#[test]
+	fn errors_per_minute_uses_recent_buckets() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		let now = Utc::now();
+		metrics.update_timelines(&now);
+		for _ in 0..3 {
+			metrics.count_error(&now);
+		}
+		metrics.count_warning(&now);
+		assert_eq!(metrics.errors_per_minute(), 3);
+		assert_eq!(metrics.warnings_per_minute(), 1);
+	}
+
+	#[test]
+	fn accumulates_rewards() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Total payment of AttoTokens(340282366920938463463374607431768211000) received for record"),
+			log_line("INFO", "Total payment of AttoTokens(500) received for record"),
+			log_line("INFO", "Total payment of NanoTokens(2) received for record"),
+			log_line("INFO", "Total payment of AttoTokens() received for record"),
+			log_line("INFO", "Total payment of AttoTokens(abc) received for record"),
+		]);
+		assert_eq!(metrics.rewards, u128::MAX);
+
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Total payment of AttoTokens(500) received for record"),
+			log_line("INFO", "Total payment of NanoTokens(2) received for record"),
+		]);
+		assert_eq!(metrics.rewards, 2_000_000_500);
+		for bucket_set in metrics.rewards_timeline.bucket_sets().values() {
+			assert_eq!(bucket_set.buckets().iter().sum::<u64>(), 2_000_000_500); // In attos, however old the lines
+		}
+	}
+
+	#[test]
+	fn tracks_latest_store_cost() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		// Logged now, as timelines ignore lines older than their oldest bucket
+		let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
+		let now_line = |message: &str| format!(" INFO {} [sn/src/node/mod.rs:L211]: {}", now, message);
+		gather(&mut metrics, &[now_line("Starting up")]);
+		assert_eq!(metrics.store_cost, None);
+		assert!(metrics.store_cost_timeline.bucket_sets().values().all(|bs| bs.buckets().iter().all(|v| *v == 0)));
+
+		for (message, attos) in [
+			("Store cost is AttoTokens(5000)", 5000),
+			("Quoting store cost: NanoTokens(12) for record", 12_000_000_000),
+			("Cost is now 1,250 attos", 1250),
+			("store_cost 0.000000000000000025 ANT", 25),
+			("Store cost 2.5 nanos", 2_500_000_000),
+			("store cost: 99999999999999999999999", u64::MAX),
+		] {
+			gather(&mut metrics, &[now_line(message)]);
+			assert_eq!(metrics.store_cost, Some(attos), "{}", message);
+			for bucket_set in metrics.store_cost_timeline.bucket_sets().values() {
+				assert_eq!(bucket_set.buckets().last(), Some(&attos), "{}", message);
+			}
+		}
+
+		// The latest cost carries on into later lines
+		gather(&mut metrics, &[now_line("Getting chunk")]);
+		assert_eq!(metrics.store_cost, Some(u64::MAX));
+	}
+
+	#[test]
+	fn tracks_records_stored() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		// Logged now, as timelines ignore lines older than their oldest bucket
+		let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
+		let now_line = |message: &str| format!(" INFO {} [sn/src/node/mod.rs:L211]: {}", now, message);
+		let records_bucket = |metrics: &NodeMetrics| {
+			let bucket_sets = metrics.records_timeline.bucket_sets();
+			bucket_sets.values().next().and_then(|bs| bs.buckets().last().copied())
+		};
+
+		// Changes before the first report are left to it
+		gather(&mut metrics, &[now_line("Removed 2 records"), now_line("Successfully stored record 8a3f")]);
+		assert_eq!(metrics.records_stored, None);
+
+		gather(&mut metrics, &[now_line("Records stored: 120")]);
+		assert_eq!(metrics.records_stored, Some(120));
+		assert_eq!(records_bucket(&metrics), Some(120));
+
+		gather(&mut metrics, &[now_line("Removed 20 records"), now_line("Replicated record 8a3f"), now_line("replicated 4 records")]);
+		assert_eq!(metrics.records_stored, Some(105));
+		assert_eq!(records_bucket(&metrics), Some(105));
+
+		gather(&mut metrics, &[now_line("Pruned 500 records")]);
+		assert_eq!(metrics.records_stored, Some(0));
+
+		// The next report is trusted over the adjustments
+		gather(&mut metrics, &[now_line("record count = 98")]);
+		assert_eq!(metrics.records_stored, Some(98));
+		assert_eq!(records_bucket(&metrics), Some(98));
+	}
+
+	#[test]
+	fn classifies_replication_and_relocation() {
+		use ReplicationDirection::{Inbound, Outbound};
+		let inbound = Some(ChurnEvent::Replication(Some(Inbound)));
+		let outbound = Some(ChurnEvent::Replication(Some(Outbound)));
+		assert_eq!(classify_churn("Received replication list from peer 12D3KooWabc"), inbound);
+		assert_eq!(classify_churn("Fetching replicated record 8a3f"), inbound);
+		assert_eq!(classify_churn("Replicate request from peer 12D3KooWabc"), inbound);
+		assert_eq!(classify_churn("Sending replication keys to 5 peers"), outbound);
+		assert_eq!(classify_churn("Replicating 12 keys to peer 12D3KooWabc"), outbound);
+		assert_eq!(classify_churn("Failed to replicate record to peers"), outbound);
+		assert_eq!(classify_churn("Replicated record 8a3f"), Some(ChurnEvent::Replication(None)));
+		assert_eq!(classify_churn("Node relocated to section 10"), Some(ChurnEvent::Relocation));
+		assert_eq!(classify_churn("Relocation of peer 12D3KooWabc started"), Some(ChurnEvent::Relocation));
+		assert_eq!(classify_churn("[sn_networking::replication_fetcher] Stored record 8a3f"), None);
+		assert_eq!(classify_churn("Successfully stored record 8a3f"), None);
+	}
+
+	#[test]
+	fn counts_replications_and_relocations() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
+		let now_line = |message: &str| format!(" INFO {} [sn/src/node/mod.rs:L211]: {}", now, message);
+		gather(
+			&mut metrics,
+			&[
+				now_line("Records stored: 10"),
+				now_line("Received replication list from peer 12D3KooWabc"),
+				now_line("Sending replication keys to 5 peers"),
+				now_line("Replicating 3 keys to peer 12D3KooWdef"),
+				now_line("replicated 4 records"),
+				now_line("Node relocated to section 10"),
+				now_line("Successfully stored record 8a3f"),
+			],
+		);
+		assert_eq!((metrics.replications, metrics.replications_in, metrics.replications_out), (4, 1, 2));
+		assert_eq!(metrics.relocations, 1);
+		assert_eq!(metrics.records_stored, Some(14)); // Replicating records still adds to those stored
+		let latest = |timeline: &TimelineSet| timeline.bucket_sets().values().next().and_then(|bs| bs.buckets().last().copied());
+		assert_eq!(latest(&metrics.replications_timeline), Some(4));
+		assert_eq!(latest(&metrics.relocations_timeline), Some(1));
+
+		metrics.reset_counters(&Utc::now());
+		assert_eq!((metrics.replications, metrics.replications_in, metrics.replications_out, metrics.relocations), (0, 0, 0, 0));
+	}
+
+	#[test]
+	fn samples_latencies_from_lines_which_log_them() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
+		let now_line = |message: &str| format!(" INFO {} [sn/src/node/mod.rs:L211]: {}", now, message);
+		gather(
+			&mut metrics,
+			&[
+				now_line("Round trip time to peer: 4ms"),
+				now_line("Successfully stored record 8a3f"),
+				now_line("rtt=2ms"),
+				now_line("Query response time: 30 ms"),
+			],
+		);
+		assert_eq!(metrics.activity_puts, 1);
+		let stats = metrics.latencies.stats().unwrap();
+		assert_eq!((stats.min, stats.mean, stats.p95), (2_000, 12_000.0, 30_000));
+		let latest = |timeline: &TimelineSet| timeline.bucket_sets().values().next().and_then(|bs| bs.buckets().last().copied());
+		assert_eq!(latest(&metrics.latency_timeline), Some(12)); // The mean in milliseconds
+		assert_eq!(metrics.last_anomaly, None);
+
+		metrics.reset_counters(&Utc::now());
+		assert!(metrics.latencies.is_empty());
+	}
+
+	#[test]
+	fn parses_token_amounts() {
+		assert_eq!(parse_token_amount("1_000", 1), Some(1000));
+		assert_eq!(parse_token_amount("1.5", 1_000_000_000), Some(1_500_000_000));
+		assert_eq!(parse_token_amount("1.5", 1), Some(1));
+		assert_eq!(parse_token_amount("abc", 1), None);
+	}
+
+	#[test]
+	fn reset_counters_restores_initial_values() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Retrieved record from the network"),
+			log_line("INFO", "Successfully stored record"),
+			log_line("ERROR", "Failed to do something"),
+			log_line("WARN", "About to fail"),
+			log_line("INFO", "Total payment of AttoTokens(500) received for record"),
+		]);
+		metrics.update_timelines(&Utc::now());
+		metrics.reset_counters(&Utc::now());
+
+		let initial = NodeMetrics::new(&test_opt());
+		assert_eq!(metrics.activity_gets, initial.activity_gets);
+		assert_eq!(metrics.activity_puts, initial.activity_puts);
+		assert_eq!(metrics.activity_errors, initial.activity_errors);
+		assert_eq!(metrics.activity_warnings, initial.activity_warnings);
+		assert_eq!(metrics.rewards, initial.rewards);
+		assert_eq!(metrics.level_count(LogLevel::Error), 0);
+		assert_eq!(metrics.errors_per_minute(), 0);
+		for timeline in metrics.timelines() {
+			for bs in timeline.bucket_sets().values() {
+				assert!(bs.buckets.iter().all(|b| *b == 0), "{} not cleared", timeline.get_name());
+				assert_eq!(bs.buckets.len(), bs.max_buckets);
+			}
+		}
+		assert!(metrics.counters_reset.is_some());
+	}
+
+	#[test]
+	fn classifies_staleness() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		let now = Utc::now();
+		let stale_after = Duration::minutes(5);
+		assert_eq!(metrics.time_since_activity(&now), None);
+		assert!(!metrics.is_stale(&now, stale_after));
+
+		metrics.last_activity = Some(now - Duration::seconds(30));
+		assert_eq!(metrics.time_since_activity(&now), Some(Duration::seconds(30)));
+		assert!(!metrics.is_stale(&now, stale_after));
+
+		metrics.last_activity = Some(now - Duration::minutes(5));
+		assert!(!metrics.is_stale(&now, stale_after));
+
+		metrics.last_activity = Some(now - Duration::minutes(5) - Duration::seconds(1));
+		assert!(metrics.is_stale(&now, stale_after));
+
+		// Node clock ahead of ours
+		metrics.last_activity = Some(now + Duration::minutes(10));
+		assert_eq!(metrics.time_since_activity(&now), Some(Duration::zero()));
+		assert!(!metrics.is_stale(&now, stale_after));
+	}
+
+	#[test]
+	fn tracks_latest_log_entry_time() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			" INFO 2022-01-15T20:21:05.000000Z [sn/src/node/mod.rs:L211]: later".to_string(),
+			" INFO 2022-01-15T20:21:02.000000Z [sn/src/node/mod.rs:L211]: earlier".to_string(),
+		]);
+		let expected = DateTime::parse_from_rfc3339("2022-01-15T20:21:05Z").unwrap();
+		assert_eq!(metrics.last_activity, Some(expected.with_timezone(&Utc)));
+	}
+
+	#[test]
+	fn filters_nodes_by_substring() {
+		assert!(NodeFilter::new("").matches("/home/user/node-1/safenode.log"));
+		assert!(NodeFilter::new("node-1/").matches("/home/user/node-1/safenode.log"));
+		assert!(!NodeFilter::new("node-2/").matches("/home/user/node-1/safenode.log"));
+		assert!(!NodeFilter::new("Node-1").matches("/home/user/node-1/safenode.log"));
+	}
+
+	#[test]
+	fn filters_nodes_by_regex() {
+		let filter = NodeFilter::new("node-[12]/");
+		assert!(filter.matches("/nodes/node-1/safenode.log"));
+		assert!(filter.matches("/nodes/node-2/safenode.log"));
+		assert!(!filter.matches("/nodes/node-3/safenode.log"));
+		assert!(NodeFilter::new("^/nodes/.*\\.log$").matches("/nodes/node-3/safenode.log"));
+	}
+
+	#[test]
+	fn filters_nodes_by_fuzzy_name() {
+		let mut monitor = LogMonitor::new(&test_opt(), "/nodes/node-1/safenode.log".to_string(), 10);
+		monitor.name = String::from("alpha-one");
+		assert!(!NodeFilter::new("a1").matches_node(&monitor));
+		assert!(NodeFilter::new("aone").matches_node(&monitor));
+		assert!(!NodeFilter::new("onea").matches_node(&monitor));
+		assert_eq!(NodeFilter::new("aone").fuzzy_match(&monitor.name).unwrap().positions, vec![0, 6, 7, 8]);
+		assert_eq!(NodeFilter::new("").fuzzy_match(&monitor.name).unwrap().score, 0);
+	}
+
+	#[test]
+	fn invalid_regex_filter_is_literal() {
+		let filter = NodeFilter::new("node[1");
+		assert!(filter.matches("/nodes/node[1/safenode.log"));
+		assert!(!filter.matches("/nodes/node1/safenode.log"));
+	}
+
+	// Hand each character typed to App::handle_key(), saying whether any quit
+	fn type_keys(app: &mut App, typed: &str) -> bool {
+		let mut quit = false;
+		for c in typed.chars() {
+			quit |= app.handle_key(BindingKey::Char(c)).unwrap();
+		}
+		quit
+	}
+
+	#[test]
+	fn q_typed_into_the_filter_does_not_quit() {
+		let mut app = test_app(&["/nodes/quic-1/safenode.log", "/nodes/tcp-1/safenode.log"]);
+		assert!(!type_keys(&mut app, "/quic"));
+		assert_eq!(app.dash_state.node_filter.get_text(), "quic");
+		app.handle_key(BindingKey::Enter).unwrap();
+		assert!(type_keys(&mut app, "q"));
+	}
+
+	#[test]
+	fn q_typed_into_a_node_jump_does_not_quit() {
+		let mut app = test_app(&["/nodes/tcp-1/safenode.log", "/nodes/quic-1/safenode.log"]);
+		assert!(!type_keys(&mut app, "'quic"));
+		assert_eq!(app.dash_state.node_jump.as_ref().map(|jump| jump.typed()), Some("quic"));
+		app.handle_key(BindingKey::Enter).unwrap();
+		assert!(type_keys(&mut app, "q"));
+	}
+
+	#[test]
+	fn q_while_help_is_shown_does_not_quit() {
+		let mut app = test_app(&["/nodes/node-1/safenode.log"]);
+		assert!(!type_keys(&mut app, "?q"));
+		assert!(app.dash_state.help_visible);
+		assert!(!type_keys(&mut app, "?"));
+		assert!(!app.dash_state.help_visible);
+		assert!(type_keys(&mut app, "q"));
+	}
+
+	#[test]
+	fn parses_startup_banner() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		assert_eq!(metrics.running_version, None);
+		assert_eq!(metrics.peer_id, None);
+
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", "Self PeerId is 12D3KooWHqCVbBgq9jrNJ8d77nGJQABPzKEiLENxH4oq7qCbXfDy"),
+			log_line("INFO", "Successfully stored record 8a3f"),
+		]);
+		assert_eq!(metrics.running_version, Some("0.110.0".to_string()));
+		assert_eq!(metrics.peer_id, Some("12D3KooWHqCVbBgq9jrNJ8d77nGJQABPzKEiLENxH4oq7qCbXfDy".to_string()));
+		assert!(metrics.node_started.is_some());
+
+		// A later banner, e.g. after an upgrade and log rotation, replaces the values
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.111.2-alpha.0"),
+			log_line("INFO", "PeerId is 12D3KooWAbc"),
+		]);
+		assert_eq!(metrics.running_version, Some("0.111.2-alpha.0".to_string()));
+		assert_eq!(metrics.peer_id, Some("12D3KooWAbc".to_string()));
+	}
+
+	#[test]
+	fn parses_listen_addresses() {
+		let address = |line: &str| parse_listen_address(line).map(|address| address.to_string());
+		assert_eq!(
+			address(r#"Local node is listening ListenerId(1) on "/ip4/10.0.0.5/udp/12000/quic-v1/p2p/12D3KooWAbc""#),
+			Some(String::from("10.0.0.5:12000"))
+		);
+		assert_eq!(
+			address("NewListenAddr { listener_id: ListenerId(2), address: /ip6/2001:db8::5/tcp/12000 }"),
+			Some(String::from("[2001:db8::5]:12000"))
+		);
+		assert_eq!(address("Node listening for connections on 192.168.1.20:12000"), Some(String::from("192.168.1.20:12000")));
+		assert_eq!(address("Listening on [::1]:12000"), Some(String::from("[::1]:12000")));
+		assert_eq!(address("Listening on 300.1.1.1:12000"), None);
+		assert_eq!(address("Connected to 10.0.0.7:12000"), None);
+	}
+
+	#[test]
+	fn tracks_the_listen_address() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		assert_eq!(metrics.listen_address, None);
+
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", r#"Local node is listening ListenerId(1) on "/ip4/10.0.0.5/udp/12000/quic-v1""#),
+			log_line("INFO", r#"Local node is listening ListenerId(1) on "/ip4/127.0.0.1/udp/12000/quic-v1""#),
+		]);
+		assert_eq!(metrics.listen_address, "10.0.0.5:12000".parse().ok());
+
+		// A restart may bind elsewhere
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", r#"Local node is listening ListenerId(1) on "/ip4/127.0.0.1/udp/12001/quic-v1""#),
+			log_line("INFO", r#"Local node is listening ListenerId(1) on "/ip6/2001:db8::5/udp/12001/quic-v1""#),
+		]);
+		assert_eq!(metrics.listen_address, "[2001:db8::5]:12001".parse().ok());
+	}
+
+	#[test]
+	fn counts_the_current_run_and_lifetime() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Successfully stored record 91bc"),
+			log_line("ERROR", "Failed to connect"),
+			log_line("INFO", "Total payment of AttoTokens(500) received for record"),
+		]);
+		assert_eq!(metrics.current_run(), metrics.lifetime());
+
+		// A restart starts a new run but the lifetime counters go on
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.111.0"),
+			log_line("INFO", "Successfully stored record aa01"),
+			log_line("WARN", "About to fail"),
+		]);
+		let lifetime = metrics.lifetime();
+		assert_eq!((lifetime.puts, lifetime.errors, lifetime.warnings, lifetime.rewards), (3, 1, 1, 500));
+		let current_run = metrics.current_run();
+		assert_eq!((current_run.puts, current_run.errors, current_run.warnings, current_run.rewards), (1, 0, 1, 0));
+
+		// Resetting the counters resets both
+		metrics.reset_counters(&Utc::now());
+		gather(&mut metrics, &[log_line("INFO", "Successfully stored record bb02")]);
+		assert_eq!(metrics.lifetime().puts, 1);
+		assert_eq!(metrics.current_run().puts, 1);
+	}
+
+	#[test]
+	fn counts_from_a_marker_across_a_restart() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Successfully stored record 91bc"),
+			log_line("ERROR", "Failed to connect"),
+		]);
+		assert_eq!(metrics.since_marker(), None);
+		metrics.drop_marker(&Utc::now());
+		assert_eq!(metrics.since_marker(), Some(RunCounters::default()));
+
+		gather(&mut metrics, &[
+			log_line("INFO", "Successfully stored record aa01"),
+			log_line("INFO", "Retrieved record aa01 from local store"),
+		]);
+		let since = metrics.since_marker().unwrap();
+		assert_eq!((since.puts, since.gets, since.errors), (1, 1, 0));
+
+		// The restart starts a new run from zero, but the marker counts on from the lifetime counters
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.111.0"),
+			log_line("INFO", "Successfully stored record bb02"),
+			log_line("ERROR", "Failed to connect"),
+		]);
+		assert_eq!(metrics.current_run().puts, 1);
+		let since = metrics.since_marker().unwrap();
+		assert_eq!((since.puts, since.gets, since.errors), (2, 1, 1));
+		assert_eq!(metrics.lifetime().puts, 4);
+	}
+
+	#[test]
+	fn counts_from_a_marker_across_a_reset() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Successfully stored record 91bc"),
+		]);
+		metrics.drop_marker(&Utc::now());
+		gather(&mut metrics, &[log_line("INFO", "Successfully stored record aa01")]);
+		metrics.reset_counters(&Utc::now());
+		gather(&mut metrics, &[
+			log_line("INFO", "Successfully stored record bb02"),
+			log_line("INFO", "Successfully stored record cc03"),
+			log_line("INFO", "Successfully stored record dd04"),
+		]);
+		// Counted since the reset, which came after the marker, rather than less the 2 before it
+		assert_eq!(metrics.since_marker().map(|since| since.puts), Some(3));
+		metrics.marker = None;
+		assert_eq!(metrics.since_marker(), None);
+	}
+
+	#[test]
+	fn counts_restarts() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", "Successfully stored record 8a3f"),
+		]);
+		assert_eq!(metrics.restarts, 0);
+
+		gather(&mut metrics, &[
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("ERROR", "Failed to connect"),
+			log_line("INFO", "Running safenode v0.110.0"),
+			log_line("INFO", "Running safenode v0.111.0"),
+		]);
+		assert_eq!(metrics.restarts, 3);
+		assert_eq!(metrics.running_version, Some("0.111.0".to_string()));
+	}
+
+	#[test]
+	fn times_uptime_from_the_latest_startup_banner() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		let started = Utc.ymd(2022, 1, 15).and_hms_micro(20, 21, 2, 659471);
+		assert_eq!(metrics.uptime(&started), None);
+
+		gather(&mut metrics, &[log_line("INFO", "Running safenode v0.110.0")]);
+		assert_eq!(metrics.node_started, Some(started));
+		assert_eq!(metrics.uptime(&(started + Duration::hours(76))), Some(Duration::hours(76)));
+
+		// A restart starts the uptime again
+		let restart = " INFO 2022-01-18T09:00:00.000000Z [sn/src/node/mod.rs:L211]: Running safenode v0.110.0";
+		gather(&mut metrics, &[restart.to_string()]);
+		let restarted = Utc.ymd(2022, 1, 18).and_hms(9, 0, 0);
+		assert_eq!(metrics.node_started, Some(restarted));
+		assert_eq!(metrics.uptime(&(restarted + Duration::minutes(5))), Some(Duration::minutes(5)));
+	}
+
+	#[test]
+	fn ignores_lines_without_startup_banner() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("INFO", "Running the replication loop"),
+			log_line("INFO", "Connected to peer 12D3KooWAbc"),
+		]);
+		assert_eq!(metrics.running_version, None);
+		assert_eq!(metrics.peer_id, None);
+	}
+
+	#[test]
+	fn tracks_connected_peers() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("DEBUG", "ConnectionEstablished { peer_id: 12D3KooWAbc, endpoint: Dialer }"),
+			log_line("DEBUG", "ConnectionEstablished { peer_id: 12D3KooWDef, endpoint: Listener }"),
+			log_line("DEBUG", "ConnectionEstablished { peer_id: 12D3KooWGhi, endpoint: Dialer }"),
+			log_line("DEBUG", "ConnectionClosed { peer_id: 12D3KooWAbc, cause: None }"),
+		]);
+		assert_eq!(metrics.peers, 2);
+
+		// An absolute count replaces the running count
+		gather(&mut metrics, &[
+			log_line("INFO", "New peer added to routing table: PeerId(\"12D3KooWJkl\"), now we have #25 connected peers"),
+			log_line("DEBUG", "ConnectionEstablished { peer_id: 12D3KooWMno, endpoint: Dialer }"),
+		]);
+		assert_eq!(metrics.peers, 26);
+
+		gather(&mut metrics, &[log_line("INFO", "Routing table size: 3")]);
+		assert_eq!(metrics.peers, 3);
+
+		// Disconnects never take the count below zero
+		let disconnects = vec![log_line("DEBUG", "ConnectionClosed { peer_id: 12D3KooWAbc, cause: None }"); 5];
+		gather(&mut metrics, &disconnects);
+		assert_eq!(metrics.peers, 0);
+	}
+
+	#[test]
+	fn tracks_network_disconnections() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		assert!(metrics.connected);
+		assert_eq!(metrics.disconnects, 0);
+
+		gather(&mut metrics, &[log_line("WARN", "Disconnected from the network")]);
+		assert!(!metrics.connected);
+		assert_eq!(metrics.disconnects, 1);
+
+		// Repeats while disconnected are the same episode
+		gather(&mut metrics, &[log_line("WARN", "Lost connection to the network, retrying")]);
+		assert!(!metrics.connected);
+		assert_eq!(metrics.disconnects, 1);
+
+		gather(&mut metrics, &[log_line("INFO", "Reconnected to the network")]);
+		assert!(metrics.connected);
+		assert_eq!(metrics.disconnects, 1);
+
+		gather(&mut metrics, &[
+			log_line("INFO", "Connected to network"),
+			log_line("WARN", "disconnected from network"),
+			log_line("INFO", "reconnected to the network"),
+		]);
+		assert!(metrics.connected);
+		assert_eq!(metrics.disconnects, 2);
+
+		// Peer connections are not the node's own
+		gather(&mut metrics, &[log_line("DEBUG", "ConnectionClosed { peer_id: 12D3KooWAbc, cause: None }")]);
+		assert!(metrics.connected);
+	}
+
+
+	fn write_lines(path: &Path, lines: &[String], append: bool) {
+		let mut file = std::fs::OpenOptions::new()
+			.create(true)
+			.write(true)
+			.append(append)
+			.truncate(!append)
+			.open(path)
+			.unwrap();
+		for line in lines {
+			writeln!(file, "{}", line).unwrap();
+		}
+	}
+
+	#[test]
+	fn follows_truncated_logfile() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		let stored = log_line("INFO", "Successfully stored record 8a3f");
+		write_lines(&path, &[stored.clone(), stored.clone(), stored.clone()], false);
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 3);
+
+		// Truncate, then append fewer bytes than had been read
+		write_lines(&path, &[], false);
+		write_lines(&path, &[log_line("INFO", "Retrieved record 8a3f")], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 3);
+		assert_eq!(monitor.metrics.activity_gets, 1);
+
+		write_lines(&path, &[stored], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 4);
+		assert_eq!(monitor.metrics.activity_gets, 1);
+	}
+
+	#[test]
+	fn chooses_where_to_start_reading() {
+		assert_eq!(start_offset(ReadFrom::Start, 1000, None), 0);
+		assert_eq!(start_offset(ReadFrom::Start, 1000, Some(600)), 600);
+		assert_eq!(start_offset(ReadFrom::Start, 1000, Some(1200)), 0); // Truncated since saved
+		assert_eq!(start_offset(ReadFrom::End, 1000, None), 1000);
+		assert_eq!(start_offset(ReadFrom::End, 1000, Some(600)), 1000);
+		assert_eq!("end".parse::<ReadFrom>(), Ok(ReadFrom::End));
+		assert!("middle".parse::<ReadFrom>().is_err());
+	}
+
+	#[test]
+	fn replays_lines_into_buckets_for_their_time() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		let newest_bucket_time = monitor.metrics.puts_timeline.get_bucket_set("1 second columns").unwrap().bucket_time.unwrap();
+		let line_before = |millis: i64| {
+			let time = (newest_bucket_time - Duration::milliseconds(millis)).format("%Y-%m-%dT%H:%M:%S%.6fZ");
+			format!(" INFO {} [sn/src/node/mod.rs:L211]: Successfully stored record 8a3f", time)
+		};
+		write_lines(&path, &[line_before(9500), line_before(8500), line_before(8500), line_before(5500)], false);
+		monitor.load_logfile_from(&mut dash_state, start_offset(ReadFrom::Start, 0, None)).unwrap();
+
+		// Each line is counted in the bucket of its own time rather than the newest
+		let buckets = monitor.metrics.puts_timeline.get_bucket_set("1 second columns").unwrap().buckets();
+		let newest = buckets.len() - 1;
+		assert_eq!(&buckets[newest - 9..], &[1, 2, 0, 0, 1, 0, 0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn parses_log_times() {
+		let time = Utc.ymd(2022, 1, 15).and_hms_micro(20, 21, 2, 659_471);
+		assert_eq!(parse_log_time("2022-01-15T20:21:02.659471Z"), Some(time));
+		assert_eq!(parse_log_time("2022-01-15T21:21:02.659471+01:00"), Some(time));
+		assert_eq!(parse_log_time("2022-01-15T15:21:02.659471-0500"), Some(time));
+		assert_eq!(parse_log_time("2022-01-15T20:21:02.659471"), Some(time));
+		assert_eq!(parse_log_time("2022-01-15T20:21:02Z"), Some(Utc.ymd(2022, 1, 15).and_hms(20, 21, 2)));
+		assert_eq!(
+			parse_log_time("2022-01-15T20:21:02.659471123Z"),
+			Some(Utc.ymd(2022, 1, 15).and_hms_nano(20, 21, 2, 659_471_123))
+		);
+		assert_eq!(parse_log_time("2022-01-15 at noon"), None);
+
+		let metadata = LogEntry::decode_metadata("[2022-01-15T21:21:02.659471+01:00 INFO sn_node::node] Node started", LogProfile::auto()).unwrap();
+		assert_eq!(metadata.time, time);
+		assert_eq!(metadata.category, "INFO");
+		assert_eq!(metadata.source, "sn_node::node");
+		assert_eq!(metadata.message, "Node started");
+	}
+
+	#[test]
+	fn buckets_lines_by_their_logged_time() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		let newest_bucket_time = metrics.puts_timeline.get_bucket_set("1 second columns").unwrap().bucket_time.unwrap();
+		let line_before = |millis: i64| {
+			let time = (newest_bucket_time - Duration::milliseconds(millis)).to_rfc3339();
+			format!("[{} INFO sn_node::put_validation] Successfully stored record 8a3f", time)
+		};
+		gather(&mut metrics, &[
+			line_before(3500),
+			String::from("  continued, so logged at the same time as the line before"),
+			line_before(1500),
+		]);
+		let buckets = metrics.puts_timeline.get_bucket_set("1 second columns").unwrap().buckets();
+		let newest = buckets.len() - 1;
+		assert_eq!(&buckets[newest - 3..], &[1, 0, 1, 0]);
+
+		// Lines without a time count as logged now
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[String::from("Successfully stored record 8a3f")]);
+		assert_eq!(metrics.activity_puts, 1);
+		assert!(metrics.last_activity.is_some());
+	}
+
+	#[test]
+	fn notes_logfiles_which_cant_be_read_until_they_can() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		std::fs::create_dir(&path).unwrap(); // Exists, but can't be read as a logfile
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+		assert!(monitor.read_error.is_some());
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert!(monitor.read_error.is_some());
+
+		std::fs::remove_dir(&path).unwrap();
+		write_lines(&path, &[log_line("INFO", "Successfully stored record 8a3f")], false);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.read_error, None);
+		assert_eq!(monitor.metrics.activity_puts, 1);
+	}
+
+	#[test]
+	fn waits_for_logfile_to_be_created() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("node-1").join("safenode.log");
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert!(monitor.waiting_for_file);
+		assert!(monitor.tail.is_empty());
+
+		std::fs::create_dir(path.parent().unwrap()).unwrap();
+		write_lines(&path, &[log_line("INFO", "Successfully stored record 8a3f")], false);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert!(!monitor.waiting_for_file);
+		assert_eq!(monitor.metrics.activity_puts, 1);
+
+		write_lines(&path, &[log_line("INFO", "Retrieved record 8a3f")], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_gets, 1);
+		assert_eq!(monitor.tail.len(), 2);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn follows_rotated_logfile() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		let stored = log_line("INFO", "Successfully stored record 8a3f");
+		write_lines(&path, std::slice::from_ref(&stored), false);
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+
+		// The replacement is already longer than the part of the original which was read
+		std::fs::rename(&path, dir.path().join("safenode.log.1")).unwrap();
+		write_lines(&path, &[stored.clone(), stored.clone(), stored], false);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 4);
+	}
+
+	#[test]
+	fn leaves_partial_line_until_complete() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		std::fs::write(&path, log_line("INFO", "Successfully stored")).unwrap();
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 0);
+		assert_eq!(monitor.logfile_offset, 0);
+
+		write_lines(&path, &[" record 8a3f".to_string()], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+	}
+
+	#[test]
+	fn parses_a_line_split_across_reads_once() {
+		use std::io::Write;
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		let line = log_line("INFO", "Successfully stored record 8a3f");
+		let (first, rest) = line.split_at(line.len() - 12);
+		write_lines(&path, std::slice::from_ref(&line), false);
+		let append = |text: &str| {
+			let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+			file.write_all(text.as_bytes()).unwrap();
+		};
+		append(first);
+
+		// Starting from the end while the line is being written, it is read whole once complete
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.skip_existing_lines();
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert!(monitor.content.items.is_empty());
+		append(rest);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.content.items.len(), 0); // Still no newline
+		append("\n");
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.content.items, vec![line.clone()]);
+		assert_eq!(monitor.metrics.activity_puts, 1);
+
+		// Split across reads after loading, as from a saved offset
+		append(first);
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		let len = std::fs::metadata(&path).unwrap().len();
+		monitor.load_logfile_from(&mut dash_state, len - 5).unwrap();
+		assert!(monitor.content.items.is_empty());
+		append(&format!("{}\n", rest));
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.content.items, vec![line]);
+		assert_eq!(monitor.metrics.activity_puts, 1);
+		assert_eq!(monitor.logfile_offset, std::fs::metadata(&path).unwrap().len());
+
+		assert_eq!(line_boundary(&path.to_string_lossy(), 0), 0);
+		assert_eq!(line_boundary(&path.to_string_lossy(), 1_000_000), 1_000_000);
+	}
+
+	#[test]
+	fn applies_lines_written_while_paused_on_resume() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		write_lines(&path, &[], false);
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+
+		dash_state.paused = true;
+		write_lines(&path, &[
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Retrieved record 8a3f"),
+		], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 0);
+		assert_eq!(monitor.metrics.activity_gets, 0);
+		assert!(monitor.content.items.is_empty());
+
+		dash_state.paused = false;
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+		assert_eq!(monitor.metrics.activity_gets, 1);
+		assert_eq!(monitor.content.items.len(), 2);
+	}
+
+	#[test]
+	fn ignores_lines_while_disabled() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		write_lines(&path, &[log_line("INFO", "Successfully stored record 8a3f")], false);
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+
+		monitor.set_enabled(false);
+		write_lines(&path, &[log_line("INFO", "Successfully stored record 91bc")], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+
+		// Lines logged while disabled stay ignored, later ones are read
+		monitor.set_enabled(true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+		write_lines(&path, &[log_line("INFO", "Successfully stored record aa01")], true);
+		monitor.follow_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 2);
+		assert_eq!(monitor.content.items.len(), 2);
+	}
+
+	#[test]
+	fn accumulates_bytes_transferred() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		gather(&mut metrics, &[
+			log_line("DEBUG", "Received 2048 bytes from 12D3KooWAbc"),
+			log_line("DEBUG", "Sent 512 bytes to 12D3KooWAbc"),
+			log_line("DEBUG", "Inbound request of 1000 bytes"),
+			log_line("DEBUG", "outbound response: 24 bytes"),
+			log_line("DEBUG", "Received a request from 12D3KooWAbc"),
+			log_line("INFO", "Successfully stored record 8a3f"),
+		]);
+		assert_eq!(metrics.bytes_in, 3048);
+		assert_eq!(metrics.bytes_out, 536);
+		assert_eq!(metrics.activity_puts, 1);
+
+		metrics.reset_counters(&Utc::now());
+		assert_eq!(metrics.bytes_in, 0);
+		assert_eq!(metrics.bytes_out, 0);
+	}
+
+	#[test]
+	fn gathers_metrics_from_stdin() {
+		let input = [
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Retrieved record 8a3f"),
+			log_line("INFO", "Successfully stored record 91c0"),
+		]
+		.join("\n");
+		let mut app = test_app(&[STDIN_LOGFILE]);
+		app.stdin_reader = Some(StdinReader::spawn(std::io::Cursor::new(input)));
+
+		let timeout = std::time::Instant::now() + std::time::Duration::from_secs(5);
+		while !app.monitors[STDIN_LOGFILE].input_ended && std::time::Instant::now() < timeout {
+			app.follow_logfiles().unwrap();
+			std::thread::sleep(std::time::Duration::from_millis(1));
+		}
+		let monitor = &app.monitors[STDIN_LOGFILE];
+		assert!(monitor.input_ended);
+		assert_eq!(monitor.metrics.activity_puts, 2);
+		assert_eq!(monitor.metrics.activity_gets, 1);
+		assert_eq!(monitor.content.items.len(), 3);
+	}
+
+	#[test]
+	fn gathers_metrics_from_a_replay() {
+		let lines = vec![
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Retrieved record 8a3f"),
+		];
+		let mut app = test_app(&["node-1.log"]);
+		app.replay = Some(Replay::from_lines(lines.into_iter(), 60.0, LogProfile::auto()));
+		app.monitors.get_mut("node-1.log").unwrap().is_replay = true;
+
+		app.dash_state.paused = true;
+		app.follow_logfiles().unwrap();
+		assert!(app.monitors["node-1.log"].content.items.is_empty());
+
+		app.dash_state.paused = false;
+		app.follow_logfiles().unwrap();
+		let monitor = &app.monitors["node-1.log"];
+		assert!(monitor.input_ended);
+		assert_eq!(monitor.metrics.activity_puts, 1);
+		assert_eq!(monitor.metrics.activity_gets, 1);
+		let replay = app.replay.as_ref().unwrap();
+		assert_eq!(replay.clock(), Some(Utc.ymd(2022, 1, 15).and_hms_micro(20, 21, 2, 659_471)));
+	}
+
+	#[test]
+	fn prefers_capacity_override_then_logged_then_default() {
+		let opt = Opt::from_iter(vec!["vdash", "--node-capacity", "4KB"]);
+		let mut metrics = NodeMetrics::new(&opt);
+		metrics.used_space = 1000;
+		assert_eq!(metrics.capacity(), Some(4000));
+		assert_eq!(metrics.storage_ratio(), Some(0.25));
+
+		gather(&mut metrics, &[log_line("INFO", "Max capacity: 2000")]);
+		assert_eq!(metrics.capacity(), Some(2000));
+		assert_eq!(metrics.storage_ratio(), Some(0.5));
+
+		metrics.capacity_override = Some(10_000);
+		assert_eq!(metrics.storage_ratio(), Some(0.1));
+
+		let mut unknown = NodeMetrics::new(&test_opt());
+		unknown.used_space = 1000;
+		assert_eq!(unknown.capacity(), None);
+		assert_eq!(unknown.storage_ratio(), None);
+	}
+
+	#[test]
+	fn keeps_tail_of_every_line_read() {
+		let opt = Opt::from_iter(vec!["vdash", "--tail-lines", "2"]);
+		let mut monitor = LogMonitor::new(&opt, "node-1/safenode.log".to_string(), opt.lines_max);
+		monitor.process_line(&log_line("INFO", "Successfully stored record 8a3f"), &Utc::now()).unwrap();
+		monitor.process_line("not a log line", &Utc::now()).unwrap();
+		monitor.process_line(&log_line("WARN", "Disconnected from the network"), &Utc::now()).unwrap();
+		let tail: Vec<&String> = monitor.tail.iter().collect();
+		assert_eq!(tail, vec!["not a log line", &log_line("WARN", "Disconnected from the network")]);
+	}
+
+	#[test]
+	fn keeps_within_its_caps_however_many_lines_are_read() {
+		let opt = Opt::from_iter(vec!["vdash", "--lines-max", "5", "--tail-lines", "4", "--history-max", "3"]);
+		let mut monitor = LogMonitor::new(&opt, "node-1/safenode.log".to_string(), opt.lines_max);
+		let activity = "Running as Node: SendToSection [ msg: MsgEnvelope { message: QueryResponse { response: QueryResponse::GetChunk(Ok(..)), id: 1 } ]";
+		for i in 0..1000 {
+			monitor.process_line(&log_line("INFO", &format!("Successfully stored record {}", i)), &Utc::now()).unwrap();
+			monitor.process_line(&log_line("INFO", activity), &Utc::now()).unwrap();
+			assert!(monitor.content.items.len() <= 5);
+			assert!(monitor.tail.len() <= 4);
+			assert!(monitor.metrics.log_history.len() <= 3);
+			assert!(monitor.metrics.activity_history.len() <= 3);
+		}
+		assert_eq!(monitor.metrics.log_history.len(), 3);
+		assert_eq!(monitor.metrics.activity_history.len(), 3);
+		assert!(monitor.metrics.log_history.back().unwrap().logstring.contains("QueryResponse::"));
+
+		let opt = Opt::from_iter(vec!["vdash", "--history-max", "0"]);
+		let mut metrics = NodeMetrics::new(&opt);
+		gather(&mut metrics, &[log_line("INFO", "Successfully stored record 8a3f")]);
+		assert!(metrics.log_history.is_empty());
+	}
+
+	#[test]
+	fn leaves_metrics_served_by_the_node_to_its_endpoint() {
+		let mut metrics = NodeMetrics::new(&test_opt());
+		metrics.metrics_from_endpoint = true;
+		gather(&mut metrics, &[
+			log_line("INFO", "Successfully stored record 8a3f"),
+			log_line("INFO", "Records stored: 1234"),
+			log_line("ERROR", "Failed to do something"),
+		]);
+		assert_eq!((metrics.activity_puts, metrics.records_stored), (0, None));
+		assert_eq!(metrics.activity_errors, 1);
+		assert_eq!(metrics.last_anomaly, None);
+	}
+
+	#[test]
+	fn shares_warnings_of_every_node() {
+		let opt = test_opt();
+		let recent_warnings = RecentWarnings::shared(10);
+		let mut monitors: Vec<LogMonitor> = ["node-1/safenode.log", "node-2/safenode.log"]
+			.iter()
+			.map(|logfile| {
+				let mut monitor = LogMonitor::new(&opt, logfile.to_string(), opt.lines_max);
+				monitor.name = default_node_name(&logfile.replace("/safenode.log", ""));
+				monitor.recent_warnings = Some(recent_warnings.clone());
+				monitor
+			})
+			.collect();
+		monitors[0].process_line(&log_line("WARN", "Disconnected from the network"), &Utc::now()).unwrap();
+		monitors[1].process_line(&log_line("INFO", "Successfully stored record 8a3f"), &Utc::now()).unwrap();
+		monitors[1].process_line(&log_line("ERROR", "Failed to store record"), &Utc::now()).unwrap();
+
+		let recent_warnings = recent_warnings.lock().unwrap();
+		let described: Vec<String> = recent_warnings.iter().map(|entry| entry.describe()).collect();
+		assert_eq!(described, vec![
+			format!("20:21:02 node-1: {}", log_line("WARN", "Disconnected from the network").trim()),
+			format!("20:21:02 node-2: {}", log_line("ERROR", "Failed to store record").trim()),
+		]);
+	}
+
+	#[test]
+	fn spotlights_lines_with_keywords_from_every_node() {
+		let opt = test_opt();
+		let keywords = spotlight_keywords_from_toml("[[keyword]]\ntext = \"slashed\"\n").unwrap();
+		let spotlight = Spotlight::shared(keywords, 10);
+		let mut monitors: Vec<LogMonitor> = ["node-1/safenode.log", "node-2/safenode.log"]
+			.iter()
+			.map(|logfile| {
+				let mut monitor = LogMonitor::new(&opt, logfile.to_string(), opt.lines_max);
+				monitor.name = default_node_name(&logfile.replace("/safenode.log", ""));
+				monitor.spotlight = Some(spotlight.clone());
+				monitor
+			})
+			.collect();
+		monitors[0].process_line(&log_line("INFO", "Successfully stored record 8a3f"), &Utc::now()).unwrap();
+		monitors[1].process_line(&log_line("INFO", "Peer Slashed for bad records"), &Utc::now()).unwrap();
+
+		let spotlight = spotlight.lock().unwrap();
+		let nodes: Vec<&str> = spotlight.iter().map(|entry| entry.node.as_str()).collect();
+		assert_eq!(nodes, vec!["node-2"]);
+		assert_eq!(monitors[1].metrics.activity_puts, 0); // Still parsed as usual
+		assert_eq!(monitors[0].metrics.activity_puts, 1);
+	}
+
+	#[test]
+	fn truncates_long_lines() {
+		assert_eq!(truncate_line("short", 5), "short");
+		assert_eq!(truncate_line("longer", 5), "long…");
+		assert_eq!(truncate_line("ééééé€", 5), "éééé…");
+		assert_eq!(truncate_line("longer", 0), "longer");
+
+		let opt = Opt::from_iter(vec!["vdash", "--max-line-len", "100"]);
+		let mut monitor = LogMonitor::new(&opt, "node-1/safenode.log".to_string(), opt.lines_max);
+		let line = log_line("INFO", &format!("Successfully stored record 8a3f {}", "x".repeat(10_000)));
+		monitor.process_line(&line, &Utc::now()).unwrap();
+		let kept = monitor.tail.iter().next().unwrap();
+		assert_eq!(kept.chars().count(), 100);
+		assert!(kept.ends_with('…'));
+		assert!(line.starts_with(kept.trim_end_matches('…')));
+		assert_eq!(monitor.content.items[0].chars().count(), 100);
+		assert_eq!(monitor.metrics.activity_puts, 1);
+	}
+
+	#[test]
+	fn records_diagnostics_of_unparsed_lines() {
+		let opt = test_opt();
+		let diagnostics = Diagnostics::shared(10);
+		let mut monitor = LogMonitor::new(&opt, "node-1/safenode.log".to_string(), opt.lines_max);
+		monitor.name = String::from("node-1");
+		monitor.diagnostics = Some(diagnostics.clone());
+		monitor.process_line(&log_line("INFO", "Successfully stored record 8a3f"), &Utc::now()).unwrap();
+		monitor.process_line(" INFO 15/01/2022 20:21:02 Stored record 91bc", &Utc::now()).unwrap();
+		monitor.process_line(&log_line("INFO", "Record 8a3f was copied to 4 holders"), &Utc::now()).unwrap();
+		monitor.process_line("	 ➤ more of the last entry", &Utc::now()).unwrap();
+
+		let diagnostics = diagnostics.lock().unwrap();
+		let problems: Vec<(&str, &str)> = diagnostics.iter().map(|d| (d.node.as_str(), d.problem.as_str())).collect();
+		assert_eq!(problems, vec![
+			("node-1", "log line without a time vdash can read"),
+			("node-1", "unrecognised line mentioning 'Record'"),
+		]);
+		assert_eq!(diagnostics.iter().next().unwrap().line, " INFO 15/01/2022 20:21:02 Stored record 91bc");
+	}
+
+	#[test]
+	fn processes_a_script_of_lines_through_the_whole_pipeline() {
+		let mut app = test_app(&["node-1/safenode.log", "node-2/safenode.log", "node-3/safenode.log"]);
+		let now = Utc.ymd(2022, 1, 15).and_hms(20, 22, 0);
+		let script = [
+			(0, log_line("INFO", "Successfully stored record 8a3f")),
+			(0, log_line("INFO", "Successfully stored record 91c0")),
+			(0, log_line("INFO", "Retrieved record 8a3f from local store")),
+			(0, log_line("INFO", "Records stored: 120")),
+			(0, log_line("DEBUG", "ConnectionEstablished { peer_id: 12D3KooWAbc, endpoint: Dialer }")),
+			(1, String::from("a line without a time")),
+			(1, log_line("WARN", "Disconnected from the network")),
+			(1, log_line("ERROR", "Failed to store record")),
+			(2, log_line("INFO", "Successfully stored record 7e21")), // Disabled, so dropped
+		];
+		app.monitors.get_mut("node-3/safenode.log").unwrap().set_enabled(false);
+		for (node_idx, line) in &script {
+			app.process_line(*node_idx, line, &now).unwrap();
+		}
+		assert!(app.process_line(3, &script[0].1, &now).is_err());
+
+		let node = |logfile: &str| &app.monitors[logfile];
+		let counts = |logfile: &str| {
+			let metrics = &node(logfile).metrics;
+			(metrics.activity_puts, metrics.activity_gets, metrics.activity_errors, metrics.peers, metrics.records_stored)
+		};
+		assert_eq!(counts("node-1/safenode.log"), (2, 1, 0, 1, Some(120)));
+		assert_eq!(counts("node-2/safenode.log"), (0, 0, 1, 0, None));
+		assert_eq!(counts("node-3/safenode.log"), (0, 0, 0, 0, None));
+		assert_eq!(node("node-1/safenode.log").content.items.len(), 5);
+		assert!(node("node-3/safenode.log").content.items.is_empty());
+
+		// A line without a time, and not continuing an entry, is taken as logged at the time given
+		assert_eq!(node("node-2/safenode.log").metrics.last_activity, Some(now));
+
+		let puts = node("node-1/safenode.log").metrics.timeline_named("PUTS").unwrap();
+		for bucket_set in puts.bucket_sets().values() {
+			assert_eq!(bucket_set.buckets().iter().sum::<u64>(), 2);
+		}
+
+		let warnings: Vec<String> = app.dash_state.recent_warnings.lock().unwrap().iter().map(|entry| entry.describe()).collect();
+		assert_eq!(warnings, vec![
+			format!("20:21:02 node-2: {}", log_line("WARN", "Disconnected from the network").trim()),
+			format!("20:21:02 node-2: {}", log_line("ERROR", "Failed to store record").trim()),
+		]);
+		let diagnostics = app.dash_state.diagnostics.lock().unwrap();
+		let problems: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.describe()).collect();
+		// Noted at the time the line was processed
+		assert_eq!(problems, vec![format!(
+			"20:22:00 node-2: unrecognised line mentioning 'record': {}",
+			log_line("ERROR", "Failed to store record").trim()
+		)]);
+	}
+
+	#[test]
+	fn decodes_invalid_utf8_lossily() {
+		assert_eq!(decode_line(b"plain line\r\n"), "plain line");
+		assert_eq!(decode_line(b"bad \xff\xfe byte\n"), "bad \u{FFFD}\u{FFFD} byte");
+		assert_eq!(decode_line(b"cut short \xe2\x82"), "cut short \u{FFFD}");
+	}
+
+	#[test]
+	fn processes_lines_with_invalid_utf8() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		let mut bytes = log_line("INFO", "Successfully stored record 8a3f").into_bytes();
+		bytes.extend_from_slice(b" \xc3\x28 trailing garbage\n");
+		bytes.extend_from_slice(log_line("INFO", "Retrieved record 8a3f").as_bytes());
+		bytes.push(b'\n');
+		std::fs::write(&path, &bytes).unwrap();
+
+		let mut dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&test_opt(), path.to_string_lossy().to_string(), 100);
+		monitor.load_logfile(&mut dash_state).unwrap();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+		assert_eq!(monitor.metrics.activity_gets, 1);
+		assert!(monitor.tail.iter().next().unwrap().ends_with(" \u{FFFD}( trailing garbage"));
+	}
+
+	#[test]
+	fn filters_nodes_by_name_or_logfile() {
+		let opt = Opt::from_iter(vec!["vdash", "--name", "/nodes/node-1/safenode.log=alpha", "/nodes/node-1/safenode.log"]);
+		assert_eq!(opt.node_names, vec![("/nodes/node-1/safenode.log".to_string(), "alpha".to_string())]);
+
+		let mut monitor = LogMonitor::new(&opt, "/nodes/node-1/safenode.log".to_string(), opt.lines_max);
+		assert_eq!(monitor.name, "safenode.log");
+		monitor.name = String::from("alpha");
+		assert!(NodeFilter::new("alp").matches_node(&monitor));
+		assert!(NodeFilter::new("node-1/").matches_node(&monitor));
+		assert!(!NodeFilter::new("beta").matches_node(&monitor));
+	}
+
+	#[test]
+	fn steps_through_included_items() {
+		// Items 1 and 3 of 5 are hidden
+		let visible = |i: usize| i != 1 && i != 3;
+		for wrap in [false, true] {
+			assert_eq!(step_index(5, Some(0), true, wrap, visible), Some(2));
+			assert_eq!(step_index(5, Some(2), true, wrap, visible), Some(4));
+			assert_eq!(step_index(5, Some(4), false, wrap, visible), Some(2));
+			assert_eq!(step_index(5, Some(2), false, wrap, visible), Some(0));
+			assert_eq!(step_index(5, None, true, wrap, visible), Some(0));
+			assert_eq!(step_index(5, None, false, wrap, visible), Some(4));
+			assert_eq!(step_index(5, Some(2), true, wrap, |_| false), None);
+			assert_eq!(step_index(0, None, true, wrap, visible), None);
+		}
+
+		// At either end
+		assert_eq!(step_index(5, Some(4), true, true, visible), Some(0));
+		assert_eq!(step_index(5, Some(0), false, true, visible), Some(4));
+		assert_eq!(step_index(5, Some(4), true, false, visible), Some(4));
+		assert_eq!(step_index(5, Some(0), false, false, visible), Some(0));
+
+		// Hidden items at the ends are skipped, and a hidden current item is left
+		let visible = |i: usize| i != 0 && i != 4;
+		assert_eq!(step_index(5, Some(3), true, true, visible), Some(1));
+		assert_eq!(step_index(5, Some(1), false, true, visible), Some(3));
+		assert_eq!(step_index(5, Some(3), true, false, visible), Some(3));
+		assert_eq!(step_index(5, Some(1), false, false, visible), Some(1));
+		assert_eq!(step_index(5, Some(4), true, false, visible), Some(3));
+		assert_eq!(step_index(5, Some(0), false, false, visible), Some(1));
+	}
+}
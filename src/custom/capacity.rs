@@ -0,0 +1,216 @@
+///! Storage capacity of nodes, for when their logs don't say
+///!
+///! A node's capacity is, in order of preference: its entry in the
+///! --node-capacities file, the capacity it logged, or --node-capacity
+use std::collections::HashMap;
+use std::path::Path;
+
+///! Parses a size such as '1024', '500MB', '2 TB' or '1.5GiB' into bytes
+pub fn parse_size(size: &str) -> Result<u64, String> {
+	let bytes = byte_unit::Byte::from_str(size.trim())
+		.map_err(|_e| format!("invalid size '{}', expected bytes such as 500GB", size))?
+		.get_bytes();
+	if bytes == 0 || bytes > u128::from(u64::MAX) {
+		return Err(format!("size '{}' is out of range", size));
+	}
+	Ok(bytes as u64)
+}
+
+///! The fraction of capacity used, None when the capacity is unknown
+pub fn storage_ratio(used: u64, capacity: Option<u64>) -> Option<f64> {
+	match capacity {
+		Some(capacity) if capacity > 0 => Some((used as f64 / capacity as f64).min(1.0)),
+		_ => None,
+	}
+}
+
+///! When a node's storage is projected to be full
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageProjection {
+	Unknown, // Too few samples, or the capacity isn't known
+	Full,
+	FullIn(chrono::Duration),
+	Stable,
+	Declining,
+}
+
+///! The fewest buckets with storage used to project from
+pub const MIN_PROJECTION_SAMPLES: usize = 3;
+
+// Far enough ahead to be never, and within what a chrono::Duration holds
+const MAX_PROJECTION_MILLISECONDS: f64 = 1e15;
+
+///! Project when storage will be full by fitting a line (least squares) to
+///! the storage used in each bucket, oldest first. Empty buckets, in which
+///! nothing was logged, are left out
+pub fn project_storage(buckets: &[u64], bucket_duration: chrono::Duration, capacity: Option<u64>) -> StorageProjection {
+	let capacity = match capacity {
+		Some(capacity) if capacity > 0 => capacity as f64,
+		_ => return StorageProjection::Unknown,
+	};
+	let samples: Vec<(f64, f64)> = buckets
+		.iter()
+		.enumerate()
+		.filter(|(_i, used)| **used > 0)
+		.map(|(i, used)| (i as f64, *used as f64))
+		.collect();
+	if samples.len() < MIN_PROJECTION_SAMPLES {
+		return StorageProjection::Unknown;
+	}
+
+	let n = samples.len() as f64;
+	let mean_x = samples.iter().map(|(x, _y)| x).sum::<f64>() / n;
+	let mean_y = samples.iter().map(|(_x, y)| y).sum::<f64>() / n;
+	let covariance: f64 = samples.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+	let variance: f64 = samples.iter().map(|(x, _y)| (x - mean_x) * (x - mean_x)).sum();
+	let slope = covariance / variance; // Bytes per bucket
+	let latest = samples[samples.len() - 1].1;
+	if latest >= capacity {
+		return StorageProjection::Full;
+	}
+	if slope == 0.0 {
+		return StorageProjection::Stable;
+	}
+	if slope < 0.0 {
+		return StorageProjection::Declining;
+	}
+
+	let fitted = mean_y + slope * ((buckets.len() - 1) as f64 - mean_x);
+	if fitted >= capacity {
+		return StorageProjection::Full;
+	}
+	let buckets_to_full = (capacity - fitted) / slope;
+	let milliseconds = (buckets_to_full * bucket_duration.num_milliseconds() as f64).min(MAX_PROJECTION_MILLISECONDS);
+	StorageProjection::FullIn(chrono::Duration::milliseconds(milliseconds as i64))
+}
+
+///! The rate at which storage used grew (or shrank, below zero) into each
+///! bucket from the one before, in bytes per minute, oldest first. A bucket
+///! in which nothing was logged, or which follows one, has a rate of zero
+///! rather than a spike from or to nothing
+pub fn storage_growth_per_minute(buckets: &[u64], bucket_duration: chrono::Duration) -> Vec<i64> {
+	let milliseconds = i128::from(bucket_duration.num_milliseconds().max(1));
+	buckets
+		.iter()
+		.enumerate()
+		.map(|(i, used)| match i.checked_sub(1).map(|previous| buckets[previous]) {
+			Some(previous) if previous > 0 && *used > 0 => {
+				let change = i128::from(*used) - i128::from(previous);
+				let rate = change * 60_000 / milliseconds;
+				rate.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+			}
+			_ => 0,
+		})
+		.collect()
+}
+
+///! Capacities of individual nodes, keyed by logfile path
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeCapacities {
+	nodes: HashMap<String, u64>,
+}
+
+impl NodeCapacities {
+	pub fn load(path: &Path) -> Result<NodeCapacities, String> {
+		let toml = std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read node capacities {}: {}", path.display(), e))?;
+		NodeCapacities::from_toml(&toml).map_err(|e| format!("invalid node capacities {}: {}", path.display(), e))
+	}
+
+	///! Each entry maps a logfile to a number of bytes or a size string, e.g.
+	///! "/home/me/node-1/safenode.log" = "2TB"
+	pub fn from_toml(toml: &str) -> Result<NodeCapacities, String> {
+		let table: toml::value::Table = toml::from_str(toml).map_err(|e| e.to_string())?;
+		let mut nodes = HashMap::new();
+		for (logfile, value) in table {
+			let capacity = match &value {
+				toml::Value::Integer(bytes) if *bytes > 0 => *bytes as u64,
+				toml::Value::String(size) => parse_size(size).map_err(|e| format!("{}: {}", logfile, e))?,
+				_ => return Err(format!("{}: expected a size such as \"500GB\"", logfile)),
+			};
+			nodes.insert(logfile, capacity);
+		}
+		Ok(NodeCapacities { nodes })
+	}
+
+	pub fn capacity_for(&self, logfile: &str) -> Option<u64> {
+		self.nodes.get(logfile).copied()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_sizes() {
+		assert_eq!(parse_size("1024"), Ok(1024));
+		assert_eq!(parse_size("500MB"), Ok(500_000_000));
+		assert_eq!(parse_size(" 2 TB"), Ok(2_000_000_000_000));
+		assert_eq!(parse_size("1KiB"), Ok(1024));
+		assert!(parse_size("0").is_err());
+		assert!(parse_size("lots").is_err());
+	}
+
+	#[test]
+	fn computes_storage_ratio() {
+		assert_eq!(storage_ratio(256, Some(1024)), Some(0.25));
+		assert_eq!(storage_ratio(0, Some(1024)), Some(0.0));
+		assert_eq!(storage_ratio(2048, Some(1024)), Some(1.0));
+		assert_eq!(storage_ratio(256, Some(0)), None);
+		assert_eq!(storage_ratio(256, None), None);
+	}
+
+	#[test]
+	fn loads_per_node_capacities() {
+		let capacities = NodeCapacities::from_toml(
+			"\"node-1/safenode.log\" = \"2GB\"\n\"node-2/safenode.log\" = 1000\n",
+		)
+		.unwrap();
+		assert_eq!(capacities.capacity_for("node-1/safenode.log"), Some(2_000_000_000));
+		assert_eq!(capacities.capacity_for("node-2/safenode.log"), Some(1000));
+		assert_eq!(capacities.capacity_for("node-3/safenode.log"), None);
+
+		assert!(NodeCapacities::from_toml("\"node-1/safenode.log\" = \"big\"").is_err());
+		assert!(NodeCapacities::from_toml("\"node-1/safenode.log\" = -5").is_err());
+	}
+
+	#[test]
+	fn projects_when_storage_is_full() {
+		let minute = chrono::Duration::minutes(1);
+		// Growing 100 bytes a minute, with a gap where nothing was logged
+		assert_eq!(
+			project_storage(&[0, 0, 100, 200, 0, 400], minute, Some(1000)),
+			StorageProjection::FullIn(chrono::Duration::minutes(6))
+		);
+		assert_eq!(project_storage(&[800, 900, 1000], minute, Some(1000)), StorageProjection::Full);
+
+		assert_eq!(project_storage(&[500, 500, 500, 500], minute, Some(1000)), StorageProjection::Stable);
+		assert_eq!(project_storage(&[500, 450, 480, 400], minute, Some(1000)), StorageProjection::Declining);
+
+		// Too few samples, or no capacity to reach
+		assert_eq!(project_storage(&[0, 0, 0, 100, 200], minute, Some(1000)), StorageProjection::Unknown);
+		assert_eq!(project_storage(&[], minute, Some(1000)), StorageProjection::Unknown);
+		assert_eq!(project_storage(&[100, 200, 300], minute, None), StorageProjection::Unknown);
+
+		// Growing too slowly to ever be full
+		match project_storage(&[1, 1, 2], chrono::Duration::weeks(1), Some(u64::MAX)) {
+			StorageProjection::FullIn(duration) => assert!(duration > chrono::Duration::weeks(52 * 1000)),
+			projection => panic!("unexpected {:?}", projection),
+		}
+	}
+
+	#[test]
+	fn computes_storage_growth_rates() {
+		let minute = chrono::Duration::minutes(1);
+		// Growing, a plateau, a surge, shrinking, and gaps where nothing was logged
+		let used = [0, 1000, 1500, 1500, 4500, 0, 5000, 4000, 0, 0, 4200];
+		assert_eq!(storage_growth_per_minute(&used, minute), vec![0, 0, 500, 0, 3000, 0, 0, -1000, 0, 0, 0]);
+
+		// Per minute whatever the bucket duration
+		assert_eq!(storage_growth_per_minute(&[100, 400, 250], chrono::Duration::seconds(30)), vec![0, 600, -300]);
+		assert_eq!(storage_growth_per_minute(&[100, 400], chrono::Duration::hours(1)), vec![0, 5]);
+		assert_eq!(storage_growth_per_minute(&[1, u64::MAX], chrono::Duration::milliseconds(1)), vec![0, i64::MAX]);
+		assert!(storage_growth_per_minute(&[], minute).is_empty());
+	}
+}
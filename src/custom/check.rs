@@ -0,0 +1,270 @@
+///! Health checks for --check, which reads the logfiles once and reports
+///! on each node instead of showing the dashboard
+///!
+///! A node is healthy if it has logged within --stale-after, is logging
+///! fewer errors per minute than --check-errors and is connected
+///!
+///! The summary also scores each node's health from 0 to 100, see
+///! health_score(), weighing how recently it logged, its error rate, whether
+///! it is connected and its storage headroom by --health-weights
+use chrono::{DateTime, Duration, Utc};
+
+use crate::custom::app::NodeMetrics;
+use crate::custom::ui::format_elapsed;
+
+#[derive(Clone, Debug)]
+pub struct HealthCriteria {
+	pub stale_after: Duration,
+	pub max_errors_per_minute: u64,
+}
+
+///! What is wrong with a node, if anything
+#[derive(Debug, Default, PartialEq)]
+pub struct NodeHealth {
+	pub problems: Vec<String>,
+}
+
+impl NodeHealth {
+	pub fn is_healthy(&self) -> bool {
+		self.problems.is_empty()
+	}
+
+	///! One line for the --check report, e.g. 'FAIL node-1: disconnected'
+	pub fn report_line(&self, name: &str) -> String {
+		if self.is_healthy() {
+			format!("OK   {}", name)
+		} else {
+			format!("FAIL {}: {}", name, self.problems.join(", "))
+		}
+	}
+}
+
+///! How much each part of a node's state counts towards its health score.
+///! Parsed from --health-weights, e.g. 'stale=30,errors=30,connection=25,storage=15'
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthWeights {
+	pub stale: f64,
+	pub errors: f64,
+	pub connection: f64,
+	pub storage: f64,
+}
+
+impl Default for HealthWeights {
+	fn default() -> HealthWeights {
+		HealthWeights { stale: 30.0, errors: 30.0, connection: 25.0, storage: 15.0 }
+	}
+}
+
+impl std::str::FromStr for HealthWeights {
+	type Err = String;
+
+	///! Weights not given keep their defaults
+	fn from_str(s: &str) -> Result<HealthWeights, String> {
+		let mut weights = HealthWeights::default();
+		for entry in s.split(',').map(str::trim) {
+			let invalid = || format!("invalid health weight '{}', expected stale, errors, connection or storage=<weight>", entry);
+			let (name, weight) = entry.split_once('=').ok_or_else(invalid)?;
+			let weight = match weight.trim().parse::<f64>() {
+				Ok(weight) if weight >= 0.0 && weight.is_finite() => weight,
+				_ => return Err(invalid()),
+			};
+			match name.trim() {
+				"stale" => weights.stale = weight,
+				"errors" => weights.errors = weight,
+				"connection" => weights.connection = weight,
+				"storage" => weights.storage = weight,
+				_ => return Err(invalid()),
+			}
+		}
+		Ok(weights)
+	}
+}
+
+///! Storage used from which a node's headroom starts to count against it
+pub const STORAGE_HEADROOM_RATIO: f64 = 0.8;
+
+///! What a node's health score is made from
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthState {
+	pub since_activity: Option<Duration>, // None if nothing has been logged
+	pub errors_per_minute: u64,
+	pub connected: bool,
+	pub storage_ratio: Option<f64>, // None until the node's capacity is known
+}
+
+impl HealthState {
+	pub fn of(metrics: &NodeMetrics, now: &DateTime<Utc>) -> HealthState {
+		HealthState {
+			since_activity: metrics.time_since_activity(now),
+			errors_per_minute: metrics.errors_per_minute(),
+			connected: metrics.connected,
+			storage_ratio: metrics.storage_ratio(),
+		}
+	}
+}
+
+///! A score from 0 (failing) to 100 (healthy): the weighted mean of a score
+///! for each part of the node's state. Logging within stale_after scores
+///! fully, falling to nothing at twice that. Errors score less the nearer
+///! they are to max_errors_per_minute, and nothing from there. Storage scores
+///! fully up to STORAGE_HEADROOM_RATIO used, falling to nothing when full,
+///! and fully while the capacity is unknown
+pub fn health_score(state: &HealthState, criteria: &HealthCriteria, weights: &HealthWeights) -> u8 {
+	let stale = match state.since_activity {
+		None => 0.0,
+		Some(elapsed) => {
+			let stale_after = criteria.stale_after.num_milliseconds().max(1) as f64;
+			2.0 - elapsed.num_milliseconds() as f64 / stale_after
+		}
+	};
+	let errors = 1.0 - state.errors_per_minute as f64 / criteria.max_errors_per_minute.max(1) as f64;
+	let connection = if state.connected { 1.0 } else { 0.0 };
+	let storage = state.storage_ratio.map_or(1.0, |ratio| (1.0 - ratio) / (1.0 - STORAGE_HEADROOM_RATIO));
+	let parts = [
+		(stale, weights.stale),
+		(errors, weights.errors),
+		(connection, weights.connection),
+		(storage, weights.storage),
+	];
+	let total_weight: f64 = parts.iter().map(|(_score, weight)| weight).sum();
+	if total_weight <= 0.0 {
+		return 100;
+	}
+	let weighted: f64 = parts.iter().map(|(score, weight)| score.clamp(0.0, 1.0) * weight).sum();
+	(weighted * 100.0 / total_weight).round().clamp(0.0, 100.0) as u8
+}
+
+pub fn evaluate_health(metrics: &NodeMetrics, criteria: &HealthCriteria, now: &DateTime<Utc>) -> NodeHealth {
+	let mut health = NodeHealth::default();
+	match metrics.time_since_activity(now) {
+		None => health.problems.push(String::from("nothing logged")),
+		Some(elapsed) if elapsed > criteria.stale_after => {
+			health.problems.push(format!("stale (last line {} ago)", format_elapsed(elapsed)))
+		}
+		Some(_) => {}
+	}
+	let errors_per_minute = metrics.errors_per_minute();
+	if errors_per_minute >= criteria.max_errors_per_minute {
+		health.problems.push(format!("{} errors/min", errors_per_minute));
+	}
+	if !metrics.connected {
+		health.problems.push(String::from("disconnected"));
+	}
+	health
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::Opt;
+	use structopt::StructOpt;
+
+	fn criteria() -> HealthCriteria {
+		HealthCriteria {
+			stale_after: Duration::minutes(5),
+			max_errors_per_minute: 10,
+		}
+	}
+
+	fn active_metrics(now: &DateTime<Utc>) -> NodeMetrics {
+		let mut metrics = NodeMetrics::new(&Opt::from_iter(vec!["vdash"]));
+		metrics.update_timelines(now);
+		metrics.last_activity = Some(*now - Duration::seconds(30));
+		metrics
+	}
+
+	#[test]
+	fn passes_a_healthy_node() {
+		let now = Utc::now();
+		let health = evaluate_health(&active_metrics(&now), &criteria(), &now);
+		assert!(health.is_healthy());
+		assert_eq!(health.report_line("node-1"), "OK   node-1");
+	}
+
+	#[test]
+	fn fails_unhealthy_nodes() {
+		let now = Utc::now();
+		let never_logged = NodeMetrics::new(&Opt::from_iter(vec!["vdash"]));
+		assert_eq!(evaluate_health(&never_logged, &criteria(), &now).problems, vec!["nothing logged"]);
+
+		let mut stale = active_metrics(&now);
+		stale.last_activity = Some(now - Duration::minutes(10));
+		assert_eq!(evaluate_health(&stale, &criteria(), &now).problems, vec!["stale (last line 10m 0s ago)"]);
+
+		let mut erroring = active_metrics(&now);
+		for _ in 0..10 {
+			erroring.errors_timeline.increment_value(&now);
+		}
+		assert_eq!(evaluate_health(&erroring, &criteria(), &now).problems, vec!["10 errors/min"]);
+
+		let mut disconnected = active_metrics(&now);
+		disconnected.connected = false;
+		let health = evaluate_health(&disconnected, &criteria(), &now);
+		assert!(!health.is_healthy());
+		assert_eq!(health.report_line("node-1"), "FAIL node-1: disconnected");
+	}
+
+	fn healthy_state() -> HealthState {
+		HealthState {
+			since_activity: Some(Duration::seconds(30)),
+			errors_per_minute: 0,
+			connected: true,
+			storage_ratio: Some(0.25),
+		}
+	}
+
+	#[test]
+	fn scores_a_healthy_node_fully() {
+		let weights = HealthWeights::default();
+		assert_eq!(health_score(&healthy_state(), &criteria(), &weights), 100);
+		let unknown_capacity = HealthState { storage_ratio: None, ..healthy_state() };
+		assert_eq!(health_score(&unknown_capacity, &criteria(), &weights), 100);
+	}
+
+	#[test]
+	fn scores_a_degraded_node_by_weight() {
+		let weights = HealthWeights::default();
+		let erroring = HealthState { errors_per_minute: 5, ..healthy_state() }; // Half of --check-errors
+		assert_eq!(health_score(&erroring, &criteria(), &weights), 85);
+		let filling = HealthState { storage_ratio: Some(0.9), ..healthy_state() };
+		assert_eq!(health_score(&filling, &criteria(), &weights), 93); // Half its headroom
+		let quiet = HealthState { since_activity: Some(Duration::minutes(5) + Duration::seconds(150)), ..healthy_state() };
+		assert_eq!(health_score(&quiet, &criteria(), &weights), 85);
+
+		// Only errors count
+		let errors_only: HealthWeights = "stale=0,connection=0,storage=0".parse().unwrap();
+		assert_eq!(health_score(&erroring, &criteria(), &errors_only), 50);
+	}
+
+	#[test]
+	fn scores_a_failing_node_low() {
+		let weights = HealthWeights::default();
+		let failing = HealthState {
+			since_activity: Some(Duration::hours(1)),
+			errors_per_minute: 50,
+			connected: false,
+			storage_ratio: Some(1.2), // Over capacity still scores no less than nothing
+		};
+		assert_eq!(health_score(&failing, &criteria(), &weights), 0);
+		let never_logged = HealthState { since_activity: None, ..healthy_state() };
+		assert_eq!(health_score(&never_logged, &criteria(), &weights), 70);
+		let disconnected = HealthState { connected: false, ..healthy_state() };
+		assert_eq!(health_score(&disconnected, &criteria(), &weights), 75);
+
+		let nothing_counts: HealthWeights = "stale=0,errors=0,connection=0,storage=0".parse().unwrap();
+		assert_eq!(health_score(&failing, &criteria(), &nothing_counts), 100);
+	}
+
+	#[test]
+	fn parses_health_weights() {
+		let weights: HealthWeights = "errors=50, storage=0".parse().unwrap();
+		assert_eq!(weights, HealthWeights { errors: 50.0, storage: 0.0, ..HealthWeights::default() });
+		for invalid in &["errors", "errors=-1", "errors=lots", "uptime=10", ""] {
+			assert!(invalid.parse::<HealthWeights>().is_err(), "accepted '{}'", invalid);
+		}
+		assert_eq!(
+			"uptime=10".parse::<HealthWeights>(),
+			Err(String::from("invalid health weight 'uptime=10', expected stale, errors, connection or storage=<weight>"))
+		);
+	}
+}
@@ -0,0 +1,48 @@
+///! Copying text to the system clipboard, for the copy-metrics key
+///!
+///! As with desktop notifications the text is handed to the OS's own tool:
+///! pbcopy on macOS, and elsewhere on unix the first of wl-copy, xclip and
+///! xsel which runs. Without a clipboard, such as when headless over ssh,
+///! copying fails with an error saying so rather than anything else happening
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+///! Copy text to the clipboard, returning the name of the tool which took it
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<&'static str> {
+	for (program, args) in clipboard_commands() {
+		let mut command = Command::new(program);
+		command.args(*args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null());
+		let mut child = match command.spawn() {
+			Ok(child) => child,
+			Err(_e) => continue, // Not installed
+		};
+		// A tool which can't reach a display exits without reading it all
+		let written = match child.stdin.take() {
+			Some(mut stdin) => stdin.write_all(text.as_bytes()).is_ok(),
+			None => false,
+		};
+		if child.wait()?.success() && written {
+			return Ok(program);
+		}
+	}
+	Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no clipboard is available"))
+}
+
+#[cfg(target_os = "macos")]
+fn clipboard_commands() -> &'static [(&'static str, &'static [&'static str])] {
+	&[("pbcopy", &[])]
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn clipboard_commands() -> &'static [(&'static str, &'static [&'static str])] {
+	&[
+		("wl-copy", &[]),
+		("xclip", &["-selection", "clipboard"]),
+		("xsel", &["--clipboard", "--input"]),
+	]
+}
+
+#[cfg(not(unix))]
+fn clipboard_commands() -> &'static [(&'static str, &'static [&'static str])] {
+	&[]
+}
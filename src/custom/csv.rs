@@ -0,0 +1,129 @@
+///! CSV export of timeline history, for offline analysis
+///!
+///! One row per bucket of each node and timeline, with a column per metric:
+///!   node,timeline,timestamp,PUTS,GETS,...
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+use crate::custom::timelines::TimelineSet;
+
+pub struct TimelinesCsv {
+	csv: String,
+}
+
+impl TimelinesCsv {
+	///! Start with the header row, naming each metric
+	pub fn new(metric_names: &[&str]) -> TimelinesCsv {
+		let mut columns = vec!["node", "timeline", "timestamp"];
+		columns.extend_from_slice(metric_names);
+		let mut csv = TimelinesCsv { csv: String::new() };
+		csv.push_row(&columns.iter().map(|column| column.to_string()).collect::<Vec<String>>());
+		csv
+	}
+
+	///! Add a row for each bucket of a node's timelines, oldest first. The
+	///! timelines must all have the bucket set and be given in header order
+	pub fn push_node(&mut self, logfile: &str, timelines: &[&TimelineSet], bucket_set_name: &str) {
+		let bucket_sets: Vec<_> = timelines
+			.iter()
+			.filter_map(|timeline| timeline.bucket_sets().get(bucket_set_name))
+			.collect();
+		let first = match bucket_sets.first() {
+			Some(first) if bucket_sets.len() == timelines.len() => first,
+			_ => return,
+		};
+		let latest_time = match first.bucket_time {
+			Some(latest_time) => latest_time,
+			None => return, // Never updated, so there's nothing to export
+		};
+
+		let buckets = first.buckets.len();
+		for index in 0..buckets {
+			let buckets_behind = (buckets - 1 - index) as i32;
+			let timestamp: DateTime<Utc> = latest_time - first.bucket_duration * buckets_behind;
+			let mut row = vec![
+				logfile.to_string(),
+				bucket_set_name.to_string(),
+				timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+			];
+			for bucket_set in bucket_sets.iter() {
+				row.push(bucket_set.buckets.get(index).copied().unwrap_or(0).to_string());
+			}
+			self.push_row(&row);
+		}
+	}
+
+	fn push_row(&mut self, fields: &[String]) {
+		let fields: Vec<String> = fields.iter().map(|field| quote_field(field)).collect();
+		self.csv.push_str(&fields.join(","));
+		self.csv.push('\n');
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.csv
+	}
+
+	pub fn write(&self, path: &Path) -> Result<(), Error> {
+		fs::write(path, &self.csv)
+	}
+}
+
+// Quote a field only if it would otherwise be misread, doubling any quotes
+fn quote_field(field: &str) -> String {
+	if field.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::{Duration, TimeZone};
+
+	fn timeline(name: &str, values: &[u64]) -> TimelineSet {
+		let mut timeline = TimelineSet::new(name.to_string());
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), values.len());
+		let bucket_set = timeline.bucket_sets_mut().get_mut("1 minute columns").unwrap();
+		bucket_set.buckets = values.to_vec();
+		bucket_set.bucket_time = Some(Utc.ymd(2022, 1, 15).and_hms(20, 21, 0));
+		timeline
+	}
+
+	#[test]
+	fn writes_a_row_per_bucket() {
+		let puts = timeline("PUTS", &[1, 0, 4]);
+		let gets = timeline("GETS", &[7, 8, 9]);
+		let mut csv = TimelinesCsv::new(&["PUTS", "GETS"]);
+		csv.push_node("node-1/safenode.log", &[&puts, &gets], "1 minute columns");
+		assert_eq!(
+			csv.as_str(),
+			"node,timeline,timestamp,PUTS,GETS\n\
+			node-1/safenode.log,1 minute columns,2022-01-15T20:19:00Z,1,7\n\
+			node-1/safenode.log,1 minute columns,2022-01-15T20:20:00Z,0,8\n\
+			node-1/safenode.log,1 minute columns,2022-01-15T20:21:00Z,4,9\n"
+		);
+	}
+
+	#[test]
+	fn writes_just_the_header_for_empty_timelines() {
+		let mut never_updated = TimelineSet::new("PUTS".to_string());
+		never_updated.add_bucket_set("1 minute columns", Duration::minutes(1), 3);
+		let mut unknown_bucket_set = timeline("PUTS", &[1]);
+		unknown_bucket_set.bucket_sets_mut().clear();
+		let mut csv = TimelinesCsv::new(&["PUTS"]);
+		csv.push_node("a.log", &[&never_updated], "1 minute columns");
+		csv.push_node("b.log", &[&unknown_bucket_set], "1 minute columns");
+		assert_eq!(csv.as_str(), "node,timeline,timestamp,PUTS\n");
+	}
+
+	#[test]
+	fn quotes_fields_when_needed() {
+		assert_eq!(quote_field("plain.log"), "plain.log");
+		assert_eq!(quote_field("a,b.log"), "\"a,b.log\"");
+		assert_eq!(quote_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+	}
+}
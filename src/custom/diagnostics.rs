@@ -0,0 +1,172 @@
+///! Lines vdash could not make sense of, for the diagnostics view and --debug-log
+///!
+///! A line is noted if it looks like a log line but has no time vdash can
+///! read, or if it mentions something vdash measures (records, chunks,
+///! rewards, peers...) but none of its parsers recognised it. This helps
+///! when a new node release changes what it logs. The last --diagnostics-max
+///! are kept, and with --debug-log each is also appended to a file
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::custom::app::LogLevel;
+
+pub type SharedDiagnostics = Arc<Mutex<Diagnostics>>;
+
+lazy_static::lazy_static! {
+	static ref INTERESTING_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?P<topic>records?|chunks?|rewards?|store ?cost|payments?|peers?|storage|connected)\b").expect("The regex failed to compile. This is a bug.");
+}
+
+///! What is odd about a line, if anything. A line with a level is expected
+///! to have a time; one with a time but no recognised content is only noted
+///! if it mentions something which vdash measures
+pub fn diagnose_line(line: &str, timed: bool, recognised: bool) -> Option<String> {
+	if !timed {
+		return match LogLevel::classify(line) {
+			LogLevel::Other => None,
+			_ => Some(String::from("log line without a time vdash can read")),
+		};
+	}
+	if recognised {
+		return None;
+	}
+	INTERESTING_PATTERN
+		.captures(line)
+		.map(|captures| format!("unrecognised line mentioning '{}'", &captures["topic"]))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+	pub node: String,
+	pub time: DateTime<Utc>, // When it was read
+	pub problem: String,
+	pub line: String,
+}
+
+impl Diagnostic {
+	pub fn describe(&self) -> String {
+		format!("{} {}: {}: {}", self.time.format("%H:%M:%S"), self.node, self.problem, self.line.trim())
+	}
+}
+
+///! A fixed capacity ring buffer of diagnostics, oldest first
+pub struct Diagnostics {
+	entries: VecDeque<Diagnostic>,
+	capacity: usize,
+	debug_log: Option<File>,
+}
+
+impl Diagnostics {
+	pub fn new(capacity: usize) -> Diagnostics {
+		Diagnostics {
+			entries: VecDeque::with_capacity(capacity),
+			capacity,
+			debug_log: None,
+		}
+	}
+
+	pub fn shared(capacity: usize) -> SharedDiagnostics {
+		Arc::new(Mutex::new(Diagnostics::new(capacity)))
+	}
+
+	///! Append each diagnostic to a file as well, for --debug-log
+	pub fn open_debug_log(&mut self, path: &Path) -> Result<(), std::io::Error> {
+		self.debug_log = Some(OpenOptions::new().create(true).append(true).open(path)?);
+		Ok(())
+	}
+
+	///! Add a diagnostic, dropping the oldest once full. A failure to write
+	///! the --debug-log stops further writes rather than interrupting the dashboard
+	pub fn push(&mut self, diagnostic: Diagnostic) {
+		log::debug!("couldn't parse: {}", diagnostic.describe());
+		if let Some(debug_log) = self.debug_log.as_mut() {
+			if writeln!(debug_log, "{}", diagnostic.describe()).is_err() {
+				self.debug_log = None;
+			}
+		}
+		if self.capacity == 0 {
+			return;
+		}
+		if self.entries.len() == self.capacity {
+			self.entries.pop_front();
+		}
+		self.entries.push_back(diagnostic);
+	}
+
+	///! The diagnostics held, oldest first
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Diagnostic> + ExactSizeIterator {
+		self.entries.iter()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn diagnostic(line: &str) -> Diagnostic {
+		Diagnostic {
+			node: String::from("node-1"),
+			time: Utc::now(),
+			problem: String::from("odd"),
+			line: line.to_string(),
+		}
+	}
+
+	#[test]
+	fn diagnoses_unparsed_and_unrecognised_lines() {
+		assert_eq!(
+			diagnose_line(" INFO 15/01/2022 20:21:02 stored record 8a3f", false, false),
+			Some(String::from("log line without a time vdash can read"))
+		);
+		assert_eq!(diagnose_line("	 ➤ continuation of an entry", false, false), None);
+		assert_eq!(
+			diagnose_line(" INFO 2022-01-15T20:21:02Z [sn_node]: Fetched 3 Records from the network", true, false),
+			Some(String::from("unrecognised line mentioning 'Records'"))
+		);
+		assert_eq!(diagnose_line(" INFO 2022-01-15T20:21:02Z [sn_node]: Stored record 8a3f", true, true), None);
+		assert_eq!(diagnose_line(" INFO 2022-01-15T20:21:02Z [sn_node]: Nothing to see", true, false), None);
+	}
+
+	#[test]
+	fn keeps_the_last_diagnostics() {
+		let mut diagnostics = Diagnostics::new(2);
+		assert!(diagnostics.is_empty());
+		for line in &["one", "two", "three"] {
+			diagnostics.push(diagnostic(line));
+		}
+		assert_eq!(diagnostics.len(), 2);
+		assert_eq!(diagnostics.iter().map(|d| d.line.as_str()).collect::<Vec<_>>(), vec!["two", "three"]);
+	}
+
+	#[test]
+	fn appends_to_debug_log() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash-debug.log");
+		std::fs::write(&path, "earlier\n").unwrap();
+		let mut diagnostics = Diagnostics::new(0);
+		diagnostics.open_debug_log(&path).unwrap();
+		diagnostics.push(diagnostic("first"));
+		diagnostics.push(diagnostic("second"));
+		assert!(diagnostics.is_empty());
+
+		let written = std::fs::read_to_string(&path).unwrap();
+		let lines: Vec<&str> = written.lines().collect();
+		assert_eq!(lines.len(), 3);
+		assert_eq!(lines[0], "earlier");
+		assert!(lines[1].ends_with(" node-1: odd: first"), "{}", lines[1]);
+		assert!(lines[2].ends_with(" node-1: odd: second"), "{}", lines[2]);
+	}
+}
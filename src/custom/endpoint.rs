@@ -0,0 +1,389 @@
+///! Node metrics read from a node's own metrics endpoint, for --node-metrics-url
+///!
+///! Newer safenode builds serve their metrics over HTTP in the Prometheus
+///! text format. The endpoint of each node given one is fetched on its own
+///! thread every tick, and the metrics it knows are set in the node's
+///! NodeMetrics in place of those parsed from its logfile, so the dashboard
+///! shows them just the same. The logfile is still followed for everything
+///! else, such as the startup banner, errors and warnings. Only plain HTTP
+///! is supported
+use chrono::{DateTime, Utc};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use crate::custom::app::NodeMetrics;
+
+///! A sample of the Prometheus text format, e.g. 'sn_networking_store_cost 5000'
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+	pub name: String,
+	pub labels: Vec<(String, String)>,
+	pub value: f64,
+}
+
+impl Sample {
+	pub fn label(&self, name: &str) -> Option<&str> {
+		self.labels.iter().find(|(label, _value)| label == name).map(|(_label, value)| value.as_str())
+	}
+}
+
+///! The samples of a Prometheus text format response, ignoring comments,
+///! timestamps and any lines which can't be parsed
+pub fn parse_prometheus_text(text: &str) -> Vec<Sample> {
+	text.lines().filter_map(parse_sample).collect()
+}
+
+fn parse_sample(line: &str) -> Option<Sample> {
+	let line = line.trim();
+	if line.is_empty() || line.starts_with('#') {
+		return None;
+	}
+	let name_end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+	let name = &line[..name_end];
+	let mut rest = &line[name_end..];
+	let mut labels = Vec::<(String, String)>::new();
+	if rest.starts_with('{') {
+		let labels_end = rest.find('}')?;
+		for label in rest[1..labels_end].split(',').filter(|label| !label.trim().is_empty()) {
+			let (label, value) = label.split_at(label.find('=')?);
+			let value = value[1..].trim().trim_matches('"');
+			labels.push((label.trim().to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")));
+		}
+		rest = &rest[labels_end + 1..];
+	}
+	let value = rest.split_whitespace().next()?.parse::<f64>().ok()?;
+	Some(Sample {
+		name: name.to_string(),
+		labels,
+		value,
+	})
+}
+
+///! The values of a node's metrics known to vdash, None if not served
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EndpointMetrics {
+	pub puts: Option<u64>,
+	pub gets: Option<u64>,
+	pub rewards: Option<u128>, // In attos
+	pub store_cost: Option<u64>, // In attos
+	pub records_stored: Option<u64>,
+	pub peers: Option<u64>,
+	pub bytes_in: Option<u64>,
+	pub bytes_out: Option<u64>,
+}
+
+struct EndpointMetric {
+	name: &'static str,
+	label: Option<(&'static str, &'static str)>, // Only samples with this label and value
+	field: fn(&mut EndpointMetrics) -> &mut Option<u64>,
+}
+
+///! The safenode metrics vdash reads. Samples of a metric with several
+///! labels, such as the record_type of puts, are added together
+static ENDPOINT_METRICS: [EndpointMetric; 7] = [
+	EndpointMetric { name: "sn_node_put_record_ok_total", label: None, field: |m| &mut m.puts },
+	EndpointMetric { name: "sn_node_get_record_ok_total", label: None, field: |m| &mut m.gets },
+	EndpointMetric { name: "sn_networking_store_cost", label: None, field: |m| &mut m.store_cost },
+	EndpointMetric { name: "sn_networking_records_stored", label: None, field: |m| &mut m.records_stored },
+	EndpointMetric { name: "sn_networking_connected_peers", label: None, field: |m| &mut m.peers },
+	EndpointMetric { name: "libp2p_bandwidth_bytes_total", label: Some(("direction", "Inbound")), field: |m| &mut m.bytes_in },
+	EndpointMetric { name: "libp2p_bandwidth_bytes_total", label: Some(("direction", "Outbound")), field: |m| &mut m.bytes_out },
+];
+
+static REWARDS_METRIC: &str = "sn_node_total_forwarded_rewards";
+
+impl EndpointMetrics {
+	pub fn from_samples(samples: &[Sample]) -> EndpointMetrics {
+		let mut metrics = EndpointMetrics::default();
+		for sample in samples.iter().filter(|sample| sample.value >= 0.0) {
+			if sample.name == REWARDS_METRIC {
+				metrics.rewards = Some(metrics.rewards.unwrap_or(0).saturating_add(sample.value as u128));
+				continue;
+			}
+			for metric in ENDPOINT_METRICS.iter().filter(|metric| metric.name == sample.name) {
+				if metric.label.is_none_or(|(label, value)| sample.label(label) == Some(value)) {
+					let field = (metric.field)(&mut metrics);
+					*field = Some(field.unwrap_or(0).saturating_add(sample.value as u64));
+				}
+			}
+		}
+		metrics
+	}
+
+	///! Set the metrics served in those of a node. Counters which have risen
+	///! since the previous response are added to the timelines now, and one
+	///! which has fallen, after the node restarted, counts from zero
+	pub fn apply(&self, metrics: &mut NodeMetrics, previous: Option<&EndpointMetrics>, now: &DateTime<Utc>) {
+		metrics.update_timelines(now);
+		let increase = |value: u64, previous: Option<u64>| match previous {
+			Some(previous) if value >= previous => value - previous,
+			_ => value,
+		};
+		if let Some(puts) = self.puts {
+			if let Some(previous) = previous {
+				metrics.puts_timeline.add_value(now, increase(puts, previous.puts));
+			}
+			metrics.activity_puts = puts;
+		}
+		if let Some(gets) = self.gets {
+			if let Some(previous) = previous {
+				metrics.gets_timeline.add_value(now, increase(gets, previous.gets));
+			}
+			metrics.activity_gets = gets;
+		}
+		if let Some(bytes_in) = self.bytes_in {
+			if let Some(previous) = previous {
+				metrics.bytes_in_timeline.add_value(now, increase(bytes_in, previous.bytes_in));
+			}
+			metrics.bytes_in = bytes_in;
+		}
+		if let Some(bytes_out) = self.bytes_out {
+			if let Some(previous) = previous {
+				metrics.bytes_out_timeline.add_value(now, increase(bytes_out, previous.bytes_out));
+			}
+			metrics.bytes_out = bytes_out;
+		}
+		if let Some(rewards) = self.rewards {
+			if let Some(previous) = previous {
+				let previous = previous.rewards.filter(|previous| rewards >= *previous).unwrap_or(0);
+				metrics.rewards_timeline.add_value(now, (rewards - previous).min(u128::from(u64::MAX)) as u64);
+			}
+			metrics.rewards = rewards;
+		}
+		if let Some(peers) = self.peers {
+			metrics.peers = peers;
+			metrics.peers_timeline.set_value(now, peers);
+		}
+		if let Some(store_cost) = self.store_cost {
+			metrics.store_cost = Some(store_cost);
+			metrics.store_cost_timeline.set_value(now, store_cost);
+		}
+		if let Some(records) = self.records_stored {
+			metrics.records_stored = Some(records);
+			metrics.records_timeline.set_value(now, records);
+		}
+	}
+}
+
+///! Parses a --node-metrics-url argument such as
+///! '/home/me/node-1/safenode.log=http://127.0.0.1:13001/metrics'
+pub fn parse_node_metrics_url(arg: &str) -> Result<(String, String), String> {
+	let (logfile, url) = match arg.find("=http") {
+		Some(index) => (arg[..index].trim(), arg[index + 1..].trim()),
+		None => return Err(format!("invalid node metrics url '{}', expected path=url", arg)),
+	};
+	if logfile.is_empty() {
+		return Err(format!("invalid node metrics url '{}', expected path=url", arg));
+	}
+	split_http_url(url)?;
+	Ok((logfile.to_string(), url.to_string()))
+}
+
+///! The host:port and path of an http:// URL. The port defaults to 80 and
+///! the path to /metrics
+pub fn split_http_url(url: &str) -> Result<(String, String), String> {
+	let rest = match url.strip_prefix("http://") {
+		Some(rest) => rest,
+		None if url.starts_with("https://") => return Err(format!("can't fetch {}: this build of vdash has no TLS support", url)),
+		None => return Err(format!("invalid url '{}', expected http://host:port/path", url)),
+	};
+	let (host, path) = match rest.find('/') {
+		Some(index) => (&rest[..index], &rest[index..]),
+		None => (rest, "/metrics"),
+	};
+	if host.is_empty() {
+		return Err(format!("invalid url '{}', expected http://host:port/path", url));
+	}
+	let host = if host.contains(':') && !host.ends_with(']') { host.to_string() } else { format!("{}:80", host) };
+	Ok((host, path.to_string()))
+}
+
+///! Fetch a URL with a plain HTTP GET, returning the body of a 200 response
+pub fn http_get(url: &str) -> Result<String, String> {
+	let (host, path) = split_http_url(url)?;
+	let addr = host
+		.to_socket_addrs()
+		.map_err(|e| format!("{}: {}", host, e))?
+		.next()
+		.ok_or_else(|| format!("{}: no address", host))?;
+	let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(2)).map_err(|e| e.to_string())?;
+	stream.set_read_timeout(Some(Duration::from_secs(5))).map_err(|e| e.to_string())?;
+	let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\nAccept: text/plain\r\n\r\n", path, host);
+	stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+	let mut response = String::new();
+	stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+
+	let (head, body) = match response.find("\r\n\r\n") {
+		Some(index) => (&response[..index], &response[index + 4..]),
+		None => return Err(String::from("invalid HTTP response")),
+	};
+	let status = head.lines().next().unwrap_or("");
+	match status.split_whitespace().nth(1) {
+		Some("200") => Ok(body.to_string()),
+		_ => Err(format!("HTTP status '{}'", status.trim())),
+	}
+}
+
+///! The metrics endpoint of a node, fetched on its own thread so the
+///! dashboard never waits for it
+pub struct MetricsEndpoint {
+	pub url: String,
+	pub error: Option<String>, // Why the last fetch failed, until one succeeds
+	rx: Receiver<Result<String, String>>,
+	previous: Option<EndpointMetrics>,
+}
+
+impl MetricsEndpoint {
+	///! Start fetching the URL every interval
+	pub fn spawn(url: &str, interval: Duration) -> MetricsEndpoint {
+		let (tx, rx) = channel();
+		let thread_url = url.to_string();
+		thread::spawn(move || loop {
+			if tx.send(http_get(&thread_url)).is_err() {
+				break;
+			}
+			thread::sleep(interval);
+		});
+		MetricsEndpoint {
+			url: url.to_string(),
+			error: None,
+			rx,
+			previous: None,
+		}
+	}
+
+	///! Apply the latest response received since the last call, if any, to
+	///! the node's metrics. Returns a failure to fetch or an end to one
+	///! for the debug window, once each time it changes
+	pub fn update(&mut self, metrics: &mut NodeMetrics, now: &DateTime<Utc>) -> Option<String> {
+		match self.rx.try_iter().last()? {
+			Ok(text) => {
+				let endpoint_metrics = EndpointMetrics::from_samples(&parse_prometheus_text(&text));
+				endpoint_metrics.apply(metrics, self.previous.as_ref(), now);
+				self.previous = Some(endpoint_metrics);
+				self.error.take().map(|_error| format!("fetching {} again", self.url))
+			}
+			Err(e) => {
+				let message = format!("unable to fetch {}: {}", self.url, e);
+				let changed = self.error.as_ref() != Some(&e);
+				self.error = Some(e);
+				if changed {
+					Some(message)
+				} else {
+					None
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::Opt;
+	use std::io::{BufRead, BufReader};
+	use std::net::TcpListener;
+	use structopt::StructOpt;
+
+	const RESPONSE: &str = r#"# HELP sn_node_put_record_ok_total Records stored
+# TYPE sn_node_put_record_ok_total counter
+sn_node_put_record_ok_total{record_type="Chunk"} 12
+sn_node_put_record_ok_total{record_type="Register"} 3
+sn_node_get_record_ok_total 7
+# TYPE sn_networking_store_cost gauge
+sn_networking_store_cost 5000
+sn_networking_records_stored 2048
+sn_networking_connected_peers 41
+sn_node_total_forwarded_rewards 1500000000
+libp2p_bandwidth_bytes_total{direction="Inbound"} 1048576 1642277662000
+libp2p_bandwidth_bytes_total{direction="Outbound"} 524288
+sn_unrelated_metric{path="a\"b"} 9
+not a sample
+"#;
+
+	#[test]
+	fn parses_prometheus_text() {
+		let samples = parse_prometheus_text(RESPONSE);
+		assert_eq!(samples.len(), 10);
+		assert_eq!(samples[0], Sample {
+			name: String::from("sn_node_put_record_ok_total"),
+			labels: vec![(String::from("record_type"), String::from("Chunk"))],
+			value: 12.0,
+		});
+		assert_eq!(samples[2].labels, vec![]);
+		assert_eq!(samples[7].value, 1048576.0);
+		assert_eq!(samples[9].label("path"), Some("a\"b"));
+	}
+
+	#[test]
+	fn reads_node_metrics_from_a_response() {
+		let metrics = EndpointMetrics::from_samples(&parse_prometheus_text(RESPONSE));
+		assert_eq!(metrics, EndpointMetrics {
+			puts: Some(15),
+			gets: Some(7),
+			rewards: Some(1_500_000_000),
+			store_cost: Some(5000),
+			records_stored: Some(2048),
+			peers: Some(41),
+			bytes_in: Some(1048576),
+			bytes_out: Some(524288),
+		});
+		assert_eq!(EndpointMetrics::from_samples(&[]), EndpointMetrics::default());
+	}
+
+	#[test]
+	fn sets_the_fields_of_the_log_parser() {
+		let now = Utc::now();
+		let mut node = NodeMetrics::new(&Opt::from_iter(vec!["vdash"]));
+		let first = EndpointMetrics::from_samples(&parse_prometheus_text(RESPONSE));
+		first.apply(&mut node, None, &now);
+		assert_eq!((node.activity_puts, node.activity_gets, node.peers), (15, 7, 41));
+		assert_eq!((node.store_cost, node.records_stored, node.rewards), (Some(5000), Some(2048), 1_500_000_000));
+		assert_eq!((node.bytes_in, node.bytes_out), (1048576, 524288));
+		// The first response sets the totals without adding them to the timelines
+		assert_eq!(node.puts_timeline.get_bucket_set("1 second columns").unwrap().buckets().last(), Some(&0));
+
+		let later = EndpointMetrics { puts: Some(20), ..first.clone() };
+		later.apply(&mut node, Some(&first), &now);
+		assert_eq!(node.activity_puts, 20);
+		assert_eq!(node.puts_timeline.get_bucket_set("1 second columns").unwrap().buckets().last(), Some(&5));
+		let restarted = EndpointMetrics { puts: Some(2), ..first.clone() };
+		restarted.apply(&mut node, Some(&later), &now);
+		assert_eq!(node.puts_timeline.get_bucket_set("1 second columns").unwrap().buckets().last(), Some(&7));
+	}
+
+	#[test]
+	fn parses_node_metrics_urls() {
+		assert_eq!(
+			parse_node_metrics_url("/nodes/node-1/safenode.log=http://127.0.0.1:13001/metrics"),
+			Ok(("/nodes/node-1/safenode.log".to_string(), "http://127.0.0.1:13001/metrics".to_string()))
+		);
+		assert!(parse_node_metrics_url("/nodes/node-1/safenode.log").is_err());
+		assert!(parse_node_metrics_url("=http://127.0.0.1:13001").is_err());
+		assert_eq!(
+			parse_node_metrics_url("safenode.log=https://node:13001").err().unwrap(),
+			"can't fetch https://node:13001: this build of vdash has no TLS support"
+		);
+		assert_eq!(split_http_url("http://node"), Ok(("node:80".to_string(), "/metrics".to_string())));
+		assert_eq!(split_http_url("http://[::1]:9000/m"), Ok(("[::1]:9000".to_string(), "/m".to_string())));
+	}
+
+	#[test]
+	fn fetches_a_metrics_endpoint() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+		thread::spawn(move || {
+			for (stream, status) in listener.incoming().zip(vec!["200 OK", "404 Not Found"]) {
+				let mut stream = stream.unwrap();
+				let request: Vec<String> = BufReader::new(&stream).lines().map(|line| line.unwrap()).take_while(|line| !line.is_empty()).collect();
+				assert_eq!(request[0], "GET /metrics HTTP/1.0");
+				write!(stream, "HTTP/1.0 {}\r\nContent-Type: text/plain\r\n\r\n{}", status, RESPONSE).unwrap();
+			}
+		});
+		assert_eq!(http_get(&url), Ok(RESPONSE.to_string()));
+		assert_eq!(http_get(&url), Err(String::from("HTTP status 'HTTP/1.0 404 Not Found'")));
+	}
+}
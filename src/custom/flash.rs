@@ -0,0 +1,124 @@
+///! Briefly highlighting the values shown which have just changed, so that
+///! live updates catch the eye
+///!
+///! Each cell, by row and column, keeps the value it last showed and the tick
+///! at which that changed. A change is highlighted for --flash-ticks ticks,
+///! brightly for the first half of them and then fading. A value shown for
+///! the first time isn't a change, and --flash-ticks 0 turns flashing off
+use std::collections::HashMap;
+
+///! How a changed value is highlighted as the flash fades
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flash {
+	Bright,
+	Fading,
+}
+
+///! The highlight of a value which changed at changed_at, None once flash_ticks
+///! have passed
+pub fn flash_at(changed_at: u64, tick: u64, flash_ticks: u64) -> Option<Flash> {
+	let age = tick.wrapping_sub(changed_at);
+	if age >= flash_ticks {
+		None
+	} else if age < flash_ticks.div_ceil(2) {
+		Some(Flash::Bright)
+	} else {
+		Some(Flash::Fading)
+	}
+}
+
+struct FlashCell {
+	value: String,
+	changed_at: Option<u64>, // None until the value first changes
+}
+
+///! The values last shown in each cell, by row and column
+pub struct ChangeFlash {
+	flash_ticks: u64,
+	cells: HashMap<(String, &'static str), FlashCell>,
+}
+
+impl ChangeFlash {
+	pub fn new(flash_ticks: u64) -> ChangeFlash {
+		ChangeFlash {
+			flash_ticks,
+			cells: HashMap::new(),
+		}
+	}
+
+	///! Note the value shown in a cell at tick, and how it is highlighted
+	pub fn observe(&mut self, row: &str, column: &'static str, value: &str, tick: u64) -> Option<Flash> {
+		if self.flash_ticks == 0 {
+			return None;
+		}
+		let cell = match self.cells.get_mut(&(row.to_string(), column)) {
+			Some(cell) => cell,
+			None => {
+				let cell = FlashCell { value: value.to_string(), changed_at: None };
+				self.cells.insert((row.to_string(), column), cell);
+				return None;
+			}
+		};
+		if cell.value != value {
+			cell.value = value.to_string();
+			cell.changed_at = Some(tick);
+		}
+		flash_at(cell.changed_at?, tick, self.flash_ticks)
+	}
+
+	///! Forget the cells of rows which are gone, such as removed nodes
+	pub fn retain_rows(&mut self, keep: impl Fn(&str) -> bool) {
+		self.cells.retain(|(row, _column), _cell| keep(row));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn flashes_values_which_change() {
+		let mut flash = ChangeFlash::new(2);
+		let values = ["10", "10", "11", "11", "11", "12", "13", "13", "13"];
+		let flashes: Vec<Option<Flash>> =
+			values.iter().enumerate().map(|(tick, value)| flash.observe("node-1", "puts", value, tick as u64)).collect();
+		assert_eq!(
+			flashes,
+			vec![
+				None, // Shown for the first time
+				None,
+				Some(Flash::Bright),
+				Some(Flash::Fading),
+				None,
+				Some(Flash::Bright),
+				Some(Flash::Bright), // Changed again, so bright again
+				Some(Flash::Fading),
+				None,
+			]
+		);
+
+		// Each cell is tracked apart
+		assert_eq!(flash.observe("node-1", "gets", "13", 9), None);
+		assert_eq!(flash.observe("node-2", "puts", "13", 9), None);
+		assert_eq!(flash.observe("node-1", "puts", "14", 9), Some(Flash::Bright));
+
+		flash.retain_rows(|row| row != "node-1");
+		assert_eq!(flash.observe("node-1", "puts", "15", 10), None); // Forgotten, so shown for the first time
+	}
+
+	#[test]
+	fn fades_after_its_ticks() {
+		assert_eq!(flash_at(5, 5, 4), Some(Flash::Bright));
+		assert_eq!(flash_at(5, 6, 4), Some(Flash::Bright));
+		assert_eq!(flash_at(5, 7, 4), Some(Flash::Fading));
+		assert_eq!(flash_at(5, 8, 4), Some(Flash::Fading));
+		assert_eq!(flash_at(5, 9, 4), None);
+		assert_eq!(flash_at(5, 5, 1), Some(Flash::Bright));
+		assert_eq!(flash_at(5, 6, 1), None);
+		assert_eq!(flash_at(u64::MAX, 0, 2), Some(Flash::Fading)); // Across the ticks wrapping
+
+		let mut off = ChangeFlash::new(0);
+		assert_eq!(off.observe("node-1", "puts", "1", 0), None);
+		assert_eq!(off.observe("node-1", "puts", "2", 1), None);
+	}
+}
@@ -0,0 +1,128 @@
+///! Focusing the node and detail views on one metric, cycled with the
+///! cycle-focus-metric key: the panels and timelines related to it are drawn
+///! brightly, and the rest dimmed, until the focus is cycled off again
+use tui::style::{Modifier, Style};
+
+use crate::custom::panels::PanelKind;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusMetric {
+	Errors,
+	Storage,
+	Traffic,
+	Peers,
+}
+
+///! Every metric which can be focused on, in the order they are cycled
+pub static FOCUS_METRICS: [(FocusMetric, &str); 4] = [
+	(FocusMetric::Errors, "errors"),
+	(FocusMetric::Storage, "storage"),
+	(FocusMetric::Traffic, "traffic"),
+	(FocusMetric::Peers, "peers"),
+];
+
+impl FocusMetric {
+	pub fn name(&self) -> &'static str {
+		FOCUS_METRICS.iter().find(|(metric, _name)| metric == self).map(|(_metric, name)| *name).unwrap_or("")
+	}
+
+	///! The metric after current, with None (no focus) after the last and before the first
+	pub fn next(current: Option<FocusMetric>) -> Option<FocusMetric> {
+		let index = match current {
+			None => 0,
+			Some(current) => FOCUS_METRICS.iter().position(|(metric, _name)| *metric == current).map_or(0, |index| index + 1),
+		};
+		FOCUS_METRICS.get(index).map(|(metric, _name)| *metric)
+	}
+
+	///! The panels which show something of the metric
+	pub fn relevant_panels(&self) -> &'static [PanelKind] {
+		match self {
+			FocusMetric::Errors => &[PanelKind::Stats, PanelKind::Log],
+			FocusMetric::Storage => &[PanelKind::Resources, PanelKind::Growth],
+			FocusMetric::Traffic => &[PanelKind::Stats, PanelKind::Latency],
+			FocusMetric::Peers => &[PanelKind::Stats],
+		}
+	}
+
+	///! The titles of the timelines of the metric
+	pub fn relevant_timelines(&self) -> &'static [&'static str] {
+		match self {
+			FocusMetric::Errors => &["ERRORS", "WARNINGS"],
+			FocusMetric::Storage => &["STORAGE %", "RECORDS", "STORE COST"],
+			FocusMetric::Traffic => &["PUTS", "GETS", "BYTES IN", "BYTES OUT", "LATENCY ms"],
+			FocusMetric::Peers => &["PEERS", "REPLICATIONS", "RELOCATIONS"],
+		}
+	}
+}
+
+///! How a panel or timeline is drawn given the focus
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Emphasis {
+	Normal, // No metric is focused on
+	Bright,
+	Dim,
+}
+
+pub fn panel_emphasis(focus: Option<FocusMetric>, panel: PanelKind) -> Emphasis {
+	match focus {
+		None => Emphasis::Normal,
+		Some(metric) if metric.relevant_panels().contains(&panel) => Emphasis::Bright,
+		Some(_metric) => Emphasis::Dim,
+	}
+}
+
+///! Of a chart of the timelines titled, which is bright if any of them is of the metric
+pub fn chart_emphasis(focus: Option<FocusMetric>, titles: &[&str]) -> Emphasis {
+	match focus {
+		None => Emphasis::Normal,
+		Some(metric) if titles.iter().any(|title| metric.relevant_timelines().contains(title)) => Emphasis::Bright,
+		Some(_metric) => Emphasis::Dim,
+	}
+}
+
+///! The style added over what is drawn, or None to leave it as it is
+pub fn emphasis_style(emphasis: Emphasis) -> Option<Style> {
+	match emphasis {
+		Emphasis::Normal => None,
+		Emphasis::Bright => Some(Style::default().add_modifier(Modifier::BOLD)),
+		Emphasis::Dim => Some(Style::default().add_modifier(Modifier::DIM)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cycles_the_focus_and_off_again() {
+		let mut focus = None;
+		let mut names = Vec::<&str>::new();
+		for _ in 0..FOCUS_METRICS.len() {
+			focus = FocusMetric::next(focus);
+			names.push(focus.map_or("", |metric| metric.name()));
+		}
+		assert_eq!(names, vec!["errors", "storage", "traffic", "peers"]);
+		assert_eq!(FocusMetric::next(focus), None);
+	}
+
+	#[test]
+	fn emphasises_the_panels_of_the_metric() {
+		let errors = Some(FocusMetric::Errors);
+		assert_eq!(panel_emphasis(errors, PanelKind::Log), Emphasis::Bright);
+		assert_eq!(panel_emphasis(errors, PanelKind::Stats), Emphasis::Bright);
+		assert_eq!(panel_emphasis(errors, PanelKind::Growth), Emphasis::Dim);
+		assert_eq!(panel_emphasis(Some(FocusMetric::Storage), PanelKind::Growth), Emphasis::Bright);
+		assert_eq!(panel_emphasis(None, PanelKind::Growth), Emphasis::Normal);
+
+		// An overlaid chart is bright if either of its timelines is of the metric
+		assert_eq!(chart_emphasis(errors, &["STORAGE %", "ERRORS"]), Emphasis::Bright);
+		assert_eq!(chart_emphasis(errors, &["PUTS", "GETS"]), Emphasis::Dim);
+		assert_eq!(chart_emphasis(Some(FocusMetric::Traffic), &["PUTS", "GETS"]), Emphasis::Bright);
+		assert_eq!(chart_emphasis(None, &["ERRORS"]), Emphasis::Normal);
+
+		assert_eq!(emphasis_style(Emphasis::Normal), None);
+		assert_eq!(emphasis_style(Emphasis::Dim), Some(Style::default().add_modifier(Modifier::DIM)));
+		assert_eq!(emphasis_style(Emphasis::Bright), Some(Style::default().add_modifier(Modifier::BOLD)));
+	}
+}
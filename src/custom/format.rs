@@ -0,0 +1,248 @@
+///! How numbers are shown by the dashboard, see --units, --group-digits,
+///! --precision and --compact-counts
+///!
+///! Without --units each quantity keeps its customary units: storage in SI
+///! units (GB) and bytes transferred in binary units (GiB). Without
+///! --precision each value keeps its customary decimal places, such as one for
+///! rates and none for the storage percentage in the summary
+use byte_unit::Byte;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Units {
+	/// Powers of 1000: kB, MB, GB
+	Si,
+	/// Powers of 1024: KiB, MiB, GiB
+	Binary,
+}
+
+impl std::str::FromStr for Units {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Units, String> {
+		match s {
+			"si" => Ok(Units::Si),
+			"binary" => Ok(Units::Binary),
+			_ => Err(format!("invalid units '{}', expected si or binary", s)),
+		}
+	}
+}
+
+///! A count such as '10000' or '100K', for --compact-counts
+pub fn parse_compact_threshold(s: &str) -> Result<u64, String> {
+	let error = || format!("invalid count '{}', expected a number such as 10000 or 100K", s);
+	let upper = s.trim().to_uppercase();
+	let (number, unit) = match COMPACT_SUFFIXES.iter().find(|(_unit, suffix)| upper.ends_with(suffix)) {
+		Some((unit, suffix)) => (&upper[..upper.len() - suffix.len()], *unit),
+		None => (upper.as_str(), 1),
+	};
+	let number: u64 = number.trim().parse().map_err(|_e| error())?;
+	number.checked_mul(unit).ok_or_else(error)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NumberFormat {
+	pub units: Option<Units>, // None for the customary units of each quantity
+	pub group_digits: bool,   // Thousands separated by commas, e.g. 1,234,567
+	pub precision: Option<usize>, // Decimal places of every value with a fraction, None for each one's customary places
+	pub compact_from: Option<u64>, // Counts of the summary of at least this many are shown as e.g. 1.48M
+}
+
+///! Suffixes of compact counts, largest first
+static COMPACT_SUFFIXES: [(u64, &str); 4] = [(1_000_000_000_000, "T"), (1_000_000_000, "B"), (1_000_000, "M"), (1_000, "K")];
+
+///! Rewards and store costs are held in attos, the smallest unit of a token
+const ATTOS_PER_NANO: u128 = 1_000_000_000;
+const ATTOS_PER_TOKEN: u128 = 1_000_000_000_000_000_000;
+const MAX_TOKEN_DIGITS: usize = 18; // Finer than an atto means nothing
+
+impl NumberFormat {
+	///! Bytes in the largest unit below them, to one decimal place by default
+	pub fn format_bytes(&self, bytes: u64, customary: Units) -> String {
+		let binary = self.units.unwrap_or(customary) == Units::Binary;
+		Byte::from_bytes(bytes as u128).get_appropriate_unit(binary).format(self.digits(1))
+	}
+
+	pub fn format_count(&self, count: u64) -> String {
+		self.group(&count.to_string())
+	}
+
+	///! A count with a K, M, B or T suffix once at least --compact-counts, to
+	///! two decimal places by default, e.g. '1.48M'. Smaller counts, or any
+	///! without --compact-counts, are spelled out as by format_count()
+	pub fn format_compact_count(&self, count: u64) -> String {
+		match self.compact_from {
+			Some(compact_from) if count >= compact_from && count >= 1_000 => {}
+			_ => return self.format_count(count),
+		}
+		let digits = self.digits(2);
+		let index = COMPACT_SUFFIXES.iter().position(|(unit, _suffix)| count >= *unit).unwrap_or(COMPACT_SUFFIXES.len() - 1);
+		let (unit, suffix) = COMPACT_SUFFIXES[index];
+		let value = count as f64 / unit as f64;
+		let rounded = format!("{:.*}", digits, value);
+		// Rounding up to 1000 of one suffix is 1 of the next, e.g. 999,999 is 1.00M not 1000.00K
+		if index > 0 && rounded.parse::<f64>().is_ok_and(|rounded| rounded >= 1_000.0) {
+			let (unit, suffix) = COMPACT_SUFFIXES[index - 1];
+			return format!("{}{}", self.group(&format!("{:.*}", digits, count as f64 / unit as f64)), suffix);
+		}
+		format!("{}{}", self.group(&rounded), suffix)
+	}
+
+	///! A value with a fixed number of decimal places, --precision or else those
+	///! customary for it, grouping the whole part
+	pub fn format_decimal(&self, value: f64, customary_digits: usize) -> String {
+		self.group(&format!("{:.*}", self.digits(customary_digits), value))
+	}
+
+	///! A ratio as a percentage, e.g. '42%' for 0.42 with no decimal places
+	pub fn format_percent(&self, ratio: f64, customary_digits: usize) -> String {
+		format!("{}%", self.format_decimal(ratio * 100.0, customary_digits))
+	}
+
+	///! Rewards in tokens, nanos or attos depending on magnitude, to three
+	///! decimal places by default. Attos are whole so have none
+	pub fn format_rewards(&self, attos: u128) -> String {
+		let (unit, name) = if attos >= ATTOS_PER_TOKEN {
+			(ATTOS_PER_TOKEN, "tokens")
+		} else if attos >= ATTOS_PER_NANO {
+			(ATTOS_PER_NANO, "nanos")
+		} else {
+			return format!("{} attos", attos);
+		};
+		let whole = attos / unit;
+		let digits = self.digits(3).min(MAX_TOKEN_DIGITS);
+		if digits == 0 {
+			return format!("{} {}", whole, name);
+		}
+		let fraction = (attos % unit) * 10u128.pow(digits as u32) / unit;
+		format!("{}.{:0width$} {}", whole, fraction, name, width = digits)
+	}
+
+	fn digits(&self, customary_digits: usize) -> usize {
+		self.precision.unwrap_or(customary_digits)
+	}
+
+	fn group(&self, number: &str) -> String {
+		if !self.group_digits {
+			return number.to_string();
+		}
+		let (sign, unsigned) = match number.strip_prefix('-') {
+			Some(unsigned) => ("-", unsigned),
+			None => ("", number),
+		};
+		let (whole, fraction) = match unsigned.find('.') {
+			Some(point) => unsigned.split_at(point),
+			None => (unsigned, ""),
+		};
+		let mut grouped = String::from(sign);
+		for (i, digit) in whole.chars().enumerate() {
+			if i > 0 && (whole.len() - i) % 3 == 0 {
+				grouped.push(',');
+			}
+			grouped.push(digit);
+		}
+		grouped.push_str(fraction);
+		grouped
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn formats_bytes_in_either_units() {
+		let customary = NumberFormat::default();
+		let si = NumberFormat { units: Some(Units::Si), ..NumberFormat::default() };
+		let binary = NumberFormat { units: Some(Units::Binary), ..NumberFormat::default() };
+		for (bytes, in_si, in_binary) in &[
+			(0, "0 B", "0 B"),
+			(999, "999 B", "999 B"),
+			(1536, "1.5 KB", "1.5 KiB"),
+			(5_000_000, "5.0 MB", "4.8 MiB"),
+			(3 * 1024 * 1024 * 1024, "3.2 GB", "3.0 GiB"),
+			(2_000_000_000_000, "2.0 TB", "1.8 TiB"),
+		] {
+			assert_eq!(si.format_bytes(*bytes, Units::Binary), *in_si);
+			assert_eq!(binary.format_bytes(*bytes, Units::Si), *in_binary);
+			assert_eq!(customary.format_bytes(*bytes, Units::Si), *in_si);
+			assert_eq!(customary.format_bytes(*bytes, Units::Binary), *in_binary);
+		}
+		assert_eq!("binary".parse::<Units>(), Ok(Units::Binary));
+		assert!("gb".parse::<Units>().is_err());
+	}
+
+	#[test]
+	fn groups_digits_when_asked() {
+		let plain = NumberFormat::default();
+		let grouped = NumberFormat { group_digits: true, ..NumberFormat::default() };
+		for (count, as_grouped) in &[(0, "0"), (999, "999"), (1000, "1,000"), (123_456, "123,456"), (1_234_567, "1,234,567")] {
+			assert_eq!(plain.format_count(*count), count.to_string());
+			assert_eq!(grouped.format_count(*count), *as_grouped);
+		}
+		assert_eq!(grouped.format_count(u64::MAX), "18,446,744,073,709,551,615");
+		assert_eq!(plain.format_decimal(12345.25, 1), "12345.2");
+		assert_eq!(grouped.format_decimal(12345.25, 1), "12,345.2");
+		assert_eq!(grouped.format_decimal(-1234.5, 2), "-1,234.50");
+		assert_eq!(grouped.format_decimal(0.5, 1), "0.5");
+	}
+
+	#[test]
+	fn compacts_counts_from_the_threshold() {
+		let compact = NumberFormat { compact_from: Some(10_000), ..NumberFormat::default() };
+		for (count, compacted) in &[
+			(0, "0"),
+			(9_999, "9999"), // Below the threshold, in full
+			(10_000, "10.00K"),
+			(1_482_930, "1.48M"),
+			(999_999, "1.00M"),
+			(999_994_999, "999.99M"),
+			(2_500_000_000, "2.50B"),
+			(7_250_000_000_000, "7.25T"),
+			(u64::MAX, "18446744.07T"),
+		] {
+			assert_eq!(compact.format_compact_count(*count), *compacted);
+		}
+		let grouped = NumberFormat { group_digits: true, precision: Some(0), ..compact };
+		assert_eq!(grouped.format_compact_count(u64::MAX), "18,446,744T");
+		assert_eq!(grouped.format_compact_count(1_482_930), "1M");
+		assert_eq!(grouped.format_compact_count(9_999), "9,999");
+		let precise = NumberFormat { precision: Some(3), ..compact };
+		assert_eq!(precise.format_compact_count(1_482_930), "1.483M");
+
+		// Below a thousand there is no suffix, whatever the threshold
+		let always = NumberFormat { compact_from: Some(0), ..NumberFormat::default() };
+		assert_eq!(always.format_compact_count(999), "999");
+		assert_eq!(always.format_compact_count(1_000), "1.00K");
+		assert_eq!(NumberFormat::default().format_compact_count(1_482_930), "1482930");
+
+		assert_eq!(parse_compact_threshold("10000"), Ok(10_000));
+		assert_eq!(parse_compact_threshold("100k"), Ok(100_000));
+		assert_eq!(parse_compact_threshold("2B"), Ok(2_000_000_000));
+		assert_eq!(parse_compact_threshold("1.5M"), Err(String::from("invalid count '1.5M', expected a number such as 10000 or 100K")));
+		assert!(parse_compact_threshold("20000000T").is_err());
+	}
+
+	#[test]
+	fn rounds_to_the_precision_given() {
+		let precision = |digits: usize| NumberFormat { precision: Some(digits), ..NumberFormat::default() };
+		let customary = NumberFormat::default();
+		assert_eq!(customary.format_decimal(12.345678, 1), "12.3");
+		assert_eq!(customary.format_percent(0.4267, 0), "43%");
+		assert_eq!(customary.format_rewards(1_234_567_891_234), "1234.567 nanos");
+		assert_eq!(customary.format_bytes(1536, Units::Binary), "1.5 KiB");
+
+		for (digits, decimal, percent, rewards, bytes) in &[
+			(0, "12", "43%", "1234 nanos", "2 KiB"),
+			(2, "12.35", "42.67%", "1234.56 nanos", "1.50 KiB"),
+			(4, "12.3457", "42.6700%", "1234.5678 nanos", "1.5000 KiB"),
+		] {
+			let format = precision(*digits);
+			assert_eq!(format.format_decimal(12.345678, 1), *decimal);
+			assert_eq!(format.format_percent(0.4267, 0), *percent);
+			assert_eq!(format.format_rewards(1_234_567_891_234), *rewards);
+			assert_eq!(format.format_bytes(1536, Units::Binary), *bytes);
+		}
+		assert_eq!(precision(4).format_rewards(999), "999 attos");
+		assert_eq!(precision(40).format_rewards(1_500_000_000_000_000_001), "1.500000000000000001 tokens");
+	}
+}
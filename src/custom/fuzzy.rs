@@ -0,0 +1,138 @@
+///! Fuzzy matching of the node filter against node names, as in fzf
+///!
+///! The characters of the query must appear in the name in order, but not
+///! necessarily together. Of the ways they can be found, the best scoring is
+///! chosen: each character matched scores, more so at the start of a word or
+///! straight after the previous match, and gaps between matches cost a little.
+///! A query in lower case matches either case, one with capitals only itself
+static SCORE_MATCH: i64 = 16;
+static BONUS_BOUNDARY: i64 = 8; // At the start of the name or of a word within it
+static BONUS_CONSECUTIVE: i64 = 6; // Straight after the previous match
+static PENALTY_GAP_START: i64 = 3;
+static PENALTY_GAP_EXTENSION: i64 = 1; // For each character skipped after the first
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+	pub score: i64,
+	pub positions: Vec<usize>, // Indices of the characters of the candidate matched
+}
+
+///! The best match of the query in the candidate, None if it doesn't match.
+///! An empty query matches anything with a score of 0
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+	let case_sensitive = query.chars().any(char::is_uppercase);
+	let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+	let query: Vec<char> = query.chars().map(fold).collect();
+	let original: Vec<char> = candidate.chars().collect();
+	let candidate: Vec<char> = original.iter().copied().map(fold).collect();
+	if query.len() > candidate.len() {
+		return None;
+	}
+	if query.is_empty() {
+		return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+	}
+
+	// best[i][j] is the best score of the query up to i with i matched at j,
+	// reached from the match of i - 1 at from[i][j]
+	let mut best = vec![vec![None::<i64>; candidate.len()]; query.len()];
+	let mut from = vec![vec![0usize; candidate.len()]; query.len()];
+	for (i, q) in query.iter().enumerate() {
+		for j in i..candidate.len() {
+			if candidate[j] != *q {
+				continue;
+			}
+			let score = SCORE_MATCH + if is_boundary(&original, j) { BONUS_BOUNDARY } else { 0 };
+			if i == 0 {
+				best[i][j] = Some(score);
+				continue;
+			}
+			for k in (i - 1)..j {
+				let previous = match best[i - 1][k] {
+					Some(previous) => previous,
+					None => continue,
+				};
+				let gap = j - k - 1;
+				let adjustment = if gap == 0 {
+					BONUS_CONSECUTIVE
+				} else {
+					-(PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (gap as i64 - 1))
+				};
+				let total = previous + score + adjustment;
+				if best[i][j].is_none_or(|best| total > best) {
+					best[i][j] = Some(total);
+					from[i][j] = k;
+				}
+			}
+		}
+	}
+
+	let last = query.len() - 1;
+	let (mut j, score) = best[last]
+		.iter()
+		.enumerate()
+		.filter_map(|(j, score)| score.map(|score| (j, score)))
+		.fold(None, |found: Option<(usize, i64)>, (j, score)| match found {
+			Some((_, best)) if best >= score => found,
+			_ => Some((j, score)),
+		})?;
+	let mut positions = vec![0; query.len()];
+	for i in (0..query.len()).rev() {
+		positions[i] = j;
+		j = from[i][j];
+	}
+	Some(FuzzyMatch { score, positions })
+}
+
+// Starts a word: the first character, after a separator such as '-' or '/',
+// or a capital after a small letter
+fn is_boundary(candidate: &[char], index: usize) -> bool {
+	if index == 0 {
+		return true;
+	}
+	let previous = candidate[index - 1];
+	!previous.is_alphanumeric() || (previous.is_lowercase() && candidate[index].is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn positions(query: &str, candidate: &str) -> Option<Vec<usize>> {
+		fuzzy_match(query, candidate).map(|found| found.positions)
+	}
+
+	fn score(query: &str, candidate: &str) -> i64 {
+		fuzzy_match(query, candidate).unwrap().score
+	}
+
+	#[test]
+	fn matches_characters_in_order() {
+		assert_eq!(positions("nd1", "node-1"), Some(vec![0, 2, 5]));
+		assert_eq!(positions("log", "safenode.log"), Some(vec![9, 10, 11]));
+		assert_eq!(positions("1nd", "node-1"), None);
+		assert_eq!(positions("node-12", "node-1"), None);
+		assert_eq!(fuzzy_match("", "node-1"), Some(FuzzyMatch { score: 0, positions: vec![] }));
+	}
+
+	#[test]
+	fn matches_case_smartly() {
+		assert_eq!(positions("node", "Node-1"), Some(vec![0, 1, 2, 3]));
+		assert_eq!(positions("Node", "node-1"), None);
+		assert_eq!(positions("nB", "nodeAlpha nodeBeta"), Some(vec![10, 14]));
+	}
+
+	#[test]
+	fn chooses_the_best_alignment() {
+		// Greedily 'n' would match at 0, but 'no' together at 2 is better
+		assert_eq!(positions("no", "n-node"), Some(vec![2, 3]));
+		assert_eq!(positions("sn", "safenode"), Some(vec![0, 4]));
+		assert_eq!(positions("ab", "xaxab"), Some(vec![3, 4]));
+	}
+
+	#[test]
+	fn ranks_closer_matches_higher() {
+		assert!(score("node", "node-1") > score("node", "n-o-d-e"));
+		assert!(score("n1", "node-1") > score("n1", "nodexxxxx1"));
+		assert!(score("ap", "alpha-pi") > score("ap", "alpha"));
+	}
+}
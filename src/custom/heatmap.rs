@@ -0,0 +1,131 @@
+///! The heatmap view: a row for each node and a column for each bucket of one
+///! metric's timeline, each cell coloured by its value, like a contribution
+///! graph, for spotting patterns across the fleet
+///!
+///! A cell's level is its value as a share of the largest value of any cell
+///! shown, so that the rows of every node can be compared. Zero is the lowest
+///! level, and a bucket from before the node's timeline started, holding
+///! nothing, is left blank
+use tui::style::Style;
+
+use crate::custom::theme::Theme;
+use crate::custom::timelines::{timeline_key, BucketSet};
+
+///! Levels a cell's value is shown at, with zero at level 0
+pub const HEAT_LEVELS: usize = 5;
+
+///! The glyph of each level, so that levels can be told apart without colour
+pub static HEAT_GLYPHS: [&str; HEAT_LEVELS] = ["·", "░", "▒", "▓", "█"];
+
+///! The level of a value, of values up to max. Any value above zero is at
+///! least level 1, and max is the top level
+pub fn heat_level(value: u64, max: u64) -> usize {
+	if value == 0 || max == 0 {
+		return 0;
+	}
+	let top = (HEAT_LEVELS - 1) as u128;
+	(u128::from(value.min(max)) * top).div_ceil(u128::from(max)) as usize
+}
+
+///! The style of a level, from the theme's heatmap palette or plain in monochrome
+pub fn heat_style(theme: &Theme, level: usize) -> Style {
+	if theme.monochrome || theme.heat_palette.is_empty() {
+		return theme.text;
+	}
+	Style::default().fg(theme.heat_palette[level.min(theme.heat_palette.len() - 1)])
+}
+
+///! The values of the newest buckets of a bucket set, at most columns of them,
+///! oldest first. None for a bucket before the timeline started with nothing
+///! in it, though lines logged then (such as those read on startup) fill it
+pub fn heat_cells(bucket_set: &BucketSet, columns: usize) -> Vec<Option<u64>> {
+	let buckets = bucket_set.buckets();
+	let started = bucket_set.completed_buckets + 1; // Buckets since the timeline started, with the latest
+	let skip = buckets.len().saturating_sub(columns);
+	buckets
+		.iter()
+		.enumerate()
+		.skip(skip)
+		.map(|(index, value)| {
+			let age = buckets.len() - 1 - index;
+			if *value == 0 && age >= started {
+				None
+			} else {
+				Some(*value)
+			}
+		})
+		.collect()
+}
+
+///! The timeline named by --heatmap-metric, given as in its title or as e.g.
+///! 'bytes_in', of the timelines of each node
+pub fn find_heatmap_metric(name: &str, timeline_names: &[String]) -> Result<String, String> {
+	let key = timeline_key(name);
+	timeline_names.iter().find(|timeline_name| timeline_key(timeline_name) == key).cloned().ok_or_else(|| {
+		let names: Vec<String> = timeline_names.iter().map(|name| name.to_lowercase().replace(' ', "_")).collect();
+		format!("invalid heatmap metric '{}', expected {}", name, names.join(", "))
+	})
+}
+
+///! The metric after current, back to the first after the last
+pub fn next_heatmap_metric(current: &str, timeline_names: &[String]) -> Option<String> {
+	let index = timeline_names.iter().position(|name| name == current).map_or(0, |index| index + 1);
+	timeline_names.get(index % timeline_names.len().max(1)).cloned()
+}
+
+///! The largest value of the cells of every row, which is at the top level
+pub fn heat_max(rows: &[Vec<Option<u64>>]) -> u64 {
+	rows.iter().flatten().flatten().copied().max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::{Duration, Utc};
+	use tui::style::Color;
+
+	#[test]
+	fn maps_values_to_levels_over_the_range() {
+		let levels: Vec<usize> = [0, 1, 25, 26, 50, 51, 75, 76, 100, 200].iter().map(|value| heat_level(*value, 100)).collect();
+		assert_eq!(levels, vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 4]);
+		assert_eq!(heat_level(1, u64::MAX), 1); // However small, more than zero
+		assert_eq!(heat_level(u64::MAX, u64::MAX), HEAT_LEVELS - 1);
+		assert_eq!(heat_level(0, 0), 0);
+
+		let theme = Theme::default();
+		let colours: Vec<Option<Color>> = (0..HEAT_LEVELS).map(|level| heat_style(&theme, level).fg).collect();
+		assert_eq!(colours, theme.heat_palette.iter().map(|colour| Some(*colour)).collect::<Vec<Option<Color>>>());
+		assert_eq!(heat_style(&theme, 99).fg, theme.heat_palette.last().copied());
+		assert_eq!(heat_style(&Theme::monochrome(), 4), Theme::monochrome().text);
+	}
+
+	#[test]
+	fn finds_and_cycles_metrics() {
+		let names: Vec<String> = ["PUTS", "BYTES IN", "STORAGE %"].iter().map(|name| name.to_string()).collect();
+		assert_eq!(find_heatmap_metric("bytes_in", &names), Ok(String::from("BYTES IN")));
+		assert_eq!(find_heatmap_metric("Puts", &names), Ok(String::from("PUTS")));
+		assert_eq!(
+			find_heatmap_metric("cpu", &names),
+			Err(String::from("invalid heatmap metric 'cpu', expected puts, bytes_in, storage_%"))
+		);
+		assert_eq!(next_heatmap_metric("PUTS", &names), Some(String::from("BYTES IN")));
+		assert_eq!(next_heatmap_metric("STORAGE %", &names), Some(String::from("PUTS")));
+		assert_eq!(next_heatmap_metric("PUTS", &[]), None);
+	}
+
+	#[test]
+	fn leaves_buckets_before_the_timeline_blank() {
+		let start = Utc::now();
+		let mut bucket_set = BucketSet::new(Duration::seconds(1), 6);
+		bucket_set.bucket_time = Some(start);
+		bucket_set.buckets_mut()[1] = 4; // Read from the log for a time before starting
+		bucket_set.buckets_mut()[5] = 2;
+		bucket_set.completed_buckets = 2;
+		assert_eq!(heat_cells(&bucket_set, 10), vec![None, Some(4), None, Some(0), Some(0), Some(2)]);
+		assert_eq!(heat_cells(&bucket_set, 2), vec![Some(0), Some(2)]);
+
+		let rows = vec![heat_cells(&bucket_set, 10), vec![None, Some(9)], Vec::new()];
+		assert_eq!(heat_max(&rows), 9);
+		assert_eq!(heat_max(&[vec![None]]), 0);
+	}
+}
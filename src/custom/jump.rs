@@ -0,0 +1,71 @@
+///! Jumping to a node by typing the start of its name, after the jump-to-node
+///! key. Unlike the filter this hides nothing: each character typed moves the
+///! focus to the first node whose name starts with what has been typed, or
+///! failing that contains it, ignoring case. Where no name matches, the paths
+///! of the logfiles are matched instead. Enter keeps the node jumped to and
+///! Esc returns to the node shown before
+
+///! What has been typed so far, and the node to return to if cancelled
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeJump {
+	typed: String,
+	origin: String, // Logfile of the node shown when the jump started
+}
+
+impl NodeJump {
+	pub fn new(origin: &str) -> NodeJump {
+		NodeJump { typed: String::new(), origin: origin.to_string() }
+	}
+
+	pub fn typed(&self) -> &str {
+		&self.typed
+	}
+
+	pub fn origin(&self) -> &str {
+		&self.origin
+	}
+
+	pub fn push(&mut self, c: char) {
+		self.typed.push(c);
+	}
+
+	pub fn pop(&mut self) {
+		self.typed.pop();
+	}
+}
+
+///! The index of the first name starting with typed, or else the first
+///! containing it, ignoring case. None if nothing has been typed or none match
+pub fn jump_match(typed: &str, names: &[&str]) -> Option<usize> {
+	if typed.is_empty() {
+		return None;
+	}
+	let typed = typed.to_lowercase();
+	let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+	names.iter().position(|name| name.starts_with(&typed)).or_else(|| names.iter().position(|name| name.contains(&typed)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn jumps_to_names_by_prefix_then_substring() {
+		let names = ["alpha-1", "beta-1", "Alpha-2", "gamma-alpha", "node-10", "node-1"];
+		assert_eq!(jump_match("a", &names), Some(0));
+		assert_eq!(jump_match("ALPHA-2", &names), Some(2));
+		assert_eq!(jump_match("b", &names), Some(1));
+		assert_eq!(jump_match("node-1", &names), Some(4)); // The first in order, though another matches exactly
+		assert_eq!(jump_match("mma", &names), Some(3)); // Within a name, as none starts with it
+		assert_eq!(jump_match("ha-2", &names), Some(2));
+		assert_eq!(jump_match("delta", &names), None);
+		assert_eq!(jump_match("", &names), None);
+		assert_eq!(jump_match("a", &[]), None);
+
+		let mut jump = NodeJump::new("node-3/safenode.log");
+		jump.push('b');
+		jump.push('x');
+		jump.pop();
+		assert_eq!((jump.typed(), jump.origin()), ("b", "node-3/safenode.log"));
+	}
+}
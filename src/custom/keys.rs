@@ -0,0 +1,433 @@
+///! Key bindings, shared by the event handlers of both terminal backends
+///! and by the help overlay so that the two can't disagree
+///!
+///! The keys of each action can be changed with --keys vim, which moves
+///! navigation to h, j, k, l, g and G, and with --bind action=keys. Edit
+///! src/custom/keys.rs to change the defaults of a customised fork of logtail-dash
+
+///! A key press, independent of the terminal backend
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BindingKey {
+	Char(char),
+	Left,
+	Right,
+	Up,
+	Down,
+	Tab,
+	Enter,
+	Esc,
+	Backspace,
+	Home,
+	End,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+	Quit,
+	ToggleHelp,
+	SummaryView,
+	NodeView,
+	ToggleDetail,
+	DebugView,
+	WarningsView,
+	DiagnosticsView,
+	HeatmapView,
+	CycleSortKey,
+	ReverseSort,
+	StartFilter,
+	JumpToNode,
+	ResetCounters,
+	TogglePause,
+	ZoomIn,
+	ZoomOut,
+	ToggleLogScale,
+	ToggleOverlay,
+	ToggleNormalize,
+	ToggleTimelineFocus,
+	FreezeTimelines,
+	ThawTimelines,
+	DropMarker,
+	ClearMarker,
+	AcknowledgeAlert,
+	ToggleRates,
+	ExportCsv,
+	Snapshot,
+	CopyMetrics,
+	ToggleStatsPanel,
+	ToggleResourcesPanel,
+	ToggleLatencyPanel,
+	ToggleTimelinesPanel,
+	ToggleGrowthPanel,
+	ToggleLogPanel,
+	CycleFocusMetric,
+	ToggleEnabled,
+	MarkForCompare,
+	CompareView,
+	ToggleFollow,
+	ToggleGroup,
+	NextNode,
+	PreviousNode,
+	ScrollUp,
+	ScrollDown,
+	ScrollTop,
+	ScrollBottom,
+}
+
+pub struct KeyBinding {
+	pub keys: &'static [BindingKey],
+	pub action: Action,
+	pub name: &'static str, // Of the action, for --bind
+	pub description: &'static str,
+}
+
+use BindingKey::*;
+
+///! Every key handled outside of filter editing, in the order shown by the help overlay
+pub static KEY_BINDINGS: &[KeyBinding] = &[
+	KeyBinding { keys: &[Char('?'), Char('h'), Char('H')], action: Action::ToggleHelp, name: "toggle-help", description: "Show or hide this help (Esc also hides it)" },
+	KeyBinding { keys: &[Char('q'), Char('Q')], action: Action::Quit, name: "quit", description: "Quit" },
+	KeyBinding { keys: &[Char('s'), Char('S')], action: Action::SummaryView, name: "summary-view", description: "Summary of all nodes" },
+	KeyBinding { keys: &[Char('v'), Char('V')], action: Action::NodeView, name: "node-view", description: "Node view" },
+	KeyBinding { keys: &[Char('d'), Char('D')], action: Action::ToggleDetail, name: "toggle-detail", description: "Detail view of the node shown, or back to the node view" },
+	KeyBinding { keys: &[Char('g')], action: Action::DebugView, name: "debug-view", description: "Debug view" },
+	KeyBinding { keys: &[Char('e'), Char('E')], action: Action::WarningsView, name: "warnings-view", description: "Recent warnings and errors of every node" },
+	KeyBinding { keys: &[Char('z'), Char('Z')], action: Action::DiagnosticsView, name: "diagnostics-view", description: "Lines vdash couldn't parse, or back to the node view" },
+	KeyBinding { keys: &[Char('%')], action: Action::HeatmapView, name: "heatmap-view", description: "Heatmap of a metric of every node, or its next metric" },
+	KeyBinding { keys: &[Char('k')], action: Action::CycleSortKey, name: "cycle-sort-key", description: "Change the metric the summary is sorted by" },
+	KeyBinding { keys: &[Char('K')], action: Action::ReverseSort, name: "reverse-sort", description: "Reverse the order of the summary" },
+	KeyBinding { keys: &[Char('/')], action: Action::StartFilter, name: "start-filter", description: "Filter nodes by logfile (Enter keeps, Esc clears)" },
+	KeyBinding { keys: &[Char('\'')], action: Action::JumpToNode, name: "jump-to-node", description: "Jump to a node by typing its name (Enter keeps, Esc goes back)" },
+	KeyBinding { keys: &[Char('r'), Char('R')], action: Action::ResetCounters, name: "reset-counters", description: "Reset the counters of the node shown" },
+	KeyBinding { keys: &[Char('p'), Char('P'), Char(' ')], action: Action::TogglePause, name: "toggle-pause", description: "Pause or resume reading the logfiles" },
+	KeyBinding { keys: &[Char('i'), Char('I'), Char('+')], action: Action::ZoomIn, name: "zoom-in", description: "Zoom the timeline in" },
+	KeyBinding { keys: &[Char('o'), Char('O'), Char('-')], action: Action::ZoomOut, name: "zoom-out", description: "Zoom the timeline out" },
+	KeyBinding { keys: &[Char('l'), Char('L')], action: Action::ToggleLogScale, name: "toggle-log-scale", description: "Linear or logarithmic timelines" },
+	KeyBinding { keys: &[Char('m'), Char('M')], action: Action::ToggleOverlay, name: "toggle-overlay", description: "Overlay PUTS and GETS (and bytes in and out) on one timeline" },
+	KeyBinding { keys: &[Char('n'), Char('N')], action: Action::ToggleNormalize, name: "toggle-normalize", description: "Scale overlaid timelines to a shared or their own maximum" },
+	KeyBinding { keys: &[Char('t'), Char('T')], action: Action::ToggleTimelineFocus, name: "toggle-timeline-focus", description: "Timeline cursor, moved with left/right" },
+	KeyBinding { keys: &[Char('[')], action: Action::FreezeTimelines, name: "freeze-timelines", description: "Freeze the timelines of the node shown, drawn faintly behind the live ones to compare" },
+	KeyBinding { keys: &[Char(']')], action: Action::ThawTimelines, name: "thaw-timelines", description: "Clear the frozen timelines of the node shown" },
+	KeyBinding { keys: &[Char('@')], action: Action::DropMarker, name: "drop-marker", description: "Drop a marker, after which each node's counters also show what they have added since" },
+	KeyBinding { keys: &[Char('_')], action: Action::ClearMarker, name: "clear-marker", description: "Clear the marker" },
+	KeyBinding { keys: &[Char('!')], action: Action::AcknowledgeAlert, name: "acknowledge-alert", description: "Acknowledge the alert at the top of the banner, muting it until it clears" },
+	KeyBinding { keys: &[Char('c'), Char('C')], action: Action::ToggleRates, name: "toggle-rates", description: "Show counters as totals since start, totals this run of the node, or rates per minute" },
+	KeyBinding { keys: &[Char('x'), Char('X')], action: Action::ExportCsv, name: "export-csv", description: "Export the timelines of every node to CSV (see --export-csv)" },
+	KeyBinding { keys: &[Char('u'), Char('U')], action: Action::Snapshot, name: "snapshot", description: "Write the screen as text (see --snapshot)" },
+	KeyBinding { keys: &[Char('&')], action: Action::CopyMetrics, name: "copy-metrics", description: "Copy the status of the node shown to the clipboard, as text" },
+	KeyBinding { keys: &[Char('1')], action: Action::ToggleStatsPanel, name: "toggle-stats-panel", description: "Hide or show the node's stats (see --hide)" },
+	KeyBinding { keys: &[Char('2')], action: Action::ToggleResourcesPanel, name: "toggle-resources-panel", description: "Hide or show the node's resources" },
+	KeyBinding { keys: &[Char('3')], action: Action::ToggleLatencyPanel, name: "toggle-latency-panel", description: "Hide or show the detail view's latencies" },
+	KeyBinding { keys: &[Char('4')], action: Action::ToggleTimelinesPanel, name: "toggle-timelines-panel", description: "Hide or show the detail view's timelines" },
+	KeyBinding { keys: &[Char('5')], action: Action::ToggleGrowthPanel, name: "toggle-growth-panel", description: "Hide or show the detail view's storage growth" },
+	KeyBinding { keys: &[Char('6')], action: Action::ToggleLogPanel, name: "toggle-log-panel", description: "Hide or show the detail view's log" },
+	KeyBinding { keys: &[Char('*')], action: Action::CycleFocusMetric, name: "cycle-focus-metric", description: "Emphasise the panels of errors, storage, traffic or peers and dim the rest, or neither" },
+	KeyBinding { keys: &[Char('b'), Char('B')], action: Action::ToggleEnabled, name: "toggle-enabled", description: "Disable the node shown, ignoring what it logs, or enable it again" },
+	KeyBinding { keys: &[Char('a'), Char('A')], action: Action::MarkForCompare, name: "mark-for-compare", description: "Mark or unmark the node shown for comparison (up to two)" },
+	KeyBinding { keys: &[Char('w'), Char('W')], action: Action::CompareView, name: "compare-view", description: "Compare the two marked nodes side by side, or back to the node view" },
+	KeyBinding { keys: &[Char('f'), Char('F')], action: Action::ToggleFollow, name: "toggle-follow", description: "Follow new lines in the detail view's log, or keep it still" },
+	KeyBinding { keys: &[Char('#')], action: Action::ToggleGroup, name: "toggle-group", description: "Collapse or expand the summary's group of the node shown (see --names)" },
+	KeyBinding { keys: &[Right, Tab], action: Action::NextNode, name: "next-node", description: "Next node" },
+	KeyBinding { keys: &[Left], action: Action::PreviousNode, name: "previous-node", description: "Previous node" },
+	KeyBinding { keys: &[Up], action: Action::ScrollUp, name: "scroll-up", description: "Scroll the logfile (or the detail view's log or the warnings) up, or focus the summary's row above" },
+	KeyBinding { keys: &[Down], action: Action::ScrollDown, name: "scroll-down", description: "Scroll the logfile (or the detail view's log or the warnings) down, or focus the summary's row below" },
+	KeyBinding { keys: &[Home], action: Action::ScrollTop, name: "scroll-top", description: "Scroll to the start of the logfile (or the detail view's log), or focus the summary's first row" },
+	KeyBinding { keys: &[End], action: Action::ScrollBottom, name: "scroll-bottom", description: "Scroll to the end of the logfile, following the detail view's log, or focus the summary's last row" },
+];
+
+///! The keys changed by --keys vim. Keys taken from other actions are moved to
+///! ones which are free
+static VIM_BINDINGS: &[(Action, &[BindingKey])] = &[
+	(Action::ToggleHelp, &[Char('?')]),
+	(Action::DebugView, &[Char('e')]),
+	(Action::CycleSortKey, &[Char('y')]),
+	(Action::ReverseSort, &[Char('Y')]),
+	(Action::ToggleLogScale, &[Char('L')]),
+	(Action::PreviousNode, &[Left, Char('h')]),
+	(Action::NextNode, &[Right, Tab, Char('l')]),
+	(Action::ScrollUp, &[Up, Char('k')]),
+	(Action::ScrollDown, &[Down, Char('j')]),
+	(Action::ScrollTop, &[Home, Char('g')]),
+	(Action::ScrollBottom, &[End, Char('G')]),
+];
+
+///! The sets of key bindings chosen with --keys
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyPreset {
+	Default,
+	Vim,
+}
+
+impl std::str::FromStr for KeyPreset {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<KeyPreset, String> {
+		match s {
+			"default" => Ok(KeyPreset::Default),
+			"vim" => Ok(KeyPreset::Vim),
+			_ => Err(format!("invalid keys '{}', expected default or vim", s)),
+		}
+	}
+}
+
+///! The keys of every action, as resolved from a preset and any --bind
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap {
+	bindings: Vec<(Action, Vec<BindingKey>)>, // In the order of KEY_BINDINGS
+}
+
+impl Default for KeyMap {
+	fn default() -> Self {
+		KeyMap::preset(KeyPreset::Default)
+	}
+}
+
+impl KeyMap {
+	pub fn preset(preset: KeyPreset) -> KeyMap {
+		let mut key_map = KeyMap {
+			bindings: KEY_BINDINGS.iter().map(|binding| (binding.action, binding.keys.to_vec())).collect(),
+		};
+		if preset == KeyPreset::Vim {
+			for (action, keys) in VIM_BINDINGS {
+				key_map.bind(*action, keys);
+			}
+		}
+		key_map
+	}
+
+	///! Replace the keys of an action, taking them from any other action
+	pub fn bind(&mut self, action: Action, keys: &[BindingKey]) {
+		for (bound_action, bound_keys) in self.bindings.iter_mut() {
+			if *bound_action == action {
+				*bound_keys = keys.to_vec();
+			} else {
+				bound_keys.retain(|key| !keys.contains(key));
+			}
+		}
+	}
+
+	pub fn action_for_key(&self, key: BindingKey) -> Option<Action> {
+		self.bindings
+			.iter()
+			.find(|(_action, keys)| keys.contains(&key))
+			.map(|(action, _keys)| *action)
+	}
+
+	pub fn keys_for(&self, action: Action) -> &[BindingKey] {
+		match self.bindings.iter().find(|(bound_action, _keys)| *bound_action == action) {
+			Some((_action, keys)) => keys,
+			None => &[],
+		}
+	}
+}
+
+///! Parses a --bind argument such as 'quit=x' or 'next-node=right,tab,n'.
+///! Keys are single characters or named as in the help overlay
+pub fn parse_key_binding(arg: &str) -> Result<(Action, Vec<BindingKey>), String> {
+	let (name, keys) = match arg.find('=') {
+		Some(index) => (arg[..index].trim(), &arg[index + 1..]),
+		None => return Err(format!("invalid binding '{}', expected action=keys", arg)),
+	};
+	let action = match KEY_BINDINGS.iter().find(|binding| binding.name == name) {
+		Some(binding) => binding.action,
+		None => return Err(format!("unknown action '{}' in binding '{}'", name, arg)),
+	};
+	let keys = keys.split(',').map(parse_key).collect::<Result<Vec<BindingKey>, String>>()?;
+	Ok((action, keys))
+}
+
+fn parse_key(name: &str) -> Result<BindingKey, String> {
+	let mut chars = name.chars();
+	if let (Some(c), None) = (chars.next(), chars.next()) {
+		return Ok(Char(c));
+	}
+	match name.trim().to_lowercase().as_str() {
+		"space" => Ok(Char(' ')),
+		"left" => Ok(Left),
+		"right" => Ok(Right),
+		"up" => Ok(Up),
+		"down" => Ok(Down),
+		"tab" => Ok(Tab),
+		"enter" => Ok(Enter),
+		"esc" => Ok(Esc),
+		"backspace" => Ok(Backspace),
+		"home" => Ok(Home),
+		"end" => Ok(End),
+		_ => Err(format!("unknown key '{}'", name)),
+	}
+}
+
+///! How a key is shown in the help overlay
+pub fn key_name(key: BindingKey) -> String {
+	match key {
+		Char(' ') => String::from("space"),
+		Char(c) => c.to_string(),
+		Left => String::from("left"),
+		Right => String::from("right"),
+		Up => String::from("up"),
+		Down => String::from("down"),
+		Tab => String::from("tab"),
+		Enter => String::from("enter"),
+		Esc => String::from("esc"),
+		Backspace => String::from("backspace"),
+		Home => String::from("home"),
+		End => String::from("end"),
+	}
+}
+
+///! The keys of an action for the help overlay. Upper case letters are
+///! left out when the lower case letter does the same
+pub fn key_names(keys: &[BindingKey]) -> String {
+	let names: Vec<String> = keys
+		.iter()
+		.filter(|key| match key {
+			Char(c) if c.is_ascii_uppercase() => !keys.contains(&Char(c.to_ascii_lowercase())),
+			_ => true,
+		})
+		.map(|key| key_name(*key))
+		.collect();
+	names.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn every_action_has_a_binding() {
+		for action in &[
+			Action::Quit,
+			Action::ToggleHelp,
+			Action::SummaryView,
+			Action::NodeView,
+			Action::ToggleDetail,
+			Action::DebugView,
+			Action::WarningsView,
+			Action::DiagnosticsView,
+			Action::HeatmapView,
+			Action::CycleSortKey,
+			Action::ReverseSort,
+			Action::StartFilter,
+			Action::JumpToNode,
+			Action::ResetCounters,
+			Action::TogglePause,
+			Action::ZoomIn,
+			Action::ZoomOut,
+			Action::ToggleLogScale,
+			Action::ToggleOverlay,
+			Action::ToggleNormalize,
+			Action::ToggleTimelineFocus,
+			Action::FreezeTimelines,
+			Action::ThawTimelines,
+			Action::DropMarker,
+			Action::ClearMarker,
+			Action::AcknowledgeAlert,
+			Action::ToggleRates,
+			Action::ExportCsv,
+			Action::Snapshot,
+			Action::CopyMetrics,
+			Action::ToggleStatsPanel,
+			Action::ToggleResourcesPanel,
+			Action::ToggleLatencyPanel,
+			Action::ToggleTimelinesPanel,
+			Action::ToggleGrowthPanel,
+			Action::ToggleLogPanel,
+			Action::CycleFocusMetric,
+			Action::ToggleEnabled,
+			Action::MarkForCompare,
+			Action::CompareView,
+			Action::ToggleFollow,
+			Action::ToggleGroup,
+			Action::NextNode,
+			Action::PreviousNode,
+			Action::ScrollUp,
+			Action::ScrollDown,
+			Action::ScrollTop,
+			Action::ScrollBottom,
+		] {
+			assert!(KEY_BINDINGS.iter().any(|binding| binding.action == *action), "no binding for {:?}", action);
+		}
+	}
+
+	#[test]
+	fn binds_each_handled_key_once() {
+		let key_map = KeyMap::default();
+		let handled = "?hHqQsSvVdDgeEzZ%kK/rRpP iI+oO-lLmMnNtT[]cCxXuUbBaAwWfF#@_!&123456*'";
+		for c in handled.chars() {
+			assert!(key_map.action_for_key(Char(c)).is_some(), "'{}' is not bound", c);
+		}
+		for key in &[Left, Right, Up, Down, Tab, Home, End] {
+			assert!(key_map.action_for_key(*key).is_some(), "{:?} is not bound", key);
+		}
+		assert_eq!(key_map.action_for_key(Char('k')), Some(Action::CycleSortKey));
+		assert_eq!(key_map.action_for_key(Char('K')), Some(Action::ReverseSort));
+		assert_eq!(key_map.action_for_key(Char('j')), None);
+
+		let keys: Vec<BindingKey> = KEY_BINDINGS.iter().flat_map(|binding| binding.keys.iter().copied()).collect();
+		for (i, key) in keys.iter().enumerate() {
+			assert!(!keys[i + 1..].contains(key), "{:?} is bound more than once", key);
+		}
+	}
+
+	#[test]
+	fn names_binding_keys() {
+		let names: Vec<String> = KEY_BINDINGS.iter().map(|binding| key_names(binding.keys)).collect();
+		assert!(names.contains(&String::from("p, space")));
+		assert!(names.contains(&String::from("right, tab")));
+		assert!(names.contains(&String::from("k")));
+		assert!(names.contains(&String::from("K")));
+	}
+
+	#[test]
+	fn routes_keys_for_each_preset() {
+		let default = KeyMap::preset(KeyPreset::Default);
+		assert_eq!(default.action_for_key(Char('q')), Some(Action::Quit));
+		assert_eq!(default.action_for_key(Char('j')), None);
+		assert_eq!(default.action_for_key(Char('k')), Some(Action::CycleSortKey));
+		assert_eq!(default.action_for_key(Char('g')), Some(Action::DebugView));
+		assert_eq!(default.action_for_key(Down), Some(Action::ScrollDown));
+
+		let vim = KeyMap::preset(KeyPreset::Vim);
+		assert_eq!(vim.action_for_key(Char('q')), Some(Action::Quit));
+		assert_eq!(vim.action_for_key(Char('j')), Some(Action::ScrollDown));
+		assert_eq!(vim.action_for_key(Char('k')), Some(Action::ScrollUp));
+		assert_eq!(vim.action_for_key(Char('h')), Some(Action::PreviousNode));
+		assert_eq!(vim.action_for_key(Char('l')), Some(Action::NextNode));
+		assert_eq!(vim.action_for_key(Char('g')), Some(Action::ScrollTop));
+		assert_eq!(vim.action_for_key(Char('G')), Some(Action::ScrollBottom));
+		assert_eq!(vim.action_for_key(Char('y')), Some(Action::CycleSortKey));
+		assert_eq!(vim.action_for_key(Down), Some(Action::ScrollDown));
+
+		// Displaced actions keep a key, and no key does two things
+		for binding in KEY_BINDINGS {
+			assert!(!vim.keys_for(binding.action).is_empty(), "{:?} has no key", binding.action);
+		}
+		let keys: Vec<BindingKey> = KEY_BINDINGS.iter().flat_map(|binding| vim.keys_for(binding.action).to_vec()).collect();
+		for (i, key) in keys.iter().enumerate() {
+			assert!(!keys[i + 1..].contains(key), "{:?} is bound more than once", key);
+		}
+	}
+
+	#[test]
+	fn rebinds_keys() {
+		let mut key_map = KeyMap::default();
+		let (action, keys) = parse_key_binding("quit=x,esc").unwrap();
+		key_map.bind(action, &keys);
+		assert_eq!(key_map.action_for_key(Char('x')), Some(Action::Quit));
+		assert_eq!(key_map.action_for_key(Esc), Some(Action::Quit));
+		assert_eq!(key_map.action_for_key(Char('q')), None);
+		assert_eq!(key_map.action_for_key(Char('X')), Some(Action::ExportCsv));
+
+		// Taken from the action they were bound to
+		let (action, keys) = parse_key_binding("next-node=n,space").unwrap();
+		key_map.bind(action, &keys);
+		assert_eq!(key_map.action_for_key(Char('n')), Some(Action::NextNode));
+		assert_eq!(key_map.keys_for(Action::ToggleNormalize), &[Char('N')]);
+		assert_eq!(key_map.keys_for(Action::TogglePause), &[Char('p'), Char('P')]);
+
+		assert_eq!(parse_key_binding("toggle-pause=space"), Ok((Action::TogglePause, vec![Char(' ')])));
+		assert!(parse_key_binding("scroll-top=").is_err());
+		assert!(parse_key_binding("quit").is_err());
+		assert!(parse_key_binding("leave=x").is_err());
+		assert!(parse_key_binding("quit=pageup").is_err());
+	}
+}
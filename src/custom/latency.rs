@@ -0,0 +1,153 @@
+///! Round trip and response latencies, for builds which log them
+///!
+///! Lines such as 'Round trip time to peer: 12ms', 'rtt=850µs', 'latency 1.2s'
+///! or 'Response time: 35 ms' add a sample, and lines without a latency are
+///! ignored. Each node keeps the latest LATENCY_SAMPLES_MAX samples, from which
+///! the detail view shows the min, mean and 95th percentile, with a timeline
+///! of the mean
+use regex::Regex;
+use std::collections::VecDeque;
+
+///! Samples kept by each node, dropping the oldest
+pub const LATENCY_SAMPLES_MAX: usize = 1000;
+
+lazy_static::lazy_static! {
+	static ref LATENCY_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:round[ _-]?trip(?:[ _]time)?|rtt|latency|response[ _]time|responded in)\b[^\d\n]{0,20}?(?P<value>\d+(?:\.\d+)?)\s*(?P<unit>ns|µs|us|ms|millis(?:econds?)?|secs?|seconds?|s)\b").expect("The regex failed to compile. This is a bug.");
+}
+
+///! The latency logged in a line, in microseconds
+pub fn parse_latency(line: &str) -> Option<u64> {
+	let captures = LATENCY_PATTERN.captures(line)?;
+	let value = captures["value"].parse::<f64>().ok()?;
+	let micros_per_unit = match captures["unit"].to_lowercase().as_str() {
+		"ns" => 0.001,
+		"µs" | "us" => 1.0,
+		"ms" | "millis" | "millisecond" | "milliseconds" => 1_000.0,
+		_ => 1_000_000.0,
+	};
+	Some((value * micros_per_unit).round() as u64)
+}
+
+///! A latency in microseconds as shown, e.g. '850µs', '12.3ms' or '1.25s'
+pub fn format_latency(micros: f64) -> String {
+	if micros < 1_000.0 {
+		format!("{:.0}µs", micros)
+	} else if micros < 1_000_000.0 {
+		format!("{:.1}ms", micros / 1_000.0)
+	} else {
+		format!("{:.2}s", micros / 1_000_000.0)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyStats {
+	pub min: u64, // In microseconds, as are the others
+	pub mean: f64,
+	pub p95: u64,
+}
+
+///! The latest latencies logged by a node, oldest first
+#[derive(Debug, Clone)]
+pub struct LatencySamples {
+	samples: VecDeque<u64>,
+	capacity: usize,
+}
+
+impl LatencySamples {
+	pub fn new(capacity: usize) -> LatencySamples {
+		LatencySamples {
+			samples: VecDeque::with_capacity(capacity),
+			capacity,
+		}
+	}
+
+	pub fn push(&mut self, micros: u64) {
+		if self.capacity == 0 {
+			return;
+		}
+		if self.samples.len() == self.capacity {
+			self.samples.pop_front();
+		}
+		self.samples.push_back(micros);
+	}
+
+	pub fn len(&self) -> usize {
+		self.samples.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.samples.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.samples.clear();
+	}
+
+	///! The min, mean and 95th percentile (by nearest rank) of the samples, None without any
+	pub fn stats(&self) -> Option<LatencyStats> {
+		let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+		sorted.sort_unstable();
+		let min = *sorted.first()?;
+		let total: u128 = sorted.iter().map(|micros| u128::from(*micros)).sum();
+		let rank = (sorted.len() * 95).div_ceil(100);
+		Some(LatencyStats {
+			min,
+			mean: total as f64 / sorted.len() as f64,
+			p95: sorted[rank.max(1) - 1],
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_latencies_in_any_unit() {
+		assert_eq!(parse_latency("Round trip time to peer: 12ms"), Some(12_000));
+		assert_eq!(parse_latency("Round trip time: 12ms to peer 12D3KooW"), Some(12_000));
+		assert_eq!(parse_latency("rtt=850µs"), Some(850));
+		assert_eq!(parse_latency("RTT 850us"), Some(850));
+		assert_eq!(parse_latency("Network latency 1.25s"), Some(1_250_000));
+		assert_eq!(parse_latency("response time: 35 ms"), Some(35_000));
+		assert_eq!(parse_latency("Query responded in 1.5 milliseconds"), Some(1_500));
+		assert_eq!(parse_latency("round-trip 2 secs"), Some(2_000_000));
+		assert_eq!(parse_latency("latency 1500ns"), Some(2));
+		assert_eq!(parse_latency("latency is 12"), None); // No unit
+		assert_eq!(parse_latency("Successfully stored record in 12ms"), None);
+	}
+
+	#[test]
+	fn computes_min_mean_and_p95() {
+		let mut samples = LatencySamples::new(LATENCY_SAMPLES_MAX);
+		assert_eq!(samples.stats(), None);
+		for millis in 1..=20 {
+			samples.push(millis * 1_000);
+		}
+		assert_eq!(samples.stats(), Some(LatencyStats { min: 1_000, mean: 10_500.0, p95: 19_000 }));
+
+		samples.push(1_000_000); // A slow outlier moves the mean and p95, not the min
+		let stats = samples.stats().unwrap();
+		assert_eq!((stats.min, stats.p95), (1_000, 20_000));
+		assert_eq!(format_latency(stats.mean), "57.6ms");
+
+		let mut one = LatencySamples::new(10);
+		one.push(850);
+		assert_eq!(one.stats(), Some(LatencyStats { min: 850, mean: 850.0, p95: 850 }));
+		assert_eq!(format_latency(850.0), "850µs");
+		assert_eq!(format_latency(1_250_000.0), "1.25s");
+	}
+
+	#[test]
+	fn keeps_a_bounded_number_of_samples() {
+		let mut samples = LatencySamples::new(3);
+		for micros in &[100, 5, 6, 7] {
+			samples.push(*micros);
+		}
+		assert_eq!(samples.len(), 3);
+		assert_eq!(samples.stats().map(|stats| stats.min), Some(5)); // The oldest, 100, was dropped
+		samples.clear();
+		assert!(samples.is_empty());
+	}
+}
@@ -0,0 +1,148 @@
+///! Built-in pattern sets for the log formats of different safenode versions,
+///! chosen with --log-profile
+///!
+///! The layout of the time, level and source of each line has changed across
+///! versions, and so have the messages logged for GETs and PUTs. E.g.
+///!	legacy:		' INFO 2022-01-15T20:21:02.659471Z [sn/src/node/mod.rs:L211]: StoredNewChunk'
+///!	current:	'[2024-05-09T12:31:33.123456Z INFO sn_node::node] Successfully stored record'
+///!
+///! auto, the default, tries the patterns of every profile, so that either
+///! format is understood, at the cost of sometimes recognising a line of the
+///! other format in a log of one
+use regex::{Captures, Regex};
+
+static LEGACY_LAYOUT: &str =
+	r"(?P<module>\[.*\])* (?P<category>[A-Z]{4,6}) (?P<time_string>\d{4}-\d\d-\d\dT[^ ]+) (?P<source>\[.*\])(?P<message>.*)";
+static CURRENT_LAYOUT: &str =
+	r"^\[(?P<time_string>\d{4}-\d\d-\d\dT[^ \]]+) (?P<category>[A-Z]{4,6}) (?P<source>[^\]]*)\] ?(?P<message>.*)";
+
+static LEGACY_GETS: [&str; 1] = ["Getting chunk"];
+static LEGACY_PUTS: [&str; 2] = ["StoredNewChunk", "Editing Register success!"];
+static CURRENT_GETS: [&str; 1] = ["Retrieved record"];
+static CURRENT_PUTS: [&str; 1] = ["Successfully stored record"];
+
+lazy_static::lazy_static! {
+	static ref PROFILES: Vec<LogProfile> = vec![
+		LogProfile::new(
+			"auto",
+			&[LEGACY_LAYOUT, CURRENT_LAYOUT],
+			&[&LEGACY_GETS[..], &CURRENT_GETS[..]].concat(),
+			&[&LEGACY_PUTS[..], &CURRENT_PUTS[..]].concat(),
+		),
+		LogProfile::new("legacy", &[LEGACY_LAYOUT], &LEGACY_GETS, &LEGACY_PUTS),
+		LogProfile::new("current", &[CURRENT_LAYOUT], &CURRENT_GETS, &CURRENT_PUTS),
+	];
+}
+
+///! The patterns of one log format
+#[derive(Debug)]
+pub struct LogProfile {
+	pub name: &'static str,
+	layouts: Vec<Regex>, // Of the metadata of a line, tried in turn, see LogEntry::decode_metadata()
+	gets: Vec<&'static str>, // Messages of lines counted as a GET
+	puts: Vec<&'static str>,
+}
+
+impl LogProfile {
+	fn new(name: &'static str, layouts: &[&str], gets: &[&'static str], puts: &[&'static str]) -> LogProfile {
+		LogProfile {
+			name,
+			layouts: layouts
+				.iter()
+				.map(|layout| Regex::new(layout).expect("The regex failed to compile. This is a bug."))
+				.collect(),
+			gets: gets.to_vec(),
+			puts: puts.to_vec(),
+		}
+	}
+
+	///! The profile which understands every format, the default
+	pub fn auto() -> &'static LogProfile {
+		&PROFILES[0]
+	}
+
+	///! The category, time_string, source and message of a line, from the first
+	///! layout it matches
+	pub fn captures<'t>(&self, line: &'t str) -> Option<Captures<'t>> {
+		self.layouts.iter().find_map(|layout| layout.captures(line))
+	}
+
+	pub fn is_get(&self, line: &str) -> bool {
+		self.gets.iter().any(|message| line.contains(message))
+	}
+
+	pub fn is_put(&self, line: &str) -> bool {
+		self.puts.iter().any(|message| line.contains(message))
+	}
+}
+
+///! Parses --log-profile, e.g. 'legacy'
+pub fn parse_log_profile(name: &str) -> Result<&'static LogProfile, String> {
+	PROFILES
+		.iter()
+		.find(|profile| profile.name == name.trim())
+		.ok_or_else(|| format!("invalid log profile '{}', expected auto, legacy or current", name))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::app::{LogEntry, NodeMetrics};
+	use crate::custom::opt::Opt;
+	use chrono::Utc;
+	use structopt::StructOpt;
+
+	static LEGACY_LINES: [&str; 2] = [
+		" INFO 2022-01-15T20:21:02.659471Z [sn/src/node/mod.rs:L211]: StoredNewChunk 8a3f",
+		" INFO 2022-01-15T20:21:03.659471Z [sn/src/node/mod.rs:L230]: Getting chunk 8a3f",
+	];
+	static CURRENT_LINES: [&str; 2] = [
+		"[2024-05-09T12:31:33.123456Z INFO sn_node::put_validation] Successfully stored record 8a3f",
+		"[2024-05-09T12:31:34.123456Z INFO sn_node::node] Retrieved record 8a3f",
+	];
+
+	// The PUTS and GETS counted, and the lines with a time, of lines parsed under a profile
+	fn parse(profile: &str, lines: &[&str]) -> (u64, u64, usize) {
+		let mut metrics = NodeMetrics::new(&Opt::from_iter(vec!["vdash", "--log-profile", profile]));
+		let mut timed = 0;
+		for line in lines {
+			if LogEntry::decode_metadata(line, metrics.log_profile).is_some() {
+				timed += 1;
+			}
+			metrics.gather_metrics(line, &Utc::now()).unwrap();
+		}
+		(metrics.activity_puts, metrics.activity_gets, timed)
+	}
+
+	#[test]
+	fn parses_each_format_under_its_profile() {
+		assert_eq!(parse("legacy", &LEGACY_LINES), (1, 1, 2));
+		assert_eq!(parse("current", &CURRENT_LINES), (1, 1, 2));
+		assert_eq!(parse("auto", &LEGACY_LINES), (1, 1, 2));
+		assert_eq!(parse("auto", &CURRENT_LINES), (1, 1, 2));
+
+		// Lines of the other format have no time and aren't counted
+		assert_eq!(parse("legacy", &CURRENT_LINES), (0, 0, 0));
+		assert_eq!(parse("current", &LEGACY_LINES), (0, 0, 0));
+
+		let legacy = parse_log_profile("legacy").unwrap();
+		let metadata = LogEntry::decode_metadata(LEGACY_LINES[0], legacy).unwrap();
+		assert_eq!((metadata.category.as_str(), metadata.source.as_str()), ("INFO", "[sn/src/node/mod.rs:L211]"));
+		assert_eq!(metadata.message, ": StoredNewChunk 8a3f");
+		let current = parse_log_profile("current").unwrap();
+		let metadata = LogEntry::decode_metadata(CURRENT_LINES[1], current).unwrap();
+		assert_eq!((metadata.category.as_str(), metadata.source.as_str()), ("INFO", "sn_node::node"));
+		assert_eq!(metadata.message, "Retrieved record 8a3f");
+	}
+
+	#[test]
+	fn parses_profile_names() {
+		assert_eq!(parse_log_profile("legacy").map(|profile| profile.name), Ok("legacy"));
+		assert_eq!(parse_log_profile(" current").map(|profile| profile.name), Ok("current"));
+		assert_eq!(LogProfile::auto().name, "auto");
+		assert_eq!(
+			parse_log_profile("newest").map(|profile| profile.name),
+			Err(String::from("invalid log profile 'newest', expected auto, legacy or current"))
+		);
+	}
+}
@@ -1,4 +1,44 @@
+pub mod alerts;
+pub mod ansi;
 pub mod app;
+pub mod capacity;
+pub mod check;
+pub mod clipboard;
+pub mod csv;
+pub mod diagnostics;
+pub mod endpoint;
+pub mod flash;
+pub mod focus;
+pub mod format;
+pub mod fuzzy;
+pub mod heatmap;
+pub mod jump;
+pub mod keys;
+pub mod latency;
+pub mod log_profile;
+pub mod names;
+pub mod notify;
 pub mod opt;
+pub mod panels;
+pub mod parse_report;
+pub mod platform;
+pub mod prefs;
+pub mod prometheus;
+pub mod query;
+pub mod redraw;
+pub mod replay;
+pub mod rotated;
+pub mod run_for;
+pub mod screenshot;
+pub mod snapshot;
+pub mod spotlight;
+pub mod state;
+pub mod stdin;
+pub mod tail;
+pub mod theme;
+pub mod timelines;
 pub mod ui;
 pub mod ui_debug;
+pub mod user_metrics;
+pub mod validate;
+pub mod warnings;
@@ -0,0 +1,148 @@
+///! Friendly names for nodes, shown instead of their logfile paths
+///!
+///! Names come from a --names file, or from --name path=label which takes
+///! precedence. Unnamed nodes are shown by the basename of their logfile.
+///! A name may end with a tag, as in 'node-1#host-a', to group the node with
+///! others of the same tag in the summary
+use std::collections::HashMap;
+use std::path::Path;
+
+///! The group shown for nodes without a tag, when others have one
+pub const DEFAULT_GROUP: &str = "ungrouped";
+
+///! Parses a --name argument such as '/home/me/node-1/safenode.log=node-1',
+///! or '/home/me/node-1/safenode.log=node-1#host-a' with a tag
+pub fn parse_node_name(arg: &str) -> Result<(String, String), String> {
+	let (logfile, name) = match arg.rfind('=') {
+		Some(index) => (arg[..index].trim(), arg[index + 1..].trim()),
+		None => return Err(format!("invalid name '{}', expected path=label or path=label#tag", arg)),
+	};
+	if logfile.is_empty() || split_tag(name).is_none() {
+		return Err(format!("invalid name '{}', expected path=label or path=label#tag", arg));
+	}
+	Ok((logfile.to_string(), name.to_string()))
+}
+
+///! Splits 'node-1#host-a' into its name and tag. None if either is empty
+pub fn split_tag(name: &str) -> Option<(&str, Option<&str>)> {
+	let (name, tag) = match name.rfind('#') {
+		Some(index) => (name[..index].trim(), Some(name[index + 1..].trim())),
+		None => (name.trim(), None),
+	};
+	if name.is_empty() || tag == Some("") {
+		return None;
+	}
+	Some((name, tag))
+}
+
+///! The name shown for a logfile which hasn't been given one
+pub fn default_node_name(logfile: &str) -> String {
+	match Path::new(logfile).file_name() {
+		Some(basename) => basename.to_string_lossy().to_string(),
+		None => logfile.to_string(),
+	}
+}
+
+///! Names and tags of individual nodes, keyed by logfile path
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeNames {
+	names: HashMap<String, String>,
+	tags: HashMap<String, String>,
+}
+
+impl NodeNames {
+	pub fn load(path: &Path) -> Result<NodeNames, String> {
+		let toml = std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read node names {}: {}", path.display(), e))?;
+		NodeNames::from_toml(&toml).map_err(|e| format!("invalid node names {}: {}", path.display(), e))
+	}
+
+	///! Each entry maps a logfile to its name, and optionally a tag, e.g.
+	///! "/home/me/node-1/safenode.log" = "node-1#host-a"
+	pub fn from_toml(toml: &str) -> Result<NodeNames, String> {
+		let table: toml::value::Table = toml::from_str(toml).map_err(|e| e.to_string())?;
+		let mut names = NodeNames::default();
+		for (logfile, value) in table {
+			match value {
+				toml::Value::String(name) if split_tag(&name).is_some() => names.insert(&logfile, &name),
+				_ => return Err(format!("{}: expected a name such as \"node-1\" or \"node-1#host-a\"", logfile)),
+			}
+		}
+		Ok(names)
+	}
+
+	///! Name a logfile, tagging it if the name ends with '#tag'
+	pub fn insert(&mut self, logfile: &str, name: &str) {
+		let (name, tag) = split_tag(name).unwrap_or((name, None));
+		self.names.insert(logfile.to_string(), name.to_string());
+		match tag {
+			Some(tag) => self.tags.insert(logfile.to_string(), tag.to_string()),
+			None => self.tags.remove(logfile),
+		};
+	}
+
+	///! The name given to a logfile, or else its basename
+	pub fn name_for(&self, logfile: &str) -> String {
+		match self.names.get(logfile) {
+			Some(name) => name.clone(),
+			None => default_node_name(logfile),
+		}
+	}
+
+	///! The tag given to a logfile, if any
+	pub fn tag_for(&self, logfile: &str) -> Option<String> {
+		self.tags.get(logfile).cloned()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resolves_mapped_and_unmapped_names() {
+		let mut names = NodeNames::from_toml("\"/nodes/node-1/safenode.log\" = \"alpha\"\n").unwrap();
+		names.insert("/nodes/node-2/safenode.log", "beta");
+		assert_eq!(names.name_for("/nodes/node-1/safenode.log"), "alpha");
+		assert_eq!(names.name_for("/nodes/node-2/safenode.log"), "beta");
+		assert_eq!(names.name_for("/nodes/node-3/node-3.log"), "node-3.log");
+		assert_eq!(names.name_for("relative.log"), "relative.log");
+		assert_eq!(names.name_for("-"), "-");
+
+		assert!(NodeNames::from_toml("\"/nodes/node-1/safenode.log\" = 5").is_err());
+		assert!(NodeNames::from_toml("\"/nodes/node-1/safenode.log\" = \" \"").is_err());
+	}
+
+	#[test]
+	fn parses_name_arguments() {
+		assert_eq!(
+			parse_node_name("/nodes/node-1/safenode.log=alpha"),
+			Ok(("/nodes/node-1/safenode.log".to_string(), "alpha".to_string()))
+		);
+		assert_eq!(parse_node_name("a=b=c"), Ok(("a=b".to_string(), "c".to_string())));
+		assert!(parse_node_name("/nodes/node-1/safenode.log").is_err());
+		assert!(parse_node_name("=alpha").is_err());
+		assert!(parse_node_name("/nodes/node-1/safenode.log=").is_err());
+	}
+
+	#[test]
+	fn tags_names_ending_with_a_tag() {
+		let mut names = NodeNames::from_toml("\"/nodes/node-1/safenode.log\" = \"alpha#host-a\"\n").unwrap();
+		assert_eq!(names.name_for("/nodes/node-1/safenode.log"), "alpha");
+		assert_eq!(names.tag_for("/nodes/node-1/safenode.log"), Some(String::from("host-a")));
+
+		let (logfile, name) = parse_node_name("/nodes/node-2/safenode.log=beta # host-b").unwrap();
+		names.insert(&logfile, &name);
+		assert_eq!(names.name_for(&logfile), "beta");
+		assert_eq!(names.tag_for(&logfile), Some(String::from("host-b")));
+
+		// Renaming without a tag ungroups the node
+		names.insert("/nodes/node-1/safenode.log", "alpha");
+		assert_eq!(names.tag_for("/nodes/node-1/safenode.log"), None);
+		assert_eq!(names.tag_for("/nodes/node-3/safenode.log"), None);
+
+		assert!(parse_node_name("/nodes/node-1/safenode.log=alpha#").is_err());
+		assert!(parse_node_name("/nodes/node-1/safenode.log=#host-a").is_err());
+		assert!(NodeNames::from_toml("\"/nodes/node-1/safenode.log\" = \"alpha#\"").is_err());
+	}
+}
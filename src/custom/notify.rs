@@ -0,0 +1,212 @@
+///! Desktop notifications of error spikes, for --notify
+///!
+///! A node is reported when its errors per minute rise to the threshold,
+///! and not again until it has dropped below it and the debounce interval
+///! has passed, so a node erroring steadily doesn't cause a stream of them.
+///! A rise within the debounce interval is reported once it has passed, if
+///! the rate is still at the threshold.
+///! None are shown during --quiet-hours
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use std::collections::HashMap;
+use std::process::Command;
+use std::thread;
+
+///! Detects each node's error rate crossing a threshold, independent of
+///! how the notification is shown
+pub struct ErrorSpikeDetector {
+	threshold: u64,
+	debounce: Duration,
+	nodes: HashMap<String, NodeSpikeState>,
+}
+
+#[derive(Default)]
+struct NodeSpikeState {
+	reported: bool, // The rate has been at the threshold since last notified
+	last_notified: Option<DateTime<Utc>>,
+}
+
+impl ErrorSpikeDetector {
+	pub fn new(threshold: u64, debounce: Duration) -> ErrorSpikeDetector {
+		ErrorSpikeDetector {
+			threshold,
+			debounce,
+			nodes: HashMap::new(),
+		}
+	}
+
+	///! True if the node should be notified about: its rate is at the
+	///! threshold, this rise hasn't been reported and it wasn't notified about
+	///! within the debounce interval
+	pub fn check(&mut self, logfile: &str, errors_per_minute: u64, now: &DateTime<Utc>) -> bool {
+		let state = self.nodes.entry(logfile.to_string()).or_default();
+		if errors_per_minute < self.threshold {
+			state.reported = false;
+			return false;
+		}
+		if state.reported {
+			return false;
+		}
+		if let Some(last_notified) = state.last_notified {
+			if *now - last_notified < self.debounce {
+				return false;
+			}
+		}
+		state.reported = true;
+		state.last_notified = Some(*now);
+		true
+	}
+}
+
+///! Value of --quiet-hours, a daily window which may cross midnight
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuietHours {
+	pub start: NaiveTime,
+	pub end: NaiveTime, // Not itself quiet
+}
+
+impl QuietHours {
+	///! True if the time of day is within the window
+	pub fn contains(&self, time: NaiveTime) -> bool {
+		if self.start <= self.end {
+			self.start <= time && time < self.end
+		} else {
+			time >= self.start || time < self.end
+		}
+	}
+}
+
+impl std::str::FromStr for QuietHours {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<QuietHours, String> {
+		let invalid = || format!("invalid quiet hours '{}', expected HH:MM-HH:MM", s);
+		let mut times = s.split('-').map(|time| NaiveTime::parse_from_str(time.trim(), "%H:%M"));
+		let (start, end) = match (times.next(), times.next(), times.next()) {
+			(Some(Ok(start)), Some(Ok(end)), None) => (start, end),
+			_ => return Err(invalid()),
+		};
+		if start == end {
+			return Err(format!("quiet hours '{}' must start and end at different times", s));
+		}
+		Ok(QuietHours { start, end })
+	}
+}
+
+///! The title and text of the notification of a node's error spike
+pub fn error_spike_message(name: &str, errors_per_minute: u64) -> (String, String) {
+	(
+		format!("vdash: {} errors", name),
+		format!("{} is logging {} errors per minute", name, errors_per_minute),
+	)
+}
+
+///! Show a desktop notification using the OS's own tool, without waiting for it
+pub fn notify_desktop(title: &str, text: &str) -> std::io::Result<()> {
+	let mut command = desktop_notification_command(title, text)?;
+	let mut child = command.spawn()?;
+	thread::spawn(move || child.wait());
+	Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_notification_command(title: &str, text: &str) -> std::io::Result<Command> {
+	let mut command = Command::new("osascript");
+	command.arg("-e").arg(format!("display notification {:?} with title {:?}", text, title));
+	Ok(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn desktop_notification_command(title: &str, text: &str) -> std::io::Result<Command> {
+	let mut command = Command::new("notify-send");
+	command.arg(title).arg(text);
+	Ok(command)
+}
+
+#[cfg(not(unix))]
+fn desktop_notification_command(_title: &str, _text: &str) -> std::io::Result<Command> {
+	Err(std::io::Error::new(
+		std::io::ErrorKind::Other,
+		"desktop notifications are not supported on this platform",
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::TimeZone;
+
+	#[test]
+	fn notifies_when_rate_crosses_threshold() {
+		let start = Utc.ymd(2022, 1, 15).and_hms(20, 0, 0);
+		let mut detector = ErrorSpikeDetector::new(10, Duration::minutes(5));
+		assert!(!detector.check("a.log", 5, &start));
+		assert!(detector.check("a.log", 12, &start));
+
+		// Staying above the threshold isn't a new crossing
+		assert!(!detector.check("a.log", 30, &(start + Duration::minutes(10))));
+
+		// Nodes are independent
+		assert!(detector.check("b.log", 10, &start));
+	}
+
+	#[test]
+	fn debounces_repeated_crossings() {
+		let start = Utc.ymd(2022, 1, 15).and_hms(20, 0, 0);
+		let mut detector = ErrorSpikeDetector::new(10, Duration::minutes(5));
+		assert!(detector.check("a.log", 20, &start));
+		assert!(!detector.check("a.log", 0, &(start + Duration::minutes(1))));
+		assert!(!detector.check("a.log", 20, &(start + Duration::minutes(2)))); // Too soon
+		assert!(!detector.check("a.log", 0, &(start + Duration::minutes(3))));
+		assert!(detector.check("a.log", 20, &(start + Duration::minutes(6))));
+	}
+
+	#[test]
+	fn reports_a_rise_within_the_debounce_once_it_has_passed() {
+		let start = Utc.ymd(2022, 1, 15).and_hms(20, 0, 0);
+		let mut detector = ErrorSpikeDetector::new(10, Duration::minutes(5));
+		assert!(detector.check("a.log", 20, &start));
+		assert!(!detector.check("a.log", 0, &(start + Duration::minutes(1))));
+		assert!(!detector.check("a.log", 20, &(start + Duration::minutes(2)))); // Too soon, so kept pending
+		assert!(!detector.check("a.log", 25, &(start + Duration::minutes(4))));
+		assert!(detector.check("a.log", 25, &(start + Duration::minutes(5))));
+		assert!(!detector.check("a.log", 25, &(start + Duration::minutes(6)))); // Reported
+	}
+
+	#[test]
+	fn keeps_quiet_within_the_window() {
+		let time = |hour, minute| NaiveTime::from_hms(hour, minute, 0);
+		let daytime: QuietHours = "09:00-17:30".parse().unwrap();
+		assert!(daytime.contains(time(9, 0)));
+		assert!(daytime.contains(time(12, 0)));
+		assert!(!daytime.contains(time(17, 30)));
+		assert!(!daytime.contains(time(8, 59)));
+
+		let overnight: QuietHours = "22:00-07:00".parse().unwrap();
+		assert!(overnight.contains(time(22, 0)));
+		assert!(overnight.contains(time(23, 59)));
+		assert!(overnight.contains(time(0, 0)));
+		assert!(overnight.contains(time(6, 59)));
+		assert!(!overnight.contains(time(7, 0)));
+		assert!(!overnight.contains(time(12, 0)));
+		assert!(!overnight.contains(time(21, 59)));
+	}
+
+	#[test]
+	fn reports_bad_quiet_hours() {
+		assert_eq!("22:00".parse::<QuietHours>(), Err(String::from("invalid quiet hours '22:00', expected HH:MM-HH:MM")));
+		assert!("25:00-07:00".parse::<QuietHours>().is_err());
+		assert!("22:00-07:00-08:00".parse::<QuietHours>().is_err());
+		assert_eq!(
+			"07:00-07:00".parse::<QuietHours>(),
+			Err(String::from("quiet hours '07:00-07:00' must start and end at different times"))
+		);
+	}
+
+	#[test]
+	fn describes_error_spike() {
+		assert_eq!(
+			error_spike_message("node-1", 42),
+			(String::from("vdash: node-1 errors"), String::from("node-1 is logging 42 errors per minute"))
+		);
+	}
+}
@@ -2,10 +2,29 @@
 ///!
 ///! Edit src/custom/opt.rs to create a customised fork of logtail-dash
 
-pub static MIN_TIMELINE_STEPS: usize = 10;
-
 pub use structopt::StructOpt;
 
+use crate::custom::app::{DashViewMain, ReadFrom, SortKey};
+use crate::custom::capacity::parse_size;
+use crate::custom::check::HealthWeights;
+use crate::custom::endpoint::parse_node_metrics_url;
+use crate::custom::format::{parse_compact_threshold, Units};
+use crate::custom::keys::{parse_key_binding, Action, BindingKey, KeyPreset};
+use crate::custom::log_profile::{parse_log_profile, LogProfile};
+use crate::custom::notify::QuietHours;
+use crate::custom::names::parse_node_name;
+use crate::custom::panels::HiddenPanels;
+use crate::custom::platform::{logfile_key, strip_verbatim_prefix};
+use crate::custom::replay::parse_speed;
+use crate::custom::theme::Palette;
+use crate::custom::timelines::{parse_chart_style, parse_duration, parse_good_direction, GoodDirection, TimelineSteps};
+use crate::custom::ui::widgets::sparkline::{ChartStyle, Downsample, SparkStyle};
+use crate::custom::ui::{PrimaryGauge, SummaryColumns};
+use crate::shared::logging::parse_log_level;
+
+///! Shortest --tick-rate accepted, as faster redraws only burn CPU
+pub static MIN_TICK_RATE: u64 = 50;
+
 #[derive(StructOpt, Debug)]
 #[structopt(
 	about = "Monitor Safe Network nodes in the terminal.\nNavigate using tab and arrow keys."
@@ -15,22 +34,378 @@ pub struct Opt {
 	#[structopt(short = "l", long, default_value = "100")]
 	pub lines_max: usize,
 
-	/// Event update tick in milliseconds
+	/// Number of raw lines to keep for each node, as shown in the detail view
+	#[structopt(long, default_value = "50")]
+	pub tail_lines: usize,
+
+	/// Longer lines are cut to this many characters, ending with '…', before
+	/// they are parsed, kept or shown. 0 for no limit
+	#[structopt(long, default_value = "4096")]
+	pub max_line_len: usize,
+
+	/// Number of recent WARN and ERROR lines to keep from all nodes, as shown
+	/// in the warnings view
 	#[structopt(long, default_value = "200")]
+	pub warnings_max: usize,
+
+	/// Number of lines vdash couldn't parse to keep from all nodes, as shown
+	/// in the diagnostics view ('z')
+	#[structopt(long, default_value = "200")]
+	pub diagnostics_max: usize,
+
+	/// Number of parsed log entries and node activities to keep for each node
+	#[structopt(long, default_value = "1000")]
+	pub history_max: usize,
+
+	/// File to which each line vdash couldn't parse is appended, with its node
+	/// and what was wrong with it
+	#[structopt(long, parse(from_os_str))]
+	pub debug_log: Option<std::path::PathBuf>,
+
+	/// File to which vdash's own log records are appended, for troubleshooting
+	/// vdash itself: logfiles opened and rotated, lines it couldn't parse and
+	/// the metrics server starting and stopping. Without it RUST_LOG sends them to stderr
+	#[structopt(long, parse(from_os_str))]
+	pub log_file: Option<std::path::PathBuf>,
+
+	/// Least severe of vdash's own records written to --log-file: off, error,
+	/// warn, info, debug or trace
+	#[structopt(long, default_value = "info", parse(try_from_str = parse_log_level))]
+	pub log_level: log::LevelFilter,
+
+	/// Milliseconds between dashboard updates (minimum 50). Keys are handled
+	/// as they are pressed whatever the rate
+	#[structopt(long, default_value = "200", parse(try_from_str = parse_tick_rate))]
 	pub tick_rate: u64,
 
-	/// Steps (width) of each timeline, helps tweak right justification.
+	/// Milliseconds between dashboard updates while the terminal reports it is
+	/// not focused, e.g. in a background tmux pane. Logs are still read as they
+	/// arrive. Termion only: vdash-crossterm gets no focus events from its
+	/// crossterm version, so always updates at --tick-rate and ignores this
+	#[structopt(long, default_value = "2000", parse(try_from_str = parse_tick_rate))]
+	pub idle_tick_rate: u64,
+
+	/// Quit after this many seconds, as if 'q' had been pressed, e.g. to take screenshots in CI
+	#[structopt(long)]
+	pub run_for: Option<u64>,
+
+	/// Steps (width) of each timeline, helps tweak right justification. Either a
+	/// number of steps for the default timelines, or timelines and their steps
+	/// such as '1m:60,1h:48,1d:30,1w:52' (units: s, m, h, d, w, y)
 	#[structopt(short, long, default_value = "210")]
-	pub timeline_steps: usize,
+	pub timeline_steps: TimelineSteps,
+
+	/// Start timeline buckets on wall-clock boundaries, such as the top of the
+	/// hour or midnight UTC, rather than when vdash starts
+	#[structopt(long)]
+	pub align_buckets: bool,
 
-	/// Ignore any existing logfile content
+	/// Where to start reading each logfile: start, to replay its history, or
+	/// end, to show only lines added from now on
+	#[structopt(long, default_value = "start")]
+	pub from: ReadFrom,
+
+	/// Ignore any existing logfile content, the same as --from end
 	#[structopt(short, long)]
 	pub ignore_existing: bool,
 
-	/// One or more logfiles to monitor
+	/// Before reading each logfile, replay its rotated and gzipped logfiles
+	/// from the same directory (e.g. safenode.log.1.gz), oldest first
+	#[structopt(long)]
+	pub replay_gz: bool,
+
+	/// Replay a whole logfile at --speed, paced by the times its lines were
+	/// logged, with the timelines following the times replayed (e.g. for a post-mortem)
+	#[structopt(long)]
+	pub replay: Option<String>,
+
+	/// How many times faster than it was logged to replay the --replay logfile
+	#[structopt(long, default_value = "60", parse(try_from_str = parse_speed))]
+	pub speed: f64,
+
+	/// The log format of the nodes' safenode version: legacy, current or auto
+	/// to understand either (see src/custom/log_profile.rs)
+	#[structopt(long, default_value = "auto", parse(try_from_str = parse_log_profile))]
+	pub log_profile: &'static LogProfile,
+
+	/// One or more logfiles to monitor, or glob patterns such as 'nodes/*/logs/safenode.log'
 	#[structopt(name = "LOGFILE")]
 	pub files: Vec<String>,
 
+	/// Interval in seconds between rescans of glob patterns for new logfiles
+	#[structopt(long, default_value = "10")]
+	pub glob_rescan: u64,
+
+	/// Monitor a logfile given more than once, such as through a symlink or
+	/// overlapping glob patterns, as a node each time rather than once
+	#[structopt(long)]
+	pub keep_duplicates: bool,
+
+	/// Highlight nodes which have not logged anything for this long, e.g. 90s, 5m or 1h
+	#[structopt(long, default_value = "5m", parse(try_from_str = parse_duration))]
+	pub stale_after: chrono::Duration,
+
+	/// Average rates per minute, such as errors per minute, over this long up to
+	/// the latest, e.g. 30s, 5m or 1h, for steadier numbers over longer windows
+	#[structopt(long, default_value = "1m", parse(try_from_str = parse_duration))]
+	pub rate_window: chrono::Duration,
+
+	/// How timelines with more buckets than fit are combined into columns:
+	/// max (which keeps spikes visible) or mean
+	#[structopt(long, default_value = "max")]
+	pub downsample: Downsample,
+
+	/// Glyphs the timelines are drawn with: bars, braille or shaded, for
+	/// fonts which don't show the partial blocks of bars well
+	#[structopt(long, default_value = "bars")]
+	pub spark_style: SparkStyle,
+
+	/// How timelines are charted: bars, line or area (a line shaded below),
+	/// for every timeline, or for one with e.g. 'bytes_in=area'. May be repeated
+	#[structopt(long = "chart-style", parse(try_from_str = parse_chart_style), number_of_values = 1)]
+	pub chart_styles: Vec<(Option<String>, ChartStyle)>,
+
+	/// Colour each timeline by its trend over the columns shown, good or bad
+	/// for its metric (e.g. errors falling is good), or neutral when flat
+	#[structopt(long)]
+	pub trend_colors: bool,
+
+	/// Which way is good for a timeline with --trend-colors, e.g. 'store_cost=up'
+	/// or 'peers=down'. May be repeated
+	#[structopt(long = "good-direction", parse(try_from_str = parse_good_direction), number_of_values = 1)]
+	pub good_directions: Vec<(String, GoodDirection)>,
+
+	/// Columns of the summary table in order, of node, name, puts, gets, errors,
+	/// errors_per_minute, storage, storage_ratio, rewards, uptime and health
+	#[structopt(long, default_value = "node,storage,errors_per_minute,rewards,name")]
+	pub columns: SummaryColumns,
+
+	/// How many of the busiest nodes, by lines logged per minute, to list in the
+	/// top talkers panel beside the summary (0 to hide it)
+	#[structopt(long, default_value = "5")]
+	pub top_talkers: usize,
+
+	/// Units for sizes: si (kB, MB, GB) or binary (KiB, MiB, GiB). By default
+	/// storage is shown in si units and bytes transferred in binary units
+	#[structopt(long)]
+	pub units: Option<Units>,
+
+	/// Separate the thousands of counts with commas, e.g. 1,234,567
+	#[structopt(long)]
+	pub group_digits: bool,
+
+	/// Decimal places of rates, percentages, sizes and rewards. By default each
+	/// keeps its own, e.g. one for rates and three for rewards
+	#[structopt(long)]
+	pub precision: Option<usize>,
+
+	/// Show counts in the summary of at least this many with a K, M, B or T
+	/// suffix, e.g. 1.48M, to --precision decimal places or else two. Accepts a
+	/// suffix itself, as in 100K
+	#[structopt(long, parse(try_from_str = parse_compact_threshold))]
+	pub compact_counts: Option<u64>,
+
+	/// Highlight timeline buckets more than this many standard deviations above
+	/// the mean of those shown (0 to disable)
+	#[structopt(long, default_value = "3")]
+	pub spike_sigma: f64,
+
+	/// Storage capacity of nodes which don't log one, e.g. 500GB or 2TB
+	#[structopt(long, parse(try_from_str = parse_size))]
+	pub node_capacity: Option<u64>,
+
+	/// TOML file of capacities for individual nodes, e.g. "/path/to/safenode.log" = "2TB"
+	#[structopt(long, parse(from_os_str))]
+	pub node_capacities: Option<std::path::PathBuf>,
+
+	/// Show a desktop notification when a node's errors per minute rise to --notify-errors
+	#[structopt(long)]
+	pub notify: bool,
+
+	/// Errors per minute at which --notify reports a node
+	#[structopt(long, default_value = "10")]
+	pub notify_errors: u64,
+
+	/// Least time between --notify notifications about the same node, e.g. 90s, 5m or 1h
+	#[structopt(long, default_value = "5m", parse(try_from_str = parse_duration))]
+	pub notify_debounce: chrono::Duration,
+
+	/// Local times between which --notify stays quiet, as HH:MM-HH:MM such as
+	/// 22:00-07:00. Alerts are still shown on screen
+	#[structopt(long)]
+	pub quiet_hours: Option<QuietHours>,
+
+	/// TOML file of names shown for nodes instead of their logfiles, e.g. "/path/to/safenode.log" = "node-1#host-a"
+	/// where the optional #tag groups nodes in the summary
+	#[structopt(long, parse(from_os_str))]
+	pub names: Option<std::path::PathBuf>,
+
+	/// Name shown for a node instead of its logfile, as path=label or path=label#tag. May be repeated
+	#[structopt(long = "name", parse(try_from_str = parse_node_name), number_of_values = 1)]
+	pub node_names: Vec<(String, String)>,
+
+	/// Metrics endpoint of a node, as path=url such as
+	/// '/path/to/safenode.log=http://127.0.0.1:13001/metrics', fetched every tick
+	/// for the metrics it serves instead of parsing them from the logfile. May be repeated
+	#[structopt(long = "node-metrics-url", parse(try_from_str = parse_node_metrics_url), number_of_values = 1)]
+	pub node_metrics_urls: Vec<(String, String)>,
+
+	/// TOML file of extra metrics to extract from log lines with regexes (see src/custom/user_metrics.rs)
+	#[structopt(long, parse(from_os_str))]
+	pub metrics_config: Option<std::path::PathBuf>,
+
+	/// TOML file of alert rules, shown in a banner while a node breaks one (see src/custom/alerts.rs)
+	#[structopt(long, parse(from_os_str))]
+	pub alerts: Option<std::path::PathBuf>,
+
+	/// Don't blink the summary row and storage gauge of nodes breaking a critical
+	/// alert rule, for those who find flashing distracting
+	#[structopt(long)]
+	pub no_blink: bool,
+
+	/// Ticks for which a value in the summary which has just changed stays
+	/// highlighted, fading for the second half of them. 0 turns this off
+	#[structopt(long, default_value = "2")]
+	pub flash_ticks: u64,
+
+	/// TOML file of keywords whose lines are gathered above the warnings view and
+	/// highlighted in the log (see src/custom/spotlight.rs)
+	#[structopt(long, parse(from_os_str))]
+	pub spotlight: Option<std::path::PathBuf>,
+
+	/// TOML file of dashboard colours (see src/custom/theme.rs)
+	#[structopt(long, parse(from_os_str))]
+	pub theme: Option<std::path::PathBuf>,
+
+	/// Colours on which --theme is based: default, or colorblind for blues and
+	/// oranges in place of greens and reds
+	#[structopt(long, default_value = "default")]
+	pub palette: Palette,
+
+	/// Stop at the first and last node, and logfile line, rather than wrapping around
+	#[structopt(long)]
+	pub no_wrap_nav: bool,
+
+	/// Key bindings: default, or vim for h, j, k, l, g and G navigation
+	#[structopt(long, default_value = "default")]
+	pub keys: KeyPreset,
+
+	/// Keys for an action, replacing its usual keys, as action=keys such as
+	/// 'quit=x' or 'next-node=right,n'. May be repeated
+	#[structopt(long = "bind", parse(try_from_str = parse_key_binding), number_of_values = 1)]
+	pub key_bindings: Vec<(Action, Vec<BindingKey>)>,
+
+	/// View to start in: node, summary, detail, warnings or heatmap. Defaults to the view in use on last exit
+	#[structopt(long, parse(try_from_str = parse_view))]
+	pub view: Option<DashViewMain>,
+
+	/// Metric to sort the summary by: name, storage-used, error-rate, rewards or health
+	#[structopt(long, parse(try_from_str = parse_sort_key))]
+	pub sort: Option<SortKey>,
+
+	/// Panels of the node and detail views to hide, of stats, resources, latency,
+	/// timelines, growth and log, e.g. 'timelines,log' ('' for none). Defaults to
+	/// those hidden on last exit
+	#[structopt(long)]
+	pub hide: Option<HiddenPanels>,
+
+	/// Show only nodes whose name or logfile contains this text, or matches it as a regex
+	#[structopt(long)]
+	pub filter: Option<String>,
+
+	/// File in which the view, sort order and filter are remembered between
+	/// sessions [default: ~/.config/vdash/preferences.toml]
+	#[structopt(long, parse(from_os_str))]
+	pub prefs_file: Option<std::path::PathBuf>,
+
+	/// Neither restore nor save the view, sort order and filter
+	#[structopt(long)]
+	pub no_prefs: bool,
+
+	/// Use the terminal's default colours throughout, e.g. for dumb terminals
+	#[structopt(long)]
+	pub no_color: bool,
+
+	/// Save node metrics and timelines to this file, and restore them on startup
+	#[structopt(long, parse(from_os_str))]
+	pub state_file: Option<std::path::PathBuf>,
+
+	/// Write a JSON snapshot of node metrics to this file every second. Use '-'
+	/// to write a line of JSON to stdout every second instead of showing the dashboard
+	#[structopt(long)]
+	pub dump_json: Option<String>,
+
+	/// Read the logfiles once, print the health of each node and exit nonzero
+	/// if any is stale, disconnected or logging --check-errors errors a minute
+	#[structopt(long)]
+	pub check: bool,
+
+	/// Check every option, file, pattern and address given, print what is
+	/// wrong with each and exit nonzero if anything is, without starting
+	#[structopt(long)]
+	pub validate: bool,
+
+	/// Parse a logfile once and print how many lines each --metrics-config
+	/// pattern matched, with examples, instead of showing the dashboard
+	#[structopt(long, parse(from_os_str))]
+	pub parse_report: Option<std::path::PathBuf>,
+
+	/// Errors per minute at which --check treats a node as unhealthy, and from
+	/// which its health score gets nothing for errors
+	#[structopt(long, default_value = "10")]
+	pub check_errors: u64,
+
+	/// How much each part of a node's state counts towards its health score,
+	/// e.g. 'errors=50,storage=0'. Parts not given keep their defaults
+	#[structopt(long, default_value = "stale=30,errors=30,connection=25,storage=15")]
+	pub health_weights: HealthWeights,
+
+	/// Colour the summary's rows by health score: as a full gauge from 80, a
+	/// warning from 50 and critical below
+	#[structopt(long)]
+	pub health_colors: bool,
+
+	/// Metric shown by the gauge of each node's resources: storage (used of its
+	/// capacity), error-rate (errors per minute of --check-errors) or peers (of --gauge-peers)
+	#[structopt(long, default_value = "storage")]
+	pub primary_gauge: PrimaryGauge,
+
+	/// Connected peers at which --primary-gauge peers is full
+	#[structopt(long, default_value = "50")]
+	pub gauge_peers: u64,
+
+	/// Timeline shown by the heatmap view, such as puts, errors or bytes_in
+	#[structopt(long, default_value = "puts")]
+	pub heatmap_metric: String,
+
+	/// Serve node metrics in Prometheus format at http://<metrics-addr>/metrics, e.g. 127.0.0.1:9100, [::1]:9100 or localhost:9100
+	#[structopt(long)]
+	pub metrics_addr: Option<String>,
+
+	/// PEM certificate chain with which to serve --metrics-addr over HTTPS. Requires --metrics-tls-key
+	#[structopt(long, parse(from_os_str))]
+	pub metrics_tls_cert: Option<std::path::PathBuf>,
+
+	/// PEM private key of --metrics-tls-cert
+	#[structopt(long, parse(from_os_str))]
+	pub metrics_tls_key: Option<std::path::PathBuf>,
+
+	/// Answer queries for node metrics as JSON on a Unix domain socket. Send a line holding a node name or logfile, or '*' for every node
+	#[structopt(long, parse(from_os_str))]
+	pub query_socket: Option<std::path::PathBuf>,
+
+	/// File to which the 'x' key exports the timelines of every node as CSV
+	#[structopt(long, default_value = "vdash-timelines.csv", parse(from_os_str))]
+	pub export_csv: std::path::PathBuf,
+
+	/// File to which the 'u' key writes the screen as text
+	#[structopt(long, default_value = "vdash-snapshot.txt", parse(from_os_str))]
+	pub snapshot: std::path::PathBuf,
+
+	/// Keep the colours of the --snapshot as ANSI escape sequences
+	#[structopt(long)]
+	pub snapshot_ansi: bool,
+
 	/// Show a debug window to the right of the logfile view in main dashboard
 	#[structopt(short, long)]
 	pub debug_window: bool,
@@ -39,3 +414,206 @@ pub struct Opt {
 	#[structopt(long)]
 	pub debug_dashboard: bool,
 }
+
+///! Parses --view, e.g. 'summary'
+pub fn parse_view(view: &str) -> Result<DashViewMain, String> {
+	DashViewMain::from_name(view).ok_or_else(|| format!("invalid view '{}', expected node, summary, detail, warnings or heatmap", view))
+}
+
+///! Parses --sort, e.g. 'error-rate' (or 'error rate')
+pub fn parse_sort_key(key: &str) -> Result<SortKey, String> {
+	SortKey::from_name(&key.replace('-', " "))
+		.ok_or_else(|| format!("invalid sort '{}', expected name, storage-used, error-rate, rewards or health", key))
+}
+
+///! Parses --tick-rate in milliseconds, rejecting rates below MIN_TICK_RATE
+pub fn parse_tick_rate(tick_rate: &str) -> Result<u64, String> {
+	let milliseconds = tick_rate
+		.parse::<u64>()
+		.map_err(|_e| format!("invalid tick rate '{}', expected milliseconds", tick_rate))?;
+	if milliseconds < MIN_TICK_RATE {
+		return Err(format!("tick rate must be at least {}ms", MIN_TICK_RATE));
+	}
+	Ok(milliseconds)
+}
+
+///! True if the path contains glob metacharacters and should be expanded
+pub fn is_glob_pattern(path: &str) -> bool {
+	strip_verbatim_prefix(path).contains(['*', '?', '['])
+}
+
+///! Returns the paths matching a glob pattern, sorted so nodes are listed in a
+///! stable order. On Windows either separator may be used, and case is ignored
+pub fn glob_matches(pattern: &str) -> Result<Vec<String>, String> {
+	let options = glob::MatchOptions { case_sensitive: !cfg!(windows), ..glob::MatchOptions::new() };
+	let paths = glob::glob_with(&strip_verbatim_prefix(pattern), options)
+		.map_err(|e| format!("invalid glob pattern '{}': {}", pattern, e))?;
+	let mut matches: Vec<String> = paths
+		.filter_map(|entry| entry.ok())
+		.filter(|path| path.is_file())
+		.filter_map(|path| path.to_str().map(String::from))
+		.collect();
+	matches.sort();
+	Ok(matches)
+}
+
+///! Expands any glob patterns among the LOGFILE arguments
+///!
+///! Literal paths are passed through unchanged (they need not exist yet)
+///! while a glob pattern which matches nothing is an error.
+pub fn expand_logfile_paths(paths: &[String]) -> Result<Vec<String>, String> {
+	let mut expanded = Vec::<String>::new();
+	for path in paths {
+		if is_glob_pattern(path) {
+			let matches = glob_matches(path)?;
+			if matches.is_empty() {
+				return Err(format!("no logfiles match pattern '{}'", path));
+			}
+			for m in matches {
+				if !expanded.contains(&m) {
+					expanded.push(m);
+				}
+			}
+		} else if !expanded.contains(path) {
+			expanded.push(path.clone());
+		}
+	}
+	Ok(expanded)
+}
+
+///! The logfiles of paths which are of different files, the first of those of
+///! the same file after following symlinks and '..' segments, see logfile_key().
+///! Also each path dropped, with the path kept for its file
+pub fn dedup_logfile_paths(paths: &[String]) -> (Vec<String>, Vec<(String, String)>) {
+	let mut kept = Vec::<(String, String)>::new(); // Of each path kept, its key
+	let mut duplicates = Vec::<(String, String)>::new();
+	for path in paths {
+		let key = logfile_key(path);
+		match kept.iter().find(|(_path, kept_key)| *kept_key == key) {
+			Some((kept_path, _key)) => duplicates.push((path.clone(), kept_path.clone())),
+			None => kept.push((path.clone(), key)),
+		}
+	}
+	(kept.into_iter().map(|(path, _key)| path).collect(), duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs::{create_dir, File};
+
+	fn temp_logs() -> tempfile::TempDir {
+		let dir = tempfile::tempdir().unwrap();
+		for node in &["node-1", "node-2", "node-3"] {
+			create_dir(dir.path().join(node)).unwrap();
+			File::create(dir.path().join(node).join("safenode.log")).unwrap();
+		}
+		File::create(dir.path().join("node-1").join("safenode.log.1")).unwrap();
+		File::create(dir.path().join("notes.txt")).unwrap();
+		dir
+	}
+
+	fn path_in(dir: &tempfile::TempDir, relative: &str) -> String {
+		dir.path().join(relative).to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn detects_glob_patterns() {
+		assert!(is_glob_pattern("logs/*.log"));
+		assert!(is_glob_pattern("node-?/safenode.log"));
+		assert!(is_glob_pattern("node-[12]/safenode.log"));
+		assert!(!is_glob_pattern("/home/user/node/safenode.log"));
+		assert!(!is_glob_pattern(r"\\?\C:\nodes\node-1\safenode.log")); // A Windows verbatim path
+		assert!(is_glob_pattern(r"\\?\C:\nodes\*\safenode.log"));
+	}
+
+	#[test]
+	fn expands_glob_patterns_to_matching_files() {
+		let dir = temp_logs();
+		let expanded = expand_logfile_paths(&[path_in(&dir, "*/safenode.log")]).unwrap();
+		assert_eq!(
+			expanded,
+			vec![
+				path_in(&dir, "node-1/safenode.log"),
+				path_in(&dir, "node-2/safenode.log"),
+				path_in(&dir, "node-3/safenode.log"),
+			]
+		);
+
+		let expanded = expand_logfile_paths(&[path_in(&dir, "node-[12]/safenode.log")]).unwrap();
+		assert_eq!(expanded.len(), 2);
+	}
+
+	#[test]
+	fn keeps_literal_paths_and_removes_duplicates() {
+		let dir = temp_logs();
+		let literal = path_in(&dir, "node-9/safenode.log");
+		let expanded = expand_logfile_paths(&[
+			literal.clone(),
+			path_in(&dir, "node-1/safenode.log"),
+			path_in(&dir, "node-?/safenode.log"),
+		])
+		.unwrap();
+		assert_eq!(expanded[0], literal);
+		assert_eq!(expanded.len(), 4);
+	}
+
+	#[test]
+	fn collapses_paths_to_the_same_file() {
+		let dir = temp_logs();
+		let node_1 = path_in(&dir, "node-1/safenode.log");
+		let dotted = path_in(&dir, "node-2/../node-1/safenode.log");
+		let mut paths = vec![node_1.clone(), path_in(&dir, "node-2/safenode.log"), dotted.clone()];
+		let mut expected = vec![(dotted, node_1.clone())];
+		#[cfg(unix)]
+		{
+			let linked = path_in(&dir, "linked.log");
+			std::os::unix::fs::symlink(dir.path().join("node-2").join("safenode.log"), &linked).unwrap();
+			paths.push(linked.clone());
+			expected.push((linked, path_in(&dir, "node-2/safenode.log")));
+		}
+		let (kept, duplicates) = dedup_logfile_paths(&paths);
+		assert_eq!(kept, vec![node_1, path_in(&dir, "node-2/safenode.log")]);
+		assert_eq!(duplicates, expected);
+
+		// Not yet created, in the same directory
+		let (kept, _duplicates) = dedup_logfile_paths(&[path_in(&dir, "node-3/later.log"), path_in(&dir, "node-1/../node-3/later.log")]);
+		assert_eq!(kept.len(), 1);
+		assert!(!Opt::from_iter(vec!["vdash"]).keep_duplicates);
+	}
+
+	#[test]
+	fn pattern_matching_nothing_is_an_error() {
+		let dir = temp_logs();
+		let result = expand_logfile_paths(&[path_in(&dir, "*/missing-*.log")]);
+		assert!(result.unwrap_err().contains("no logfiles match pattern"));
+	}
+
+	#[test]
+	fn parses_tick_rate() {
+		assert_eq!(parse_tick_rate("200"), Ok(200));
+		assert_eq!(parse_tick_rate("50"), Ok(50));
+		assert!(parse_tick_rate("49").is_err());
+		assert!(parse_tick_rate("0").is_err());
+		assert!(parse_tick_rate("-100").is_err());
+		assert!(parse_tick_rate("fast").is_err());
+
+		assert_eq!(Opt::from_iter(vec!["vdash"]).tick_rate, 200);
+		assert_eq!(Opt::from_iter(vec!["vdash", "--tick-rate", "1000"]).tick_rate, 1000);
+		assert!(Opt::from_iter_safe(vec!["vdash", "--tick-rate", "10"]).is_err());
+	}
+
+	#[test]
+	fn parses_view_and_sort_overrides() {
+		assert_eq!(parse_view("summary"), Ok(DashViewMain::DashSummary));
+		assert!(parse_view("debug").is_err());
+		assert_eq!(parse_sort_key("error-rate"), Ok(SortKey::ErrorRate));
+		assert_eq!(parse_sort_key("storage used"), Ok(SortKey::StorageUsed));
+		assert_eq!(parse_sort_key("health"), Ok(SortKey::Health));
+		assert!(parse_sort_key("age").is_err());
+
+		let opt = Opt::from_iter(vec!["vdash", "--view", "detail", "--sort", "rewards"]);
+		assert_eq!(opt.view, Some(DashViewMain::DashDetail));
+		assert_eq!(opt.sort, Some(SortKey::Rewards));
+	}
+}
@@ -0,0 +1,125 @@
+///! Panels of the node and detail views which can be hidden, with --hide or
+///! the keys 1 to 6, for the others to take their space
+///!
+///! The panels hidden are saved in the preferences file. --hide overrides
+///! them, and --hide '' shows every panel
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PanelKind {
+	Stats,
+	Resources,
+	Latency,
+	Timelines,
+	Growth,
+	Log,
+}
+
+///! Every panel which can be hidden, by name, in the order of their keys
+pub static PANEL_KINDS: [(PanelKind, &str); 6] = [
+	(PanelKind::Stats, "stats"),
+	(PanelKind::Resources, "resources"),
+	(PanelKind::Latency, "latency"),
+	(PanelKind::Timelines, "timelines"),
+	(PanelKind::Growth, "growth"),
+	(PanelKind::Log, "log"),
+];
+
+impl PanelKind {
+	///! As for --hide, e.g. 'timelines'
+	pub fn name(&self) -> &'static str {
+		PANEL_KINDS.iter().find(|(kind, _name)| kind == self).map(|(_kind, name)| *name).unwrap_or("")
+	}
+
+	pub fn from_name(name: &str) -> Option<PanelKind> {
+		PANEL_KINDS.iter().find(|(_kind, kind_name)| *kind_name == name).map(|(kind, _name)| *kind)
+	}
+}
+
+///! The panels hidden, in the order of PANEL_KINDS
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HiddenPanels(Vec<PanelKind>);
+
+impl HiddenPanels {
+	pub fn is_hidden(&self, kind: PanelKind) -> bool {
+		self.0.contains(&kind)
+	}
+
+	pub fn is_shown(&self, kind: PanelKind) -> bool {
+		!self.is_hidden(kind)
+	}
+
+	///! Hide a panel which is shown, or show it again
+	pub fn toggle(&mut self, kind: PanelKind) {
+		if self.is_hidden(kind) {
+			self.0.retain(|hidden| *hidden != kind);
+		} else {
+			self.0.push(kind);
+			self.0.sort_by_key(|hidden| PANEL_KINDS.iter().position(|(kind, _name)| kind == hidden));
+		}
+	}
+
+	pub fn names(&self) -> Vec<String> {
+		self.0.iter().map(|kind| kind.name().to_string()).collect()
+	}
+
+	///! The panels named, ignoring names which aren't of a panel
+	pub fn from_names(names: &[String]) -> HiddenPanels {
+		let mut hidden = HiddenPanels::default();
+		for kind in names.iter().filter_map(|name| PanelKind::from_name(name)) {
+			if hidden.is_shown(kind) {
+				hidden.toggle(kind);
+			}
+		}
+		hidden
+	}
+}
+
+impl std::str::FromStr for HiddenPanels {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<HiddenPanels, String> {
+		let mut hidden = HiddenPanels::default();
+		for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+			let kind = PanelKind::from_name(name).ok_or_else(|| {
+				let names: Vec<&str> = PANEL_KINDS.iter().map(|(_kind, name)| *name).collect();
+				format!("invalid panel '{}', expected one of {}", name, names.join(", "))
+			})?;
+			if hidden.is_shown(kind) {
+				hidden.toggle(kind);
+			}
+		}
+		Ok(hidden)
+	}
+}
+
+impl fmt::Display for HiddenPanels {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.names().join(","))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_and_toggles_hidden_panels() {
+		let mut hidden: HiddenPanels = "log, timelines".parse().unwrap();
+		assert!(hidden.is_hidden(PanelKind::Log) && hidden.is_hidden(PanelKind::Timelines));
+		assert!(hidden.is_shown(PanelKind::Stats));
+		assert_eq!(hidden.to_string(), "timelines,log"); // In the order of their keys
+
+		hidden.toggle(PanelKind::Log);
+		hidden.toggle(PanelKind::Stats);
+		assert_eq!(hidden.names(), vec!["stats", "timelines"]);
+		assert_eq!(HiddenPanels::from_names(&hidden.names()), hidden);
+		assert_eq!(HiddenPanels::from_names(&[String::from("rewards"), String::from("log")]).names(), vec!["log"]);
+
+		assert_eq!("".parse::<HiddenPanels>(), Ok(HiddenPanels::default()));
+		assert_eq!(
+			"rewards".parse::<HiddenPanels>(),
+			Err(String::from("invalid panel 'rewards', expected one of stats, resources, latency, timelines, growth, log"))
+		);
+	}
+}
@@ -0,0 +1,177 @@
+///! A dry run of the line parser over one logfile, for --parse-report
+///!
+///! Every line is parsed as it would be by the dashboard, and the report
+///! says how many lines each --metrics-config pattern matched, with a few
+///! of the lines matched, so that patterns can be checked against real logs
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::custom::app::{decode_line, LogEntry, NodeMetrics};
+use crate::custom::opt::Opt;
+use crate::custom::user_metrics::{UserMetricSpec, UserMetrics};
+
+///! Lines shown of those matched by each pattern
+pub const PARSE_REPORT_EXAMPLES: usize = 2;
+
+#[derive(Debug, PartialEq)]
+pub struct PatternReport {
+	pub name: String,
+	pub matches: usize, // Lines matched
+	pub value: Option<u64>, // Of the metric after the last line
+	pub examples: Vec<String>,
+}
+
+pub struct ParseReport {
+	pub lines: usize,
+	pub timed_lines: usize, // Those with a log time vdash understood
+	pub patterns: Vec<PatternReport>,
+	specs: Arc<Vec<UserMetricSpec>>,
+	metrics: NodeMetrics,
+}
+
+impl ParseReport {
+	pub fn new(opt: &Opt, specs: Arc<Vec<UserMetricSpec>>) -> ParseReport {
+		let mut metrics = NodeMetrics::new(opt);
+		metrics.user_metrics = UserMetrics::new(specs.clone());
+		ParseReport {
+			lines: 0,
+			timed_lines: 0,
+			patterns: specs
+				.iter()
+				.map(|spec| PatternReport {
+					name: spec.name.clone(),
+					matches: 0,
+					value: None,
+					examples: Vec::new(),
+				})
+				.collect(),
+			specs,
+			metrics,
+		}
+	}
+
+	pub fn process_line(&mut self, line: &str) -> Result<(), std::io::Error> {
+		self.lines += 1;
+		if LogEntry::decode_metadata(line, self.metrics.log_profile).is_some() {
+			self.timed_lines += 1;
+		}
+		self.metrics.gather_metrics(line, &Utc::now())?;
+		for (spec, pattern) in self.specs.iter().zip(self.patterns.iter_mut()) {
+			if spec.is_match(line) {
+				pattern.matches += 1;
+				if pattern.examples.len() < PARSE_REPORT_EXAMPLES {
+					pattern.examples.push(line.to_string());
+				}
+			}
+		}
+		for ((_name, value), pattern) in self.metrics.user_metrics.values().zip(self.patterns.iter_mut()) {
+			pattern.value = value;
+		}
+		Ok(())
+	}
+
+	pub fn read_logfile(&mut self, path: &Path) -> Result<(), std::io::Error> {
+		let mut f = BufReader::new(std::fs::File::open(path)?);
+		let mut bytes = Vec::<u8>::new();
+		loop {
+			bytes.clear();
+			if f.read_until(b'\n', &mut bytes)? == 0 {
+				return Ok(());
+			}
+			self.process_line(&decode_line(&bytes))?;
+		}
+	}
+
+	///! The report printed by --parse-report
+	pub fn format(&self, logfile: &str) -> String {
+		let mut text = format!("{}: {} lines, {} with a log time\n", logfile, self.lines, self.timed_lines);
+		if self.patterns.is_empty() {
+			text.push_str("No patterns to check, see --metrics-config\n");
+		}
+		for pattern in self.patterns.iter() {
+			let value = match pattern.value {
+				Some(value) => value.to_string(),
+				None => String::from("not set"),
+			};
+			text.push_str(&format!("{}: {} lines matched, value {}\n", pattern.name, pattern.matches, value));
+			for example in pattern.examples.iter() {
+				text.push_str(&format!("\t{}\n", example.trim()));
+			}
+		}
+		text
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::user_metrics::user_metric_specs_from_toml;
+	use structopt::StructOpt;
+
+	const CONFIG: &str = r#"
+		[[metric]]
+		name = "Replications"
+		pattern = "Replicated chunk [0-9a-f]+"
+
+		[[metric]]
+		name = "Paid"
+		pattern = "paid (\\d+) nanos"
+		group = 1
+		add = true
+
+		[[metric]]
+		name = "Unused"
+		pattern = "never logged"
+	"#;
+
+	fn report_of(lines: &[&str]) -> ParseReport {
+		let specs = Arc::new(user_metric_specs_from_toml(CONFIG).unwrap());
+		let mut report = ParseReport::new(&Opt::from_iter(vec!["vdash"]), specs);
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("safenode.log");
+		std::fs::write(&path, lines.join("\n")).unwrap();
+		report.read_logfile(&path).unwrap();
+		report
+	}
+
+	#[test]
+	fn counts_lines_matched_by_each_pattern() {
+		let lines = [
+			" INFO 2022-01-15T20:21:02.659471Z [sn/src/node/mod.rs:L211]: Replicated chunk 8a3f",
+			" INFO 2022-01-15T20:21:03.659471Z [sn/src/node/mod.rs:L211]: paid 10 nanos",
+			"not a log line",
+			" INFO 2022-01-15T20:21:04.659471Z [sn/src/node/mod.rs:L211]: Replicated chunk 91bc",
+			" INFO 2022-01-15T20:21:05.659471Z [sn/src/node/mod.rs:L211]: Replicated chunk aa01, paid 5 nanos",
+		];
+		let report = report_of(&lines);
+		assert_eq!(report.lines, 5);
+		assert_eq!(report.timed_lines, 4);
+
+		let expected_matches = |pattern: &str| lines.iter().filter(|line| regex::Regex::new(pattern).unwrap().is_match(line)).count();
+		let matches: Vec<(&str, usize)> = report.patterns.iter().map(|p| (p.name.as_str(), p.matches)).collect();
+		assert_eq!(matches, vec![
+			("Replications", expected_matches("Replicated chunk [0-9a-f]+")),
+			("Paid", expected_matches("paid (\\d+) nanos")),
+			("Unused", 0),
+		]);
+		assert_eq!(matches[0].1, 3);
+		assert_eq!(report.patterns[0].examples, vec![lines[0].to_string(), lines[3].to_string()]);
+		assert_eq!(report.patterns[1].value, Some(15));
+	}
+
+	#[test]
+	fn formats_the_report() {
+		let report = report_of(&[" INFO 2022-01-15T20:21:03.659471Z [sn/src/node/mod.rs:L211]: paid 10 nanos"]);
+		assert_eq!(
+			report.format("safenode.log"),
+			"safenode.log: 1 lines, 1 with a log time\n\
+			Replications: 0 lines matched, value 0\n\
+			Paid: 1 lines matched, value 10\n\
+			\tINFO 2022-01-15T20:21:03.659471Z [sn/src/node/mod.rs:L211]: paid 10 nanos\n\
+			Unused: 0 lines matched, value 0\n"
+		);
+	}
+}
@@ -0,0 +1,178 @@
+///! Differences between platforms in logfile paths, and in telling when a
+///! logfile has been replaced by rotation
+///!
+///! linemux reports the canonical path of each logfile, which on Windows is
+///! a verbatim path such as '\\?\C:\nodes\safenode.log', while the paths
+///! given may be relative, use either separator or differ in case. Paths are
+///! compared once normalized, see logfile_key()
+///!
+///! A rotated logfile is told from the original by its inode on unix. There
+///! are no inodes on Windows, so there a logfile is taken to be replaced when
+///! it is smaller or older than when last read
+use std::path::Path;
+use std::time::SystemTime;
+
+static VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+static VERBATIM_PREFIX: &str = r"\\?\";
+
+///! A path without any Windows verbatim prefix, e.g. 'C:\nodes' for '\\?\C:\nodes'
+pub fn strip_verbatim_prefix(path: &str) -> String {
+	if let Some(rest) = path.strip_prefix(VERBATIM_UNC_PREFIX) {
+		format!(r"\\{}", rest)
+	} else {
+		path.strip_prefix(VERBATIM_PREFIX).unwrap_or(path).to_string()
+	}
+}
+
+///! A path written one way, for the platform, so that paths to the same file
+///! compare equal without touching the filesystem
+pub fn normalize_path(path: &str) -> String {
+	normalize_path_for(path, cfg!(windows))
+}
+
+// On Windows either separator may be used, and case doesn't matter
+fn normalize_path_for(path: &str, windows: bool) -> String {
+	let (path, separator) = if windows {
+		(strip_verbatim_prefix(path).replace('/', "\\").to_lowercase(), '\\')
+	} else {
+		(path.to_string(), '/')
+	};
+	let unc = windows && path.starts_with(r"\\");
+	let rooted = path.starts_with(separator);
+	let parts: Vec<&str> = path.split(separator).filter(|part| !part.is_empty() && *part != ".").collect();
+	let root = match (unc, rooted) {
+		(true, _) => r"\\",
+		(false, true) => &path[..1],
+		(false, false) => "",
+	};
+	format!("{}{}", root, parts.join(&separator.to_string()))
+}
+
+///! A logfile's path as it is reported by linemux: absolute, canonical where
+///! the file or its directory exists, and normalized
+pub fn logfile_key(logfile: &str) -> String {
+	let path = Path::new(logfile);
+	let absolute = match std::env::current_dir() {
+		Ok(dir) if path.is_relative() => dir.join(path),
+		_ => path.to_path_buf(),
+	};
+	let canonical = absolute
+		.canonicalize()
+		.ok()
+		.or_else(|| Some(absolute.parent()?.canonicalize().ok()?.join(absolute.file_name()?)))
+		.unwrap_or(absolute);
+	normalize_path(&canonical.to_string_lossy())
+}
+
+///! What is known of a logfile when it was last read, to tell whether the
+///! file now at its path has replaced it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogfileStamp {
+	pub inode: Option<u64>, // None where there are no inodes, as on Windows
+	pub len: u64,
+	pub modified: Option<SystemTime>,
+}
+
+impl LogfileStamp {
+	///! None if the logfile doesn't exist
+	pub fn of(logfile: &str) -> Option<LogfileStamp> {
+		let metadata = std::fs::metadata(logfile).ok()?;
+		Some(LogfileStamp {
+			inode: inode(&metadata),
+			len: metadata.len(),
+			modified: metadata.modified().ok(),
+		})
+	}
+
+	///! Whether current, of the file now at the path, replaced this one. By
+	///! inode where both have one, or else if it is smaller or older
+	pub fn is_replaced_by(&self, current: &LogfileStamp) -> bool {
+		if let (Some(inode), Some(current_inode)) = (self.inode, current.inode) {
+			return inode != current_inode;
+		}
+		let older = match (self.modified, current.modified) {
+			(Some(modified), Some(current_modified)) => current_modified < modified,
+			_ => false,
+		};
+		current.len < self.len || older
+	}
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> Option<u64> {
+	use std::os::unix::fs::MetadataExt;
+	Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> Option<u64> {
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn normalizes_paths_for_each_platform() {
+		let windows = |path| normalize_path_for(path, true);
+		assert_eq!(windows(r"\\?\C:\Nodes\node-1\safenode.log"), r"c:\nodes\node-1\safenode.log");
+		assert_eq!(windows("C:/Nodes/node-1//safenode.log"), r"c:\nodes\node-1\safenode.log");
+		assert_eq!(windows(r"C:\Nodes\.\node-1\safenode.log\"), r"c:\nodes\node-1\safenode.log");
+		assert_eq!(windows(r"\\?\UNC\server\share\safenode.log"), r"\\server\share\safenode.log");
+		assert_eq!(windows(r"\\server\share\safenode.log"), r"\\server\share\safenode.log");
+		assert_eq!(windows(r"\logs\safenode.log"), r"\logs\safenode.log");
+		assert_eq!(windows(r"node-1\safenode.log"), r"node-1\safenode.log");
+
+		let unix = |path| normalize_path_for(path, false);
+		assert_eq!(unix("/home/user//nodes/./node-1/safenode.log"), "/home/user/nodes/node-1/safenode.log");
+		assert_eq!(unix("nodes/Node-1/"), "nodes/Node-1"); // Case matters
+		assert_eq!(unix(r"nodes\node-1"), r"nodes\node-1"); // As is a backslash
+
+		assert_eq!(strip_verbatim_prefix(r"\\?\C:\nodes"), r"C:\nodes");
+		assert_eq!(strip_verbatim_prefix("/nodes"), "/nodes");
+	}
+
+	#[test]
+	fn keys_logfiles_however_written() {
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("safenode.log");
+		std::fs::write(&logfile, "").unwrap();
+		let canonical = logfile.canonicalize().unwrap().to_string_lossy().to_string();
+		let written = dir.path().join(".").join("safenode.log").to_string_lossy().to_string();
+		assert_eq!(logfile_key(&written), normalize_path(&canonical));
+
+		// Not yet created, in a directory which exists
+		let missing = dir.path().join("later.log").to_string_lossy().to_string();
+		assert!(logfile_key(&missing).ends_with("later.log"));
+	}
+
+	#[test]
+	fn tells_replaced_logfiles_without_inodes() {
+		let now = SystemTime::now();
+		let stamp = |inode, len, modified| LogfileStamp { inode, len, modified: Some(modified) };
+		let read = stamp(None, 1000, now);
+
+		// Grown since, or unchanged, it is the same file
+		assert!(!read.is_replaced_by(&stamp(None, 1500, now + Duration::from_secs(1))));
+		assert!(!read.is_replaced_by(&read));
+
+		// Smaller, or older, it has been replaced
+		assert!(read.is_replaced_by(&stamp(None, 10, now + Duration::from_secs(1))));
+		assert!(read.is_replaced_by(&stamp(None, 1500, now - Duration::from_secs(60))));
+		assert!(!read.is_replaced_by(&LogfileStamp { modified: None, ..read }));
+
+		// With inodes, only they decide
+		assert!(stamp(Some(1), 1000, now).is_replaced_by(&stamp(Some(2), 1500, now)));
+		assert!(!stamp(Some(1), 1000, now).is_replaced_by(&stamp(Some(1), 10, now)));
+
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("safenode.log");
+		assert_eq!(LogfileStamp::of(&logfile.to_string_lossy()), None);
+		std::fs::write(&logfile, "line\n").unwrap();
+		let written = LogfileStamp::of(&logfile.to_string_lossy()).unwrap();
+		assert_eq!(written.len, 5);
+		assert_eq!(written.inode.is_some(), cfg!(unix));
+	}
+}
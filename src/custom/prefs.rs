@@ -0,0 +1,157 @@
+///! User interface preferences remembered between sessions
+///!
+///! The view, summary sort order, node filter and panels hidden on exit are
+///! saved to a small TOML file, separate from the --state-file, and restored
+///! on startup. The --view, --sort, --filter and --hide options override them.
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::custom::app::{DashState, DashViewMain, SortKey};
+use crate::custom::opt::Opt;
+use crate::custom::panels::HiddenPanels;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct UiPreferences {
+	pub view: Option<String>, // As for --view, e.g. 'summary'
+	pub sort: Option<String>, // As for --sort, e.g. 'error rate'
+	pub sort_ascending: Option<bool>,
+	pub filter: Option<String>,
+	pub hidden_panels: Option<Vec<String>>, // As for --hide, e.g. ['log']
+}
+
+///! The preferences file: --prefs-file, or else vdash/preferences.toml in
+///! the user's config directory. None if --no-prefs was given
+pub fn preferences_path(opt: &Opt) -> Option<PathBuf> {
+	if opt.no_prefs {
+		return None;
+	}
+	opt.prefs_file.clone().or_else(default_preferences_path)
+}
+
+fn default_preferences_path() -> Option<PathBuf> {
+	let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+		.filter(|dir| !dir.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+		.or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+	Some(config_dir.join("vdash").join("preferences.toml"))
+}
+
+impl UiPreferences {
+	pub fn from_dash_state(dash_state: &DashState) -> UiPreferences {
+		let filter = dash_state.node_filter.get_text();
+		UiPreferences {
+			view: dash_state.main_view.name().map(String::from),
+			sort: Some(dash_state.sort_key.name().to_string()),
+			sort_ascending: Some(dash_state.sort_ascending),
+			filter: if filter.is_empty() { None } else { Some(filter.clone()) },
+			hidden_panels: Some(dash_state.hidden_panels.names()),
+		}
+	}
+
+	///! Restore the view, sort order and hidden panels. Unrecognised values are ignored, and
+	///! the filter is left to the caller as it may move the focus
+	pub fn apply(&self, dash_state: &mut DashState) {
+		if let Some(view) = self.view.as_deref().and_then(DashViewMain::from_name) {
+			dash_state.main_view = view;
+		}
+		if let Some(sort_key) = self.sort.as_deref().and_then(SortKey::from_name) {
+			dash_state.sort_key = sort_key;
+		}
+		if let Some(sort_ascending) = self.sort_ascending {
+			dash_state.sort_ascending = sort_ascending;
+		}
+		if let Some(hidden_panels) = &self.hidden_panels {
+			dash_state.hidden_panels = HiddenPanels::from_names(hidden_panels);
+		}
+	}
+
+	pub fn from_toml(toml: &str) -> Result<UiPreferences, String> {
+		toml::from_str(toml).map_err(|e| e.to_string())
+	}
+
+	pub fn to_toml(&self) -> Result<String, String> {
+		toml::to_string(self).map_err(|e| e.to_string())
+	}
+
+	///! Returns None if the preferences file doesn't exist yet
+	pub fn load(path: &Path) -> Result<Option<UiPreferences>, Error> {
+		let toml = match fs::read_to_string(path) {
+			Ok(toml) => toml,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e),
+		};
+		UiPreferences::from_toml(&toml).map(Some).map_err(Error::other)
+	}
+
+	///! Creates the directory of the preferences file if need be
+	pub fn save(&self, path: &Path) -> Result<(), Error> {
+		let toml = self.to_toml().map_err(Error::other)?;
+		if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(path, toml)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::panels::PanelKind;
+
+	#[test]
+	fn round_trips_preferences() {
+		let mut dash_state = DashState::new();
+		dash_state.main_view = DashViewMain::DashSummary;
+		dash_state.sort_key = SortKey::ErrorRate;
+		dash_state.sort_ascending = false;
+		dash_state.node_filter.set_text("node-[12]");
+		dash_state.hidden_panels.toggle(PanelKind::Log);
+
+		let preferences = UiPreferences::from_dash_state(&dash_state);
+		let restored = UiPreferences::from_toml(&preferences.to_toml().unwrap()).unwrap();
+		assert_eq!(restored, preferences);
+
+		let mut restored_state = DashState::new();
+		restored.apply(&mut restored_state);
+		assert!(restored_state.main_view == DashViewMain::DashSummary);
+		assert_eq!(restored_state.sort_key, SortKey::ErrorRate);
+		assert!(!restored_state.sort_ascending);
+		assert_eq!(restored.filter.as_deref(), Some("node-[12]"));
+		assert!(restored_state.hidden_panels.is_hidden(PanelKind::Log));
+		assert!(restored_state.hidden_panels.is_shown(PanelKind::Timelines));
+	}
+
+	#[test]
+	fn tolerates_missing_and_unknown_preferences() {
+		let preferences = UiPreferences::from_toml("view = \"sideways\"\n").unwrap();
+		let mut dash_state = DashState::new();
+		preferences.apply(&mut dash_state);
+		assert!(dash_state.main_view == DashViewMain::DashNode);
+		assert_eq!(dash_state.sort_key, SortKey::Logfile);
+		assert!(dash_state.sort_ascending);
+
+		// The debug dashboard and an empty filter aren't remembered
+		dash_state.main_view = DashViewMain::DashDebug;
+		let preferences = UiPreferences::from_dash_state(&dash_state);
+		assert_eq!(preferences.view, None);
+		assert_eq!(preferences.filter, None);
+		assert_eq!(UiPreferences::from_toml(&preferences.to_toml().unwrap()).unwrap(), preferences);
+
+		assert!(UiPreferences::from_toml("sort_ascending = \"yes\"").is_err());
+	}
+
+	#[test]
+	fn saves_and_loads_preferences_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash").join("preferences.toml");
+		assert_eq!(UiPreferences::load(&path).unwrap(), None);
+
+		let preferences = UiPreferences { view: Some("detail".to_string()), ..UiPreferences::default() };
+		preferences.save(&path).unwrap();
+		assert_eq!(UiPreferences::load(&path).unwrap(), Some(preferences));
+	}
+}
@@ -0,0 +1,412 @@
+///! Prometheus exposition of node metrics, served by --metrics-addr
+///!
+///! The server runs on its own thread and serves the most recent
+///! MetricsSnapshot, which the dashboard replaces once a second.
+///!
+///! With --metrics-tls-cert and --metrics-tls-key the metrics are served over
+///! HTTPS instead, by rustls. The certificate and key are loaded on startup, so
+///! that vdash refuses to start with any it can't use rather than serve the
+///! metrics unencrypted.
+///!
+///! The address may be IPv4 or IPv6, such as 0.0.0.0:9100 or [::1]:9100, or a
+///! hostname such as localhost:9100, which is bound at every address it
+///! resolves to until one succeeds.
+use std::io::{BufRead, BufReader, Error, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rustls::pki_types::pem::{self, PemObject};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+use crate::custom::snapshot::{MetricsSnapshot, NodeSnapshot};
+
+pub type SharedSnapshot = Arc<Mutex<Option<MetricsSnapshot>>>;
+
+// Longer request lines are cut short rather than read into memory
+const MAX_REQUEST_LINE: u64 = 8192;
+
+struct MetricFamily {
+	name: &'static str,
+	kind: &'static str,
+	help: &'static str,
+	value: fn(&NodeSnapshot) -> Option<String>,
+}
+
+static METRIC_FAMILIES: [MetricFamily; 11] = [
+	MetricFamily { name: "vdash_puts_total", kind: "counter", help: "Chunks and records stored by the node", value: |n| Some(n.puts.to_string()) },
+	MetricFamily { name: "vdash_gets_total", kind: "counter", help: "Chunks and records retrieved from the node", value: |n| Some(n.gets.to_string()) },
+	MetricFamily { name: "vdash_errors_total", kind: "counter", help: "ERROR lines in the node logfile", value: |n| Some(n.errors.to_string()) },
+	MetricFamily { name: "vdash_warnings_total", kind: "counter", help: "WARN lines in the node logfile", value: |n| Some(n.warnings.to_string()) },
+	MetricFamily { name: "vdash_rewards_attos_total", kind: "counter", help: "Rewards received by the node in attos", value: |n| Some(n.rewards.to_string()) },
+	MetricFamily { name: "vdash_storage_used_bytes", kind: "gauge", help: "Storage used by the node", value: |n| Some(n.storage_used.to_string()) },
+	MetricFamily { name: "vdash_storage_max_bytes", kind: "gauge", help: "Storage capacity of the node", value: |n| Some(n.storage_max.to_string()) },
+	MetricFamily { name: "vdash_storage_ratio", kind: "gauge", help: "Fraction of the node's storage capacity used", value: |n| n.storage_ratio.map(|r| r.to_string()) },
+	MetricFamily { name: "vdash_peers", kind: "gauge", help: "Peers connected to the node", value: |n| n.peers.map(|p| p.to_string()) },
+	MetricFamily { name: "vdash_received_bytes_total", kind: "counter", help: "Bytes the node logged receiving", value: |n| Some(n.bytes_in.to_string()) },
+	MetricFamily { name: "vdash_sent_bytes_total", kind: "counter", help: "Bytes the node logged sending", value: |n| Some(n.bytes_out.to_string()) },
+];
+
+///! Escape a label value as required by the exposition format
+fn escape_label(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+///! Format a snapshot in the Prometheus text exposition format, with
+///! a 'node' label holding the logfile path of each node
+pub fn format_prometheus(snapshot: &MetricsSnapshot) -> String {
+	let mut text = String::new();
+	for family in METRIC_FAMILIES.iter() {
+		let samples: Vec<(String, String)> = snapshot
+			.nodes
+			.iter()
+			.filter_map(|node| (family.value)(node).map(|value| (escape_label(&node.logfile), value)))
+			.collect();
+		if samples.is_empty() {
+			continue;
+		}
+		text.push_str(&format!("# HELP {} {}\n", family.name, family.help));
+		text.push_str(&format!("# TYPE {} {}\n", family.name, family.kind));
+		for (node, value) in samples {
+			text.push_str(&format!("{}{{node=\"{}\"}} {}\n", family.name, node, value));
+		}
+	}
+	text
+}
+
+///! The certificate chain and private key for serving metrics over HTTPS
+#[derive(Debug)]
+pub struct MetricsTls {
+	pub cert: PathBuf,
+	pub config: Arc<ServerConfig>,
+}
+
+///! The TLS configuration of the metrics server, None to serve plain HTTP.
+///! Both or neither of the certificate and key must be given, each a PEM
+///! file, and the key must be that of the certificate
+pub fn metrics_tls_config(cert: Option<&Path>, key: Option<&Path>) -> Result<Option<MetricsTls>, String> {
+	let (cert, key) = match (cert, key) {
+		(None, None) => return Ok(None),
+		(Some(cert), Some(key)) => (cert, key),
+		(Some(_), None) => return Err(String::from("--metrics-tls-cert needs --metrics-tls-key")),
+		(None, Some(_)) => return Err(String::from("--metrics-tls-key needs --metrics-tls-cert")),
+	};
+	let certs: Vec<CertificateDer<'static>> = CertificateDer::pem_file_iter(cert)
+		.and_then(|certs| certs.collect())
+		.map_err(|e| pem_error(cert, "certificate", e))?;
+	if certs.is_empty() {
+		return Err(pem_error(cert, "certificate", pem::Error::NoItemsFound));
+	}
+	let private_key = PrivateKeyDer::from_pem_file(key).map_err(|e| pem_error(key, "private key", e))?;
+	let config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+		.with_safe_default_protocol_versions()
+		.and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, private_key))
+		.map_err(|e| format!("can't serve HTTPS with {} and {}: {}", cert.display(), key.display(), e))?;
+	Ok(Some(MetricsTls {
+		cert: cert.to_path_buf(),
+		config: Arc::new(config),
+	}))
+}
+
+fn pem_error(path: &Path, holding: &str, e: pem::Error) -> String {
+	match e {
+		pem::Error::Io(e) => format!("failed to read {}: {}", path.display(), e),
+		pem::Error::NoItemsFound => format!("{} is not a PEM file holding a {}", path.display(), holding),
+		e => format!("{} is not a PEM file holding a {}: {}", path.display(), holding, e),
+	}
+}
+
+///! Resolve a 'host:port' address to listen on, where the host is an IPv4
+///! address, an IPv6 address in brackets or a hostname
+pub fn resolve_listen_addr(addr: &str) -> Result<Vec<SocketAddr>, String> {
+	let expected = || format!("invalid address '{}', expected host:port such as 127.0.0.1:9100, [::1]:9100 or localhost:9100", addr);
+	let (host, port) = addr.rsplit_once(':').ok_or_else(expected)?;
+	let bracketed = host.starts_with('[') && host.ends_with(']');
+	if host.is_empty() || port.parse::<u16>().is_err() || (host.contains(':') && !bracketed) {
+		return Err(expected());
+	}
+	let addrs: Vec<SocketAddr> = addr.to_socket_addrs().map_err(|e| format!("can't resolve '{}': {}", addr, e))?.collect();
+	if addrs.is_empty() {
+		return Err(format!("can't resolve '{}': no addresses found", addr));
+	}
+	Ok(addrs)
+}
+
+///! Start serving /metrics on a background thread, over HTTPS if tls is given,
+///! returning the bound address. Each connection is served on a thread of its
+///! own, so that a slow client can't hold up the next scrape
+pub fn spawn_metrics_server(addr: &str, tls: Option<&MetricsTls>, snapshot: SharedSnapshot) -> Result<SocketAddr, Error> {
+	let addrs = resolve_listen_addr(addr).map_err(Error::other)?;
+	let listener = TcpListener::bind(&addrs[..])?;
+	let local_addr = listener.local_addr()?;
+	let tls_config = tls.map(|tls| tls.config.clone());
+	thread::spawn(move || {
+		for stream in listener.incoming().flatten() {
+			let tls_config = tls_config.clone();
+			let snapshot = snapshot.clone();
+			thread::spawn(move || {
+				if let Err(e) = handle_connection(stream, tls_config.as_ref(), &snapshot) {
+					log::debug!("metrics request failed: {}", e);
+				}
+			});
+		}
+	});
+	Ok(local_addr)
+}
+
+// The handshake, like the request, must complete within the read timeout
+fn handle_connection(mut stream: TcpStream, tls_config: Option<&Arc<ServerConfig>>, snapshot: &SharedSnapshot) -> Result<(), Error> {
+	stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+	match tls_config {
+		None => serve_request(&mut stream, snapshot),
+		Some(tls_config) => {
+			let connection = ServerConnection::new(tls_config.clone()).map_err(Error::other)?;
+			let mut stream = StreamOwned::new(connection, stream);
+			serve_request(&mut stream, snapshot)?;
+			stream.conn.send_close_notify();
+			stream.flush()
+		}
+	}
+}
+
+fn serve_request<S: Read + Write>(stream: &mut S, snapshot: &SharedSnapshot) -> Result<(), Error> {
+	let mut request_line = String::new();
+	BufReader::new((&mut *stream).take(MAX_REQUEST_LINE)).read_line(&mut request_line)?;
+
+	let mut parts = request_line.split_whitespace();
+	let (status, body) = match (parts.next(), parts.next()) {
+		(Some("GET"), Some("/metrics")) => {
+			let body = match &*snapshot.lock().unwrap() {
+				Some(snapshot) => format_prometheus(snapshot),
+				None => String::new(),
+			};
+			("200 OK", body)
+		}
+		_ => ("404 Not Found", String::from("Not Found\n")),
+	};
+	write!(
+		stream,
+		"HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		status,
+		body.len(),
+		body
+	)?;
+	stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Read;
+
+	fn one_node_snapshot() -> MetricsSnapshot {
+		MetricsSnapshot {
+			timestamp: chrono::Utc::now(),
+			nodes: vec![NodeSnapshot {
+				logfile: "/home/user/node-1/safenode.log".to_string(),
+				puts: 5,
+				gets: 7,
+				errors: 2,
+				storage_used: 256,
+				storage_max: 1024,
+				storage_ratio: Some(0.25),
+				rewards: 2_000_000_500,
+				..Default::default()
+			}],
+		}
+	}
+
+	#[test]
+	fn formats_exposition_text_for_one_node() {
+		let text = format_prometheus(&one_node_snapshot());
+		let node = "{node=\"/home/user/node-1/safenode.log\"}";
+		for sample in &[
+			format!("vdash_puts_total{} 5", node),
+			format!("vdash_gets_total{} 7", node),
+			format!("vdash_errors_total{} 2", node),
+			format!("vdash_warnings_total{} 0", node),
+			format!("vdash_rewards_attos_total{} 2000000500", node),
+			format!("vdash_storage_ratio{} 0.25", node),
+		] {
+			assert!(text.lines().any(|line| line == sample), "missing '{}' in:\n{}", sample, text);
+		}
+		assert!(text.contains("# TYPE vdash_puts_total counter\n"));
+		assert!(text.contains("# TYPE vdash_storage_ratio gauge\n"));
+		assert!(!text.contains("vdash_peers"));
+
+		// Every line is a comment or a 'name{labels} value' sample
+		for line in text.lines() {
+			if !line.starts_with('#') {
+				let (series, value) = line.rsplit_once(' ').unwrap();
+				assert!(series.starts_with("vdash_") && series.ends_with('}'));
+				assert!(value.parse::<f64>().is_ok());
+			}
+		}
+	}
+
+	#[test]
+	fn escapes_node_labels() {
+		assert_eq!(escape_label("C:\\logs\\\"node\"\n"), "C:\\\\logs\\\\\\\"node\\\"\\n");
+	}
+
+	#[test]
+	fn serves_metrics_over_http() {
+		let snapshot: SharedSnapshot = Arc::new(Mutex::new(Some(one_node_snapshot())));
+		let addr = spawn_metrics_server("127.0.0.1:0", None, snapshot).unwrap();
+
+		let mut stream = TcpStream::connect(addr).unwrap();
+		write!(stream, "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		assert!(response.contains("vdash_gets_total{node=\"/home/user/node-1/safenode.log\"} 7\n"));
+
+		let mut stream = TcpStream::connect(addr).unwrap();
+		write!(stream, "GET / HTTP/1.1\r\n\r\n").unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+	}
+
+	#[test]
+	fn serves_metrics_while_another_client_is_slow() {
+		let snapshot: SharedSnapshot = Arc::new(Mutex::new(Some(one_node_snapshot())));
+		let addr = spawn_metrics_server("127.0.0.1:0", None, snapshot).unwrap();
+
+		let _slow = TcpStream::connect(addr).unwrap();
+		let started = std::time::Instant::now();
+		let mut stream = TcpStream::connect(addr).unwrap();
+		write!(stream, "GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		// Well within the 5s the slow client has to send its request
+		assert!(started.elapsed() < std::time::Duration::from_secs(4));
+	}
+
+	// A client sending an endless request line
+	struct EndlessRequest {
+		response: Vec<u8>,
+	}
+
+	impl Read for EndlessRequest {
+		fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+			std::io::repeat(b'a').read(buf)
+		}
+	}
+
+	impl Write for EndlessRequest {
+		fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+			self.response.write(buf)
+		}
+
+		fn flush(&mut self) -> Result<(), Error> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn cuts_short_an_endless_request_line() {
+		let mut client = EndlessRequest { response: Vec::new() };
+		serve_request(&mut client, &Arc::new(Mutex::new(None))).unwrap();
+		assert!(String::from_utf8(client.response).unwrap().starts_with("HTTP/1.1 404 Not Found\r\n"));
+	}
+
+	#[test]
+	fn resolves_ipv4_ipv6_and_hostname_addresses() {
+		let socket_addr = |addr: &str| addr.parse::<SocketAddr>().unwrap();
+		assert_eq!(resolve_listen_addr("0.0.0.0:9100"), Ok(vec![socket_addr("0.0.0.0:9100")]));
+		assert_eq!(resolve_listen_addr("127.0.0.1:9100"), Ok(vec![socket_addr("127.0.0.1:9100")]));
+		assert_eq!(resolve_listen_addr("[::1]:9100"), Ok(vec![socket_addr("[::1]:9100")]));
+		assert_eq!(resolve_listen_addr("[::]:0"), Ok(vec![socket_addr("[::]:0")]));
+
+		let localhost = resolve_listen_addr("localhost:9100").unwrap();
+		assert!(!localhost.is_empty());
+		assert!(localhost.iter().all(|addr| addr.ip().is_loopback() && addr.port() == 9100));
+
+		let expected = "expected host:port such as 127.0.0.1:9100, [::1]:9100 or localhost:9100";
+		for addr in &["9100", "localhost", ":9100", "127.0.0.1:http", "127.0.0.1:99999", "::1:9100", "[::1]"] {
+			assert_eq!(resolve_listen_addr(addr), Err(format!("invalid address '{}', {}", addr, expected)));
+		}
+		assert!(resolve_listen_addr("[not-an-ip]:9100").unwrap_err().starts_with("can't resolve '[not-an-ip]:9100': "));
+	}
+
+	#[test]
+	fn serves_metrics_on_ipv6_and_hostname_addresses() {
+		let snapshot: SharedSnapshot = Arc::new(Mutex::new(Some(one_node_snapshot())));
+		let addr = spawn_metrics_server("localhost:0", None, snapshot.clone()).unwrap();
+		assert!(addr.ip().is_loopback());
+		if let Ok(addr) = spawn_metrics_server("[::1]:0", None, snapshot) {
+			assert!(addr.is_ipv6()); // Unless IPv6 is disabled, as in some containers
+		}
+		let no_port = spawn_metrics_server("localhost", None, Arc::new(Mutex::new(None))).unwrap_err();
+		assert!(no_port.to_string().starts_with("invalid address 'localhost'"));
+	}
+
+	// A self-signed certificate for localhost and its key, written to dir
+	fn write_localhost_cert(dir: &Path) -> (PathBuf, PathBuf, CertificateDer<'static>) {
+		let generated = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+		let cert = dir.join("cert.pem");
+		let key = dir.join("key.pem");
+		std::fs::write(&cert, generated.cert.pem()).unwrap();
+		std::fs::write(&key, generated.key_pair.serialize_pem()).unwrap();
+		(cert, key, generated.cert.der().clone())
+	}
+
+	#[test]
+	fn requires_both_tls_cert_and_key() {
+		let dir = tempfile::tempdir().unwrap();
+		let (cert, key, _der) = write_localhost_cert(dir.path());
+
+		assert!(metrics_tls_config(None, None).unwrap().is_none());
+		assert_eq!(metrics_tls_config(Some(&cert), None).unwrap_err(), "--metrics-tls-cert needs --metrics-tls-key");
+		assert_eq!(metrics_tls_config(None, Some(&key)).unwrap_err(), "--metrics-tls-key needs --metrics-tls-cert");
+		assert_eq!(
+			metrics_tls_config(Some(&key), Some(&cert)).unwrap_err(),
+			format!("{} is not a PEM file holding a certificate", key.display())
+		);
+		let missing = dir.path().join("missing.pem");
+		assert!(metrics_tls_config(Some(&cert), Some(&missing)).unwrap_err().starts_with("failed to read "));
+
+		// A key which isn't that of the certificate
+		let other_dir = tempfile::tempdir().unwrap();
+		let (_other_cert, other_key, _der) = write_localhost_cert(other_dir.path());
+		let mismatched = metrics_tls_config(Some(&cert), Some(&other_key)).unwrap_err();
+		assert!(mismatched.starts_with("can't serve HTTPS with "), "{}", mismatched);
+
+		assert_eq!(metrics_tls_config(Some(&cert), Some(&key)).unwrap().unwrap().cert, cert);
+	}
+
+	#[test]
+	fn serves_metrics_over_https() {
+		let dir = tempfile::tempdir().unwrap();
+		let (cert, key, der) = write_localhost_cert(dir.path());
+		let tls = metrics_tls_config(Some(&cert), Some(&key)).unwrap();
+		let snapshot: SharedSnapshot = Arc::new(Mutex::new(Some(one_node_snapshot())));
+		let addr = spawn_metrics_server("127.0.0.1:0", tls.as_ref(), snapshot).unwrap();
+
+		let mut roots = rustls::RootCertStore::empty();
+		roots.add(der).unwrap();
+		let client_config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+			.with_safe_default_protocol_versions()
+			.unwrap()
+			.with_root_certificates(roots)
+			.with_no_client_auth();
+		let connection = rustls::ClientConnection::new(Arc::new(client_config), rustls::pki_types::ServerName::from(rustls::pki_types::DnsName::try_from_str("localhost").unwrap())).unwrap();
+		let mut stream = StreamOwned::new(connection, TcpStream::connect(addr).unwrap());
+		write!(stream, "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		assert!(response.contains("vdash_gets_total{node=\"/home/user/node-1/safenode.log\"} 7\n"));
+
+		// Plain HTTP gets no metrics
+		let mut stream = TcpStream::connect(addr).unwrap();
+		write!(stream, "GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+		let mut response = Vec::new();
+		let _ = stream.read_to_end(&mut response);
+		assert!(!String::from_utf8_lossy(&response).contains("vdash_gets_total"));
+	}
+}
@@ -0,0 +1,175 @@
+///! Live queries of node metrics over a Unix domain socket, for --query-socket
+///!
+///! Each line a client sends is a node name or logfile, or '*' for every
+///! node, and is answered by a line of JSON: the node's NodeSnapshot, the
+///! whole MetricsSnapshot for '*', or {"error": ...}. The socket is served
+///! on its own thread from the same snapshot as --metrics-addr.
+use std::io::{BufRead, BufReader, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::custom::prometheus::SharedSnapshot;
+use crate::custom::snapshot::MetricsSnapshot;
+
+///! The request for every node
+pub const ALL_NODES: &str = "*";
+
+///! The line of JSON answering a request, without its line ending
+pub fn query_response(snapshot: Option<&MetricsSnapshot>, request: &str) -> String {
+	let request = request.trim();
+	let snapshot = match snapshot {
+		Some(snapshot) => snapshot,
+		None => return error_json("no metrics yet"),
+	};
+	let response = if request == ALL_NODES {
+		serde_json::to_string(snapshot)
+	} else {
+		match snapshot.nodes.iter().find(|node| node.name == request || node.logfile == request) {
+			Some(node) => serde_json::to_string(node),
+			None => return error_json(&format!("no node '{}'", request)),
+		}
+	};
+	response.unwrap_or_else(|e| error_json(&e.to_string()))
+}
+
+fn error_json(message: &str) -> String {
+	json!({ "error": message }).to_string()
+}
+
+///! The listening socket, whose file is removed when this is dropped
+pub struct QuerySocket {
+	path: PathBuf,
+}
+
+impl Drop for QuerySocket {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_file(&self.path);
+	}
+}
+
+///! Start answering queries on a background thread. A socket file left by
+///! a vdash which didn't exit cleanly is replaced, but not one in use, and
+///! any other file at the path is left alone
+#[cfg(unix)]
+pub fn spawn_query_socket(path: &Path, snapshot: SharedSnapshot) -> Result<QuerySocket, Error> {
+	use std::os::unix::fs::FileTypeExt;
+	use std::os::unix::net::{UnixListener, UnixStream};
+	use std::thread;
+
+	match std::fs::symlink_metadata(path) {
+		Ok(metadata) if metadata.file_type().is_socket() => {
+			if UnixStream::connect(path).is_ok() {
+				return Err(Error::new(ErrorKind::AddrInUse, "the socket is in use"));
+			}
+			std::fs::remove_file(path)?;
+		}
+		Ok(_metadata) => {
+			return Err(Error::new(ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path.display())));
+		}
+		Err(e) if e.kind() == ErrorKind::NotFound => {}
+		Err(e) => return Err(e),
+	}
+	let listener = UnixListener::bind(path)?;
+	thread::spawn(move || {
+		for stream in listener.incoming().flatten() {
+			if let Err(e) = handle_connection(stream, &snapshot) {
+				log::debug!("metrics query failed: {}", e);
+			}
+		}
+	});
+	Ok(QuerySocket { path: path.to_path_buf() })
+}
+
+#[cfg(not(unix))]
+pub fn spawn_query_socket(_path: &Path, _snapshot: SharedSnapshot) -> Result<QuerySocket, Error> {
+	Err(Error::new(ErrorKind::Other, "Unix domain sockets are not supported on this platform"))
+}
+
+#[cfg(unix)]
+fn handle_connection(stream: std::os::unix::net::UnixStream, snapshot: &SharedSnapshot) -> Result<(), Error> {
+	stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+	let mut writer = &stream;
+	for request in BufReader::new(&stream).lines() {
+		let response = query_response(snapshot.lock().unwrap().as_ref(), &request?);
+		writeln!(writer, "{}", response)?;
+		writer.flush()?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::snapshot::NodeSnapshot;
+
+	fn two_node_snapshot() -> MetricsSnapshot {
+		let node = |name: &str, puts| NodeSnapshot {
+			logfile: format!("/home/user/{}/safenode.log", name),
+			name: name.to_string(),
+			puts,
+			..Default::default()
+		};
+		MetricsSnapshot {
+			timestamp: chrono::Utc::now(),
+			nodes: vec![node("node-1", 5), node("node-2", 7)],
+		}
+	}
+
+	#[test]
+	fn answers_queries_with_json() {
+		let snapshot = two_node_snapshot();
+		let response = |request| serde_json::from_str::<serde_json::Value>(&query_response(Some(&snapshot), request)).unwrap();
+
+		let node: NodeSnapshot = serde_json::from_value(response("node-2\n")).unwrap();
+		assert_eq!(node, snapshot.nodes[1]);
+		assert_eq!(response("/home/user/node-1/safenode.log")["puts"], 5);
+
+		let all: MetricsSnapshot = serde_json::from_value(response(" * ")).unwrap();
+		assert_eq!(all, snapshot);
+
+		assert_eq!(response("node-3"), json!({ "error": "no node 'node-3'" }));
+		assert_eq!(query_response(None, "*"), r#"{"error":"no metrics yet"}"#);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn serves_queries_over_socket() {
+		use std::os::unix::net::UnixStream;
+		use std::sync::{Arc, Mutex};
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash.sock");
+		drop(std::os::unix::net::UnixListener::bind(&path).unwrap()); // As left by a vdash which didn't exit cleanly
+		assert!(path.exists());
+		let snapshot: SharedSnapshot = Arc::new(Mutex::new(Some(two_node_snapshot())));
+		let socket = spawn_query_socket(&path, snapshot.clone()).unwrap();
+		assert!(spawn_query_socket(&path, snapshot).is_err());
+
+		let stream = UnixStream::connect(&path).unwrap();
+		writeln!(&stream, "node-1\n*").unwrap();
+		let mut lines = BufReader::new(&stream).lines();
+		let node: NodeSnapshot = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+		assert_eq!(node.puts, 5);
+		let all: MetricsSnapshot = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+		assert_eq!(all.nodes.len(), 2);
+
+		drop(socket);
+		assert!(!path.exists());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn leaves_other_files_alone() {
+		use std::sync::{Arc, Mutex};
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("notes.txt");
+		std::fs::write(&path, "not a socket").unwrap();
+		let snapshot: SharedSnapshot = Arc::new(Mutex::new(None));
+		let e = spawn_query_socket(&path, snapshot).err().unwrap();
+		assert_eq!(e.kind(), ErrorKind::AlreadyExists);
+		assert_eq!(e.to_string(), format!("{} exists and is not a socket", path.display()));
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+	}
+}
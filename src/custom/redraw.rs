@@ -0,0 +1,126 @@
+///! Coalescing of screen updates, so that drawing keeps to the tick rate
+///!
+///! Lines are parsed as fast as they arrive, which may be thousands a second
+///! from a busy node, but each only marks the screen out of date. It is then
+///! drawn once, however many lines arrived, when --tick-rate has passed since
+///! it was last drawn. Keys are still answered by drawing straight away.
+///!
+///! Terminals which report focus, such as those in a tmux pane with
+///! focus-events on, say when vdash is in the background. Until it is focused
+///! again ticks and frames slow to --idle-tick-rate, while lines are still read
+///! as they arrive. Terminals which never report focus keep the full rate
+use std::time::{Duration, Instant};
+
+///! The tick rate for the terminal's focus: the idle rate while it reports
+///! being unfocused, else the full rate, including when it has never said
+pub fn effective_tick_rate(tick_rate: Duration, idle_tick_rate: Duration, focused: Option<bool>) -> Duration {
+	match focused {
+		Some(false) => idle_tick_rate.max(tick_rate),
+		_ => tick_rate,
+	}
+}
+
+pub struct RedrawLimiter {
+	interval: Duration, // Least time between frames
+	idle_interval: Duration, // While the terminal is unfocused
+	focused: Option<bool>, // As last reported by the terminal, None if never
+	last_drawn: Option<Instant>,
+	stale: bool, // Something shown has changed since the last frame
+	frames: u64, // Drawn so far
+}
+
+impl RedrawLimiter {
+	pub fn new(interval: Duration, idle_interval: Duration) -> RedrawLimiter {
+		RedrawLimiter {
+			interval,
+			idle_interval,
+			focused: None,
+			last_drawn: None,
+			stale: true,
+			frames: 0,
+		}
+	}
+
+	///! Note a change to what is shown, to be drawn in the next frame
+	pub fn mark_stale(&mut self) {
+		self.stale = true;
+	}
+
+	///! True if a frame should be drawn now: something has changed and the
+	///! interval has passed since the last
+	pub fn is_due(&self, now: Instant) -> bool {
+		let interval = self.tick_rate();
+		self.stale && self.last_drawn.is_none_or(|last_drawn| now.duration_since(last_drawn) >= interval)
+	}
+
+	///! Note the terminal gaining or losing focus, returning the tick rate for it.
+	///! Regaining focus draws afresh at the next chance
+	pub fn set_focused(&mut self, focused: bool) -> Duration {
+		self.focused = Some(focused);
+		self.stale = true;
+		self.tick_rate()
+	}
+
+	pub fn tick_rate(&self) -> Duration {
+		effective_tick_rate(self.interval, self.idle_interval, self.focused)
+	}
+
+	///! Note that a frame has been drawn, however it was prompted
+	pub fn drawn(&mut self, now: Instant) {
+		self.last_drawn = Some(now);
+		self.stale = false;
+		self.frames += 1;
+	}
+
+	pub fn frames(&self) -> u64 {
+		self.frames
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn draws_once_however_many_changes_between_ticks() {
+		let start = Instant::now();
+		let tick = Duration::from_millis(200);
+		let mut redraw = RedrawLimiter::new(tick, tick * 10);
+		assert!(redraw.is_due(start));
+		redraw.drawn(start);
+		assert!(!redraw.is_due(start + tick)); // Nothing has changed
+
+		for line in 0..10_000 {
+			redraw.mark_stale();
+			let now = start + Duration::from_micros(line);
+			if redraw.is_due(now) {
+				redraw.drawn(now);
+			}
+		}
+		assert_eq!(redraw.frames(), 1);
+		let next_tick = start + tick;
+		assert!(redraw.is_due(next_tick));
+		redraw.drawn(next_tick);
+		assert_eq!(redraw.frames(), 2);
+		assert!(!redraw.is_due(next_tick + tick));
+	}
+
+	#[test]
+	fn slows_to_the_idle_rate_while_unfocused() {
+		let tick = Duration::from_millis(200);
+		let idle = Duration::from_secs(2);
+		assert_eq!(effective_tick_rate(tick, idle, None), tick); // The terminal doesn't report focus
+		assert_eq!(effective_tick_rate(tick, idle, Some(true)), tick);
+		assert_eq!(effective_tick_rate(tick, idle, Some(false)), idle);
+		assert_eq!(effective_tick_rate(tick, Duration::from_millis(50), Some(false)), tick); // Never faster
+
+		let start = Instant::now();
+		let mut redraw = RedrawLimiter::new(tick, idle);
+		redraw.drawn(start);
+		assert_eq!(redraw.set_focused(false), idle);
+		assert!(!redraw.is_due(start + tick));
+		assert!(redraw.is_due(start + idle));
+		assert_eq!(redraw.set_focused(true), tick);
+		assert!(redraw.is_due(start + tick));
+	}
+}
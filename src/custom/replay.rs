@@ -0,0 +1,168 @@
+///! Replay of a whole logfile at speed with --replay, for post-mortems
+///!
+///! The lines are read up front and fed to the node one tick at a time, paced
+///! by the times they were logged scaled by --speed, so that at 60x an hour of
+///! logging replays in a minute. The timelines follow the replay's clock
+///! rather than the time now, so they evolve as they did when the lines were
+///! logged. Lines without a time go with the line before them. The clock
+///! stops while paused, and at the last line once every line has been replayed
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::custom::app::{decode_line, LogEntry};
+use crate::custom::log_profile::LogProfile;
+
+///! The wall clock time to wait between lines logged at these times, at speed.
+///! Lines logged out of order are replayed without waiting
+pub fn scaled_delay(from: &DateTime<Utc>, to: &DateTime<Utc>, speed: f64) -> Duration {
+	let logged = (*to - *from).to_std().unwrap_or_default();
+	if speed > 0.0 {
+		logged.div_f64(speed)
+	} else {
+		logged // Not a speed parse_speed() accepts
+	}
+}
+
+///! Parses --speed, a factor greater than zero such as 60 or 0.5
+pub fn parse_speed(speed: &str) -> Result<f64, String> {
+	match speed.parse::<f64>() {
+		Ok(factor) if factor > 0.0 && factor.is_finite() => Ok(factor),
+		_ => Err(format!("invalid speed '{}', expected a factor greater than 0 such as 60", speed)),
+	}
+}
+
+pub struct Replay {
+	lines: VecDeque<(DateTime<Utc>, String)>, // Yet to be replayed, with the time each was logged
+	speed: f64,
+	clock: Option<DateTime<Utc>>, // The time in the log replayed up to, None without any lines
+	last_advanced: Option<Instant>,
+}
+
+impl Replay {
+	pub fn load(path: &str, speed: f64, profile: &LogProfile) -> std::io::Result<Replay> {
+		let bytes = std::fs::read(path)?;
+		let lines = bytes.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()).map(decode_line);
+		Ok(Replay::from_lines(lines, speed, profile))
+	}
+
+	pub fn from_lines(lines: impl Iterator<Item = String>, speed: f64, profile: &LogProfile) -> Replay {
+		let mut timed = VecDeque::<(DateTime<Utc>, String)>::new();
+		let mut untimed = Vec::<String>::new(); // Before the first timed line
+		for line in lines {
+			let time = LogEntry::decode_metadata(&line, profile).filter(|metadata| metadata.timed).map(|metadata| metadata.time);
+			match time.or_else(|| timed.back().map(|(time, _line)| *time)) {
+				Some(time) => {
+					for line in untimed.drain(..) {
+						timed.push_back((time, line));
+					}
+					timed.push_back((time, line));
+				}
+				None => untimed.push(line),
+			}
+		}
+		let now = Utc::now();
+		timed.extend(untimed.into_iter().map(|line| (now, line))); // No line had a time
+		Replay {
+			clock: timed.front().map(|(time, _line)| *time),
+			lines: timed,
+			speed,
+			last_advanced: None,
+		}
+	}
+
+	///! The time in the log replayed up to, for the timelines
+	pub fn clock(&self) -> Option<DateTime<Utc>> {
+		self.clock
+	}
+
+	pub fn is_ended(&self) -> bool {
+		self.lines.is_empty()
+	}
+
+	///! Wall clock time until the next line is due, None once all are replayed
+	pub fn next_delay(&self) -> Option<Duration> {
+		let (next, _line) = self.lines.front()?;
+		Some(self.clock.map_or(Duration::default(), |clock| scaled_delay(&clock, next, self.speed)))
+	}
+
+	///! Move the clock on by the time since last advanced, at speed, returning
+	///! the lines logged up to it. While paused the clock stands still
+	pub fn advance(&mut self, now: Instant, paused: bool) -> Vec<String> {
+		let elapsed = self.last_advanced.map_or(Duration::default(), |last| now.duration_since(last));
+		self.last_advanced = Some(now);
+		let clock = match self.clock {
+			Some(clock) if !paused && !self.is_ended() => {
+				let replayed = chrono::Duration::from_std(elapsed.mul_f64(self.speed)).unwrap_or_else(|_e| chrono::Duration::zero());
+				clock + replayed
+			}
+			_ => return Vec::new(),
+		};
+		let mut due = Vec::<String>::new();
+		let mut latest = None::<DateTime<Utc>>;
+		while self.lines.front().is_some_and(|(time, _line)| *time <= clock) {
+			if let Some((time, line)) = self.lines.pop_front() {
+				latest = Some(latest.map_or(time, |latest| latest.max(time)));
+				due.push(line);
+			}
+		}
+		// Once ended, stop at the last line rather than running on
+		self.clock = Some(if self.is_ended() { latest.unwrap_or(clock) } else { clock });
+		due
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::TimeZone;
+
+	fn logged_at(second: u32, message: &str) -> String {
+		format!("[2024-03-01T12:00:{:02}.000000Z INFO sn_node] {}", second, message)
+	}
+
+	#[test]
+	fn scales_delays_between_lines_by_speed() {
+		let at = |second: u32| Utc.ymd(2024, 3, 1).and_hms(12, 0, second);
+		assert_eq!(scaled_delay(&at(0), &at(30), 1.0), Duration::from_secs(30));
+		assert_eq!(scaled_delay(&at(0), &at(30), 60.0), Duration::from_millis(500));
+		assert_eq!(scaled_delay(&at(0), &at(30), 0.5), Duration::from_secs(60));
+		assert_eq!(scaled_delay(&at(30), &at(0), 60.0), Duration::default()); // Out of order
+
+		let lines = vec![logged_at(0, "a"), logged_at(6, "b"), String::from("  continues b"), logged_at(18, "c")];
+		let mut replay = Replay::from_lines(lines.into_iter(), 6.0, LogProfile::auto());
+		assert_eq!(replay.next_delay(), Some(Duration::default()));
+		let start = Instant::now();
+		assert_eq!(replay.advance(start, false), vec![logged_at(0, "a")]);
+		assert_eq!(replay.next_delay(), Some(Duration::from_secs(1)));
+		assert!(replay.advance(start + Duration::from_millis(500), false).is_empty());
+		assert_eq!(replay.advance(start + Duration::from_secs(1), false), vec![logged_at(6, "b"), String::from("  continues b")]);
+		assert_eq!(replay.next_delay(), Some(Duration::from_secs(2)));
+		assert_eq!(replay.clock(), Some(Utc.ymd(2024, 3, 1).and_hms(12, 0, 6)));
+	}
+
+	#[test]
+	fn stands_still_while_paused_and_once_ended() {
+		let lines = vec![logged_at(0, "a"), logged_at(10, "b")];
+		let mut replay = Replay::from_lines(lines.into_iter(), 10.0, LogProfile::auto());
+		let start = Instant::now();
+		replay.advance(start, false);
+		assert!(replay.advance(start + Duration::from_secs(5), true).is_empty());
+		assert_eq!(replay.clock(), Some(Utc.ymd(2024, 3, 1).and_hms(12, 0, 0)));
+		assert_eq!(replay.advance(start + Duration::from_secs(6), false), vec![logged_at(10, "b")]);
+		assert!(replay.is_ended());
+		assert_eq!(replay.next_delay(), None);
+		assert_eq!(replay.clock(), Some(Utc.ymd(2024, 3, 1).and_hms(12, 0, 10)));
+		replay.advance(start + Duration::from_secs(60), false);
+		assert_eq!(replay.clock(), Some(Utc.ymd(2024, 3, 1).and_hms(12, 0, 10)));
+	}
+
+	#[test]
+	fn parses_speed() {
+		assert_eq!(parse_speed("60"), Ok(60.0));
+		assert_eq!(parse_speed("0.5"), Ok(0.5));
+		assert!(parse_speed("0").is_err());
+		assert!(parse_speed("-2").is_err());
+		assert!(parse_speed("fast").is_err());
+	}
+}
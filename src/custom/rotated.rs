@@ -0,0 +1,120 @@
+///! Rotated and compressed logfiles of a node, replayed for --replay-gz
+///!
+///! Those beside a logfile whose names start with its own up to the first
+///! '.' and end '.gz', such as safenode.log.1.gz for safenode.log, are read
+///! oldest first before the logfile itself so its timelines have history.
+use std::fs;
+use std::io::{BufRead, BufReader, Error};
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+
+use crate::custom::app::decode_line;
+
+///! The rotated logfiles of a logfile, oldest (least recently modified) first
+pub fn rotated_logfiles(logfile: &str) -> Vec<PathBuf> {
+	let logfile = Path::new(logfile);
+	let prefix = match logfile.file_name().and_then(|name| name.to_str()) {
+		Some(name) => format!("{}.", name.split('.').next().unwrap_or(name)),
+		None => return Vec::new(),
+	};
+	let dir = match logfile.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir,
+		_ => Path::new("."),
+	};
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(_e) => return Vec::new(),
+	};
+	let mut rotated: Vec<(std::time::SystemTime, PathBuf)> = entries
+		.flatten()
+		.filter(|entry| {
+			let name = entry.file_name().to_string_lossy().to_string();
+			name.starts_with(&prefix) && name.ends_with(".gz")
+		})
+		.filter_map(|entry| {
+			let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok()?;
+			Some((modified, entry.path()))
+		})
+		.collect();
+	rotated.sort();
+	rotated.into_iter().map(|(_modified, path)| path).collect()
+}
+
+///! The lines of a gzipped logfile, decompressed as they are read. A file
+///! which isn't gzipped fails on reading the first line
+pub fn read_gz_lines(path: &Path) -> Result<impl Iterator<Item = Result<String, Error>>, Error> {
+	let reader = BufReader::new(MultiGzDecoder::new(fs::File::open(path)?));
+	Ok(reader.split(b'\n').map(|bytes| bytes.map(|bytes| decode_line(&bytes))))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::app::LogMonitor;
+	use crate::custom::opt::{Opt, StructOpt};
+	use std::io::Write;
+	use std::time::{Duration, SystemTime};
+
+	static PUT_LINE: &str = "[sn] INFO 2024-01-15T20:21:02.659471Z [src]: Successfully stored record\n";
+
+	fn gzip(text: &str) -> Vec<u8> {
+		let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+		encoder.write_all(text.as_bytes()).unwrap();
+		encoder.finish().unwrap()
+	}
+
+	fn write_modified(path: &Path, contents: &[u8], modified: SystemTime) {
+		fs::write(path, contents).unwrap();
+		fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+	}
+
+	#[test]
+	fn finds_rotated_logfiles_oldest_first() {
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("safenode.log");
+		fs::write(&logfile, "").unwrap();
+		let now = SystemTime::now();
+		let sample = gzip(PUT_LINE);
+		write_modified(&dir.path().join("safenode.log.1.gz"), &sample, now - Duration::from_secs(60));
+		write_modified(&dir.path().join("safenode.log.2.gz"), &sample, now - Duration::from_secs(3600));
+		write_modified(&dir.path().join("safenode.20240115.log.gz"), &sample, now - Duration::from_secs(7200));
+		write_modified(&dir.path().join("othernode.log.1.gz"), &sample, now);
+		write_modified(&dir.path().join("safenode.log.1"), &sample, now);
+
+		let names: Vec<String> = rotated_logfiles(logfile.to_str().unwrap())
+			.iter()
+			.map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+			.collect();
+		assert_eq!(names, vec!["safenode.20240115.log.gz", "safenode.log.2.gz", "safenode.log.1.gz"]);
+		assert!(rotated_logfiles(dir.path().join("missing/safenode.log").to_str().unwrap()).is_empty());
+	}
+
+	#[test]
+	fn replays_gzipped_lines_into_metrics() {
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("safenode.log");
+		// Written as two gzip members, as by appending to a gzipped file
+		let mut sample = gzip(PUT_LINE);
+		sample.extend(gzip("not a log line\r\nlast line unterminated"));
+		fs::write(dir.path().join("safenode.log.1.gz"), &sample).unwrap();
+		fs::write(dir.path().join("safenode.log.2.gz"), b"not gzip").unwrap();
+
+		let lines = |name: &str| read_gz_lines(&dir.path().join(name)).unwrap().collect::<Result<Vec<String>, Error>>();
+		assert_eq!(lines("safenode.log.1.gz").unwrap(), vec![
+			PUT_LINE.trim_end(),
+			"not a log line",
+			"last line unterminated",
+		]);
+		assert!(lines("safenode.log.2.gz").is_err());
+		assert!(read_gz_lines(&dir.path().join("missing.gz")).is_err());
+
+		let opt = Opt::from_iter(vec!["vdash"]);
+		let mut monitor = LogMonitor::new(&opt, logfile.to_str().unwrap().to_string(), opt.lines_max);
+		let warnings = monitor.replay_rotated_logfiles();
+		assert_eq!(monitor.metrics.activity_puts, 1);
+		assert_eq!(monitor.tail.len(), 3);
+		assert_eq!(warnings.len(), 1);
+		assert!(warnings[0].contains("safenode.log.2.gz"), "{}", warnings[0]);
+	}
+}
@@ -0,0 +1,42 @@
+///! Exit after --run-for seconds, e.g. to take screenshots in CI
+///!
+///! The timer starts when vdash is launched and is checked each tick. Once it
+///! has run out vdash draws a last frame and quits as if 'q' had been pressed,
+///! saving its state and restoring the terminal, with an exit code of 0
+use std::time::{Duration, Instant};
+
+pub struct RunFor {
+	deadline: Option<Instant>, // None to run until quit
+}
+
+impl RunFor {
+	pub fn new(started: Instant, run_for: Option<Duration>) -> RunFor {
+		RunFor {
+			deadline: run_for.and_then(|run_for| started.checked_add(run_for)),
+		}
+	}
+
+	///! True once the time to run for has passed
+	pub fn is_over(&self, now: Instant) -> bool {
+		self.deadline.is_some_and(|deadline| now >= deadline)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn is_over_once_the_time_has_elapsed() {
+		let started = Instant::now();
+		let run_for = RunFor::new(started, Some(Duration::from_secs(5)));
+		assert!(!run_for.is_over(started));
+		assert!(!run_for.is_over(started + Duration::from_millis(4_999)));
+		assert!(run_for.is_over(started + Duration::from_secs(5)));
+		assert!(run_for.is_over(started + Duration::from_secs(60)));
+
+		assert!(RunFor::new(started, Some(Duration::default())).is_over(started));
+		let forever = RunFor::new(started, None);
+		assert!(!forever.is_over(started + Duration::from_secs(365 * 24 * 60 * 60)));
+	}
+}
@@ -0,0 +1,99 @@
+///! The dashboard as text, written by the 'u' key to --snapshot
+///!
+///! The screen is drawn again to an off-screen buffer the size of the
+///! terminal, and each row of cells written as a line of plain text, or
+///! with --snapshot-ansi as text coloured by SGR escape sequences. This
+///! also lets tests compare what is drawn with the text expected
+use std::io::Error;
+use std::path::Path;
+
+use tui::{
+	backend::TestBackend,
+	buffer::Buffer,
+	layout::Rect,
+	style::{Color, Modifier},
+	Terminal,
+};
+
+use crate::custom::ansi::sgr_sequence;
+use crate::custom::app::App;
+use crate::custom::ui::draw_dashboard;
+
+///! The rows of the buffer as lines of text, without trailing spaces. With
+///! ansi each change of style starts with an SGR sequence, and each styled
+///! line ends by resetting the style
+pub fn buffer_to_text(buffer: &Buffer, ansi: bool) -> String {
+	let area = buffer.area();
+	let mut text = String::new();
+	for y in area.top()..area.bottom() {
+		let mut line = String::new();
+		let mut style = None;
+		for x in area.left()..area.right() {
+			let cell = buffer.get(x, y);
+			if ansi {
+				let cell_style = (cell.fg, cell.bg, cell.modifier);
+				if style != Some(cell_style) {
+					line.push_str(&sgr_sequence(cell.fg, cell.bg, cell.modifier));
+					style = Some(cell_style);
+				}
+			}
+			line.push_str(&cell.symbol);
+		}
+		if ansi {
+			line.push_str(&sgr_sequence(Color::Reset, Color::Reset, Modifier::empty()));
+		} else {
+			line.truncate(line.trim_end().len());
+		}
+		text.push_str(&line);
+		text.push('\n');
+	}
+	text
+}
+
+///! The dashboard as it would be drawn on a terminal of the given size
+pub fn draw_to_buffer(app: &mut App, area: Rect) -> Result<Buffer, Error> {
+	let mut terminal = Terminal::new(TestBackend::new(area.width, area.height))?;
+	terminal.draw(|f| draw_dashboard(f, app))?;
+	Ok(terminal.backend().buffer().clone())
+}
+
+pub fn write_screenshot(app: &mut App, area: Rect, path: &Path, ansi: bool) -> Result<(), Error> {
+	let buffer = draw_to_buffer(app, area)?;
+	std::fs::write(path, buffer_to_text(&buffer, ansi))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tui::style::Style;
+	use tui::widgets::{Block, Borders, Paragraph, Widget};
+
+	fn small_layout() -> Buffer {
+		let area = Rect::new(0, 0, 12, 4);
+		let mut buffer = Buffer::empty(area);
+		let paragraph = Paragraph::new("vdash").block(Block::default().title("Node").borders(Borders::ALL));
+		paragraph.render(area, &mut buffer);
+		buffer.set_style(Rect::new(1, 1, 5, 1), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+		buffer
+	}
+
+	#[test]
+	fn writes_the_buffer_as_text() {
+		assert_eq!(
+			buffer_to_text(&small_layout(), false),
+			"┌Node──────┐\n\
+			│vdash     │\n\
+			│          │\n\
+			└──────────┘\n"
+		);
+	}
+
+	#[test]
+	fn writes_styles_as_ansi_sequences() {
+		let text = buffer_to_text(&small_layout(), true);
+		let lines: Vec<&str> = text.lines().collect();
+		assert_eq!(lines.len(), 4);
+		assert_eq!(lines[0], "\x1b[0m┌Node──────┐\x1b[0m");
+		assert_eq!(lines[1], "\x1b[0m│\x1b[0;1;31mvdash\x1b[0m     │\x1b[0m");
+	}
+}
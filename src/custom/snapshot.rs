@@ -0,0 +1,154 @@
+///! Snapshot of node metrics for export, as written by --dump-json
+///!
+///! Field names form the JSON output so treat them as a stable interface:
+///! add new fields rather than renaming or removing existing ones.
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::custom::app::LogMonitor;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MetricsSnapshot {
+	pub timestamp: DateTime<Utc>,
+	pub nodes: Vec<NodeSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct NodeSnapshot {
+	pub logfile: String,
+	#[serde(default)]
+	pub name: String, // As shown by the dashboard, see --names
+	pub node_started: Option<DateTime<Utc>>,
+	pub running_version: Option<String>,
+	pub peer_id: Option<String>,
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub warnings: u64,
+	pub storage_used: u64,
+	pub storage_max: u64,
+	pub storage_ratio: Option<f64>, // None until the node's capacity is known
+	pub rewards: u128,              // In attos
+	pub peers: Option<u64>,
+	pub bytes_in: u64,
+	pub bytes_out: u64,
+}
+
+impl MetricsSnapshot {
+	///! Snapshot of the monitors in the order given by logfile_names
+	pub fn from_monitors(logfile_names: &[String], monitors: &HashMap<String, LogMonitor>) -> MetricsSnapshot {
+		let nodes = logfile_names
+			.iter()
+			.filter_map(|logfile| monitors.get(logfile))
+			.filter(|monitor| !monitor.is_debug_dashboard_log)
+			.map(NodeSnapshot::from_monitor)
+			.collect();
+
+		MetricsSnapshot {
+			timestamp: Utc::now(),
+			nodes,
+		}
+	}
+
+	pub fn to_json(&self) -> Result<String, Error> {
+		serde_json::to_string(self).map_err(Error::other)
+	}
+
+	///! Write the snapshot to a file, or as a line to stdout if the path is '-'
+	pub fn write_json(&self, path: &str) -> Result<(), Error> {
+		let json = self.to_json()?;
+		if path == "-" {
+			let stdout = std::io::stdout();
+			let mut stdout = stdout.lock();
+			writeln!(stdout, "{}", json)?;
+			return stdout.flush();
+		}
+
+		// Write via a temporary file so readers never see a partial snapshot
+		let mut temp_path = Path::new(path).as_os_str().to_owned();
+		temp_path.push(".tmp");
+		fs::write(&temp_path, json)?;
+		fs::rename(&temp_path, path)
+	}
+}
+
+impl NodeSnapshot {
+	pub fn from_monitor(monitor: &LogMonitor) -> NodeSnapshot {
+		let metrics = &monitor.metrics;
+		NodeSnapshot {
+			logfile: monitor.logfile.clone(),
+			name: monitor.name.clone(),
+			node_started: metrics.node_started,
+			running_version: metrics.running_version.clone(),
+			peer_id: metrics.peer_id.clone(),
+			puts: metrics.activity_puts,
+			gets: metrics.activity_gets,
+			errors: metrics.activity_errors,
+			warnings: metrics.activity_warnings,
+			storage_used: metrics.used_space,
+			storage_max: metrics.capacity().unwrap_or(0),
+			storage_ratio: metrics.storage_ratio(),
+			rewards: metrics.rewards,
+			peers: Some(metrics.peers),
+			bytes_in: metrics.bytes_in,
+			bytes_out: metrics.bytes_out,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	#[test]
+	fn serializes_populated_snapshot() {
+		let opt = Opt::from_iter(vec!["vdash"]);
+		let mut monitor = LogMonitor::new(&opt, "node-1/safenode.log".to_string(), opt.lines_max);
+		monitor.metrics.activity_puts = 5;
+		monitor.metrics.activity_gets = 7;
+		monitor.metrics.activity_errors = 2;
+		monitor.metrics.used_space = 256;
+		monitor.metrics.max_capacity = 1024;
+		monitor.metrics.rewards = 2_000_000_500;
+
+		let mut monitors = HashMap::new();
+		monitors.insert(monitor.logfile.clone(), monitor);
+		let logfile_names = vec!["node-1/safenode.log".to_string(), "missing.log".to_string()];
+		let snapshot = MetricsSnapshot::from_monitors(&logfile_names, &monitors);
+
+		let json: serde_json::Value = serde_json::from_str(&snapshot.to_json().unwrap()).unwrap();
+		assert!(json.get("timestamp").is_some());
+		let nodes = json["nodes"].as_array().unwrap();
+		assert_eq!(nodes.len(), 1);
+		let node = &nodes[0];
+		for key in &[
+			"logfile", "name", "node_started", "running_version", "peer_id", "puts", "gets", "errors", "warnings",
+			"storage_used", "storage_max", "storage_ratio", "rewards", "peers", "bytes_in", "bytes_out",
+		] {
+			assert!(node.get(key).is_some(), "missing key '{}'", key);
+		}
+		assert_eq!(node["logfile"], "node-1/safenode.log");
+		assert_eq!(node["puts"], 5);
+		assert_eq!(node["gets"], 7);
+		assert_eq!(node["errors"], 2);
+		assert_eq!(node["storage_ratio"], 0.25);
+		assert_eq!(node["rewards"], 2_000_000_500u64);
+		assert_eq!(node["peers"], 0);
+	}
+
+	#[test]
+	fn writes_snapshot_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("metrics.json");
+		let snapshot = MetricsSnapshot::from_monitors(&[], &HashMap::new());
+		snapshot.write_json(path.to_str().unwrap()).unwrap();
+		let written: MetricsSnapshot = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+		assert_eq!(written, snapshot);
+	}
+}
@@ -0,0 +1,224 @@
+///! Lines picked out by keyword with --spotlight, gathered from every node
+///!
+///! The TOML file lists keywords, each with a severity:
+///!
+///!	[[keyword]]
+///!	text = "slashed"
+///!	severity = "critical"
+///!
+///!	[[keyword]]
+///!	text = "penalty"
+///!
+///! Keywords match anywhere in a line, ignoring case. As its lines are
+///! classified each node adds those containing a keyword to one buffer shared
+///! by all the nodes, shown above the warnings view, and they are highlighted
+///! in the detail view's log. A line containing several keywords is given the
+///! most severe. Severities are info, warning (the default) and critical, and
+///! up to --warnings-max lines are kept, dropping the oldest
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::custom::alerts::Severity;
+
+pub type SharedSpotlight = Arc<Mutex<Spotlight>>;
+
+#[derive(Deserialize)]
+struct SpotlightFile {
+	#[serde(default)]
+	keyword: Vec<KeywordEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeywordEntry {
+	text: String,
+	severity: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpotlightKeyword {
+	pub text: String, // In lower case, to match any case
+	pub severity: Severity,
+}
+
+pub fn load_spotlight_keywords(path: &Path) -> Result<Vec<SpotlightKeyword>, String> {
+	let toml = std::fs::read_to_string(path).map_err(|e| format!("failed to read spotlight {}: {}", path.display(), e))?;
+	spotlight_keywords_from_toml(&toml).map_err(|e| format!("invalid spotlight {}: {}", path.display(), e))
+}
+
+pub fn spotlight_keywords_from_toml(toml: &str) -> Result<Vec<SpotlightKeyword>, String> {
+	let file: SpotlightFile = toml::from_str(toml).map_err(|e| e.to_string())?;
+	let mut keywords = Vec::<SpotlightKeyword>::new();
+	for entry in file.keyword {
+		let text = entry.text.trim().to_lowercase();
+		if text.is_empty() {
+			return Err(String::from("empty keyword"));
+		}
+		let severity = match &entry.severity {
+			Some(severity) => severity.parse::<Severity>()?,
+			None => Severity::Warning,
+		};
+		keywords.push(SpotlightKeyword { text, severity });
+	}
+	Ok(keywords)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpotlightEntry {
+	pub node: String, // The name of the node, see --names
+	pub time: DateTime<Utc>,
+	pub keyword: String,
+	pub severity: Severity,
+	pub line: String,
+}
+
+impl SpotlightEntry {
+	///! As shown above the warnings view, e.g. '20:21:02 node-1 [slashed]: ...'
+	pub fn describe(&self) -> String {
+		format!("{} {} [{}]: {}", self.time.format("%H:%M:%S"), self.node, self.keyword, self.line.trim())
+	}
+}
+
+///! The keywords, and a fixed capacity ring buffer of the lines matching them, oldest first
+pub struct Spotlight {
+	keywords: Vec<SpotlightKeyword>,
+	entries: VecDeque<SpotlightEntry>,
+	capacity: usize,
+}
+
+impl Spotlight {
+	pub fn new(keywords: Vec<SpotlightKeyword>, capacity: usize) -> Spotlight {
+		Spotlight {
+			keywords,
+			entries: VecDeque::with_capacity(capacity),
+			capacity,
+		}
+	}
+
+	pub fn shared(keywords: Vec<SpotlightKeyword>, capacity: usize) -> SharedSpotlight {
+		Arc::new(Mutex::new(Spotlight::new(keywords, capacity)))
+	}
+
+	pub fn has_keywords(&self) -> bool {
+		!self.keywords.is_empty()
+	}
+
+	///! The most severe keyword in the line, the first given of those as severe
+	pub fn keyword_in(&self, line: &str) -> Option<&SpotlightKeyword> {
+		if self.keywords.is_empty() {
+			return None;
+		}
+		let line = line.to_lowercase();
+		self.keywords.iter().filter(|keyword| line.contains(&keyword.text)).fold(None, |found, keyword| match found {
+			Some(found) if found.severity >= keyword.severity => Some(found),
+			_ => Some(keyword),
+		})
+	}
+
+	///! Keep the line if it contains a keyword, dropping the oldest once full.
+	///! True if it did
+	pub fn check(&mut self, node: &str, time: DateTime<Utc>, line: &str) -> bool {
+		let (keyword, severity) = match self.keyword_in(line) {
+			Some(keyword) => (keyword.text.clone(), keyword.severity),
+			None => return false,
+		};
+		if self.capacity > 0 {
+			if self.entries.len() == self.capacity {
+				self.entries.pop_front();
+			}
+			self.entries.push_back(SpotlightEntry {
+				node: node.to_string(),
+				time,
+				keyword,
+				severity,
+				line: line.to_string(),
+			});
+		}
+		true
+	}
+
+	///! The entries held, oldest first
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &SpotlightEntry> + ExactSizeIterator {
+		self.entries.iter()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::TimeZone;
+
+	const KEYWORDS: &str = r#"
+		[[keyword]]
+		text = "penalty"
+
+		[[keyword]]
+		text = "Slashed"
+		severity = "critical"
+
+		[[keyword]]
+		text = "my-keyword"
+		severity = "info"
+	"#;
+
+	#[test]
+	fn routes_lines_with_keywords_into_the_spotlight() {
+		let mut spotlight = Spotlight::new(spotlight_keywords_from_toml(KEYWORDS).unwrap(), 10);
+		assert!(spotlight.has_keywords());
+		let time = Utc.ymd(2022, 1, 15).and_hms(20, 21, 2);
+		let lines = [
+			"[2022-01-15T20:21:02Z INFO sn_node] Stored record 8a3f",
+			"[2022-01-15T20:21:02Z WARN sn_node] Node SLASHED for bad behaviour",
+			"[2022-01-15T20:21:02Z INFO sn_node] Penalty applied, and slashed",
+			"[2022-01-15T20:21:02Z DEBUG sn_node] found My-Keyword here",
+			"[2022-01-15T20:21:02Z ERROR sn_node] Failed to connect",
+		];
+		let kept: Vec<bool> = lines.iter().map(|line| spotlight.check("node-1", time, line)).collect();
+		assert_eq!(kept, vec![false, true, true, true, false]);
+
+		let found: Vec<(&str, Severity)> = spotlight.iter().map(|entry| (entry.keyword.as_str(), entry.severity)).collect();
+		assert_eq!(found, vec![("slashed", Severity::Critical), ("slashed", Severity::Critical), ("my-keyword", Severity::Info)]);
+		assert_eq!(
+			spotlight.iter().next().unwrap().describe(),
+			"20:21:02 node-1 [slashed]: [2022-01-15T20:21:02Z WARN sn_node] Node SLASHED for bad behaviour"
+		);
+		assert_eq!(spotlight.keyword_in("a PENALTY").map(|keyword| keyword.severity), Some(Severity::Warning));
+	}
+
+	#[test]
+	fn keeps_nothing_without_keywords_or_capacity() {
+		let time = Utc::now();
+		let mut spotlight = Spotlight::new(Vec::new(), 10);
+		assert!(!spotlight.check("node-1", time, "slashed"));
+		assert!(spotlight.is_empty());
+
+		let mut spotlight = Spotlight::new(spotlight_keywords_from_toml(KEYWORDS).unwrap(), 2);
+		for line in &["penalty 1", "penalty 2", "penalty 3"] {
+			spotlight.check("node-1", time, line);
+		}
+		let lines: Vec<&str> = spotlight.iter().map(|entry| entry.line.as_str()).collect();
+		assert_eq!(lines, vec!["penalty 2", "penalty 3"]);
+	}
+
+	#[test]
+	fn reports_bad_keywords() {
+		let error = |toml: &str| spotlight_keywords_from_toml(toml).err().unwrap();
+		assert_eq!(error("[[keyword]]\ntext = \" \""), "empty keyword");
+		assert_eq!(
+			error("[[keyword]]\ntext = \"slashed\"\nseverity = \"dire\""),
+			"invalid severity 'dire', expected info, warning or critical"
+		);
+		assert!(spotlight_keywords_from_toml("").unwrap().is_empty());
+	}
+}
@@ -0,0 +1,265 @@
+///! Dashboard state saved and restored using --state-file
+///!
+///! Node counters and timeline buckets are saved as JSON so that a
+///! restarted vdash continues from where it left off. Each node also
+///! records how much of its logfile had been processed, so that only lines
+///! appended since the state was saved are loaded on restart.
+use std::collections::HashMap;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::custom::app::{LogMonitor, NodeMetrics};
+use crate::custom::stdin::STDIN_LOGFILE;
+use crate::custom::timelines::TimelineSet;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct DashboardState {
+	pub nodes: HashMap<String, NodeState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct NodeState {
+	pub logfile_offset: u64,
+	pub node_started: Option<DateTime<Utc>>,
+	pub running_version: Option<String>,
+	#[serde(default)]
+	pub peer_id: Option<String>,
+	pub activity_gets: u64,
+	pub activity_puts: u64,
+	pub activity_errors: u64,
+	pub activity_warnings: u64,
+	pub rewards: u128,
+	#[serde(default)]
+	pub store_cost: Option<u64>,
+	#[serde(default)]
+	pub records_stored: Option<u64>,
+	#[serde(default)]
+	pub peers: u64,
+	#[serde(default)]
+	pub bytes_in: u64,
+	#[serde(default)]
+	pub bytes_out: u64,
+	#[serde(default)]
+	pub disconnected: bool,
+	#[serde(default)]
+	pub disconnects: u64,
+	#[serde(default)]
+	pub restarts: u64,
+	pub timelines: HashMap<String, TimelineState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct TimelineState {
+	pub bucket_sets: HashMap<String, BucketSetState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct BucketSetState {
+	pub bucket_time: Option<DateTime<Utc>>,
+	pub buckets: Vec<u64>,
+}
+
+impl DashboardState {
+	pub fn from_monitors(monitors: &HashMap<String, LogMonitor>) -> DashboardState {
+		let mut nodes = HashMap::<String, NodeState>::new();
+		for (logfile, monitor) in monitors.iter() {
+			if monitor.is_debug_dashboard_log || monitor.is_replay || logfile == STDIN_LOGFILE {
+				continue; // None can be resumed from an offset
+			}
+			nodes.insert(logfile.clone(), NodeState::from_metrics(&monitor.metrics, monitor.logfile_offset));
+		}
+		DashboardState { nodes }
+	}
+
+	///! Returns None if the state file doesn't exist yet
+	pub fn load(path: &Path) -> Result<Option<DashboardState>, Error> {
+		let json = match fs::read_to_string(path) {
+			Ok(json) => json,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e),
+		};
+		let state = serde_json::from_str(&json).map_err(Error::other)?;
+		Ok(Some(state))
+	}
+
+	///! Writes via a temporary file so that an interrupted save can't corrupt the state
+	pub fn save(&self, path: &Path) -> Result<(), Error> {
+		let json = serde_json::to_string(self).map_err(Error::other)?;
+		let mut temp_path = path.as_os_str().to_owned();
+		temp_path.push(".tmp");
+		fs::write(&temp_path, json)?;
+		fs::rename(&temp_path, path)
+	}
+
+	///! Drop saved nodes whose logfiles are no longer monitored
+	pub fn retain_logfiles(&mut self, logfiles: &[String]) {
+		self.nodes.retain(|logfile, _| logfiles.contains(logfile));
+	}
+}
+
+impl NodeState {
+	pub fn from_metrics(metrics: &NodeMetrics, logfile_offset: u64) -> NodeState {
+		let mut timelines = HashMap::<String, TimelineState>::new();
+		for timeline in metrics.timelines() {
+			timelines.insert(timeline.get_name().clone(), TimelineState::from_timeline(timeline));
+		}
+
+		NodeState {
+			logfile_offset,
+			node_started: metrics.node_started,
+			running_version: metrics.running_version.clone(),
+			peer_id: metrics.peer_id.clone(),
+			activity_gets: metrics.activity_gets,
+			activity_puts: metrics.activity_puts,
+			activity_errors: metrics.activity_errors,
+			activity_warnings: metrics.activity_warnings,
+			rewards: metrics.rewards,
+			store_cost: metrics.store_cost,
+			records_stored: metrics.records_stored,
+			peers: metrics.peers,
+			bytes_in: metrics.bytes_in,
+			bytes_out: metrics.bytes_out,
+			disconnected: !metrics.connected,
+			disconnects: metrics.disconnects,
+			restarts: metrics.restarts,
+			timelines,
+		}
+	}
+
+	pub fn restore(&self, metrics: &mut NodeMetrics) {
+		metrics.node_started = self.node_started;
+		metrics.running_version = self.running_version.clone();
+		metrics.peer_id = self.peer_id.clone();
+		metrics.activity_gets = self.activity_gets;
+		metrics.activity_puts = self.activity_puts;
+		metrics.activity_errors = self.activity_errors;
+		metrics.activity_warnings = self.activity_warnings;
+		metrics.rewards = self.rewards;
+		metrics.store_cost = self.store_cost;
+		metrics.records_stored = self.records_stored;
+		metrics.peers = self.peers;
+		metrics.bytes_in = self.bytes_in;
+		metrics.bytes_out = self.bytes_out;
+		metrics.connected = !self.disconnected;
+		metrics.disconnects = self.disconnects;
+		metrics.restarts = self.restarts;
+		for timeline in metrics.timelines_mut() {
+			if let Some(timeline_state) = self.timelines.get(timeline.get_name()) {
+				timeline_state.restore(timeline);
+			}
+		}
+	}
+}
+
+impl TimelineState {
+	pub fn from_timeline(timeline: &TimelineSet) -> TimelineState {
+		let mut bucket_sets = HashMap::<String, BucketSetState>::new();
+		for (name, bs) in timeline.bucket_sets() {
+			bucket_sets.insert(
+				name.clone(),
+				BucketSetState {
+					bucket_time: bs.bucket_time,
+					buckets: bs.buckets.clone(),
+				},
+			);
+		}
+		TimelineState { bucket_sets }
+	}
+
+	///! Restores matching bucket sets, keeping the most recent buckets if
+	///! the number of timeline steps has changed since the state was saved
+	pub fn restore(&self, timeline: &mut TimelineSet) {
+		for (name, bs) in timeline.bucket_sets_mut() {
+			if let Some(saved) = self.bucket_sets.get(name) {
+				let keep = saved.buckets.len().min(bs.max_buckets);
+				let mut buckets = vec![0; bs.max_buckets - keep];
+				buckets.extend_from_slice(&saved.buckets[saved.buckets.len() - keep..]);
+				bs.buckets = buckets;
+				bs.bucket_time = saved.bucket_time;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	fn populated_metrics() -> NodeMetrics {
+		let mut metrics = NodeMetrics::new(&Opt::from_iter(vec!["vdash", "--timeline-steps", "1s:10,1m:20"]));
+		let time = Utc::now();
+		metrics.update_timelines(&time);
+		metrics.node_started = Some(time);
+		metrics.running_version = Some("0.24.0".to_string());
+		metrics.activity_puts = 3;
+		metrics.activity_errors = 1;
+		metrics.rewards = u128::MAX;
+		metrics.connected = false;
+		metrics.disconnects = 2;
+		metrics.restarts = 3;
+		for timeline in metrics.timelines_mut() {
+			timeline.increment_value(&time);
+		}
+		metrics
+	}
+
+	#[test]
+	fn round_trips_dashboard_state() {
+		let metrics = populated_metrics();
+		let mut state = DashboardState::default();
+		state.nodes.insert("node-1/safenode.log".to_string(), NodeState::from_metrics(&metrics, 1234));
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash-state.json");
+		state.save(&path).unwrap();
+		let loaded = DashboardState::load(&path).unwrap().unwrap();
+		assert_eq!(loaded, state);
+
+		let node = &loaded.nodes["node-1/safenode.log"];
+		assert_eq!(node.logfile_offset, 1234);
+		assert_eq!(node.rewards, u128::MAX);
+		assert_eq!(node.timelines["PUTS"].bucket_sets["1 minute columns"].buckets.len(), 20);
+		assert_eq!(node.timelines["PUTS"].bucket_sets["1 second columns"].buckets[9], 1);
+	}
+
+	#[test]
+	fn restores_metrics_and_timelines() {
+		let saved = NodeState::from_metrics(&populated_metrics(), 0);
+
+		let mut metrics = NodeMetrics::new(&Opt::from_iter(vec!["vdash", "--timeline-steps", "1s:15,1m:10"]));
+		saved.restore(&mut metrics);
+		assert_eq!(metrics.activity_puts, 3);
+		assert_eq!(metrics.running_version, Some("0.24.0".to_string()));
+		assert!(!metrics.connected);
+		assert_eq!(metrics.disconnects, 2);
+		assert_eq!(metrics.restarts, 3);
+
+		let puts = metrics.puts_timeline.get_bucket_set("1 second columns").unwrap();
+		assert_eq!(puts.buckets.len(), 15);
+		assert_eq!(puts.buckets[14], 1);
+		assert_eq!(puts.buckets.iter().sum::<u64>(), 1);
+		let puts = metrics.puts_timeline.get_bucket_set("1 minute columns").unwrap();
+		assert_eq!(puts.buckets.len(), 10);
+		assert_eq!(puts.buckets[9], 1);
+	}
+
+	#[test]
+	fn missing_state_file_loads_as_none() {
+		let dir = tempfile::tempdir().unwrap();
+		assert!(DashboardState::load(&dir.path().join("missing.json")).unwrap().is_none());
+	}
+
+	#[test]
+	fn drops_logfiles_no_longer_monitored() {
+		let mut state = DashboardState::default();
+		state.nodes.insert("node-1.log".to_string(), NodeState::default());
+		state.nodes.insert("node-2.log".to_string(), NodeState::default());
+		state.retain_logfiles(&["node-2.log".to_string(), "node-3.log".to_string()]);
+		assert_eq!(state.nodes.keys().collect::<Vec<_>>(), vec!["node-2.log"]);
+	}
+}
@@ -0,0 +1,107 @@
+///! Reading a node's log from stdin, e.g. 'tail -f safenode.log | vdash -'
+///!
+///! Lines are read on their own thread so the dashboard never waits for input
+use std::io::BufRead;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+use crate::custom::app::decode_line;
+
+///! The LOGFILE argument which means read from stdin
+pub static STDIN_LOGFILE: &str = "-";
+
+pub struct StdinReader {
+	rx: Receiver<String>,
+	ended: bool,
+}
+
+impl StdinReader {
+	///! Start reading lines from input, which is stdin except when testing
+	pub fn spawn<R: BufRead + Send + 'static>(mut input: R) -> StdinReader {
+		let (tx, rx) = channel();
+		thread::spawn(move || {
+			let mut bytes = Vec::<u8>::new();
+			loop {
+				bytes.clear();
+				match input.read_until(b'\n', &mut bytes) {
+					Ok(0) | Err(_) => break,
+					Ok(_len) => {}
+				}
+				if tx.send(decode_line(&bytes)).is_err() {
+					break;
+				}
+			}
+		});
+		StdinReader { rx, ended: false }
+	}
+
+	///! Lines received since the last call, without waiting for more
+	pub fn read_lines(&mut self) -> Vec<String> {
+		let mut lines = Vec::<String>::new();
+		loop {
+			match self.rx.try_recv() {
+				Ok(line) => lines.push(line),
+				Err(TryRecvError::Empty) => break,
+				Err(TryRecvError::Disconnected) => {
+					self.ended = true;
+					break;
+				}
+			}
+		}
+		lines
+	}
+
+	///! True once the input has closed and every line has been read
+	pub fn is_ended(&self) -> bool {
+		self.ended
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Cursor;
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn reads_lines_until_end_of_input() {
+		let mut reader = StdinReader::spawn(Cursor::new("one\ntwo\r\nthree"));
+		let mut lines = Vec::<String>::new();
+		let timeout = Instant::now() + Duration::from_secs(5);
+		while !reader.is_ended() && Instant::now() < timeout {
+			lines.append(&mut reader.read_lines());
+			thread::sleep(Duration::from_millis(1));
+		}
+		assert!(reader.is_ended());
+		assert_eq!(lines, vec!["one", "two", "three"]);
+		assert!(reader.read_lines().is_empty());
+	}
+
+	#[test]
+	fn joins_a_line_split_across_reads() {
+		use std::io::{BufReader, Read};
+		// Each part is a separate read, as when a line is written in two
+		let input = Cursor::new("one\ntw").chain(Cursor::new("o\nthree\n"));
+		let mut reader = StdinReader::spawn(BufReader::with_capacity(4, input));
+		let mut lines = Vec::<String>::new();
+		let timeout = Instant::now() + Duration::from_secs(5);
+		while !reader.is_ended() && Instant::now() < timeout {
+			lines.append(&mut reader.read_lines());
+			thread::sleep(Duration::from_millis(1));
+		}
+		assert_eq!(lines, vec!["one", "two", "three"]);
+	}
+
+	#[test]
+	fn keeps_lines_with_invalid_utf8() {
+		let input: &[u8] = b"one\ntw\xffo\nthree\n";
+		let mut reader = StdinReader::spawn(Cursor::new(input));
+		let mut lines = Vec::<String>::new();
+		let timeout = Instant::now() + Duration::from_secs(5);
+		while !reader.is_ended() && Instant::now() < timeout {
+			lines.append(&mut reader.read_lines());
+			thread::sleep(Duration::from_millis(1));
+		}
+		assert_eq!(lines, vec!["one", "tw\u{FFFD}o", "three"]);
+	}
+}
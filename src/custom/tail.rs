@@ -0,0 +1,225 @@
+///! The most recent raw logfile lines of a node, oldest first
+///!
+///! Every line read from a node's logfile is kept, whether or not vdash
+///! understood it, up to --tail-lines lines
+///!
+///! The tail follows new lines unless it has been scrolled up or following
+///! was turned off, when the lines shown stay put until followed again
+use std::collections::VecDeque;
+
+///! A fixed capacity ring buffer of lines
+pub struct LineTail {
+	lines: VecDeque<String>,
+	capacity: usize,
+	pushed: usize, // Lines ever added, evicted or not
+	following: bool,
+	view_end: usize, // When not following, lines ever added up to the last shown
+}
+
+impl LineTail {
+	pub fn new(capacity: usize) -> LineTail {
+		LineTail {
+			lines: VecDeque::with_capacity(capacity),
+			capacity,
+			pushed: 0,
+			following: true,
+			view_end: 0,
+		}
+	}
+
+	///! Add a line, dropping the oldest once full
+	pub fn push(&mut self, line: &str) {
+		if self.capacity == 0 {
+			return;
+		}
+		if self.lines.len() == self.capacity {
+			self.lines.pop_front();
+		}
+		self.lines.push_back(line.to_string());
+		self.pushed += 1;
+	}
+
+	///! The lines held, oldest first
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+		self.lines.iter()
+	}
+
+	pub fn len(&self) -> usize {
+		self.lines.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.lines.is_empty()
+	}
+
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	pub fn is_following(&self) -> bool {
+		self.following
+	}
+
+	///! Following again jumps to the newest line
+	pub fn set_following(&mut self, following: bool) {
+		if self.following && !following {
+			self.view_end = self.pushed;
+		}
+		self.following = following;
+	}
+
+	///! Scrolling up stops following, so new lines don't move the view
+	pub fn scroll_up(&mut self, lines: usize) {
+		self.set_following(false);
+		self.view_end = self.view_end().saturating_sub(lines).max(self.earliest_view_end());
+	}
+
+	pub fn scroll_down(&mut self, lines: usize) {
+		if !self.following {
+			self.view_end = (self.view_end() + lines).min(self.pushed);
+		}
+	}
+
+	///! Lines added since the view stopped following
+	pub fn new_lines(&self) -> usize {
+		self.pushed - self.view_end()
+	}
+
+	///! The lines to show in a window of the given number of rows, oldest first
+	pub fn visible(&self, rows: usize) -> impl Iterator<Item = &String> {
+		let end = self.view_end() - self.first_held();
+		let start = end.saturating_sub(rows);
+		let end = end.max((start + rows).min(self.len()));
+		self.lines.range(start..end)
+	}
+
+	// Lines ever added, up to the last to show, but not before those evicted
+	fn view_end(&self) -> usize {
+		if self.following {
+			self.pushed
+		} else {
+			self.view_end.max(self.earliest_view_end())
+		}
+	}
+
+	// Showing at least the oldest line still held
+	fn earliest_view_end(&self) -> usize {
+		self.first_held() + 1.min(self.len())
+	}
+
+	// Lines ever added before the oldest still held
+	fn first_held(&self) -> usize {
+		self.pushed - self.lines.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_the_last_lines() {
+		let mut tail = LineTail::new(3);
+		assert!(tail.is_empty());
+		for line in &["one", "two", "three", "four", "five"] {
+			tail.push(line);
+		}
+		assert_eq!(tail.len(), 3);
+		assert_eq!(tail.iter().collect::<Vec<_>>(), vec!["three", "four", "five"]);
+	}
+
+	#[test]
+	fn evicts_oldest_only_beyond_capacity() {
+		let mut tail = LineTail::new(2);
+		tail.push("one");
+		tail.push("two");
+		assert_eq!(tail.len(), 2);
+		assert_eq!(tail.iter().collect::<Vec<_>>(), vec!["one", "two"]);
+
+		tail.push("three");
+		assert_eq!(tail.len(), 2);
+		assert_eq!(tail.iter().collect::<Vec<_>>(), vec!["two", "three"]);
+
+		for i in 0..100 {
+			tail.push(&i.to_string());
+		}
+		assert_eq!(tail.len(), tail.capacity());
+		assert_eq!(tail.iter().collect::<Vec<_>>(), vec!["98", "99"]);
+	}
+
+	#[test]
+	fn zero_capacity_keeps_nothing() {
+		let mut tail = LineTail::new(0);
+		tail.push("one");
+		assert!(tail.is_empty());
+		assert_eq!(tail.iter().count(), 0);
+	}
+
+	#[test]
+	fn following_shows_the_newest_lines() {
+		let mut tail = LineTail::new(10);
+		for i in 0..5 {
+			tail.push(&i.to_string());
+		}
+		assert!(tail.is_following());
+		assert_eq!(tail.visible(3).collect::<Vec<_>>(), vec!["2", "3", "4"]);
+		tail.push("5");
+		assert_eq!(tail.visible(3).collect::<Vec<_>>(), vec!["3", "4", "5"]);
+		assert_eq!(tail.new_lines(), 0);
+
+		// Scrolling down is already at the bottom
+		tail.scroll_down(2);
+		assert!(tail.is_following());
+		assert_eq!(tail.visible(10).count(), 6);
+	}
+
+	#[test]
+	fn not_following_keeps_the_view_still() {
+		let mut tail = LineTail::new(6);
+		for i in 0..5 {
+			tail.push(&i.to_string());
+		}
+		tail.scroll_up(1);
+		assert!(!tail.is_following());
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["2", "3"]);
+
+		tail.push("5");
+		tail.push("6");
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["2", "3"]);
+		assert_eq!(tail.new_lines(), 3);
+
+		tail.scroll_down(1);
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["3", "4"]);
+		assert_eq!(tail.new_lines(), 2);
+
+		// Never scrolls past either end
+		tail.scroll_up(100);
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["1", "2"]);
+		tail.scroll_down(100);
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["5", "6"]);
+		assert_eq!(tail.new_lines(), 0);
+		assert!(!tail.is_following());
+
+		tail.scroll_up(2);
+		tail.set_following(true);
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["5", "6"]);
+	}
+
+	#[test]
+	fn evicted_lines_move_a_still_view() {
+		let mut tail = LineTail::new(3);
+		for i in 0..3 {
+			tail.push(&i.to_string());
+		}
+		tail.set_following(false);
+		tail.scroll_up(2);
+		assert_eq!(tail.visible(1).collect::<Vec<_>>(), vec!["0"]);
+		for i in 3..5 {
+			tail.push(&i.to_string());
+		}
+		// The lines shown were dropped, so show the oldest remaining
+		assert_eq!(tail.visible(1).collect::<Vec<_>>(), vec!["2"]);
+		assert_eq!(tail.visible(2).collect::<Vec<_>>(), vec!["2", "3"]);
+		assert_eq!(tail.new_lines(), 2);
+	}
+}
@@ -0,0 +1,507 @@
+///! Colours used by the dashboard, optionally loaded from a --theme file
+///!
+///! A theme file is TOML which maps UI elements to either a colour name
+///! (the element's foreground colour) or a table of fg, bg and bold:
+///!
+///!	gauge_fill = "blue"
+///!	header = "#875f00"
+///!	selected_row = { fg = "black", bg = "light_cyan", bold = true }
+///!
+///! Colours are named (black, red, green, yellow, blue, magenta, cyan,
+///! gray, dark_gray, light_red ... light_cyan, white, reset), '#rrggbb'
+///! or a terminal colour index from 0 to 255. Elements not in the file
+///! keep the colours of the --palette, the default theme unless given.
+///!
+///! With --palette colorblind the greens and reds which mark good and bad
+///! are replaced by blues and oranges, which are easier to tell apart with
+///! red-green colour blindness.
+use std::path::Path;
+
+use serde::Deserialize;
+use tui::style::{Color, Modifier, Style};
+
+use crate::custom::heatmap::HEAT_LEVELS;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+	pub text: Style,
+	pub header: Style,
+	pub error_text: Style,
+	pub gauge_fill: Style,
+	pub gauge_warning: Style,
+	pub gauge_critical: Style,
+	pub gauge_empty: Style,
+	pub gauge_marker: Style,
+	pub selected_row: Style,
+	pub logfile_text: Style,
+	pub puts_sparkline: Style,
+	pub gets_sparkline: Style,
+	pub errors_sparkline: Style,
+	pub peers_sparkline: Style,
+	pub spike_sparkline: Style,
+	pub disabled_text: Style, // Nodes disabled with the 'b' key
+	pub filter_match: Style, // Characters of node names matched by the filter
+	pub trend_good: Style, // Timelines heading the right way, see --trend-colors
+	pub trend_bad: Style,
+	pub changed_value: Style, // Summary values which have just changed, see --flash-ticks
+	pub node_palette: &'static [Color], // Of the nodes' series, see node_colour()
+	pub heat_palette: &'static [Color], // Of each level of the heatmap, lowest first
+	pub monochrome: bool,
+}
+
+///! The colours of the theme on which a --theme file is based
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+	Default,
+	Colorblind,
+}
+
+impl std::str::FromStr for Palette {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Palette, String> {
+		match s {
+			"default" => Ok(Palette::Default),
+			"colorblind" | "colourblind" => Ok(Palette::Colorblind),
+			_ => Err(format!("invalid palette '{}', expected default or colorblind", s)),
+		}
+	}
+}
+
+impl Palette {
+	pub fn theme(self) -> Theme {
+		match self {
+			Palette::Default => Theme::default(),
+			Palette::Colorblind => Theme::colorblind(),
+		}
+	}
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Theme {
+			text: Style::default().fg(Color::Blue),
+			header: Style::default().fg(Color::Yellow),
+			error_text: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+			gauge_fill: Style::default().fg(Color::Green),
+			gauge_warning: Style::default().fg(Color::Yellow),
+			gauge_critical: Style::default().fg(Color::Red),
+			gauge_empty: Style::default(),
+			gauge_marker: Style::default().fg(Color::White),
+			selected_row: Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD),
+			logfile_text: Style::default().fg(Color::Black).bg(Color::White),
+			puts_sparkline: Style::default().fg(Color::Yellow),
+			gets_sparkline: Style::default().fg(Color::Green),
+			errors_sparkline: Style::default().fg(Color::Red),
+			peers_sparkline: Style::default().fg(Color::Cyan),
+			spike_sparkline: Style::default().fg(Color::Magenta),
+			disabled_text: Style::default().fg(Color::DarkGray),
+			filter_match: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+			trend_good: Style::default().fg(Color::Green),
+			trend_bad: Style::default().fg(Color::Red),
+			changed_value: Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+			node_palette: &NODE_PALETTE,
+			heat_palette: &HEAT_PALETTE,
+			monochrome: false,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StyleSpec {
+	Colour(String),
+	Style {
+		fg: Option<String>,
+		bg: Option<String>,
+		#[serde(default)]
+		bold: bool,
+	},
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+	text: Option<StyleSpec>,
+	header: Option<StyleSpec>,
+	error_text: Option<StyleSpec>,
+	gauge_fill: Option<StyleSpec>,
+	gauge_warning: Option<StyleSpec>,
+	gauge_critical: Option<StyleSpec>,
+	gauge_empty: Option<StyleSpec>,
+	gauge_marker: Option<StyleSpec>,
+	selected_row: Option<StyleSpec>,
+	logfile_text: Option<StyleSpec>,
+	puts_sparkline: Option<StyleSpec>,
+	gets_sparkline: Option<StyleSpec>,
+	errors_sparkline: Option<StyleSpec>,
+	peers_sparkline: Option<StyleSpec>,
+	spike_sparkline: Option<StyleSpec>,
+	disabled_text: Option<StyleSpec>,
+	filter_match: Option<StyleSpec>,
+	trend_good: Option<StyleSpec>,
+	trend_bad: Option<StyleSpec>,
+	changed_value: Option<StyleSpec>,
+}
+
+impl Theme {
+	///! The theme for the command line options: no colours with --no-color,
+	///! otherwise the --palette with any changes made by the --theme file
+	pub fn resolve(theme_path: Option<&Path>, palette: Palette, no_color: bool) -> Result<Theme, String> {
+		if no_color {
+			return Ok(Theme::monochrome());
+		}
+		match theme_path {
+			Some(path) => Theme::load(path, palette.theme()),
+			None => Ok(palette.theme()),
+		}
+	}
+
+	///! Blues and oranges in place of greens and reds, for --palette colorblind
+	pub fn colorblind() -> Theme {
+		let sky_blue = Color::Indexed(39);
+		let orange = Color::Indexed(214);
+		let vermillion = Color::Indexed(202);
+		Theme {
+			error_text: Style::default().fg(vermillion).add_modifier(Modifier::BOLD),
+			gauge_fill: Style::default().fg(sky_blue),
+			gauge_warning: Style::default().fg(orange),
+			gauge_critical: Style::default().fg(vermillion),
+			selected_row: Style::default().bg(Color::LightBlue).add_modifier(Modifier::BOLD),
+			puts_sparkline: Style::default().fg(orange),
+			gets_sparkline: Style::default().fg(sky_blue),
+			errors_sparkline: Style::default().fg(vermillion),
+			peers_sparkline: Style::default().fg(Color::White),
+			trend_good: Style::default().fg(sky_blue),
+			trend_bad: Style::default().fg(vermillion),
+			node_palette: &COLORBLIND_NODE_PALETTE,
+			heat_palette: &COLORBLIND_HEAT_PALETTE,
+			..Theme::default()
+		}
+	}
+
+	///! Default terminal colours for every element
+	pub fn monochrome() -> Theme {
+		let plain = Style::default();
+		Theme {
+			text: plain,
+			header: plain,
+			error_text: plain,
+			gauge_fill: plain,
+			gauge_warning: plain,
+			gauge_critical: plain,
+			gauge_empty: plain,
+			gauge_marker: plain,
+			selected_row: plain,
+			logfile_text: plain,
+			puts_sparkline: plain,
+			gets_sparkline: plain,
+			errors_sparkline: plain,
+			peers_sparkline: plain,
+			spike_sparkline: plain,
+			disabled_text: plain,
+			filter_match: Style::default().add_modifier(Modifier::UNDERLINED), // No colour, so still shown
+			trend_good: plain,
+			trend_bad: plain,
+			changed_value: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD), // Fades to bold
+			node_palette: &NODE_PALETTE,
+			heat_palette: &HEAT_PALETTE,
+			monochrome: true,
+		}
+	}
+
+	///! Load a theme file, keeping the colours of base for elements it doesn't give
+	pub fn load(path: &Path, base: Theme) -> Result<Theme, String> {
+		let toml = std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read theme {}: {}", path.display(), e))?;
+		Theme::from_toml_over(&toml, base).map_err(|e| format!("invalid theme {}: {}", path.display(), e))
+	}
+
+	pub fn from_toml(toml: &str) -> Result<Theme, String> {
+		Theme::from_toml_over(toml, Theme::default())
+	}
+
+	pub fn from_toml_over(toml: &str, base: Theme) -> Result<Theme, String> {
+		let file: ThemeFile = toml::from_str(toml).map_err(|e| e.to_string())?;
+		let mut theme = base;
+		for (name, spec, style) in [
+			("text", file.text, &mut theme.text),
+			("header", file.header, &mut theme.header),
+			("error_text", file.error_text, &mut theme.error_text),
+			("gauge_fill", file.gauge_fill, &mut theme.gauge_fill),
+			("gauge_warning", file.gauge_warning, &mut theme.gauge_warning),
+			("gauge_critical", file.gauge_critical, &mut theme.gauge_critical),
+			("gauge_empty", file.gauge_empty, &mut theme.gauge_empty),
+			("gauge_marker", file.gauge_marker, &mut theme.gauge_marker),
+			("selected_row", file.selected_row, &mut theme.selected_row),
+			("logfile_text", file.logfile_text, &mut theme.logfile_text),
+			("puts_sparkline", file.puts_sparkline, &mut theme.puts_sparkline),
+			("gets_sparkline", file.gets_sparkline, &mut theme.gets_sparkline),
+			("errors_sparkline", file.errors_sparkline, &mut theme.errors_sparkline),
+			("peers_sparkline", file.peers_sparkline, &mut theme.peers_sparkline),
+			("spike_sparkline", file.spike_sparkline, &mut theme.spike_sparkline),
+			("disabled_text", file.disabled_text, &mut theme.disabled_text),
+			("filter_match", file.filter_match, &mut theme.filter_match),
+			("trend_good", file.trend_good, &mut theme.trend_good),
+			("trend_bad", file.trend_bad, &mut theme.trend_bad),
+			("changed_value", file.changed_value, &mut theme.changed_value),
+		] {
+			if let Some(spec) = spec {
+				*style = resolve_style(&spec).map_err(|e| format!("{} for '{}'", e, name))?;
+			}
+		}
+		Ok(theme)
+	}
+
+	///! The style of a node's series and its entry in the node list, which is
+	///! plain text in monochrome
+	pub fn node_style(&self, index: usize) -> Style {
+		if self.monochrome {
+			return self.text;
+		}
+		Style::default().fg(self.node_colour(index))
+	}
+
+	///! The colour of the node with the given index. A node keeps its index, and
+	///! so its colour, while others are added or removed
+	pub fn node_colour(&self, index: usize) -> Color {
+		self.node_palette[index % self.node_palette.len()]
+	}
+}
+
+///! Colours which tell nodes apart when their timelines are overlaid,
+///! avoiding red which marks errors
+const NODE_PALETTE: [Color; 12] = [
+	Color::Yellow,
+	Color::Green,
+	Color::Cyan,
+	Color::Magenta,
+	Color::LightBlue,
+	Color::Indexed(208), // Orange
+	Color::LightGreen,
+	Color::LightMagenta,
+	Color::LightCyan,
+	Color::Indexed(141), // Purple
+	Color::LightYellow,
+	Color::Indexed(109), // Grey blue
+];
+
+///! Node colours of --palette colorblind, without the vermillion of errors
+const COLORBLIND_NODE_PALETTE: [Color; 8] = [
+	Color::Indexed(214), // Orange
+	Color::Indexed(39),  // Sky blue
+	Color::Indexed(43),  // Bluish green
+	Color::Indexed(227), // Yellow
+	Color::Indexed(26),  // Blue
+	Color::Indexed(175), // Reddish purple
+	Color::White,
+	Color::Indexed(245), // Grey
+];
+
+///! Heatmap levels, from grey for nothing up through ever brighter greens
+const HEAT_PALETTE: [Color; HEAT_LEVELS] = [
+	Color::DarkGray,
+	Color::Indexed(22),
+	Color::Indexed(28),
+	Color::Indexed(34),
+	Color::Indexed(46),
+];
+
+///! Heatmap levels of --palette colorblind, in blues
+const COLORBLIND_HEAT_PALETTE: [Color; HEAT_LEVELS] = [
+	Color::DarkGray,
+	Color::Indexed(18),
+	Color::Indexed(25),
+	Color::Indexed(32),
+	Color::Indexed(39),
+];
+
+fn resolve_style(spec: &StyleSpec) -> Result<Style, String> {
+	match spec {
+		StyleSpec::Colour(colour) => Ok(Style::default().fg(parse_colour(colour)?)),
+		StyleSpec::Style { fg, bg, bold } => {
+			let mut style = Style::default();
+			if let Some(fg) = fg {
+				style = style.fg(parse_colour(fg)?);
+			}
+			if let Some(bg) = bg {
+				style = style.bg(parse_colour(bg)?);
+			}
+			if *bold {
+				style = style.add_modifier(Modifier::BOLD);
+			}
+			Ok(style)
+		}
+	}
+}
+
+///! Parses a colour name such as 'light_green', '#rrggbb' or an index 0-255
+pub fn parse_colour(colour: &str) -> Result<Color, String> {
+	let name: String = colour
+		.trim()
+		.to_lowercase()
+		.chars()
+		.filter(|c| !matches!(c, '_' | '-' | ' '))
+		.collect();
+	let named = match name.as_str() {
+		"reset" | "default" => Some(Color::Reset),
+		"black" => Some(Color::Black),
+		"red" => Some(Color::Red),
+		"green" => Some(Color::Green),
+		"yellow" => Some(Color::Yellow),
+		"blue" => Some(Color::Blue),
+		"magenta" => Some(Color::Magenta),
+		"cyan" => Some(Color::Cyan),
+		"gray" | "grey" => Some(Color::Gray),
+		"darkgray" | "darkgrey" => Some(Color::DarkGray),
+		"lightred" => Some(Color::LightRed),
+		"lightgreen" => Some(Color::LightGreen),
+		"lightyellow" => Some(Color::LightYellow),
+		"lightblue" => Some(Color::LightBlue),
+		"lightmagenta" => Some(Color::LightMagenta),
+		"lightcyan" => Some(Color::LightCyan),
+		"white" => Some(Color::White),
+		_ => None,
+	};
+	if let Some(colour) = named {
+		return Ok(colour);
+	}
+
+	if let Some(hex) = name.strip_prefix('#') {
+		if hex.len() == 6 {
+			if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+				return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+			}
+		}
+	} else if let Ok(index) = name.parse::<u8>() {
+		return Ok(Color::Indexed(index));
+	}
+	Err(format!("unknown colour '{}'", colour))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn loads_sample_theme() {
+		let theme = Theme::from_toml(
+			r##"
+			gauge_fill = "blue"
+			gauge_empty = { bg = "gray" }
+			error_text = { fg = "#aa0000", bold = true }
+			header = "DarkGray"
+			selected_row = { fg = "black", bg = "214" }
+			"##,
+		)
+		.unwrap();
+
+		assert_eq!(theme.gauge_fill, Style::default().fg(Color::Blue));
+		assert_eq!(
+			theme.error_text,
+			Style::default().fg(Color::Rgb(0xaa, 0, 0)).add_modifier(Modifier::BOLD)
+		);
+		assert_eq!(theme.header, Style::default().fg(Color::DarkGray));
+		assert_eq!(theme.selected_row, Style::default().fg(Color::Black).bg(Color::Indexed(214)));
+		assert_eq!(theme.text, Theme::default().text);
+	}
+
+	#[test]
+	fn rejects_unknown_colours_and_elements() {
+		let error = Theme::from_toml("gauge_fill = \"greenish\"").unwrap_err();
+		assert!(error.contains("unknown colour 'greenish' for 'gauge_fill'"), "{}", error);
+
+		let error = Theme::from_toml("selected_row = { bg = \"#12345\" }").unwrap_err();
+		assert!(error.contains("unknown colour '#12345'"), "{}", error);
+
+		assert!(Theme::from_toml("gauge_colour = \"green\"").is_err());
+	}
+
+	#[test]
+	fn no_color_resolves_every_element_to_default_style() {
+		let theme = Theme::resolve(Some(Path::new("ignored-theme.toml")), Palette::Colorblind, true).unwrap();
+		assert!(theme.monochrome);
+		for style in &[
+			theme.text,
+			theme.header,
+			theme.error_text,
+			theme.gauge_fill,
+			theme.gauge_warning,
+			theme.gauge_critical,
+			theme.gauge_empty,
+			theme.gauge_marker,
+			theme.selected_row,
+			theme.logfile_text,
+			theme.puts_sparkline,
+			theme.gets_sparkline,
+			theme.errors_sparkline,
+			theme.peers_sparkline,
+			theme.spike_sparkline,
+			theme.disabled_text,
+		] {
+			assert_eq!(*style, Style::default());
+		}
+		assert_eq!(Theme::resolve(None, Palette::Default, false).unwrap(), Theme::default());
+	}
+
+	#[test]
+	fn parses_colours() {
+		assert_eq!(parse_colour("light_green"), Ok(Color::LightGreen));
+		assert_eq!(parse_colour("Light Green"), Ok(Color::LightGreen));
+		assert_eq!(parse_colour("#FF8000"), Ok(Color::Rgb(255, 128, 0)));
+		assert_eq!(parse_colour("7"), Ok(Color::Indexed(7)));
+		assert!(parse_colour("256").is_err());
+	}
+
+	#[test]
+	fn assigns_stable_distinct_node_colours() {
+		let theme = Theme::default();
+		let colours: Vec<Color> = (0..NODE_PALETTE.len()).map(|i| theme.node_colour(i)).collect();
+		for (i, colour) in colours.iter().enumerate() {
+			assert!(!colours[i + 1..].contains(colour), "{:?} is repeated", colour);
+			assert_ne!(*colour, Color::Red);
+			assert_eq!(theme.node_colour(i), *colour);
+		}
+		// Beyond the palette the colours cycle
+		assert_eq!(theme.node_colour(NODE_PALETTE.len() + 3), colours[3]);
+
+		assert_eq!(theme.node_style(1), Style::default().fg(Color::Green));
+		assert_eq!(Theme::monochrome().node_style(1), Style::default());
+	}
+
+	#[test]
+	fn colorblind_palette_avoids_red_and_green() {
+		assert_eq!("colorblind".parse::<Palette>(), Ok(Palette::Colorblind));
+		assert_eq!("default".parse::<Palette>(), Ok(Palette::Default));
+		assert!("rainbow".parse::<Palette>().is_err());
+
+		let theme = Theme::resolve(None, Palette::Colorblind, false).unwrap();
+		let fg = |style: Style| style.fg.unwrap();
+		let good = fg(theme.gauge_fill);
+		let warning = fg(theme.gauge_warning);
+		let bad = fg(theme.gauge_critical);
+		assert_eq!((good, warning, bad), (Color::Indexed(39), Color::Indexed(214), Color::Indexed(202)));
+		assert_eq!(fg(theme.error_text), bad);
+		assert_eq!(fg(theme.errors_sparkline), bad);
+		assert_ne!(fg(theme.puts_sparkline), fg(theme.gets_sparkline));
+
+		let reds_and_greens = [Color::Red, Color::LightRed, Color::Green, Color::LightGreen];
+		for style in &[theme.error_text, theme.gauge_fill, theme.gauge_critical, theme.selected_row, theme.gets_sparkline] {
+			assert!(!reds_and_greens.contains(&style.fg.unwrap_or(Color::Reset)), "{:?}", style);
+			assert!(!reds_and_greens.contains(&style.bg.unwrap_or(Color::Reset)), "{:?}", style);
+		}
+		let colours: Vec<Color> = (0..COLORBLIND_NODE_PALETTE.len()).map(|i| theme.node_colour(i)).collect();
+		for (i, colour) in colours.iter().enumerate() {
+			assert!(!colours[i + 1..].contains(colour), "{:?} is repeated", colour);
+			assert_ne!(*colour, bad);
+		}
+
+		// A theme file changes the palette's colours
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("theme.toml");
+		std::fs::write(&path, "gauge_fill = \"white\"\n").unwrap();
+		let theme = Theme::resolve(Some(&path), Palette::Colorblind, false).unwrap();
+		assert_eq!(theme.gauge_fill, Style::default().fg(Color::White));
+		assert_eq!(theme.gauge_critical, Theme::colorblind().gauge_critical);
+	}
+}
@@ -0,0 +1,1003 @@
+///! Timelines which record metrics as histories of 'marching buckets'
+///!
+///! Edit src/custom/timelines.rs to change the default timelines
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::custom::ui::widgets::sparkline::ChartStyle;
+
+pub static MIN_TIMELINE_STEPS: usize = 10;
+pub static MAX_TIMELINE_STEPS: usize = 10_000;
+
+///! Default timelines, each with the number of steps given by --timeline-steps
+static DEFAULT_TIMELINES: [&str; 6] = ["1s", "1m", "1h", "1d", "1w", "1y"];
+
+///! A timeline bucket duration and the number of buckets to keep
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineSpec {
+	pub name: String,
+	pub duration: Duration,
+	pub steps: usize,
+}
+
+impl TimelineSpec {
+	///! Creates a spec from a duration such as '30s', '5m', '1h', '1d', '1w' or '1y'
+	pub fn new(duration_spec: &str, steps: usize) -> Result<TimelineSpec, String> {
+		let duration_spec = duration_spec.trim();
+		let (count, unit_name, duration) = parse_duration_parts(duration_spec)?;
+		if !(MIN_TIMELINE_STEPS..=MAX_TIMELINE_STEPS).contains(&steps) {
+			return Err(format!(
+				"timeline steps for '{}' must be between {} and {}",
+				duration_spec, MIN_TIMELINE_STEPS, MAX_TIMELINE_STEPS
+			));
+		}
+
+		Ok(TimelineSpec {
+			name: format!("{} {} columns", count, unit_name),
+			duration,
+			steps,
+		})
+	}
+
+	///! The time covered by all of the timeline's buckets
+	pub fn window(&self) -> Duration {
+		self.duration * self.steps as i32
+	}
+}
+
+///! The timeline to show after zooming in (to shorter buckets) or out (to
+///! longer ones) from the active timeline, of timelines ordered shortest
+///! first. Zooming stops at the shortest and longest
+pub fn zoom_timeline(active: usize, timelines: usize, zoom_in: bool) -> usize {
+	let longest = timelines.saturating_sub(1);
+	if zoom_in {
+		active.min(longest).saturating_sub(1)
+	} else {
+		(active + 1).min(longest)
+	}
+}
+
+///! Parses a duration such as '30s', '5m', '1h', '1d', '1w' or '1y'
+pub fn parse_duration(duration_spec: &str) -> Result<Duration, String> {
+	parse_duration_parts(duration_spec.trim()).map(|(_count, _unit_name, duration)| duration)
+}
+
+fn parse_duration_parts(duration_spec: &str) -> Result<(i64, &'static str, Duration), String> {
+	let split = duration_spec
+		.find(|c: char| !c.is_ascii_digit())
+		.unwrap_or(duration_spec.len());
+	let (count, unit) = duration_spec.split_at(split);
+	let count = count
+		.parse::<i64>()
+		.map_err(|_| format!("invalid duration '{}'", duration_spec))?;
+	if count <= 0 {
+		return Err(format!("duration '{}' must be positive", duration_spec));
+	}
+	let (unit_seconds, unit_name) = match unit {
+		"s" => (1, "second"),
+		"m" => (60, "minute"),
+		"h" => (60 * 60, "hour"),
+		"d" => (24 * 60 * 60, "day"),
+		"w" => (7 * 24 * 60 * 60, "week"),
+		"y" => (365 * 24 * 60 * 60, "year"),
+		_ => return Err(format!("invalid duration unit in '{}' (use s, m, h, d, w or y)", duration_spec)),
+	};
+	let seconds = count
+		.checked_mul(unit_seconds)
+		.filter(|seconds| *seconds <= 100 * 365 * 24 * 60 * 60)
+		.ok_or_else(|| format!("duration '{}' is too long", duration_spec))?;
+	Ok((count, unit_name, Duration::seconds(seconds)))
+}
+
+///! Value of --timeline-steps
+///!
+///! Either a number of steps to use for each of the default timelines,
+///! or a list of timelines such as '1m:60,1h:48,1d:30,1w:52'
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineSteps {
+	pub timelines: Vec<TimelineSpec>,
+}
+
+impl TimelineSteps {
+	pub fn with_defaults(steps: usize) -> Result<TimelineSteps, String> {
+		let mut timelines = Vec::<TimelineSpec>::new();
+		for duration_spec in DEFAULT_TIMELINES.iter() {
+			timelines.push(TimelineSpec::new(duration_spec, steps)?);
+		}
+		Ok(TimelineSteps { timelines })
+	}
+}
+
+impl FromStr for TimelineSteps {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		if let Ok(steps) = s.parse::<usize>() {
+			return TimelineSteps::with_defaults(steps);
+		}
+
+		let mut timelines = Vec::<TimelineSpec>::new();
+		for entry in s.split(',') {
+			let mut parts = entry.split(':');
+			let (duration_spec, steps) = match (parts.next(), parts.next(), parts.next()) {
+				(Some(duration_spec), Some(steps), None) => (duration_spec, steps),
+				_ => return Err(format!("invalid timeline '{}' (expected <duration>:<steps>, e.g. 1h:48)", entry)),
+			};
+			let steps = steps
+				.trim()
+				.parse::<usize>()
+				.map_err(|_| format!("invalid timeline steps in '{}'", entry))?;
+			let spec = TimelineSpec::new(duration_spec, steps)?;
+			if timelines.iter().any(|t| t.duration == spec.duration) {
+				return Err(format!("duplicate timeline duration '{}'", duration_spec.trim()));
+			}
+			timelines.push(spec);
+		}
+		timelines.sort_by_key(|t| t.duration);
+		Ok(TimelineSteps { timelines })
+	}
+}
+
+///! Maintains one or more 'marching bucket' histories for
+///! a given metric, each with its own duration and granularity.
+///!
+///! A BucketSet is used to hold the history of values with
+///! a given bucket_duration and maximum number of buckets.
+///!
+///! A BucketSet begins with a single bucket of fixed
+///! duration holding the initial metric value. New buckets
+///! are added as time progresses until the number of buckets
+///! covers the total duration of the BucketSet. At this
+///! point the oldest bucket is removed when a new bucket is
+///! added, so that the total duration remains constant and
+///! the specified maximum number of buckets is never
+///! exceeded.
+///!
+///! By adding more than one BucketSet, a given metric can be
+///! recorded for different durations and with different
+///! granularities. E.g. 60 * 1s buckets covers a minute
+///! and 60 * 1m buckets covers an hour, and so on.
+///!
+///! Buckets start when the first time is given, unless aligned
+///! to the wall clock, when they start on a whole number of
+///! bucket durations since the Unix epoch (see aligned_bucket_start).
+pub struct TimelineSet {
+	name: String,
+	bucket_sets: HashMap<String, BucketSet>,
+}
+
+pub struct BucketSet {
+	pub bucket_time: Option<DateTime<Utc>>,
+	pub total_duration: Duration,
+	pub bucket_duration: Duration,
+	pub max_buckets: usize,
+	pub buckets: Vec<u64>,
+	pub completed_buckets: usize, // Since started or cleared
+	pub frozen: Option<Vec<u64>>, // A copy of the buckets, drawn behind them for comparison
+	pub aligned: bool, // Buckets start on wall-clock boundaries
+	pub overflow: u64, // Of the oldest bucket, added from times older than every bucket
+}
+
+impl TimelineSet {
+	pub fn new(name: String) -> TimelineSet {
+		TimelineSet {
+			name,
+			bucket_sets: HashMap::<String, BucketSet>::new(),
+		}
+	}
+
+	pub fn get_name(&self) -> &String {
+		&self.name
+	}
+
+	pub fn add_bucket_set(&mut self, name: &str, duration: Duration, max_buckets: usize) {
+		self.bucket_sets
+			.insert(name.to_string(), BucketSet::new(duration, max_buckets));
+	}
+
+	pub fn get_bucket_set(&mut self, bucket_set_name: &str) -> Option<&BucketSet> {
+		self.bucket_sets.get(bucket_set_name)
+	}
+
+	///! Start the buckets of every bucket set on wall-clock boundaries, for
+	///! --align-buckets. Call before the first update_current_time()
+	pub fn align_to_wall_clock(&mut self) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			bs.aligned = true;
+		}
+	}
+
+	///! Zero all buckets, keeping the current bucket times
+	pub fn clear(&mut self) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			for bucket in bs.buckets.iter_mut() {
+				*bucket = 0;
+			}
+			bs.completed_buckets = 0;
+			bs.overflow = 0;
+		}
+	}
+
+	///! Keep a copy of the buckets of a bucket set as they are now, replacing any
+	///! kept before, to draw behind the live buckets. Clearing the timeline
+	///! keeps the copy
+	pub fn freeze(&mut self, bucket_set_name: &str) {
+		if let Some(bs) = self.bucket_sets.get_mut(bucket_set_name) {
+			bs.frozen = Some(bs.buckets.clone());
+		}
+	}
+
+	///! Drop the frozen copies of every bucket set
+	pub fn thaw(&mut self) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			bs.frozen = None;
+		}
+	}
+
+	pub fn bucket_sets(&self) -> &HashMap<String, BucketSet> {
+		&self.bucket_sets
+	}
+
+	pub fn bucket_sets_mut(&mut self) -> &mut HashMap<String, BucketSet> {
+		&mut self.bucket_sets
+	}
+
+	///! Update all bucket_sets with new current time
+	///!
+	///! Call significantly more frequently than the smallest BucketSet duration
+	pub fn update_current_time(&mut self, new_time: &DateTime<Utc>) {
+		// debug_log!("update_current_time()");
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			if let Some(mut bucket_time) = bs.bucket_time {
+				let mut end_time = bs.next_bucket_time(&bucket_time);
+				// debug_log!(format!("end_time       : {}", end_time).as_str());
+
+				while bs.has_ended(&end_time, new_time) {
+					// debug_log!("Start new bucket");
+					// Start new bucket
+					bs.bucket_time = Some(end_time);
+					bucket_time = end_time;
+					end_time = bs.next_bucket_time(&bucket_time);
+
+					bs.buckets.push(0);
+					bs.completed_buckets += 1;
+					if bs.buckets.len() > bs.max_buckets {
+						bs.buckets.remove(0);
+						bs.overflow = 0;
+					}
+				}
+			} else if bs.aligned {
+				bs.bucket_time = Some(aligned_bucket_start(new_time, bs.bucket_duration));
+			} else {
+				bs.bucket_time = Some(*new_time);
+			}
+		}
+	}
+
+	///! Count per minute averaged over the trailing window, see --rate-window.
+	///! The buckets covering the window, of the finest BucketSet holding that
+	///! much, are summed and divided by the time they cover. A window longer
+	///! than any BucketSet holds is clamped to all of the longest
+	pub fn rate_per_minute(&self, window: Duration) -> u64 {
+		let held = |bs: &BucketSet| bs.bucket_duration * bs.buckets.len() as i32;
+		let sets: Vec<&BucketSet> = self.bucket_sets.values().filter(|bs| !bs.buckets.is_empty()).collect();
+		let covering = sets.iter().filter(|bs| held(bs) >= window).min_by_key(|bs| bs.bucket_duration);
+		let bs = match covering.or_else(|| sets.iter().max_by_key(|bs| held(bs))) {
+			Some(bs) => bs,
+			None => return 0,
+		};
+		let bucket_ms = bs.bucket_duration.num_milliseconds().max(1);
+		let count = (window.num_milliseconds().max(1) + bucket_ms - 1) / bucket_ms;
+		let count = count.clamp(1, bs.buckets.len() as i64) as usize;
+		let sum: u128 = bs.buckets.iter().rev().take(count).map(|value| u128::from(*value)).sum();
+		(sum * 60_000 / (count as u128 * bucket_ms as u128)).min(u128::from(u64::MAX)) as u64
+	}
+
+	///! Rate per minute over the latest complete bucket of the finest BucketSet,
+	///! None until a bucket has completed. For counts, where each bucket holds
+	///! the increase of a cumulative count
+	pub fn completed_rate_per_minute(&self) -> Option<f64> {
+		let bs = self.bucket_sets.values().min_by_key(|bs| bs.bucket_duration)?;
+		if bs.completed_buckets == 0 || bs.buckets.len() < 2 {
+			return None;
+		}
+		let increase = bs.buckets[bs.buckets.len() - 2];
+		counter_rate_per_minute(Some(0), increase, bs.bucket_duration)
+	}
+
+	///! Record an absolute value, such as a count of peers, in the bucket for
+	///! time. A value from before every bucket is of no use, and is ignored
+	pub fn set_value(&mut self, time: &DateTime<Utc>, value: u64) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			if let Some(index) = bs.bucket_index(time) {
+				bs.buckets[index] = value;
+			}
+		}
+	}
+
+	///! Add an amount, such as a number of bytes transferred, to the bucket for
+	///! time. An amount from before every bucket, such as when replaying a
+	///! logfile, is added to the oldest bucket so that totals stay right
+	pub fn add_value(&mut self, time: &DateTime<Utc>, amount: u64) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			match bs.bucket_index(time) {
+				Some(index) => bs.buckets[index] = bs.buckets[index].saturating_add(amount),
+				None => bs.add_overflow(amount),
+			}
+		}
+	}
+
+	pub fn increment_value(&mut self, time: &DateTime<Utc>) {
+		// debug_log!("increment_value()");
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			// debug_log!(format!("name       : {}", _name).as_str());
+			// Times older than every bucket are counted in the oldest
+			match bs.bucket_index(time) {
+				Some(index) => bs.buckets[index] += 1,
+				None => bs.add_overflow(1),
+			}
+		}
+	}
+}
+
+///! A timeline whose buckets are the sums of the corresponding buckets of
+///! timelines, such as the PUTS of every node. Buckets are aligned at the
+///! newest, so a timeline with fewer buckets adds nothing to the oldest
+pub fn sum_timelines(name: &str, timelines: &[&TimelineSet]) -> TimelineSet {
+	let mut sum = TimelineSet::new(name.to_string());
+	for timeline in timelines {
+		for (bucket_set_name, bs) in timeline.bucket_sets() {
+			let summed = sum
+				.bucket_sets
+				.entry(bucket_set_name.clone())
+				.or_insert_with(|| BucketSet::new(bs.bucket_duration, 0));
+			summed.buckets = sum_aligned_buckets(&summed.buckets, &bs.buckets);
+			summed.max_buckets = summed.max_buckets.max(bs.max_buckets);
+			summed.total_duration = summed.bucket_duration * summed.max_buckets as i32;
+			summed.bucket_time = summed.bucket_time.max(bs.bucket_time);
+			summed.completed_buckets = summed.completed_buckets.max(bs.completed_buckets);
+			summed.overflow = summed.overflow.saturating_add(bs.overflow);
+			summed.aligned |= bs.aligned;
+		}
+	}
+	sum
+}
+
+///! The start of the wall-clock aligned bucket holding time, a whole number
+///! of bucket_durations since the Unix epoch. So one minute buckets start at
+///! the top of the minute, one hour buckets on the hour and one day buckets
+///! at midnight UTC, the same for every node and every session
+pub fn aligned_bucket_start(time: &DateTime<Utc>, bucket_duration: Duration) -> DateTime<Utc> {
+	let millis = bucket_duration.num_milliseconds().max(1);
+	Utc.timestamp_millis(time.timestamp_millis().div_euclid(millis) * millis)
+}
+
+///! Bucket by bucket sums, aligned at the newest bucket of each
+pub fn sum_aligned_buckets(a: &[u64], b: &[u64]) -> Vec<u64> {
+	let len = a.len().max(b.len());
+	let bucket = |buckets: &[u64], index: usize| match (index + buckets.len()).checked_sub(len) {
+		Some(index) => buckets[index],
+		None => 0,
+	};
+	(0..len).map(|index| bucket(a, index).saturating_add(bucket(b, index))).collect()
+}
+
+///! The rate per minute of a cumulative count which went from previous to
+///! current over duration. None without a previous sample to compare with
+pub fn counter_rate_per_minute(previous: Option<u64>, current: u64, duration: Duration) -> Option<f64> {
+	let previous = previous?;
+	let seconds = duration.num_milliseconds() as f64 / 1000.0;
+	if seconds <= 0.0 {
+		return None;
+	}
+	Some(current.saturating_sub(previous) as f64 * 60.0 / seconds)
+}
+
+///! Which way the values of a timeline are heading
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trend {
+	Rising,
+	Falling,
+	Flat,
+}
+
+///! The sign of the slope of the least squares line through the values,
+///! oldest first. Fewer than two values, or all the same, are Flat
+pub fn trend(values: &[u64]) -> Trend {
+	let n = values.len() as f64;
+	if values.len() < 2 {
+		return Trend::Flat;
+	}
+	let mean_x = (n - 1.0) / 2.0;
+	let mean_y = values.iter().map(|value| *value as f64).sum::<f64>() / n;
+	let covariance: f64 = values.iter().enumerate().map(|(x, y)| (x as f64 - mean_x) * (*y as f64 - mean_y)).sum();
+	if covariance.abs() < 1e-9 {
+		Trend::Flat
+	} else if covariance > 0.0 {
+		Trend::Rising
+	} else {
+		Trend::Falling
+	}
+}
+
+///! For --trend-colors, whether a metric rising is good or bad
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GoodDirection {
+	Up,
+	Down,
+}
+
+impl FromStr for GoodDirection {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<GoodDirection, String> {
+		match s.trim() {
+			"up" => Ok(GoodDirection::Up),
+			"down" => Ok(GoodDirection::Down),
+			_ => Err(format!("invalid direction '{}', expected up or down", s)),
+		}
+	}
+}
+
+impl GoodDirection {
+	///! True for a good trend, false for a bad one and None for a flat one
+	pub fn is_good(&self, trend: Trend) -> Option<bool> {
+		match (trend, self) {
+			(Trend::Flat, _) => None,
+			(Trend::Rising, GoodDirection::Up) | (Trend::Falling, GoodDirection::Down) => Some(true),
+			_ => Some(false),
+		}
+	}
+}
+
+///! The good direction of each timeline, by name. Timelines not listed, such
+///! as STORAGE %, are neither and keep their own colours
+static GOOD_DIRECTIONS: [(&str, GoodDirection); 8] = [
+	("PUTS", GoodDirection::Up),
+	("GETS", GoodDirection::Up),
+	("ERRORS", GoodDirection::Down),
+	("WARNINGS", GoodDirection::Down),
+	("PEERS", GoodDirection::Up),
+	("RECORDS", GoodDirection::Up),
+	("BYTES IN", GoodDirection::Up),
+	("BYTES OUT", GoodDirection::Up),
+];
+
+///! The good directions of timelines, the defaults changed by any --good-direction
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoodDirections(Vec<(String, GoodDirection)>);
+
+impl Default for GoodDirections {
+	fn default() -> GoodDirections {
+		GoodDirections(GOOD_DIRECTIONS.iter().map(|(name, direction)| (name.to_string(), *direction)).collect())
+	}
+}
+
+impl GoodDirections {
+	pub fn set(&mut self, timeline_name: &str, direction: GoodDirection) {
+		let name = timeline_key(timeline_name);
+		self.0.retain(|(known, _direction)| *known != name);
+		self.0.push((name, direction));
+	}
+
+	pub fn direction_for(&self, timeline_name: &str) -> Option<GoodDirection> {
+		let name = timeline_key(timeline_name);
+		self.0.iter().find(|(known, _direction)| *known == name).map(|(_name, direction)| *direction)
+	}
+}
+
+// Timelines are named as in their titles, e.g. 'BYTES IN', or as 'bytes_in'
+pub fn timeline_key(name: &str) -> String {
+	name.trim().replace('_', " ").to_uppercase()
+}
+
+///! How each timeline is charted, by name, from --chart-style
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartStyles {
+	default: ChartStyle, // Of timelines not listed
+	timelines: Vec<(String, ChartStyle)>,
+}
+
+impl Default for ChartStyles {
+	fn default() -> ChartStyles {
+		ChartStyles { default: ChartStyle::Bars, timelines: Vec::new() }
+	}
+}
+
+impl ChartStyles {
+	///! Set the style of a timeline, or of every timeline not given its own with None
+	pub fn set(&mut self, timeline_name: Option<&str>, style: ChartStyle) {
+		match timeline_name {
+			None => self.default = style,
+			Some(timeline_name) => {
+				let name = timeline_key(timeline_name);
+				self.timelines.retain(|(known, _style)| *known != name);
+				self.timelines.push((name, style));
+			}
+		}
+	}
+
+	pub fn style_for(&self, timeline_name: &str) -> ChartStyle {
+		let name = timeline_key(timeline_name);
+		self.timelines.iter().find(|(known, _style)| *known == name).map_or(self.default, |(_name, style)| *style)
+	}
+}
+
+///! Parses a --chart-style argument, a style for every timeline such as 'line'
+///! or one for a timeline such as 'bytes_in=area'
+pub fn parse_chart_style(arg: &str) -> Result<(Option<String>, ChartStyle), String> {
+	match arg.find('=') {
+		None => Ok((None, arg.parse()?)),
+		Some(index) if !arg[..index].trim().is_empty() => Ok((Some(timeline_key(&arg[..index])), arg[index + 1..].parse()?)),
+		_ => Err(format!("invalid chart style '{}', expected a style, or timeline=style", arg)),
+	}
+}
+
+///! Parses a --good-direction argument such as 'errors=down' or 'store_cost=up'
+pub fn parse_good_direction(arg: &str) -> Result<(String, GoodDirection), String> {
+	match arg.find('=') {
+		Some(index) if !arg[..index].trim().is_empty() => Ok((timeline_key(&arg[..index]), arg[index + 1..].parse()?)),
+		_ => Err(format!("invalid good direction '{}', expected timeline=up or timeline=down", arg)),
+	}
+}
+
+impl BucketSet {
+	pub fn new(bucket_duration: Duration, max_buckets: usize) -> BucketSet {
+		BucketSet {
+			bucket_duration,
+			max_buckets,
+			total_duration: bucket_duration * max_buckets as i32,
+
+			bucket_time: None,
+			buckets: vec![0; max_buckets],
+			completed_buckets: 0,
+			frozen: None,
+			aligned: false,
+			overflow: 0,
+		}
+	}
+
+	///! The start of the bucket after the one starting at bucket_time. When
+	///! aligned, a bucket_time which isn't (such as one saved by an earlier
+	///! session without --align-buckets) is brought back into line
+	fn next_bucket_time(&self, bucket_time: &DateTime<Utc>) -> DateTime<Utc> {
+		let end_time = *bucket_time + self.bucket_duration;
+		if self.aligned {
+			aligned_bucket_start(&end_time, self.bucket_duration)
+		} else {
+			end_time
+		}
+	}
+
+	///! Whether the bucket ending at end_time is over by now. An aligned bucket
+	///! is over at its end, so that a time on a boundary starts the next one
+	fn has_ended(&self, end_time: &DateTime<Utc>, now: &DateTime<Utc>) -> bool {
+		if self.aligned {
+			end_time <= now
+		} else {
+			end_time.lt(now)
+		}
+	}
+
+	///! Index of the bucket holding time, None if it is older than every bucket
+	fn bucket_index(&self, time: &DateTime<Utc>) -> Option<usize> {
+		let latest = self.buckets.len().checked_sub(1)?;
+		let bucket_time = match self.bucket_time {
+			Some(bucket_time) if time.lt(&bucket_time) => bucket_time,
+			_ => return Some(latest),
+		};
+		if self.aligned {
+			// Count the wall-clock boundaries between time and the latest bucket
+			let millis = self.bucket_duration.num_milliseconds().max(1);
+			let buckets_behind = bucket_time.timestamp_millis().div_euclid(millis) - time.timestamp_millis().div_euclid(millis);
+			return latest.checked_sub(buckets_behind.max(0) as usize);
+		}
+		let time_difference = (bucket_time - *time).num_nanoseconds();
+		let bucket_duration = self.bucket_duration.num_nanoseconds();
+		match (time_difference, bucket_duration) {
+			(Some(time_difference), Some(bucket_duration)) => {
+				let buckets_behind = (time_difference / bucket_duration) as usize;
+				latest.checked_sub(buckets_behind)
+			}
+			_ => Some(latest),
+		}
+	}
+
+	///! Fold an amount from before every bucket into the oldest, marking it as
+	///! holding more than its own time, see overflow
+	fn add_overflow(&mut self, amount: u64) {
+		if let Some(oldest) = self.buckets.first_mut() {
+			*oldest = oldest.saturating_add(amount);
+			self.overflow = self.overflow.saturating_add(amount);
+		}
+	}
+
+	pub fn set_bucket_value(&mut self, value: u64) {
+		let index = self.buckets.len() - 1;
+		self.buckets[index] = value;
+	}
+
+	pub fn increment_value(&mut self) {
+		let index = self.buckets.len() - 1;
+		self.buckets[index] += 1;
+	}
+
+	pub fn buckets(&self) -> &Vec<u64> {
+		&self.buckets
+	}
+
+	pub fn buckets_mut(&mut self) -> &mut Vec<u64> {
+		&mut self.buckets
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn number_keeps_default_timelines() {
+		let steps: TimelineSteps = "210".parse().unwrap();
+		let names: Vec<&str> = steps.timelines.iter().map(|t| t.name.as_str()).collect();
+		assert_eq!(
+			names,
+			vec![
+				"1 second columns",
+				"1 minute columns",
+				"1 hour columns",
+				"1 day columns",
+				"1 week columns",
+				"1 year columns",
+			]
+		);
+		assert!(steps.timelines.iter().all(|t| t.steps == 210));
+		assert_eq!(steps.timelines[4].duration, Duration::days(7));
+	}
+
+	#[test]
+	fn parses_timeline_spec() {
+		let steps: TimelineSteps = "1w:52, 1m:60,1h:48,1d:30".parse().unwrap();
+		assert_eq!(
+			steps.timelines,
+			vec![
+				TimelineSpec { name: "1 minute columns".to_string(), duration: Duration::minutes(1), steps: 60 },
+				TimelineSpec { name: "1 hour columns".to_string(), duration: Duration::hours(1), steps: 48 },
+				TimelineSpec { name: "1 day columns".to_string(), duration: Duration::days(1), steps: 30 },
+				TimelineSpec { name: "1 week columns".to_string(), duration: Duration::weeks(1), steps: 52 },
+			]
+		);
+
+		let steps: TimelineSteps = "15m:96".parse().unwrap();
+		assert_eq!(steps.timelines[0].name, "15 minute columns");
+		assert_eq!(steps.timelines[0].duration, Duration::minutes(15));
+	}
+
+	#[test]
+	fn rejects_invalid_timeline_specs() {
+		for spec in &[
+			"", "5", "1m", "1m:60:2", "0m:60", "-1m:60", "1x:60", "m:60", "1m:abc",
+			"1m:5", "1m:100000", "1m:60,60s:60", "999999999999y:60",
+		] {
+			assert!(spec.parse::<TimelineSteps>().is_err(), "accepted '{}'", spec);
+		}
+	}
+
+	#[test]
+	fn freezes_a_copy_of_the_buckets_until_thawed() {
+		let now = Utc::now();
+		let mut timeline = TimelineSet::new("PUTS".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 3);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 3);
+		timeline.update_current_time(&now);
+		timeline.increment_value(&now);
+
+		timeline.freeze("1 second columns");
+		timeline.increment_value(&now);
+		let frozen = |timeline: &TimelineSet, name: &str| timeline.bucket_sets()[name].frozen.clone();
+		assert_eq!(frozen(&timeline, "1 second columns"), Some(vec![0, 0, 1]));
+		assert_eq!(frozen(&timeline, "1 minute columns"), None);
+
+		timeline.clear(); // Resetting the counters keeps the copy to compare with
+		assert_eq!(frozen(&timeline, "1 second columns"), Some(vec![0, 0, 1]));
+		timeline.thaw();
+		assert_eq!(frozen(&timeline, "1 second columns"), None);
+	}
+
+	#[test]
+	fn sets_absolute_values_in_matching_bucket() {
+		let now = Utc::now();
+		let mut timeline = TimelineSet::new("PEERS".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 10);
+		timeline.update_current_time(&now);
+
+		timeline.set_value(&now, 7);
+		timeline.set_value(&now, 5);
+		timeline.set_value(&(now - Duration::seconds(3)), 9);
+		timeline.set_value(&(now - Duration::seconds(60)), 99); // Too old for any bucket
+
+		let buckets = timeline.get_bucket_set("1 second columns").unwrap().buckets();
+		assert_eq!(buckets[9], 5);
+		assert_eq!(buckets[6], 9);
+		assert_eq!(buckets.iter().filter(|&&value| value != 0).count(), 2);
+	}
+
+	#[test]
+	fn adds_amounts_to_matching_bucket() {
+		let now = Utc::now();
+		let mut timeline = TimelineSet::new("BYTES IN".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 10);
+		timeline.update_current_time(&now);
+
+		timeline.add_value(&now, 1000);
+		timeline.add_value(&now, 24);
+		timeline.add_value(&(now - Duration::seconds(2)), 500);
+		timeline.add_value(&(now - Duration::seconds(60)), 99); // Too old for any bucket, so in the oldest
+
+		let buckets = timeline.get_bucket_set("1 second columns").unwrap().buckets();
+		assert_eq!(buckets[9], 1024);
+		assert_eq!(buckets[7], 500);
+		assert_eq!(buckets[0], 99);
+		assert_eq!(timeline.rate_per_minute(Duration::seconds(10)), 1623 * 6);
+	}
+
+	#[test]
+	fn folds_times_before_the_window_into_the_oldest_bucket() {
+		let now = Utc.ymd(2022, 1, 15).and_hms(12, 0, 0);
+		let mut timeline = TimelineSet::new("PUTS".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 4);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 4);
+		timeline.update_current_time(&now);
+
+		// Replayed from an hour before, and from within the window
+		for minutes in 55..60 {
+			timeline.increment_value(&(now - Duration::minutes(minutes)));
+		}
+		timeline.increment_value(&(now - Duration::seconds(2)));
+		timeline.add_value(&(now - Duration::hours(2)), 10);
+		timeline.set_value(&(now - Duration::hours(2)), 1000); // An absolute value that old is ignored
+
+		let bucket_set = |timeline: &mut TimelineSet, name: &str| {
+			let bs = timeline.get_bucket_set(name).unwrap();
+			(bs.buckets().clone(), bs.overflow)
+		};
+		assert_eq!(bucket_set(&mut timeline, "1 second columns"), (vec![15, 1, 0, 0], 15));
+		assert_eq!(bucket_set(&mut timeline, "1 minute columns"), (vec![15, 0, 0, 1], 15));
+		let total: u64 = timeline.get_bucket_set("1 minute columns").unwrap().buckets().iter().sum();
+		assert_eq!(total, 16); // Nothing lost
+
+		let summed = sum_timelines("PUTS", &[&timeline, &timeline]);
+		assert_eq!(summed.bucket_sets()["1 minute columns"].overflow, 30);
+
+		// Once the oldest bucket drops out of the window, so does what it held
+		timeline.update_current_time(&(now + Duration::milliseconds(1500)));
+		assert_eq!(bucket_set(&mut timeline, "1 second columns"), (vec![1, 0, 0, 0], 0));
+		assert_eq!(bucket_set(&mut timeline, "1 minute columns").1, 15);
+		timeline.clear();
+		assert_eq!(bucket_set(&mut timeline, "1 minute columns"), (vec![0, 0, 0, 0], 0));
+	}
+
+	#[test]
+	fn averages_rates_over_the_window() {
+		let now = Utc.ymd(2022, 1, 15).and_hms(12, 0, 0);
+		let mut timeline = TimelineSet::new("ERRORS".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 60);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 10);
+		timeline.update_current_time(&now);
+		for seconds in 0..60 {
+			timeline.increment_value(&(now - Duration::seconds(seconds))); // One a second for the last minute
+		}
+		timeline.add_value(&now, 9); // And a burst just now, of 10 in the latest second
+
+		// Windows held by the finest buckets
+		assert_eq!(timeline.rate_per_minute(Duration::minutes(1)), 69);
+		assert_eq!(timeline.rate_per_minute(Duration::seconds(10)), 19 * 6);
+		assert_eq!(timeline.rate_per_minute(Duration::seconds(1)), 10 * 60);
+		assert_eq!(timeline.rate_per_minute(Duration::seconds(0)), 10 * 60); // At least a bucket
+
+		// Longer windows come from the coarser buckets, averaged over the whole window
+		assert_eq!(timeline.rate_per_minute(Duration::minutes(5)), 69 / 5);
+		assert_eq!(timeline.rate_per_minute(Duration::seconds(90)), 69 / 2);
+
+		// Longer than any BucketSet holds, clamped to the 10 minutes held
+		assert_eq!(timeline.rate_per_minute(Duration::hours(1)), 69 / 10);
+		assert_eq!(timeline.rate_per_minute(Duration::minutes(10)), 69 / 10);
+
+		assert_eq!(TimelineSet::new("EMPTY".to_string()).rate_per_minute(Duration::minutes(1)), 0);
+	}
+
+	#[test]
+	fn aligns_buckets_to_the_wall_clock() {
+		let at = |hour: u32, minute: u32, second: u32| Utc.ymd(2022, 1, 15).and_hms(hour, minute, second);
+		assert_eq!(aligned_bucket_start(&at(20, 21, 2), Duration::hours(1)), at(20, 0, 0));
+		assert_eq!(aligned_bucket_start(&at(20, 21, 2), Duration::minutes(15)), at(20, 15, 0));
+		assert_eq!(aligned_bucket_start(&at(20, 21, 2), Duration::days(1)), at(0, 0, 0));
+		assert_eq!(aligned_bucket_start(&at(20, 0, 0), Duration::hours(1)), at(20, 0, 0)); // On a boundary
+
+		let mut timeline = TimelineSet::new("PUTS".to_string());
+		timeline.add_bucket_set("1 hour columns", Duration::hours(1), 4);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 4);
+		timeline.align_to_wall_clock();
+		timeline.update_current_time(&at(20, 21, 2));
+		let bucket_time = |timeline: &mut TimelineSet, name: &str| timeline.get_bucket_set(name).unwrap().bucket_time;
+		assert_eq!(bucket_time(&mut timeline, "1 hour columns"), Some(at(20, 0, 0)));
+		assert_eq!(bucket_time(&mut timeline, "1 minute columns"), Some(at(20, 21, 0)));
+
+		timeline.increment_value(&at(20, 21, 2));
+		timeline.increment_value(&at(20, 20, 30)); // The minute before, the same hour
+		timeline.increment_value(&at(19, 59, 59)); // The hour before, too old for the minutes but in the oldest
+		let buckets = |timeline: &mut TimelineSet, name: &str| timeline.get_bucket_set(name).unwrap().buckets().clone();
+		assert_eq!(buckets(&mut timeline, "1 hour columns"), vec![0, 0, 1, 2]);
+		assert_eq!(buckets(&mut timeline, "1 minute columns"), vec![1, 0, 1, 1]);
+
+		// The top of the hour starts the next hour's bucket
+		timeline.update_current_time(&at(21, 0, 0));
+		timeline.increment_value(&at(21, 0, 0));
+		assert_eq!(bucket_time(&mut timeline, "1 hour columns"), Some(at(21, 0, 0)));
+		assert_eq!(buckets(&mut timeline, "1 hour columns"), vec![0, 1, 2, 1]);
+		assert_eq!(buckets(&mut timeline, "1 minute columns"), vec![0, 0, 0, 1]);
+
+		// Started at another time, a second timeline has the same boundaries
+		let mut later = TimelineSet::new("PUTS".to_string());
+		later.add_bucket_set("1 hour columns", Duration::hours(1), 4);
+		later.align_to_wall_clock();
+		later.update_current_time(&at(20, 48, 40));
+		later.update_current_time(&at(21, 5, 0));
+		later.increment_value(&at(20, 30, 0));
+		assert_eq!(bucket_time(&mut later, "1 hour columns"), Some(at(21, 0, 0)));
+		assert_eq!(buckets(&mut later, "1 hour columns"), vec![0, 0, 1, 0]);
+	}
+
+	#[test]
+	fn computes_rate_from_two_samples() {
+		assert_eq!(counter_rate_per_minute(Some(100), 130, Duration::minutes(1)), Some(30.0));
+		assert_eq!(counter_rate_per_minute(Some(100), 130, Duration::seconds(10)), Some(180.0));
+		assert_eq!(counter_rate_per_minute(Some(0), 5, Duration::hours(1)), Some(5.0 / 60.0));
+		assert_eq!(counter_rate_per_minute(Some(100), 100, Duration::minutes(1)), Some(0.0));
+		assert_eq!(counter_rate_per_minute(Some(100), 40, Duration::minutes(1)), Some(0.0)); // Counters were reset
+		assert_eq!(counter_rate_per_minute(None, 130, Duration::minutes(1)), None);
+		assert_eq!(counter_rate_per_minute(Some(100), 130, Duration::zero()), None);
+	}
+
+	#[test]
+	fn completed_rate_waits_for_first_bucket() {
+		let start = Utc::now();
+		let mut timeline = TimelineSet::new("PUTS".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 10);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 10);
+		timeline.update_current_time(&start);
+		for _ in 0..3 {
+			timeline.increment_value(&start);
+		}
+		assert_eq!(timeline.completed_rate_per_minute(), None);
+
+		timeline.update_current_time(&(start + Duration::milliseconds(1500)));
+		assert_eq!(timeline.completed_rate_per_minute(), Some(180.0));
+
+		timeline.clear();
+		assert_eq!(timeline.completed_rate_per_minute(), None);
+	}
+
+	#[test]
+	fn zooms_through_timelines_shortest_first() {
+		let steps: TimelineSteps = "1h:48,1m:60,1d:30".parse().unwrap();
+		let windows: Vec<Duration> = steps.timelines.iter().map(|t| t.window()).collect();
+		assert_eq!(windows, vec![Duration::hours(1), Duration::hours(48), Duration::days(30)]);
+
+		let count = steps.timelines.len();
+		let mut active = 0;
+		let mut zoomed_out = vec![active];
+		for _ in 0..3 {
+			active = zoom_timeline(active, count, false);
+			zoomed_out.push(active);
+		}
+		assert_eq!(zoomed_out, vec![0, 1, 2, 2]);
+
+		let mut zoomed_in = vec![active];
+		for _ in 0..3 {
+			active = zoom_timeline(active, count, true);
+			zoomed_in.push(active);
+		}
+		assert_eq!(zoomed_in, vec![2, 1, 0, 0]);
+
+		assert_eq!(zoom_timeline(0, 1, false), 0);
+		assert_eq!(zoom_timeline(0, 0, true), 0);
+		assert_eq!(zoom_timeline(0, 0, false), 0);
+	}
+
+	#[test]
+	fn never_keeps_more_than_its_buckets() {
+		let start = Utc::now();
+		let mut timeline = TimelineSet::new("PUTS".to_string());
+		timeline.add_bucket_set("1 second columns", Duration::seconds(1), 10);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 12);
+		for second in 0..10_000 {
+			let now = start + Duration::seconds(second);
+			timeline.update_current_time(&now);
+			timeline.increment_value(&now);
+			for bs in timeline.bucket_sets().values() {
+				assert!(bs.buckets().len() <= bs.max_buckets);
+			}
+		}
+		assert_eq!(timeline.get_bucket_set("1 second columns").unwrap().buckets().len(), 10);
+		assert!(TimelineSpec::new("1s", MAX_TIMELINE_STEPS + 1).is_err());
+	}
+
+	#[test]
+	fn sums_timelines_bucket_by_bucket() {
+		assert_eq!(sum_aligned_buckets(&[1, 2, 3], &[10, 20]), vec![1, 12, 23]);
+		assert_eq!(sum_aligned_buckets(&[], &[4, 5]), vec![4, 5]);
+		assert_eq!(sum_aligned_buckets(&[u64::MAX], &[1]), vec![u64::MAX]);
+
+		let now = Utc::now();
+		let mut long = TimelineSet::new("PUTS".to_string());
+		long.add_bucket_set("1 second columns", Duration::seconds(1), 5);
+		let mut short = TimelineSet::new("PUTS".to_string());
+		short.add_bucket_set("1 second columns", Duration::seconds(1), 3);
+		short.add_bucket_set("1 minute columns", Duration::minutes(1), 2);
+		for timeline in [&mut long, &mut short].iter_mut() {
+			timeline.update_current_time(&now);
+			timeline.increment_value(&now);
+			timeline.increment_value(&(now - Duration::seconds(2)));
+		}
+		long.increment_value(&(now - Duration::seconds(4)));
+
+		let mut sum = sum_timelines("ALL PUTS", &[&long, &short]);
+		assert_eq!(sum.get_name(), "ALL PUTS");
+		let seconds = sum.get_bucket_set("1 second columns").unwrap();
+		assert_eq!(seconds.buckets(), &vec![1, 0, 2, 0, 2]);
+		assert_eq!(seconds.max_buckets, 5);
+		assert_eq!(seconds.bucket_time, Some(now));
+		assert_eq!(sum.get_bucket_set("1 minute columns").unwrap().buckets(), &vec![0, 2]);
+		assert!(sum_timelines("ALL PUTS", &[]).bucket_sets().is_empty());
+	}
+
+	#[test]
+	fn finds_the_sign_of_the_trend() {
+		assert_eq!(trend(&[1, 2, 3, 4]), Trend::Rising);
+		assert_eq!(trend(&[9, 1, 0, 0]), Trend::Falling);
+		assert_eq!(trend(&[5, 5, 5]), Trend::Flat);
+		assert_eq!(trend(&[0, 8, 0, 8, 0]), Trend::Flat); // Symmetric, so no slope
+		assert_eq!(trend(&[0, 0, 0, 0, 0, 1]), Trend::Rising); // However slightly
+		assert_eq!(trend(&[7]), Trend::Flat);
+		assert_eq!(trend(&[]), Trend::Flat);
+	}
+
+	#[test]
+	fn judges_trends_by_good_direction() {
+		let mut directions = GoodDirections::default();
+		assert_eq!(directions.direction_for("ERRORS"), Some(GoodDirection::Down));
+		assert_eq!(directions.direction_for("bytes_in"), Some(GoodDirection::Up));
+		assert_eq!(directions.direction_for("STORAGE %"), None);
+		assert_eq!(GoodDirection::Down.is_good(Trend::Falling), Some(true));
+		assert_eq!(GoodDirection::Down.is_good(Trend::Rising), Some(false));
+		assert_eq!(GoodDirection::Up.is_good(Trend::Rising), Some(true));
+		assert_eq!(GoodDirection::Up.is_good(Trend::Flat), None);
+
+		let (name, direction) = parse_good_direction("store_cost=up").unwrap();
+		directions.set(&name, direction);
+		directions.set("PEERS", GoodDirection::Down);
+		assert_eq!(directions.direction_for("STORE COST"), Some(GoodDirection::Up));
+		assert_eq!(directions.direction_for("PEERS"), Some(GoodDirection::Down));
+		assert!(parse_good_direction("errors").is_err());
+		assert!(parse_good_direction("=up").is_err());
+		assert_eq!(parse_good_direction("errors=sideways"), Err(String::from("invalid direction 'sideways', expected up or down")));
+	}
+
+	#[test]
+	fn chooses_each_timelines_chart_style() {
+		let mut styles = ChartStyles::default();
+		assert_eq!(styles.style_for("PUTS"), ChartStyle::Bars);
+		for arg in &["bytes_in=area", "line", "puts=bars"] {
+			let (name, style) = parse_chart_style(arg).unwrap();
+			styles.set(name.as_deref(), style);
+		}
+		assert_eq!(styles.style_for("BYTES IN"), ChartStyle::Area);
+		assert_eq!(styles.style_for("PUTS"), ChartStyle::Bars);
+		assert_eq!(styles.style_for("ERRORS"), ChartStyle::Line); // The default, changed
+		assert_eq!(parse_chart_style("=line"), Err(String::from("invalid chart style '=line', expected a style, or timeline=style")));
+		assert_eq!(parse_chart_style("puts=dots"), Err(String::from("invalid chart style 'dots', expected bars, line or area")));
+	}
+}
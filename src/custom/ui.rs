@@ -2,57 +2,919 @@
 ///
 /// Edit src/custom/ui.rs to create a customised fork of logtail-dash
 
-use super::app::{TIMELINES, App, DashState, DashViewMain, LogMonitor, DEBUG_WINDOW_NAME};
+use super::alerts::{blink_phase, Alert, Severity, BLINK_TICKS};
+use super::ansi::ansi_spans;
+use super::app::{App, CounterView, DashState, DashViewMain, LogLevel, LogMonitor, NodeMetrics, SortKey, COMPARE_NODES, DEBUG_WINDOW_NAME, REWARDS_TIMELINE};
+use super::capacity::{project_storage, storage_growth_per_minute, storage_ratio, StorageProjection};
+use super::check::{health_score, HealthState};
+use super::flash::Flash;
+use super::heatmap::{heat_cells, heat_level, heat_max, heat_style, HEAT_GLYPHS};
+use super::keys::{key_names, KEY_BINDINGS};
+use super::format::{NumberFormat, Units};
+use super::latency::format_latency;
+use super::fuzzy::FuzzyMatch;
+use super::names::DEFAULT_GROUP;
+use super::focus::{chart_emphasis, emphasis_style, panel_emphasis, Emphasis};
+use super::panels::PanelKind;
+use super::theme::Theme;
+use super::timelines::{sum_timelines, trend, GoodDirection, TimelineSet, Trend};
 use super::ui_debug::draw_dashboard as debug_draw_dashboard;
 
 #[path = "../widgets/mod.rs"]
 pub mod widgets;
-use self::widgets::sparkline::Sparkline2;
+use self::widgets::sparkline::{downsample, downsample_factor, Axis, MultiSparkline, Scale, SignedSparkline, Sparkline2};
 use self::widgets::gauge::Gauge2;
+use self::widgets::mmm::{min_mean_max, MinMeanMax};
+use self::widgets::scrollbar::Scrollbar;
 use std::collections::HashMap;
 
 use tui::{
 	backend::Backend,
 	layout::{Constraint, Direction, Layout, Rect},
 	style::{Color, Modifier, Style},
-	text::{Spans},
-	widgets::{Block, Borders, List, ListItem},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Row, Table},
 	Frame,
 };
 
 pub fn draw_dashboard<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+	app.dash_state.click_targets = ClickTargets::default();
+	app.dash_state.screen_area = f.size();
+	let banner_height = app.dash_state.active_alerts.len().min(ALERT_BANNER_LINES) as u16;
+	let chunks = dashboard_areas(f.size(), banner_height);
+	if banner_height > 0 {
+		draw_alert_banner(f, chunks[0], &app.dash_state);
+	}
+	let area = chunks[1];
 	match app.dash_state.main_view {
-		DashViewMain::DashSummary => {} //draw_summary_dash(f, dash_state, monitors),
-		DashViewMain::DashNode => draw_node_dash(f, &mut app.dash_state, &mut app.monitors),
-		DashViewMain::DashDebug => debug_draw_dashboard(f, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashSummary => draw_summary_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashNode => draw_node_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashDetail => draw_detail(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashCompare => draw_compare_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashWarnings => draw_warnings(f, area, &app.dash_state),
+		DashViewMain::DashDiagnostics => draw_diagnostics(f, area, &app.dash_state),
+		DashViewMain::DashHeatmap => draw_heatmap(f, area, &app.dash_state, &app.monitors),
+		DashViewMain::DashDebug => debug_draw_dashboard(f, area, &app.dash_state, &mut app.monitors),
+	}
+	draw_status_bar(f, chunks[2], &app.dash_state, &app.monitors);
+	if app.dash_state.help_visible {
+		draw_help(f, &app.dash_state);
+	}
+}
+
+///! The alert banner, view and status bar of a screen of the given size. Laid
+///! out afresh on every frame, so a resized terminal is filled at its new size
+pub fn dashboard_areas(size: Rect, banner_height: u16) -> Vec<Rect> {
+	Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(banner_height), Constraint::Min(0), Constraint::Length(1)].as_ref())
+		.split(size)
+}
+
+// Most alerts shown above the dashboard, the last line summarising any more
+const ALERT_BANNER_LINES: usize = 3;
+
+fn draw_alert_banner<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState) {
+	let theme = &dash_state.theme;
+	let lines: Vec<Spans> = alert_banner_lines(&dash_state.active_alerts, area.height as usize)
+		.into_iter()
+		.map(|(severity, text, acknowledged)| {
+			let style = if acknowledged { theme.disabled_text } else { severity_style(theme, severity).add_modifier(Modifier::REVERSED) };
+			Spans::from(Span::styled(text, style))
+		})
+		.collect();
+	f.render_widget(Paragraph::new(lines), area);
+}
+
+// Of alerts and lines spotlit by keyword
+fn severity_style(theme: &Theme, severity: Severity) -> Style {
+	match severity {
+		Severity::Critical => theme.error_text,
+		Severity::Warning => theme.gauge_warning,
+		Severity::Info => theme.text,
+	}
+}
+
+// The banner lines of the alerts, most severe first, with 'and N more' when
+// there are more than rows, and whether each is acknowledged
+fn alert_banner_lines(alerts: &[Alert], rows: usize) -> Vec<(Severity, String, bool)> {
+	let mut lines: Vec<(Severity, String, bool)> =
+		alerts.iter().take(rows).map(|alert| (alert.severity, alert.describe(), alert.acknowledged)).collect();
+	if alerts.len() > rows && rows > 0 {
+		let hidden = alerts.len() - rows + 1;
+		let last = &alerts[rows - 1];
+		lines[rows - 1] = (last.severity, format!("... and {} more alerts", hidden), last.acknowledged);
+	}
+	lines
+}
+
+///! Screen regions drawn on the last frame, so that mouse clicks can be mapped
+///! back to what was under them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClickTargets {
+	pub node_rows: Rect,
+	pub node_logfiles: Vec<String>, // In the order of the rows
+}
+
+impl ClickTargets {
+	///! The logfile of the node whose summary row is at a screen position
+	pub fn node_at(&self, column: u16, row: u16) -> Option<&String> {
+		row_at(self.node_rows, column, row).and_then(|index| self.node_logfiles.get(index))
+	}
+}
+
+// Index of the one line row of area at a screen position
+fn row_at(area: Rect, column: u16, row: u16) -> Option<usize> {
+	if column < area.left() || column >= area.right() || row < area.top() || row >= area.bottom() {
+		return None;
+	}
+	Some((row - area.top()) as usize)
+}
+
+///! A modal list of the key bindings, drawn over whichever view is showing
+fn draw_help<B: Backend>(f: &mut Frame<B>, dash_state: &DashState) {
+	let theme = &dash_state.theme;
+	let names: Vec<String> = KEY_BINDINGS.iter().map(|binding| key_names(dash_state.key_map.keys_for(binding.action))).collect();
+	let names_width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+	let items: Vec<ListItem> = KEY_BINDINGS
+		.iter()
+		.zip(names.iter())
+		.map(|(binding, name)| {
+			ListItem::new(Spans::from(vec![
+				Span::styled(format!(" {:<width$}  ", name, width = names_width), theme.header),
+				Span::styled(binding.description, theme.text),
+			]))
+		})
+		.collect();
+
+	let descriptions_width = KEY_BINDINGS.iter().map(|binding| binding.description.len()).max().unwrap_or(0);
+	let width = (names_width + descriptions_width + 5) as u16; // Borders, padding and gap
+	let height = KEY_BINDINGS.len() as u16 + 2;
+	let area = centered_rect(width, height, f.size());
+
+	let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Keys - press Esc to close"));
+	f.render_widget(Clear, area);
+	f.render_widget(list, area);
+}
+
+// A rect of the given size centered in area, shrunk to fit if need be
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+	let width = width.min(area.width);
+	let height = height.min(area.height);
+	Rect::new(
+		area.x + (area.width - width) / 2,
+		area.y + (area.height - height) / 2,
+		width,
+		height,
+	)
+}
+
+///! One row of the summary view
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeSummary {
+	pub index: usize,
+	pub logfile: String,
+	pub name: String,
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub storage_used: u64,
+	pub storage_ratio: Option<f64>, // None until the node's capacity is known
+	pub errors_per_minute: u64,
+	pub lines_per_minute: u64, // Every line logged, over the finest timeline bucket
+	pub rewards: u128,
+	pub is_stale: bool,
+	pub is_disconnected: bool,
+	pub enabled: bool,
+	pub read_error: Option<String>, // Why the logfile can't be read
+	pub filter_match: Option<FuzzyMatch>, // Of the name, while filtering
+	pub tag: Option<String>, // Of the node's group, see --names
+	pub uptime: Option<chrono::Duration>, // Since the node's latest startup banner
+	pub health: u8, // From 0 to 100, see health_score()
+}
+
+impl NodeSummary {
+	fn from_monitor(monitor: &LogMonitor, dash_state: &DashState, now: &chrono::DateTime<chrono::Utc>) -> NodeSummary {
+		NodeSummary {
+			index: monitor.index,
+			logfile: monitor.logfile.clone(),
+			name: monitor.name.clone(),
+			puts: monitor.metrics.activity_puts,
+			gets: monitor.metrics.activity_gets,
+			errors: monitor.metrics.activity_errors,
+			storage_used: monitor.metrics.used_space,
+			storage_ratio: monitor.metrics.storage_ratio(),
+			errors_per_minute: monitor.metrics.errors_per_minute(),
+			lines_per_minute: monitor.metrics.lines_per_minute(),
+			rewards: monitor.metrics.rewards,
+			is_stale: monitor.metrics.is_stale(now, dash_state.stale_after),
+			is_disconnected: !monitor.metrics.connected,
+			enabled: monitor.enabled,
+			read_error: monitor.read_error.clone(),
+			filter_match: if dash_state.node_filter.is_empty() { None } else { dash_state.node_filter.fuzzy_match(&monitor.name) },
+			tag: monitor.tag.clone(),
+			uptime: monitor.metrics.uptime(now),
+			health: health_score(&HealthState::of(&monitor.metrics, now), &dash_state.health_criteria(), &dash_state.health_weights),
+		}
+	}
+}
+
+///! A column of the summary table, see --columns
+pub struct SummaryColumn {
+	pub name: &'static str, // As given to --columns
+	pub header: &'static str,
+	width: u16, // 0 for the rest of the row
+	cell: fn(&NodeSummary, &NumberFormat) -> String,
+}
+
+impl SummaryColumn {
+	// Whether a change of its value flashes, which isn't news for a node's
+	// number, its name or its uptime, which changes by itself
+	fn flashes(&self) -> bool {
+		!matches!(self.name, "node" | "name" | "uptime")
+	}
+}
+
+pub static SUMMARY_COLUMNS: [SummaryColumn; 11] = [
+	SummaryColumn { name: "node", header: "Node", width: 4, cell: |s, _| format!("{:>4}", s.index + 1) },
+	SummaryColumn { name: "name", header: "Name", width: 0, cell: |s, _| summary_name(s) },
+	SummaryColumn { name: "puts", header: "Puts", width: 10, cell: |s, n| format!("{:>10}", n.format_compact_count(s.puts)) },
+	SummaryColumn { name: "gets", header: "Gets", width: 10, cell: |s, n| format!("{:>10}", n.format_compact_count(s.gets)) },
+	SummaryColumn { name: "errors", header: "Errors", width: 10, cell: |s, n| format!("{:>10}", n.format_compact_count(s.errors)) },
+	SummaryColumn {
+		name: "errors_per_minute",
+		header: "Errors/min",
+		width: 10,
+		cell: |s, n| format!("{:>10}", n.format_compact_count(s.errors_per_minute)),
+	},
+	SummaryColumn { name: "storage", header: "Storage", width: 10, cell: |s, n| format!("{:>10}", format_size(n, s.storage_used)) },
+	SummaryColumn {
+		name: "storage_ratio",
+		header: "Used",
+		width: 6,
+		cell: |s, n| format!("{:>6}", s.storage_ratio.map_or(String::from("-"), |ratio| n.format_percent(ratio, 0))),
+	},
+	SummaryColumn { name: "rewards", header: "Rewards", width: 20, cell: |s, n| format!("{:>20}", format_rewards(n, s.rewards)) },
+	SummaryColumn { name: "uptime", header: "Uptime", width: 10, cell: |s, _| format!("{:>10}", format_uptime(s.uptime)) },
+	SummaryColumn { name: "health", header: "Health", width: 6, cell: |s, _| format!("{:>6}", s.health) },
+];
+
+///! Value of --columns: the columns of the summary table in order, such as
+///! 'name,puts,gets,errors,storage,rewards'
+#[derive(Clone, Debug, PartialEq)]
+pub struct SummaryColumns(Vec<usize>); // Indices of SUMMARY_COLUMNS
+
+impl SummaryColumns {
+	pub fn iter(&self) -> impl Iterator<Item = &'static SummaryColumn> + '_ {
+		self.0.iter().map(|index| &SUMMARY_COLUMNS[*index])
+	}
+
+	pub fn headers(&self) -> Vec<&'static str> {
+		self.iter().map(|column| column.header).collect()
+	}
+
+	pub fn cells(&self, summary: &NodeSummary, number_format: &NumberFormat) -> Vec<String> {
+		self.iter().map(|column| (column.cell)(summary, number_format)).collect()
+	}
+
+	fn widths(&self) -> Vec<Constraint> {
+		self.iter()
+			.map(|column| if column.width == 0 { Constraint::Min(10) } else { Constraint::Length(column.width) })
+			.collect()
+	}
+
+	///! The cells of a group's header: its totals, labelled in the name column
+	pub fn group_cells(&self, group: &NodeGroup, collapsed: bool, number_format: &NumberFormat) -> Vec<String> {
+		let nodes = group.summaries.len();
+		let label = format!("{} {} ({} node{})", if collapsed { "▸" } else { "▾" }, group.tag, nodes, if nodes == 1 { "" } else { "s" });
+		let totals = NodeSummary {
+			index: 0,
+			logfile: String::new(),
+			name: label,
+			puts: group.totals.puts,
+			gets: group.totals.gets,
+			errors: group.totals.errors,
+			storage_used: group.totals.storage_used,
+			storage_ratio: group.totals.average_storage_ratio,
+			errors_per_minute: group.totals.errors_per_minute,
+			lines_per_minute: 0,
+			rewards: group.totals.rewards,
+			is_stale: false,
+			is_disconnected: false,
+			enabled: true,
+			read_error: None,
+			filter_match: None,
+			tag: Some(group.tag.clone()),
+			uptime: None,
+			health: 0,
+		};
+		self.iter()
+			.map(|column| match column.name {
+				"node" | "uptime" | "health" => String::new(),
+				_ => (column.cell)(&totals, number_format),
+			})
+			.collect()
+	}
+
+	// Where the name column starts, after the columns before it and their spacing
+	fn name_offset(&self, spacing: u16) -> Option<u16> {
+		let before = self.iter().position(|column| column.name == "name")?;
+		Some(self.iter().take(before).map(|column| column.width + spacing).sum())
+	}
+
+	// Where each column starts
+	fn offsets(&self, spacing: u16) -> Vec<u16> {
+		self.iter()
+			.scan(0, |offset, column| {
+				let start = *offset;
+				*offset += column.width + spacing;
+				Some(start)
+			})
+			.collect()
+	}
+}
+
+impl Default for SummaryColumns {
+	fn default() -> SummaryColumns {
+		"node,storage,errors_per_minute,rewards,name".parse().unwrap()
+	}
+}
+
+impl std::str::FromStr for SummaryColumns {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<SummaryColumns, String> {
+		let mut columns = Vec::<usize>::new();
+		for name in s.split(',').map(str::trim) {
+			let index = SUMMARY_COLUMNS.iter().position(|column| column.name == name).ok_or_else(|| {
+				let names: Vec<&str> = SUMMARY_COLUMNS.iter().map(|column| column.name).collect();
+				format!("unknown column '{}', expected one of {}", name, names.join(", "))
+			})?;
+			if columns.contains(&index) {
+				return Err(format!("column '{}' is given more than once", name));
+			}
+			columns.push(index);
+		}
+		Ok(SummaryColumns(columns))
+	}
+}
+
+///! Totals across the enabled nodes of the summary view
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FleetSummary {
+	pub nodes: usize,
+	pub disabled: usize, // Nodes left out of the totals
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub storage_used: u64,
+	pub average_storage_ratio: Option<f64>, // Of the nodes whose capacity is known
+	pub rewards: u128,
+	pub stale: usize,
+	pub disconnected: usize,
+	pub errors_per_minute: u64,
+}
+
+fn summarise_fleet(summaries: &[NodeSummary]) -> FleetSummary {
+	let mut fleet = FleetSummary {
+		nodes: summaries.iter().filter(|summary| summary.enabled).count(),
+		disabled: summaries.iter().filter(|summary| !summary.enabled).count(),
+		..FleetSummary::default()
+	};
+	let mut ratio_total = 0.0;
+	let mut ratio_count = 0;
+	for summary in summaries.iter().filter(|summary| summary.enabled) {
+		fleet.puts += summary.puts;
+		fleet.gets += summary.gets;
+		fleet.errors += summary.errors;
+		fleet.storage_used += summary.storage_used;
+		fleet.rewards = fleet.rewards.saturating_add(summary.rewards);
+		fleet.errors_per_minute += summary.errors_per_minute;
+		if summary.is_stale {
+			fleet.stale += 1;
+		}
+		if summary.is_disconnected {
+			fleet.disconnected += 1;
+		}
+		if let Some(storage_ratio) = summary.storage_ratio {
+			ratio_total += storage_ratio;
+			ratio_count += 1;
+		}
+	}
+	if ratio_count > 0 {
+		fleet.average_storage_ratio = Some(ratio_total / ratio_count as f64);
+	}
+	fleet
+}
+
+///! Nodes of the summary sharing a tag, with their totals
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeGroup {
+	pub tag: String,
+	pub totals: FleetSummary,
+	pub summaries: Vec<NodeSummary>, // In the order given
+}
+
+///! Partition the summaries by tag, in tag order with untagged nodes last in
+///! DEFAULT_GROUP. The nodes of each group keep their order
+pub fn group_node_summaries(summaries: Vec<NodeSummary>) -> Vec<NodeGroup> {
+	let mut groups = Vec::<NodeGroup>::new();
+	for summary in summaries {
+		let tag = summary.tag.clone().unwrap_or_else(|| String::from(DEFAULT_GROUP));
+		match groups.iter_mut().find(|group| group.tag == tag) {
+			Some(group) => group.summaries.push(summary),
+			None => groups.push(NodeGroup { tag, totals: FleetSummary::default(), summaries: vec![summary] }),
+		}
+	}
+	groups.sort_by(|a, b| (a.tag == DEFAULT_GROUP).cmp(&(b.tag == DEFAULT_GROUP)).then(a.tag.cmp(&b.tag)));
+	for group in groups.iter_mut() {
+		group.totals = summarise_fleet(&group.summaries);
+	}
+	groups
+}
+
+// Stable sort by the chosen metric, with ties in name order whatever the direction
+fn sort_node_summaries(summaries: &mut [NodeSummary], sort_key: SortKey, ascending: bool) {
+	use std::cmp::Ordering;
+	summaries.sort_by(|a, b| {
+		let ordering = match sort_key {
+			SortKey::Logfile => Ordering::Equal,
+			SortKey::StorageUsed => a.storage_used.cmp(&b.storage_used),
+			SortKey::ErrorRate => a.errors_per_minute.cmp(&b.errors_per_minute),
+			SortKey::Rewards => a.rewards.cmp(&b.rewards),
+			SortKey::Health => a.health.cmp(&b.health),
+		};
+		let ordering = if ascending { ordering } else { ordering.reverse() };
+		let name_ordering = if sort_key == SortKey::Logfile && !ascending {
+			b.name.cmp(&a.name).then(b.logfile.cmp(&a.logfile))
+		} else {
+			a.name.cmp(&b.name).then(a.logfile.cmp(&b.logfile))
+		};
+		ordering.then(name_ordering)
+	});
+}
+
+fn draw_summary_dash<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitors: &mut HashMap<String, LogMonitor>,
+) {
+	let now = chrono::Utc::now();
+	let mut summaries: Vec<NodeSummary> = monitors
+		.values()
+		.filter(|monitor| !monitor.is_debug_dashboard_log)
+		.filter(|monitor| dash_state.node_filter.matches_node(monitor))
+		.map(|monitor| NodeSummary::from_monitor(monitor, dash_state, &now))
+		.collect();
+	sort_node_summaries(&mut summaries, dash_state.sort_key, dash_state.sort_ascending);
+	if !dash_state.node_filter.is_empty() {
+		rank_by_filter_match(&mut summaries);
+	}
+
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(FLEET_TIMELINE_HEIGHT)].as_ref())
+		.split(area);
+
+	let theme = &dash_state.theme;
+	let number_format = &dash_state.number_format;
+	draw_fleet_summary(f, chunks[0], dash_state, &summarise_fleet(&summaries));
+	if dash_state.overlay_timelines {
+		draw_fleet_timeline(f, chunks[2], dash_state, monitors, &summaries);
+	} else {
+		draw_fleet_totals(f, chunks[2], dash_state, monitors, &summaries);
+	}
+
+	// Once any node is tagged, nodes are listed under the header of their group
+	let mut rows = Vec::<(Vec<String>, Style, &NodeSummary)>::new();
+	let mut group_rows = Vec::<bool>::new(); // For each row, whether it is a group's header
+	let groups = if summaries.iter().any(|summary| summary.tag.is_some()) {
+		group_node_summaries(summaries.clone())
+	} else {
+		Vec::new()
+	};
+	let node_row = |summary| {
+		let style = blinking_style(summary_style(summary, theme, dash_state.health_colors), dash_state, &summary.name);
+		(dash_state.summary_columns.cells(summary, number_format), style, summary)
+	};
+	if groups.is_empty() {
+		rows.extend(summaries.iter().map(node_row));
+		group_rows.resize(rows.len(), false);
+	}
+	for group in groups.iter() {
+		let collapsed = dash_state.collapsed_groups.contains(&group.tag);
+		rows.push((dash_state.summary_columns.group_cells(group, collapsed, number_format), theme.header, &group.summaries[0]));
+		group_rows.push(true);
+		if !collapsed {
+			rows.extend(group.summaries.iter().map(node_row));
+			group_rows.resize(rows.len(), false);
+		}
+	}
+
+	let table_area = if dash_state.top_talkers > 0 {
+		let columns = Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([Constraint::Min(0), Constraint::Length(TOP_TALKERS_WIDTH)].as_ref())
+			.split(chunks[1]);
+		draw_top_talkers(f, columns[1], dash_state, &summaries);
+		columns[0]
+	} else {
+		chunks[1]
+	};
+
+	// Only the rows which fit are drawn, scrolled to keep the focused node's in view
+	let inner = Block::default().borders(Borders::ALL).inner(table_area);
+	let header_height = 2.min(inner.height); // The header and the gap below it
+	let visible_rows = (inner.height - header_height) as usize;
+	let selected = rows.iter().zip(group_rows.iter()).position(|((_cells, _style, summary), is_group)| {
+		!is_group && monitors.get(&summary.logfile).is_some_and(|monitor| monitor.has_focus)
+	});
+	let offset = viewport_offset(selected, rows.len(), visible_rows, dash_state.summary_offset);
+	let shown = offset..rows.len().min(offset + visible_rows);
+	let table_rows = rows[shown.clone()].iter().enumerate().map(|(index, (cells, style, _summary))| {
+		let style = if selected == Some(offset + index) { style.patch(theme.selected_row) } else { *style };
+		Row::StyledData(cells.iter(), style)
+	});
+
+	let widths = dash_state.summary_columns.widths();
+	let direction = if dash_state.sort_ascending { "ascending" } else { "descending" };
+	let position = if rows.len() > visible_rows {
+		format!(" - {}-{} of {}", shown.start + 1, shown.end, rows.len())
+	} else {
+		String::new()
+	};
+	let title = format!(
+		"Nodes - sorted by {} ({}){}{}{}",
+		dash_state.sort_key.name(),
+		direction,
+		position,
+		filter_title(dash_state),
+		paused_title(dash_state)
+	);
+	let table = Table::new(dash_state.summary_columns.headers().into_iter(), table_rows)
+		.block(Block::default().borders(Borders::ALL).title(title))
+		.header_style(theme.header)
+		.widths(&widths)
+		.column_spacing(2);
+	f.render_widget(table, table_area);
+
+	// Over the right border beside the rows, so that the columns stay where they are
+	let node_rows = Rect::new(inner.x, inner.y + header_height, inner.width, inner.height - header_height);
+	if table_area.width > 0 {
+		let scrollbar = Scrollbar::default().position(offset, rows.len()).track_style(theme.disabled_text).thumb_style(theme.header);
+		f.render_widget(scrollbar, Rect::new(table_area.right() - 1, node_rows.y, 1, node_rows.height));
+	}
+
+	// Values which have changed since they were last drawn flash, see --flash-ticks
+	let offsets = dash_state.summary_columns.offsets(2);
+	let mut changed = Vec::<(Rect, Style)>::new();
+	for (row, ((cells, _style, summary), is_group)) in rows[shown.clone()].iter().zip(group_rows[shown.clone()].iter()).enumerate() {
+		if *is_group || row as u16 >= node_rows.height {
+			continue;
+		}
+		let columns = dash_state.summary_columns.iter().zip(cells.iter()).zip(offsets.iter());
+		for ((column, cell), offset) in columns.filter(|((column, _cell), _offset)| column.flashes()) {
+			let style = match dash_state.change_flash.observe(&summary.logfile, column.name, cell, dash_state.ticks) {
+				Some(Flash::Bright) => theme.changed_value,
+				Some(Flash::Fading) => fading_style(theme.changed_value),
+				None => continue,
+			};
+			let x = node_rows.x + offset;
+			if x < node_rows.right() {
+				let width = column.width.min(node_rows.right() - x);
+				changed.push((Rect::new(x, node_rows.y + row as u16, width, 1), style));
+			}
+		}
+	}
+	f.render_widget(ChangedCells(changed), node_rows);
+	dash_state.change_flash.retain_rows(|logfile| monitors.contains_key(logfile));
+
+	let name_offset = dash_state.summary_columns.name_offset(2);
+	if let Some(name_offset) = name_offset.filter(|offset| inner.width >= offset + 10) {
+		let names = Rect::new(inner.x + name_offset, node_rows.y, inner.width - name_offset, node_rows.height);
+		let matches = rows[shown.clone()].iter().zip(group_rows[shown.clone()].iter()).map(|((_cells, _style, summary), is_group)| {
+			match &summary.filter_match {
+				Some(found) if !is_group => found.positions.as_slice(),
+				_ => &[][..],
+			}
+		});
+		f.render_widget(MatchHighlights { positions: matches.collect(), style: theme.filter_match }, names);
+	}
+	dash_state.summary_logfiles = rows
+		.iter()
+		.zip(group_rows.iter())
+		.filter(|(_row, is_group)| !**is_group)
+		.map(|((_cells, _style, summary), _is_group)| summary.logfile.clone())
+		.collect();
+	dash_state.summary_offset = offset;
+	dash_state.click_targets = ClickTargets {
+		node_rows,
+		node_logfiles: rows[shown].iter().map(|(_cells, _style, summary)| summary.logfile.clone()).collect(), // A group's header shows its first node
+	};
+}
+
+///! The first row to show of a list of len rows in height rows, scrolled from
+///! offset only as far as keeps the selected row in view, and no further than
+///! fills the rows
+pub fn viewport_offset(selected: Option<usize>, len: usize, height: usize, offset: usize) -> usize {
+	let offset = match selected {
+		Some(selected) if height > 0 && selected < offset => selected,
+		Some(selected) if height > 0 && selected >= offset + height => selected + 1 - height,
+		_ => offset,
+	};
+	offset.min(len.saturating_sub(height))
+}
+
+const TOP_TALKERS_WIDTH: u16 = 32;
+
+///! The n enabled nodes logging the most lines per minute, busiest first
+///! with ties in name order
+pub fn rank_top_talkers(summaries: &[NodeSummary], n: usize) -> Vec<&NodeSummary> {
+	let mut ranked: Vec<&NodeSummary> = summaries.iter().filter(|summary| summary.enabled).collect();
+	ranked.sort_by(|a, b| b.lines_per_minute.cmp(&a.lines_per_minute).then(a.name.cmp(&b.name)).then(a.logfile.cmp(&b.logfile)));
+	ranked.truncate(n);
+	ranked
+}
+
+// The busiest of the nodes listed, beside the summary table
+fn draw_top_talkers<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, summaries: &[NodeSummary]) {
+	let theme = &dash_state.theme;
+	let items: Vec<ListItem> = rank_top_talkers(summaries, dash_state.top_talkers)
+		.iter()
+		.map(|summary| {
+			let rate = format!("{}/min", dash_state.number_format.format_count(summary.lines_per_minute));
+			let name_width = (area.width as usize).saturating_sub(rate.len() + 3);
+			let name: String = summary.name.chars().take(name_width).collect();
+			ListItem::new(Span::styled(format!("{:<width$} {}", name, rate, width = name_width), summary_style(summary, theme, dash_state.health_colors)))
+		})
+		.collect();
+	let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Top talkers (lines)"));
+	f.render_widget(list, area);
+}
+
+///! The style reversed while the node breaks a critical alert rule not
+///! acknowledged and the blink is lit, unless --no-blink
+fn blinking_style(style: Style, dash_state: &DashState, node: &str) -> Style {
+	let critical = dash_state
+		.active_alerts
+		.iter()
+		.any(|alert| alert.node == node && alert.severity == Severity::Critical && !alert.acknowledged);
+	if critical && dash_state.blink && blink_phase(dash_state.ticks, BLINK_TICKS) {
+		style.add_modifier(Modifier::REVERSED)
+	} else {
+		style
+	}
+}
+
+fn summary_style(summary: &NodeSummary, theme: &Theme, health_colors: bool) -> Style {
+	if !summary.enabled {
+		theme.disabled_text
+	} else if summary.is_stale || summary.is_disconnected || summary.read_error.is_some() {
+		theme.error_text
+	} else if health_colors {
+		health_style(summary.health, theme)
+	} else {
+		theme.node_style(summary.index)
+	}
+}
+
+///! The colour of a health score with --health-colors: that of a full gauge
+///! from 80, a warning from 50 and critical below
+pub fn health_style(health: u8, theme: &Theme) -> Style {
+	if health >= 80 {
+		theme.gauge_fill
+	} else if health >= 50 {
+		theme.gauge_warning
+	} else {
+		theme.gauge_critical
+	}
+}
+
+///! Put the nodes which best match the filter first, otherwise keeping their
+///! order. Those whose names don't fuzzy match, but matched another way, go last
+pub fn rank_by_filter_match(summaries: &mut [NodeSummary]) {
+	summaries.sort_by_key(|summary| std::cmp::Reverse(summary.filter_match.as_ref().map_or(i64::MIN, |found| found.score)));
+}
+
+// Styles the characters of each row's name matched by the filter, drawn over them
+struct MatchHighlights<'a> {
+	positions: Vec<&'a [usize]>, // Of each row
+	style: Style,
+}
+
+impl tui::widgets::Widget for MatchHighlights<'_> {
+	fn render(self, area: Rect, buf: &mut tui::buffer::Buffer) {
+		for (row, positions) in self.positions.iter().enumerate().take(area.height as usize) {
+			for position in positions.iter().filter(|position| **position < area.width as usize) {
+				buf.set_style(Rect::new(area.x + *position as u16, area.y + row as u16, 1, 1), self.style);
+			}
+		}
+	}
+}
+
+///! What is left of a style as its flash fades: no colours and not reversed,
+///! but still bold or underlined
+pub fn fading_style(style: Style) -> Style {
+	Style { fg: None, bg: None, add_modifier: style.add_modifier - Modifier::REVERSED, sub_modifier: Modifier::empty() }
+}
+
+// Cells of the summary to restyle, over the table
+struct ChangedCells(Vec<(Rect, Style)>);
+
+impl tui::widgets::Widget for ChangedCells {
+	fn render(self, _area: Rect, buf: &mut tui::buffer::Buffer) {
+		for (cell, style) in self.0 {
+			buf.set_style(cell, style);
+		}
+	}
+}
+
+// The name of a node in the summary, with why it isn't being read
+fn summary_name(summary: &NodeSummary) -> String {
+	if !summary.enabled {
+		return format!("{} (disabled)", summary.name);
+	}
+	match &summary.read_error {
+		Some(reason) => format!("{} (error: {})", summary.name, reason),
+		None => summary.name.clone(),
 	}
 }
 
+const FLEET_TIMELINE_HEIGHT: u16 = 12;
+
+// The PUTS and GETS of all the nodes listed, summed bucket by bucket
+fn draw_fleet_totals<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &DashState,
+	monitors: &HashMap<String, LogMonitor>,
+	summaries: &[NodeSummary],
+) {
+	let metrics: Vec<&NodeMetrics> = summaries
+		.iter()
+		.filter(|summary| summary.enabled)
+		.filter_map(|summary| monitors.get(&summary.logfile))
+		.map(|monitor| &monitor.metrics)
+		.collect();
+	let puts: Vec<&TimelineSet> = metrics.iter().map(|metrics| &metrics.puts_timeline).collect();
+	let gets: Vec<&TimelineSet> = metrics.iter().map(|metrics| &metrics.gets_timeline).collect();
+	let puts = sum_timelines("ALL PUTS", &puts);
+	let gets = sum_timelines("ALL GETS", &gets);
+	let theme = &dash_state.theme;
+	let charts = vec![
+		vec![(&puts, "ALL PUTS", theme.puts_sparkline, Axis::Left)],
+		vec![(&gets, "ALL GETS", theme.gets_sparkline, Axis::Left)],
+	];
+	draw_timelines(f, area, dash_state, &charts);
+}
+
+// The PUTS of every node listed, overlaid in the colour of each node
+fn draw_fleet_timeline<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &DashState,
+	monitors: &HashMap<String, LogMonitor>,
+	summaries: &[NodeSummary],
+) {
+	let series: Vec<TimelineSeries> = summaries
+		.iter()
+		.filter(|summary| summary.enabled)
+		.filter_map(|summary| monitors.get(&summary.logfile))
+		.map(|monitor| {
+			(&monitor.metrics.puts_timeline, monitor.name.as_str(), dash_state.theme.node_style(monitor.index), Axis::Left)
+		})
+		.collect();
+	if series.is_empty() {
+		return;
+	}
+	draw_timelines(f, area, dash_state, &[series]);
+}
+
+fn draw_fleet_summary<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, fleet: &FleetSummary) {
+	let theme = &dash_state.theme;
+	let number_format = &dash_state.number_format;
+	let storage_ratio = match fleet.average_storage_ratio {
+		Some(storage_ratio) => number_format.format_percent(storage_ratio, 1),
+		None => String::from("unknown"),
+	};
+	let text = format!(
+		"PUTS {}  GETS {}  ERRORS {}  Storage {} (average {} full)  Rewards {}",
+		number_format.format_compact_count(fleet.puts),
+		number_format.format_compact_count(fleet.gets),
+		number_format.format_compact_count(fleet.errors),
+		format_size(number_format, fleet.storage_used),
+		storage_ratio,
+		format_rewards(number_format, fleet.rewards),
+	);
+	let title = format!("All nodes ({})", fleet.nodes);
+	let widget = List::new(vec![ListItem::new(Span::styled(text, theme.header))])
+		.block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(widget, area);
+}
+
+///! One line of fleet health below every view: nodes, problems, error rate
+///! and storage, across all nodes whatever the filter
+fn draw_status_bar<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitors: &HashMap<String, LogMonitor>) {
+	let now = chrono::Utc::now();
+	let summaries: Vec<NodeSummary> = monitors
+		.values()
+		.filter(|monitor| !monitor.is_debug_dashboard_log)
+		.map(|monitor| NodeSummary::from_monitor(monitor, dash_state, &now))
+		.collect();
+	let fleet = summarise_fleet(&summaries);
+	let theme = &dash_state.theme;
+	let style = if fleet.stale > 0 || fleet.disconnected > 0 { theme.error_text } else { theme.header };
+	let text = status_bar_text(&fleet, area.width);
+	f.render_widget(List::new(vec![ListItem::new(Span::styled(text, style))]), area);
+}
+
+// The status bar fields in order of priority, dropping the lowest priority
+// fields that don't fit within width, and truncating to width as a last resort
+fn status_bar_text(fleet: &FleetSummary, width: u16) -> String {
+	let storage = match fleet.average_storage_ratio {
+		Some(ratio) => format!("storage {:.0}% full", ratio * 100.0),
+		None => String::from("storage unknown"),
+	};
+	let nodes = match fleet.disabled {
+		0 => format!("{} nodes", fleet.nodes),
+		disabled => format!("{} nodes ({} disabled)", fleet.nodes, disabled),
+	};
+	let fields = [
+		nodes,
+		format!("{} disconnected", fleet.disconnected),
+		format!("{} stale", fleet.stale),
+		format!("{} errors/min", fleet.errors_per_minute),
+		storage,
+	];
+	let width = width as usize;
+	let mut text = String::new();
+	for field in fields.iter() {
+		let extended = if text.is_empty() { field.clone() } else { format!("{} | {}", text, field) };
+		if extended.chars().count() > width {
+			break;
+		}
+		text = extended;
+	}
+	if text.is_empty() {
+		text = fields[0].chars().take(width).collect();
+	}
+	text
+}
+
+// Marks window titles while log ingestion is paused
+fn paused_title(dash_state: &DashState) -> &'static str {
+	if dash_state.paused { " - PAUSED (press p to resume)" } else { "" }
+}
+
+// The metric focused on for window titles, empty when there is none
+fn focus_title(dash_state: &DashState) -> String {
+	match dash_state.focus_metric {
+		Some(metric) => format!(" - focus: {}", metric.name()),
+		None => String::new(),
+	}
+}
+
+// Describes the node filter, and any jump to a node being typed, for window
+// titles. Empty when neither
+fn filter_title(dash_state: &DashState) -> String {
+	let cursor = if dash_state.filter_editing { "_" } else { "" };
+	let mut title = String::new();
+	if !dash_state.node_filter.is_empty() || dash_state.filter_editing {
+		title = format!(" - filter: {}{}", dash_state.node_filter.get_text(), cursor);
+	}
+	if let Some(jump) = &dash_state.node_jump {
+		title = format!("{} - jump to: {}_", title, jump.typed());
+	}
+	title
+}
+
 fn draw_node_dash<B: Backend>(
 	f: &mut Frame<B>,
+	area: Rect,
 	dash_state: &mut DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
 	// Horizonatal bands:
 	let constraints = [
-		Constraint::Length(12), // Stats summary and graphs
-		Constraint::Length(18), // Timeline
+		Constraint::Length(20), // Stats summary and graphs
+		Constraint::Length(22), // Timeline
 		Constraint::Min(0),     // Bottom panel
 	];
 
-	let size = f.size();
+	let size = area;
 	let chunks = Layout::default()
 		.direction(Direction::Vertical)
 		.constraints(constraints.as_ref())
 		.split(size);
 
-	for entry in monitors.into_iter() {
-		let (logfile, mut monitor) = entry;
+	let average_storage = average_storage_ratio(monitors);
+	for monitor in monitors.values_mut() {
 		if monitor.has_focus {
 			// Stats and Graphs / Timeline / Logfile
-			draw_node(f, chunks[0], dash_state, &mut monitor);
-			draw_timeline(f, chunks[1], dash_state, &mut monitor);
-			draw_bottom_panel(f, chunks[2], dash_state, &logfile, &mut monitor);
+			draw_node(f, chunks[0], dash_state, monitor, average_storage);
+			draw_timeline(f, chunks[1], dash_state, monitor);
+			draw_bottom_panel(f, chunks[2], dash_state, monitor);
 			return;
 		}
 	}
@@ -60,69 +922,752 @@ fn draw_node_dash<B: Backend>(
 	draw_debug_window(f, size, dash_state);
 }
 
-fn draw_node<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor) {
-	// Columns:
-	let constraints = [
-		Constraint::Length(40), // Stats summary
-		Constraint::Min(10),    // Graphs
+///! The nodes marked for comparison side by side, with the timelines of each
+///! metric drawn to a scale shared by both so that they can be compared fairly.
+///! Falls back to the node view unless both marked nodes are being monitored
+fn draw_compare_dash<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitors: &mut HashMap<String, LogMonitor>,
+) {
+	let marked: Vec<String> = dash_state
+		.compare_nodes
+		.iter()
+		.filter(|logfile| monitors.contains_key(*logfile))
+		.cloned()
+		.collect();
+	if marked.len() < COMPARE_NODES {
+		draw_node_dash(f, area, dash_state, monitors);
+		return;
+	}
+
+	let active_timeline = dash_state.timelines.get(dash_state.active_timeline).map(|spec| spec.name.clone());
+	let maxima = {
+		let nodes: Vec<Vec<&[u64]>> = marked
+			.iter()
+			.map(|logfile| {
+				compare_timelines(&monitors[logfile].metrics)
+					.iter()
+					.map(|timeline| {
+						let bucket_set = active_timeline.as_ref().and_then(|name| timeline.bucket_sets().get(name));
+						bucket_set.map_or(&[][..], |bs| bs.buckets().as_slice())
+					})
+					.collect()
+			})
+			.collect();
+		shared_chart_maxima(&nodes)
+	};
+
+	let columns = panel_areas(area, &COMPARE_PANELS);
+	for (logfile, column) in marked.iter().zip(columns.iter()) {
+		let column = match column {
+			Some(column) => column,
+			None => continue,
+		};
+		let monitor = match monitors.get_mut(logfile) {
+			Some(monitor) => monitor,
+			None => continue,
+		};
+		let rows = Layout::default()
+			.direction(Direction::Vertical)
+			.constraints([Constraint::Length(1), Constraint::Length(20), Constraint::Min(0)].as_ref())
+			.split(*column);
+		let name = Span::styled(monitor.name.clone(), dash_state.theme.node_style(monitor.index));
+		f.render_widget(List::new(vec![ListItem::new(name)]), rows[0]);
+		draw_node_stats(f, rows[1], dash_state, monitor);
+
+		let theme = &dash_state.theme;
+		let styles = [theme.puts_sparkline, theme.gets_sparkline, theme.errors_sparkline, theme.peers_sparkline, theme.peers_sparkline];
+		let charts: Vec<Vec<TimelineSeries>> = compare_timelines(&monitor.metrics)
+			.iter()
+			.zip(styles.iter())
+			.map(|(timeline, style)| vec![(*timeline, timeline.get_name().as_str(), *style, Axis::Left)])
+			.collect();
+		draw_scaled_timelines(f, rows[2], dash_state, &charts, &maxima);
+	}
+}
+
+// The timelines shown for each node in the compare view, one chart each
+fn compare_timelines(metrics: &NodeMetrics) -> [&TimelineSet; 5] {
+	[
+		&metrics.puts_timeline,
+		&metrics.gets_timeline,
+		&metrics.errors_timeline,
+		&metrics.peers_timeline,
+		&metrics.storage_timeline,
+	]
+}
+
+// The largest value in each chart of any node, so that the same chart of
+// every node can be drawn to one scale. nodes[node][chart] holds the buckets
+fn shared_chart_maxima(nodes: &[Vec<&[u64]>]) -> Vec<u64> {
+	let charts = nodes.iter().map(|charts| charts.len()).max().unwrap_or(0);
+	(0..charts)
+		.map(|chart| {
+			nodes
+				.iter()
+				.filter_map(|charts| charts.get(chart))
+				.filter_map(|buckets| buckets.iter().max())
+				.max()
+				.copied()
+				.unwrap_or(0)
+		})
+		.collect()
+}
+
+///! Everything about the node with focus: its metrics, all of its timelines
+///! and the most recent lines of its logfile
+fn draw_detail<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitors: &mut HashMap<String, LogMonitor>,
+) {
+	// Horizontal bands: metrics and timelines / log tail, of the panels not hidden
+	let size = area;
+	let hidden = &dash_state.hidden_panels;
+	let has_latencies = monitors.values().any(|monitor| monitor.has_focus && !monitor.metrics.latencies.is_empty());
+	let metrics_shown = [
+		hidden.is_shown(PanelKind::Stats),
+		hidden.is_shown(PanelKind::Latency) && has_latencies,
+		hidden.is_shown(PanelKind::Resources),
 	];
+	let timelines_shown = [hidden.is_shown(PanelKind::Timelines), hidden.is_shown(PanelKind::Growth)];
+	let columns_shown = [metrics_shown.contains(&true), timelines_shown.contains(&true)];
+	let bands = visible_rows(
+		size,
+		&[(Constraint::Percentage(70), columns_shown.contains(&true)), (Constraint::Percentage(30), hidden.is_shown(PanelKind::Log))],
+	);
+	let columns = match bands[0] {
+		Some(band) => visible_panel_areas(band, &DETAIL_PANELS, &columns_shown),
+		None => vec![None; DETAIL_PANELS.len()],
+	};
+	let metrics_rows = columns[0].map(|column| {
+		let heights = [Constraint::Length(20), Constraint::Length(3), Constraint::Min(0)];
+		visible_rows(column, &[(heights[0], metrics_shown[0]), (heights[1], metrics_shown[1]), (heights[2], metrics_shown[2])])
+	});
+	let timelines_rows = columns[1].map(|column| {
+		visible_rows(
+			column,
+			&[(Constraint::Min(0), timelines_shown[0]), (Constraint::Length(STORAGE_GROWTH_HEIGHT), timelines_shown[1])],
+		)
+	});
 
-	let chunks = Layout::default()
-		.direction(Direction::Horizontal)
-		.constraints(constraints.as_ref())
-		.split(area);
+	let average_storage = average_storage_ratio(monitors);
+	for monitor in monitors.values_mut() {
+		if monitor.has_focus {
+			if let Some(metrics_rows) = &metrics_rows {
+				if let Some(stats_area) = metrics_rows[0] {
+					draw_node_stats(f, stats_area, dash_state, monitor);
+					emphasise_panel(f, stats_area, dash_state, PanelKind::Stats);
+				}
+				if let Some(latency_area) = metrics_rows[1] {
+					draw_node_latency(f, latency_area, dash_state, monitor);
+					emphasise_panel(f, latency_area, dash_state, PanelKind::Latency);
+				}
+				if let Some(storage_area) = metrics_rows[2] {
+					draw_node_storage(f, storage_area, dash_state, monitor, average_storage);
+					emphasise_panel(f, storage_area, dash_state, PanelKind::Resources);
+				}
+			}
+
+			let theme = &dash_state.theme;
+			let metrics = &monitor.metrics;
+			let puts = (&metrics.puts_timeline, "PUTS", theme.puts_sparkline, Axis::Left);
+			let gets = (&metrics.gets_timeline, "GETS", theme.gets_sparkline, Axis::Left);
+			let errors = (&metrics.errors_timeline, "ERRORS", theme.errors_sparkline, Axis::Left);
+			let warnings = (&metrics.warnings_timeline, "WARNINGS", theme.errors_sparkline, Axis::Left);
+			let peers = (&metrics.peers_timeline, "PEERS", theme.peers_sparkline, Axis::Left);
+			let storage = (&metrics.storage_timeline, "STORAGE %", theme.peers_sparkline, Axis::Left);
+			let records = (&metrics.records_timeline, "RECORDS", theme.puts_sparkline, Axis::Left);
+			let store_cost = (&metrics.store_cost_timeline, "STORE COST", theme.peers_sparkline, Axis::Left);
+			let bytes_in = (&metrics.bytes_in_timeline, "BYTES IN", theme.puts_sparkline, Axis::Left);
+			let bytes_out = (&metrics.bytes_out_timeline, "BYTES OUT", theme.gets_sparkline, Axis::Left);
+			let replications = (&metrics.replications_timeline, "REPLICATIONS", theme.puts_sparkline, Axis::Left);
+			let relocations = (&metrics.relocations_timeline, "RELOCATIONS", theme.errors_sparkline, Axis::Left);
+			let latency = (&metrics.latency_timeline, "LATENCY ms", theme.peers_sparkline, Axis::Left);
+			let rewards = (&metrics.rewards_timeline, REWARDS_TIMELINE, theme.gets_sparkline, Axis::Left);
+			let charts = if dash_state.overlay_timelines {
+				// Errors often rise as storage fills, so compare them on their own scale
+				let errors = (errors.0, errors.1, errors.2, Axis::Right);
+				let relocations = (relocations.0, relocations.1, relocations.2, Axis::Right);
+				vec![
+					vec![puts, gets], vec![storage, errors], vec![warnings], vec![peers], vec![records], vec![store_cost], vec![rewards],
+					vec![bytes_in, bytes_out], vec![replications, relocations], vec![latency],
+				]
+			} else {
+				vec![
+					vec![puts], vec![gets], vec![errors], vec![warnings], vec![peers], vec![records], vec![storage], vec![store_cost],
+					vec![rewards], vec![bytes_in], vec![bytes_out], vec![replications], vec![relocations], vec![latency],
+				]
+			};
+			if let Some(timelines_rows) = &timelines_rows {
+				if let Some(timelines_area) = timelines_rows[0] {
+					draw_timelines(f, timelines_area, dash_state, &charts);
+				}
+				if let Some(growth_area) = timelines_rows[1] {
+					draw_storage_growth(f, growth_area, dash_state, metrics);
+					emphasise_panel(f, growth_area, dash_state, PanelKind::Growth);
+				}
+			}
+			if let Some(log_area) = bands[1] {
+				draw_log_tail(f, log_area, dash_state, monitor);
+				emphasise_panel(f, log_area, dash_state, PanelKind::Log);
+			}
+			return;
+		}
+	}
+
+	draw_debug_window(f, size, dash_state);
+}
+
+// The min, mean and 95th percentile of the latencies the node has logged, if any
+fn draw_node_latency<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitor: &LogMonitor) {
+	let latencies = &monitor.metrics.latencies;
+	let stats = match latencies.stats() {
+		Some(stats) => stats,
+		None => return,
+	};
+	let title = format!("Latency - last {} samples", latencies.len());
+	let mmm = MinMeanMax::default()
+		.block(Block::default().borders(Borders::ALL).title(title))
+		.given_figures([
+			("min", format_latency(stats.min as f64)),
+			("mean", format_latency(stats.mean)),
+			("p95", format_latency(stats.p95 as f64)),
+		])
+		.style(dash_state.theme.text)
+		.label_style(dash_state.theme.header);
+	f.render_widget(mmm, area);
+}
+
+// The most recent raw lines of a node's logfile, newest at the bottom unless scrolled up
+fn draw_log_tail<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitor: &LogMonitor) {
+	let theme = &dash_state.theme;
+	let rows = area.height.saturating_sub(2) as usize;
+	let spotlight = dash_state.spotlight.lock().unwrap();
+	let items: Vec<ListItem> = monitor
+		.tail
+		.visible(rows)
+		.map(|line| {
+			let style = match spotlight.keyword_in(line) {
+				Some(keyword) => severity_style(theme, keyword.severity).add_modifier(Modifier::REVERSED),
+				None => theme.logfile_text,
+			};
+			ListItem::new(ansi_spans(line, style))
+		})
+		.collect();
+	let mut title = format!("Node {:>2} Log - last {} lines ({})", monitor.index + 1, monitor.tail.len(), monitor.name);
+	if !monitor.tail.is_following() {
+		title.push_str(&format!(" - not following, {} new lines ('f' to follow)", monitor.tail.new_lines()));
+	}
+	let widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(widget, area);
+}
+
+fn draw_warnings<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState) {
+	let theme = &dash_state.theme;
+	let area = if dash_state.spotlight.lock().unwrap().has_keywords() {
+		let chunks = Layout::default()
+			.direction(Direction::Vertical)
+			.constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+			.split(area);
+		draw_spotlight(f, chunks[0], dash_state);
+		chunks[1]
+	} else {
+		area
+	};
+	let recent_warnings = dash_state.recent_warnings.lock().unwrap();
+	let rows = area.height.saturating_sub(2) as usize;
+	let items: Vec<ListItem> = recent_warnings
+		.visible(rows)
+		.map(|entry| {
+			let style = if entry.level == LogLevel::Error { theme.error_text } else { theme.header };
+			ListItem::new(Spans::from(Span::styled(entry.describe(), style)))
+		})
+		.collect();
+	let mut title = format!("Warnings and Errors - last {} of all nodes", recent_warnings.len());
+	if recent_warnings.scroll() > 0 {
+		title.push_str(&format!(" - {} newer below", recent_warnings.scroll()));
+	}
+	let widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(widget, area);
+}
+
+// The newest lines matching --spotlight keywords, coloured by severity
+fn draw_spotlight<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState) {
+	let spotlight = dash_state.spotlight.lock().unwrap();
+	let rows = area.height.saturating_sub(2) as usize;
+	let items: Vec<ListItem> = spotlight
+		.iter()
+		.skip(spotlight.len().saturating_sub(rows))
+		.map(|entry| ListItem::new(Spans::from(Span::styled(entry.describe(), severity_style(&dash_state.theme, entry.severity)))))
+		.collect();
+	let title = format!("Spotlight - last {} lines of all nodes matching --spotlight keywords", spotlight.len());
+	let widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(widget, area);
+}
+
+fn draw_diagnostics<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState) {
+	let diagnostics = dash_state.diagnostics.lock().unwrap();
+	let rows = area.height.saturating_sub(2) as usize;
+	let items: Vec<ListItem> = diagnostics
+		.iter()
+		.skip(diagnostics.len().saturating_sub(rows))
+		.map(|diagnostic| ListItem::new(Spans::from(diagnostic.describe())).style(dash_state.theme.text))
+		.collect();
+	let title = format!("Diagnostics - last {} lines vdash couldn't parse", diagnostics.len());
+	let widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(widget, area);
+}
+
+// Widest node name shown by the heatmap, leaving the rest for its cells
+const HEATMAP_NAME_WIDTH: usize = 20;
+
+// The --heatmap-metric of the nodes listed in the summary, in its order, a
+// column for each of the newest buckets of the timeline being shown
+fn draw_heatmap<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitors: &HashMap<String, LogMonitor>) {
+	let theme = &dash_state.theme;
+	let now = chrono::Utc::now();
+	let mut summaries: Vec<NodeSummary> = monitors
+		.values()
+		.filter(|monitor| !monitor.is_debug_dashboard_log)
+		.filter(|monitor| dash_state.node_filter.matches_node(monitor))
+		.map(|monitor| NodeSummary::from_monitor(monitor, dash_state, &now))
+		.collect();
+	sort_node_summaries(&mut summaries, dash_state.sort_key, dash_state.sort_ascending);
+	let active_timeline = match dash_state.timelines.get(dash_state.active_timeline) {
+		Some(spec) => spec.name.clone(),
+		None => return,
+	};
+
+	let inner = Block::default().borders(Borders::ALL).inner(area);
+	let name_width = summaries.iter().map(|summary| summary.name.chars().count()).max().unwrap_or(0).min(HEATMAP_NAME_WIDTH);
+	let columns = (inner.width as usize).saturating_sub(name_width + 1);
+	let rows: Vec<Vec<Option<u64>>> = summaries
+		.iter()
+		.map(|summary| {
+			monitors
+				.get(&summary.logfile)
+				.and_then(|monitor| monitor.metrics.timeline_named(&dash_state.heatmap_metric))
+				.and_then(|timeline| timeline.bucket_sets().get(&active_timeline))
+				.map_or_else(Vec::new, |bucket_set| heat_cells(bucket_set, columns))
+		})
+		.collect();
+	let max = heat_max(&rows);
+
+	let lines: Vec<Spans> = summaries
+		.iter()
+		.zip(rows.iter())
+		.map(|(summary, cells)| {
+			let name: String = summary.name.chars().take(name_width).collect();
+			let mut spans = vec![Span::styled(format!("{:<width$} ", name, width = name_width), theme.text)];
+			spans.push(Span::raw(" ".repeat(columns - cells.len()))); // Older than any bucket kept
+			for cell in cells {
+				spans.push(match cell {
+					Some(value) => {
+						let level = heat_level(*value, max);
+						Span::styled(HEAT_GLYPHS[level], heat_style(theme, level))
+					}
+					None => Span::raw(" "),
+				});
+			}
+			Spans::from(spans)
+		})
+		.collect();
+	let title = format!(
+		"Heatmap - {} of {} nodes, {}, largest {}{}{}",
+		dash_state.heatmap_metric,
+		summaries.len(),
+		active_timeline,
+		dash_state.number_format.format_count(max),
+		filter_title(dash_state),
+		paused_title(dash_state)
+	);
+	let widget = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(widget, area);
+}
+
+// Average chunk store fill across nodes with a known capacity, None unless there are several
+fn average_storage_ratio(monitors: &HashMap<String, LogMonitor>) -> Option<f64> {
+	let ratios: Vec<f64> = monitors
+		.values()
+		.filter(|monitor| !monitor.is_debug_dashboard_log && monitor.enabled)
+		.filter_map(|monitor| monitor.metrics.storage_ratio())
+		.collect();
+
+	if ratios.len() < 2 {
+		return None;
+	}
+	Some(ratios.iter().sum::<f64>() / ratios.len() as f64)
+}
+
+fn draw_node<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor, average_storage: Option<f64>) {
+	// Columns, or rows on a narrow terminal: stats summary / graphs, of those not hidden
+	let hidden = &dash_state.hidden_panels;
+	let areas = visible_panel_areas(area, &NODE_PANELS, &[hidden.is_shown(PanelKind::Stats), hidden.is_shown(PanelKind::Resources)]);
+	if let Some(stats_area) = areas[0] {
+		draw_node_stats(f, stats_area, dash_state, monitor);
+		emphasise_panel(f, stats_area, dash_state, PanelKind::Stats);
+	}
+	if let Some(storage_area) = areas[1] {
+		draw_node_storage(f, storage_area, dash_state, monitor, average_storage);
+		emphasise_panel(f, storage_area, dash_state, PanelKind::Resources);
+	}
+}
+
+// Brighten or dim a panel once drawn, for the metric focused on if any
+fn emphasise_panel<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, panel: PanelKind) {
+	emphasise(f, area, panel_emphasis(dash_state.focus_metric, panel));
+}
+
+fn emphasise<B: Backend>(f: &mut Frame<B>, area: Rect, emphasis: Emphasis) {
+	if let Some(style) = emphasis_style(emphasis) {
+		f.render_widget(ChangedCells(vec![(area, style)]), area);
+	}
+}
+
+///! The narrowest a panel of graphs or timelines can usefully be
+const MIN_PANEL_WIDTH: u16 = 30;
+
+///! The width of the node stats, which is also as narrow as they can be
+const NODE_STATS_WIDTH: u16 = 40;
+
+///! A panel drawn beside the others if there is room, or else above or below
+///! them. The least important are hidden if there isn't room for them stacked
+#[derive(Clone, Copy, Debug)]
+struct Panel {
+	width: Constraint, // When side by side
+	min_width: u16,
+	min_height: u16,
+	importance: u8,
+}
+
+const NODE_PANELS: [Panel; 2] = [
+	Panel { width: Constraint::Length(NODE_STATS_WIDTH), min_width: NODE_STATS_WIDTH, min_height: 20, importance: 1 },
+	Panel { width: Constraint::Min(MIN_PANEL_WIDTH), min_width: MIN_PANEL_WIDTH, min_height: 8, importance: 0 },
+];
+
+// Node stats and resources / timelines
+const DETAIL_PANELS: [Panel; 2] = [
+	Panel { width: Constraint::Length(NODE_STATS_WIDTH), min_width: NODE_STATS_WIDTH, min_height: 20, importance: 1 },
+	Panel { width: Constraint::Min(MIN_PANEL_WIDTH), min_width: MIN_PANEL_WIDTH, min_height: 10, importance: 0 },
+];
 
-	draw_node_stats(f, chunks[0], monitor);
-	draw_node_storage(f, chunks[1], dash_state, monitor);
+// One per node compared: name, stats and timelines
+const COMPARE_PANELS: [Panel; COMPARE_NODES] = [
+	Panel { width: Constraint::Ratio(1, 2), min_width: NODE_STATS_WIDTH, min_height: 31, importance: 0 },
+	Panel { width: Constraint::Ratio(1, 2), min_width: NODE_STATS_WIDTH, min_height: 31, importance: 0 },
+];
+
+///! The area of each panel, side by side if the area is at least as wide as
+///! their minimum widths, or else stacked in order. Stacked panels are shown
+///! most important first (earliest first when as important) while there is
+///! height for them, leaving None for those hidden. The most important is
+///! always shown
+fn panel_areas(area: Rect, panels: &[Panel]) -> Vec<Option<Rect>> {
+	let min_width: u32 = panels.iter().map(|panel| panel.min_width as u32).sum();
+	if area.width as u32 >= min_width {
+		let constraints: Vec<Constraint> = panels.iter().map(|panel| panel.width).collect();
+		return Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints(constraints)
+			.split(area)
+			.into_iter()
+			.map(Some)
+			.collect();
+	}
+
+	let mut by_importance: Vec<usize> = (0..panels.len()).collect();
+	by_importance.sort_by_key(|i| std::cmp::Reverse(panels[*i].importance));
+	let mut shown = vec![false; panels.len()];
+	let mut height = 0u32;
+	for (n, i) in by_importance.into_iter().enumerate() {
+		height += panels[i].min_height as u32;
+		if n > 0 && height > area.height as u32 {
+			break;
+		}
+		shown[i] = true;
+	}
+
+	let last_shown = shown.iter().rposition(|shown| *shown);
+	let constraints: Vec<Constraint> = (0..panels.len())
+		.filter(|i| shown[*i])
+		.map(|i| {
+			if Some(i) == last_shown {
+				Constraint::Min(panels[i].min_height)
+			} else {
+				Constraint::Length(panels[i].min_height)
+			}
+		})
+		.collect();
+	let mut areas = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(constraints)
+		.split(area)
+		.into_iter();
+	shown.into_iter().map(|shown| if shown { areas.next() } else { None }).collect()
 }
 
-fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMonitor) {
+///! The areas of the panels shown, laid out by panel_areas() as if the others
+///! didn't exist, and None for those not shown
+fn visible_panel_areas(area: Rect, panels: &[Panel], shown: &[bool]) -> Vec<Option<Rect>> {
+	let visible: Vec<Panel> = panels.iter().zip(shown).filter(|(_panel, shown)| **shown).map(|(panel, _shown)| *panel).collect();
+	if visible.is_empty() {
+		return vec![None; panels.len()];
+	}
+	let mut areas = panel_areas(area, &visible).into_iter();
+	shown.iter().map(|shown| if *shown { areas.next().flatten() } else { None }).collect()
+}
+
+///! Split an area into rows of the heights given, of only those rows shown.
+///! The last row shown takes any height left over
+fn visible_rows(area: Rect, rows: &[(Constraint, bool)]) -> Vec<Option<Rect>> {
+	let constraints: Vec<Constraint> = rows.iter().filter(|(_height, shown)| *shown).map(|(height, _shown)| *height).collect();
+	if constraints.is_empty() {
+		return vec![None; rows.len()];
+	}
+	let mut areas = Layout::default().direction(Direction::Vertical).constraints(constraints).split(area).into_iter();
+	rows.iter().map(|(_height, shown)| if *shown { areas.next() } else { None }).collect()
+}
+
+fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitor: &mut LogMonitor) {
 	// TODO maybe add items to monitor.metrics_status and make items from that as in draw_logfile()
-	let mut items = Vec::<ListItem>::new();
-	push_subheading(&mut items, &"Node".to_string());
+	let theme = &dash_state.theme;
+	let now = chrono::Utc::now();
+	let items: Vec<ListItem> = node_stats(dash_state, monitor, &now).into_iter().map(|stat| node_stat_item(theme, stat)).collect();
+	let heading = node_stats_heading(dash_state, monitor, &now);
+	let mut block = Block::default().borders(Borders::ALL);
+	if monitor.metrics.is_stale(&now, dash_state.stale_after) || !monitor.metrics.connected {
+		block = block
+			.title(Span::styled(heading, theme.error_text))
+			.border_style(theme.error_text);
+	} else {
+		block = block.title(heading);
+	}
+	let monitor_widget = List::new(items).block(block);
+	f.render_stateful_widget(monitor_widget, area, &mut monitor.metrics_status.state);
+}
+
+///! A node's status as text, line by line as drawn in its panel, for the
+///! copy-metrics key
+pub fn node_stats_text(dash_state: &DashState, monitor: &LogMonitor, now: &chrono::DateTime<chrono::Utc>) -> String {
+	let mut lines = vec![node_stats_heading(dash_state, monitor, now)];
+	for stat in node_stats(dash_state, monitor, now) {
+		match stat {
+			NodeStat::Subheading(subheading) if subheading.is_empty() => {}
+			NodeStat::Subheading(subheading) => lines.push(subheading),
+			NodeStat::Metric(metric, value) | NodeStat::Warning(metric, value) => {
+				lines.push(format_metric(&metric, &value).trim_end().to_string())
+			}
+		}
+	}
+	lines.join("\n") + "\n"
+}
+
+///! The lines of a node's status, drawn in its panel and copied as text by the
+///! copy-metrics key
+pub fn node_stats(dash_state: &DashState, monitor: &LogMonitor, now: &chrono::DateTime<chrono::Utc>) -> Vec<NodeStat> {
+	let now = *now;
+	let mut stats = Vec::<NodeStat>::new();
+	push_subheading(&mut stats, &"Node".to_string());
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"Role".to_string(),
 		&monitor.metrics.agebracket_string(),
 	);
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"Age".to_string(),
 		&monitor.metrics.node_age.to_string()
 	);
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"Name".to_string(),
 		&monitor.metrics.node_name,
 	);
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"Section".to_string(),
 		&monitor.metrics.section_prefix,
 	);
+	push_metric(
+		&mut stats,
+		&"Version".to_string(),
+		&version_status(monitor.metrics.running_version.as_deref(), monitor.metrics.restarts),
+	);
+	push_metric(
+		&mut stats,
+		&"PeerId".to_string(),
+		&monitor.metrics.peer_id.as_ref().map_or(String::from("unknown"), |peer_id| abbreviate_peer_id(peer_id)),
+	);
+	push_metric(
+		&mut stats,
+		&"Listening".to_string(),
+		&monitor.metrics.listen_address.map_or(String::from("unknown"), |address| address.to_string()),
+	);
+	if let Some(endpoint) = &monitor.metrics_endpoint {
+		match &endpoint.error {
+			Some(error) => push_warning_metric(&mut stats, &"Metrics from".to_string(), &format!("{} ({})", endpoint.url, error)),
+			None => push_metric(&mut stats, &"Metrics from".to_string(), &endpoint.url),
+		}
+	}
+
+	push_metric(&mut stats, &"Uptime".to_string(), &format_uptime(monitor.metrics.uptime(&now)));
+	let is_stale = monitor.metrics.is_stale(&now, dash_state.stale_after);
+	let last_line = match monitor.metrics.time_since_activity(&now) {
+		Some(elapsed) => format!("{} ago", format_elapsed(elapsed)),
+		None => String::from("never"),
+	};
+	if is_stale {
+		push_warning_metric(&mut stats, &"Last line".to_string(), &last_line);
+	} else {
+		push_metric(&mut stats, &"Last line".to_string(), &last_line);
+	}
 
-	push_subheading(&mut items, &"".to_string());
+	let metrics = &monitor.metrics;
+	let counter_view = dash_state.counter_view;
+	let show_rates = counter_view == CounterView::Rates;
+	let totals = if counter_view == CounterView::CurrentRun { metrics.current_run() } else { metrics.lifetime() };
+	// The total since start (or this run), or the rate over the latest complete bucket of its timeline
+	let number_format = &dash_state.number_format;
+	let count = |count: u64| number_format.format_count(count);
+	let count_rate = |rate: f64| format_count_rate(number_format, rate);
+	let bytes_rate = |rate: f64| format_bytes_rate(number_format, rate);
+	let counter = |total: String, timeline: &TimelineSet, format_value: &dyn Fn(f64) -> String| {
+		if show_rates {
+			format_rate(timeline.completed_rate_per_minute(), format_value)
+		} else {
+			total
+		}
+	};
+	// With a marker, totals are followed by what they have added since it, e.g. '5,200 +1,240'
+	let since_marker = if show_rates { None } else { metrics.since_marker() };
+	let with_added = |total: String, added: Option<String>| match added {
+		Some(added) => format!("{} +{}", total, added),
+		None => total,
+	};
+	let subheading = match counter_view {
+		CounterView::Lifetime => "",
+		CounterView::CurrentRun => "This run",
+		CounterView::Rates => "Rates",
+	};
+	let subheading = match (&metrics.marker, since_marker) {
+		(Some(marker), Some(_since)) => {
+			let marked = format!("+ since marker {} ago", format_elapsed(now - marker.dropped));
+			if subheading.is_empty() { marked } else { format!("{}, {}", subheading, marked) }
+		}
+		_ => String::from(subheading),
+	};
+	push_subheading(&mut stats, &subheading);
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"GETS".to_string(),
-		&monitor.metrics.activity_gets.to_string(),
+		&counter(with_added(count(totals.gets), since_marker.map(|since| count(since.gets))), &metrics.gets_timeline, &count_rate),
 	);
 
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"PUTS".to_string(),
-		&monitor.metrics.activity_puts.to_string(),
+		&counter(with_added(count(totals.puts), since_marker.map(|since| count(since.puts))), &metrics.puts_timeline, &count_rate),
 	);
 
 	push_metric(
-		&mut items,
+		&mut stats,
 		&"ERRORS".to_string(),
-		&monitor.metrics.activity_errors.to_string(),
+		&counter(
+			with_added(
+				format!("{} ({}/min)", count(totals.errors), count(metrics.errors_per_minute())),
+				since_marker.map(|since| count(since.errors)),
+			),
+			&metrics.errors_timeline,
+			&count_rate,
+		),
+	);
+
+	push_metric(
+		&mut stats,
+		&"WARNINGS".to_string(),
+		&counter(
+			with_added(
+				format!("{} ({}/min)", count(totals.warnings), count(metrics.warnings_per_minute())),
+				since_marker.map(|since| count(since.warnings)),
+			),
+			&metrics.warnings_timeline,
+			&count_rate,
+		),
+	);
+
+	push_metric(
+		&mut stats,
+		&"REWARDS".to_string(),
+		&with_added(format_rewards(number_format, totals.rewards), since_marker.map(|since| format_rewards(number_format, since.rewards))),
+	);
+
+	push_metric(
+		&mut stats,
+		&"STORE COST".to_string(),
+		&metrics.store_cost.map_or(String::from("unknown"), |attos| format_rewards(number_format, attos as u128)),
+	);
+
+	push_metric(
+		&mut stats,
+		&"PEERS".to_string(),
+		&count(metrics.peers),
+	);
+
+	push_metric(
+		&mut stats,
+		&"RECORDS".to_string(),
+		&metrics.records_stored.map_or(String::from("unknown"), count),
 	);
 
-	push_subheading(&mut items, &"".to_string());
+	push_metric(
+		&mut stats,
+		&"REPLICATIONS".to_string(),
+		&counter(
+			format!("{} ({} in, {} out)", count(metrics.replications), count(metrics.replications_in), count(metrics.replications_out)),
+			&metrics.replications_timeline,
+			&count_rate,
+		),
+	);
+
+	push_metric(
+		&mut stats,
+		&"RELOCATIONS".to_string(),
+		&counter(count(metrics.relocations), &metrics.relocations_timeline, &count_rate),
+	);
+
+	push_metric(
+		&mut stats,
+		&"BYTES IN".to_string(),
+		&counter(
+			format!("{} ({}/min)", format_bytes(number_format, totals.bytes_in), format_bytes(number_format, metrics.bytes_in_per_minute())),
+			&metrics.bytes_in_timeline,
+			&bytes_rate,
+		),
+	);
+
+	push_metric(
+		&mut stats,
+		&"BYTES OUT".to_string(),
+		&counter(
+			format!("{} ({}/min)", format_bytes(number_format, totals.bytes_out), format_bytes(number_format, metrics.bytes_out_per_minute())),
+			&metrics.bytes_out_timeline,
+			&bytes_rate,
+		),
+	);
+
+	let network = network_status(monitor.metrics.connected, monitor.metrics.disconnects);
+	if monitor.metrics.connected {
+		push_metric(&mut stats, &"Network".to_string(), &network);
+	} else {
+		push_warning_metric(&mut stats, &"Network".to_string(), &network);
+	}
+
 	// TODO re-instate when available
-	// push_subheading(&mut items, &"Network".to_string());
+	// push_subheading(&mut stats, &"Network".to_string());
 	// push_metric(
 	// 	&mut items,
 	// 	&"Elders".to_string(),
@@ -133,39 +1678,225 @@ fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMo
 	// 	&"Adults".to_string(),
 	// 	&monitor.metrics.elders.to_string(),
 	// );
+	stats
+}
 
-	let heading = format!("Node {:>2} Status", monitor.index + 1);
-	let monitor_widget = List::new(items).block(
-		Block::default()
-			.borders(Borders::ALL)
-			.title(heading.to_string()),
+///! The title of a node's status panel, e.g. 'Node  1 Status (stale)'
+fn node_stats_heading(dash_state: &DashState, monitor: &LogMonitor, now: &chrono::DateTime<chrono::Utc>) -> String {
+	let mut heading = format!(
+		"Node {:>2} Status{}{}{}",
+		monitor.index + 1,
+		filter_title(dash_state),
+		focus_title(dash_state),
+		paused_title(dash_state)
 	);
-	f.render_stateful_widget(monitor_widget, area, &mut monitor.metrics_status.state);
+	if let Some(reset_time) = monitor.metrics.counters_reset {
+		if *now - reset_time < chrono::Duration::seconds(RESET_NOTICE_SECONDS) {
+			heading = format!("{} (counters reset)", heading);
+		}
+	}
+	if monitor.input_ended {
+		heading = format!("{} (input ended)", heading);
+	}
+	if monitor.waiting_for_file {
+		heading = format!("{} (waiting for file)", heading);
+	}
+	if !monitor.enabled {
+		heading = format!("{} (disabled)", heading);
+	}
+	if let Some(reason) = &monitor.read_error {
+		heading = format!("{} (error: {})", heading, reason);
+	}
+	if dash_state.main_view != DashViewMain::DashCompare && dash_state.compare_nodes.contains(&monitor.logfile) {
+		heading = format!("{} (marked)", heading);
+	}
+	if !monitor.metrics.connected {
+		heading = format!("{} (DISCONNECTED)", heading);
+	}
+	if monitor.metrics.is_stale(now, dash_state.stale_after) {
+		heading = format!("{} (stale)", heading);
+	}
+	heading
 }
 
-fn push_subheading(items: &mut Vec<ListItem>, subheading: &String) {
-	items.push(
-		ListItem::new(vec![Spans::from(subheading.clone())])
-			.style(Style::default().fg(Color::Yellow)),
-	);
+// Seconds to show that a node's counters have been reset
+const RESET_NOTICE_SECONDS: i64 = 3;
+
+///! A line of a node's status
+#[derive(Debug, PartialEq)]
+pub enum NodeStat {
+	Subheading(String),
+	Metric(String, String),
+	Warning(String, String), // A metric drawn as an error
 }
 
-fn push_metric(items: &mut Vec<ListItem>, metric: &String, value: &String) {
-	let s = format!("{:<12}: {:>12}", metric, value);
-	items.push(
-		ListItem::new(vec![Spans::from(s.clone())])
-			.style(Style::default().fg(Color::Blue)),
+fn push_subheading(stats: &mut Vec<NodeStat>, subheading: &String) {
+	stats.push(NodeStat::Subheading(subheading.clone()));
+}
+
+fn push_metric(stats: &mut Vec<NodeStat>, metric: &String, value: &String) {
+	stats.push(NodeStat::Metric(metric.clone(), value.clone()));
+}
+
+fn push_warning_metric(stats: &mut Vec<NodeStat>, metric: &String, value: &String) {
+	stats.push(NodeStat::Warning(metric.clone(), value.clone()));
+}
+
+// e.g. 'PUTS        :        5,200'
+fn format_metric(metric: &str, value: &str) -> String {
+	format!("{:<12}: {:>12}", metric, value)
+}
+
+fn node_stat_item(theme: &Theme, stat: NodeStat) -> ListItem<'static> {
+	match stat {
+		NodeStat::Subheading(subheading) => ListItem::new(vec![Spans::from(subheading)]).style(theme.header),
+		NodeStat::Metric(metric, value) => ListItem::new(vec![Spans::from(format_metric(&metric, &value))]).style(theme.text),
+		NodeStat::Warning(metric, value) => ListItem::new(vec![Spans::from(format_metric(&metric, &value))]).style(theme.error_text),
+	}
+}
+
+// e.g. '0.110.0', with the number of restarts if any
+fn version_status(version: Option<&str>, restarts: u64) -> String {
+	let version = version.unwrap_or("unknown");
+	match restarts {
+		0 => version.to_string(),
+		1 => format!("{} (1 restart)", version),
+		_ => format!("{} ({} restarts)", version, restarts),
+	}
+}
+
+// e.g. 'connected' or 'DISCONNECTED', with the number of disconnections if any
+fn network_status(connected: bool, disconnects: u64) -> String {
+	let status = if connected { "connected" } else { "DISCONNECTED" };
+	match disconnects {
+		0 => status.to_string(),
+		1 => format!("{} (1 drop)", status),
+		_ => format!("{} ({} drops)", status, disconnects),
+	}
+}
+
+// Return a short duration such as '45s', '3m 20s', '2h 5m' or '3d 4h'
+pub fn format_elapsed(elapsed: chrono::Duration) -> String {
+	let seconds = elapsed.num_seconds().max(0);
+	if seconds < 60 {
+		format!("{}s", seconds)
+	} else if seconds < 60 * 60 {
+		format!("{}m {}s", seconds / 60, seconds % 60)
+	} else if seconds < 24 * 60 * 60 {
+		format!("{}h {}m", seconds / (60 * 60), seconds % (60 * 60) / 60)
+	} else {
+		format!("{}d {}h", seconds / (24 * 60 * 60), seconds % (24 * 60 * 60) / (60 * 60))
+	}
+}
+
+// E.g. 'up 3d 4h', or '—' until the node's startup banner is seen
+pub fn format_uptime(uptime: Option<chrono::Duration>) -> String {
+	match uptime {
+		Some(uptime) => format!("up {}", format_elapsed(uptime)),
+		None => String::from("—"),
+	}
+}
+
+// The time covered by a timeline, in years or weeks once it spans two of them
+fn format_window(window: chrono::Duration) -> String {
+	let days = window.num_days();
+	if days >= 2 * 365 {
+		format!("{} years", days / 365)
+	} else if days >= 2 * 7 {
+		format!("{} weeks", days / 7)
+	} else {
+		format_elapsed(window)
+	}
+}
+
+// Borders, title, a chart with two lines each side of zero, and its time axis
+const STORAGE_GROWTH_HEIGHT: u16 = 8;
+
+// How fast storage used grew into each column of the active timeline, above
+// zero, or shrank, below it, so that plateaus and surges stand out
+fn draw_storage_growth<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, metrics: &NodeMetrics) {
+	let bucket_set = dash_state
+		.timelines
+		.get(dash_state.active_timeline)
+		.and_then(|spec| metrics.used_space_timeline.bucket_sets().get(&spec.name));
+	let bucket_set = match bucket_set {
+		Some(bucket_set) => bucket_set,
+		None => return,
+	};
+	let block = Block::default().borders(Borders::ALL);
+	let inner = block.inner(area);
+	let factor = downsample_factor(bucket_set.buckets().len(), inner.width as usize);
+	let column_duration = bucket_set.bucket_duration * factor as i32;
+	let columns = downsample(bucket_set.buckets(), factor, dash_state.downsample);
+	let rates = storage_growth_per_minute(&columns, column_duration);
+	let number_format = &dash_state.number_format;
+	let title = format!(
+		"STORAGE GROWTH per minute (max {}, min {})",
+		format_signed_size(number_format, rates.iter().max().copied().unwrap_or(0)),
+		format_signed_size(number_format, rates.iter().min().copied().unwrap_or(0)),
 	);
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+		.split(inner);
+	let theme = &dash_state.theme;
+	let sparkline = SignedSparkline::default()
+		.block(Block::default().title(title))
+		.data(&rates)
+		.style(theme.puts_sparkline)
+		.negative_style(theme.errors_sparkline)
+		.bar_set(dash_state.spark_style.bar_set());
+	f.render_widget(sparkline, rows[0]);
+	let axis = timeline_axis(column_duration, rates.len(), rows[1].width);
+	f.render_widget(List::new(vec![ListItem::new(Span::styled(axis, theme.text))]), rows[1]);
+	f.render_widget(block, area);
+}
+
+// e.g. '+1.2 MB', '-300 B' or '0 B'
+fn format_signed_size(number_format: &NumberFormat, bytes: i64) -> String {
+	let sign = match bytes.signum() {
+		1 => "+",
+		-1 => "-",
+		_ => "",
+	};
+	format!("{}{}", sign, format_size(number_format, bytes.unsigned_abs()))
+}
+
+// When the node's storage will be full, from its trend over the active timeline
+fn storage_projection(dash_state: &DashState, metrics: &NodeMetrics) -> StorageProjection {
+	let bucket_set = dash_state
+		.timelines
+		.get(dash_state.active_timeline)
+		.and_then(|spec| metrics.used_space_timeline.bucket_sets().get(&spec.name));
+	match bucket_set {
+		Some(bucket_set) => project_storage(bucket_set.buckets(), bucket_set.bucket_duration, metrics.capacity()),
+		None => StorageProjection::Unknown,
+	}
+}
+
+fn format_projection(projection: StorageProjection) -> String {
+	match projection {
+		StorageProjection::Unknown => String::from("—"),
+		StorageProjection::Full => String::from("full"),
+		StorageProjection::FullIn(duration) => format_elapsed(duration),
+		StorageProjection::Stable => String::from("stable"),
+		StorageProjection::Declining => String::from("declining"),
+	}
 }
 
 // TODO split into two sub functions, one for gauges, one for text strings
-fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut DashState, monitor: &mut LogMonitor) {
-	let used_string = format_size(monitor.metrics.used_space, 1);
-	let max_string = format_size(monitor.metrics.max_capacity, 1);
+fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor, average_storage: Option<f64>) {
+	let theme = &dash_state.theme;
+	let number_format = &dash_state.number_format;
+	let used_string = format_size(number_format, monitor.metrics.used_space);
+	let max_string = match monitor.metrics.capacity() {
+		Some(capacity) => format_size(number_format, capacity),
+		None => String::from("unknown"),
+	};
 	let device_limit_string = match &monitor.chunk_store_fsstats {
 		Some(fsstats) => {
 			let chunk_store_limit = fsstats.free_space();
-			format_size(chunk_store_limit, 1).to_string()
+			format_size(number_format, chunk_store_limit)
 		},
 		None => {
 			"unknown".to_string()
@@ -179,11 +1910,7 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 				.borders(Borders::ALL)
 				.title(heading.clone()),
 		)
-		.highlight_style(
-			Style::default()
-				.bg(Color::LightGreen)
-				.add_modifier(Modifier::BOLD),
-		);
+		.highlight_style(theme.selected_row);
 	f.render_stateful_widget(monitor_widget, area, &mut monitor.content.state);
 
 	// Two rows top=gauges / bottom=text
@@ -213,40 +1940,61 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 		.split(rows[0]);
 
 	let mut label_items = Vec::<ListItem>::new();
-	push_storage_subheading(&mut label_items, &"Chunks".to_string());
-	let mut gauges_column = columns[1];
-	gauges_column.height = 1;
+	push_storage_subheading(&mut label_items, theme, &"Chunks".to_string());
 
 	// One gauge gap for heading, and an extra gauge so the last one drawn doesn't expand to the bottom
 	let constraints = vec![Constraint::Length(1); 1 + 2];
 	let gauges = Layout::default()
 		.direction(Direction::Vertical)
-		.constraints(constraints.as_ref())
+		.constraints(constraints.as_slice())
 		.split(columns[1]);
 
-	push_storage_metric(
-		&mut label_items,
-		&"Chunk storage".to_string(),
-		&format_size(monitor.metrics.used_space, 1)
-	);
+	let reading = primary_gauge_reading(dash_state.primary_gauge, &GaugeInputs::of(&monitor.metrics), dash_state);
+	push_storage_metric(&mut label_items, theme, &reading.name.to_string(), &reading.value);
 
+	// Full is bad for storage and errors, but good for peers
+	let (thresholds, gradient) = if reading.full_is_good {
+		(
+			vec![(0.0, theme.gauge_critical), (0.25, theme.gauge_warning), (0.5, theme.gauge_fill)],
+			(theme.gauge_critical, theme.gauge_fill),
+		)
+	} else {
+		(vec![(0.75, theme.gauge_warning), (0.9, theme.gauge_critical)], (theme.gauge_fill, theme.gauge_critical))
+	};
+	let gauge_label = reading.label;
 	let gauge = Gauge2::default()
 		.block(Block::default())
-		.gauge_style(Style::default().fg(Color::Yellow))
-		.ratio(ratio(monitor.metrics.used_space, monitor.metrics.max_capacity));
+		.style(blinking_style(theme.gauge_empty, dash_state, &monitor.name))
+		.gauge_style(theme.gauge_fill)
+		.thresholds(thresholds)
+		.gradient(gradient.0.fg.unwrap_or(Color::Reset), gradient.1.fg.unwrap_or(Color::Reset))
+		.label_fn(move |_| gauge_label.clone())
+		.marker(if dash_state.primary_gauge == PrimaryGauge::Storage { average_storage } else { None })
+		.marker_style(theme.gauge_marker)
+		.monochrome(theme.monochrome)
+		.indeterminate(reading.ratio.is_none())
+		.ratio_clamped(reading.ratio.unwrap_or(0.0));
 	f.render_widget(gauge, gauges[1]);
 
-	push_storage_subheading(&mut label_items, &"".to_string());
-	push_storage_subheading(&mut label_items, &"Device".to_string());
+	push_storage_metric(
+		&mut label_items,
+		theme,
+		&"Full in".to_string(),
+		&format_projection(storage_projection(dash_state, &monitor.metrics))
+	);
+
+	push_storage_subheading(&mut label_items, theme, &"Device".to_string());
 
 	push_storage_metric(
 		&mut label_items,
+		theme,
 		&"Space Avail".to_string(),
 		&max_string
 	);
 
 	push_storage_metric(
 		&mut label_items,
+		theme,
 		&"Space Free".to_string(),
 		&device_limit_string
 	);
@@ -260,7 +2008,7 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 
 
 	let mut text_items = Vec::<ListItem>::new();
-	push_storage_subheading(&mut text_items, &"Load".to_string());
+	push_storage_subheading(&mut text_items, theme, &"Load".to_string());
 
 	let node_text = format!("{:<13}: CPU {} (MAX {}) Memory {}",
 		"Node",
@@ -270,7 +2018,7 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 	);
 	text_items.push(
 		ListItem::new(vec![Spans::from(node_text.clone())])
-			.style(Style::default().fg(Color::Blue)),
+			.style(theme.text),
 	);
 
 	let system_text = format!("{:<13}: CPU {} LoadAvg {} {} {}",
@@ -282,8 +2030,20 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 	);
 	text_items.push(
 		ListItem::new(vec![Spans::from(system_text.clone())])
-			.style(Style::default().fg(Color::Blue)),
+			.style(theme.text),
 	);
+
+	if !monitor.metrics.user_metrics.is_empty() {
+		push_storage_subheading(&mut text_items, theme, &"".to_string());
+		push_storage_subheading(&mut text_items, theme, &"Custom".to_string());
+		for (name, value) in monitor.metrics.user_metrics.values() {
+			let value = value.map_or(String::from("-"), |value| value.to_string());
+			text_items.push(
+				ListItem::new(vec![Spans::from(format!("{:<13}: {}", name, value))])
+					.style(theme.text),
+			);
+		}
+	}
 	// Render labels
 	let text_widget = List::new(text_items).block(
 		Block::default()
@@ -292,37 +2052,149 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 	f.render_widget(text_widget, rows[1]);
 }
 
+const PEER_ID_PREFIX_CHARS: usize = 6;
+const PEER_ID_SUFFIX_CHARS: usize = 5;
+
+// Return the start and end of a PeerId so it fits beside the other metrics
+fn abbreviate_peer_id(peer_id: &str) -> String {
+	let chars: Vec<char> = peer_id.chars().collect();
+	if chars.len() <= PEER_ID_PREFIX_CHARS + PEER_ID_SUFFIX_CHARS + 1 {
+		return peer_id.to_string();
+	}
+	let prefix: String = chars[..PEER_ID_PREFIX_CHARS].iter().collect();
+	let suffix: String = chars[chars.len() - PEER_ID_SUFFIX_CHARS..].iter().collect();
+	format!("{}…{}", prefix, suffix)
+}
+
 // Return string representation in TB, MB, KB or bytes depending on magnitude
-fn format_size(bytes: u64, fractional_digits: usize) -> String {
-	use::byte_unit::Byte;
-	let bytes = Byte::from_bytes(bytes as u128);
-	bytes.get_appropriate_unit(false).format(fractional_digits)
-}
-
-// Return ratio from two u64
-fn ratio(numerator: u64, denomimator: u64) -> f64 {
-	let percent = numerator as f64 / denomimator as f64;
-	if  percent.is_nan() || percent < 0.0 {
-		0.0
-	} else if percent > 1.0 {
-		1.0
-	} else {
-		percent
+///! The metric shown by the gauge of each node's resources, see --primary-gauge
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrimaryGauge {
+	Storage, // Used of the capacity
+	ErrorRate, // Errors per minute of --check-errors
+	Peers, // Connected peers of --gauge-peers
+}
+
+impl std::str::FromStr for PrimaryGauge {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<PrimaryGauge, String> {
+		match s {
+			"storage" => Ok(PrimaryGauge::Storage),
+			"error-rate" => Ok(PrimaryGauge::ErrorRate),
+			"peers" => Ok(PrimaryGauge::Peers),
+			_ => Err(format!("invalid primary gauge '{}', expected storage, error-rate or peers", s)),
+		}
+	}
+}
+
+///! What a node's primary gauge is drawn from
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaugeInputs {
+	pub used_space: u64,
+	pub capacity: Option<u64>,
+	pub errors_per_minute: u64,
+	pub peers: u64,
+}
+
+impl GaugeInputs {
+	pub fn of(metrics: &NodeMetrics) -> GaugeInputs {
+		GaugeInputs {
+			used_space: metrics.used_space,
+			capacity: metrics.capacity(),
+			errors_per_minute: metrics.errors_per_minute(),
+			peers: metrics.peers,
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaugeReading {
+	pub name: &'static str, // Beside the gauge, with the value
+	pub value: String,
+	pub ratio: Option<f64>, // None when it can't be known, drawn indeterminate
+	pub label: String, // On the gauge
+	pub full_is_good: bool,
+}
+
+///! The ratio and labels of a node's primary gauge
+pub fn primary_gauge_reading(gauge: PrimaryGauge, inputs: &GaugeInputs, dash_state: &DashState) -> GaugeReading {
+	let number_format = &dash_state.number_format;
+	match gauge {
+		PrimaryGauge::Storage => {
+			let used = format_size(number_format, inputs.used_space);
+			let ratio = storage_ratio(inputs.used_space, inputs.capacity);
+			let label = match inputs.capacity.filter(|_capacity| ratio.is_some()) {
+				Some(capacity) => format!("{} / {}", used, format_size(number_format, capacity)),
+				None => format!("{} used, capacity unknown", used),
+			};
+			GaugeReading { name: "Chunk storage", value: used, ratio, label, full_is_good: false }
+		}
+		PrimaryGauge::ErrorRate => {
+			let max = dash_state.check_errors.max(1);
+			GaugeReading {
+				name: "Errors/min",
+				value: number_format.format_count(inputs.errors_per_minute),
+				ratio: Some(inputs.errors_per_minute as f64 / max as f64),
+				label: format!("{} of {} errors/min", number_format.format_count(inputs.errors_per_minute), number_format.format_count(max)),
+				full_is_good: false,
+			}
+		}
+		PrimaryGauge::Peers => {
+			let full = dash_state.gauge_peers.max(1);
+			GaugeReading {
+				name: "Peers",
+				value: number_format.format_count(inputs.peers),
+				ratio: Some(inputs.peers as f64 / full as f64),
+				label: format!("{} of {} peers", number_format.format_count(inputs.peers), number_format.format_count(full)),
+				full_is_good: true,
+			}
+		}
+	}
+}
+
+fn format_size(number_format: &NumberFormat, bytes: u64) -> String {
+	number_format.format_bytes(bytes, Units::Si)
+}
+
+// Return bytes transferred in GiB, MiB, KiB or bytes depending on magnitude
+// Format a rate per minute, or a dash for the first interval when there is no rate yet
+fn format_rate(rate: Option<f64>, format_value: impl Fn(f64) -> String) -> String {
+	match rate {
+		Some(rate) => format!("{}/min", format_value(rate)),
+		None => String::from("—"),
 	}
 }
 
-fn push_storage_subheading(items: &mut Vec<ListItem>, subheading: &String) {
+fn format_count_rate(number_format: &NumberFormat, rate: f64) -> String {
+	number_format.format_decimal(rate, 1)
+}
+
+fn format_bytes_rate(number_format: &NumberFormat, rate: f64) -> String {
+	format_bytes(number_format, rate.round() as u64)
+}
+
+fn format_bytes(number_format: &NumberFormat, bytes: u64) -> String {
+	number_format.format_bytes(bytes, Units::Binary)
+}
+
+// Return rewards in tokens, nanos or attos depending on magnitude
+fn format_rewards(number_format: &NumberFormat, attos: u128) -> String {
+	number_format.format_rewards(attos)
+}
+
+fn push_storage_subheading(items: &mut Vec<ListItem>, theme: &Theme, subheading: &String) {
 	items.push(
 		ListItem::new(vec![Spans::from(subheading.clone())])
-			.style(Style::default().fg(Color::Yellow)),
+			.style(theme.header),
 	);
 }
 
-fn push_storage_metric(items: &mut Vec<ListItem>, metric: &String, value: &String) {
+fn push_storage_metric(items: &mut Vec<ListItem>, theme: &Theme, metric: &String, value: &String) {
 	let s = format!("{:<13}:{:>9}", metric, value);
 	items.push(
 		ListItem::new(vec![Spans::from(s.clone())])
-			.style(Style::default().fg(Color::Blue)),
+			.style(theme.text),
 	);
 }
 
@@ -332,115 +2204,376 @@ fn draw_timeline<B: Backend>(
 	dash_state: &mut DashState,
 	monitor: &mut LogMonitor,
 ) {
-	let active_timeline_name = match TIMELINES.get(dash_state.active_timeline) {
+	let theme = &dash_state.theme;
+	let puts = (&monitor.metrics.puts_timeline, "PUTS", theme.puts_sparkline, Axis::Left);
+	let gets = (&monitor.metrics.gets_timeline, "GETS", theme.gets_sparkline, Axis::Left);
+	let errors = (&monitor.metrics.errors_timeline, "ERRORS", theme.errors_sparkline, Axis::Left);
+	let peers = (&monitor.metrics.peers_timeline, "PEERS", theme.peers_sparkline, Axis::Left);
+	let charts = if dash_state.overlay_timelines {
+		vec![vec![puts, gets], vec![errors], vec![peers]]
+	} else {
+		vec![vec![puts], vec![gets], vec![errors], vec![peers]]
+	};
+	draw_timelines(f, area, dash_state, &charts);
+}
+
+// A timeline, its title, the style to draw it in and the y-scale it is drawn
+// to when overlaid on others
+type TimelineSeries<'a> = (&'a TimelineSet, &'a str, Style, Axis);
+
+// A window of charts of the active timeline, one above the other. A chart of
+// more than one series overlays them. Timelines with more buckets than fit
+// are downsampled so each column combines several buckets
+fn draw_timelines<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &DashState,
+	charts: &[Vec<TimelineSeries>],
+) {
+	draw_scaled_timelines(f, area, dash_state, charts, &[]);
+}
+
+// As draw_timelines(), but with the chart of a single series drawn to the
+// maximum for that chart in maxima, if there is one, rather than its own
+fn draw_scaled_timelines<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &DashState,
+	charts: &[Vec<TimelineSeries>],
+	maxima: &[u64],
+) {
+	let (active_timeline_name, active_window) = match dash_state.timelines.get(dash_state.active_timeline) {
 		None => {
 			// debug_log!("ERROR getting active timeline name");
 			return;
 		}
-		Some((name, _)) => name,
+		Some(spec) => (spec.name.clone(), spec.window()),
 	};
 
+	let constraints = vec![Constraint::Ratio(1, charts.len() as u32); charts.len()];
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.margin(1)
+		.constraints(constraints.as_slice())
+		.split(area);
+
+	let theme = &dash_state.theme;
+	let mut cursor_values = Vec::<String>::new();
+	let mut cursor_time = None;
+	let mut buckets_per_column = 1;
+	for (chart_index, (chart, chunk)) in charts.iter().zip(chunks.iter()).enumerate() {
+		let mut columns_of_series = Vec::<(Vec<u64>, &str, Style, Axis)>::new();
+		let mut frozen_columns = None; // Of a chart of one series, see TimelineSet::freeze()
+		let mut first_bucket_set = None;
+		let mut overflowed = false; // The oldest column holds times from before the window
+		let mut factor = 1;
+		for (timeline, title, style, axis) in chart.iter() {
+			if let Some(bucket_set) = timeline.bucket_sets().get(&active_timeline_name) {
+				factor = downsample_factor(bucket_set.buckets().len(), chunk.width as usize);
+				let columns = downsample(bucket_set.buckets(), factor, dash_state.downsample);
+				// Overlaid series keep their own colours, to tell them apart
+				let style = match &dash_state.trend_directions {
+					Some(directions) if chart.len() == 1 => {
+						trend_style(theme, *style, trend(&columns), directions.direction_for(timeline.get_name()))
+					}
+					_ => *style,
+				};
+				columns_of_series.push((columns, title, style, *axis));
+				if chart.len() == 1 {
+					frozen_columns = bucket_set.frozen.as_ref().map(|frozen| downsample(frozen, factor, dash_state.downsample));
+				}
+				first_bucket_set = first_bucket_set.or(Some(bucket_set));
+				overflowed |= bucket_set.overflow > 0;
+			}
+		}
+		let bucket_set = match first_bucket_set {
+			Some(bucket_set) => bucket_set,
+			None => continue,
+		};
+		buckets_per_column = buckets_per_column.max(factor);
+		let column_duration = bucket_set.bucket_duration * factor as i32;
+		let series: Vec<OverlaidSeries> = columns_of_series
+			.iter()
+			.map(|(columns, title, style, axis)| (columns.as_slice(), *title, *style, *axis))
+			.collect();
+		let columns = series[0].0.len();
+		let cursor = if dash_state.timeline_has_focus {
+			cursor_index(columns, dash_state.timeline_cursor)
+		} else {
+			None
+		};
+		if let Some(index) = cursor {
+			for (buckets, title, _style, _axis) in series.iter() {
+				if let Some(value) = buckets.get(index) {
+					cursor_values.push(format!("{} {}", title, chart_value(&dash_state.number_format, title, *value)));
+				}
+			}
+			let age = (columns - 1 - index) as i32;
+			cursor_time = bucket_set.bucket_time.map(|time| time - column_duration * age);
+		}
+		let rows = Layout::default()
+			.direction(Direction::Vertical)
+			.constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+			.split(*chunk);
+		match series.as_slice() {
+			[series] => draw_sparkline(f, rows[0], series, frozen_columns.as_deref(), maxima.get(chart_index).copied(), cursor, dash_state),
+			_ => draw_overlaid_sparklines(f, rows[0], &series, cursor, dash_state),
+		}
+		let mut axis = timeline_axis(column_duration, columns, rows[1].width);
+		if overflowed && !axis.is_empty() {
+			axis.replace_range(..axis.chars().next().map_or(0, char::len_utf8), OVERFLOW_MARK);
+		}
+		f.render_widget(List::new(vec![ListItem::new(Span::styled(axis, theme.text))]), rows[1]);
+		let titles: Vec<&str> = chart.iter().map(|(_timeline, title, _style, _axis)| *title).collect();
+		emphasise(f, *chunk, chart_emphasis(dash_state.focus_metric, &titles));
+	}
+
+	let mut title = format!("Timeline - {}, last {}", active_timeline_name, format_window(active_window));
+	if buckets_per_column > 1 {
+		title = format!("{} ({} buckets per column)", title, buckets_per_column);
+	}
+	if dash_state.timeline_has_focus {
+		let time = match cursor_time {
+			Some(time) => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+			None => String::from("unknown time"),
+		};
+		title = format!("{} - {} at {}", title, cursor_values.join(", "), time);
+	}
 	let window_widget = Block::default()
 		.borders(Borders::ALL)
-		.title(format!("Timeline - {}", active_timeline_name).to_string());
+		.title(title);
 	f.render_widget(window_widget, area);
+}
 
-	// For debugging the bucket state
-	//
-	// if let Some(b_time) = monitor.metrics.sparkline_bucket_time {
-	// 	dash_state._debug_window(format!("sparkline_b_time: {}", b_time).as_str());
-	// 	dash_state._debug_window(
-	// 		format!(
-	// 			"sparkline_b_width: {}",
-	// 			monitor.metrics.sparkline_bucket_width
-	// 		)
-	// 		.as_str(),
-	// 	);
-	// }
-
-	// let mut i = 0;
-	// while i < monitor.metrics.puts_sparkline.len() {
-	// 	dash_state._debug_window(
-	// 		format!(
-	// 			"{:>2}: {:>2} puts, {:>2} gets",
-	// 			i, monitor.metrics.puts_sparkline[i], monitor.metrics.gets_sparkline[i]
-	// 		)
-	// 		.as_str(),
-	// 	);
-	// 	i += 1;
-	// }
+// The style of a timeline with --trend-colors: good or bad for its metric,
+// neutral when flat, and its own style for a metric with no good direction
+fn trend_style(theme: &Theme, style: Style, trend: Trend, direction: Option<GoodDirection>) -> Style {
+	match direction.map(|direction| direction.is_good(trend)) {
+		Some(Some(true)) => theme.trend_good,
+		Some(Some(false)) => theme.trend_bad,
+		Some(None) => theme.text,
+		None => style,
+	}
+}
 
-	let chunks = Layout::default()
-		.direction(Direction::Vertical)
-		.margin(1)
-		.constraints(
-			[
-				Constraint::Percentage(33),
-				Constraint::Percentage(33),
-				Constraint::Percentage(33),
-			]
-			.as_ref(),
-		)
-		.split(area);
+// Sparkline title with the value of the tallest bar, as there is no y-axis
+fn sparkline_title(title: &str, buckets: &[u64], scale: Scale, number_format: &NumberFormat) -> String {
+	let max = chart_value(number_format, title, buckets.iter().max().copied().unwrap_or(0));
+	match scale {
+		Scale::Linear => format!("{} (max {})", title, max),
+		Scale::Log10 => format!("{} (max {}, log scale)", title, max),
+	}
+}
 
-	if let Some(bucket_set) = monitor
-		.metrics
-		.puts_timeline
-		.get_bucket_set(active_timeline_name)
-	{
-		draw_sparkline(f, chunks[0], &bucket_set.buckets(), &"PUTS", Color::Yellow);
-	};
+// A value of the timeline titled as it is labelled on its chart. Rewards are
+// held in attos, so are shown in the tokens or nanos of the rewards formatter
+fn chart_value(number_format: &NumberFormat, title: &str, value: u64) -> String {
+	if title == REWARDS_TIMELINE {
+		format_rewards(number_format, value as u128)
+	} else {
+		value.to_string()
+	}
+}
 
-	if let Some(bucket_set) = monitor
-		.metrics
-		.gets_timeline
-		.get_bucket_set(active_timeline_name)
-	{
-		draw_sparkline(f, chunks[1], &bucket_set.buckets(), &"GETS", Color::Green);
-	};
+// The min, mean and max of a chart's buckets, labelled as chart_value()
+fn chart_figures(number_format: &NumberFormat, title: &str, buckets: &[u64]) -> Option<[(&'static str, String); 3]> {
+	if title != REWARDS_TIMELINE {
+		return None;
+	}
+	let (min, mean, max) = min_mean_max(buckets)?;
+	Some([
+		("min", chart_value(number_format, title, min)),
+		("mean", chart_value(number_format, title, mean.round() as u64)),
+		("max", chart_value(number_format, title, max)),
+	])
+}
 
-	if let Some(bucket_set) = monitor
-		.metrics
-		.errors_timeline
-		.get_bucket_set(active_timeline_name)
-	{
-		draw_sparkline(f, chunks[2], &bucket_set.buckets(), &"ERRORS", Color::Red);
-	};
+// Beneath the oldest column of a timeline, when it also holds what was logged before the window
+const OVERFLOW_MARK: &str = "«";
+
+// Fewest columns between the ticks of a timeline axis
+const AXIS_TICK_SPACING: usize = 10;
+
+// Tick spans tried in turn, in seconds, so the ticks fall on round times
+const AXIS_TICK_SECONDS: [i64; 21] = [
+	1, 2, 5, 10, 15, 30,
+	60, 2 * 60, 5 * 60, 10 * 60, 15 * 60, 30 * 60,
+	3600, 2 * 3600, 3 * 3600, 6 * 3600, 12 * 3600,
+	86400, 2 * 86400, 7 * 86400, 30 * 86400,
+];
+
+// A width line of relative time labels such as '-1h' and '-30m' for a
+// timeline whose newest bucket is in column buckets - 1 and labelled 'now'.
+// Ticks are spaced further apart than the labels are wide, so narrow
+// charts get fewer of them
+fn timeline_axis(bucket_duration: chrono::Duration, buckets: usize, width: u16) -> String {
+	let width = width as usize;
+	let mut line = vec![' '; width];
+	let shown = buckets.min(width);
+	if shown == 0 {
+		return line.into_iter().collect();
+	}
+
+	let bucket_seconds = bucket_duration.num_seconds().max(1);
+	let step = AXIS_TICK_SECONDS
+		.iter()
+		.filter(|seconds| *seconds % bucket_seconds == 0)
+		.map(|seconds| (*seconds / bucket_seconds) as usize)
+		.find(|step| *step >= AXIS_TICK_SPACING)
+		.unwrap_or(AXIS_TICK_SPACING);
+
+	// Labels are right aligned to their bucket, newest first, skipping any that would overlap
+	let mut free_end = width; // Columns from here on are taken
+	let mut age = 0;
+	while age < shown {
+		let label = if age == 0 {
+			String::from("now")
+		} else {
+			format!("-{}", format_span(bucket_seconds * age as i64))
+		};
+		let end = shown - age; // Exclusive
+		if end <= free_end && label.len() <= end {
+			let start = end - label.len();
+			for (i, c) in label.chars().enumerate() {
+				line[start + i] = c;
+			}
+			free_end = start.saturating_sub(1);
+		}
+		age += step;
+	}
+	line.into_iter().collect()
+}
+
+// A span of time in its largest whole unit, e.g. '30s', '5m', '2h' or '7d'
+fn format_span(seconds: i64) -> String {
+	if seconds % 86400 == 0 {
+		format!("{}d", seconds / 86400)
+	} else if seconds % 3600 == 0 {
+		format!("{}h", seconds / 3600)
+	} else if seconds % 60 == 0 {
+		format!("{}m", seconds / 60)
+	} else {
+		format!("{}s", seconds)
+	}
+}
+
+// Index of the bucket which is cursor buckets before the newest, clamped to the buckets shown
+fn cursor_index(shown_buckets: usize, cursor: usize) -> Option<usize> {
+	if shown_buckets == 0 {
+		return None;
+	}
+	Some(shown_buckets - 1 - cursor.min(shown_buckets - 1))
 }
 
 fn draw_sparkline<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
-	buckets: &Vec<u64>,
-	title: &str,
-	fg_colour: tui::style::Color,
+	series: &OverlaidSeries,
+	frozen: Option<&[u64]>,
+	max: Option<u64>,
+	cursor: Option<usize>,
+	dash_state: &DashState,
 	) {
 
-		let sparkline = Sparkline2::default()
+		let (buckets, title, style, _axis) = *series;
+		let scale = dash_state.timeline_scale;
+		let chart_style = dash_state.chart_styles.style_for(title); // Titled with the timeline's name
+		let number_format = &dash_state.number_format;
+		let mut title = sparkline_title(title, buckets, scale, number_format);
+		if let Some(frozen) = frozen {
+			title = format!("{} - frozen max {}", title, chart_value(number_format, series.1, frozen.iter().max().copied().unwrap_or(0)));
+		}
+		let mut sparkline = Sparkline2::default()
 		.block(Block::default().title(title))
-		.data(buckets_right_justify(
-			&buckets,
-			area.width,
-		))
-		.style(Style::default().fg(fg_colour));
+		.data(buckets)
+		.backdrop(frozen)
+		.backdrop_style(style.add_modifier(Modifier::DIM))
+		.cursor(cursor)
+		.scale(scale)
+		.bar_set(dash_state.spark_style.bar_set())
+		.chart_style(chart_style)
+		.spike_sigma(dash_state.spike_sigma)
+		.spike_style(dash_state.theme.spike_sparkline)
+		.style(style);
+	if let Some(max) = max {
+		sparkline = sparkline.max(max);
+	}
 	f.render_widget(sparkline, area);
+
+	// Statistics of the visible buckets, to the right of the title
+	let width = (area.width / 2).min(MMM_WIDTH);
+	let mut mmm = MinMeanMax::default().values(buckets).style(style);
+	if let Some(figures) = chart_figures(&dash_state.number_format, series.1, buckets) {
+		mmm = mmm.given_figures(figures);
+	}
+	f.render_widget(mmm, Rect::new(area.right() - width, area.top(), width, area.height.min(1)));
 }
 
-// Right justify and truncate (left) a set of buckets to width
-fn buckets_right_justify(buckets: &Vec<u64>, width: u16) -> &[u64] {
-	let width = width as usize;
-	if width < buckets.len() {
-		return &buckets[buckets.len() - width..];
+// The buckets of a timeline shown in a chart, its title, style and y-scale
+type OverlaidSeries<'a> = (&'a [u64], &'a str, Style, Axis);
+
+// Sparklines drawn over one another, titled with a legend of their colours.
+// When some are drawn to the right-hand y-scale the legend has a line for
+// each scale
+fn draw_overlaid_sparklines<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	series: &[OverlaidSeries],
+	cursor: Option<usize>,
+	dash_state: &DashState,
+) {
+	let scale = dash_state.timeline_scale;
+	let dual_scale = series.iter().any(|(_buckets, _title, _style, axis)| *axis == Axis::Right);
+	let mut left_legend = Vec::<Span>::new();
+	let mut right_legend = Vec::<Span>::new();
+	if dual_scale {
+		left_legend.push(Span::styled("left: ", dash_state.theme.text));
+		right_legend.push(Span::styled("right: ", dash_state.theme.text));
+	}
+	let mut sparkline = MultiSparkline::default()
+		.cursor(cursor)
+		.scale(scale)
+		.bar_set(dash_state.spark_style.bar_set())
+		.normalize(dash_state.normalize_overlay);
+	for (buckets, title, style, axis) in series.iter() {
+		let legend = if *axis == Axis::Right { &mut right_legend } else { &mut left_legend };
+		legend.push(Span::styled("▇ ", *style));
+		legend.push(Span::styled(format!("{}  ", sparkline_title(title, buckets, Scale::Linear, &dash_state.number_format)), dash_state.theme.text));
+		sparkline = match axis {
+			Axis::Left => sparkline.series(buckets, *style),
+			Axis::Right => sparkline.right_series(buckets, *style),
+		};
+	}
+	let mut notes = Vec::<&str>::new();
+	if dash_state.normalize_overlay {
+		notes.push("normalized");
+	}
+	if scale == Scale::Log10 {
+		notes.push("log scale");
+	}
+	if !notes.is_empty() {
+		left_legend.push(Span::styled(format!("({})", notes.join(", ")), dash_state.theme.text));
 	}
 
-	buckets
+	if !dual_scale || area.height < 3 {
+		left_legend.extend(right_legend);
+		f.render_widget(sparkline.block(Block::default().title(Spans::from(left_legend))), area);
+		return;
+	}
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+		.split(area);
+	f.render_widget(List::new(vec![ListItem::new(Spans::from(left_legend))]), rows[0]);
+	f.render_widget(sparkline.block(Block::default().title(Spans::from(right_legend))), rows[1]);
 }
 
+const MMM_WIDTH: u16 = 36;
+
 fn draw_bottom_panel<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
 	dash_state: &mut DashState,
-	logfile: &String,
 	monitor: &mut LogMonitor,
 ) {
 	if dash_state.debug_window {
@@ -455,23 +2588,21 @@ fn draw_bottom_panel<B: Backend>(
 			.constraints(constraints.as_ref())
 			.split(area);
 
-		draw_logfile(f, chunks[0], &logfile, monitor);
+		draw_logfile(f, chunks[0], &dash_state.theme, monitor);
 		draw_debug_window(f, chunks[1], dash_state);
 	} else {
-		draw_logfile(f, area, &logfile, monitor);
+		draw_logfile(f, area, &dash_state.theme, monitor);
 	}
 }
 
 pub fn draw_logfile<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
-	logfile: &String,
+	theme: &Theme,
 	monitor: &mut LogMonitor,
 ) {
 	let highlight_style = match monitor.has_focus {
-		true => Style::default()
-			.bg(Color::LightGreen)
-			.add_modifier(Modifier::BOLD),
+		true => theme.selected_row,
 		false => Style::default().add_modifier(Modifier::BOLD),
 	};
 
@@ -480,12 +2611,12 @@ pub fn draw_logfile<B: Backend>(
 		.items
 		.iter()
 		.map(|s| {
-			ListItem::new(vec![Spans::from(s.clone())])
-				.style(Style::default().fg(Color::Black).bg(Color::White))
+			ListItem::new(ansi_spans(s, Style::default()))
+				.style(theme.logfile_text)
 		})
 		.collect();
 
-	let node_log_title = format!("Node Log ({})", logfile);
+	let node_log_title = format!("Node Log ({})", monitor.name);
 
 	let logfile_widget = List::new(items)
 		.block(
@@ -500,9 +2631,7 @@ pub fn draw_logfile<B: Backend>(
 
 fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState) {
 	let highlight_style = match dash_state.debug_window_has_focus {
-		true => Style::default()
-			.bg(Color::LightGreen)
-			.add_modifier(Modifier::BOLD),
+		true => dash_state.theme.selected_row,
 		false => Style::default().add_modifier(Modifier::BOLD),
 	};
 
@@ -512,7 +2641,7 @@ fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut
 		.iter()
 		.map(|s| {
 			ListItem::new(vec![Spans::from(s.clone())])
-				.style(Style::default().fg(Color::Black).bg(Color::White))
+				.style(dash_state.theme.logfile_text)
 		})
 		.collect();
 
@@ -530,3 +2659,679 @@ fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut
 		&mut dash_state.debug_window_list.state,
 	);
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn summary(logfile: &str, storage_used: u64, errors_per_minute: u64, rewards: u128) -> NodeSummary {
+		NodeSummary {
+			index: 0,
+			logfile: logfile.to_string(),
+			name: logfile.to_string(),
+			puts: 0,
+			gets: 0,
+			errors: 0,
+			storage_used,
+			storage_ratio: None,
+			errors_per_minute,
+			lines_per_minute: 0,
+			rewards,
+			is_stale: false,
+			is_disconnected: false,
+			enabled: true,
+			read_error: None,
+			filter_match: None,
+			tag: None,
+			uptime: None,
+			health: 100,
+		}
+	}
+
+	fn sorted_logfiles(sort_key: SortKey, ascending: bool) -> Vec<String> {
+		let mut summaries = vec![
+			summary("c.log", 300, 1, 10),
+			summary("a.log", 100, 5, 30),
+			summary("d.log", 100, 0, 20),
+			summary("b.log", 200, 5, 30),
+		];
+		sort_node_summaries(&mut summaries, sort_key, ascending);
+		summaries.into_iter().map(|s| s.logfile).collect()
+	}
+
+	#[test]
+	fn sorts_node_summaries_by_name() {
+		let mut summaries = vec![summary("a.log", 0, 0, 0), summary("b.log", 0, 0, 0), summary("c.log", 0, 0, 0)];
+		summaries[0].name = String::from("zulu");
+		summaries[1].name = String::from("alpha");
+		summaries[2].name = String::from("alpha");
+		sort_node_summaries(&mut summaries, SortKey::Logfile, true);
+		let logfiles: Vec<&str> = summaries.iter().map(|s| s.logfile.as_str()).collect();
+		assert_eq!(logfiles, vec!["b.log", "c.log", "a.log"]);
+	}
+
+	#[test]
+	fn ranks_and_highlights_nodes_by_filter_match() {
+		let mut summaries: Vec<NodeSummary> = ["nxxxxxx1.log", "other.log", "node-1.log", "n1.log"]
+			.iter()
+			.map(|logfile| {
+				let mut summary = summary(logfile, 0, 0, 0);
+				summary.filter_match = crate::custom::fuzzy::fuzzy_match("n1", logfile);
+				summary
+			})
+			.collect();
+		rank_by_filter_match(&mut summaries);
+		let logfiles: Vec<&str> = summaries.iter().map(|s| s.logfile.as_str()).collect();
+		assert_eq!(logfiles, vec!["n1.log", "node-1.log", "nxxxxxx1.log", "other.log"]);
+
+		let area = Rect::new(0, 0, 12, 2);
+		let mut buffer = tui::buffer::Buffer::empty(area);
+		let style = Style::default().add_modifier(Modifier::UNDERLINED);
+		let positions = [&[0, 5, 20][..], &[1][..], &[0][..]];
+		tui::widgets::Widget::render(MatchHighlights { positions: positions.to_vec(), style }, area, &mut buffer);
+		let underlined: Vec<(u16, u16)> = (0..2)
+			.flat_map(|y| (0..12).map(move |x| (x, y)))
+			.filter(|(x, y)| buffer.get(*x, *y).modifier.contains(Modifier::UNDERLINED))
+			.collect();
+		assert_eq!(underlined, vec![(0, 0), (5, 0), (1, 1)]);
+	}
+
+	#[test]
+	fn shows_the_columns_configured_in_order() {
+		let columns: SummaryColumns = "name, puts,gets,errors,storage_ratio,rewards".parse().unwrap();
+		assert_eq!(columns.headers(), vec!["Name", "Puts", "Gets", "Errors", "Used", "Rewards"]);
+		let mut node = summary("node-1.log", 0, 0, 1_500_000_000_000_000_000);
+		node.puts = 12;
+		node.gets = 3;
+		node.errors = 1234;
+		node.storage_ratio = Some(0.25);
+		let number_format = NumberFormat { group_digits: true, ..NumberFormat::default() };
+		let cells: Vec<String> = columns.cells(&node, &number_format).iter().map(|cell| cell.trim().to_string()).collect();
+		assert_eq!(cells, vec!["node-1.log", "12", "3", "1,234", "25%", &format_rewards(&NumberFormat::default(), node.rewards)]);
+		assert_eq!(columns.name_offset(2), Some(0));
+
+		let default = SummaryColumns::default();
+		assert_eq!(default.headers(), vec!["Node", "Storage", "Errors/min", "Rewards", "Name"]);
+		assert_eq!(default.name_offset(2), Some(4 + 10 + 10 + 20 + 4 * 2));
+		assert_eq!("puts,gets".parse::<SummaryColumns>().unwrap().name_offset(2), None);
+		assert_eq!(default.offsets(2), vec![0, 4 + 2, 4 + 10 + 2 * 2, 4 + 10 + 10 + 3 * 2, 4 + 10 + 10 + 20 + 4 * 2]);
+	}
+
+	#[test]
+	fn reports_bad_columns() {
+		assert_eq!(
+			"name,cpu".parse::<SummaryColumns>(),
+			Err(String::from(
+				"unknown column 'cpu', expected one of node, name, puts, gets, errors, errors_per_minute, storage, storage_ratio, rewards, uptime, health"
+			))
+		);
+		assert_eq!("name,puts,name".parse::<SummaryColumns>(), Err(String::from("column 'name' is given more than once")));
+	}
+
+	#[test]
+	fn sorts_node_summaries_by_logfile() {
+		assert_eq!(sorted_logfiles(SortKey::Logfile, true), vec!["a.log", "b.log", "c.log", "d.log"]);
+		assert_eq!(sorted_logfiles(SortKey::Logfile, false), vec!["d.log", "c.log", "b.log", "a.log"]);
+	}
+
+	#[test]
+	fn sorts_node_summaries_by_storage_used() {
+		assert_eq!(sorted_logfiles(SortKey::StorageUsed, true), vec!["a.log", "d.log", "b.log", "c.log"]);
+		assert_eq!(sorted_logfiles(SortKey::StorageUsed, false), vec!["c.log", "b.log", "a.log", "d.log"]);
+	}
+
+	#[test]
+	fn sorts_node_summaries_by_error_rate() {
+		assert_eq!(sorted_logfiles(SortKey::ErrorRate, true), vec!["d.log", "c.log", "a.log", "b.log"]);
+		assert_eq!(sorted_logfiles(SortKey::ErrorRate, false), vec!["a.log", "b.log", "c.log", "d.log"]);
+	}
+
+	#[test]
+	fn sorts_node_summaries_by_rewards() {
+		assert_eq!(sorted_logfiles(SortKey::Rewards, true), vec!["c.log", "d.log", "a.log", "b.log"]);
+		assert_eq!(sorted_logfiles(SortKey::Rewards, false), vec!["a.log", "b.log", "d.log", "c.log"]);
+	}
+
+	#[test]
+	fn sorts_and_colours_node_summaries_by_health() {
+		let scored = |name: &str, health: u8| NodeSummary { health, ..summary(name, 0, 0, 0) };
+		let mut summaries = vec![scored("a.log", 40), scored("b.log", 100), scored("c.log", 65), scored("d.log", 40)];
+		sort_node_summaries(&mut summaries, SortKey::Health, true);
+		let logfiles: Vec<&str> = summaries.iter().map(|s| s.logfile.as_str()).collect();
+		assert_eq!(logfiles, vec!["a.log", "d.log", "c.log", "b.log"]);
+
+		let theme = Theme::default();
+		assert_eq!(summary_style(&summaries[3], &theme, true), theme.gauge_fill);
+		assert_eq!(summary_style(&summaries[2], &theme, true), theme.gauge_warning);
+		assert_eq!(summary_style(&summaries[0], &theme, true), theme.gauge_critical);
+		assert_eq!(summary_style(&summaries[0], &theme, false), theme.node_style(0));
+		let stale = NodeSummary { is_stale: true, ..scored("e.log", 100) };
+		assert_eq!(summary_style(&stale, &theme, true), theme.error_text);
+	}
+
+	#[test]
+	fn ranks_top_talkers_by_line_rate() {
+		let talker = |name: &str, lines_per_minute: u64| NodeSummary { lines_per_minute, ..summary(name, 0, 0, 0) };
+		let summaries = vec![
+			talker("node-c", 40),
+			talker("node-b", 120),
+			talker("node-e", 0),
+			talker("node-a", 40),
+			NodeSummary { enabled: false, ..talker("node-x", 500) },
+			talker("node-d", 75),
+		];
+		let ranked = |n: usize| -> Vec<(&str, u64)> {
+			rank_top_talkers(&summaries, n).iter().map(|summary| (summary.name.as_str(), summary.lines_per_minute)).collect()
+		};
+		let expected = vec![("node-b", 120), ("node-d", 75), ("node-a", 40), ("node-c", 40), ("node-e", 0)];
+		assert_eq!(ranked(10), expected);
+		assert_eq!(ranked(3), expected[..3].to_vec());
+		assert!(ranked(0).is_empty());
+	}
+
+	#[test]
+	fn titles_sparklines_with_scale() {
+		let number_format = NumberFormat::default();
+		assert_eq!(sparkline_title("PUTS", &[1, 5, 2], Scale::Linear, &number_format), "PUTS (max 5)");
+		assert_eq!(sparkline_title("PUTS", &[], Scale::Log10, &number_format), "PUTS (max 0, log scale)");
+	}
+
+	#[test]
+	fn labels_rewards_charts_in_tokens() {
+		let number_format = NumberFormat::default();
+		let title = |buckets: &[u64]| sparkline_title(REWARDS_TIMELINE, buckets, Scale::Linear, &number_format);
+		assert_eq!(title(&[500, 999]), "REWARDS (max 999 attos)");
+		assert_eq!(title(&[500, 1_500_000_000]), "REWARDS (max 1.500 nanos)");
+		assert_eq!(title(&[2_500_000_000_000_000_000]), "REWARDS (max 2.500 tokens)");
+		assert_eq!(title(&[]), "REWARDS (max 0 attos)");
+		let precise = NumberFormat { precision: Some(1), ..NumberFormat::default() };
+		assert_eq!(sparkline_title(REWARDS_TIMELINE, &[1_250_000_000], Scale::Log10, &precise), "REWARDS (max 1.2 nanos, log scale)");
+
+		assert_eq!(
+			chart_figures(&number_format, REWARDS_TIMELINE, &[1_000_000_000, 3_000_000_000]),
+			Some([("min", String::from("1.000 nanos")), ("mean", String::from("2.000 nanos")), ("max", String::from("3.000 nanos"))])
+		);
+		assert_eq!(chart_figures(&number_format, REWARDS_TIMELINE, &[]), None);
+		assert_eq!(chart_figures(&number_format, "PUTS", &[1_000_000_000]), None); // Left to the chart's own figures
+		assert_eq!(chart_value(&number_format, "PUTS", 1_500_000_000), "1500000000");
+	}
+
+	#[test]
+	fn clamps_timeline_cursor() {
+		assert_eq!(cursor_index(0, 0), None);
+		assert_eq!(cursor_index(10, 0), Some(9));
+		assert_eq!(cursor_index(10, 3), Some(6));
+		assert_eq!(cursor_index(10, 9), Some(0));
+		assert_eq!(cursor_index(10, 50), Some(0));
+	}
+
+	#[test]
+	fn formats_elapsed_time() {
+		use chrono::Duration;
+		assert_eq!(format_elapsed(Duration::seconds(-5)), "0s");
+		assert_eq!(format_elapsed(Duration::seconds(45)), "45s");
+		assert_eq!(format_elapsed(Duration::seconds(200)), "3m 20s");
+		assert_eq!(format_elapsed(Duration::minutes(125)), "2h 5m");
+		assert_eq!(format_elapsed(Duration::hours(76)), "3d 4h");
+	}
+
+	#[test]
+	fn colours_timelines_by_trend() {
+		let theme = Theme::default();
+		let own = theme.puts_sparkline;
+		assert_eq!(trend_style(&theme, own, Trend::Falling, Some(GoodDirection::Down)), theme.trend_good);
+		assert_eq!(trend_style(&theme, own, Trend::Rising, Some(GoodDirection::Down)), theme.trend_bad);
+		assert_eq!(trend_style(&theme, own, Trend::Rising, Some(GoodDirection::Up)), theme.trend_good);
+		assert_eq!(trend_style(&theme, own, Trend::Falling, Some(GoodDirection::Up)), theme.trend_bad);
+		assert_eq!(trend_style(&theme, own, Trend::Flat, Some(GoodDirection::Up)), theme.text);
+		assert_eq!(trend_style(&theme, own, Trend::Rising, None), own);
+	}
+
+	#[test]
+	fn formats_uptime() {
+		use chrono::Duration;
+		assert_eq!(format_uptime(None), "—");
+		assert_eq!(format_uptime(Some(Duration::seconds(0))), "up 0s");
+		assert_eq!(format_uptime(Some(Duration::seconds(59))), "up 59s");
+		assert_eq!(format_uptime(Some(Duration::minutes(90))), "up 1h 30m");
+		assert_eq!(format_uptime(Some(Duration::hours(76))), "up 3d 4h");
+		assert_eq!(format_uptime(Some(Duration::days(400))), "up 400d 0h");
+
+		let mut summary = summary("a.log", 0, 0, 0);
+		summary.uptime = Some(Duration::hours(76));
+		let columns: SummaryColumns = "uptime".parse().unwrap();
+		assert_eq!(columns.cells(&summary, &NumberFormat::default()), vec!["  up 3d 4h"]);
+	}
+
+	#[test]
+	fn formats_rewards_by_magnitude() {
+		assert_eq!(format_rewards(&NumberFormat::default(), 0), "0 attos");
+		assert_eq!(format_rewards(&NumberFormat::default(), 999_999_999), "999999999 attos");
+		assert_eq!(format_rewards(&NumberFormat::default(), 2_000_000_500), "2.000 nanos");
+		assert_eq!(format_rewards(&NumberFormat::default(), 1_500_000_000_000_000_000), "1.500 tokens");
+		assert_eq!(format_rewards(&NumberFormat::default(), u128::MAX), "340282366920938463463.374 tokens");
+	}
+
+	#[test]
+	fn abbreviates_long_peer_ids() {
+		assert_eq!(abbreviate_peer_id("12D3KooWHqCVbBgq9jrNJ8d77nGJQABPzKEiLENxH4oq7qCbXfDy"), "12D3Ko…bXfDy");
+		assert_eq!(abbreviate_peer_id("12D3KooWAbc"), "12D3KooWAbc");
+		assert_eq!(abbreviate_peer_id(""), "");
+	}
+
+	#[test]
+	fn summarises_fleet_totals() {
+		let mut a = summary("a.log", 100, 0, 30);
+		a.puts = 5;
+		a.gets = 7;
+		a.errors = 1;
+		a.storage_ratio = Some(0.25);
+		let mut b = summary("b.log", 300, 0, 12);
+		b.puts = 1;
+		b.gets = 2;
+		b.errors = 3;
+		b.storage_ratio = Some(0.75);
+		let c = summary("c.log", 50, 0, 0); // Capacity not yet known
+
+		let fleet = summarise_fleet(&[a, b, c]);
+		assert_eq!(fleet.nodes, 3);
+		assert_eq!(fleet.puts, 6);
+		assert_eq!(fleet.gets, 9);
+		assert_eq!(fleet.errors, 4);
+		assert_eq!(fleet.storage_used, 450);
+		assert_eq!(fleet.rewards, 42);
+		assert_eq!(fleet.average_storage_ratio, Some(0.5));
+	}
+
+	#[test]
+	fn summarises_empty_fleet() {
+		assert_eq!(summarise_fleet(&[]), FleetSummary::default());
+		let fleet = summarise_fleet(&[summary("a.log", 100, 0, 0)]);
+		assert_eq!(fleet.average_storage_ratio, None);
+	}
+
+	#[test]
+	fn groups_node_summaries_by_tag() {
+		let tagged = |logfile: &str, tag: Option<&str>, storage_used: u64, rewards: u128| {
+			let mut summary = summary(logfile, storage_used, 1, rewards);
+			summary.tag = tag.map(String::from);
+			summary
+		};
+		let summaries = vec![
+			tagged("a.log", None, 100, 1),
+			tagged("b.log", Some("host-b"), 200, 2),
+			tagged("c.log", Some("host-a"), 300, 3),
+			tagged("d.log", Some("host-b"), 400, 4),
+		];
+		let groups = group_node_summaries(summaries);
+		let partition: Vec<(&str, Vec<&str>)> = groups
+			.iter()
+			.map(|group| (group.tag.as_str(), group.summaries.iter().map(|s| s.logfile.as_str()).collect()))
+			.collect();
+		assert_eq!(partition, vec![("host-a", vec!["c.log"]), ("host-b", vec!["b.log", "d.log"]), ("ungrouped", vec!["a.log"])]);
+
+		let host_b = &groups[1].totals;
+		assert_eq!((host_b.nodes, host_b.storage_used, host_b.rewards, host_b.errors_per_minute), (2, 600, 6, 2));
+
+		let columns: SummaryColumns = "node,name,storage,errors_per_minute".parse().unwrap();
+		let cells = columns.group_cells(&groups[1], false, &NumberFormat::default());
+		assert_eq!(cells[0], "");
+		assert_eq!(cells[1], "▾ host-b (2 nodes)");
+		assert_eq!(cells[3].trim(), "2");
+		assert_eq!(columns.group_cells(&groups[0], true, &NumberFormat::default())[1], "▸ host-a (1 node)");
+		assert!(group_node_summaries(Vec::new()).is_empty());
+	}
+
+	#[test]
+	fn formats_bytes_in_binary_units() {
+		let number_format = NumberFormat::default();
+		assert_eq!(format_bytes(&number_format, 0), "0 B");
+		assert_eq!(format_bytes(&number_format, 1023), "1023 B");
+		assert_eq!(format_bytes(&number_format, 1536), "1.5 KiB");
+		assert_eq!(format_bytes(&number_format, 5 * 1024 * 1024), "5.0 MiB");
+		assert_eq!(format_bytes(&number_format, 3 * 1024 * 1024 * 1024), "3.0 GiB");
+	}
+
+	#[test]
+	fn centers_rect_within_area() {
+		let area = Rect::new(10, 5, 40, 20);
+		assert_eq!(centered_rect(20, 10, area), Rect::new(20, 10, 20, 10));
+		assert_eq!(centered_rect(21, 9, area), Rect::new(19, 10, 21, 9));
+		assert_eq!(centered_rect(60, 30, area), area);
+	}
+
+	#[test]
+	fn maps_clicks_to_node_rows() {
+		let targets = ClickTargets {
+			node_rows: Rect::new(1, 6, 30, 3),
+			node_logfiles: vec![String::from("a.log"), String::from("b.log")],
+		};
+		assert_eq!(targets.node_at(1, 6), Some(&String::from("a.log")));
+		assert_eq!(targets.node_at(30, 7), Some(&String::from("b.log")));
+		assert_eq!(targets.node_at(5, 8), None); // Row without a node
+		assert_eq!(targets.node_at(5, 5), None); // Header
+		assert_eq!(targets.node_at(0, 6), None); // Border
+		assert_eq!(targets.node_at(31, 6), None);
+		assert_eq!(targets.node_at(5, 9), None);
+		assert_eq!(ClickTargets::default().node_at(0, 0), None);
+	}
+
+	fn gauge_inputs() -> GaugeInputs {
+		GaugeInputs { used_space: 250_000_000, capacity: Some(1_000_000_000), errors_per_minute: 4, peers: 30 }
+	}
+
+	#[test]
+	fn reads_each_primary_gauge_from_the_node() {
+		let dash_state = DashState::new(); // 10 errors per minute at most, full with 50 peers
+		let storage = primary_gauge_reading(PrimaryGauge::Storage, &gauge_inputs(), &dash_state);
+		assert_eq!((storage.name, storage.ratio, storage.full_is_good), ("Chunk storage", Some(0.25), false));
+		assert_eq!(storage.label, "250.0 MB / 1000.0 MB");
+		let unknown = primary_gauge_reading(PrimaryGauge::Storage, &GaugeInputs { capacity: None, ..gauge_inputs() }, &dash_state);
+		assert_eq!((unknown.ratio, unknown.label.as_str()), (None, "250.0 MB used, capacity unknown"));
+
+		let errors = primary_gauge_reading(PrimaryGauge::ErrorRate, &gauge_inputs(), &dash_state);
+		assert_eq!((errors.name, errors.value.as_str(), errors.ratio), ("Errors/min", "4", Some(0.4)));
+		assert_eq!(errors.label, "4 of 10 errors/min");
+		assert!(!errors.full_is_good);
+
+		let peers = primary_gauge_reading(PrimaryGauge::Peers, &gauge_inputs(), &dash_state);
+		assert_eq!((peers.name, peers.value.as_str(), peers.ratio), ("Peers", "30", Some(0.6)));
+		assert_eq!(peers.label, "30 of 50 peers");
+		assert!(peers.full_is_good);
+	}
+
+	#[test]
+	fn parses_primary_gauge() {
+		assert_eq!("storage".parse(), Ok(PrimaryGauge::Storage));
+		assert_eq!("error-rate".parse(), Ok(PrimaryGauge::ErrorRate));
+		assert_eq!("peers".parse(), Ok(PrimaryGauge::Peers));
+		assert_eq!(
+			"cpu".parse::<PrimaryGauge>(),
+			Err(String::from("invalid primary gauge 'cpu', expected storage, error-rate or peers"))
+		);
+	}
+
+	#[test]
+	fn scrolls_the_viewport_with_the_selection() {
+		assert_eq!(viewport_offset(Some(3), 20, 10, 0), 0); // Already in view
+		assert_eq!(viewport_offset(Some(10), 20, 10, 0), 1); // Just below, so scroll one row
+		assert_eq!(viewport_offset(Some(15), 20, 10, 2), 6);
+		assert_eq!(viewport_offset(Some(4), 20, 10, 6), 4); // Above
+		assert_eq!(viewport_offset(Some(19), 20, 10, 0), 10); // Jumped to the bottom
+		assert_eq!(viewport_offset(Some(0), 20, 10, 10), 0); // And back to the top
+		assert_eq!(viewport_offset(None, 20, 10, 7), 7);
+	}
+
+	#[test]
+	fn keeps_the_viewport_full() {
+		assert_eq!(viewport_offset(None, 20, 10, 15), 10); // The list shrank
+		assert_eq!(viewport_offset(Some(2), 5, 10, 3), 0); // Everything fits
+		assert_eq!(viewport_offset(None, 0, 10, 3), 0);
+		assert_eq!(viewport_offset(Some(5), 20, 0, 3), 3); // No rows to show
+	}
+
+	#[test]
+	fn describes_network_status() {
+		assert_eq!(network_status(true, 0), "connected");
+		assert_eq!(network_status(false, 1), "DISCONNECTED (1 drop)");
+		assert_eq!(network_status(true, 3), "connected (3 drops)");
+	}
+
+	#[test]
+	fn describes_version_and_restarts() {
+		assert_eq!(version_status(Some("0.110.0"), 0), "0.110.0");
+		assert_eq!(version_status(Some("0.110.0"), 1), "0.110.0 (1 restart)");
+		assert_eq!(version_status(None, 4), "unknown (4 restarts)");
+	}
+
+	#[test]
+	fn labels_timeline_axis() {
+		use chrono::Duration;
+		let minute = Duration::minutes(1);
+		assert_eq!(
+			timeline_axis(minute, 40, 40),
+			"      -30m      -20m      -10m       now"
+		);
+		// Chart wider than the buckets, which are drawn from the left
+		assert_eq!(timeline_axis(minute, 15, 20), " -10m       now     ");
+		assert_eq!(timeline_axis(Duration::seconds(1), 35, 35), " -30s      -20s      -10s       now");
+		assert_eq!(timeline_axis(Duration::hours(1), 30, 30), "   -1d        -12h         now");
+		assert_eq!(timeline_axis(Duration::days(1), 10, 10), "       now");
+	}
+
+	#[test]
+	fn shows_fewer_ticks_when_narrow() {
+		use chrono::Duration;
+		let minute = Duration::minutes(1);
+		let ticks = |width| timeline_axis(minute, 210, width).split_whitespace().count();
+		assert_eq!(ticks(210), 21);
+		assert_eq!(ticks(40), 4);
+		assert_eq!(ticks(5), 1);
+		assert_eq!(timeline_axis(minute, 210, 2), "  ");
+		assert_eq!(timeline_axis(minute, 0, 4), "    ");
+	}
+
+	#[test]
+	fn formats_spans() {
+		assert_eq!(format_span(45), "45s");
+		assert_eq!(format_span(90), "90s");
+		assert_eq!(format_span(300), "5m");
+		assert_eq!(format_span(7200), "2h");
+		assert_eq!(format_span(7 * 86400), "7d");
+	}
+
+	#[test]
+	fn formats_rates() {
+		let number_format = NumberFormat::default();
+		assert_eq!(format_rate(None, |rate| format_count_rate(&number_format, rate)), "—");
+		assert_eq!(format_rate(Some(12.25), |rate| format_count_rate(&number_format, rate)), "12.2/min");
+		assert_eq!(format_rate(Some(0.0), |rate| format_count_rate(&number_format, rate)), "0.0/min");
+		assert_eq!(format_rate(Some(1536.4), |rate| format_bytes_rate(&number_format, rate)), format!("{}/min", format_bytes(&number_format, 1536)));
+	}
+
+	#[test]
+	fn fits_status_bar_to_width() {
+		let fleet = FleetSummary {
+			nodes: 12,
+			stale: 1,
+			disconnected: 2,
+			errors_per_minute: 7,
+			average_storage_ratio: Some(0.423),
+			..FleetSummary::default()
+		};
+		let full = "12 nodes | 2 disconnected | 1 stale | 7 errors/min | storage 42% full";
+		assert_eq!(status_bar_text(&fleet, 200), full);
+		assert_eq!(status_bar_text(&fleet, full.len() as u16), full);
+		assert_eq!(status_bar_text(&fleet, 60), "12 nodes | 2 disconnected | 1 stale | 7 errors/min");
+		assert_eq!(status_bar_text(&fleet, 30), "12 nodes | 2 disconnected");
+		assert_eq!(status_bar_text(&fleet, 10), "12 nodes");
+		assert_eq!(status_bar_text(&fleet, 5), "12 no");
+		assert_eq!(status_bar_text(&fleet, 0), "");
+		assert_eq!(
+			status_bar_text(&FleetSummary::default(), 200),
+			"0 nodes | 0 disconnected | 0 stale | 0 errors/min | storage unknown"
+		);
+	}
+
+	#[test]
+	fn counts_unhealthy_nodes() {
+		let mut a = summary("a.log", 100, 3, 0);
+		a.is_stale = true;
+		let mut b = summary("b.log", 100, 2, 0);
+		b.is_disconnected = true;
+		b.is_stale = true;
+		let fleet = summarise_fleet(&[a, b, summary("c.log", 100, 0, 0)]);
+		assert_eq!((fleet.stale, fleet.disconnected, fleet.errors_per_minute), (2, 1, 5));
+	}
+
+	#[test]
+	fn leaves_disabled_nodes_out_of_the_fleet() {
+		let mut a = summary("a.log", 100, 3, 10);
+		a.storage_ratio = Some(0.2);
+		let mut b = summary("b.log", 500, 7, 20);
+		b.storage_ratio = Some(0.8);
+		b.is_stale = true;
+		b.enabled = false;
+		let fleet = summarise_fleet(&[a, b]);
+		assert_eq!((fleet.nodes, fleet.disabled), (1, 1));
+		assert_eq!((fleet.storage_used, fleet.errors_per_minute, fleet.rewards, fleet.stale), (100, 3, 10, 0));
+		assert_eq!(fleet.average_storage_ratio, Some(0.2));
+		assert!(status_bar_text(&fleet, 80).starts_with("1 nodes (1 disabled) | "));
+	}
+
+	#[test]
+	fn compared_nodes_share_chart_scales() {
+		let node_a: Vec<&[u64]> = vec![&[1, 5, 2], &[0, 0], &[7]];
+		let node_b: Vec<&[u64]> = vec![&[3, 4], &[9, 1], &[]];
+		assert_eq!(shared_chart_maxima(&[node_a.clone(), node_b.clone()]), vec![5, 9, 7]);
+		assert_eq!(shared_chart_maxima(&[node_b, node_a]), vec![5, 9, 7]);
+
+		// Charts missing from one node are scaled by the other
+		let node_c: Vec<&[u64]> = vec![&[2]];
+		let node_d: Vec<&[u64]> = vec![&[1], &[0, 6]];
+		assert_eq!(shared_chart_maxima(&[node_c, node_d]), vec![2, 6]);
+		assert!(shared_chart_maxima(&[]).is_empty());
+	}
+
+	#[test]
+	fn lays_out_panels_for_terminal_size() {
+		let areas = |width, height, panels: &[Panel]| panel_areas(Rect::new(0, 0, width, height), panels);
+
+		// Wide enough: side by side, the graphs taking what the stats leave
+		assert_eq!(areas(120, 20, &NODE_PANELS), vec![Some(Rect::new(0, 0, 40, 20)), Some(Rect::new(40, 0, 80, 20))]);
+		assert_eq!(areas(70, 20, &NODE_PANELS), vec![Some(Rect::new(0, 0, 40, 20)), Some(Rect::new(40, 0, 30, 20))]);
+
+		// Too narrow: stacked, if there is the height
+		assert_eq!(areas(69, 40, &NODE_PANELS), vec![Some(Rect::new(0, 0, 69, 20)), Some(Rect::new(0, 20, 69, 20))]);
+		assert_eq!(areas(50, 28, &NODE_PANELS), vec![Some(Rect::new(0, 0, 50, 20)), Some(Rect::new(0, 20, 50, 8))]);
+
+		// Without the height the least important is hidden, never the most
+		assert_eq!(areas(50, 20, &NODE_PANELS), vec![Some(Rect::new(0, 0, 50, 20)), None]);
+		assert_eq!(areas(20, 10, &NODE_PANELS), vec![Some(Rect::new(0, 0, 20, 10)), None]);
+
+		// As important, the earliest is kept
+		assert_eq!(areas(80, 62, &COMPARE_PANELS), vec![Some(Rect::new(0, 0, 40, 62)), Some(Rect::new(40, 0, 40, 62))]);
+		assert_eq!(areas(79, 62, &COMPARE_PANELS), vec![Some(Rect::new(0, 0, 79, 31)), Some(Rect::new(0, 31, 79, 31))]);
+		assert_eq!(areas(79, 40, &COMPARE_PANELS), vec![Some(Rect::new(0, 0, 79, 40)), None]);
+	}
+
+	#[test]
+	fn gives_hidden_panels_space_to_those_shown() {
+		let area = Rect::new(0, 0, 120, 20);
+		assert_eq!(visible_panel_areas(area, &NODE_PANELS, &[true, true]), panel_areas(area, &NODE_PANELS));
+		assert_eq!(visible_panel_areas(area, &NODE_PANELS, &[false, true]), vec![None, Some(area)]);
+		assert_eq!(visible_panel_areas(area, &NODE_PANELS, &[true, false]), vec![Some(area), None]);
+		assert_eq!(visible_panel_areas(area, &NODE_PANELS, &[false, false]), vec![None, None]);
+
+		// Stacked on a narrow terminal, a hidden panel no longer crowds out another
+		let narrow = Rect::new(0, 0, 50, 20);
+		assert_eq!(visible_panel_areas(narrow, &NODE_PANELS, &[true, true]), vec![Some(narrow), None]);
+		assert_eq!(visible_panel_areas(narrow, &NODE_PANELS, &[false, true]), vec![None, Some(narrow)]);
+
+		let rows = |shown: [bool; 3]| {
+			let heights = [Constraint::Length(20), Constraint::Length(3), Constraint::Min(0)];
+			visible_rows(Rect::new(0, 0, 40, 30), &[(heights[0], shown[0]), (heights[1], shown[1]), (heights[2], shown[2])])
+		};
+		assert_eq!(
+			rows([true, true, true]),
+			vec![Some(Rect::new(0, 0, 40, 20)), Some(Rect::new(0, 20, 40, 3)), Some(Rect::new(0, 23, 40, 7))]
+		);
+		assert_eq!(rows([false, true, true]), vec![None, Some(Rect::new(0, 0, 40, 3)), Some(Rect::new(0, 3, 40, 27))]);
+		assert_eq!(rows([true, false, false]), vec![Some(Rect::new(0, 0, 40, 30)), None, None]); // Taking the rest
+		assert_eq!(rows([false, false, false]), vec![None, None, None]);
+	}
+
+	#[test]
+	fn lays_out_the_dashboard_afresh_when_resized() {
+		for (width, height) in [(120, 40), (80, 24), (200, 60), (40, 10), (20, 3)] {
+			let size = Rect::new(0, 0, width, height);
+			let areas = dashboard_areas(size, ALERT_BANNER_LINES as u16);
+			assert_eq!(areas.len(), 3);
+			assert_eq!(areas.iter().map(|area| area.height).sum::<u16>(), height, "{}x{}", width, height);
+			for (i, area) in areas.iter().enumerate() {
+				assert_eq!((area.x, area.width), (0, width));
+				assert_eq!(size.union(*area), size, "{:?} is off the screen", area);
+				for other in &areas[i + 1..] {
+					assert!(area.area() == 0 || other.area() == 0 || !area.intersects(*other), "{:?} overlaps {:?}", area, other);
+				}
+			}
+			assert_eq!(areas[2].bottom(), height); // The status bar stays at the bottom
+
+			let panels: Vec<Rect> = panel_areas(areas[1], &NODE_PANELS).into_iter().flatten().collect();
+			for (i, panel) in panels.iter().enumerate() {
+				assert_eq!(areas[1].union(*panel), areas[1]);
+				assert!(panels[i + 1..].iter().all(|other| !panel.intersects(*other)), "{}x{}", width, height);
+			}
+		}
+	}
+
+	#[test]
+	fn flashes_changed_values_then_fades() {
+		let flashing: Vec<&str> = SUMMARY_COLUMNS.iter().filter(|column| column.flashes()).map(|column| column.name).collect();
+		assert!(flashing.contains(&"puts") && flashing.contains(&"health"));
+		assert!(!flashing.contains(&"name") && !flashing.contains(&"uptime"));
+
+		let theme = Theme::default();
+		assert_eq!(fading_style(theme.changed_value), Style::default().add_modifier(Modifier::BOLD));
+		assert_eq!(fading_style(Theme::monochrome().changed_value), Style::default().add_modifier(Modifier::BOLD));
+	}
+
+	#[test]
+	fn labels_timeline_windows() {
+		assert_eq!(format_window(chrono::Duration::minutes(210)), "3h 30m");
+		assert_eq!(format_window(chrono::Duration::days(13)), "13d 0h");
+		assert_eq!(format_window(chrono::Duration::days(210)), "30 weeks");
+		assert_eq!(format_window(chrono::Duration::weeks(210)), "4 years");
+	}
+
+	#[test]
+	fn summarises_alerts_beyond_the_banner() {
+		let alert = |node: &str, severity: Severity| Alert {
+			node: node.to_string(),
+			rule: String::from("peers < 5"),
+			severity,
+			value: 3.0,
+			acknowledged: false,
+		};
+		let alerts = vec![alert("a", Severity::Critical), Alert { acknowledged: true, ..alert("b", Severity::Warning) }];
+		assert_eq!(alert_banner_lines(&alerts, 3), vec![
+			(Severity::Critical, String::from("CRITICAL a: peers < 5 (3)"), false),
+			(Severity::Warning, String::from("WARNING b: peers < 5 (3) - acknowledged"), true),
+		]);
+
+		let alerts: Vec<Alert> = ["a", "b", "c", "d"].iter().map(|node| alert(node, Severity::Info)).collect();
+		let lines = alert_banner_lines(&alerts, 3);
+		assert_eq!(lines.len(), 3);
+		assert_eq!(lines[1].1, "INFO b: peers < 5 (3)");
+		assert_eq!(lines[2].1, "... and 2 more alerts");
+	}
+
+	#[test]
+	fn copies_node_stats_as_drawn() {
+		use crate::custom::opt::Opt;
+		use structopt::StructOpt;
+
+		let dash_state = DashState::new();
+		let mut monitor = LogMonitor::new(&Opt::from_iter(vec!["vdash"]), "/nodes/node-1/safenode.log".to_string(), 10);
+		monitor.index = 0; // Numbered as created, so as the first node whichever tests run
+		for line in &[
+			" INFO 2022-01-15T20:21:02.659471Z [sn/src/node/mod.rs:L211]: StoredNewChunk 8a3f",
+			" INFO 2022-01-15T20:21:03.659471Z [sn/src/node/mod.rs:L211]: StoredNewChunk 9b4e",
+		] {
+			monitor.metrics.gather_metrics(line, &chrono::Utc::now()).unwrap();
+		}
+		let now = chrono::TimeZone::ymd(&chrono::Utc, 2022, 1, 15).and_hms(20, 21, 10);
+		let text = node_stats_text(&dash_state, &monitor, &now);
+		let lines: Vec<&str> = text.lines().collect();
+		assert_eq!(lines[0], "Node  1 Status (waiting for file)"); // As there is no such file
+		assert_eq!(lines[1], "Node");
+		assert!(lines.contains(&"PUTS        :            2"), "{}", text);
+		assert!(lines.contains(&"Last line   :       6s ago"), "{}", text);
+		assert!(lines.contains(&"PeerId      :      unknown"), "{}", text);
+		assert!(text.ends_with('\n'));
+
+		// A line for each drawn, less the empty subheading of lifetime counters
+		let stats = node_stats(&dash_state, &monitor, &now);
+		assert_eq!(lines.len(), 1 + stats.iter().filter(|stat| **stat != NodeStat::Subheading(String::new())).count());
+	}
+}
@@ -5,18 +5,25 @@ use std::collections::HashMap;
 
 use tui::{
 	backend::Backend,
+	layout::Rect,
 	Frame
 };
 
 pub fn draw_dashboard<B: Backend>(
 	f: &mut Frame<B>,
+	area: Rect,
 	dash_state: &DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
 	match dash_state.main_view {
 		DashViewMain::DashSummary => {}
 		DashViewMain::DashNode => {}
-		DashViewMain::DashDebug => draw_debug_dashboard(f, dash_state, monitors),
+		DashViewMain::DashDetail => {}
+		DashViewMain::DashCompare => {}
+		DashViewMain::DashWarnings => {}
+		DashViewMain::DashDiagnostics => {}
+		DashViewMain::DashHeatmap => {}
+		DashViewMain::DashDebug => draw_debug_dashboard(f, area, dash_state, monitors),
 	}
 }
 
@@ -24,12 +31,13 @@ use super::ui::draw_logfile;
 
 fn draw_debug_dashboard<B: Backend>(
 	f: &mut Frame<B>,
-	_dash_state: &DashState,
+	area: Rect,
+	dash_state: &DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
-	for (logfile, monitor) in monitors.iter_mut() {
+	for monitor in monitors.values_mut() {
 		if monitor.is_debug_dashboard_log {
-			draw_logfile(f, f.size(), logfile, monitor);
+			draw_logfile(f, area, &dash_state.theme, monitor);
 		}
 	}
 }
@@ -0,0 +1,232 @@
+///! Metrics defined with --metrics-config, for things vdash doesn't parse itself
+///!
+///! The TOML file lists metrics, each with a regex which is matched against
+///! every logfile line. A metric counts matching lines, or with a capture
+///! group is set to the number captured, or adds it up with 'add = true':
+///!
+///!	[[metric]]
+///!	name = "Replications"
+///!	pattern = "Replicated chunk [0-9a-f]+"
+///!
+///!	[[metric]]
+///!	name = "Store cost"
+///!	pattern = "store cost is (\\d+)"
+///!	group = 1
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+struct MetricsFile {
+	#[serde(default)]
+	metric: Vec<MetricEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MetricEntry {
+	name: String,
+	pattern: String,
+	group: Option<usize>,
+	#[serde(default)]
+	add: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UserMetricKind {
+	Count,      // Matching lines
+	Set(usize), // Number captured by a group, replacing the last
+	Add(usize), // Total of the numbers captured by a group
+}
+
+pub struct UserMetricSpec {
+	pub name: String,
+	pub kind: UserMetricKind,
+	regex: Regex,
+}
+
+impl UserMetricSpec {
+	pub fn is_match(&self, line: &str) -> bool {
+		self.regex.is_match(line)
+	}
+}
+
+///! Compile the metrics of a --metrics-config file
+pub fn load_user_metric_specs(path: &Path) -> Result<Vec<UserMetricSpec>, String> {
+	let toml = std::fs::read_to_string(path)
+		.map_err(|e| format!("failed to read metrics config {}: {}", path.display(), e))?;
+	user_metric_specs_from_toml(&toml).map_err(|e| format!("invalid metrics config {}: {}", path.display(), e))
+}
+
+pub fn user_metric_specs_from_toml(toml: &str) -> Result<Vec<UserMetricSpec>, String> {
+	let file: MetricsFile = toml::from_str(toml).map_err(|e| e.to_string())?;
+	let mut specs = Vec::<UserMetricSpec>::new();
+	for entry in file.metric {
+		if specs.iter().any(|spec| spec.name == entry.name) {
+			return Err(format!("metric '{}' is defined more than once", entry.name));
+		}
+		let regex = Regex::new(&entry.pattern)
+			.map_err(|e| format!("metric '{}' has an invalid pattern: {}", entry.name, e))?;
+		let kind = match (entry.group, entry.add) {
+			(None, false) => UserMetricKind::Count,
+			(None, true) => return Err(format!("metric '{}' needs a group to add up", entry.name)),
+			(Some(group), _) if group == 0 || group >= regex.captures_len() => {
+				return Err(format!(
+					"metric '{}' uses group {} but its pattern has {} group(s)",
+					entry.name,
+					group,
+					regex.captures_len() - 1
+				))
+			}
+			(Some(group), false) => UserMetricKind::Set(group),
+			(Some(group), true) => UserMetricKind::Add(group),
+		};
+		specs.push(UserMetricSpec { name: entry.name, kind, regex });
+	}
+	Ok(specs)
+}
+
+///! The values of the user's metrics for one node
+#[derive(Clone, Default)]
+pub struct UserMetrics {
+	specs: Arc<Vec<UserMetricSpec>>,
+	values: Vec<Option<u64>>, // None until a Set metric has matched
+}
+
+impl UserMetrics {
+	pub fn new(specs: Arc<Vec<UserMetricSpec>>) -> UserMetrics {
+		let values = specs
+			.iter()
+			.map(|spec| match spec.kind {
+				UserMetricKind::Set(_) => None,
+				_ => Some(0),
+			})
+			.collect();
+		UserMetrics { specs, values }
+	}
+
+	///! Update each metric whose pattern matches the line. Returns true if any did
+	pub fn process_line(&mut self, line: &str) -> bool {
+		let mut matched = false;
+		for (spec, value) in self.specs.iter().zip(self.values.iter_mut()) {
+			let captures = match spec.regex.captures(line) {
+				Some(captures) => captures,
+				None => continue,
+			};
+			let captured = |group: usize| captures.get(group).and_then(|m| m.as_str().parse::<u64>().ok());
+			match spec.kind {
+				UserMetricKind::Count => *value = Some(value.unwrap_or(0) + 1),
+				UserMetricKind::Set(group) => match captured(group) {
+					Some(number) => *value = Some(number),
+					None => continue,
+				},
+				UserMetricKind::Add(group) => match captured(group) {
+					Some(number) => *value = Some(value.unwrap_or(0).saturating_add(number)),
+					None => continue,
+				},
+			}
+			matched = true;
+		}
+		matched
+	}
+
+	///! Zero the counts and totals, leaving values which are set
+	pub fn reset(&mut self) {
+		for (spec, value) in self.specs.iter().zip(self.values.iter_mut()) {
+			if !matches!(spec.kind, UserMetricKind::Set(_)) {
+				*value = Some(0);
+			}
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.specs.is_empty()
+	}
+
+	///! Each metric's name and value, in the order they were defined
+	pub fn values(&self) -> impl Iterator<Item = (&str, Option<u64>)> {
+		self.specs.iter().map(|spec| spec.name.as_str()).zip(self.values.iter().copied())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const CONFIG: &str = r#"
+		[[metric]]
+		name = "Replications"
+		pattern = "Replicated chunk [0-9a-f]+"
+
+		[[metric]]
+		name = "Store cost"
+		pattern = "store cost is (\\d+)"
+		group = 1
+
+		[[metric]]
+		name = "Paid"
+		pattern = "paid (\\d+) nanos"
+		group = 1
+		add = true
+	"#;
+
+	fn user_metrics() -> UserMetrics {
+		UserMetrics::new(Arc::new(user_metric_specs_from_toml(CONFIG).unwrap()))
+	}
+
+	#[test]
+	fn updates_metrics_from_matching_lines() {
+		let mut metrics = user_metrics();
+		assert_eq!(
+			metrics.values().collect::<Vec<_>>(),
+			vec![("Replications", Some(0)), ("Store cost", None), ("Paid", Some(0))]
+		);
+
+		assert!(metrics.process_line("INFO Replicated chunk 8a3f to 4 peers"));
+		assert!(metrics.process_line("INFO Replicated chunk 91bc to 4 peers"));
+		assert!(metrics.process_line("DEBUG store cost is 120"));
+		assert!(metrics.process_line("DEBUG store cost is 95"));
+		assert!(metrics.process_line("INFO paid 10 nanos"));
+		assert!(metrics.process_line("INFO paid 15 nanos"));
+		assert_eq!(
+			metrics.values().collect::<Vec<_>>(),
+			vec![("Replications", Some(2)), ("Store cost", Some(95)), ("Paid", Some(25))]
+		);
+
+		metrics.reset();
+		assert_eq!(
+			metrics.values().collect::<Vec<_>>(),
+			vec![("Replications", Some(0)), ("Store cost", Some(95)), ("Paid", Some(0))]
+		);
+	}
+
+	#[test]
+	fn ignores_non_matching_lines() {
+		let mut metrics = user_metrics();
+		assert!(!metrics.process_line("INFO Replicated record 8a3f"));
+		assert!(!metrics.process_line("DEBUG store cost is unknown"));
+		assert!(!metrics.process_line(""));
+		assert_eq!(
+			metrics.values().collect::<Vec<_>>(),
+			vec![("Replications", Some(0)), ("Store cost", None), ("Paid", Some(0))]
+		);
+		assert!(UserMetrics::default().is_empty());
+	}
+
+	#[test]
+	fn reports_bad_config() {
+		let error = |toml: &str| user_metric_specs_from_toml(toml).err().unwrap();
+		assert!(error("[[metric]]\nname = \"Bad\"\npattern = \"(unclosed\"").contains("metric 'Bad' has an invalid pattern"));
+		assert_eq!(
+			error("[[metric]]\nname = \"Bad\"\npattern = \"cost (\\\\d+)\"\ngroup = 2"),
+			"metric 'Bad' uses group 2 but its pattern has 1 group(s)"
+		);
+		assert_eq!(error("[[metric]]\nname = \"Bad\"\npattern = \"x\"\nadd = true"), "metric 'Bad' needs a group to add up");
+		assert_eq!(
+			error("[[metric]]\nname = \"A\"\npattern = \"x\"\n[[metric]]\nname = \"A\"\npattern = \"y\""),
+			"metric 'A' is defined more than once"
+		);
+		assert!(user_metric_specs_from_toml("").unwrap().is_empty());
+	}
+}
@@ -0,0 +1,229 @@
+///! Checking the whole configuration without starting, for --validate
+///!
+///! Every option which names a file, pattern, address or timeline is checked
+///! in turn and the problems of all of them reported together, where starting
+///! normally stops at the first. Options which fail to parse at all, such as
+///! an unknown --view, are still reported alone by the argument parser
+use std::fmt;
+use std::path::Path;
+
+use crate::custom::alerts::load_alert_rules;
+use crate::custom::app::NodeMetrics;
+use crate::custom::capacity::NodeCapacities;
+use crate::custom::endpoint::split_http_url;
+use crate::custom::heatmap::find_heatmap_metric;
+use crate::custom::names::NodeNames;
+use crate::custom::opt::{expand_logfile_paths, Opt};
+use crate::custom::prometheus::{metrics_tls_config, resolve_listen_addr};
+use crate::custom::spotlight::load_spotlight_keywords;
+use crate::custom::theme::Theme;
+use crate::custom::timelines::TimelineSpec;
+use crate::custom::ui::format_elapsed;
+use crate::custom::user_metrics::load_user_metric_specs;
+
+///! The outcome of checking each part of the configuration, in order
+pub struct ValidationReport {
+	pub checks: Vec<(String, Result<(), String>)>, // What was checked, and what is wrong with it
+}
+
+impl ValidationReport {
+	pub fn errors(&self) -> Vec<&str> {
+		self.checks.iter().filter_map(|(_name, result)| result.as_ref().err()).map(|e| e.as_str()).collect()
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.errors().is_empty()
+	}
+}
+
+impl fmt::Display for ValidationReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (name, result) in &self.checks {
+			match result {
+				Ok(()) => writeln!(f, "ok     {}", name)?,
+				Err(e) => writeln!(f, "ERROR  {}: {}", name, e)?,
+			}
+		}
+		match self.errors().len() {
+			0 => writeln!(f, "The configuration is valid"),
+			1 => writeln!(f, "1 problem found"),
+			count => writeln!(f, "{} problems found", count),
+		}
+	}
+}
+
+///! Check every part of the configuration given by opt
+pub fn validate_config(opt: &Opt) -> ValidationReport {
+	let mut checks = Vec::<(String, Result<(), String>)>::new();
+	let mut check = |name: String, result: Result<(), String>| checks.push((name, result));
+
+	let logfiles = if opt.files.is_empty() {
+		Err(String::from("no logfile(s) specified"))
+	} else {
+		expand_logfile_paths(&opt.files).map(|_files| ())
+	};
+	check(String::from("logfiles"), logfiles);
+	if let Some(replay) = &opt.replay {
+		check(format!("--replay {}", replay), file_exists(Path::new(replay)));
+	}
+	check(String::from("--timeline-steps"), validate_timelines(&opt.timeline_steps.timelines));
+	let timeline_names: Vec<String> = NodeMetrics::new(opt).timelines().iter().map(|timeline| timeline.get_name().clone()).collect();
+	check(
+		format!("--heatmap-metric {}", opt.heatmap_metric),
+		find_heatmap_metric(&opt.heatmap_metric, &timeline_names).map(|_metric| ()),
+	);
+	check(
+		String::from("theme"),
+		Theme::resolve(opt.theme.as_deref(), opt.palette, opt.no_color).map(|_theme| ()),
+	);
+	if let Some(path) = &opt.spotlight {
+		check(format!("--spotlight {}", path.display()), load_spotlight_keywords(path).map(|_keywords| ()));
+	}
+	if let Some(path) = &opt.node_capacities {
+		check(format!("--node-capacities {}", path.display()), NodeCapacities::load(path).map(|_capacities| ()));
+	}
+	if let Some(path) = &opt.names {
+		check(format!("--names {}", path.display()), NodeNames::load(path).map(|_names| ()));
+	}
+	if let Some(path) = &opt.metrics_config {
+		check(format!("--metrics-config {}", path.display()), load_user_metric_specs(path).map(|_specs| ()));
+	}
+	if let Some(path) = &opt.alerts {
+		check(format!("--alerts {}", path.display()), load_alert_rules(path).map(|_rules| ()));
+	}
+	if let Some(path) = &opt.log_file {
+		check(format!("--log-file {}", path.display()), directory_exists(path));
+	}
+	if let Some(path) = &opt.debug_log {
+		check(format!("--debug-log {}", path.display()), directory_exists(path));
+	}
+	if opt.metrics_tls_cert.is_some() || opt.metrics_tls_key.is_some() {
+		let tls = metrics_tls_config(opt.metrics_tls_cert.as_deref(), opt.metrics_tls_key.as_deref());
+		check(String::from("metrics TLS"), tls.map(|_tls| ()));
+	}
+	if let Some(addr) = &opt.metrics_addr {
+		check(format!("--metrics-addr {}", addr), resolve_listen_addr(addr).map(|_addrs| ()));
+	}
+	if let Some(path) = &opt.query_socket {
+		check(format!("--query-socket {}", path.display()), directory_exists(path));
+	}
+	for (logfile, url) in &opt.node_metrics_urls {
+		check(format!("--node-metrics-url {}={}", logfile, url), resolve_http_url(url));
+	}
+	ValidationReport { checks }
+}
+
+// Timelines shorter than one before them, such as '1m:60,1h:1', are likely mistakes
+fn validate_timelines(timelines: &[TimelineSpec]) -> Result<(), String> {
+	let mut problems = Vec::<String>::new();
+	for pair in timelines.windows(2) {
+		let (shorter, longer) = (&pair[0], &pair[1]);
+		if longer.window() < shorter.window() {
+			problems.push(format!(
+				"{} cover {}, less than the {} of {}",
+				longer.name,
+				format_elapsed(longer.window()),
+				format_elapsed(shorter.window()),
+				shorter.name
+			));
+		}
+	}
+	if problems.is_empty() { Ok(()) } else { Err(problems.join("; ")) }
+}
+
+fn file_exists(path: &Path) -> Result<(), String> {
+	if path.is_file() {
+		Ok(())
+	} else {
+		Err(format!("{} is not a file", path.display()))
+	}
+}
+
+// Files which are created if need be, so only their directory need exist
+fn directory_exists(path: &Path) -> Result<(), String> {
+	match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+			Err(format!("directory {} does not exist", parent.display()))
+		}
+		_ => Ok(()),
+	}
+}
+
+fn resolve_http_url(url: &str) -> Result<(), String> {
+	use std::net::ToSocketAddrs;
+	let (host, _path) = split_http_url(url)?;
+	match host.to_socket_addrs().map(|mut addrs| addrs.next()) {
+		Ok(Some(_addr)) => Ok(()),
+		Ok(None) => Err(format!("can't resolve '{}': no addresses found", host)),
+		Err(e) => Err(format!("can't resolve '{}': {}", host, e)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+	use structopt::StructOpt;
+
+	#[test]
+	fn reports_every_problem() {
+		let mut metrics_config = tempfile::NamedTempFile::new().unwrap();
+		writeln!(metrics_config, "[[metric]]\nname = \"Broken\"\npattern = \"(unclosed\"").unwrap();
+		let metrics_config = metrics_config.path().to_string_lossy().to_string();
+		let opt = Opt::from_iter(vec![
+			"vdash",
+			"--metrics-config",
+			&metrics_config,
+			"--alerts",
+			"/nonexistent/alerts.toml",
+			"--heatmap-metric",
+			"cpu",
+			"--metrics-addr",
+			"localhost",
+			"--metrics-tls-key",
+			"key.pem",
+			"--timeline-steps",
+			"1m:1000,1h:10",
+			"--log-file",
+			"/nonexistent/vdash.log",
+		]);
+		let report = validate_config(&opt);
+		let failed: Vec<&str> = report
+			.checks
+			.iter()
+			.filter(|(_name, result)| result.is_err())
+			.map(|(name, _result)| name.as_str().split(' ').next().unwrap())
+			.collect();
+		assert_eq!(
+			failed,
+			vec![
+				"logfiles",
+				"--timeline-steps",
+				"--heatmap-metric",
+				"--metrics-config",
+				"--alerts",
+				"--log-file",
+				"metrics",
+				"--metrics-addr",
+			]
+		);
+		assert!(!report.is_valid());
+		let errors = report.errors();
+		assert_eq!(errors[1], "1 hour columns cover 10h 0m, less than the 16h 40m of 1 minute columns");
+		assert!(errors[3].contains("invalid pattern"), "{}", errors[3]);
+		assert_eq!(errors[6], "--metrics-tls-key needs --metrics-tls-cert");
+		let text = report.to_string();
+		assert!(text.contains("ok     theme\n"), "{}", text);
+		assert!(text.ends_with("8 problems found\n"), "{}", text);
+	}
+
+	#[test]
+	fn passes_a_valid_configuration() {
+		let logfile = tempfile::NamedTempFile::new().unwrap();
+		let logfile = logfile.path().to_string_lossy().to_string();
+		let opt = Opt::from_iter(vec!["vdash", "--metrics-addr", "127.0.0.1:9100", "--timeline-steps", "1m:60,1h:48", &logfile]);
+		let report = validate_config(&opt);
+		assert!(report.is_valid(), "{}", report);
+		assert!(report.to_string().ends_with("The configuration is valid\n"));
+	}
+}
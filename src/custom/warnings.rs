@@ -0,0 +1,174 @@
+///! The most recent WARN and ERROR lines of every node, for the warnings view
+///!
+///! Each node adds its warnings and errors to one buffer shared by all the
+///! nodes as its lines are classified, so the view shows them in the order
+///! they were read, tagged with the node and the time they were logged. Up to
+///! --warnings-max lines are kept, dropping the oldest
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::custom::app::LogLevel;
+
+pub type SharedWarnings = Arc<Mutex<RecentWarnings>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WarningEntry {
+	pub node: String, // The name of the node, see --names
+	pub time: DateTime<Utc>,
+	pub level: LogLevel,
+	pub line: String,
+}
+
+impl WarningEntry {
+	///! As shown by the warnings view, e.g. '20:21:02 node-1: WARN ...'
+	pub fn describe(&self) -> String {
+		format!("{} {}: {}", self.time.format("%H:%M:%S"), self.node, self.line.trim())
+	}
+}
+
+///! A fixed capacity ring buffer of warnings, oldest first
+pub struct RecentWarnings {
+	entries: VecDeque<WarningEntry>,
+	capacity: usize,
+	scroll: usize, // Entries hidden below the view, 0 to show the newest
+}
+
+impl RecentWarnings {
+	pub fn new(capacity: usize) -> RecentWarnings {
+		RecentWarnings {
+			entries: VecDeque::with_capacity(capacity),
+			capacity,
+			scroll: 0,
+		}
+	}
+
+	pub fn shared(capacity: usize) -> SharedWarnings {
+		Arc::new(Mutex::new(RecentWarnings::new(capacity)))
+	}
+
+	///! Add an entry, dropping the oldest once full. A view scrolled back
+	///! stays on the entries it shows
+	pub fn push(&mut self, entry: WarningEntry) {
+		if self.capacity == 0 {
+			return;
+		}
+		if self.entries.len() == self.capacity {
+			self.entries.pop_front();
+		}
+		self.entries.push_back(entry);
+		if self.scroll > 0 {
+			self.scroll = (self.scroll + 1).min(self.entries.len() - 1);
+		}
+	}
+
+	///! The entries held, oldest first
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &WarningEntry> + ExactSizeIterator {
+		self.entries.iter()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	pub fn scroll(&self) -> usize {
+		self.scroll
+	}
+
+	pub fn scroll_up(&mut self, entries: usize) {
+		self.scroll = (self.scroll + entries).min(self.entries.len().saturating_sub(1));
+	}
+
+	pub fn scroll_down(&mut self, entries: usize) {
+		self.scroll = self.scroll.saturating_sub(entries);
+	}
+
+	///! Scroll to the oldest entry, or back to following the newest
+	pub fn scroll_to_end(&mut self, newest: bool) {
+		self.scroll = if newest { 0 } else { self.entries.len().saturating_sub(1) };
+	}
+
+	///! The entries to show in a window of the given number of rows, oldest first
+	pub fn visible(&self, rows: usize) -> impl Iterator<Item = &WarningEntry> {
+		let end = self.entries.len() - self.scroll;
+		let start = end.saturating_sub(rows);
+		let end = end.max((start + rows).min(self.entries.len()));
+		self.entries.range(start..end)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::{Duration, TimeZone};
+
+	fn entry(node: &str, seconds: i64, level: LogLevel) -> WarningEntry {
+		WarningEntry {
+			node: node.to_string(),
+			time: Utc.ymd(2022, 1, 15).and_hms(20, 21, 0) + Duration::seconds(seconds),
+			level,
+			line: format!("{:?} from {}", level, node),
+		}
+	}
+
+	fn nodes(warnings: &RecentWarnings) -> Vec<&str> {
+		warnings.iter().map(|entry| entry.node.as_str()).collect()
+	}
+
+	#[test]
+	fn keeps_warnings_of_all_nodes_in_order_read() {
+		let mut warnings = RecentWarnings::new(10);
+		assert!(warnings.is_empty());
+		warnings.push(entry("node-1", 0, LogLevel::Warn));
+		warnings.push(entry("node-2", 1, LogLevel::Error));
+		warnings.push(entry("node-1", 2, LogLevel::Error));
+		assert_eq!(nodes(&warnings), vec!["node-1", "node-2", "node-1"]);
+		assert_eq!(warnings.iter().last().unwrap().describe(), "20:21:02 node-1: Error from node-1");
+		assert_eq!(warnings.visible(2).map(|entry| entry.time.timestamp() % 60).collect::<Vec<_>>(), vec![1, 2]);
+	}
+
+	#[test]
+	fn evicts_the_oldest_beyond_capacity() {
+		let mut warnings = RecentWarnings::new(2);
+		for (i, node) in ["a", "b", "c", "d"].iter().enumerate() {
+			warnings.push(entry(node, i as i64, LogLevel::Warn));
+		}
+		assert_eq!(warnings.len(), warnings.capacity());
+		assert_eq!(nodes(&warnings), vec!["c", "d"]);
+
+		let mut none_kept = RecentWarnings::new(0);
+		none_kept.push(entry("a", 0, LogLevel::Error));
+		assert!(none_kept.is_empty());
+	}
+
+	#[test]
+	fn scrolls_within_the_warnings_held() {
+		let mut warnings = RecentWarnings::new(4);
+		for (i, node) in ["a", "b", "c"].iter().enumerate() {
+			warnings.push(entry(node, i as i64, LogLevel::Warn));
+		}
+		let shown = |warnings: &RecentWarnings| warnings.visible(2).map(|entry| entry.node.clone()).collect::<Vec<_>>();
+		assert_eq!(shown(&warnings), vec!["b", "c"]);
+		warnings.scroll_up(1);
+		assert_eq!(shown(&warnings), vec!["a", "b"]);
+
+		// New entries don't move a view scrolled back
+		warnings.push(entry("d", 3, LogLevel::Warn));
+		assert_eq!(shown(&warnings), vec!["a", "b"]);
+
+		warnings.scroll_up(100);
+		assert_eq!(shown(&warnings), vec!["a", "b"]);
+		warnings.scroll_to_end(true);
+		assert_eq!(shown(&warnings), vec!["c", "d"]);
+		warnings.scroll_down(1);
+		assert_eq!(warnings.scroll(), 0);
+	}
+}
@@ -1,21 +1,28 @@
 #![allow(dead_code)]
 use std::io;
 use std::sync::{
-	atomic::{AtomicBool, Ordering},
+	atomic::{AtomicBool, AtomicU64, Ordering},
 	Arc,
 };
 use std::thread;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use termion::event::Key;
+use termion::event::{Event as TermionEvent, Key};
 use termion::input::TermRead;
 
 pub enum Event<I> {
 	Input(I),
+	Focus(bool), // The terminal gained (true) or lost focus
 	Tick,
 }
 
+// Sent by terminals with focus reporting enabled, see ENABLE_FOCUS_REPORTING
+const FOCUS_GAINED: &[u8] = b"\x1b[I";
+const FOCUS_LOST: &[u8] = b"\x1b[O";
+pub const ENABLE_FOCUS_REPORTING: &str = "\x1b[?1004h";
+pub const DISABLE_FOCUS_REPORTING: &str = "\x1b[?1004l";
+
 /// A small event handler that wrap termion input and tick events. Each event
 /// type is handled in its own thread and returned to a common `Receiver`
 pub struct Events {
@@ -23,12 +30,29 @@ pub struct Events {
 	input_handle: thread::JoinHandle<()>,
 	ignore_exit_key: Arc<AtomicBool>,
 	tick_handle: thread::JoinHandle<()>,
+	tick_rate: TickRate,
+}
+
+/// The time between ticks, which may be changed while they are sent
+#[derive(Clone)]
+pub struct TickRate(Arc<AtomicU64>); // In milliseconds
+
+impl TickRate {
+	/// Takes effect once the current wait between ticks is over
+	pub fn set(&self, tick_rate: Duration) {
+		self.0.store(tick_rate.as_millis() as u64, Ordering::Relaxed);
+	}
+
+	fn get(&self) -> Duration {
+		Duration::from_millis(self.0.load(Ordering::Relaxed))
+	}
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
 	pub exit_key: Key,
 	pub tick_rate: Duration,
+	pub keys_from_tty: bool, // For when stdin is not the keyboard
 }
 
 impl Default for Config {
@@ -36,10 +60,17 @@ impl Default for Config {
 		Config {
 			exit_key: Key::Char('q'),
 			tick_rate: Duration::from_millis(250),
+			keys_from_tty: false,
 		}
 	}
 }
 
+impl Default for Events {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Events {
 	pub fn new() -> Events {
 		Events::with_config(Config::default())
@@ -48,30 +79,25 @@ impl Events {
 	pub fn with_config(config: Config) -> Events {
 		let (tx, rx) = mpsc::unbounded_channel();
 		let ignore_exit_key = Arc::new(AtomicBool::new(false));
+		let tick_rate = TickRate(Arc::new(AtomicU64::new(config.tick_rate.as_millis() as u64)));
 		let input_handle = {
 			let tx = tx.clone();
 			let ignore_exit_key = ignore_exit_key.clone();
 			thread::spawn(move || {
-				let stdin = io::stdin();
-				for evt in stdin.keys() {
-					if let Ok(key) = evt {
-						if let Err(err) = tx.send(Event::Input(key)) {
-							eprintln!("{}", err);
-							return;
-						}
-						if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
-							return;
-						}
-					}
-				}
+				let events: Box<dyn Iterator<Item = io::Result<TermionEvent>>> = match termion::get_tty() {
+					Ok(tty) if config.keys_from_tty => Box::new(tty.events()),
+					_ => Box::new(io::stdin().events()),
+				};
+				forward_input(events.flatten(), &tx, config.exit_key, &ignore_exit_key);
 			})
 		};
 		let tick_handle = {
+			let tick_rate = tick_rate.clone();
 			thread::spawn(move || loop {
 				if tx.send(Event::Tick).is_err() {
 					break;
 				}
-				thread::sleep(config.tick_rate);
+				thread::sleep(tick_rate.get());
 			})
 		};
 		Events {
@@ -79,9 +105,14 @@ impl Events {
 			ignore_exit_key,
 			input_handle,
 			tick_handle,
+			tick_rate,
 		}
 	}
 
+	pub fn tick_rate(&self) -> TickRate {
+		self.tick_rate.clone()
+	}
+
 	pub fn disable_exit_key(&mut self) {
 		self.ignore_exit_key.store(true, Ordering::Relaxed);
 	}
@@ -90,3 +121,58 @@ impl Events {
 		self.ignore_exit_key.store(false, Ordering::Relaxed);
 	}
 }
+
+/// Send keys and focus changes until the receiver is dropped, or until the
+/// exit key has been sent unless that is disabled
+fn forward_input(
+	events: impl Iterator<Item = TermionEvent>,
+	tx: &mpsc::UnboundedSender<Event<Key>>,
+	exit_key: Key,
+	ignore_exit_key: &AtomicBool,
+) {
+	for event in events {
+		let event = match event {
+			TermionEvent::Key(key) => Event::Input(key),
+			TermionEvent::Unsupported(bytes) if bytes == FOCUS_GAINED => Event::Focus(true),
+			TermionEvent::Unsupported(bytes) if bytes == FOCUS_LOST => Event::Focus(false),
+			_ => continue,
+		};
+		let is_exit_key = matches!(event, Event::Input(key) if key == exit_key);
+		if let Err(err) = tx.send(event) {
+			eprintln!("{}", err);
+			return;
+		}
+		if !ignore_exit_key.load(Ordering::Relaxed) && is_exit_key {
+			return;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn forwarded_keys(typed: &str, ignore_exit_key: bool) -> Vec<Key> {
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let events = typed.chars().map(|c| TermionEvent::Key(Key::Char(c)));
+		forward_input(events, &tx, Config::default().exit_key, &AtomicBool::new(ignore_exit_key));
+		let mut keys = Vec::new();
+		while let Ok(event) = rx.try_recv() {
+			if let Event::Input(key) = event {
+				keys.push(key);
+			}
+		}
+		keys
+	}
+
+	fn keys(typed: &str) -> Vec<Key> {
+		typed.chars().map(Key::Char).collect()
+	}
+
+	#[test]
+	fn keeps_forwarding_keys_after_q_unless_it_is_the_exit_key() {
+		// With quit bound to another key, as by --bind quit=x
+		assert_eq!(forwarded_keys("qhqx", true), keys("qhqx"));
+		assert_eq!(forwarded_keys("qhqx", false), keys("q"));
+	}
+}
@@ -0,0 +1,110 @@
+///! Logging of vdash's own operation, for troubleshooting vdash rather than
+///! the nodes it monitors
+///!
+///! With --log-file the records of the log macros at --log-level and above are
+///! appended to the file, one line each with the time, level and module, and
+///! never reach the terminal where the dashboard would draw over them. Without
+///! it env_logger writes to stderr as set by RUST_LOG
+use log::{LevelFilter, Log, Metadata, Record};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+///! Parses --log-level, e.g. 'debug'
+pub fn parse_log_level(level: &str) -> Result<LevelFilter, String> {
+	level
+		.parse::<LevelFilter>()
+		.map_err(|_e| format!("invalid log level '{}', expected off, error, warn, info, debug or trace", level))
+}
+
+///! Writes each record at or above its level as a line
+pub struct WriterLogger<W: Write + Send> {
+	level: LevelFilter,
+	writer: Mutex<W>,
+}
+
+impl<W: Write + Send> WriterLogger<W> {
+	pub fn new(writer: W, level: LevelFilter) -> WriterLogger<W> {
+		WriterLogger {
+			level,
+			writer: Mutex::new(writer),
+		}
+	}
+}
+
+impl<W: Write + Send> Log for WriterLogger<W> {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+		let time = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+		if let Ok(mut writer) = self.writer.lock() {
+			// Flushed each time, so that nothing is lost if vdash is killed
+			let _ = writeln!(writer, "{} {:<5} {}: {}", time, record.level(), record.target(), record.args());
+			let _ = writer.flush();
+		}
+	}
+
+	fn flush(&self) {
+		if let Ok(mut writer) = self.writer.lock() {
+			let _ = writer.flush();
+		}
+	}
+}
+
+///! Send vdash's own log records to log_file at level and above, or to
+///! env_logger without one. Call once, before the terminal is set up
+pub fn init_logging(log_file: Option<&Path>, level: LevelFilter) -> std::io::Result<()> {
+	let path = match log_file {
+		Some(path) => path,
+		None => {
+			env_logger::init();
+			return Ok(());
+		}
+	};
+	let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+	// Only fails if a logger is already set, which leaves that one in place
+	if log::set_boxed_logger(Box::new(WriterLogger::new(file, level))).is_ok() {
+		log::set_max_level(level);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use log::Level;
+
+	fn log_at(logger: &WriterLogger<Vec<u8>>, level: Level, message: &str) {
+		logger.log(&Record::builder().level(level).target("vdash::custom::app").args(format_args!("{}", message)).build());
+	}
+
+	#[test]
+	fn writes_records_at_the_level_and_above() {
+		let logger = WriterLogger::new(Vec::<u8>::new(), LevelFilter::Info);
+		log_at(&logger, Level::Info, "opened node-1.log");
+		log_at(&logger, Level::Debug, "parsed a line");
+		log_at(&logger, Level::Warn, "node-1.log was rotated");
+		let written = String::from_utf8(logger.writer.lock().unwrap().clone()).unwrap();
+		let lines: Vec<&str> = written.lines().collect();
+		assert_eq!(lines.len(), 2);
+		assert!(lines[0].ends_with(" INFO  vdash::custom::app: opened node-1.log"), "{}", lines[0]);
+		assert!(lines[1].ends_with(" WARN  vdash::custom::app: node-1.log was rotated"), "{}", lines[1]);
+		assert!(!logger.enabled(&Metadata::builder().level(Level::Trace).build()));
+	}
+
+	#[test]
+	fn parses_log_level() {
+		assert_eq!(parse_log_level("debug"), Ok(LevelFilter::Debug));
+		assert_eq!(parse_log_level("WARN"), Ok(LevelFilter::Warn));
+		assert_eq!(parse_log_level("off"), Ok(LevelFilter::Off));
+		assert_eq!(
+			parse_log_level("loud"),
+			Err(String::from("invalid log level 'loud', expected off, error, warn, info, debug or trace"))
+		);
+	}
+}
@@ -1,3 +1,5 @@
+pub mod logging;
+pub mod terminal;
 pub mod util;
 
 #[cfg(feature = "termion")]
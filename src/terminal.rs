@@ -0,0 +1,61 @@
+///! Restores the terminal when the dashboard exits, whether normally or
+///! after a panic, so it isn't left in raw mode with no cursor
+///!
+///! Each backend sets its own teardown once the terminal is set up, and
+///! restore_terminal() runs it at most once from wherever is first to exit
+use std::panic;
+use std::sync::Mutex;
+
+type Teardown = Box<dyn FnOnce() + Send>;
+
+lazy_static::lazy_static! {
+	static ref TEARDOWN: Mutex<Option<Teardown>> = Mutex::new(None);
+}
+
+///! Set how to leave raw mode, show the cursor and leave the alternate screen
+pub fn set_terminal_teardown(teardown: impl FnOnce() + Send + 'static) {
+	*lock_teardown() = Some(Box::new(teardown));
+}
+
+///! Run the terminal teardown, if there is one and it hasn't already run
+pub fn restore_terminal() {
+	let teardown = lock_teardown().take();
+	if let Some(teardown) = teardown {
+		teardown();
+	}
+}
+
+///! Restore the terminal before the default panic message is printed, so
+///! the message stays visible
+pub fn install_panic_hook() {
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		restore_terminal();
+		default_hook(info);
+	}));
+}
+
+// A panic while the teardown was held must not stop it running later
+fn lock_teardown() -> std::sync::MutexGuard<'static, Option<Teardown>> {
+	TEARDOWN.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	#[test]
+	fn restores_the_terminal_only_once() {
+		let teardowns = Arc::new(AtomicUsize::new(0));
+		let counter = teardowns.clone();
+		set_terminal_teardown(move || {
+			counter.fetch_add(1, Ordering::SeqCst);
+		});
+
+		restore_terminal();
+		restore_terminal();
+		assert_eq!(teardowns.load(Ordering::SeqCst), 1);
+	}
+}
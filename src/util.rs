@@ -58,7 +58,7 @@ pub struct TabsState<'a> {
 }
 
 impl<'a> TabsState<'a> {
-	pub fn new(titles: Vec<&'a str>) -> TabsState {
+	pub fn new(titles: Vec<&'a str>) -> TabsState<'a> {
 		TabsState { titles, index: 0 }
 	}
 	pub fn next(&mut self) {
@@ -79,6 +79,12 @@ pub struct StatefulList<T> {
 	pub items: Vec<T>,
 }
 
+impl<T> Default for StatefulList<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl<T> StatefulList<T> {
 	pub fn new() -> StatefulList<T> {
 		StatefulList {
@@ -0,0 +1,206 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Widget},
+};
+
+/// Rows in each glyph of a BigNumber
+pub const GLYPH_HEIGHT: u16 = 5;
+
+/// The 3x5 glyph of a character, '#' for a filled cell, None for characters
+/// there is no glyph for. The decimal point and comma are one cell wide
+fn glyph(c: char) -> Option<[&'static str; GLYPH_HEIGHT as usize]> {
+    let rows = match c.to_ascii_uppercase() {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", " ##", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        '.' => [" ", " ", " ", " ", "#"],
+        ',' => [" ", " ", " ", "#", "#"],
+        '-' => ["   ", "   ", "###", "   ", "   "],
+        ' ' => ["   ", "   ", "   ", "   ", "   "],
+        '%' => ["# #", "  #", " # ", "#  ", "# #"],
+        'B' => ["## ", "# #", "## ", "# #", "## "],
+        'K' => ["# #", "# #", "## ", "# #", "# #"],
+        'M' => ["# #", "###", "###", "# #", "# #"],
+        'G' => ["###", "#  ", "# #", "# #", "###"],
+        'T' => ["###", " # ", " # ", " # ", " # "],
+        '/' => ["  #", "  #", " # ", "#  ", "#  "],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// The columns a BigNumber needs for some text, with one between each glyph
+pub fn big_text_width(text: &str) -> u16 {
+    let widths: Vec<u16> = text.chars().filter_map(glyph).map(|rows| rows[0].len() as u16).collect();
+    widths.iter().sum::<u16>() + widths.len().saturating_sub(1) as u16
+}
+
+/// A widget showing a figure such as "42", "1.5GB" or "87%" in digits
+/// GLYPH_HEIGHT rows tall, so that it can be read from across the room.
+///
+/// Digits, '.', ',', '-', '/', '%' and the unit letters B, K, M, G and T (in
+/// either case) have glyphs, and other characters are left out. Where the area
+/// is too short the figure is drawn as ordinary text on its top row, and where
+/// it is too narrow the glyphs which don't fit are left off the right.
+///
+/// # Examples:
+///
+/// ```
+/// # use tui::widgets::{Widget, BigNumber, Block, Borders};
+/// # use tui::style::{Style, Color};
+/// BigNumber::default()
+///     .block(Block::default().borders(Borders::ALL).title("PUTS"))
+///     .style(Style::default().fg(Color::Yellow))
+///     .text("1.5K");
+/// ```
+#[derive(Debug, Clone)]
+pub struct BigNumber<'a> {
+    block: Option<Block<'a>>,
+    text: &'a str,
+    style: Style,
+    symbol: &'a str,
+}
+
+impl<'a> Default for BigNumber<'a> {
+    fn default() -> BigNumber<'a> {
+        BigNumber {
+            block: None,
+            text: "",
+            style: Style::default(),
+            symbol: tui::symbols::block::FULL,
+        }
+    }
+}
+
+impl<'a> BigNumber<'a> {
+    pub fn block(mut self, block: Block<'a>) -> BigNumber<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn text(mut self, text: &'a str) -> BigNumber<'a> {
+        self.text = text;
+        self
+    }
+
+    /// Style of the filled cells of each glyph
+    pub fn style(mut self, style: Style) -> BigNumber<'a> {
+        self.style = style;
+        self
+    }
+
+    /// Symbol for the filled cells, a full block by default
+    pub fn symbol(mut self, symbol: &'a str) -> BigNumber<'a> {
+        self.symbol = symbol;
+        self
+    }
+}
+
+impl<'a> Widget for BigNumber<'a> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+        if area.height < 1 || area.width < 1 {
+            return;
+        }
+        if area.height < GLYPH_HEIGHT {
+            buf.set_stringn(area.left(), area.top(), self.text, area.width as usize, self.style);
+            return;
+        }
+
+        let mut x = area.left();
+        for rows in self.text.chars().filter_map(glyph) {
+            let width = rows[0].len() as u16;
+            if x + width > area.right() {
+                break;
+            }
+            for (dy, row) in rows.iter().enumerate() {
+                for (dx, cell) in row.chars().enumerate() {
+                    if cell == '#' {
+                        buf.get_mut(x + dx as u16, area.top() + dy as u16)
+                            .set_symbol(self.symbol)
+                            .set_style(self.style);
+                    }
+                }
+            }
+            x += width + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color;
+
+    fn rendered_rows(widget: BigNumber, width: u16, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buffer = Buffer::empty(area);
+        widget.render(area, &mut buffer);
+        (0..height)
+            .map(|y| (0..width).map(|x| if buffer.get(x, y).symbol == " " { ' ' } else { '#' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn renders_42_as_a_cell_grid() {
+        let widget = BigNumber::default().text("42").style(Style::default().fg(Color::Yellow));
+        assert_eq!(
+            rendered_rows(widget.clone(), 8, 5),
+            vec![
+                "# # ### ",
+                "# #   # ",
+                "### ### ",
+                "  # #   ",
+                "  # ### ",
+            ]
+        );
+        let area = Rect::new(0, 0, 8, 5);
+        let mut buffer = Buffer::empty(area);
+        widget.render(area, &mut buffer);
+        assert_eq!(buffer.get(0, 0).symbol, tui::symbols::block::FULL);
+        assert_eq!(buffer.get(0, 0).fg, Color::Yellow);
+        assert_eq!(buffer.get(1, 0).fg, Color::Reset); // Unfilled cells are left alone
+    }
+
+    #[test]
+    fn renders_decimal_points_and_units() {
+        assert_eq!(big_text_width("42"), 7);
+        assert_eq!(big_text_width("1.5K"), 13);
+        assert_eq!(big_text_width("x"), 0);
+        assert_eq!(
+            rendered_rows(BigNumber::default().text("1.5K"), 13, 5),
+            vec![
+                " #    ### # #",
+                "##    #   # #",
+                " #    ### ## ",
+                " #      # # #",
+                "### # ### # #",
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_text_without_room() {
+        assert_eq!(rendered_rows(BigNumber::default().text("42"), 5, 1), vec!["##   "]);
+        assert_eq!(
+            rendered_rows(BigNumber::default().text("42"), 5, 5),
+            vec!["# #  ", "# #  ", "###  ", "  #  ", "  #  "]
+        );
+    }
+}
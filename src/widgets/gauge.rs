@@ -1,16 +1,40 @@
+use std::fmt;
+use std::rc::Rc;
 use tui::{
     buffer::Buffer,
-    layout::Rect,
+    layout::{Direction, Rect},
     style::{Color, Style},
+    symbols,
     text::Span,
     widgets::{Block, Widget},
 };
 
+/// Where to draw the label of a Gauge2
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelPosition {
+    Center,
+    Left,
+    /// Right aligned, drawn using the unfilled gauge colours even when the fill reaches it
+    Right,
+}
+
+/// Formats the label of a Gauge2 from its ratio
+#[derive(Clone)]
+pub struct LabelFn<'a>(Rc<dyn Fn(f64) -> String + 'a>);
+
+impl<'a> fmt::Debug for LabelFn<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("LabelFn")
+    }
+}
+
 /// A widget to display a task progress.
 ///
 /// Note: Gauge requires minimum height of 2, Gauge2 has been modified to work with 
 /// a height of 1
 ///
+/// Gauge2 can also be drawn vertically, filling from the bottom up (see `direction()`)
+///
 /// # Examples:
 ///
 /// ```
@@ -26,8 +50,22 @@ pub struct Gauge2<'a> {
     block: Option<Block<'a>>,
     ratio: f64,
     label: Option<Span<'a>>,
+    label_fn: Option<LabelFn<'a>>,
     style: Style,
     gauge_style: Style,
+    thresholds: Vec<(f64, Style)>,
+    gradient: Option<(Color, Color)>,
+    direction: Direction,
+    label_position: LabelPosition,
+    marker: Option<f64>,
+    marker_style: Style,
+    fine: bool,
+    segments: Vec<(f64, Style)>,
+    monochrome: bool,
+    indeterminate: bool,
+    show_value: bool,
+    total: f64,
+    empty_symbol: char,
 }
 
 impl<'a> Default for Gauge2<'a> {
@@ -36,8 +74,22 @@ impl<'a> Default for Gauge2<'a> {
             block: None,
             ratio: 0.0,
             label: None,
+            label_fn: None,
             style: Style::default(),
             gauge_style: Style::default(),
+            thresholds: Vec::new(),
+            gradient: None,
+            direction: Direction::Horizontal,
+            label_position: LabelPosition::Center,
+            marker: None,
+            marker_style: Style::default(),
+            fine: false,
+            segments: Vec::new(),
+            monochrome: false,
+            indeterminate: false,
+            show_value: false,
+            total: 1.0,
+            empty_symbol: ' ',
         }
     }
 }
@@ -58,15 +110,40 @@ impl<'a> Gauge2<'a> {
     }
 
     /// Sets ratio ([0.0, 1.0]) directly.
+    ///
+    /// A NaN or infinite ratio (e.g. from dividing by an unknown zero
+    /// capacity) is treated as 0.0, other values outside [0.0, 1.0] panic.
     pub fn ratio(mut self, ratio: f64) -> Gauge2<'a> {
+        if !ratio.is_finite() {
+            self.ratio = 0.0;
+            return self;
+        }
         assert!(
-            ratio <= 1.0 && ratio >= 0.0,
+            (0.0..=1.0).contains(&ratio),
             "{}", format!("Ratio ({}) should be between 0 and 1 inclusively.", ratio).to_string()
         );
         self.ratio = ratio;
         self
     }
 
+    /// Sets ratio, clamped into [0.0, 1.0] rather than panicking when a
+    /// transient value is out of range. NaN and infinite ratios are 0.0.
+    pub fn ratio_clamped(mut self, ratio: f64) -> Gauge2<'a> {
+        if !(0.0..=1.0).contains(&ratio) {
+            log::debug!("Gauge2 ratio ({}) clamped to between 0 and 1", ratio);
+        }
+        self.ratio = sanitize_ratio(ratio);
+        self
+    }
+
+    pub fn get_ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    pub fn get_label(&self) -> Option<&Span<'a>> {
+        self.label.as_ref()
+    }
+
     pub fn label<T>(mut self, label: T) -> Gauge2<'a>
     where
         T: Into<Span<'a>>,
@@ -75,6 +152,13 @@ impl<'a> Gauge2<'a> {
         self
     }
 
+    /// Replaces the default percentage label with one made from the ratio,
+    /// e.g. to show "450 GB / 2 TB". An explicit label() takes precedence.
+    pub fn label_fn(mut self, label_fn: impl Fn(f64) -> String + 'a) -> Gauge2<'a> {
+        self.label_fn = Some(LabelFn(Rc::new(label_fn)));
+        self
+    }
+
     pub fn style(mut self, style: Style) -> Gauge2<'a> {
         self.style = style;
         self
@@ -84,10 +168,226 @@ impl<'a> Gauge2<'a> {
         self.gauge_style = style;
         self
     }
+
+    /// Ascending (ratio, style) cutoffs. The style of the highest cutoff
+    /// at or below the ratio replaces gauge_style, e.g. to turn a
+    /// gauge red when nearly full.
+    pub fn thresholds(mut self, thresholds: Vec<(f64, Style)>) -> Gauge2<'a> {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Fills with a colour between these two, from the first when empty to
+    /// the second when full, in place of gauge_style's foreground and any
+    /// thresholds. Only RGB colours can be blended, so with any other the
+    /// gauge_style and thresholds are used as usual.
+    pub fn gradient(mut self, from: Color, to: Color) -> Gauge2<'a> {
+        self.gradient = Some((from, to));
+        self
+    }
+
+    pub fn label_position(mut self, label_position: LabelPosition) -> Gauge2<'a> {
+        self.label_position = label_position;
+        self
+    }
+
+    /// Draws a line across the gauge at this ratio (e.g. to show an average)
+    pub fn marker(mut self, marker: Option<f64>) -> Gauge2<'a> {
+        self.marker = marker;
+        self
+    }
+
+    pub fn marker_style(mut self, style: Style) -> Gauge2<'a> {
+        self.marker_style = style;
+        self
+    }
+
+    /// Draws the partly filled cell at the end of the fill using eighth blocks
+    pub fn fine(mut self, fine: bool) -> Gauge2<'a> {
+        self.fine = fine;
+        self
+    }
+
+    /// Stacked fills drawn left to right, each a (ratio of the width, style).
+    /// Replaces the single ratio fill when not empty, and the total is
+    /// limited to 1.0. Horizontal gauges only.
+    pub fn segments(mut self, segments: Vec<(f64, Style)>) -> Gauge2<'a> {
+        self.segments = segments;
+        self
+    }
+
+    /// Draws the filled part using block characters instead of swapping
+    /// the foreground and background colours, for terminals without colour.
+    pub fn monochrome(mut self, monochrome: bool) -> Gauge2<'a> {
+        self.monochrome = monochrome;
+        self
+    }
+
+    /// For when the ratio is unknown (e.g. storage of unknown capacity). Shades
+    /// the whole gauge instead of filling it so that only the label is read
+    pub fn indeterminate(mut self, indeterminate: bool) -> Gauge2<'a> {
+        self.indeterminate = indeterminate;
+        self
+    }
+
+    /// Makes the default label the value out of total() rather than a percentage,
+    /// each rounded to two decimal places
+    pub fn show_value(mut self, show_value: bool) -> Gauge2<'a> {
+        self.show_value = show_value;
+        self
+    }
+
+    /// The value of a full gauge, for show_value()
+    pub fn total(mut self, total: f64) -> Gauge2<'a> {
+        self.total = total;
+        self
+    }
+
+    /// Fills the unfilled part of the gauge with this glyph in style(), e.g. '░',
+    /// so that the bar can be found against any background. Blank by default.
+    pub fn empty_symbol(mut self, symbol: char) -> Gauge2<'a> {
+        self.empty_symbol = symbol;
+        self
+    }
+
+    /// Horizontal (default) fills from the left, Vertical fills from the bottom
+    pub fn direction(mut self, direction: Direction) -> Gauge2<'a> {
+        self.direction = direction;
+        self
+    }
+}
+
+/// The colour a ratio ([0.0, 1.0]) of the way from one RGB colour to
+/// another, or None unless both are RGB
+pub fn interpolate_color(from: Color, to: Color, ratio: f64) -> Option<Color> {
+    let (from, to) = match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => ((r1, g1, b1), (r2, g2, b2)),
+        _ => return None,
+    };
+    let ratio = sanitize_ratio(ratio);
+    let blend = |from: u8, to: u8| (f64::from(from) + (f64::from(to) - f64::from(from)) * ratio).round() as u8;
+    Some(Color::Rgb(blend(from.0, to.0), blend(from.1, to.1), blend(from.2, to.2)))
+}
+
+// Rounded to at most two decimal places, so that a value such as 0.1 * 3
+// shows as 0.3 rather than 0.30000000000000004
+fn round_value(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn sanitize_ratio(ratio: f64) -> f64 {
+    if ratio.is_finite() {
+        ratio.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+impl<'a> Gauge2<'a> {
+    /// For fine() gauges, fill whole cells only (truncating rather than
+    /// rounding) and draw the remaining fraction as an eighth block in the
+    /// following cell. Returns the whole cell (end, fill_top).
+    fn render_partial_cell(&self, gauge_area: Rect, gauge_style: Style, buf: &mut Buffer) -> (u16, u16) {
+        let (length, left_blocks) = match self.direction {
+            Direction::Horizontal => (gauge_area.width, true),
+            Direction::Vertical => (gauge_area.height, false),
+        };
+        let filled = f64::from(length) * self.ratio;
+        let whole = filled.floor() as u16;
+        let eighths = ((filled - filled.floor()) * 8.0).round() as usize;
+        let symbol = match (eighths, left_blocks) {
+            (0, _) => None,
+            (1, true) => Some(symbols::block::ONE_EIGHTH),
+            (2, true) => Some(symbols::block::ONE_QUARTER),
+            (3, true) => Some(symbols::block::THREE_EIGHTHS),
+            (4, true) => Some(symbols::block::HALF),
+            (5, true) => Some(symbols::block::FIVE_EIGHTHS),
+            (6, true) => Some(symbols::block::THREE_QUARTERS),
+            (7, true) => Some(symbols::block::SEVEN_EIGHTHS),
+            (1, false) => Some(symbols::bar::ONE_EIGHTH),
+            (2, false) => Some(symbols::bar::ONE_QUARTER),
+            (3, false) => Some(symbols::bar::THREE_EIGHTHS),
+            (4, false) => Some(symbols::bar::HALF),
+            (5, false) => Some(symbols::bar::FIVE_EIGHTHS),
+            (6, false) => Some(symbols::bar::THREE_QUARTERS),
+            (7, false) => Some(symbols::bar::SEVEN_EIGHTHS),
+            _ => Some(symbols::block::FULL),
+        };
+
+        let (end, fill_top, partial_area) = match self.direction {
+            Direction::Horizontal => {
+                let end = gauge_area.left() + whole;
+                (end, gauge_area.top(), Rect::new(end, gauge_area.top(), 1, gauge_area.height))
+            }
+            Direction::Vertical => {
+                let fill_top = gauge_area.bottom() - whole;
+                (gauge_area.right(), fill_top, Rect::new(gauge_area.left(), fill_top.saturating_sub(1), gauge_area.width, 1))
+            }
+        };
+
+        if let Some(symbol) = symbol {
+            if whole < length {
+                for y in partial_area.top()..partial_area.bottom() {
+                    for x in partial_area.left()..partial_area.right() {
+                        buf.get_mut(x, y)
+                            .set_symbol(symbol)
+                            .set_fg(gauge_style.fg.unwrap_or(Color::Reset))
+                            .set_bg(gauge_style.bg.unwrap_or(Color::Reset));
+                    }
+                }
+            }
+        }
+        (end, fill_top)
+    }
+
+    /// Returns the total ratio and the (start, end, style) columns of each segment
+    fn segment_bounds(&self, gauge_area: Rect) -> (f64, Vec<(u16, u16, Style)>) {
+        let mut total = 0.0;
+        let mut bounds = Vec::new();
+        let mut start = gauge_area.left();
+        for (ratio, style) in self.segments.iter() {
+            total = sanitize_ratio(total + sanitize_ratio(*ratio));
+            let end = gauge_area.left() + (f64::from(gauge_area.width) * total).round() as u16;
+            bounds.push((start, end, *style));
+            start = end;
+        }
+        (total, bounds)
+    }
+
+    /// The label used when neither label() nor label_fn() was given
+    fn default_label(&self, ratio: f64) -> String {
+        if self.show_value {
+            format!("{}/{}", round_value(ratio * self.total), round_value(self.total))
+        } else {
+            format!("{}%", (ratio * 100.0).round())
+        }
+    }
+
+    fn effective_gauge_style(&self) -> Style {
+        let gradient = self.gradient.and_then(|(from, to)| interpolate_color(from, to, self.ratio));
+        if let Some(color) = gradient {
+            return self.gauge_style.fg(color);
+        }
+        let mut style = self.gauge_style;
+        let mut highest_cutoff = f64::NEG_INFINITY;
+        for (cutoff, threshold_style) in self.thresholds.iter() {
+            if *cutoff <= self.ratio && *cutoff >= highest_cutoff {
+                highest_cutoff = *cutoff;
+                style = *threshold_style;
+            }
+        }
+        style
+    }
 }
 
 impl<'a> Widget for Gauge2<'a> {
     fn render(mut self, area: Rect, buf: &mut Buffer) {
+        self.ratio = sanitize_ratio(self.ratio);
+        if self.indeterminate {
+            self.ratio = 0.0;
+            self.segments.clear();
+            self.marker = None;
+        }
         buf.set_style(area, self.style);
         let gauge_area = match self.block.take() {
             Some(b) => {
@@ -100,7 +400,8 @@ impl<'a> Widget for Gauge2<'a> {
             }
             None => area,
         };
-        buf.set_style(gauge_area, self.gauge_style);
+        let gauge_style = self.effective_gauge_style();
+        buf.set_style(gauge_area, gauge_style);
         if gauge_area.height < 1 {
 					return;
 			}
@@ -110,31 +411,109 @@ impl<'a> Widget for Gauge2<'a> {
 					center = gauge_area.height + gauge_area.top();
 				};
 
-        let width = (f64::from(gauge_area.width) * self.ratio).round() as u16;
-        let end = gauge_area.left() + width;
+        // Filled cells are left..end on rows from fill_top to the bottom
+        let (end, fill_top) = match self.direction {
+            _ if self.fine => self.render_partial_cell(gauge_area, gauge_style, buf),
+            Direction::Horizontal => {
+                let width = (f64::from(gauge_area.width) * self.ratio).round() as u16;
+                (gauge_area.left() + width, gauge_area.top())
+            }
+            Direction::Vertical => {
+                let height = (f64::from(gauge_area.height) * self.ratio).round() as u16;
+                (gauge_area.right(), gauge_area.bottom() - height)
+            }
+        };
+        let (segments_total, segments) = self.segment_bounds(gauge_area);
+        let (end, fill_top, ratio) = match segments.last() {
+            Some((_, segments_end, _)) => (*segments_end, gauge_area.top(), segments_total),
+            None => (end, fill_top, self.ratio),
+        };
         // Label
-        let ratio = self.ratio;
-        let label = self
-            .label
-            .unwrap_or_else(|| Span::from(format!("{}%", (ratio * 100.0).round())));
+        let label_fn = self.label_fn.take();
+        let default_label = self.default_label(ratio);
+        let label = self.label.take().unwrap_or_else(|| match label_fn {
+            Some(LabelFn(label_fn)) => Span::from(label_fn(ratio)),
+            None => Span::from(default_label),
+        });
+        let label_width = label.width() as u16;
+        let label_start = match self.label_position {
+            LabelPosition::Center => gauge_area.width.saturating_sub(label_width) / 2 + gauge_area.left(),
+            LabelPosition::Left => gauge_area.left(),
+            LabelPosition::Right => gauge_area.right().saturating_sub(label_width).max(gauge_area.left()),
+        };
+        let label_end = label_start.saturating_add(label_width).min(gauge_area.right());
 
 				for y in gauge_area.top()..gauge_area.bottom() {
+            let row_end = if y >= fill_top { end } else { gauge_area.left() };
 						// Gauge2
-            for x in gauge_area.left()..end {
-                buf.get_mut(x, y).set_symbol(" ");
+            let fill_symbol = if self.monochrome { symbols::block::FULL } else { " " };
+            for x in gauge_area.left()..row_end {
+                buf.get_mut(x, y).set_symbol(fill_symbol);
+            }
+            if self.empty_symbol != ' ' {
+                let empty_symbol = self.empty_symbol.to_string();
+                // Leaving any partly filled cell of a fine() gauge
+                for x in row_end..gauge_area.right() {
+                    let cell = buf.get_mut(x, y);
+                    if cell.symbol == " " {
+                        cell.set_symbol(&empty_symbol).set_style(self.style);
+                    }
+                }
+            }
+            if self.indeterminate {
+                for x in gauge_area.left()..gauge_area.right() {
+                    buf.get_mut(x, y).set_symbol("░");
+                }
             }
 
             if y == center {
-                let label_width = label.width() as u16;
-                let middle = (gauge_area.width - label_width) / 2 + gauge_area.left();
-                buf.set_span(middle, y, &label, gauge_area.right() - middle);
+                buf.set_span(label_start, y, &label, gauge_area.right() - label_start);
             }
 
             // Fix colors
-            for x in gauge_area.left()..end {
+            let right_label = y == center && self.label_position == LabelPosition::Right;
+            if self.monochrome {
+                continue;
+            }
+            for x in gauge_area.left()..row_end {
+                if right_label && x >= label_start && x < label_end {
+                    continue;
+                }
+                let fill_style = segments
+                    .iter()
+                    .find(|(start, end, _)| x >= *start && x < *end)
+                    .map_or(gauge_style, |(_, _, style)| *style);
                 buf.get_mut(x, y)
-                    .set_fg(self.gauge_style.bg.unwrap_or(Color::Reset))
-                    .set_bg(self.gauge_style.fg.unwrap_or(Color::Reset));
+                    .set_fg(fill_style.bg.unwrap_or(Color::Reset))
+                    .set_bg(fill_style.fg.unwrap_or(Color::Reset));
+            }
+            if right_label {
+                for x in label_start..label_end {
+                    buf.get_mut(x, y).set_style(self.style);
+                }
+            }
+        }
+
+        // Marker, ignored outside [0.0, 1.0)
+        if let Some(marker) = self.marker.filter(|marker| (0.0..1.0).contains(marker)) {
+            match self.direction {
+                Direction::Horizontal => {
+                    let x = gauge_area.left() + (f64::from(gauge_area.width) * marker).round() as u16;
+                    if x < gauge_area.right() {
+                        for y in gauge_area.top()..gauge_area.bottom() {
+                            buf.get_mut(x, y).set_symbol("│").set_style(self.marker_style);
+                        }
+                    }
+                }
+                Direction::Vertical => {
+                    let rows = (f64::from(gauge_area.height) * marker).round() as u16;
+                    if rows < gauge_area.height {
+                        let y = gauge_area.bottom() - 1 - rows;
+                        for x in gauge_area.left()..gauge_area.right() {
+                            buf.get_mut(x, y).set_symbol("─").set_style(self.marker_style);
+                        }
+                    }
+                }
             }
         }
     }
@@ -161,4 +540,300 @@ mod tests {
     fn gauge_invalid_ratio_lower_bound() {
         Gauge2::default().ratio(-0.5);
     }
+
+    fn render_gauge(gauge: Gauge2, area: Rect) -> Buffer {
+        let mut buffer = Buffer::empty(area);
+        gauge.render(area, &mut buffer);
+        buffer
+    }
+
+    fn filled_rows(buffer: &Buffer, area: Rect) -> Vec<u16> {
+        (area.top()..area.bottom())
+            .filter(|y| (area.left()..area.right()).all(|x| buffer.get(x, *y).bg == Color::Yellow))
+            .collect()
+    }
+
+    #[test]
+    fn gauge_vertical_half_fills_bottom_rows() {
+        let area = Rect::new(0, 0, 3, 4);
+        let gauge = Gauge2::default()
+            .gauge_style(Style::default().fg(Color::Yellow))
+            .direction(Direction::Vertical)
+            .ratio(0.5);
+        let buffer = render_gauge(gauge, area);
+        assert_eq!(filled_rows(&buffer, area), vec![2, 3]);
+        for x in area.left()..area.right() {
+            assert_ne!(buffer.get(x, 0).bg, Color::Yellow);
+            assert_ne!(buffer.get(x, 1).bg, Color::Yellow);
+        }
+    }
+
+    #[test]
+    fn gauge_vertical_full_fills_all_rows() {
+        let area = Rect::new(0, 0, 3, 4);
+        let gauge = Gauge2::default()
+            .gauge_style(Style::default().fg(Color::Yellow))
+            .direction(Direction::Vertical)
+            .ratio(1.0);
+        let buffer = render_gauge(gauge, area);
+        assert_eq!(filled_rows(&buffer, area), vec![0, 1, 2, 3]);
+    }
+
+    fn threshold_gauge(ratio: f64) -> Gauge2<'static> {
+        Gauge2::default()
+            .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+            .thresholds(vec![
+                (0.75, Style::default().fg(Color::Yellow).bg(Color::Blue)),
+                (0.9, Style::default().fg(Color::Red).bg(Color::White)),
+            ])
+            .ratio(ratio)
+    }
+
+    #[test]
+    fn gauge_thresholds_select_style() {
+        let area = Rect::new(0, 0, 10, 1);
+        for (ratio, fill, text) in [
+            (0.5, Color::Green, Color::Black),
+            (0.8, Color::Yellow, Color::Blue),
+            (0.95, Color::Red, Color::White),
+        ] {
+            let buffer = render_gauge(threshold_gauge(ratio), area);
+            let cell = buffer.get(0, 0);
+            assert_eq!(cell.bg, fill, "ratio {}", ratio);
+            assert_eq!(cell.fg, text, "ratio {}", ratio);
+        }
+    }
+
+    #[test]
+    fn gauge_gradient_interpolates_rgb() {
+        let (green, red) = (Color::Rgb(0, 200, 0), Color::Rgb(255, 0, 0));
+        assert_eq!(interpolate_color(green, red, 0.0), Some(green));
+        assert_eq!(interpolate_color(green, red, 0.5), Some(Color::Rgb(128, 100, 0)));
+        assert_eq!(interpolate_color(green, red, 1.0), Some(red));
+        assert_eq!(interpolate_color(Color::Green, red, 0.5), None);
+
+        let area = Rect::new(0, 0, 10, 1);
+        let buffer = render_gauge(threshold_gauge(0.5).label("").gradient(green, red), area);
+        assert_eq!(buffer.get(0, 0).bg, Color::Rgb(128, 100, 0));
+        assert_eq!(buffer.get(0, 0).fg, Color::Black);
+
+        // Named colours can't be blended, so the thresholds still apply
+        let buffer = render_gauge(threshold_gauge(0.95).label("").gradient(Color::Green, red), area);
+        assert_eq!(buffer.get(0, 0).bg, Color::Red);
+    }
+
+    #[test]
+    fn gauge_label_right_uses_unfilled_style() {
+        let area = Rect::new(0, 0, 20, 1);
+        let gauge = Gauge2::default()
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .gauge_style(Style::default().fg(Color::Yellow))
+            .label_position(LabelPosition::Right)
+            .ratio(0.95);
+        let buffer = render_gauge(gauge, area);
+        for (x, symbol) in [(17, "9"), (18, "5"), (19, "%")] {
+            let cell = buffer.get(x, 0);
+            assert_eq!(cell.symbol, symbol);
+            assert_eq!(cell.fg, Color::White);
+            assert_eq!(cell.bg, Color::Black);
+        }
+        assert_eq!(buffer.get(16, 0).bg, Color::Yellow);
+    }
+
+    #[test]
+    fn gauge_label_fn_is_centered() {
+        let area = Rect::new(0, 0, 9, 1);
+        let gauge = Gauge2::default().label_fn(|_| "abc".to_string()).ratio(0.5);
+        let buffer = render_gauge(gauge, area);
+        let row: String = (0..9).map(|x| buffer.get(x, 0).symbol.clone()).collect();
+        assert_eq!(row, "   abc   ");
+    }
+
+    #[test]
+    fn gauge_ratio_clamped() {
+        let area = Rect::new(0, 0, 4, 1);
+        let style = Style::default().fg(Color::Yellow);
+        let buffer = render_gauge(Gauge2::default().gauge_style(style).ratio_clamped(1.5), area);
+        assert_eq!(filled_rows(&buffer, area), vec![0]);
+
+        let buffer = render_gauge(Gauge2::default().gauge_style(style).ratio_clamped(-0.2), area);
+        assert!((0..4).all(|x| buffer.get(x, 0).bg != Color::Yellow));
+    }
+
+    #[test]
+    fn gauge_marker_column() {
+        let area = Rect::new(0, 0, 10, 1);
+        let gauge = Gauge2::default()
+            .label("")
+            .marker(Some(0.5))
+            .marker_style(Style::default().fg(Color::Red))
+            .ratio(0.2);
+        let buffer = render_gauge(gauge, area);
+        assert_eq!(buffer.get(5, 0).symbol, "│");
+        assert_eq!(buffer.get(5, 0).fg, Color::Red);
+        assert!((0..10).filter(|x| *x != 5).all(|x| buffer.get(x, 0).symbol != "│"));
+    }
+
+    #[test]
+    fn gauge_marker_out_of_range_is_ignored() {
+        let area = Rect::new(0, 0, 10, 1);
+        let buffer = render_gauge(Gauge2::default().label("").marker(Some(1.0)), area);
+        assert!((0..10).all(|x| buffer.get(x, 0).symbol != "│"));
+
+        // However large, away from the left edge, without overflowing
+        let area = Rect::new(5, 2, 10, 3);
+        for marker in &[1e6, -1e6, f64::NAN] {
+            let buffer = render_gauge(Gauge2::default().label("").marker(Some(*marker)), area);
+            assert!((5..15).all(|x| buffer.get(x, 3).symbol != "│"));
+            let vertical = Gauge2::default().label("").direction(Direction::Vertical).marker(Some(*marker));
+            let buffer = render_gauge(vertical, area);
+            assert!((2..5).all(|y| buffer.get(5, y).symbol != "─"));
+        }
+    }
+
+    #[test]
+    fn gauge_fine_boundary_glyph() {
+        let area = Rect::new(0, 0, 5, 1);
+        let style = Style::default().fg(Color::Yellow);
+        // 2.5 cells: two filled, then a half block
+        let buffer = render_gauge(Gauge2::default().label("").gauge_style(style).fine(true).ratio(0.5), area);
+        assert_eq!(buffer.get(1, 0).bg, Color::Yellow);
+        assert_eq!(buffer.get(2, 0).symbol, "▌");
+        assert_eq!(buffer.get(2, 0).fg, Color::Yellow);
+        assert_ne!(buffer.get(2, 0).bg, Color::Yellow);
+
+        // 1.65 cells: one filled, then five eighths
+        let buffer = render_gauge(Gauge2::default().label("").gauge_style(style).fine(true).ratio(0.33), area);
+        assert_eq!(buffer.get(0, 0).bg, Color::Yellow);
+        assert_eq!(buffer.get(1, 0).symbol, "▋");
+        assert_eq!(buffer.get(2, 0).symbol, " ");
+    }
+
+    #[test]
+    fn gauge_accessors() {
+        let gauge = Gauge2::default().percent(40);
+        assert_eq!(gauge.get_ratio(), 0.4);
+        assert!(gauge.get_label().is_none());
+        assert_eq!(gauge.label("full").get_label().map(|l| l.content.to_string()), Some("full".to_string()));
+    }
+
+    #[test]
+    fn gauge_non_finite_ratio_is_empty() {
+        let area = Rect::new(0, 0, 4, 1);
+        let style = Style::default().fg(Color::Yellow);
+        for ratio in [f64::NAN, f64::INFINITY, -f64::INFINITY] {
+            for gauge in [
+                Gauge2::default().gauge_style(style).ratio(ratio),
+                Gauge2::default().gauge_style(style).ratio_clamped(ratio),
+            ] {
+                assert_eq!(gauge.get_ratio(), 0.0);
+                let buffer = render_gauge(gauge, area);
+                assert!((0..4).all(|x| buffer.get(x, 0).bg != Color::Yellow), "ratio {}", ratio);
+            }
+        }
+    }
+
+    #[test]
+    fn gauge_segments_stack_left_to_right() {
+        let area = Rect::new(0, 0, 10, 1);
+        let gauge = Gauge2::default()
+            .label("")
+            .segments(vec![
+                (0.3, Style::default().fg(Color::Red)),
+                (0.2, Style::default().fg(Color::Blue)),
+            ]);
+        let buffer = render_gauge(gauge, area);
+        let colors: Vec<Color> = (0..10).map(|x| buffer.get(x, 0).bg).collect();
+        assert_eq!(&colors[0..3], &[Color::Red; 3]);
+        assert_eq!(&colors[3..5], &[Color::Blue; 2]);
+        assert!(colors[5..].iter().all(|c| *c != Color::Red && *c != Color::Blue));
+    }
+
+    #[test]
+    fn gauge_segments_total_is_limited() {
+        let area = Rect::new(0, 0, 10, 1);
+        let gauge = Gauge2::default()
+            .label("")
+            .segments(vec![
+                (0.8, Style::default().fg(Color::Red)),
+                (0.8, Style::default().fg(Color::Blue)),
+            ]);
+        let buffer = render_gauge(gauge, area);
+        assert_eq!(buffer.get(7, 0).bg, Color::Red);
+        assert_eq!(buffer.get(9, 0).bg, Color::Blue);
+    }
+
+    #[test]
+    fn monochrome_fills_with_blocks() {
+        let area = Rect::new(0, 0, 10, 1);
+        let buffer = render_gauge(Gauge2::default().label("").ratio(0.5).monochrome(true), area);
+        for x in 0..5 {
+            assert_eq!(buffer.get(x, 0).symbol, symbols::block::FULL);
+            assert_eq!(buffer.get(x, 0).fg, Color::Reset);
+            assert_eq!(buffer.get(x, 0).bg, Color::Reset);
+        }
+        assert_eq!(buffer.get(5, 0).symbol, " ");
+    }
+
+    #[test]
+    fn gauge_empty_symbol_fills_unfilled_cells() {
+        let area = Rect::new(0, 0, 10, 2);
+        let gauge = Gauge2::default()
+            .style(Style::default().fg(Color::DarkGray))
+            .gauge_style(Style::default().fg(Color::Yellow))
+            .label("")
+            .ratio(0.4)
+            .empty_symbol('░');
+        let buffer = render_gauge(gauge, area);
+        for y in 0..2 {
+            for x in 0..4 {
+                assert_eq!(buffer.get(x, y).symbol, " ");
+                assert_eq!(buffer.get(x, y).bg, Color::Yellow);
+            }
+            for x in 4..10 {
+                assert_eq!(buffer.get(x, y).symbol, "░");
+                assert_eq!(buffer.get(x, y).fg, Color::DarkGray);
+            }
+        }
+    }
+
+    #[test]
+    fn indeterminate_shades_instead_of_filling() {
+        let area = Rect::new(0, 0, 10, 1);
+        let gauge = Gauge2::default()
+            .gauge_style(Style::default().fg(Color::Yellow))
+            .label("?")
+            .ratio(0.5)
+            .indeterminate(true);
+        let buffer = render_gauge(gauge, area);
+        let symbols: String = (0..10).map(|x| buffer.get(x, 0).symbol.clone()).collect();
+        assert_eq!(symbols, "░░░░?░░░░░");
+        assert!((0..10).all(|x| buffer.get(x, 0).bg != Color::Yellow));
+    }
+
+    fn label_row(gauge: Gauge2, width: u16) -> String {
+        let area = Rect::new(0, 0, width, 1);
+        let buffer = render_gauge(gauge, area);
+        (0..width).map(|x| buffer.get(x, 0).symbol.clone()).collect()
+    }
+
+    #[test]
+    fn gauge_default_label_shows_percentage_or_value() {
+        assert_eq!(Gauge2::default().ratio(0.25).default_label(0.25), "25%");
+        assert_eq!(label_row(Gauge2::default().total(8.0).ratio(0.25), 7), "  25%  ");
+
+        let gauge = Gauge2::default().show_value(true).total(8.0).ratio(0.25);
+        assert_eq!(gauge.default_label(0.25), "2/8");
+        assert_eq!(label_row(gauge, 7), "  2/8  ");
+
+        // Values that floats can't represent exactly are rounded
+        let gauge = Gauge2::default().show_value(true).total(3.0).ratio(0.1);
+        assert_eq!(gauge.default_label(0.1), "0.3/3");
+        assert_eq!(label_row(gauge, 9), "  0.3/3  ");
+        assert_eq!(Gauge2::default().show_value(true).total(2.0).default_label(1.0 / 3.0), "0.67/2");
+
+        // An explicit label or closure still takes precedence
+        let gauge = Gauge2::default().show_value(true).total(8.0).label_fn(|_| "abc".to_string());
+        assert_eq!(label_row(gauge, 7), "  abc  ");
+    }
 }
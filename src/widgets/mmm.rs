@@ -0,0 +1,161 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Block, Widget},
+};
+
+/// The minimum, mean and maximum of some values, None if there are none
+pub fn min_mean_max(values: &[u64]) -> Option<(u64, f64, u64)> {
+    let min = *values.iter().min()?;
+    let max = *values.iter().max()?;
+    let total: u128 = values.iter().map(|value| u128::from(*value)).sum();
+    Some((min, total as f64 / values.len() as f64, max))
+}
+
+/// A widget showing the min, mean and max of a set of values (such as
+/// timeline buckets) as three labelled figures in equal columns
+///
+/// # Examples:
+///
+/// ```
+/// # use tui::widgets::{Widget, MinMeanMax, Block, Borders};
+/// # use tui::style::{Style, Color};
+/// MinMeanMax::default()
+///     .block(Block::default().borders(Borders::ALL).title("PUTS"))
+///     .style(Style::default().fg(Color::Yellow))
+///     .values(&[3, 1, 4, 1, 5]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MinMeanMax<'a> {
+    block: Option<Block<'a>>,
+    values: &'a [u64],
+    style: Style,
+    label_style: Style,
+    given: Option<[(&'static str, String); 3]>,
+}
+
+impl<'a> MinMeanMax<'a> {
+    pub fn block(mut self, block: Block<'a>) -> MinMeanMax<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn values(mut self, values: &'a [u64]) -> MinMeanMax<'a> {
+        self.values = values;
+        self
+    }
+
+    /// Style of the figures
+    pub fn style(mut self, style: Style) -> MinMeanMax<'a> {
+        self.style = style;
+        self
+    }
+
+    /// Style of the 'min', 'mean' and 'max' labels
+    pub fn label_style(mut self, style: Style) -> MinMeanMax<'a> {
+        self.label_style = style;
+        self
+    }
+
+    /// Show these (label, figure) columns instead of those of the values, for
+    /// statistics computed elsewhere such as a percentile or with units
+    pub fn given_figures(mut self, figures: [(&'static str, String); 3]) -> MinMeanMax<'a> {
+        self.given = Some(figures);
+        self
+    }
+
+    /// The (label, figure) of each column, with dashes when there are no values
+    pub fn figures(&self) -> [(&'static str, String); 3] {
+        if let Some(given) = &self.given {
+            return given.clone();
+        }
+        match min_mean_max(self.values) {
+            Some((min, mean, max)) => [
+                ("min", min.to_string()),
+                ("mean", format!("{:.1}", mean)),
+                ("max", max.to_string()),
+            ],
+            None => [
+                ("min", String::from("-")),
+                ("mean", String::from("-")),
+                ("max", String::from("-")),
+            ],
+        }
+    }
+}
+
+impl<'a> Widget for MinMeanMax<'a> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+        if area.height < 1 || area.width < 1 {
+            return;
+        }
+
+        let column_width = area.width / 3;
+        for (i, (label, figure)) in self.figures().iter().enumerate() {
+            let x = area.left() + column_width * i as u16;
+            let width = if i == 2 { area.right() - x } else { column_width };
+            let spans = Spans::from(vec![
+                Span::styled(format!("{} ", label), self.label_style),
+                Span::styled(figure.clone(), self.style),
+            ]);
+            buf.set_spans(x, area.top(), &spans, width);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered_row(widget: MinMeanMax, width: u16) -> String {
+        let area = Rect::new(0, 0, width, 1);
+        let mut buffer = Buffer::empty(area);
+        widget.render(area, &mut buffer);
+        (0..width).map(|x| buffer.get(x, 0).symbol.clone()).collect()
+    }
+
+    #[test]
+    fn computes_min_mean_max() {
+        assert_eq!(min_mean_max(&[4, 8, 1, 3]), Some((1, 4.0, 8)));
+        assert_eq!(min_mean_max(&[7]), Some((7, 7.0, 7)));
+        assert_eq!(min_mean_max(&[u64::MAX, u64::MAX]), Some((u64::MAX, u64::MAX as f64, u64::MAX)));
+        assert_eq!(min_mean_max(&[]), None);
+    }
+
+    #[test]
+    fn formats_figures() {
+        let values = [2, 3, 9, 0];
+        let widget = MinMeanMax::default().values(&values);
+        assert_eq!(
+            widget.figures(),
+            [("min", "0".to_string()), ("mean", "3.5".to_string()), ("max", "9".to_string())]
+        );
+        assert_eq!(rendered_row(widget, 30), "min 0     mean 3.5  max 9     ");
+    }
+
+    #[test]
+    fn renders_given_figures() {
+        let widget = MinMeanMax::default().values(&[1, 2]).given_figures([
+            ("min", String::from("2ms")),
+            ("mean", String::from("5ms")),
+            ("p95", String::from("12ms")),
+        ]);
+        assert_eq!(rendered_row(widget, 30), "min 2ms   mean 5ms  p95 12ms  ");
+    }
+
+    #[test]
+    fn renders_dashes_without_values() {
+        let widget = MinMeanMax::default();
+        assert_eq!(rendered_row(widget, 30), "min -     mean -    max -     ");
+    }
+}
@@ -1,2 +1,5 @@
+pub mod bignumber;
 pub mod gauge;
+pub mod mmm;
+pub mod scrollbar;
 pub mod sparkline;
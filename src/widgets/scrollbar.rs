@@ -0,0 +1,138 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    symbols,
+    widgets::Widget,
+};
+
+/// The rows of a scrollbar's thumb, as (first, count), for a list of `len`
+/// rows scrolled to `offset` and drawn in `height` rows. The thumb is as tall
+/// as the proportion of the list shown, and at least one row. None when the
+/// whole list fits
+pub fn scrollbar_thumb(offset: usize, len: usize, height: usize) -> Option<(usize, usize)> {
+    if height == 0 || len <= height {
+        return None;
+    }
+    let count = (height * height).div_ceil(len).clamp(1, height);
+    let max_offset = len - height;
+    let first = ((height - count) * offset.min(max_offset) + max_offset / 2) / max_offset;
+    Some((first, count))
+}
+
+/// A one column scrollbar for a list with more rows than fit, showing where
+/// the rows shown are in the list by the position of its thumb, and how much
+/// of the list they are by its length. Nothing is drawn when the list fits.
+///
+/// # Examples:
+///
+/// ```
+/// # use tui::widgets::Scrollbar;
+/// # use tui::style::{Style, Color};
+/// Scrollbar::default()
+///     .position(20, 100)
+///     .thumb_style(Style::default().fg(Color::Yellow));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scrollbar<'a> {
+    offset: usize,
+    len: usize,
+    track_style: Style,
+    thumb_style: Style,
+    track_symbol: &'a str,
+    thumb_symbol: &'a str,
+}
+
+impl<'a> Default for Scrollbar<'a> {
+    fn default() -> Scrollbar<'a> {
+        Scrollbar {
+            offset: 0,
+            len: 0,
+            track_style: Style::default(),
+            thumb_style: Style::default(),
+            track_symbol: symbols::line::VERTICAL,
+            thumb_symbol: symbols::block::FULL,
+        }
+    }
+}
+
+impl<'a> Scrollbar<'a> {
+    /// The first row shown and the rows in the whole list
+    pub fn position(mut self, offset: usize, len: usize) -> Scrollbar<'a> {
+        self.offset = offset;
+        self.len = len;
+        self
+    }
+
+    pub fn track_style(mut self, style: Style) -> Scrollbar<'a> {
+        self.track_style = style;
+        self
+    }
+
+    pub fn thumb_style(mut self, style: Style) -> Scrollbar<'a> {
+        self.thumb_style = style;
+        self
+    }
+
+    pub fn track_symbol(mut self, symbol: &'a str) -> Scrollbar<'a> {
+        self.track_symbol = symbol;
+        self
+    }
+
+    pub fn thumb_symbol(mut self, symbol: &'a str) -> Scrollbar<'a> {
+        self.thumb_symbol = symbol;
+        self
+    }
+}
+
+impl<'a> Widget for Scrollbar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 1 {
+            return;
+        }
+        let (first, count) = match scrollbar_thumb(self.offset, self.len, area.height as usize) {
+            Some(thumb) => thumb,
+            None => return,
+        };
+        let x = area.right() - 1;
+        for dy in 0..area.height {
+            let (symbol, style) = if (first..first + count).contains(&(dy as usize)) {
+                (self.thumb_symbol, self.thumb_style)
+            } else {
+                (self.track_symbol, self.track_style)
+            };
+            buf.get_mut(x, area.top() + dy).set_symbol(symbol).set_style(style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered_column(offset: usize, len: usize, height: u16) -> String {
+        let area = Rect::new(0, 0, 1, height);
+        let mut buffer = Buffer::empty(area);
+        Scrollbar::default().position(offset, len).thumb_symbol("#").track_symbol("|").render(area, &mut buffer);
+        (0..height).map(|y| buffer.get(0, y).symbol.clone()).collect()
+    }
+
+    #[test]
+    fn sizes_and_places_the_thumb() {
+        assert_eq!(scrollbar_thumb(0, 10, 10), None); // Everything fits
+        assert_eq!(scrollbar_thumb(0, 5, 0), None);
+        assert_eq!(scrollbar_thumb(0, 20, 10), Some((0, 5)));
+        assert_eq!(scrollbar_thumb(10, 20, 10), Some((5, 5)));
+        assert_eq!(scrollbar_thumb(5, 20, 10), Some((3, 5)));
+        assert_eq!(scrollbar_thumb(50, 20, 10), Some((5, 5))); // Past the end
+        assert_eq!(scrollbar_thumb(0, 1000, 10), Some((0, 1))); // At least one row
+        assert_eq!(scrollbar_thumb(990, 1000, 10), Some((9, 1)));
+    }
+
+    #[test]
+    fn draws_the_thumb_over_the_track() {
+        assert_eq!(rendered_column(0, 8, 4), "##||");
+        assert_eq!(rendered_column(4, 8, 4), "||##");
+        assert_eq!(rendered_column(0, 4, 4), "    ");
+    }
+}
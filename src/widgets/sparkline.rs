@@ -2,11 +2,260 @@ use std::cmp::min;
 use tui::{
 	buffer::Buffer,
 	layout::Rect,
-	style::Style,
+	style::{Color, Modifier, Style},
 	symbols,
 	widgets::{Block, Widget},
 };
 
+/// Fewest data points for which spike_sigma() highlights spikes, as the
+/// standard deviation of fewer says little
+pub const MIN_SPIKE_SAMPLES: usize = 10;
+
+/// Values above this are spikes: sigma standard deviations above the mean.
+/// None when there are too few values, or no variation, to judge.
+pub fn spike_threshold(data: &[u64], sigma: f64) -> Option<f64> {
+	if data.len() < MIN_SPIKE_SAMPLES || !sigma.is_finite() || sigma <= 0.0 {
+		return None;
+	}
+	let count = data.len() as f64;
+	let mean = data.iter().map(|v| *v as f64).sum::<f64>() / count;
+	let variance = data.iter().map(|v| (*v as f64 - mean).powi(2)).sum::<f64>() / count;
+	let std_dev = variance.sqrt();
+	if std_dev <= 0.0 {
+		return None;
+	}
+	Some(mean + sigma * std_dev)
+}
+
+/// How data values map to bar heights
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scale {
+	Linear,
+	/// Heights proportional to log10(1 + value), so small values stay visible
+	Log10,
+}
+
+impl Scale {
+	fn apply(self, value: u64) -> f64 {
+		match self {
+			Scale::Linear => value as f64,
+			Scale::Log10 => (1.0 + value as f64).log10(),
+		}
+	}
+}
+
+/// How buckets are combined into a column when there are more than fit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Downsample {
+	/// The largest bucket of each column, so spikes stay visible
+	Max,
+	/// The mean of the buckets of each column, rounded to the nearest
+	Mean,
+}
+
+impl std::str::FromStr for Downsample {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Downsample, String> {
+		match s {
+			"max" => Ok(Downsample::Max),
+			"mean" => Ok(Downsample::Mean),
+			_ => Err(format!("invalid downsampling '{}', expected max or mean", s)),
+		}
+	}
+}
+
+/// The glyphs timelines are drawn with, for fonts which lack some of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SparkStyle {
+	/// Eighths of a block, the finest steps
+	Bars,
+	/// Braille dots filled from the bottom, a quarter of a line at a time
+	Braille,
+	/// Light to full shading in four steps, for fonts without partial blocks
+	Shaded,
+}
+
+impl std::str::FromStr for SparkStyle {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<SparkStyle, String> {
+		match s {
+			"bars" => Ok(SparkStyle::Bars),
+			"braille" => Ok(SparkStyle::Braille),
+			"shaded" => Ok(SparkStyle::Shaded),
+			_ => Err(format!("invalid spark style '{}', expected bars, braille or shaded", s)),
+		}
+	}
+}
+
+const BRAILLE_LEVELS: symbols::bar::Set = symbols::bar::Set {
+	full: "⣿",
+	seven_eighths: "⣿",
+	three_quarters: "⣶",
+	five_eighths: "⣶",
+	half: "⣤",
+	three_eighths: "⣤",
+	one_quarter: "⣀",
+	one_eighth: "⣀",
+	empty: " ",
+};
+
+const SHADED_LEVELS: symbols::bar::Set = symbols::bar::Set {
+	full: "█",
+	seven_eighths: "█",
+	three_quarters: "▓",
+	five_eighths: "▓",
+	half: "▒",
+	three_eighths: "▒",
+	one_quarter: "░",
+	one_eighth: "░",
+	empty: " ",
+};
+
+impl SparkStyle {
+	/// The glyph for each height of a cell in eighths, see bar_symbol()
+	pub fn bar_set(self) -> symbols::bar::Set {
+		match self {
+			SparkStyle::Bars => symbols::bar::NINE_LEVELS,
+			SparkStyle::Braille => BRAILLE_LEVELS,
+			SparkStyle::Shaded => SHADED_LEVELS,
+		}
+	}
+}
+
+/// How a timeline is charted: bars, or its values plotted as a line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartStyle {
+	/// A bar up to each value, the default
+	Bars,
+	/// A point at each value, joined to the point of the column before
+	Line,
+	/// A line with the cells below it shaded
+	Area,
+}
+
+impl std::str::FromStr for ChartStyle {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<ChartStyle, String> {
+		match s.trim() {
+			"bars" => Ok(ChartStyle::Bars),
+			"line" => Ok(ChartStyle::Line),
+			"area" => Ok(ChartStyle::Area),
+			_ => Err(format!("invalid chart style '{}', expected bars, line or area", s)),
+		}
+	}
+}
+
+/// The glyph of the points of a line or area chart and of the joins between them
+pub const LINE_SYMBOL: &str = "•";
+
+/// The glyph of the cells below the line of an area chart
+pub const AREA_SYMBOL: &str = "░";
+
+/// What a cell of a line or area chart shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartCell {
+	Empty,
+	Line,
+	Fill,
+}
+
+impl ChartCell {
+	fn symbol(self) -> &'static str {
+		match self {
+			ChartCell::Empty => " ",
+			ChartCell::Line => LINE_SYMBOL,
+			ChartCell::Fill => AREA_SYMBOL,
+		}
+	}
+}
+
+/// The cells of each column of a line chart, top row first, of points at
+/// heights in eighths of a line, as from bar_heights(), in a chart height
+/// lines tall. A point is in the line its height reaches into, zero in the
+/// bottom line, and a point further up or down than the one before is
+/// joined to it by the cells between them. With fill, the cells below the
+/// line are the area's
+pub fn line_cells(heights: &[u64], height: u16, fill: bool) -> Vec<Vec<ChartCell>> {
+	let height = height as usize;
+	if height == 0 {
+		return vec![Vec::new(); heights.len()];
+	}
+	// The line of each point, counted up from the bottom one
+	let rows: Vec<usize> = heights.iter().map(|h| (h.saturating_sub(1) / 8).min(height as u64 - 1) as usize).collect();
+	rows.iter()
+		.enumerate()
+		.map(|(i, row)| {
+			let previous = if i == 0 { *row } else { rows[i - 1] };
+			let (low, high) = if previous < *row {
+				(previous + 1, *row)
+			} else if previous > *row {
+				(*row, previous - 1)
+			} else {
+				(*row, *row)
+			};
+			(0..height)
+				.rev()
+				.map(|line| {
+					if (low..=high).contains(&line) {
+						ChartCell::Line
+					} else if fill && line < low {
+						ChartCell::Fill
+					} else {
+						ChartCell::Empty
+					}
+				})
+				.collect()
+		})
+		.collect()
+}
+
+/// The glyph of a cell filled to a height in eighths, full from 8 up
+pub fn bar_symbol(bar_set: &symbols::bar::Set, eighths: u64) -> &'static str {
+	match eighths {
+		0 => bar_set.empty,
+		1 => bar_set.one_eighth,
+		2 => bar_set.one_quarter,
+		3 => bar_set.three_eighths,
+		4 => bar_set.half,
+		5 => bar_set.five_eighths,
+		6 => bar_set.three_quarters,
+		7 => bar_set.seven_eighths,
+		_ => bar_set.full,
+	}
+}
+
+/// Buckets combined into each column so that all of them fit in width columns
+pub fn downsample_factor(buckets: usize, width: usize) -> usize {
+	if width == 0 {
+		return 1;
+	}
+	buckets.div_ceil(width).max(1)
+}
+
+/// Combine every factor buckets into one. Groups are taken from the newest
+/// (last) bucket back, so only the oldest column may combine fewer
+pub fn downsample(data: &[u64], factor: usize, method: Downsample) -> Vec<u64> {
+	if factor <= 1 {
+		return data.to_vec();
+	}
+	let mut columns: Vec<u64> = data
+		.rchunks(factor)
+		.map(|group| match method {
+			Downsample::Max => group.iter().max().copied().unwrap_or(0),
+			Downsample::Mean => {
+				let sum: u64 = group.iter().sum();
+				let count = group.len() as u64;
+				(sum + count / 2) / count
+			}
+		})
+		.collect();
+	columns.reverse();
+	columns
+}
+
 /// Widget to render a sparkline over one or more lines.
 ///
 /// Based on tui::widgets::Sparkline, but with origin
@@ -24,6 +273,22 @@ pub struct Sparkline2<'a> {
 	max: Option<u64>,
 	/// A set of bar symbols used to represent the give data
 	bar_set: symbols::bar::Set,
+	/// Index of a data point to highlight
+	cursor: Option<usize>,
+	/// Style of the highlighted column
+	cursor_style: Style,
+	/// Scale used to compute bar heights
+	scale: Scale,
+	/// Standard deviations above the mean of the visible data beyond which a bar is a spike
+	spike_sigma: Option<f64>,
+	/// Style of spike bars
+	spike_style: Style,
+	/// Values of an earlier copy of the data drawn behind it, to the same y-scale
+	backdrop: Option<&'a [u64]>,
+	/// Style of the backdrop bars
+	backdrop_style: Style,
+	/// Bars, or a line through the values, scaled the same either way
+	chart_style: ChartStyle,
 }
 
 impl<'a> Default for Sparkline2<'a> {
@@ -34,6 +299,14 @@ impl<'a> Default for Sparkline2<'a> {
 			data: &[],
 			max: None,
 			bar_set: symbols::bar::NINE_LEVELS,
+			cursor: None,
+			cursor_style: Style::default().add_modifier(Modifier::REVERSED),
+			scale: Scale::Linear,
+			spike_sigma: None,
+			spike_style: Style::default().fg(Color::Magenta),
+			backdrop: None,
+			backdrop_style: Style::default().add_modifier(Modifier::DIM),
+			chart_style: ChartStyle::Bars,
 		}
 	}
 }
@@ -63,6 +336,130 @@ impl<'a> Sparkline2<'a> {
 		self.bar_set = bar_set;
 		self
 	}
+
+	pub fn scale(mut self, scale: Scale) -> Sparkline2<'a> {
+		self.scale = scale;
+		self
+	}
+
+	/// Highlights bars more than sigma standard deviations above the mean of
+	/// the visible data. None (the default) disables this
+	pub fn spike_sigma(mut self, sigma: Option<f64>) -> Sparkline2<'a> {
+		self.spike_sigma = sigma;
+		self
+	}
+
+	pub fn spike_style(mut self, style: Style) -> Sparkline2<'a> {
+		self.spike_style = style;
+		self
+	}
+
+	/// Draws these values behind the data, such as a copy of it frozen earlier
+	/// to compare with. They share the data's y-scale, so that its maximum
+	/// covers both unless given by max()
+	pub fn backdrop(mut self, backdrop: Option<&'a [u64]>) -> Sparkline2<'a> {
+		self.backdrop = backdrop;
+		self
+	}
+
+	/// Style of the backdrop, the data's style dimmed by default
+	pub fn backdrop_style(mut self, style: Style) -> Sparkline2<'a> {
+		self.backdrop_style = style;
+		self
+	}
+
+	pub fn chart_style(mut self, chart_style: ChartStyle) -> Sparkline2<'a> {
+		self.chart_style = chart_style;
+		self
+	}
+
+	/// The value drawn as the full height, shared by the data and any backdrop
+	pub fn scale_max(&self) -> u64 {
+		match self.max {
+			Some(v) => v,
+			None => {
+				let backdrop_max = self.backdrop.and_then(|backdrop| backdrop.iter().max().copied());
+				match (self.data.iter().max().copied(), backdrop_max) {
+					(None, None) => 1,
+					(data_max, backdrop_max) => data_max.max(backdrop_max).unwrap_or(1),
+				}
+			}
+		}
+	}
+
+	/// Height in eighths of a line of each bar which fits within width, for bars
+	/// up to height lines tall
+	pub fn bar_heights(&self, width: u16, height: u16) -> Vec<u64> {
+		self.heights_of(self.data, width, height)
+	}
+
+	/// As bar_heights(), for the bars of the backdrop, empty without one
+	pub fn backdrop_bar_heights(&self, width: u16, height: u16) -> Vec<u64> {
+		self.heights_of(self.backdrop.unwrap_or(&[]), width, height)
+	}
+
+	fn heights_of(&self, data: &[u64], width: u16, height: u16) -> Vec<u64> {
+		let max = self.scale_max();
+		let max_index = min(width as usize, data.len());
+		let data = data.iter().take(max_index);
+		match self.scale {
+			Scale::Linear => data
+				.map(|e| (e * u64::from(height) * 8).checked_div(max).unwrap_or(0))
+				.collect(),
+			Scale::Log10 => {
+				let scaled_max = self.scale.apply(max);
+				data.map(|e| {
+					if scaled_max <= 0.0 {
+						return 0;
+					}
+					let scaled = self.scale.apply(*e).min(scaled_max);
+					(scaled * f64::from(height) * 8.0 / scaled_max) as u64
+				})
+				.collect()
+			}
+		}
+	}
+
+	pub fn cursor(mut self, index: Option<usize>) -> Sparkline2<'a> {
+		self.cursor = index;
+		self
+	}
+
+	pub fn cursor_style(mut self, style: Style) -> Sparkline2<'a> {
+		self.cursor_style = style;
+		self
+	}
+
+	/// The column (x) where the data point at index is drawn when rendered
+	/// in area, or None if it doesn't fit
+	pub fn index_to_column(&self, area: Rect, index: usize) -> Option<u16> {
+		data_column(self.spark_area(area), self.data.len(), index)
+	}
+
+	/// The index of the data point drawn in column x when rendered in area
+	pub fn column_to_index(&self, area: Rect, x: u16) -> Option<usize> {
+		let spark_area = self.spark_area(area);
+		if x < spark_area.left() || spark_area.height < 1 {
+			return None;
+		}
+		let index = (x - spark_area.left()) as usize;
+		data_column(spark_area, self.data.len(), index).map(|_| index)
+	}
+
+	fn spark_area(&self, area: Rect) -> Rect {
+		match &self.block {
+			Some(b) => b.inner(area),
+			None => area,
+		}
+	}
+}
+
+/// Data points are drawn one per column from the left, until the area is full
+fn data_column(spark_area: Rect, data_len: usize, index: usize) -> Option<u16> {
+	if spark_area.height < 1 || index >= min(spark_area.width as usize, data_len) {
+		return None;
+	}
+	Some(spark_area.left() + index as u16)
 }
 
 impl<'a> Widget for Sparkline2<'a> {
@@ -80,39 +477,52 @@ impl<'a> Widget for Sparkline2<'a> {
 			return;
 		}
 
-		let max = match self.max {
-			Some(v) => v,
-			None => *self.data.iter().max().unwrap_or(&1u64),
-		};
-		let max_index = min(spark_area.width as usize, self.data.len());
-		let mut data = self
-			.data
-			.iter()
-			.take(max_index)
-			.map(|e| {
-				if max != 0 {
-					e * u64::from(spark_area.height) * 8 / max
-				} else {
-					0
-				}
-			})
-			.collect::<Vec<u64>>();
+		let data = self.bar_heights(spark_area.width, spark_area.height);
+		let backdrop = self.backdrop_bar_heights(spark_area.width, spark_area.height);
+		match self.chart_style {
+			ChartStyle::Bars => self.render_bars(spark_area, buf, data, backdrop),
+			ChartStyle::Line => self.render_line(spark_area, buf, &data, &backdrop, false),
+			ChartStyle::Area => self.render_line(spark_area, buf, &data, &backdrop, true),
+		}
+
+		if let Some(x) = self.cursor.and_then(|index| data_column(spark_area, self.data.len(), index)) {
+			for y in spark_area.top()..spark_area.bottom() {
+				buf.get_mut(x, y).set_style(self.cursor_style);
+			}
+		}
+	}
+}
+
+impl<'a> Sparkline2<'a> {
+	// The style of the column of a data point, marking spikes
+	fn column_style(&self, visible: &[u64], threshold: Option<f64>, i: usize) -> Style {
+		match threshold {
+			Some(threshold) if visible[i] as f64 > threshold => self.spike_style,
+			_ => self.style,
+		}
+	}
+
+	fn render_bars(&self, spark_area: Rect, buf: &mut Buffer, mut data: Vec<u64>, mut backdrop: Vec<u64>) {
+		let visible = &self.data[..data.len()];
+		let threshold = self.spike_sigma.and_then(|sigma| spike_threshold(visible, sigma));
 		for j in (0..spark_area.height).rev() {
+			for (i, b) in backdrop.iter_mut().enumerate() {
+				// The backdrop shows where the data's bar is lower
+				if data.get(i).is_none_or(|d| *d == 0) {
+					buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j)
+						.set_symbol(bar_symbol(&self.bar_set, *b))
+						.set_style(self.backdrop_style);
+				}
+				*b = b.saturating_sub(8);
+			}
 			for (i, d) in data.iter_mut().enumerate() {
-				let symbol = match *d {
-					0 => self.bar_set.empty,
-					1 => self.bar_set.one_eighth,
-					2 => self.bar_set.one_quarter,
-					3 => self.bar_set.three_eighths,
-					4 => self.bar_set.half,
-					5 => self.bar_set.five_eighths,
-					6 => self.bar_set.three_quarters,
-					7 => self.bar_set.seven_eighths,
-					_ => self.bar_set.full,
-				};
-				buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j)
-					.set_symbol(symbol)
-					.set_style(self.style);
+				let style = self.column_style(visible, threshold, i);
+				if *d > 0 || backdrop.get(i).is_none() {
+					let symbol = bar_symbol(&self.bar_set, *d);
+					buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j)
+						.set_symbol(symbol)
+						.set_style(style);
+				}
 
 				if *d > 8 {
 					*d -= 8;
@@ -122,6 +532,345 @@ impl<'a> Widget for Sparkline2<'a> {
 			}
 		}
 	}
+
+	// Points of the same heights as the bars, joined to form a line, with the
+	// backdrop's line behind it
+	fn render_line(&self, spark_area: Rect, buf: &mut Buffer, data: &[u64], backdrop: &[u64], fill: bool) {
+		let visible = &self.data[..data.len()];
+		let threshold = self.spike_sigma.and_then(|sigma| spike_threshold(visible, sigma));
+		let backdrop = line_cells(backdrop, spark_area.height, fill);
+		for (i, column) in backdrop.iter().enumerate() {
+			for (j, cell) in column.iter().enumerate() {
+				buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j as u16)
+					.set_symbol(cell.symbol())
+					.set_style(self.backdrop_style);
+			}
+		}
+		for (i, column) in line_cells(data, spark_area.height, fill).iter().enumerate() {
+			let style = self.column_style(visible, threshold, i);
+			for (j, cell) in column.iter().enumerate() {
+				let behind = backdrop.get(i).map_or(ChartCell::Empty, |column| column[j]);
+				if *cell != ChartCell::Empty || behind == ChartCell::Empty {
+					buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j as u16)
+						.set_symbol(cell.symbol())
+						.set_style(style);
+				}
+			}
+		}
+	}
+}
+
+/// Height in eighths of a line of each bar of each series which fits within
+/// width, for bars up to height lines tall. The series share a y-scale set by
+/// the largest value of any of them, unless normalize scales each to its own
+pub fn overlay_bar_heights(series: &[&[u64]], width: u16, height: u16, scale: Scale, normalize: bool) -> Vec<Vec<u64>> {
+	let shared_max = series.iter().filter_map(|data| data.iter().max()).max().copied().unwrap_or(0);
+	series
+		.iter()
+		.map(|data| {
+			let max = if normalize { data.iter().max().copied().unwrap_or(0) } else { shared_max };
+			Sparkline2::default().data(data).max(max).scale(scale).bar_heights(width, height)
+		})
+		.collect()
+}
+
+/// Which y-scale of a dual-scale chart a series is drawn to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+	Left,
+	Right,
+}
+
+/// Height in eighths of a line of each bar of each series, as for
+/// overlay_bar_heights(), but with the series of each axis sharing a y-scale
+/// set by the largest value on that axis alone. Series with no data have
+/// no bars, and an axis whose values are all zero has no height
+pub fn dual_scale_bar_heights(series: &[(&[u64], Axis)], width: u16, height: u16, scale: Scale) -> Vec<Vec<u64>> {
+	let axis_max = |axis: Axis| {
+		series
+			.iter()
+			.filter(|(_data, series_axis)| *series_axis == axis)
+			.filter_map(|(data, _axis)| data.iter().max())
+			.max()
+			.copied()
+			.unwrap_or(0)
+	};
+	let (left_max, right_max) = (axis_max(Axis::Left), axis_max(Axis::Right));
+	series
+		.iter()
+		.map(|(data, axis)| {
+			let max = if *axis == Axis::Left { left_max } else { right_max };
+			Sparkline2::default().data(data).max(max).scale(scale).bar_heights(width, height)
+		})
+		.collect()
+}
+
+/// Widget to render several series as sparklines overlaid on one another.
+///
+/// In each column the shorter bars are drawn in front of the taller ones,
+/// so every series stays visible where it is lower than the others. Series
+/// added with right_series() are scaled independently of the others, for
+/// metrics with different natural scales.
+#[derive(Debug, Clone)]
+pub struct MultiSparkline<'a> {
+	/// A block to wrap the widget in
+	block: Option<Block<'a>>,
+	/// Widget style, used where no series has a bar
+	style: Style,
+	/// The data of each series, the style to draw it in and its y-scale
+	series: Vec<(&'a [u64], Style, Axis)>,
+	/// Scale used to compute bar heights
+	scale: Scale,
+	/// Scale each series to its own maximum rather than the maximum of all
+	normalize: bool,
+	/// Index of a data point to highlight
+	cursor: Option<usize>,
+	/// Style of the highlighted column
+	cursor_style: Style,
+	/// A set of bar symbols used to represent the data
+	bar_set: symbols::bar::Set,
+}
+
+impl<'a> Default for MultiSparkline<'a> {
+	fn default() -> MultiSparkline<'a> {
+		MultiSparkline {
+			block: None,
+			style: Default::default(),
+			series: Vec::new(),
+			scale: Scale::Linear,
+			normalize: false,
+			cursor: None,
+			cursor_style: Style::default().add_modifier(Modifier::REVERSED),
+			bar_set: symbols::bar::NINE_LEVELS,
+		}
+	}
+}
+
+impl<'a> MultiSparkline<'a> {
+	pub fn block(mut self, block: Block<'a>) -> MultiSparkline<'a> {
+		self.block = Some(block);
+		self
+	}
+
+	pub fn style(mut self, style: Style) -> MultiSparkline<'a> {
+		self.style = style;
+		self
+	}
+
+	/// Add a series, drawn in style
+	pub fn series(mut self, data: &'a [u64], style: Style) -> MultiSparkline<'a> {
+		self.series.push((data, style, Axis::Left));
+		self
+	}
+
+	/// Add a series drawn to the right-hand y-scale, which is independent
+	/// of the series added with series()
+	pub fn right_series(mut self, data: &'a [u64], style: Style) -> MultiSparkline<'a> {
+		self.series.push((data, style, Axis::Right));
+		self
+	}
+
+	pub fn scale(mut self, scale: Scale) -> MultiSparkline<'a> {
+		self.scale = scale;
+		self
+	}
+
+	pub fn normalize(mut self, normalize: bool) -> MultiSparkline<'a> {
+		self.normalize = normalize;
+		self
+	}
+
+	pub fn cursor(mut self, index: Option<usize>) -> MultiSparkline<'a> {
+		self.cursor = index;
+		self
+	}
+
+	pub fn cursor_style(mut self, style: Style) -> MultiSparkline<'a> {
+		self.cursor_style = style;
+		self
+	}
+
+	pub fn bar_set(mut self, bar_set: symbols::bar::Set) -> MultiSparkline<'a> {
+		self.bar_set = bar_set;
+		self
+	}
+}
+
+impl<'a> Widget for MultiSparkline<'a> {
+	fn render(mut self, area: Rect, buf: &mut Buffer) {
+		let spark_area = match self.block.take() {
+			Some(b) => {
+				let inner_area = b.inner(area);
+				b.render(area, buf);
+				inner_area
+			}
+			None => area,
+		};
+
+		if spark_area.height < 1 {
+			return;
+		}
+
+		let heights = if self.normalize {
+			let data: Vec<&[u64]> = self.series.iter().map(|(data, _style, _axis)| *data).collect();
+			overlay_bar_heights(&data, spark_area.width, spark_area.height, self.scale, true)
+		} else {
+			let data: Vec<(&[u64], Axis)> = self.series.iter().map(|(data, _style, axis)| (*data, *axis)).collect();
+			dual_scale_bar_heights(&data, spark_area.width, spark_area.height, self.scale)
+		};
+		let columns = heights.iter().map(|h| h.len()).max().unwrap_or(0);
+		for i in 0..columns {
+			// Series with a bar in this column, shortest first
+			let mut bars: Vec<(u64, Style)> = heights
+				.iter()
+				.zip(self.series.iter())
+				.filter_map(|(h, (_data, style, _axis))| h.get(i).map(|height| (*height, *style)))
+				.collect();
+			bars.sort_by_key(|(height, _style)| *height);
+
+			for j in 0..spark_area.height {
+				let floor = u64::from(spark_area.height - 1 - j) * 8;
+				let mut covering = bars.iter().filter(|(height, _style)| *height > floor);
+				let (symbol, style) = match covering.next() {
+					None => (bar_symbol(&self.bar_set, 0), self.style),
+					Some((height, style)) => {
+						let eighths = height - floor;
+						let mut style = *style;
+						if eighths < 8 {
+							// Show the taller bar behind in the rest of the cell
+							if let Some(fg) = covering.next().and_then(|(_height, behind)| behind.fg) {
+								style = style.bg(fg);
+							}
+						}
+						(bar_symbol(&self.bar_set, eighths), style)
+					}
+				};
+				buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j)
+					.set_symbol(symbol)
+					.set_style(style);
+			}
+		}
+
+		if let Some(x) = self.cursor.and_then(|index| data_column(spark_area, columns, index)) {
+			for y in spark_area.top()..spark_area.bottom() {
+				buf.get_mut(x, y).set_style(self.cursor_style);
+			}
+		}
+	}
+}
+
+/// Depth in eighths of a line of each bar of signed data which fits within
+/// width, for a chart height lines tall with zero across its middle. Bars of
+/// positive values rise from zero and of negative values hang below it, to
+/// one scale set by the value furthest from zero, so that each half is at
+/// most half the height
+pub fn signed_bar_heights(data: &[i64], width: u16, height: u16) -> Vec<i64> {
+	let half = i128::from(height / 2).max(1) * 8;
+	let max = data.iter().take(width as usize).map(|value| i128::from(*value).abs()).max().unwrap_or(0);
+	data.iter()
+		.take(width as usize)
+		.map(|value| (i128::from(*value) * half).checked_div(max).unwrap_or(0) as i64)
+		.collect()
+}
+
+/// The glyph of a cell a bar hangs into by a depth in eighths, from the top
+fn hanging_symbol(eighths: u64) -> &'static str {
+	match eighths {
+		0 => " ",
+		1..=3 => "▔",
+		4..=7 => "▀",
+		_ => symbols::bar::FULL,
+	}
+}
+
+/// Widget to render signed data, such as a rate of change, as bars rising
+/// above or hanging below zero across the middle of the chart
+#[derive(Debug, Clone)]
+pub struct SignedSparkline<'a> {
+	/// A block to wrap the widget in
+	block: Option<Block<'a>>,
+	/// Style of the bars of positive values
+	style: Style,
+	/// Style of the bars of negative values
+	negative_style: Style,
+	/// A slice of the data to display
+	data: &'a [i64],
+	/// A set of bar symbols used for the bars of positive values
+	bar_set: symbols::bar::Set,
+}
+
+impl<'a> Default for SignedSparkline<'a> {
+	fn default() -> SignedSparkline<'a> {
+		SignedSparkline {
+			block: None,
+			style: Default::default(),
+			negative_style: Default::default(),
+			data: &[],
+			bar_set: symbols::bar::NINE_LEVELS,
+		}
+	}
+}
+
+impl<'a> SignedSparkline<'a> {
+	pub fn block(mut self, block: Block<'a>) -> SignedSparkline<'a> {
+		self.block = Some(block);
+		self
+	}
+
+	pub fn style(mut self, style: Style) -> SignedSparkline<'a> {
+		self.style = style;
+		self
+	}
+
+	pub fn negative_style(mut self, style: Style) -> SignedSparkline<'a> {
+		self.negative_style = style;
+		self
+	}
+
+	pub fn data(mut self, data: &'a [i64]) -> SignedSparkline<'a> {
+		self.data = data;
+		self
+	}
+
+	pub fn bar_set(mut self, bar_set: symbols::bar::Set) -> SignedSparkline<'a> {
+		self.bar_set = bar_set;
+		self
+	}
+}
+
+impl<'a> Widget for SignedSparkline<'a> {
+	fn render(mut self, area: Rect, buf: &mut Buffer) {
+		let spark_area = match self.block.take() {
+			Some(b) => {
+				let inner_area = b.inner(area);
+				b.render(area, buf);
+				inner_area
+			}
+			None => area,
+		};
+		if spark_area.height < 1 {
+			return;
+		}
+
+		// Lines above zero, and below it, which are as many unless there is only one
+		let half = (spark_area.height / 2).max(1);
+		for (i, depth) in signed_bar_heights(self.data, spark_area.width, spark_area.height).iter().enumerate() {
+			let x = spark_area.left() + i as u16;
+			for line in 0..half {
+				// Counted out from zero, above it and below it
+				let eighths = depth.unsigned_abs().saturating_sub(u64::from(line) * 8);
+				if *depth > 0 {
+					buf.get_mut(x, spark_area.top() + half - 1 - line)
+						.set_symbol(bar_symbol(&self.bar_set, eighths))
+						.set_style(self.style);
+				} else if *depth < 0 && half + line < spark_area.height {
+					buf.get_mut(x, spark_area.top() + half + line)
+						.set_symbol(hanging_symbol(eighths))
+						.set_style(self.negative_style);
+				}
+			}
+		}
+	}
 }
 
 #[cfg(test)]
@@ -143,4 +892,306 @@ mod tests {
 		let mut buffer = Buffer::empty(area);
 		widget.render(area, &mut buffer);
 	}
+
+	#[test]
+	fn maps_data_indexes_to_columns() {
+		let area = Rect::new(2, 1, 5, 2);
+		let data = [1, 2, 3];
+		let widget = Sparkline2::default().data(&data);
+		assert_eq!(widget.index_to_column(area, 0), Some(2));
+		assert_eq!(widget.index_to_column(area, 2), Some(4));
+		assert_eq!(widget.index_to_column(area, 3), None);
+		assert_eq!(widget.column_to_index(area, 3), Some(1));
+		assert_eq!(widget.column_to_index(area, 5), None);
+		assert_eq!(widget.column_to_index(area, 1), None);
+
+		// Data wider than the area is truncated on the right
+		let data = [1; 10];
+		let widget = Sparkline2::default().data(&data);
+		assert_eq!(widget.index_to_column(area, 4), Some(6));
+		assert_eq!(widget.index_to_column(area, 5), None);
+		assert_eq!(widget.column_to_index(area, 6), Some(4));
+		assert_eq!(widget.column_to_index(area, 7), None);
+	}
+
+	#[test]
+	fn maps_columns_inside_block() {
+		let area = Rect::new(0, 0, 6, 3);
+		let data = [1; 10];
+		let widget = Sparkline2::default().block(Block::default().borders(tui::widgets::Borders::ALL)).data(&data);
+		assert_eq!(widget.index_to_column(area, 0), Some(1));
+		assert_eq!(widget.index_to_column(area, 3), Some(4));
+		assert_eq!(widget.index_to_column(area, 4), None);
+		assert_eq!(widget.column_to_index(area, 0), None);
+	}
+
+	#[test]
+	fn highlights_cursor_column() {
+		let area = Rect::new(0, 0, 4, 2);
+		let mut buffer = Buffer::empty(area);
+		let style = Style::default().bg(tui::style::Color::Red);
+		Sparkline2::default().data(&[1, 2, 3]).cursor(Some(1)).cursor_style(style).render(area, &mut buffer);
+		assert_eq!(buffer.get(1, 0).bg, tui::style::Color::Red);
+		assert_eq!(buffer.get(1, 1).bg, tui::style::Color::Red);
+		assert_ne!(buffer.get(0, 0).bg, tui::style::Color::Red);
+		assert_ne!(buffer.get(2, 1).bg, tui::style::Color::Red);
+	}
+
+	#[test]
+	fn computes_linear_and_log_bar_heights() {
+		let data = [0, 1, 9, 99, 999];
+		let widget = Sparkline2::default().data(&data);
+		assert_eq!(widget.bar_heights(5, 1), vec![0, 0, 0, 0, 8]);
+		assert_eq!(widget.bar_heights(3, 2), vec![0, 0, 0]);
+
+		// log10(1 + v) is 0, 0.3, 1, 2 and 3 so the small values stay visible
+		let widget = widget.scale(Scale::Log10);
+		assert_eq!(widget.bar_heights(5, 3), vec![0, 2, 8, 16, 24]);
+		assert_eq!(widget.bar_heights(5, 1), vec![0, 0, 2, 5, 8]);
+
+		let widget = Sparkline2::default().data(&[0, 0]).scale(Scale::Log10);
+		assert_eq!(widget.bar_heights(2, 1), vec![0, 0]);
+		let widget = Sparkline2::default().data(&[5, 10]).max(0).scale(Scale::Log10);
+		assert_eq!(widget.bar_heights(2, 1), vec![0, 0]);
+	}
+
+	#[test]
+	fn detects_a_clear_spike() {
+		let mut data = vec![4, 5, 6, 5, 4, 5, 6, 5, 4, 5, 6, 5, 4, 5, 6, 5, 4, 5, 6, 5];
+		data[12] = 60;
+		let threshold = spike_threshold(&data, 3.0).unwrap();
+		let spikes: Vec<usize> = (0..data.len()).filter(|i| data[*i] as f64 > threshold).collect();
+		assert_eq!(spikes, vec![12]);
+
+		// A larger multiplier doesn't flag it, and neither does a flat or short window
+		let threshold = spike_threshold(&data, 5.0).unwrap();
+		assert!(data.iter().all(|v| *v as f64 <= threshold));
+		assert_eq!(spike_threshold(&[7; 20], 3.0), None);
+		assert_eq!(spike_threshold(&data[..MIN_SPIKE_SAMPLES - 1], 3.0), None);
+		assert_eq!(spike_threshold(&data, 0.0), None);
+	}
+
+	#[test]
+	fn styles_spike_bars() {
+		let mut data = vec![1; 20];
+		data[3] = 50;
+		let area = Rect::new(0, 0, 20, 1);
+		let mut buffer = Buffer::empty(area);
+		let spike_style = Style::default().fg(Color::LightRed);
+		Sparkline2::default().data(&data).spike_sigma(Some(3.0)).spike_style(spike_style).render(area, &mut buffer);
+		assert_eq!(buffer.get(3, 0).fg, Color::LightRed);
+		assert_ne!(buffer.get(2, 0).fg, Color::LightRed);
+
+		let mut buffer = Buffer::empty(area);
+		Sparkline2::default().data(&data).spike_style(spike_style).render(area, &mut buffer);
+		assert_ne!(buffer.get(3, 0).fg, Color::LightRed);
+	}
+
+	#[test]
+	fn overlaid_series_share_a_scale() {
+		let puts = [0, 2, 4, 1];
+		let gets = [8, 4, 0, 2];
+		let heights = overlay_bar_heights(&[&puts, &gets], 4, 1, Scale::Linear, false);
+		assert_eq!(heights, vec![vec![0, 2, 4, 1], vec![8, 4, 0, 2]]);
+
+		// Normalized, each series fills the height at its own maximum
+		let heights = overlay_bar_heights(&[&puts, &gets], 4, 1, Scale::Linear, true);
+		assert_eq!(heights, vec![vec![0, 4, 8, 2], vec![8, 4, 0, 2]]);
+
+		let heights = overlay_bar_heights(&[&puts, &[0, 0, 0, 0]], 2, 2, Scale::Linear, true);
+		assert_eq!(heights, vec![vec![0, 8], vec![0, 0]]);
+	}
+
+	#[test]
+	fn backdrop_shares_the_y_scale() {
+		let live = [1, 2, 4];
+		let frozen = [8, 4, 0];
+		let widget = Sparkline2::default().data(&live).backdrop(Some(&frozen));
+		assert_eq!(widget.scale_max(), 8);
+		assert_eq!(widget.bar_heights(3, 1), vec![1, 2, 4]);
+		assert_eq!(widget.backdrop_bar_heights(3, 1), vec![8, 4, 0]);
+
+		// Whichever is larger sets the scale, unless the maximum is given
+		let higher = [16, 0, 2];
+		let widget = Sparkline2::default().data(&higher).backdrop(Some(&frozen));
+		assert_eq!(widget.scale_max(), 16);
+		assert_eq!(widget.bar_heights(3, 1), vec![8, 0, 1]);
+		assert_eq!(widget.backdrop_bar_heights(3, 1), vec![4, 2, 0]);
+		let widget = widget.max(32);
+		assert_eq!(widget.scale_max(), 32);
+		assert_eq!(widget.backdrop_bar_heights(3, 1), vec![2, 1, 0]);
+
+		let widget = Sparkline2::default().data(&live);
+		assert_eq!(widget.scale_max(), 4);
+		assert!(widget.backdrop_bar_heights(3, 1).is_empty());
+	}
+
+	#[test]
+	fn draws_the_backdrop_behind_lower_bars() {
+		let area = Rect::new(0, 0, 2, 2);
+		let mut buffer = Buffer::empty(area);
+		let live = Style::default().fg(Color::Yellow);
+		let frozen = Style::default().fg(Color::DarkGray);
+		Sparkline2::default().data(&[4, 1]).backdrop(Some(&[1, 4])).style(live).backdrop_style(frozen).render(area, &mut buffer);
+
+		// Column 0: the live bar is taller and hides the backdrop
+		assert_eq!(buffer.get(0, 0).symbol, symbols::bar::FULL);
+		assert_eq!(buffer.get(0, 0).fg, Color::Yellow);
+		assert_eq!(buffer.get(0, 1).fg, Color::Yellow);
+
+		// Column 1: the backdrop shows above the live bar, which is half a line tall
+		assert_eq!(buffer.get(1, 1).symbol, symbols::bar::HALF);
+		assert_eq!(buffer.get(1, 1).fg, Color::Yellow);
+		assert_eq!(buffer.get(1, 0).symbol, symbols::bar::FULL);
+		assert_eq!(buffer.get(1, 0).fg, Color::DarkGray);
+	}
+
+	#[test]
+	fn draws_shorter_bars_in_front() {
+		let area = Rect::new(0, 0, 2, 2);
+		let mut buffer = Buffer::empty(area);
+		let red = Style::default().fg(Color::Red);
+		let blue = Style::default().fg(Color::Blue);
+		MultiSparkline::default().series(&[1, 4], red).series(&[4, 2], blue).render(area, &mut buffer);
+
+		// Column 0: red is 4 eighths tall in front of blue's 16
+		assert_eq!(buffer.get(0, 1).symbol, symbols::bar::HALF);
+		assert_eq!(buffer.get(0, 1).fg, Color::Red);
+		assert_eq!(buffer.get(0, 1).bg, Color::Blue);
+		assert_eq!(buffer.get(0, 0).symbol, symbols::bar::FULL);
+		assert_eq!(buffer.get(0, 0).fg, Color::Blue);
+
+		// Column 1: blue is 8 eighths tall in front of red's 16
+		assert_eq!(buffer.get(1, 1).symbol, symbols::bar::FULL);
+		assert_eq!(buffer.get(1, 1).fg, Color::Blue);
+		assert_eq!(buffer.get(1, 0).fg, Color::Red);
+	}
+
+	#[test]
+	fn downsamples_buckets_to_the_width() {
+		let data: Vec<u64> = (0..1000).map(|i| i % 7).collect();
+		for width in [1, 13, 80, 333, 999, 1000, 1200] {
+			let factor = downsample_factor(data.len(), width);
+			let columns = downsample(&data, factor, Downsample::Max);
+			assert!(columns.len() <= width);
+			assert_eq!(columns.len(), data.len().div_ceil(factor));
+		}
+		assert_eq!(downsample(&[1, 2, 3, 4, 5], 2, Downsample::Mean), vec![1, 3, 5]);
+		assert_eq!(downsample(&[1, 2, 3], 1, Downsample::Mean), vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn max_downsampling_preserves_spikes() {
+		let mut data = vec![1u64; 2000];
+		data[1234] = 500;
+		let factor = downsample_factor(data.len(), 80);
+		let max = downsample(&data, factor, Downsample::Max);
+		assert_eq!(max.len(), 80);
+		assert_eq!(max.iter().filter(|v| **v == 500).count(), 1);
+		assert!(max.iter().all(|v| *v == 1 || *v == 500));
+
+		let mean = downsample(&data, factor, Downsample::Mean);
+		assert!(mean.iter().all(|v| *v < 500));
+	}
+
+	#[test]
+	fn dual_scale_series_have_independent_scales() {
+		// A ratio in percent on the left and an unbounded count on the right
+		let ratio: &[u64] = &[25, 50, 100];
+		let errors: &[u64] = &[1000, 0, 4000];
+		let heights = dual_scale_bar_heights(&[(ratio, Axis::Left), (errors, Axis::Right)], 3, 1, Scale::Linear);
+		assert_eq!(heights, vec![vec![2, 4, 8], vec![2, 0, 8]]);
+
+		// Series on the same axis share its scale
+		let more: &[u64] = &[50, 50, 50];
+		let heights = dual_scale_bar_heights(&[(ratio, Axis::Left), (more, Axis::Left), (errors, Axis::Right)], 3, 1, Scale::Linear);
+		assert_eq!(heights[1], vec![4, 4, 4]);
+		assert_eq!(heights[2], vec![2, 0, 8]);
+	}
+
+	#[test]
+	fn dual_scale_tolerates_empty_series() {
+		let empty: &[u64] = &[];
+		let zeros: &[u64] = &[0, 0];
+		let errors: &[u64] = &[3, 6];
+		let heights = dual_scale_bar_heights(&[(empty, Axis::Left), (errors, Axis::Right)], 2, 2, Scale::Linear);
+		assert_eq!(heights, vec![vec![], vec![8, 16]]);
+		let heights = dual_scale_bar_heights(&[(zeros, Axis::Left), (empty, Axis::Right)], 2, 2, Scale::Linear);
+		assert_eq!(heights, vec![vec![0, 0], vec![]]);
+		assert!(dual_scale_bar_heights(&[], 2, 2, Scale::Linear).is_empty());
+
+		let widget = MultiSparkline::default().series(empty, Style::default()).right_series(zeros, Style::default());
+		let area = Rect::new(0, 0, 3, 2);
+		let mut buffer = Buffer::empty(area);
+		widget.render(area, &mut buffer);
+	}
+
+	#[test]
+	fn each_spark_style_draws_its_own_glyphs() {
+		let glyphs = |style: SparkStyle| -> Vec<&str> {
+			let bar_set = style.bar_set();
+			[0, 1, 2, 4, 6, 8, 12].iter().map(|eighths| bar_symbol(&bar_set, *eighths)).collect()
+		};
+		assert_eq!(glyphs(SparkStyle::Bars), vec![" ", "▁", "▂", "▄", "▆", "█", "█"]);
+		assert_eq!(glyphs(SparkStyle::Braille), vec![" ", "⣀", "⣀", "⣤", "⣶", "⣿", "⣿"]);
+		assert_eq!(glyphs(SparkStyle::Shaded), vec![" ", "░", "░", "▒", "▓", "█", "█"]);
+
+		// The style changes only the glyphs, not the heights they are chosen by
+		let data = [0, 1, 2, 4];
+		let area = Rect::new(0, 0, 4, 1);
+		let mut buffer = Buffer::empty(area);
+		Sparkline2::default().data(&data).bar_set(SparkStyle::Braille.bar_set()).render(area, &mut buffer);
+		let drawn: Vec<&str> = (0..4).map(|x| buffer.get(x, 0).symbol.as_str()).collect();
+		assert_eq!(drawn, vec![" ", "⣀", "⣤", "⣿"]);
+
+		let mut buffer = Buffer::empty(area);
+		MultiSparkline::default().series(&data, Style::default()).bar_set(SparkStyle::Shaded.bar_set()).render(area, &mut buffer);
+		let drawn: Vec<&str> = (0..4).map(|x| buffer.get(x, 0).symbol.as_str()).collect();
+		assert_eq!(drawn, vec![" ", "░", "▒", "█"]);
+
+		assert_eq!("shaded".parse::<SparkStyle>(), Ok(SparkStyle::Shaded));
+		assert_eq!("dots".parse::<SparkStyle>(), Err(String::from("invalid spark style 'dots', expected bars, braille or shaded")));
+	}
+
+	// The rows of a chart of data drawn in a style, top first
+	fn chart_rows(data: &[u64], height: u16, chart_style: ChartStyle) -> Vec<String> {
+		let area = Rect::new(0, 0, data.len() as u16, height);
+		let mut buffer = Buffer::empty(area);
+		Sparkline2::default().data(data).chart_style(chart_style).render(area, &mut buffer);
+		(0..height).map(|y| (0..area.width).map(|x| buffer.get(x, y).symbol.as_str()).collect()).collect()
+	}
+
+	#[test]
+	fn each_chart_style_marks_its_cells() {
+		// Heights of 0, 8, 24 and 4 eighths, of a chart three lines tall
+		let data = [0, 8, 24, 4];
+		assert_eq!(chart_rows(&data, 3, ChartStyle::Bars), vec!["  █ ", "  █ ", " ██▄"]);
+		assert_eq!(chart_rows(&data, 3, ChartStyle::Line), vec!["  • ", "  ••", "•• •"]);
+		assert_eq!(chart_rows(&data, 3, ChartStyle::Area), vec!["  • ", "  ••", "••░•"]);
+
+		// Rising and falling by more than a line, the points are joined
+		let data = [0, 16, 16, 0];
+		assert_eq!(chart_rows(&data, 2, ChartStyle::Line), vec![" •• ", "•  •"]);
+		assert_eq!(chart_rows(&data, 2, ChartStyle::Area), vec![" •• ", "•░░•"]);
+		assert_eq!(chart_rows(&[5, 5], 1, ChartStyle::Area), vec!["••"]);
+		assert!(line_cells(&[8, 16], 0, true).iter().all(|column| column.is_empty()));
+
+		assert_eq!("area".parse::<ChartStyle>(), Ok(ChartStyle::Area));
+		assert_eq!("dots".parse::<ChartStyle>(), Err(String::from("invalid chart style 'dots', expected bars, line or area")));
+	}
+
+	#[test]
+	fn hangs_negative_values_below_zero() {
+		let data = [0, 4, -4, 8, -8, 1, -1];
+		assert_eq!(signed_bar_heights(&data, 7, 4), vec![0, 8, -8, 16, -16, 2, -2]);
+		assert_eq!(signed_bar_heights(&data, 2, 4), vec![0, 16]); // Scaled to the data which fits
+		assert_eq!(signed_bar_heights(&[0, 0], 2, 4), vec![0, 0]);
+		assert_eq!(signed_bar_heights(&[i64::MIN, i64::MAX], 2, 2), vec![-8, 7]);
+
+		let area = Rect::new(0, 0, data.len() as u16, 4);
+		let mut buffer = Buffer::empty(area);
+		SignedSparkline::default().data(&data).render(area, &mut buffer);
+		let rows: Vec<String> = (0..4).map(|y| (0..area.width).map(|x| buffer.get(x, y).symbol.as_str()).collect()).collect();
+		assert_eq!(rows, vec!["   █   ", " █ █ ▂ ", "  █ █ ▔", "    █  "]);
+	}
 }